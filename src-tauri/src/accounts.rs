@@ -0,0 +1,661 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::dav::normalize_path;
+use crate::integration::MountLabel;
+use crate::mounts::MountDefinition;
+use crate::sidecar::{read_config_json, read_config_section, update_config_json, AccountInfo, CommandError, SidecarState};
+
+// ============================================================================
+// Accounts
+// ============================================================================
+//
+// The bridge keeps one set of credentials per account profile: the keyring
+// entry and the data directory (credentials file, metadata and lock
+// databases) are suffixed with the profile passed in the
+// `PROTON_DRIVE_BRIDGE_ACCOUNT` environment variable. The first account
+// signed in uses the default, unsuffixed profile so existing installs keep
+// their session. `switch_account` stops the bridge, unmounts, activates the
+// other profile and brings everything back up, reporting each step as an
+// `account:switching` event. If the bridge doesn't come up with the new
+// profile, the previous account and its config are put back and started
+// again.
+//
+// The bridge only reads the global keys of config.json, so settings that
+// differ between accounts (port, cache size, remote path, extra mounts) are
+// kept in each account's entry and swapped into the global keys on a
+// switch. The active account's settings are always the global ones.
+
+const CONFIG_KEY: &str = "accounts";
+
+/// Environment variable selecting the bridge's account profile.
+pub(crate) const ACCOUNT_ENV: &str = "PROTON_DRIVE_BRIDGE_ACCOUNT";
+
+/// Settings kept per account. Also used as a patch, where unset fields are
+/// left unchanged.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AccountConfig {
+    /// `webdav.port`
+    pub port: Option<u16>,
+    /// `cache.maxSizeMB`
+    #[serde(rename = "cacheMaxSizeMB")]
+    pub cache_max_size_mb: Option<u64>,
+    /// `remotePath`
+    pub remote_path: Option<String>,
+    /// Extra mount definitions (`mounts`)
+    pub mounts: Option<Vec<MountDefinition>>,
+    /// Name and icon of the drive in file managers (`mountLabel`)
+    pub mount_label: Option<MountLabel>,
+}
+
+impl AccountConfig {
+    /// The settings currently in the global keys of `config`.
+    fn capture(config: &serde_json::Value) -> Self {
+        Self {
+            port: config
+                .pointer("/webdav/port")
+                .and_then(|p| p.as_u64())
+                .and_then(|p| u16::try_from(p).ok()),
+            cache_max_size_mb: config.pointer("/cache/maxSizeMB").and_then(|s| s.as_u64()),
+            remote_path: config.get("remotePath").and_then(|p| p.as_str()).map(str::to_string),
+            mounts: config.get("mounts").and_then(|m| serde_json::from_value(m.clone()).ok()),
+            mount_label: config.get(crate::integration::LABEL_KEY).and_then(|l| serde_json::from_value(l.clone()).ok()),
+        }
+    }
+
+    /// Write the set fields into the global keys of `config`.
+    fn apply(&self, config: &mut serde_json::Value) {
+        if let Some(port) = self.port {
+            config["webdav"]["port"] = port.into();
+        }
+        if let Some(size) = self.cache_max_size_mb {
+            config["cache"]["maxSizeMB"] = size.into();
+        }
+        if let Some(path) = &self.remote_path {
+            config["remotePath"] = path.as_str().into();
+        }
+        if let Some(mounts) = &self.mounts {
+            config["mounts"] = serde_json::to_value(mounts).unwrap_or_default();
+        }
+        if let Some(label) = &self.mount_label {
+            config[crate::integration::LABEL_KEY] = serde_json::to_value(label).unwrap_or_default();
+        }
+    }
+
+    /// Undo [`Self::apply`] of `applied`: the global keys it set go back to
+    /// these settings, or are removed where these are unset. Keys `applied`
+    /// left alone keep whatever was written since.
+    fn restore(&self, applied: &AccountConfig, config: &mut serde_json::Value) {
+        fn put(config: &mut serde_json::Value, parent: Option<&str>, key: &str, value: Option<serde_json::Value>) {
+            let target = match parent {
+                Some(parent) => &mut config[parent],
+                None => config,
+            };
+            match (value, target.as_object_mut()) {
+                (Some(value), _) => target[key] = value,
+                (None, Some(object)) => {
+                    object.remove(key);
+                }
+                (None, None) => {}
+            }
+        }
+        if applied.port.is_some() {
+            put(config, Some("webdav"), "port", self.port.map(Into::into));
+        }
+        if applied.cache_max_size_mb.is_some() {
+            put(config, Some("cache"), "maxSizeMB", self.cache_max_size_mb.map(Into::into));
+        }
+        if applied.remote_path.is_some() {
+            put(config, None, "remotePath", self.remote_path.as_deref().map(Into::into));
+        }
+        if applied.mounts.is_some() {
+            put(config, None, "mounts", self.mounts.as_ref().and_then(|m| serde_json::to_value(m).ok()));
+        }
+        if applied.mount_label.is_some() {
+            let label = self.mount_label.as_ref().and_then(|l| serde_json::to_value(l).ok());
+            put(config, None, crate::integration::LABEL_KEY, label);
+        }
+    }
+
+    /// Settings an account starts with on activation: what it saved, and
+    /// the whole drive without extra mounts or a custom label until it has
+    /// its own. Port and cache size carry over from the previous account.
+    fn for_activation(&self) -> Self {
+        Self {
+            remote_path: self.remote_path.clone().or_else(|| Some("/".into())),
+            mounts: self.mounts.clone().or_else(|| Some(Vec::new())),
+            mount_label: self.mount_label.clone().or_else(|| Some(MountLabel::default())),
+            ..self.clone()
+        }
+    }
+
+    fn merge(&mut self, patch: AccountConfig) {
+        self.port = patch.port.or(self.port);
+        self.cache_max_size_mb = patch.cache_max_size_mb.or(self.cache_max_size_mb);
+        self.remote_path = patch.remote_path.or(self.remote_path.take());
+        self.mounts = patch.mounts.or(self.mounts.take());
+        self.mount_label = patch.mount_label.or(self.mount_label.take());
+    }
+
+    fn validate(&mut self) -> Result<(), CommandError> {
+        if self.port == Some(0) {
+            return Err(CommandError::InvalidPort("0".into()));
+        }
+        if let Some(path) = &self.remote_path {
+            self.remote_path = Some(normalize_path(path));
+        }
+        if let Some(label) = &mut self.mount_label {
+            label.validate()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownAccount {
+    /// Proton username or email
+    pub id: String,
+    /// Bridge profile holding the credentials; empty for the default one
+    pub profile: String,
+    /// Settings saved when the account was last switched away from
+    #[serde(default)]
+    pub config: AccountConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AccountsConfig {
+    pub active: Option<String>,
+    pub known: Vec<KnownAccount>,
+}
+
+impl AccountsConfig {
+    fn find(&self, id: &str) -> Option<&KnownAccount> {
+        self.known.iter().find(|a| a.id.eq_ignore_ascii_case(id))
+    }
+
+    fn find_mut(&mut self, id: &str) -> Option<&mut KnownAccount> {
+        self.known.iter_mut().find(|a| a.id.eq_ignore_ascii_case(id))
+    }
+
+    fn active_account(&self) -> Option<&KnownAccount> {
+        self.active.as_deref().and_then(|id| self.find(id))
+    }
+
+    fn is_active(&self, id: &str) -> bool {
+        self.active_account().is_some_and(|a| a.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Record the account signed in with the bridge before any switch, so
+    /// it stays on the default profile.
+    fn seed(&mut self, signed_in: Option<&str>) {
+        if self.known.is_empty() {
+            if let Some(id) = signed_in.filter(|id| !id.is_empty()) {
+                self.known.push(KnownAccount { id: id.to_string(), profile: String::new(), config: AccountConfig::default() });
+                self.active = Some(id.to_string());
+            }
+        }
+    }
+
+    /// Make `id` the active account, giving it a profile if it is new. The
+    /// default profile only goes to the first account, and only while no
+    /// session is stored in it (`default_in_use`).
+    fn activate(&mut self, id: &str, default_in_use: bool) {
+        if self.find(id).is_none() {
+            let profile = if self.known.is_empty() && !default_in_use { String::new() } else { profile_name(id) };
+            self.known.push(KnownAccount { id: id.to_string(), profile, config: AccountConfig::default() });
+        }
+        self.active = self.find(id).map(|a| a.id.clone());
+    }
+
+    /// A sign-in stores credentials in the active profile, so that profile
+    /// now belongs to `id`.
+    fn signed_in(&mut self, id: &str) {
+        let Some(active) = self.active.clone() else {
+            // The session just stored in the default profile is `id`'s own
+            self.activate(id, false);
+            return;
+        };
+        if active.eq_ignore_ascii_case(id) {
+            return;
+        }
+        self.known.retain(|a| !a.id.eq_ignore_ascii_case(id));
+        if let Some(entry) = self.find_mut(&active) {
+            entry.id = id.to_string();
+        }
+        self.active = Some(id.to_string());
+    }
+}
+
+/// Profile name for `id`, safe for use in a path and a keyring entry. Ids
+/// that only differ in punctuation (`work@proton.me`, `work.proton.me`)
+/// read the same, so a short hash of the id keeps them apart.
+fn profile_name(id: &str) -> String {
+    let id = id.trim().to_ascii_lowercase();
+    let readable: String = id.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+    let hash = hex::encode(Sha256::digest(id.as_bytes()));
+    format!("{}-{}", readable, &hash[..8])
+}
+
+/// Profile the bridge should run with, `None` for the default one.
+pub(crate) fn active_profile() -> Option<String> {
+    let accounts: AccountsConfig = read_config_section(CONFIG_KEY);
+    accounts.active_account().map(|a| a.profile.clone()).filter(|p| !p.is_empty())
+}
+
+/// Record a successful sign-in of `id` in the active profile.
+pub(crate) fn record_sign_in(id: &str) {
+    let result = update_config_json(|config| {
+        let mut accounts = accounts_in(config);
+        accounts.signed_in(id);
+        store(config, &accounts)
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to record account {}: {}", id, e);
+    }
+}
+
+/// Id of the active account, if one was recorded.
+pub(crate) fn active_account_id() -> Option<String> {
+    let accounts: AccountsConfig = read_config_section(CONFIG_KEY);
+    accounts.active_account().map(|a| a.id.clone())
+}
+
+/// Accounts known to the app, or `None` before the first sign-in was
+/// recorded.
+pub(crate) fn known_accounts() -> Option<Vec<AccountInfo>> {
+    let accounts: AccountsConfig = read_config_section(CONFIG_KEY);
+    if accounts.known.is_empty() {
+        return None;
+    }
+    let active = accounts.active_account().map(|a| a.id.clone());
+    Some(
+        accounts
+            .known
+            .iter()
+            .map(|a| AccountInfo {
+                id: a.id.clone(),
+                email: a.id.clone(),
+                name: None,
+                status: Some(if Some(&a.id) == active.as_ref() { "active" } else { "inactive" }.to_string()),
+            })
+            .collect(),
+    )
+}
+
+/// The accounts section of `config`. Changes read it inside
+/// `update_config_json`, so a sign-in recorded meanwhile isn't lost.
+fn accounts_in(config: &serde_json::Value) -> AccountsConfig {
+    config.get(CONFIG_KEY).cloned().and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default()
+}
+
+fn store(config: &mut serde_json::Value, accounts: &AccountsConfig) -> Result<(), CommandError> {
+    config[CONFIG_KEY] = serde_json::to_value(accounts).map_err(|e| CommandError::Unknown(e.to_string()))?;
+    Ok(())
+}
+
+/// The bridge's status and the account its stored session belongs to. Fails
+/// when there is a session but the bridge doesn't say whose, since seeding
+/// or switching without knowing could hand that session to another account.
+async fn signed_in_account(app: &AppHandle, state: &State<'_, SidecarState>) -> Result<(Option<String>, crate::sidecar::StatusResponse), CommandError> {
+    let status = crate::sidecar::get_status(app.clone(), state.clone()).await?;
+    let username = status.auth.username.clone().filter(|u| !u.is_empty());
+    if status.auth.logged_in && username.is_none() {
+        return Err(CommandError::SidecarCommandFailed("The bridge did not report which account is signed in".into()));
+    }
+    Ok((username, status))
+}
+
+/// The accounts section of `config`, recording `signed_in` first if nothing
+/// was recorded yet.
+fn seeded(config: &serde_json::Value, signed_in: Option<&str>) -> AccountsConfig {
+    let mut accounts = accounts_in(config);
+    accounts.seed(signed_in);
+    accounts
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SwitchPhase {
+    Unmounting,
+    StoppingServer,
+    SwappingCredentials,
+    StartingServer,
+    Mounting,
+    Completed,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchProgress {
+    pub account_id: String,
+    pub phase: SwitchPhase,
+    /// Whether the account has a stored session, once the server is back
+    pub logged_in: Option<bool>,
+    pub message: Option<String>,
+}
+
+fn emit(app: &AppHandle, account_id: &str, phase: SwitchPhase, logged_in: Option<bool>, message: Option<String>) {
+    let progress = SwitchProgress { account_id: account_id.to_string(), phase, logged_in, message };
+    let _ = app.emit("account:switching", progress);
+}
+
+/// Fail the switch with `error`, reporting it to the UI.
+fn fail(app: &AppHandle, account_id: &str, error: CommandError) -> CommandError {
+    log::error!("Switching to account {} failed: {}", account_id, error);
+    emit(app, account_id, SwitchPhase::Failed, None, Some(error.to_string()));
+    error
+}
+
+/// Drop what the app holds for the previous profile after config.json
+/// switched accounts.
+fn profile_changed(app: &AppHandle) {
+    // The control process runs with the previous account's profile
+    app.state::<crate::sidecar_client::SidecarClient>().reset();
+    // Cached listings belong to the previous account
+    let cache = app.state::<crate::cache::MetadataCache>();
+    cache.clear();
+    cache.reload_settings();
+    // Staged uploads of the previous account wait until it is back
+    app.state::<crate::upload_spool::UploadSpool>().reload_settings();
+    crate::status::invalidate(app);
+    if let Err(e) = crate::integration::relabel() {
+        log::warn!("Failed to relabel the file manager entries: {}", e);
+    }
+}
+
+/// What a switch changed in config.json, to put back if it fails.
+struct SwitchUndo {
+    /// The accounts section before the switch
+    accounts: Option<serde_json::Value>,
+    /// The outgoing account's settings
+    settings: AccountConfig,
+    /// The incoming account's settings written over them
+    applied: AccountConfig,
+}
+
+impl SwitchUndo {
+    fn apply(&self, config: &mut serde_json::Value) {
+        self.settings.restore(&self.applied, config);
+        match (&self.accounts, config.as_object_mut()) {
+            (Some(accounts), _) => config[CONFIG_KEY] = accounts.clone(),
+            (None, Some(object)) => {
+                object.remove(CONFIG_KEY);
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Undo the config.json changes of a switch after the new account failed to
+/// start, and bring the previous account's bridge and mount up again. Only
+/// the accounts section and the keys the switch swapped are put back.
+async fn restore(app: &AppHandle, state: &State<'_, SidecarState>, undo: &SwitchUndo, was_mounted: bool) {
+    if let Err(e) = update_config_json(|config| {
+        undo.apply(config);
+        Ok(())
+    }) {
+        log::error!("Failed to restore the previous account: {}", e);
+        return;
+    }
+    profile_changed(app);
+    // A bridge that came up too slowly still runs with the new profile
+    let _ = crate::sidecar::stop_sidecar(app.clone(), state.clone()).await;
+    if let Err(e) = crate::sidecar::start_sidecar(app.clone(), state.clone(), None).await {
+        log::error!("Failed to restart the previous account: {}", e);
+        return;
+    }
+    let logged_in = crate::auto_mount::wait_for_server(app).await;
+    if was_mounted && logged_in == Some(true) {
+        if let Err(e) = crate::sidecar::mount_drive(app.clone(), state.clone()).await {
+            log::warn!("Failed to remount the previous account: {}", e);
+        }
+    }
+    log::info!("Restored the previous account after a failed switch");
+}
+
+/// Make `account_id` the active account. The server and the mount are
+/// restored to their state before the switch; an account without a stored
+/// session comes back signed out, ready for `login`. When the bridge doesn't
+/// start with the new account, the previous one is restored.
+#[tauri::command]
+pub async fn switch_account(app: AppHandle, state: State<'_, SidecarState>, account_id: String) -> Result<(), CommandError> {
+    let account_id = account_id.trim().to_string();
+    if account_id.is_empty() {
+        return Err(CommandError::InvalidEmail("Account id must not be empty".into()));
+    }
+
+    let (signed_in, status) = signed_in_account(&app, &state).await?;
+    if seeded(&read_config_json()?, signed_in.as_deref()).is_active(&account_id) {
+        emit(&app, &account_id, SwitchPhase::Completed, Some(status.auth.logged_in), None);
+        return Ok(());
+    }
+
+    let was_running = status.server.running;
+    let was_mounted = matches!(crate::sidecar::check_mount_status(app.clone(), state.clone()).await, Ok(Some(_)));
+
+    if was_mounted {
+        emit(&app, &account_id, SwitchPhase::Unmounting, None, None);
+        crate::sidecar::unmount_drive(app.clone(), state.clone())
+            .await
+            .map_err(|e| fail(&app, &account_id, e))?;
+    }
+    if was_running {
+        emit(&app, &account_id, SwitchPhase::StoppingServer, None, None);
+        crate::sidecar::stop_sidecar(app.clone(), state.clone())
+            .await
+            .map_err(|e| fail(&app, &account_id, e))?;
+    }
+
+    emit(&app, &account_id, SwitchPhase::SwappingCredentials, None, None);
+    let undo = update_config_json(|config| {
+        let previous = config.get(CONFIG_KEY).cloned();
+        let mut accounts = seeded(config, signed_in.as_deref());
+        let settings = AccountConfig::capture(config);
+        if let Some(active) = accounts.active.clone() {
+            if let Some(outgoing) = accounts.find_mut(&active) {
+                outgoing.config = settings.clone();
+            }
+        }
+        accounts.activate(&account_id, status.auth.logged_in);
+        let applied = accounts.active_account().map(|a| a.config.for_activation()).unwrap_or_default();
+        applied.apply(config);
+        store(config, &accounts)?;
+        Ok(SwitchUndo { accounts: previous, settings, applied })
+    })
+    .map_err(|e| fail(&app, &account_id, e))?;
+    profile_changed(&app);
+    log::info!("Switched to account {}", account_id);
+
+    let mut logged_in = None;
+    if was_running {
+        emit(&app, &account_id, SwitchPhase::StartingServer, None, None);
+        let started = match crate::sidecar::start_sidecar(app.clone(), state.clone(), None).await {
+            Ok(_) => crate::auto_mount::wait_for_server(&app).await.ok_or(CommandError::ServerInitTimeout),
+            Err(e) => Err(e),
+        };
+        match started {
+            Ok(started) => logged_in = Some(started),
+            Err(e) => {
+                let e = fail(&app, &account_id, e);
+                restore(&app, &state, &undo, was_mounted).await;
+                return Err(e);
+            }
+        }
+    }
+    if was_mounted && logged_in == Some(true) {
+        emit(&app, &account_id, SwitchPhase::Mounting, logged_in, None);
+        crate::sidecar::mount_drive(app.clone(), state)
+            .await
+            .map_err(|e| fail(&app, &account_id, e))?;
+    }
+
+    emit(&app, &account_id, SwitchPhase::Completed, logged_in, None);
+    Ok(())
+}
+
+/// Settings of account `id`; for the active account these are the ones in
+/// effect.
+#[tauri::command]
+pub async fn get_account_config(app: AppHandle, state: State<'_, SidecarState>, id: String) -> Result<AccountConfig, CommandError> {
+    let (signed_in, _) = signed_in_account(&app, &state).await?;
+    let config = read_config_json()?;
+    let accounts = seeded(&config, signed_in.as_deref());
+    if accounts.is_active(&id) {
+        return Ok(AccountConfig::capture(&config));
+    }
+    accounts.find(&id).map(|a| a.config.clone()).ok_or(CommandError::AccountNotFound(id))
+}
+
+/// Update the settings of account `id` with the fields set in `patch`. For
+/// the active account they go straight into config.json and take effect
+/// the next time the bridge starts; other accounts get them on their next
+/// switch.
+#[tauri::command]
+pub async fn set_account_config(app: AppHandle, state: State<'_, SidecarState>, id: String, mut patch: AccountConfig) -> Result<AccountConfig, CommandError> {
+    patch.validate()?;
+    let (signed_in, _) = signed_in_account(&app, &state).await?;
+    let (updated, active) = update_config_json(|config| {
+        let mut accounts = seeded(config, signed_in.as_deref());
+        let active = accounts.is_active(&id);
+        let updated = if active {
+            patch.apply(config);
+            AccountConfig::capture(config)
+        } else {
+            let account = accounts.find_mut(&id).ok_or_else(|| CommandError::AccountNotFound(id.clone()))?;
+            account.config.merge(patch);
+            account.config.clone()
+        };
+        store(config, &accounts)?;
+        Ok((updated, active))
+    })?;
+    if active {
+        app.state::<crate::cache::MetadataCache>().reload_settings();
+        crate::status::invalidate(&app);
+        if let Err(e) = crate::integration::relabel() {
+            log::warn!("Failed to relabel the file manager entries: {}", e);
+        }
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_name_is_path_safe() {
+        let profile = profile_name(" Work.Me@Proton.me ");
+        assert!(profile.starts_with("work-me-proton-me-"));
+        assert!(profile.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+        assert_eq!(profile, profile_name("work.me@proton.me"));
+    }
+
+    #[test]
+    fn test_profile_names_keep_ids_apart() {
+        assert_ne!(profile_name("work@proton.me"), profile_name("work.proton.me"));
+        let mut accounts = AccountsConfig::default();
+        accounts.seed(Some("me@proton.me"));
+        accounts.activate("work@proton.me", false);
+        accounts.activate("work.proton.me", false);
+        assert_ne!(accounts.find("work@proton.me").unwrap().profile, accounts.find("work.proton.me").unwrap().profile);
+    }
+
+    #[test]
+    fn test_default_profile_in_use_is_not_handed_out() {
+        let mut accounts = AccountsConfig::default();
+        accounts.activate("work@proton.me", true);
+        assert_eq!(accounts.active_account().unwrap().profile, profile_name("work@proton.me"));
+    }
+
+    #[test]
+    fn test_first_account_keeps_default_profile() {
+        let mut accounts = AccountsConfig::default();
+        accounts.seed(Some("me@proton.me"));
+        accounts.activate("work@proton.me", false);
+        assert_eq!(accounts.find("me@proton.me").unwrap().profile, "");
+        assert_eq!(accounts.active_account().unwrap().profile, profile_name("work@proton.me"));
+
+        accounts.activate("ME@proton.me", false);
+        assert_eq!(accounts.active.as_deref(), Some("me@proton.me"));
+        assert_eq!(accounts.known.len(), 2);
+    }
+
+    #[test]
+    fn test_sign_in_takes_over_active_profile() {
+        let mut accounts = AccountsConfig::default();
+        accounts.signed_in("me@proton.me");
+        accounts.activate("work@proton.me", false);
+        accounts.signed_in("other@proton.me");
+        assert_eq!(
+            accounts.known,
+            vec![
+                KnownAccount { id: "me@proton.me".into(), profile: String::new(), config: AccountConfig::default() },
+                KnownAccount { id: "other@proton.me".into(), profile: profile_name("work@proton.me"), config: AccountConfig::default() },
+            ]
+        );
+        assert_eq!(accounts.active.as_deref(), Some("other@proton.me"));
+    }
+
+    #[test]
+    fn test_account_config_swaps_global_keys() {
+        let mut config = serde_json::json!({
+            "webdav": {"host": "127.0.0.1", "port": 8080},
+            "cache": {"enabled": true, "maxSizeMB": 100},
+            "remotePath": "/Work",
+        });
+        let outgoing = AccountConfig::capture(&config);
+        assert_eq!(outgoing.port, Some(8080));
+        assert_eq!(outgoing.remote_path.as_deref(), Some("/Work"));
+        assert_eq!(outgoing.mounts, None);
+
+        let incoming = AccountConfig { port: Some(8081), ..Default::default() };
+        incoming.for_activation().apply(&mut config);
+        assert_eq!(config["webdav"], serde_json::json!({"host": "127.0.0.1", "port": 8081}));
+        assert_eq!(config["cache"]["maxSizeMB"], 100);
+        assert_eq!(config["remotePath"], "/");
+        assert_eq!(config["mounts"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_failed_switch_undoes_only_swapped_keys() {
+        let mut config = serde_json::json!({
+            "webdav": {"host": "127.0.0.1", "port": 8080},
+            "cache": {"maxSizeMB": 100},
+            "accounts": {"active": "me@proton.me", "known": []},
+        });
+        let undo = SwitchUndo {
+            accounts: config.get(CONFIG_KEY).cloned(),
+            settings: AccountConfig::capture(&config),
+            applied: AccountConfig { port: Some(8081), ..Default::default() }.for_activation(),
+        };
+        undo.applied.apply(&mut config);
+        config[CONFIG_KEY] = serde_json::json!({"active": "work@proton.me", "known": []});
+        // Written by other commands while the switch ran
+        config["cache"]["maxSizeMB"] = 200.into();
+        config["travelMode"] = serde_json::json!({"enabled": true});
+
+        undo.apply(&mut config);
+        assert_eq!(config["webdav"], serde_json::json!({"host": "127.0.0.1", "port": 8080}));
+        assert_eq!(config["cache"]["maxSizeMB"], 200);
+        assert_eq!(config["travelMode"]["enabled"], true);
+        assert_eq!(config[CONFIG_KEY]["active"], "me@proton.me");
+        assert!(config.get("remotePath").is_none());
+        assert!(config.get("mounts").is_none());
+    }
+
+    #[test]
+    fn test_account_config_patch() {
+        let mut stored = AccountConfig { port: Some(8080), remote_path: Some("/".into()), ..Default::default() };
+        let mut patch = AccountConfig { remote_path: Some("Documents/".into()), ..Default::default() };
+        patch.validate().unwrap();
+        stored.merge(patch);
+        assert_eq!(stored.port, Some(8080));
+        assert_eq!(stored.remote_path.as_deref(), Some("/Documents"));
+        assert!(AccountConfig { port: Some(0), ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use rusqlite::{Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::dav::{normalize_path, DavClient, DavEntry};
+use crate::db;
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Activity feed
+// ============================================================================
+//
+// The bridge has no change feed, so remote activity is derived by diffing
+// folder listings: whenever a fresh depth-1 PROPFIND of a folder comes back
+// (through the gateway while caching is on, or from the poller below), it is
+// compared with the previous listing of that folder, and new, changed and
+// vanished items are added to the feed and announced with an `activity:new`
+// event. The first listing of a folder only sets the baseline. A background
+// task re-lists the most recently seen folders every few minutes so changes
+// made elsewhere show up without anyone browsing. The feed is kept in the
+// state database so it survives restarts; the listings it is diffed against
+// live in memory only.
+
+/// Items kept in the feed.
+const MAX_ITEMS: usize = 500;
+
+const ID_COUNTER: &str = "activity";
+
+/// Folder listings remembered for diffing.
+const MAX_FOLDERS: usize = 2000;
+
+const DEFAULT_LIMIT: usize = 50;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Folders re-listed per poll, most recently seen first.
+const POLL_FOLDERS: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivityKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityItem {
+    pub id: u64,
+    pub kind: ActivityKind,
+    pub path: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    /// Unix timestamp (seconds) the change was noticed
+    pub detected_at: u64,
+}
+
+/// What identifies a version of an item in a listing.
+#[derive(Clone, Debug, PartialEq)]
+struct Fingerprint {
+    is_dir: bool,
+    size: Option<u64>,
+    modified: Option<String>,
+    etag: Option<String>,
+}
+
+impl From<&DavEntry> for Fingerprint {
+    fn from(entry: &DavEntry) -> Self {
+        Self { is_dir: entry.is_dir, size: entry.size, modified: entry.modified.clone(), etag: entry.etag.clone() }
+    }
+}
+
+struct Listing {
+    seen: Instant,
+    items: HashMap<String, Fingerprint>,
+}
+
+#[derive(Default)]
+struct History {
+    next_id: u64,
+    /// Oldest first
+    items: VecDeque<ActivityItem>,
+}
+
+#[derive(Default)]
+pub struct ActivityFeed {
+    listings: Mutex<HashMap<String, Listing>>,
+    /// Loaded from the state database on first use
+    history: Mutex<Option<History>>,
+}
+
+/// Changes between two listings of a folder, in path order. Folders only
+/// count as modified when they turn into files or back, since their own
+/// timestamps change with every child.
+fn diff_listing(old: &HashMap<String, Fingerprint>, new: &HashMap<String, Fingerprint>) -> Vec<(ActivityKind, String, Fingerprint)> {
+    let mut changes = Vec::new();
+    for (path, print) in new {
+        match old.get(path) {
+            None => changes.push((ActivityKind::Created, path.clone(), print.clone())),
+            Some(before) if before.is_dir != print.is_dir || (!print.is_dir && before != print) => {
+                changes.push((ActivityKind::Modified, path.clone(), print.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    for (path, print) in old {
+        if !new.contains_key(path) {
+            changes.push((ActivityKind::Deleted, path.clone(), print.clone()));
+        }
+    }
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+    changes
+}
+
+fn read_history(conn: &Connection) -> rusqlite::Result<History> {
+    let mut stmt = conn.prepare("SELECT data FROM activity ORDER BY id")?;
+    let items = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|json| db::from_json(&json?))
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(History { next_id: db::counter(conn, ID_COUNTER)?, items })
+}
+
+/// Add `items` and drop what no longer fits in the feed.
+fn append_history(tx: &Transaction, items: &[ActivityItem], next_id: u64) -> rusqlite::Result<()> {
+    for item in items {
+        tx.execute("INSERT INTO activity (id, data) VALUES (?1, ?2)", rusqlite::params![item.id as i64, db::to_json(item)?])?;
+    }
+    tx.execute(
+        "DELETE FROM activity WHERE id NOT IN (SELECT id FROM activity ORDER BY id DESC LIMIT ?1)",
+        [MAX_ITEMS as i64],
+    )?;
+    db::set_counter(tx, ID_COUNTER, next_id)
+}
+
+impl ActivityFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` on the history, loading it first if needed.
+    fn with_history<T>(&self, app: &AppHandle, f: impl FnOnce(&mut History) -> T) -> Result<T, CommandError> {
+        let mut guard = self.history.lock().unwrap();
+        if guard.is_none() {
+            *guard = Some(db::with(app, |conn| read_history(conn))?);
+        }
+        Ok(f(guard.as_mut().expect("history was just loaded")))
+    }
+
+    /// Compare a fresh depth-1 listing of `folder` with the previous one and
+    /// record the differences.
+    pub fn observe(&self, app: &AppHandle, folder: &str, entries: &[DavEntry]) {
+        let folder = normalize_path(folder);
+        let items: HashMap<String, Fingerprint> = entries.iter().filter(|e| e.path != folder).map(|e| (e.path.clone(), e.into())).collect();
+
+        let previous = {
+            let mut listings = self.listings.lock().unwrap();
+            if listings.len() >= MAX_FOLDERS && !listings.contains_key(&folder) {
+                if let Some(oldest) = listings.iter().min_by_key(|(_, l)| l.seen).map(|(k, _)| k.clone()) {
+                    listings.remove(&oldest);
+                }
+            }
+            listings.insert(folder, Listing { seen: Instant::now(), items: items.clone() }).map(|l| l.items)
+        };
+        let Some(previous) = previous else { return };
+        let changes = diff_listing(&previous, &items);
+        if changes.is_empty() {
+            return;
+        }
+
+        let now = crate::sidecar::unix_now();
+        let recorded = self.with_history(app, |history| {
+            let new_items: Vec<ActivityItem> = changes
+                .into_iter()
+                .map(|(kind, path, print)| {
+                    history.next_id += 1;
+                    ActivityItem { id: history.next_id, kind, path, is_dir: print.is_dir, size: print.size, detected_at: now }
+                })
+                .collect();
+            history.items.extend(new_items.iter().cloned());
+            while history.items.len() > MAX_ITEMS {
+                history.items.pop_front();
+            }
+            (new_items, history.next_id)
+        });
+        let (new_items, next_id) = match recorded {
+            Ok(recorded) => recorded,
+            Err(e) => {
+                log::warn!("Failed to load the activity feed: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = db::transaction(app, |tx| append_history(tx, &new_items, next_id)) {
+            log::warn!("Failed to save activity: {}", e);
+        }
+        log::debug!("Activity: {} change(s) in listing", new_items.len());
+        let _ = app.emit("activity:new", new_items);
+    }
+
+    /// The most recently seen folders, newest first.
+    fn recent_folders(&self, limit: usize) -> Vec<String> {
+        let listings = self.listings.lock().unwrap();
+        let mut folders: Vec<(&String, Instant)> = listings.iter().map(|(k, l)| (k, l.seen)).collect();
+        folders.sort_by_key(|&(_, seen)| std::cmp::Reverse(seen));
+        folders.into_iter().take(limit).map(|(k, _)| k.clone()).collect()
+    }
+
+    /// Forget all listings and activity, e.g. when the account signs out.
+    pub fn clear(&self, app: &AppHandle) {
+        self.listings.lock().unwrap().clear();
+        let cleared = self.with_history(app, |history| history.items.clear()).and_then(|_| {
+            db::with(app, |conn| conn.execute("DELETE FROM activity", []).map(|_| ()))
+        });
+        if let Err(e) = cleared {
+            log::warn!("Failed to clear the activity feed: {}", e);
+        }
+    }
+
+    fn recent(&self, app: &AppHandle, limit: usize) -> Result<Vec<ActivityItem>, CommandError> {
+        self.with_history(app, |history| history.items.iter().rev().take(limit).cloned().collect())
+    }
+}
+
+/// Re-list recently seen folders so remote changes are noticed without
+/// browsing. Skipped in travel mode and while the bridge is down.
+pub async fn run(app: AppHandle) {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if app.state::<ReadOnlyState>().status().travel_mode {
+            continue;
+        }
+        let Ok(client) = DavClient::for_app(&app) else { continue };
+        let feed = app.state::<ActivityFeed>();
+        for folder in feed.recent_folders(POLL_FOLDERS) {
+            match client.propfind(&folder, 1).await {
+                Ok(entries) => feed.observe(&app, &folder, &entries),
+                Err(e) => log::debug!("Activity poll of {} failed: {}", folder, e),
+            }
+        }
+    }
+}
+
+/// Recently noticed remote changes, newest first (default 50, at most 500).
+#[tauri::command]
+pub async fn get_recent_activity(app: AppHandle, feed: State<'_, ActivityFeed>, limit: Option<usize>) -> Result<Vec<ActivityItem>, CommandError> {
+    feed.recent(&app, limit.unwrap_or(DEFAULT_LIMIT).min(MAX_ITEMS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(size: u64, etag: &str) -> Fingerprint {
+        Fingerprint { is_dir: false, size: Some(size), modified: None, etag: Some(etag.into()) }
+    }
+
+    fn dir(modified: &str) -> Fingerprint {
+        Fingerprint { is_dir: true, size: None, modified: Some(modified.into()), etag: None }
+    }
+
+    #[test]
+    fn test_diff_listing() {
+        let old = HashMap::from([
+            ("/a.txt".to_string(), file(1, "e1")),
+            ("/b.txt".to_string(), file(2, "e2")),
+            ("/Docs".to_string(), dir("Mon")),
+        ]);
+        let new = HashMap::from([
+            ("/a.txt".to_string(), file(1, "e1")),
+            ("/c.txt".to_string(), file(3, "e3")),
+            ("/Docs".to_string(), dir("Tue")),
+            ("/b.txt".to_string(), file(5, "e4")),
+        ]);
+        let changes: Vec<(ActivityKind, String)> = diff_listing(&old, &new).into_iter().map(|(k, p, _)| (k, p)).collect();
+        assert_eq!(
+            changes,
+            vec![(ActivityKind::Modified, "/b.txt".to_string()), (ActivityKind::Created, "/c.txt".to_string())]
+        );
+
+        let changes = diff_listing(&new, &HashMap::new());
+        assert_eq!(changes.len(), 4);
+        assert!(changes.iter().all(|(k, _, _)| *k == ActivityKind::Deleted));
+    }
+}
@@ -0,0 +1,375 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Per-application access
+// ============================================================================
+//
+// With app access control on, the gateway only serves loopback connections
+// from allow-listed programs, so other software running as the user cannot
+// read the drive through the local port. The program behind a connection is
+// found from the kernel's socket table: the client end of the connection in
+// /proc/net/tcp{,6} gives the socket's inode, and the process holding that
+// inode in /proc/<pid>/fd is the client; only the user's own processes are
+// searched. A program is allowed only when its executable's canonical path
+// is on the list; process names and bare file names are chosen by the
+// program itself, so they are not trusted. A name given without a path is
+// looked up once in PATH and the usual libexec folders and stored as the
+// path found. GVFS mounts are served by
+// `gvfsd-dav`, so it has to be allowed for the mounted drive to work; the
+// default list has it along with common file managers, wherever they are
+// installed.
+//
+// Connections whose process cannot be determined (it belongs to another
+// user, or has already exited) are refused. Connections from other machines
+// are left to network sharing's own checks. Refusals are logged, announced
+// with `app-access:denied` and kept in a short list so they can be allowed
+// from the UI. Only Linux exposes the socket table this way. The sidecar's
+// private port or socket behind the gateway is not covered.
+
+const CONFIG_KEY: &str = "appAccess";
+
+/// Refusals kept for `list_app_access_denials`.
+const MAX_DENIALS: usize = 50;
+
+const DEFAULT_ALLOWED: &[&str] = &["gvfsd-dav", "nautilus", "nemo", "caja", "thunar", "dolphin", "pcmanfm", "pcmanfm-qt"];
+
+/// Where helpers like `gvfsd-dav` are installed, besides PATH.
+const LIBEXEC_DIRS: &[&str] = &["/usr/libexec", "/usr/libexec/gvfs", "/usr/lib", "/usr/lib/gvfs"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppAccessSettings {
+    pub enabled: bool,
+    /// Canonical executable paths
+    pub allowed: Vec<String>,
+}
+
+impl Default for AppAccessSettings {
+    fn default() -> Self {
+        Self { enabled: false, allowed: default_allowed().to_vec() }
+    }
+}
+
+/// The installed paths of `DEFAULT_ALLOWED`, looked up once.
+fn default_allowed() -> &'static [String] {
+    static ALLOWED: OnceLock<Vec<String>> = OnceLock::new();
+    ALLOWED.get_or_init(|| {
+        let dirs = search_dirs();
+        let mut allowed: Vec<String> = DEFAULT_ALLOWED.iter().filter_map(|name| find_executable(name, &dirs)).collect();
+        allowed.sort();
+        allowed.dedup();
+        allowed
+    })
+}
+
+/// PATH followed by `LIBEXEC_DIRS`.
+fn search_dirs() -> Vec<PathBuf> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::split_paths(&path).chain(LIBEXEC_DIRS.iter().map(PathBuf::from)).collect()
+}
+
+/// Canonical path of the first file called `name` in `dirs`.
+fn find_executable(name: &str, dirs: &[PathBuf]) -> Option<String> {
+    dirs.iter()
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+        .and_then(|candidate| std::fs::canonicalize(candidate).ok())
+        .map(|path| path.display().to_string())
+}
+
+/// The canonical path an allow-list entry stands for: an absolute path with
+/// its links resolved, or a bare name looked up in `dirs`. `None` when a
+/// name is not installed or the entry is a relative path.
+fn resolve_entry(entry: &str, dirs: &[PathBuf]) -> Option<String> {
+    let path = Path::new(entry);
+    if path.is_absolute() {
+        // A program not installed yet is kept as given
+        return Some(std::fs::canonicalize(path).map(|p| p.display().to_string()).unwrap_or_else(|_| entry.to_string()));
+    }
+    if path.components().count() != 1 {
+        return None;
+    }
+    find_executable(entry, dirs)
+}
+
+/// Resolve `entries` to canonical paths, sorted and without duplicates.
+/// Entries that can't be resolved are returned separately.
+fn resolve_allowed(entries: &[String], dirs: &[PathBuf]) -> (Vec<String>, Vec<String>) {
+    let mut allowed = Vec::new();
+    let mut unresolved = Vec::new();
+    for entry in entries.iter().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        match resolve_entry(entry, dirs) {
+            Some(path) => allowed.push(path),
+            None => unresolved.push(entry.to_string()),
+        }
+    }
+    allowed.sort();
+    allowed.dedup();
+    (allowed, unresolved)
+}
+
+/// A local program, as far as it could be identified.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerProcess {
+    pub pid: u32,
+    pub exe: Option<String>,
+    /// `comm` of the process
+    pub name: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessDenial {
+    pub peer: String,
+    /// `None` when the process could not be determined
+    pub process: Option<PeerProcess>,
+    /// Unix seconds
+    pub at: u64,
+}
+
+/// The persisted settings with allowed executables resolved to their
+/// canonical paths.
+fn load_settings() -> AppAccessSettings {
+    let mut settings: AppAccessSettings = read_config_section(CONFIG_KEY);
+    let (allowed, unresolved) = resolve_allowed(&settings.allowed, &search_dirs());
+    if !unresolved.is_empty() {
+        log::warn!("App access: not allowing {:?}, no such executable", unresolved);
+    }
+    settings.allowed = allowed;
+    settings
+}
+
+pub struct AppAccessState {
+    settings: Mutex<AppAccessSettings>,
+    denials: Mutex<VecDeque<AccessDenial>>,
+}
+
+impl AppAccessState {
+    pub fn from_config() -> Self {
+        Self { settings: Mutex::new(load_settings()), denials: Mutex::new(VecDeque::new()) }
+    }
+
+    /// Pick up the `appAccess` section after config.json was replaced.
+    pub fn reload_settings(&self) {
+        *self.settings.lock().unwrap() = load_settings();
+    }
+
+    fn settings(&self) -> AppAccessSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.lock().unwrap().enabled
+    }
+
+    fn record_denial(&self, denial: AccessDenial) {
+        let mut denials = self.denials.lock().unwrap();
+        denials.push_back(denial);
+        while denials.len() > MAX_DENIALS {
+            denials.pop_front();
+        }
+    }
+}
+
+/// Whether the executable of `process` is on `allowed`, a list of canonical
+/// paths. The kernel reports `exe` with its links already resolved.
+fn is_allowed(process: &PeerProcess, allowed: &[String]) -> bool {
+    process.exe.as_deref().is_some_and(|exe| Path::new(exe).is_absolute() && allowed.iter().any(|entry| entry == exe))
+}
+
+/// An address in /proc/net/tcp{,6}: the address as 32-bit words in host
+/// byte order, and the port, both in hex.
+fn parse_proc_addr(field: &str) -> Option<SocketAddr> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    if addr.len() % 8 != 0 {
+        return None;
+    }
+    let mut octets = Vec::with_capacity(addr.len() / 2);
+    for i in (0..addr.len()).step_by(8) {
+        let word = u32::from_str_radix(addr.get(i..i + 8)?, 16).ok()?;
+        octets.extend_from_slice(&word.to_ne_bytes());
+    }
+    let ip = match octets.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(octets).ok()?)),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(octets).ok()?)),
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Inode of the socket from `local` to `remote` in the contents of a
+/// /proc/net/tcp{,6} table.
+fn find_socket_inode(table: &str, local: SocketAddr, remote: SocketAddr) -> Option<u64> {
+    let same = |a: SocketAddr, b: SocketAddr| a.port() == b.port() && a.ip().to_canonical() == b.ip().to_canonical();
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (l, r) = (parse_proc_addr(fields.get(1)?)?, parse_proc_addr(fields.get(2)?)?);
+        (same(l, local) && same(r, remote)).then(|| fields.get(9)?.parse().ok()).flatten()
+    })
+}
+
+/// The local process holding the client end of a connection from `peer` to
+/// the gateway's `local` address. Blocks on /proc.
+#[cfg(target_os = "linux")]
+fn peer_process(peer: SocketAddr, local: SocketAddr) -> Option<PeerProcess> {
+    use std::os::unix::fs::MetadataExt;
+
+    let inode = ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|table| std::fs::read_to_string(table).ok())
+        .find_map(|table| find_socket_inode(&table, peer, local))?;
+    let target = format!("socket:[{}]", inode);
+    // Only our own processes' fd tables are readable, so skip the rest
+    // without listing them.
+    let uid = std::fs::metadata("/proc/self").ok()?.uid();
+    for proc_entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = proc_entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+        if !proc_entry.metadata().is_ok_and(|m| m.uid() == uid) {
+            continue;
+        }
+        let Ok(fds) = std::fs::read_dir(proc_entry.path().join("fd")) else {
+            continue;
+        };
+        let holds = fds
+            .flatten()
+            .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|link| link.as_os_str() == target.as_str()));
+        if holds {
+            let exe = std::fs::read_link(proc_entry.path().join("exe")).ok().map(|p| p.display().to_string());
+            let name = std::fs::read_to_string(proc_entry.path().join("comm")).ok().map(|s| s.trim_end().to_string());
+            return Some(PeerProcess { pid, exe, name });
+        }
+    }
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peer_process(_peer: SocketAddr, _local: SocketAddr) -> Option<PeerProcess> {
+    None
+}
+
+/// Whether the gateway may serve the connection from `peer` to its `local`
+/// address. Only loopback connections are checked.
+pub async fn admit(app: &AppHandle, peer: SocketAddr, local: SocketAddr) -> bool {
+    let state = app.state::<AppAccessState>();
+    let settings = state.settings();
+    if !settings.enabled || !peer.ip().to_canonical().is_loopback() {
+        return true;
+    }
+    let process = tauri::async_runtime::spawn_blocking(move || peer_process(peer, local)).await.ok().flatten();
+    if let Some(process) = &process {
+        if process.pid == std::process::id() || is_allowed(process, &settings.allowed) {
+            return true;
+        }
+    }
+    log::info!("Refused gateway connection from {} ({:?}): not an allowed application", peer, process);
+    let denial = AccessDenial { peer: peer.to_string(), process, at: crate::sidecar::unix_now() };
+    state.record_denial(denial.clone());
+    let _ = app.emit("app-access:denied", denial);
+    false
+}
+
+#[tauri::command]
+pub async fn get_app_access(state: State<'_, AppAccessState>) -> Result<AppAccessSettings, CommandError> {
+    Ok(state.settings())
+}
+
+/// Replace the app access settings. Applies to new connections.
+#[tauri::command]
+pub async fn set_app_access(state: State<'_, AppAccessState>, mut settings: AppAccessSettings) -> Result<AppAccessSettings, CommandError> {
+    if settings.enabled && !cfg!(target_os = "linux") {
+        return Err(CommandError::InvalidAppAccess("Only supported on Linux".into()));
+    }
+    let (allowed, unresolved) = resolve_allowed(&settings.allowed, &search_dirs());
+    if let Some(entry) = unresolved.first() {
+        return Err(CommandError::InvalidAppAccess(format!("{} is not an installed program; give its full path", entry)));
+    }
+    settings.allowed = allowed;
+    if settings.enabled && settings.allowed.is_empty() {
+        return Err(CommandError::InvalidAppAccess("At least one application must be allowed".into()));
+    }
+    write_config_section(CONFIG_KEY, &settings)?;
+    *state.settings.lock().unwrap() = settings.clone();
+    log::info!("App access control {} ({} allowed)", if settings.enabled { "enabled" } else { "disabled" }, settings.allowed.len());
+    Ok(settings)
+}
+
+/// Recent refused connections, oldest first.
+#[tauri::command]
+pub async fn list_app_access_denials(state: State<'_, AppAccessState>) -> Result<Vec<AccessDenial>, CommandError> {
+    Ok(state.denials.lock().unwrap().iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_net_addresses() {
+        assert_eq!(parse_proc_addr("0100007F:1F90"), Some("127.0.0.1:8080".parse().unwrap()));
+        assert_eq!(parse_proc_addr("00000000000000000000000001000000:0050"), Some("[::1]:80".parse().unwrap()));
+        assert_eq!(parse_proc_addr("0000000000000000FFFF00000100007F:A1B2"), Some("[::ffff:127.0.0.1]:41394".parse().unwrap()));
+        assert_eq!(parse_proc_addr("zz"), None);
+    }
+
+    #[test]
+    fn test_find_socket_inode_of_client_end() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 11111 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:A1B2 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 22222 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:1F90 0100007F:A1B2 01 00000000:00000000 00:00000000 00000000  1000        0 33333 1 0000000000000000 20 4 30 10 -1";
+        let peer: SocketAddr = "127.0.0.1:41394".parse().unwrap();
+        let gateway: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        assert_eq!(find_socket_inode(table, peer, gateway), Some(22222));
+        assert_eq!(find_socket_inode(table, "[::ffff:127.0.0.1]:41394".parse().unwrap(), gateway), Some(22222));
+        assert_eq!(find_socket_inode(table, "127.0.0.1:1".parse().unwrap(), gateway), None);
+    }
+
+    #[test]
+    fn test_allowed_only_by_exe_path() {
+        let process = PeerProcess { pid: 7, exe: Some("/usr/libexec/gvfsd-dav".into()), name: Some("gvfsd-dav".into()) };
+        assert!(is_allowed(&process, &["/usr/libexec/gvfsd-dav".into()]));
+        // A program can name itself anything
+        assert!(!is_allowed(&process, &["gvfsd-dav".into()]));
+        assert!(!is_allowed(&process, &["/usr/bin/gvfsd-dav".into(), "curl".into()]));
+        let unnamed = PeerProcess { pid: 8, name: Some("nautilus".into()), ..Default::default() };
+        assert!(!is_allowed(&unnamed, &["nautilus".into()]));
+    }
+
+    #[test]
+    fn test_entries_resolve_to_canonical_paths() {
+        let dir = std::env::temp_dir().join(format!("app-access-test-{}", std::process::id()));
+        let bin = dir.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("nautilus"), b"").unwrap();
+        let real = std::fs::canonicalize(bin.join("nautilus")).unwrap().display().to_string();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(bin.join("nautilus"), dir.join("files")).unwrap();
+
+        let dirs = vec![dir.join("missing"), bin.clone()];
+        assert_eq!(find_executable("nautilus", &dirs), Some(real.clone()));
+        assert_eq!(resolve_entry("curl", &dirs), None);
+        assert_eq!(resolve_entry("bin/nautilus", &dirs), None);
+
+        // Duplicates further apart than neighbours are dropped too
+        let entries: Vec<String> = vec!["nautilus".into(), "/opt/other".into(), " ".into(), real.clone(), "curl".into()];
+        let (allowed, unresolved) = resolve_allowed(&entries, &dirs);
+        let mut expected = vec!["/opt/other".to_string(), real.clone()];
+        expected.sort();
+        assert_eq!(allowed, expected);
+        assert_eq!(unresolved, vec!["curl".to_string()]);
+        #[cfg(unix)]
+        assert_eq!(resolve_entry(&dir.join("files").display().to_string(), &dirs), Some(real));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
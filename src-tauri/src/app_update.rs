@@ -0,0 +1,222 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Runtime, State};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// App updates
+// ============================================================================
+//
+// Desktop installs update themselves through tauri-plugin-updater. Builds
+// that set `PDWB_APP_UPDATE_URL` and `PDWB_APP_UPDATE_PUBLIC_KEY` (the
+// minisign key releases are signed with) check the feed for the user's
+// channel: `{channel}` in the URL becomes `stable` or `beta`, and the
+// plugin fills in `{{target}}`, `{{arch}}` and `{{current_version}}`. Each
+// install also sends a fixed rollout bucket (0-99) so the feed can offer a
+// release to a growing share of users before everyone gets it. The channel
+// and bucket are kept in the `updates` config section.
+
+const CONFIG_KEY: &str = "updates";
+
+const UPDATE_URL: Option<&str> = option_env!("PDWB_APP_UPDATE_URL");
+
+const UPDATE_PUBLIC_KEY: Option<&str> = option_env!("PDWB_APP_UPDATE_PUBLIC_KEY");
+
+const ROLLOUT_HEADER: &str = "X-Update-Rollout-Bucket";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct UpdateConfig {
+    #[serde(default)]
+    channel: UpdateChannel,
+    rollout_bucket: Option<u8>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AppUpdateInfo {
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    /// The version offered, if it is newer than the running app
+    pub version: Option<String>,
+    pub notes: Option<String>,
+    /// Publish date as RFC 3339
+    pub date: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgress {
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct AppUpdateState {
+    /// Update found by the last check
+    pending: Mutex<Option<Update>>,
+    /// Held while an update is downloaded and installed
+    installing: tokio::sync::Mutex<()>,
+}
+
+impl AppUpdateState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn failed(e: impl ToString) -> CommandError {
+    CommandError::AppUpdateFailed(e.to_string())
+}
+
+/// The updater plugin, verifying against the build's release key.
+pub fn plugin<R: Runtime>() -> tauri::plugin::TauriPlugin<R, tauri_plugin_updater::Config> {
+    let builder = tauri_plugin_updater::Builder::new();
+    match UPDATE_PUBLIC_KEY {
+        Some(key) => builder.pubkey(key),
+        None => builder,
+    }
+    .build()
+}
+
+fn feed_url(template: &str, channel: UpdateChannel) -> String {
+    template.replace("{channel}", channel.as_str())
+}
+
+/// The config section, assigning this install's rollout bucket on first use.
+fn load_config() -> Result<(UpdateChannel, u8), CommandError> {
+    let mut config: UpdateConfig = read_config_section(CONFIG_KEY);
+    let bucket = match config.rollout_bucket {
+        Some(bucket) => bucket,
+        None => {
+            let bucket = rand::thread_rng().gen_range(0..100);
+            config.rollout_bucket = Some(bucket);
+            write_config_section(CONFIG_KEY, &config)?;
+            bucket
+        }
+    };
+    Ok((config.channel, bucket))
+}
+
+#[tauri::command]
+pub async fn check_app_update(app: AppHandle, state: State<'_, AppUpdateState>) -> Result<AppUpdateInfo, CommandError> {
+    let template = UPDATE_URL.ok_or_else(|| failed("updates are not configured for this build"))?;
+    let (channel, bucket) = load_config()?;
+    let url = feed_url(template, channel).parse().map_err(failed)?;
+    let update = app
+        .updater_builder()
+        .endpoints(vec![url])
+        .map_err(failed)?
+        .header(ROLLOUT_HEADER, bucket.to_string())
+        .map_err(failed)?
+        .build()
+        .map_err(failed)?
+        .check()
+        .await
+        .map_err(failed)?;
+
+    let info = AppUpdateInfo {
+        current_version: app.package_info().version.to_string(),
+        channel,
+        version: update.as_ref().map(|u| u.version.clone()),
+        notes: update.as_ref().and_then(|u| u.body.clone()),
+        date: update
+            .as_ref()
+            .and_then(|u| u.date)
+            .and_then(|d| d.format(&time::format_description::well_known::Rfc3339).ok()),
+    };
+    if let Some(version) = &info.version {
+        log::info!("App update available on the {} channel: {}", channel.as_str(), version);
+    }
+    *state.pending.lock().unwrap() = update;
+    Ok(info)
+}
+
+/// Download and install the update found by the last check, emitting
+/// `app-update:progress` while downloading. The new version runs after the
+/// app restarts; returns that version.
+#[tauri::command]
+pub async fn download_app_update(app: AppHandle, state: State<'_, AppUpdateState>) -> Result<String, CommandError> {
+    let _installing = state.installing.try_lock().map_err(|_| failed("an update is already being installed"))?;
+    let update = state
+        .pending
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| failed("no update available; check for updates first"))?;
+
+    let mut downloaded = 0u64;
+    let progress_app = app.clone();
+    update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = progress_app.emit("app-update:progress", DownloadProgress { downloaded, total });
+            },
+            || log::info!("App update downloaded"),
+        )
+        .await
+        .map_err(failed)?;
+
+    *state.pending.lock().unwrap() = None;
+    log::info!("Installed app update {}", update.version);
+    let _ = app.emit("app-update:installed", update.version.clone());
+    Ok(update.version)
+}
+
+#[tauri::command]
+pub async fn get_update_channel() -> Result<UpdateChannel, CommandError> {
+    Ok(read_config_section::<UpdateConfig>(CONFIG_KEY).channel)
+}
+
+/// Choose the release channel. The next check uses it; an update found on
+/// the previous channel is dropped.
+#[tauri::command]
+pub async fn set_update_channel(state: State<'_, AppUpdateState>, channel: UpdateChannel) -> Result<UpdateChannel, CommandError> {
+    let mut config: UpdateConfig = read_config_section(CONFIG_KEY);
+    config.channel = channel;
+    write_config_section(CONFIG_KEY, &config)?;
+    *state.pending.lock().unwrap() = None;
+    log::info!("Update channel set to {}", channel.as_str());
+    Ok(channel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_url() {
+        let template = "https://updates.example.com/{channel}/{{target}}/{{current_version}}";
+        assert_eq!(feed_url(template, UpdateChannel::Beta), "https://updates.example.com/beta/{{target}}/{{current_version}}");
+        assert_eq!(feed_url(template, UpdateChannel::Stable), "https://updates.example.com/stable/{{target}}/{{current_version}}");
+    }
+
+    #[test]
+    fn test_config_defaults_to_stable() {
+        let config: UpdateConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.channel, UpdateChannel::Stable);
+        let config: UpdateConfig = serde_json::from_str(r#"{"channel": "beta", "rolloutBucket": 42}"#).unwrap();
+        assert_eq!((config.channel, config.rollout_bucket), (UpdateChannel::Beta, Some(42)));
+    }
+}
@@ -0,0 +1,220 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Login protection
+// ============================================================================
+//
+// With network sharing requiring a login, the gateway counts failed Basic
+// auth attempts per client address. `MAX_FAILURES` failures within
+// `FAILURE_WINDOW` ban the address for `BAN_DURATION`, doubled for every
+// further ban up to `MAX_BAN_DURATION`; while banned, its requests get 429
+// without the password being checked. A successful login clears the count.
+// Bans are announced with `auth:attack_suspected`, and failures and bans are
+// kept in an audit log for `get_auth_audit_log`. Loopback clients never
+// present credentials and are not tracked. Everything is kept in memory and
+// starts over with each launch of the app.
+
+/// Failures within the window that lead to a ban.
+const MAX_FAILURES: usize = 5;
+
+/// Seconds a failure counts towards a ban.
+const FAILURE_WINDOW: u64 = 10 * 60;
+
+/// Seconds of the first ban of an address.
+const BAN_DURATION: u64 = 15 * 60;
+
+const MAX_BAN_DURATION: u64 = 24 * 60 * 60;
+
+/// Audit entries kept; older ones are dropped first.
+const MAX_AUDIT_ENTRIES: usize = 500;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum AuthAuditEvent {
+    /// Credentials were presented and rejected
+    Failure { username: Option<String> },
+    /// The address was banned until `until` (Unix seconds)
+    Banned { until: u64, failures: usize },
+    /// The ban was lifted from the UI
+    Unbanned,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthAuditEntry {
+    pub address: String,
+    /// Unix seconds
+    pub at: u64,
+    #[serde(flatten)]
+    pub event: AuthAuditEvent,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AttackSuspectedEvent {
+    pub address: String,
+    pub failures: usize,
+    /// Unix seconds
+    pub banned_until: u64,
+}
+
+#[derive(Default)]
+struct Client {
+    /// Times of recent failures
+    failures: VecDeque<u64>,
+    banned_until: Option<u64>,
+    /// Bans so far, for the back-off
+    bans: u32,
+}
+
+#[derive(Default)]
+struct Guard {
+    clients: HashMap<IpAddr, Client>,
+    audit: VecDeque<AuthAuditEntry>,
+}
+
+impl Guard {
+    fn log(&mut self, ip: IpAddr, at: u64, event: AuthAuditEvent) {
+        self.audit.push_back(AuthAuditEntry { address: ip.to_string(), at, event });
+        while self.audit.len() > MAX_AUDIT_ENTRIES {
+            self.audit.pop_front();
+        }
+    }
+
+    fn banned_until(&self, ip: IpAddr, now: u64) -> Option<u64> {
+        self.clients.get(&ip)?.banned_until.filter(|until| *until > now)
+    }
+
+    /// Count a failure; returns the new ban if it led to one.
+    fn fail(&mut self, ip: IpAddr, username: Option<String>, now: u64) -> Option<AttackSuspectedEvent> {
+        self.log(ip, now, AuthAuditEvent::Failure { username });
+        let client = self.clients.entry(ip).or_default();
+        client.failures.retain(|t| now.saturating_sub(*t) < FAILURE_WINDOW);
+        client.failures.push_back(now);
+        if client.failures.len() < MAX_FAILURES {
+            return None;
+        }
+        let duration = BAN_DURATION.saturating_mul(1 << client.bans.min(16)).min(MAX_BAN_DURATION);
+        let failures = client.failures.len();
+        let until = now + duration;
+        client.banned_until = Some(until);
+        client.bans += 1;
+        client.failures.clear();
+        self.log(ip, now, AuthAuditEvent::Banned { until, failures });
+        Some(AttackSuspectedEvent { address: ip.to_string(), failures, banned_until: until })
+    }
+
+    fn succeed(&mut self, ip: IpAddr) {
+        if let Some(client) = self.clients.get_mut(&ip) {
+            client.failures.clear();
+        }
+    }
+
+    fn unban(&mut self, ip: IpAddr, now: u64) -> bool {
+        let lifted = self.clients.remove(&ip).is_some_and(|c| c.banned_until.is_some_and(|until| until > now));
+        if lifted {
+            self.log(ip, now, AuthAuditEvent::Unbanned);
+        }
+        lifted
+    }
+}
+
+#[derive(Default)]
+pub struct AuthGuardState {
+    guard: Mutex<Guard>,
+}
+
+impl AuthGuardState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seconds left on the ban of `ip`, if it is banned.
+    pub fn retry_after(&self, ip: IpAddr) -> Option<u64> {
+        let now = crate::sidecar::unix_now();
+        self.guard.lock().unwrap().banned_until(ip.to_canonical(), now).map(|until| until - now)
+    }
+
+    /// Record rejected credentials from `ip`.
+    pub fn record_failure(&self, app: &AppHandle, ip: IpAddr, username: Option<String>) {
+        let ip = ip.to_canonical();
+        let banned = self.guard.lock().unwrap().fail(ip, username, crate::sidecar::unix_now());
+        if let Some(event) = banned {
+            log::warn!("Banned {} after {} failed logins (until {})", event.address, event.failures, event.banned_until);
+            let _ = app.emit("auth:attack_suspected", event);
+        }
+    }
+
+    pub fn record_success(&self, ip: IpAddr) {
+        self.guard.lock().unwrap().succeed(ip.to_canonical());
+    }
+}
+
+/// Failed logins and bans, oldest first.
+#[tauri::command]
+pub async fn get_auth_audit_log(state: State<'_, AuthGuardState>) -> Result<Vec<AuthAuditEntry>, CommandError> {
+    Ok(state.guard.lock().unwrap().audit.iter().cloned().collect())
+}
+
+/// Lift the ban on `address` and forget its failures. Returns whether it
+/// was banned.
+#[tauri::command]
+pub async fn unban_auth_client(state: State<'_, AuthGuardState>, address: String) -> Result<bool, CommandError> {
+    let ip: IpAddr = address
+        .trim()
+        .parse()
+        .map_err(|_| CommandError::InvalidSharingConfig(format!("{:?} is not an IP address", address)))?;
+    let lifted = state.guard.lock().unwrap().unban(ip.to_canonical(), crate::sidecar::unix_now());
+    if lifted {
+        log::info!("Lifted the login ban on {}", ip);
+    }
+    Ok(lifted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ban_after_repeated_failures() {
+        let ip: IpAddr = "192.168.1.20".parse().unwrap();
+        let mut guard = Guard::default();
+        for i in 0..MAX_FAILURES as u64 - 1 {
+            assert!(guard.fail(ip, Some("alice".into()), 100 + i).is_none());
+        }
+        let ban = guard.fail(ip, None, 110).unwrap();
+        assert_eq!(ban.banned_until, 110 + BAN_DURATION);
+        assert_eq!(guard.banned_until(ip, 111), Some(110 + BAN_DURATION));
+        assert_eq!(guard.banned_until(ip, 110 + BAN_DURATION), None);
+        assert_eq!(guard.audit.len(), MAX_FAILURES + 1);
+
+        // The next ban lasts twice as long
+        let later = 110 + BAN_DURATION;
+        for i in 0..MAX_FAILURES as u64 {
+            guard.fail(ip, None, later + i);
+        }
+        assert_eq!(guard.banned_until(ip, later + 10), Some(later + MAX_FAILURES as u64 - 1 + 2 * BAN_DURATION));
+        assert!(guard.unban(ip, later + 10));
+        assert_eq!(guard.banned_until(ip, later + 10), None);
+    }
+
+    #[test]
+    fn test_old_failures_and_successes_reset_the_count() {
+        let ip: IpAddr = "10.0.0.2".parse().unwrap();
+        let mut guard = Guard::default();
+        for i in 0..MAX_FAILURES as u64 - 1 {
+            guard.fail(ip, None, i);
+        }
+        assert!(guard.fail(ip, None, FAILURE_WINDOW + 5).is_none());
+        guard.succeed(ip);
+        for i in 0..MAX_FAILURES as u64 - 1 {
+            assert!(guard.fail(ip, None, FAILURE_WINDOW + 10 + i).is_none());
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::sidecar::{read_config_json, write_config_section, CommandError, SidecarState};
+
+// ============================================================================
+// Mount at startup
+// ============================================================================
+//
+// Autostart only launches the app. With `autoMount` set in config.json the
+// app also mounts the drive once the bridge reports `server.running`,
+// retrying with exponential backoff since the network (or GVFS) is often
+// not ready right after login. Every step is reported as an
+// `auto-mount:progress` event so the UI can show what is going on.
+
+const CONFIG_KEY: &str = "autoMount";
+
+/// How long to wait for the bridge to come up before giving up.
+const SERVER_WAIT: Duration = Duration::from_secs(120);
+
+const SERVER_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+const MAX_ATTEMPTS: u32 = 6;
+
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AutoMountPhase {
+    WaitingForServer,
+    Mounting,
+    Retrying,
+    Mounted,
+    /// Nothing to do: already mounted or not signed in
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoMountProgress {
+    pub phase: AutoMountPhase,
+    /// 1-based mount attempt, once mounting has started
+    pub attempt: Option<u32>,
+    pub max_attempts: u32,
+    /// Delay before the next attempt when `phase` is `retrying`
+    pub retry_in_seconds: Option<u64>,
+    pub message: Option<String>,
+}
+
+fn emit(app: &AppHandle, phase: AutoMountPhase, attempt: Option<u32>, retry_in: Option<Duration>, message: Option<String>) {
+    let progress = AutoMountProgress {
+        phase,
+        attempt,
+        max_attempts: MAX_ATTEMPTS,
+        retry_in_seconds: retry_in.map(|d| d.as_secs()),
+        message,
+    };
+    let _ = app.emit("auto-mount:progress", progress);
+}
+
+/// Delay after the given failed attempt (1-based): 2s, 4s, 8s, ... capped.
+fn retry_delay(attempt: u32) -> Duration {
+    INITIAL_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+fn enabled() -> bool {
+    read_config_json()
+        .ok()
+        .and_then(|v| v.get(CONFIG_KEY).and_then(|x| x.as_bool()))
+        .unwrap_or(false)
+}
+
+/// Poll the bridge until it runs. Returns whether the user is signed in, or
+/// `None` if the bridge never came up.
+pub(crate) async fn wait_for_server(app: &AppHandle) -> Option<bool> {
+    let deadline = Instant::now() + SERVER_WAIT;
+    loop {
+        if let Ok(status) = crate::sidecar::get_status(app.clone(), app.state::<SidecarState>()).await {
+            if status.server.running {
+                return Some(status.auth.logged_in);
+            }
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(SERVER_POLL_INTERVAL).await;
+    }
+}
+
+/// Startup sequence spawned from `setup`; returns immediately unless
+/// `autoMount` is enabled.
+pub async fn run(app: AppHandle) {
+    if enabled() {
+        mount_when_ready(app).await;
+    }
+}
+
+/// Wait for the bridge, then mount the drive with retries. Also used for the
+/// `--mount` launch argument.
+pub async fn mount_when_ready(app: AppHandle) {
+    emit(&app, AutoMountPhase::WaitingForServer, None, None, None);
+    match wait_for_server(&app).await {
+        None => {
+            log::warn!("Auto-mount: bridge did not start within {}s", SERVER_WAIT.as_secs());
+            emit(&app, AutoMountPhase::Failed, None, None, Some("The WebDAV server did not start".into()));
+            return;
+        }
+        Some(false) => {
+            emit(&app, AutoMountPhase::Skipped, None, None, Some("Not signed in".into()));
+            return;
+        }
+        Some(true) => {}
+    }
+    if let Ok(Some(name)) = crate::sidecar::check_mount_status(app.clone(), app.state::<SidecarState>()).await {
+        emit(&app, AutoMountPhase::Skipped, None, None, Some(format!("Already mounted as {}", name)));
+        return;
+    }
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        // Attempts made while offline would only burn the retries
+        crate::network::wait_until_online(&app).await;
+        emit(&app, AutoMountPhase::Mounting, Some(attempt), None, None);
+        match crate::sidecar::mount_drive(app.clone(), app.state::<SidecarState>()).await {
+            Ok(()) => {
+                log::info!("Auto-mount succeeded on attempt {}", attempt);
+                emit(&app, AutoMountPhase::Mounted, Some(attempt), None, None);
+                return;
+            }
+            Err(e) if attempt < MAX_ATTEMPTS => {
+                let delay = retry_delay(attempt);
+                log::warn!("Auto-mount attempt {} failed: {}; retrying in {}s", attempt, e, delay.as_secs());
+                emit(&app, AutoMountPhase::Retrying, Some(attempt), Some(delay), Some(e.to_string()));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                log::error!("Auto-mount gave up after {} attempts: {}", attempt, e);
+                emit(&app, AutoMountPhase::Failed, Some(attempt), None, Some(e.to_string()));
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_auto_mount() -> Result<bool, CommandError> {
+    Ok(enabled())
+}
+
+#[tauri::command]
+pub async fn set_auto_mount(enabled: bool) -> Result<bool, CommandError> {
+    write_config_section(CONFIG_KEY, &enabled)?;
+    Ok(enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_doubles_and_caps() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(2), Duration::from_secs(4));
+        assert_eq!(retry_delay(5), Duration::from_secs(32));
+        assert_eq!(retry_delay(6), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(40), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_progress_serializes_camel_case() {
+        let progress = AutoMountProgress {
+            phase: AutoMountPhase::Retrying,
+            attempt: Some(2),
+            max_attempts: MAX_ATTEMPTS,
+            retry_in_seconds: Some(4),
+            message: None,
+        };
+        let json = serde_json::to_value(&progress).unwrap();
+        assert_eq!(json["phase"], "retrying");
+        assert_eq!(json["retryInSeconds"], 4);
+    }
+}
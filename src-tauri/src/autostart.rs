@@ -0,0 +1,75 @@
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::sidecar::{write_config_section, CommandError};
+
+// ============================================================================
+// Start at login
+// ============================================================================
+//
+// tauri-plugin-autostart owns the login entry: an XDG autostart desktop file
+// on Linux, a launch agent on macOS and a `Run` registry value on Windows.
+// The entry starts the app with `--minimized` so it comes up out of the way
+// (see `launch_args`). `get_autostart` reports whether the entry exists
+// rather than what was last asked for; `autoStart` in config.json mirrors it
+// for the bridge's status output. At launch an existing entry is rewritten
+// so it follows the app when its path or arguments change (e.g. a moved
+// AppImage).
+
+const CONFIG_KEY: &str = "autoStart";
+
+/// Arguments the login entry starts the app with.
+const LAUNCH_ARGS: &[&str] = &["--minimized"];
+
+fn failed(e: impl ToString) -> CommandError {
+    CommandError::IoError(format!("autostart: {}", e.to_string()))
+}
+
+/// The autostart plugin, configured with the login entry's arguments.
+pub fn plugin<R: Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri_plugin_autostart::Builder::new()
+        .args(LAUNCH_ARGS.iter().copied())
+        .build()
+}
+
+fn is_enabled(app: &AppHandle) -> Result<bool, CommandError> {
+    app.autolaunch().is_enabled().map_err(failed)
+}
+
+/// Refresh an existing entry and bring the config flag in line with it.
+/// Called from `setup`.
+pub fn reconcile(app: &AppHandle) {
+    let enabled = match is_enabled(app) {
+        Ok(enabled) => enabled,
+        Err(e) => {
+            log::warn!("Cannot read the login entry: {}", e);
+            return;
+        }
+    };
+    if enabled {
+        if let Err(e) = app.autolaunch().enable() {
+            log::warn!("Failed to refresh the login entry: {}", e);
+        }
+    }
+    let _ = write_config_section(CONFIG_KEY, &enabled);
+}
+
+#[tauri::command]
+pub async fn get_autostart(app: AppHandle) -> Result<bool, CommandError> {
+    is_enabled(&app)
+}
+
+/// Create or remove the login entry; returns the resulting state.
+#[tauri::command]
+pub async fn set_autostart(app: AppHandle, enabled: bool) -> Result<bool, CommandError> {
+    let autolaunch = app.autolaunch();
+    if enabled {
+        autolaunch.enable().map_err(failed)?;
+    } else if autolaunch.is_enabled().map_err(failed)? {
+        autolaunch.disable().map_err(failed)?;
+    }
+    let enabled = is_enabled(&app)?;
+    write_config_section(CONFIG_KEY, &enabled)?;
+    log::info!("Start at login {}", if enabled { "enabled" } else { "disabled" });
+    Ok(enabled)
+}
@@ -0,0 +1,477 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::dav::{join_path, normalize_path, DavClient};
+use crate::db;
+use crate::name_mapping::{self, NameMapper};
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+use crate::special_files::SkippedItem;
+
+// ============================================================================
+// Scheduled backups
+// ============================================================================
+//
+// A backup job copies a local folder into a new, timestamped folder under
+// its remote target (`2024-03-05_120000`, UTC) on a schedule such as
+// `daily` or `every 6h`, the same format as the cache's purge schedule.
+// Files go through the regular upload path, so they show up as transfers
+// and are verified when transfer verification is on. Once a snapshot is
+// complete, the oldest snapshots beyond the job's `retention` are deleted;
+// a snapshot that failed part way is deleted again so it is not mistaken
+// for a complete one. Files matching the backup ignore rules are left out;
+// symlinks and special files are handled by `special_files`, and they and
+// files that fail to upload are listed in the summary. Local names encoded
+// by `name_mapping` are uploaded under the names they stand for.
+//
+// Jobs live in the `backupJobs` config section, the last run of each in the
+// state database. One job runs at a time; scheduled runs wait while the
+// machine is suspending or offline, and no run starts in travel mode or on
+// a read-only share. Finished runs are announced as `backup:completed` with
+// the run's summary, or `backup:failed`.
+
+const CONFIG_KEY: &str = "backupJobs";
+
+/// How often the scheduler checks for jobs that are due.
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// Shortest accepted schedule interval.
+const MIN_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Failed files listed in a summary; the count includes the rest.
+const MAX_LISTED_FAILURES: usize = 50;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct BackupJobsConfig {
+    jobs: Vec<BackupJob>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupJob {
+    pub id: String,
+    pub source_path: String,
+    /// Remote folder holding the snapshots
+    pub remote_path: String,
+    /// `hourly`, `daily`, `weekly`, `every <n>h`/`<n>d`, or `never` for
+    /// manual runs only
+    pub schedule: String,
+    /// Snapshots kept
+    pub retention: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFailure {
+    /// Path relative to the source folder
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSummary {
+    pub job_id: String,
+    /// Remote folder of the snapshot
+    pub snapshot: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// Files skipped because they could not be uploaded
+    pub failed: usize,
+    pub failures: Vec<BackupFailure>,
+    /// Entries left out under the symlink and special file policy
+    pub skipped: Vec<SkippedItem>,
+    /// Old snapshots deleted for the retention
+    pub pruned: Vec<String>,
+    /// Unix seconds
+    pub started_at: u64,
+    pub finished_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LastRun {
+    /// Unix seconds
+    started_at: u64,
+    summary: Option<BackupSummary>,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupJobStatus {
+    #[serde(flatten)]
+    pub job: BackupJob,
+    /// Summary of the last successful run
+    pub last_summary: Option<BackupSummary>,
+    /// Unix seconds when the last run started
+    pub last_run: Option<u64>,
+    pub last_error: Option<String>,
+    /// Unix seconds; `None` for manual jobs
+    pub next_run: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BackupFailed {
+    job_id: String,
+    error: String,
+}
+
+/// Runs one backup at a time.
+#[derive(Default)]
+pub struct BackupJobsState {
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl BackupJobsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Folder name of a snapshot started at `at` (Unix seconds).
+fn snapshot_name(at: u64) -> String {
+    let t = time::OffsetDateTime::from_unix_timestamp(at as i64).unwrap_or(time::OffsetDateTime::UNIX_EPOCH);
+    format!(
+        "{:04}-{:02}-{:02}_{:02}{:02}{:02}",
+        t.year(),
+        u8::from(t.month()),
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second()
+    )
+}
+
+/// Whether `name` is a folder name made by `snapshot_name`.
+fn is_snapshot_name(name: &str) -> bool {
+    name.len() == 17
+        && name.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            10 => c == '_',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// Snapshots to delete so that `retention` remain, oldest first. Names sort
+/// by time.
+fn expired(mut snapshots: Vec<String>, retention: usize) -> Vec<String> {
+    snapshots.sort();
+    let excess = snapshots.len().saturating_sub(retention.max(1));
+    snapshots.truncate(excess);
+    snapshots
+}
+
+fn schedule_interval(schedule: &str) -> Result<Option<Duration>, CommandError> {
+    let interval = crate::cache::parse_schedule(schedule).map_err(CommandError::InvalidBackupJob)?;
+    if interval.is_some_and(|i| i < MIN_INTERVAL) {
+        return Err(CommandError::InvalidBackupJob("Backups cannot be scheduled more often than hourly".into()));
+    }
+    Ok(interval)
+}
+
+/// When `job` is due next, given when it last started.
+fn next_run(job: &BackupJob, last_run: Option<u64>) -> Option<u64> {
+    let interval = schedule_interval(&job.schedule).ok().flatten()?;
+    Some(last_run.map_or(0, |t| t + interval.as_secs()))
+}
+
+fn read_run(conn: &Connection, job_id: &str) -> rusqlite::Result<Option<LastRun>> {
+    conn.query_row("SELECT data FROM backup_job_runs WHERE job_id = ?1", [job_id], |row| row.get::<_, String>(0))
+        .optional()?
+        .map(|json| db::from_json(&json))
+        .transpose()
+}
+
+/// Record the latest run, keeping the summary of the last successful one
+/// when this one failed.
+fn write_run(conn: &Connection, job_id: &str, run: &LastRun) -> rusqlite::Result<()> {
+    let mut run = run.clone();
+    if run.summary.is_none() {
+        run.summary = read_run(conn, job_id)?.and_then(|previous| previous.summary);
+    }
+    conn.execute(
+        "INSERT INTO backup_job_runs (job_id, data) VALUES (?1, ?2)
+         ON CONFLICT (job_id) DO UPDATE SET data = excluded.data",
+        rusqlite::params![job_id, db::to_json(&run)?],
+    )?;
+    Ok(())
+}
+
+/// Upload the source folder of `job` into a new snapshot folder.
+async fn snapshot(app: &AppHandle, client: &DavClient, job: &BackupJob, summary: &mut BackupSummary) -> Result<(), CommandError> {
+    let root = PathBuf::from(&job.source_path);
+    let scan_root = root.clone();
+    let ignore = crate::ignore::rules(crate::ignore::IgnoreScope::Backup);
+    let special = crate::special_files::settings();
+    let names = NameMapper::current();
+    let (files, skipped) = tauri::async_runtime::spawn_blocking(move || crate::sync::scan_local(&scan_root, &ignore, &special, &names))
+        .await
+        .map_err(|e| CommandError::Unknown(e.to_string()))??;
+    summary.skipped = skipped;
+    let source = format!("backup:{}", job.id);
+    let mut renamed = Vec::new();
+
+    let mut parent = String::from("/");
+    for segment in summary.snapshot.split('/').filter(|s| !s.is_empty()) {
+        parent = join_path(&parent, segment);
+        client.mkcol(&parent).await?;
+    }
+    let mut dirs = std::collections::HashSet::new();
+    for rel in files.keys() {
+        let Some(local) = crate::sync::local_file(&root, rel, &names) else { continue };
+        let remote = join_path(&summary.snapshot, rel);
+        if names.local_rel(rel) != *rel {
+            renamed.push(name_mapping::renamed(&source, local.display().to_string(), remote.clone()));
+        }
+        let segments: Vec<&str> = rel.split('/').collect();
+        let mut dir = summary.snapshot.clone();
+        for segment in &segments[..segments.len() - 1] {
+            dir = join_path(&dir, segment);
+            if dirs.insert(dir.clone()) {
+                client.mkcol(&dir).await?;
+            }
+        }
+        match crate::special_files::upload(app, client, &local, &remote, false).await {
+            Ok(result) => {
+                summary.files += 1;
+                summary.bytes += result.bytes;
+            }
+            // The rest would fail the same way
+            Err(e @ (CommandError::ServerNotRunning | CommandError::TravelModeActive | CommandError::ReadOnlyShare)) => return Err(e),
+            Err(e) => {
+                log::warn!("Backup {}: {} failed: {}", job.id, rel, e);
+                summary.failed += 1;
+                if summary.failures.len() < MAX_LISTED_FAILURES {
+                    summary.failures.push(BackupFailure { path: rel.clone(), error: e.to_string() });
+                }
+            }
+        }
+    }
+    name_mapping::replace(app, &source, &renamed);
+    Ok(())
+}
+
+/// Delete the snapshots of `job` beyond its retention.
+async fn prune(client: &DavClient, job: &BackupJob) -> Result<Vec<String>, CommandError> {
+    let snapshots = client
+        .propfind(&job.remote_path, 1)
+        .await?
+        .into_iter()
+        .filter(|e| e.is_dir && e.path != job.remote_path && is_snapshot_name(e.path.rsplit('/').next().unwrap_or_default()))
+        .map(|e| e.path)
+        .collect();
+    let mut pruned = Vec::new();
+    for path in expired(snapshots, job.retention) {
+        client.delete(&path).await?;
+        pruned.push(path);
+    }
+    Ok(pruned)
+}
+
+/// Run `job` and record the run, reporting through `backup:*` events.
+async fn run_job(app: &AppHandle, job: &BackupJob) -> Result<BackupSummary, CommandError> {
+    app.state::<ReadOnlyState>().check_writable()?;
+    let state = app.state::<BackupJobsState>();
+    let _guard = state.lock.lock().await;
+
+    let started_at = crate::sidecar::unix_now();
+    let mut summary = BackupSummary {
+        job_id: job.id.clone(),
+        snapshot: join_path(&job.remote_path, &snapshot_name(started_at)),
+        started_at,
+        ..Default::default()
+    };
+    log::info!("Backup {}: {} -> {}", job.id, job.source_path, summary.snapshot);
+    let result = match DavClient::for_app(app) {
+        Ok(client) => match snapshot(app, &client, job, &mut summary).await {
+            Ok(()) => prune(&client, job).await.map(|pruned| summary.pruned = pruned),
+            Err(e) => {
+                if let Err(cleanup) = client.delete(&summary.snapshot).await {
+                    log::warn!("Backup {}: incomplete snapshot {} not deleted: {}", job.id, summary.snapshot, cleanup);
+                }
+                Err(e)
+            }
+        },
+        Err(e) => Err(e),
+    };
+    summary.finished_at = crate::sidecar::unix_now();
+
+    let run = match &result {
+        Ok(()) => {
+            log::info!("Backup {} done: {} files, {} bytes, {} failed", job.id, summary.files, summary.bytes, summary.failed);
+            let _ = app.emit("backup:completed", summary.clone());
+            LastRun { started_at, summary: Some(summary.clone()), error: None }
+        }
+        Err(e) => {
+            let _ = app.emit("backup:failed", BackupFailed { job_id: job.id.clone(), error: e.to_string() });
+            LastRun { started_at, summary: None, error: Some(e.to_string()) }
+        }
+    };
+    db::with(app, |conn| write_run(conn, &job.id, &run))?;
+    result.map(|()| summary)
+}
+
+/// Run jobs as they fall due; spawned once from `setup`. A job that never
+/// ran is due right away.
+pub async fn run(app: AppHandle) {
+    loop {
+        tokio::time::sleep(SCHEDULER_TICK).await;
+        let config: BackupJobsConfig = read_config_section(CONFIG_KEY);
+        for job in &config.jobs {
+            if app.state::<crate::power::PowerState>().is_suspended() || !app.state::<crate::network::NetworkState>().is_online() {
+                break;
+            }
+            let last_run = match db::with(&app, |conn| read_run(conn, &job.id)) {
+                Ok(run) => run.map(|r| r.started_at),
+                Err(e) => {
+                    log::warn!("Backup {}: last run unknown: {}", job.id, e);
+                    continue;
+                }
+            };
+            if next_run(job, last_run).is_none_or(|due| due > crate::sidecar::unix_now()) {
+                continue;
+            }
+            match run_job(&app, job).await {
+                Ok(_) => {}
+                Err(CommandError::TravelModeActive | CommandError::ReadOnlyShare | CommandError::ServerNotRunning) => break,
+                Err(e) => log::warn!("Scheduled backup {} failed: {}", job.id, e),
+            }
+        }
+    }
+}
+
+fn find_job(job_id: &str) -> Result<BackupJob, CommandError> {
+    let config: BackupJobsConfig = read_config_section(CONFIG_KEY);
+    config.jobs.into_iter().find(|j| j.id == job_id).ok_or_else(|| CommandError::BackupJobNotFound(job_id.to_string()))
+}
+
+#[tauri::command]
+pub async fn list_backup_jobs(app: AppHandle) -> Result<Vec<BackupJobStatus>, CommandError> {
+    let config: BackupJobsConfig = read_config_section(CONFIG_KEY);
+    config
+        .jobs
+        .into_iter()
+        .map(|job| {
+            let last = db::with(&app, |conn| read_run(conn, &job.id))?;
+            let last_run = last.as_ref().map(|r| r.started_at);
+            Ok(BackupJobStatus {
+                next_run: next_run(&job, last_run),
+                last_run,
+                last_error: last.as_ref().and_then(|r| r.error.clone()),
+                last_summary: last.and_then(|r| r.summary),
+                job,
+            })
+        })
+        .collect()
+}
+
+/// Back up `source_path` into snapshots under `remote_path` on `schedule`,
+/// keeping `retention` snapshots (default 7). The first scheduled run
+/// happens within a minute.
+#[tauri::command]
+pub async fn add_backup_job(
+    source_path: String,
+    remote_path: String,
+    schedule: String,
+    retention: Option<usize>,
+) -> Result<BackupJob, CommandError> {
+    let source = PathBuf::from(&source_path);
+    if !source.is_absolute() || !source.is_dir() {
+        return Err(CommandError::InvalidBackupJob(format!("{} is not a local folder", source_path)));
+    }
+    let remote_path = normalize_path(&remote_path);
+    if remote_path == "/" {
+        return Err(CommandError::InvalidBackupJob("Back up into a folder, not the drive root".into()));
+    }
+    schedule_interval(&schedule)?;
+    let retention = retention.unwrap_or(7);
+    if retention == 0 {
+        return Err(CommandError::InvalidBackupJob("At least one snapshot has to be kept".into()));
+    }
+
+    let mut config: BackupJobsConfig = read_config_section(CONFIG_KEY);
+    let job = BackupJob {
+        id: format!("{:08x}", rand::random::<u32>()),
+        source_path: source.display().to_string(),
+        remote_path,
+        schedule: schedule.trim().to_lowercase(),
+        retention,
+    };
+    config.jobs.push(job.clone());
+    write_config_section(CONFIG_KEY, &config)?;
+    log::info!("Added backup job {}: {} -> {} ({})", job.id, job.source_path, job.remote_path, job.schedule);
+    Ok(job)
+}
+
+/// Stop running a job. Its snapshots stay on the drive.
+#[tauri::command]
+pub async fn remove_backup_job(app: AppHandle, state: State<'_, BackupJobsState>, job_id: String) -> Result<(), CommandError> {
+    let _guard = state.lock.lock().await;
+    let mut config: BackupJobsConfig = read_config_section(CONFIG_KEY);
+    let before = config.jobs.len();
+    config.jobs.retain(|j| j.id != job_id);
+    if config.jobs.len() == before {
+        return Err(CommandError::BackupJobNotFound(job_id));
+    }
+    write_config_section(CONFIG_KEY, &config)?;
+    db::with(&app, |conn| conn.execute("DELETE FROM backup_job_runs WHERE job_id = ?1", [&job_id]).map(|_| ()))?;
+    log::info!("Removed backup job {}", job_id);
+    Ok(())
+}
+
+/// Run a job now, waiting for a backup already running to finish first.
+#[tauri::command]
+pub async fn run_backup_now(app: AppHandle, id: String) -> Result<BackupSummary, CommandError> {
+    let job = find_job(&id)?;
+    run_job(&app, &job).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(schedule: &str) -> BackupJob {
+        BackupJob { id: "j".into(), source_path: "/src".into(), remote_path: "/Backups".into(), schedule: schedule.into(), retention: 2 }
+    }
+
+    #[test]
+    fn test_snapshot_names_and_retention() {
+        // 2024-03-05 12:00:09 UTC
+        let name = snapshot_name(1_709_640_009);
+        assert_eq!(name, "2024-03-05_120009");
+        assert!(is_snapshot_name(&name));
+        assert!(!is_snapshot_name("Documents"));
+        assert!(!is_snapshot_name("2024-03-05 120009"));
+
+        let snapshots = vec!["2024-03-07_000000".to_string(), "2024-03-05_000000".into(), "2024-03-06_000000".into()];
+        assert_eq!(expired(snapshots.clone(), 2), vec!["2024-03-05_000000"]);
+        assert!(expired(snapshots, 5).is_empty());
+    }
+
+    #[test]
+    fn test_schedule_and_next_run() {
+        assert_eq!(next_run(&job("daily"), None), Some(0));
+        assert_eq!(next_run(&job("every 6h"), Some(1_000)), Some(1_000 + 6 * 3600));
+        assert_eq!(next_run(&job("never"), Some(1_000)), None);
+        assert!(schedule_interval("every 30m").is_err());
+        assert!(schedule_interval("0 3 * * *").is_err());
+    }
+
+    #[test]
+    fn test_failed_run_keeps_last_summary() {
+        let conn = db::open_in_memory();
+        let summary = BackupSummary { job_id: "j".into(), files: 3, ..Default::default() };
+        write_run(&conn, "j", &LastRun { started_at: 10, summary: Some(summary), error: None }).unwrap();
+        write_run(&conn, "j", &LastRun { started_at: 20, summary: None, error: Some("offline".into()) }).unwrap();
+        let run = read_run(&conn, "j").unwrap().unwrap();
+        assert_eq!(run.started_at, 20);
+        assert_eq!(run.error.as_deref(), Some("offline"));
+        assert_eq!(run.summary.unwrap().files, 3);
+    }
+}
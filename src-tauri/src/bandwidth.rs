@@ -0,0 +1,208 @@
+use bytes::Bytes;
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Bandwidth limits
+// ============================================================================
+//
+// Limits are enforced by the gateway with one token bucket per direction,
+// shared by all connections. Changing a limit takes effect immediately for
+// transfers in flight and is persisted for the next launch.
+
+const CONFIG_KEY: &str = "bandwidth";
+
+/// Limits in kilobits per second; `None` (or 0) means unlimited.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthLimits {
+    pub upload_kbps: Option<u32>,
+    pub download_kbps: Option<u32>,
+}
+
+fn kbps_to_bytes_per_sec(kbps: Option<u32>) -> u64 {
+    kbps.map(|k| u64::from(k) * 1000 / 8).unwrap_or(0)
+}
+
+/// Token bucket limiter. Tokens are bytes; the bucket holds at most one
+/// second worth of traffic so idle periods don't allow unbounded bursts.
+#[derive(Default)]
+pub struct RateLimiter {
+    bytes_per_sec: AtomicU64,
+    bucket: Mutex<Option<(f64, Instant)>>,
+}
+
+impl RateLimiter {
+    pub fn set_rate(&self, bytes_per_sec: u64) {
+        self.bytes_per_sec.store(bytes_per_sec, Ordering::Relaxed);
+        *self.bucket.lock().unwrap() = None;
+    }
+
+    /// Take `n` bytes from the bucket and return how long the caller has to
+    /// wait before sending them.
+    pub fn reserve(&self, n: usize) -> Duration {
+        self.reserve_at(n, Instant::now())
+    }
+
+    fn reserve_at(&self, n: usize, now: Instant) -> Duration {
+        let rate = self.bytes_per_sec.load(Ordering::Relaxed);
+        if rate == 0 {
+            return Duration::ZERO;
+        }
+        let rate = rate as f64;
+        let mut bucket = self.bucket.lock().unwrap();
+        let (tokens, last) = bucket.unwrap_or((rate, now));
+        let refilled = (tokens + now.saturating_duration_since(last).as_secs_f64() * rate).min(rate);
+        let remaining = refilled - n as f64;
+        *bucket = Some((remaining, now));
+        if remaining >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-remaining / rate)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BandwidthState {
+    pub upload: Arc<RateLimiter>,
+    pub download: Arc<RateLimiter>,
+}
+
+impl BandwidthState {
+    /// Build the limiters from the limits persisted in `config.json`.
+    pub fn from_config() -> Self {
+        let state = Self::default();
+        state.apply(&read_config_section(CONFIG_KEY));
+        state
+    }
+
+    /// Pick up the persisted limits after config.json was replaced.
+    pub fn reload_settings(&self) {
+        self.apply(&read_config_section(CONFIG_KEY));
+    }
+
+    /// Whether either direction is limited.
+    pub fn is_limited(&self) -> bool {
+        self.upload.bytes_per_sec.load(Ordering::Relaxed) > 0 || self.download.bytes_per_sec.load(Ordering::Relaxed) > 0
+    }
+
+    fn apply(&self, limits: &BandwidthLimits) {
+        self.upload.set_rate(kbps_to_bytes_per_sec(limits.upload_kbps));
+        self.download.set_rate(kbps_to_bytes_per_sec(limits.download_kbps));
+    }
+}
+
+/// Byte stream wrapper that delays chunks according to a [`RateLimiter`].
+pub struct ThrottledStream {
+    inner: Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send>>,
+    limiter: Arc<RateLimiter>,
+    delay: Option<Pin<Box<tokio::time::Sleep>>>,
+    held: Option<Bytes>,
+}
+
+impl ThrottledStream {
+    pub fn new<S>(inner: S, limiter: Arc<RateLimiter>) -> Self
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        Self { inner: Box::pin(inner), limiter, delay: None, held: None }
+    }
+}
+
+impl Stream for ThrottledStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(delay) = this.delay.as_mut() {
+                ready!(delay.as_mut().poll(cx));
+                this.delay = None;
+                return Poll::Ready(this.held.take().map(Ok));
+            }
+            match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    let wait = this.limiter.reserve(chunk.len());
+                    if wait.is_zero() {
+                        return Poll::Ready(Some(Ok(chunk)));
+                    }
+                    this.held = Some(chunk);
+                    this.delay = Some(Box::pin(tokio::time::sleep(wait)));
+                }
+                other => return Poll::Ready(other),
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_bandwidth_limit() -> Result<BandwidthLimits, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+#[tauri::command]
+pub async fn set_bandwidth_limit(
+    state: State<'_, BandwidthState>,
+    upload_kbps: Option<u32>,
+    download_kbps: Option<u32>,
+) -> Result<BandwidthLimits, CommandError> {
+    let limits = BandwidthLimits {
+        upload_kbps: upload_kbps.filter(|k| *k > 0),
+        download_kbps: download_kbps.filter(|k| *k > 0),
+    };
+    write_config_section(CONFIG_KEY, &limits)?;
+    state.apply(&limits);
+    Ok(limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kbps_conversion() {
+        assert_eq!(kbps_to_bytes_per_sec(Some(8)), 1000);
+        assert_eq!(kbps_to_bytes_per_sec(None), 0);
+    }
+
+    #[test]
+    fn test_unlimited_limiter_never_waits() {
+        let limiter = RateLimiter::default();
+        assert_eq!(limiter.reserve(10_000_000), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_limiter_allows_one_second_burst_then_waits() {
+        let limiter = RateLimiter::default();
+        limiter.set_rate(1000);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve_at(1000, now), Duration::ZERO);
+        assert_eq!(limiter.reserve_at(500, now), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_limiter_refills_over_time() {
+        let limiter = RateLimiter::default();
+        limiter.set_rate(1000);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve_at(1000, now), Duration::ZERO);
+        assert_eq!(limiter.reserve_at(1000, now + Duration::from_secs(1)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_bandwidth_limits_serialize_camel_case() {
+        let json = serde_json::to_value(BandwidthLimits { upload_kbps: Some(1), download_kbps: None }).unwrap();
+        assert_eq!(json["uploadKbps"], 1);
+        assert!(json["downloadKbps"].is_null());
+    }
+}
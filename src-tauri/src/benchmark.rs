@@ -0,0 +1,246 @@
+use bytes::Bytes;
+use rand::RngCore;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::cache::MetadataCache;
+use crate::dav::DavClient;
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{CommandError, SidecarState};
+
+// ============================================================================
+// Benchmark
+// ============================================================================
+//
+// `run_benchmark` writes a file of random data to the drive root and reads
+// it back, once over the bridge's WebDAV endpoint and once through the GVFS
+// mount, then deletes it. Comparing the two tells GVFS overhead apart from
+// the bridge (its cache and Proton's API): a slow WebDAV result points at
+// the bridge or the network, a slow mount with a fast WebDAV result at GVFS.
+// Each round trip also records the latency of a metadata request.
+
+const MAX_SIZE_MB: u32 = 256;
+
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BenchmarkTarget {
+    Webdav,
+    Mount,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BenchmarkStatus {
+    Completed,
+    Failed,
+    /// Not measured, e.g. the drive isn't mounted
+    Skipped,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Throughput {
+    pub bytes: u64,
+    pub elapsed_ms: u64,
+    pub mib_per_second: f64,
+}
+
+impl Throughput {
+    fn new(bytes: u64, elapsed: Duration) -> Self {
+        let seconds = elapsed.as_secs_f64().max(0.001);
+        let mib_per_second = (bytes as f64 / MIB as f64 / seconds * 100.0).round() / 100.0;
+        Self { bytes, elapsed_ms: elapsed.as_millis() as u64, mib_per_second }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetResult {
+    pub target: BenchmarkTarget,
+    pub status: BenchmarkStatus,
+    /// Time to stat the drive root
+    pub latency_ms: Option<u64>,
+    pub upload: Option<Throughput>,
+    pub download: Option<Throughput>,
+    /// Why the target failed or was skipped
+    pub detail: Option<String>,
+}
+
+impl TargetResult {
+    fn skipped(target: BenchmarkTarget, detail: &str) -> Self {
+        Self { target, status: BenchmarkStatus::Skipped, latency_ms: None, upload: None, download: None, detail: Some(detail.to_string()) }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub size_bytes: u64,
+    pub results: Vec<TargetResult>,
+}
+
+fn validate_size(size_mb: u32) -> Result<u64, CommandError> {
+    if size_mb == 0 || size_mb > MAX_SIZE_MB {
+        return Err(CommandError::InvalidBenchmarkSize(format!("{} MB is not between 1 and {} MB", size_mb, MAX_SIZE_MB)));
+    }
+    Ok(size_mb as u64 * MIB)
+}
+
+/// Name of the temporary file, unique per run and target.
+fn file_name(run: u32, target: BenchmarkTarget) -> String {
+    let target = match target {
+        BenchmarkTarget::Webdav => "webdav",
+        BenchmarkTarget::Mount => "mount",
+    };
+    format!(".pdwb-benchmark-{:08x}-{}.bin", run, target)
+}
+
+/// Collects the measurements of one target as it goes.
+struct Run {
+    target: BenchmarkTarget,
+    latency_ms: Option<u64>,
+    upload: Option<Throughput>,
+    download: Option<Throughput>,
+}
+
+impl Run {
+    fn new(target: BenchmarkTarget) -> Self {
+        Self { target, latency_ms: None, upload: None, download: None }
+    }
+
+    fn finish(self, outcome: Result<(), String>) -> TargetResult {
+        let (status, detail) = match outcome {
+            Ok(()) => (BenchmarkStatus::Completed, None),
+            Err(e) => {
+                log::warn!("Benchmark over {:?} failed: {}", self.target, e);
+                (BenchmarkStatus::Failed, Some(e))
+            }
+        };
+        TargetResult { target: self.target, status, latency_ms: self.latency_ms, upload: self.upload, download: self.download, detail }
+    }
+}
+
+fn check_download(expected: &[u8], actual: &[u8]) -> Result<(), String> {
+    if expected != actual {
+        return Err(format!("Read back {} bytes that differ from the {} written", actual.len(), expected.len()));
+    }
+    Ok(())
+}
+
+async fn measure_webdav(client: &DavClient, run: &mut Run, path: &str, data: &Bytes) -> Result<(), String> {
+    let started = Instant::now();
+    client.stat("/").await.map_err(|e| e.to_string())?;
+    run.latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    let body = data.clone();
+    let started = Instant::now();
+    client
+        .put(path, futures_util::stream::once(async move { Ok(body) }), Some(data.len() as u64))
+        .await
+        .map_err(|e| e.to_string())?;
+    run.upload = Some(Throughput::new(data.len() as u64, started.elapsed()));
+
+    let started = Instant::now();
+    let downloaded = client.get(path).await.map_err(|e| e.to_string())?.bytes().await.map_err(|e| e.to_string())?;
+    run.download = Some(Throughput::new(downloaded.len() as u64, started.elapsed()));
+    check_download(data, &downloaded)
+}
+
+async fn bench_webdav(app: &AppHandle, run_id: u32, data: &Bytes) -> TargetResult {
+    let client = match DavClient::for_app(app) {
+        Ok(client) => client,
+        Err(e) => return TargetResult::skipped(BenchmarkTarget::Webdav, &e.to_string()),
+    };
+    let path = format!("/{}", file_name(run_id, BenchmarkTarget::Webdav));
+    let mut run = Run::new(BenchmarkTarget::Webdav);
+    let outcome = measure_webdav(&client, &mut run, &path, data).await;
+    if let Err(e) = client.delete(&path).await {
+        log::warn!("Failed to remove benchmark file {}: {}", path, e);
+    }
+    app.state::<MetadataCache>().invalidate("/");
+    run.finish(outcome)
+}
+
+async fn measure_mount(run: &mut Run, root: &std::path::Path, file: &std::path::Path, data: &Bytes) -> Result<(), String> {
+    let started = Instant::now();
+    tokio::fs::metadata(root).await.map_err(|e| e.to_string())?;
+    run.latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    let started = Instant::now();
+    tokio::fs::write(file, data).await.map_err(|e| e.to_string())?;
+    run.upload = Some(Throughput::new(data.len() as u64, started.elapsed()));
+
+    let started = Instant::now();
+    let downloaded = tokio::fs::read(file).await.map_err(|e| e.to_string())?;
+    run.download = Some(Throughput::new(downloaded.len() as u64, started.elapsed()));
+    check_download(data, &downloaded)
+}
+
+async fn bench_mount(app: &AppHandle, run_id: u32, data: &Bytes) -> TargetResult {
+    let status = match crate::sidecar::get_status(app.clone(), app.state::<SidecarState>()).await {
+        Ok(status) => status,
+        Err(e) => return TargetResult::skipped(BenchmarkTarget::Mount, &e.to_string()),
+    };
+    let root = match crate::mounts::mount_status(&crate::sidecar::local_dav_uri(&status)) {
+        (true, Some(path)) => PathBuf::from(path),
+        (true, None) => return TargetResult::skipped(BenchmarkTarget::Mount, "The mount has no local path"),
+        (false, _) => return TargetResult::skipped(BenchmarkTarget::Mount, "The drive is not mounted"),
+    };
+    let file = root.join(file_name(run_id, BenchmarkTarget::Mount));
+    let mut run = Run::new(BenchmarkTarget::Mount);
+    let outcome = measure_mount(&mut run, &root, &file, data).await;
+    if let Err(e) = tokio::fs::remove_file(&file).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove benchmark file {}: {}", file.display(), e);
+        }
+    }
+    app.state::<MetadataCache>().invalidate("/");
+    run.finish(outcome)
+}
+
+/// Measure transfers of a `size_mb` file over WebDAV and through the mount.
+#[tauri::command]
+pub async fn run_benchmark(app: AppHandle, size_mb: u32) -> Result<BenchmarkReport, CommandError> {
+    let size = validate_size(size_mb)?;
+    app.state::<ReadOnlyState>().check_writable()?;
+    // Random data, so no layer can compress or deduplicate it
+    let mut data = vec![0u8; size as usize];
+    rand::thread_rng().fill_bytes(&mut data);
+    let data = Bytes::from(data);
+    let run_id = rand::random::<u32>();
+
+    log::info!("Running a {} MB benchmark", size_mb);
+    let results = vec![bench_webdav(&app, run_id, &data).await, bench_mount(&app, run_id, &data).await];
+    Ok(BenchmarkReport { size_bytes: size, results })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_size() {
+        assert_eq!(validate_size(1).unwrap(), MIB);
+        assert_eq!(validate_size(MAX_SIZE_MB).unwrap(), MAX_SIZE_MB as u64 * MIB);
+        assert!(validate_size(0).is_err());
+        assert!(validate_size(MAX_SIZE_MB + 1).is_err());
+    }
+
+    #[test]
+    fn test_throughput() {
+        let throughput = Throughput::new(8 * MIB, Duration::from_secs(2));
+        assert_eq!(throughput, Throughput { bytes: 8 * MIB, elapsed_ms: 2000, mib_per_second: 4.0 });
+        // A zero duration doesn't divide by zero
+        assert!(Throughput::new(MIB, Duration::ZERO).mib_per_second.is_finite());
+    }
+
+    #[test]
+    fn test_file_names_differ_per_target() {
+        assert_eq!(file_name(0xabc, BenchmarkTarget::Webdav), ".pdwb-benchmark-00000abc-webdav.bin");
+        assert_ne!(file_name(1, BenchmarkTarget::Webdav), file_name(1, BenchmarkTarget::Mount));
+    }
+}
@@ -0,0 +1,154 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+// ============================================================================
+// Mock bridge
+// ============================================================================
+//
+// A stand-in for the `proton-drive-webdav-bridge` CLI with canned output, so
+// the app's handling of the bridge can be tested without Proton credentials
+// or Bun. It answers the subcommands the app runs (`--version`, `auth login`,
+// `start`, `stop`, `rpc`) the way the real CLI does on success. Environment
+// variables select other scenarios:
+//
+// - `MOCK_BRIDGE_LOGIN_FAIL`: `auth login` fails as with a wrong password
+// - `MOCK_BRIDGE_LOGGED_OUT`: `status` reports no session
+// - `MOCK_BRIDGE_SERVER_PID`: `status` reports a server running with that PID
+// - `MOCK_BRIDGE_CRASH_AFTER_MS`: `start` dies that long after starting
+//
+// Only built with the `mock-sidecar` feature.
+
+const VERSION: &str = "0.0.0-mock";
+
+const USERNAME: &str = "user@example.com";
+
+fn env_flag(name: &str) -> bool {
+    std::env::var_os(name).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).map(String::as_str)
+}
+
+fn status() -> Value {
+    let pid = std::env::var("MOCK_BRIDGE_SERVER_PID").ok().and_then(|p| p.parse::<u32>().ok());
+    let logged_in = !env_flag("MOCK_BRIDGE_LOGGED_OUT");
+    json!({
+        "schemaVersion": 2,
+        "server": {
+            "running": pid.is_some(),
+            "pid": pid,
+            "url": pid.map(|_| "http://127.0.0.1:8080"),
+        },
+        "auth": {
+            "loggedIn": logged_in,
+            "username": logged_in.then_some(USERNAME),
+        },
+        "config": {
+            "webdav": { "host": "127.0.0.1", "port": 8080, "https": false, "requireAuth": false },
+            "remotePath": "/",
+        },
+        "logFile": "/tmp/mock-bridge.log",
+    })
+}
+
+fn config() -> Value {
+    json!({
+        "webdav": { "host": "127.0.0.1", "port": 8080, "https": false, "requireAuth": false },
+        "remotePath": "/",
+        "cache": { "enabled": true, "ttlSeconds": 60, "maxSizeMB": 100 },
+        "debug": false,
+        "autoStart": false,
+    })
+}
+
+/// Serve JSON-RPC 2.0 on stdin/stdout until stdin closes.
+fn rpc() -> i32 {
+    let mut stdout = std::io::stdout().lock();
+    // The real bridge may log to stdout before it answers
+    let _ = writeln!(stdout, "info: RPC server ready");
+    let _ = stdout.flush();
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => {
+                let id = request.get("id").cloned().unwrap_or(Value::Null);
+                let result = match request.get("method").and_then(Value::as_str) {
+                    Some("status") => Ok(status()),
+                    Some("auth.status") => Ok(status()["auth"].clone()),
+                    Some("auth.logout") => Ok(json!({ "loggedOut": !env_flag("MOCK_BRIDGE_LOGGED_OUT") })),
+                    Some("config.get") => Ok(config()),
+                    Some("config.set" | "cache.purge") => Ok(Value::Null),
+                    Some(method) => Err((-32601, format!("Method not found: {}", method))),
+                    None => Err((-32600, "Invalid request".to_string())),
+                };
+                match result {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+                }
+            }
+            Err(e) => json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32700, "message": format!("Parse error: {}", e) } }),
+        };
+        let _ = writeln!(stdout, "{}", response);
+        let _ = stdout.flush();
+    }
+    0
+}
+
+fn login(args: &[String]) -> i32 {
+    let Some(username) = option(args, "--username") else {
+        eprintln!("Error: --username is required");
+        return 1;
+    };
+    if env_flag("MOCK_BRIDGE_LOGIN_FAIL") {
+        eprintln!("Error: Authentication failed: Incorrect login credentials");
+        return 1;
+    }
+    println!("User {} authenticated successfully", username);
+    0
+}
+
+/// Serve until killed, like `start --no-daemon`.
+fn start(args: &[String]) -> i32 {
+    let address = match option(args, "--socket") {
+        Some(socket) => socket.to_string(),
+        None => format!("{}:{}", option(args, "--host").unwrap_or("127.0.0.1"), option(args, "--port").unwrap_or("8080")),
+    };
+    println!("Starting WebDAV server on {}", address);
+    println!("WebDAV server started (pid {})", std::process::id());
+    let _ = std::io::stdout().flush();
+
+    let crash_after = std::env::var("MOCK_BRIDGE_CRASH_AFTER_MS").ok().and_then(|ms| ms.parse().ok()).map(Duration::from_millis);
+    if let Some(delay) = crash_after {
+        std::thread::sleep(delay);
+        eprintln!("FATAL: mock bridge crashed");
+        return 1;
+    }
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    let code = match words.as_slice() {
+        ["--version", ..] => {
+            println!("{}", VERSION);
+            0
+        }
+        ["auth", "login", ..] => login(&args),
+        ["start", ..] => start(&args),
+        ["stop", ..] => {
+            println!("WebDAV server stopped");
+            0
+        }
+        ["rpc", ..] => rpc(),
+        _ => {
+            eprintln!("error: unknown command '{}'", words.join(" "));
+            2
+        }
+    };
+    std::process::exit(code);
+}
@@ -0,0 +1,299 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use crate::cache::MetadataCache;
+use crate::dav::{normalize_path, DavClient, DavEntry};
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Remote browser listings
+// ============================================================================
+//
+// A folder with thousands of entries comes back from the bridge as one large
+// PROPFIND response that takes a while to arrive. The in-app browser lists
+// folders page by page instead: `list_remote_directory` starts a listing
+// that parses the response as it streams in (`DavClient::propfind_each`),
+// returns the first `limit` entries as soon as they are there, and hands out
+// a `cursor` for the next page. Every batch read is also announced as a
+// `browser:entries` event, so the browser can fill in the rest without
+// asking page by page.
+//
+// Entries come in the order the server sends them, except that a folder
+// that fits in the first page is sorted folders first and by name. Listings
+// are kept in memory until read to the end, cancelled with
+// `cancel_remote_listing` or left alone for `LISTING_TTL`; a cursor of a
+// listing gone by then fails with `LISTING_EXPIRED`. Complete listings are
+// recorded in the metadata cache for search and the prefetcher.
+
+const DEFAULT_PAGE_SIZE: usize = 500;
+const MAX_PAGE_SIZE: usize = 5_000;
+
+/// How long a listing nobody reads from is kept.
+const LISTING_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryPage {
+    pub listing_id: u64,
+    pub path: String,
+    pub entries: Vec<DavEntry>,
+    /// Pass back to get the next page; `None` on the last page
+    pub cursor: Option<String>,
+}
+
+/// A batch of a listing as read, sent as `browser:entries`.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowserEntries {
+    pub listing_id: u64,
+    pub path: String,
+    /// Position of the first entry in the listing
+    pub offset: usize,
+    pub entries: Vec<DavEntry>,
+    /// Whether this is the last batch
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+struct Listing {
+    path: String,
+    /// The folder's own entry, once seen
+    folder: Option<DavEntry>,
+    entries: Vec<DavEntry>,
+    done: bool,
+    error: Option<CommandError>,
+    touched: Instant,
+}
+
+/// Listings in progress or waiting to be read.
+#[derive(Default)]
+pub struct BrowserState {
+    listings: Mutex<HashMap<u64, Listing>>,
+    next_id: AtomicU64,
+    /// Woken whenever a listing gets entries or ends
+    changed: Notify,
+}
+
+impl BrowserState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn start(&self, path: &str) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut listings = self.listings.lock().unwrap();
+        listings.retain(|_, l| l.touched.elapsed() < LISTING_TTL);
+        let listing = Listing { path: path.to_string(), folder: None, entries: Vec::new(), done: false, error: None, touched: Instant::now() };
+        listings.insert(id, listing);
+        id
+    }
+
+    /// Add a batch read from the server to listing `id` and return the event
+    /// announcing it, or `None` once the listing is gone and reading should
+    /// stop.
+    fn append(&self, id: u64, batch: Vec<DavEntry>) -> Option<Result<BrowserEntries, CommandError>> {
+        let mut listings = self.listings.lock().unwrap();
+        let listing = listings.get_mut(&id)?;
+        let offset = listing.entries.len();
+        for entry in batch {
+            if entry.path != listing.path {
+                listing.entries.push(entry);
+            } else if entry.is_dir {
+                listing.folder = Some(entry);
+            } else {
+                return Some(Err(CommandError::InvalidRemotePath(format!("{} is not a folder", listing.path))));
+            }
+        }
+        self.changed.notify_waiters();
+        Some(Ok(BrowserEntries {
+            listing_id: id,
+            path: listing.path.clone(),
+            offset,
+            entries: listing.entries[offset..].to_vec(),
+            done: false,
+            error: None,
+        }))
+    }
+
+    /// Mark listing `id` as read to the end, or failed, and return the
+    /// complete listing (the folder and its children) when it succeeded.
+    fn finish(&self, id: u64, result: Result<(), CommandError>) -> Option<(BrowserEntries, Option<Vec<DavEntry>>)> {
+        let mut listings = self.listings.lock().unwrap();
+        let listing = listings.get_mut(&id)?;
+        listing.done = true;
+        listing.error = result.err();
+        self.changed.notify_waiters();
+        let complete = listing.error.is_none().then(|| listing.folder.iter().chain(&listing.entries).cloned().collect());
+        let event = BrowserEntries {
+            listing_id: id,
+            path: listing.path.clone(),
+            offset: listing.entries.len(),
+            entries: Vec::new(),
+            done: true,
+            error: listing.error.as_ref().map(|e| e.to_string()),
+        };
+        Some((event, complete))
+    }
+
+    /// The page of listing `id` starting at `offset`, once `limit` entries
+    /// are there or the listing has ended.
+    async fn page(&self, id: u64, offset: usize, limit: usize) -> Result<DirectoryPage, CommandError> {
+        loop {
+            let changed = self.changed.notified();
+            {
+                let mut listings = self.listings.lock().unwrap();
+                let listing = listings.get_mut(&id).ok_or_else(|| CommandError::ListingExpired(cursor(id, offset)))?;
+                listing.touched = Instant::now();
+                if let Some(e) = &listing.error {
+                    let e = e.clone();
+                    listings.remove(&id);
+                    return Err(e);
+                }
+                let end = (offset + limit).min(listing.entries.len());
+                if end == offset + limit || listing.done {
+                    let last = listing.done && end == listing.entries.len();
+                    let mut entries = listing.entries.get(offset..end).unwrap_or_default().to_vec();
+                    if last && offset == 0 {
+                        entries.sort_by_cached_key(|e| (!e.is_dir, e.name.to_lowercase()));
+                    }
+                    let page = DirectoryPage { listing_id: id, path: listing.path.clone(), entries, cursor: (!last).then(|| cursor(id, end)) };
+                    if last {
+                        listings.remove(&id);
+                    }
+                    return Ok(page);
+                }
+            }
+            changed.await;
+        }
+    }
+
+    fn cancel(&self, id: u64) -> bool {
+        let removed = self.listings.lock().unwrap().remove(&id).is_some();
+        self.changed.notify_waiters();
+        removed
+    }
+}
+
+fn cursor(id: u64, offset: usize) -> String {
+    format!("{}:{}", id, offset)
+}
+
+fn parse_cursor(cursor: &str) -> Option<(u64, usize)> {
+    let (id, offset) = cursor.split_once(':')?;
+    Some((id.parse().ok()?, offset.parse().ok()?))
+}
+
+/// Read listing `id` of `path` from the bridge, announcing each batch.
+async fn fetch(app: AppHandle, client: DavClient, id: u64, path: String) {
+    let state = app.state::<BrowserState>();
+    let mut failed = None;
+    let result = client
+        .propfind_each(&path, |batch| match state.append(id, batch) {
+            Some(Ok(event)) => {
+                let _ = app.emit("browser:entries", event);
+                true
+            }
+            Some(Err(e)) => {
+                failed = Some(e);
+                false
+            }
+            None => false,
+        })
+        .await;
+    let result = match failed {
+        Some(e) => Err(e),
+        None => result,
+    };
+    if let Err(e) = &result {
+        log::debug!("Listing {} failed: {}", path, e);
+    }
+    let Some((event, complete)) = state.finish(id, result) else {
+        return;
+    };
+    if let Some(entries) = complete {
+        app.state::<MetadataCache>().remember_listing(&path, &entries);
+    }
+    let _ = app.emit("browser:entries", event);
+}
+
+/// Files and folders directly inside `path` (default `/`), `limit` (default
+/// 500) at a time, for the in-app file browser. Without a `cursor` this
+/// starts a new listing and returns its first page; with the `cursor` of a
+/// page it returns the next one, and `path` is not used.
+#[tauri::command]
+pub async fn list_remote_directory(
+    app: AppHandle,
+    state: State<'_, BrowserState>,
+    path: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<DirectoryPage, CommandError> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    if let Some(cursor) = cursor {
+        let (id, offset) = parse_cursor(&cursor).ok_or(CommandError::ListingExpired(cursor))?;
+        return state.page(id, offset, limit).await;
+    }
+    let path = normalize_path(path.as_deref().unwrap_or("/"));
+    let client = DavClient::for_app(&app)?;
+    let id = state.start(&path);
+    tauri::async_runtime::spawn(fetch(app.clone(), client, id, path));
+    state.page(id, 0, limit).await
+}
+
+/// Stop reading listing `listing_id` and drop it. Returns `false` if it was
+/// already gone.
+#[tauri::command]
+pub async fn cancel_remote_listing(state: State<'_, BrowserState>, listing_id: u64) -> Result<bool, CommandError> {
+    Ok(state.cancel(listing_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri::async_runtime::block_on;
+
+    fn entry(path: &str, is_dir: bool) -> DavEntry {
+        let name = path.rsplit('/').next().unwrap_or_default().to_string();
+        DavEntry { path: path.to_string(), name, is_dir, size: None, modified: None, etag: None, content_type: None }
+    }
+
+    #[test]
+    fn test_pages_follow_the_listing() {
+        let state = BrowserState::new();
+        let id = state.start("/Docs");
+        let batch = vec![entry("/Docs", true), entry("/Docs/b.txt", false), entry("/Docs/a", true), entry("/Docs/c.txt", false)];
+        let event = state.append(id, batch).unwrap().unwrap();
+        assert_eq!((event.offset, event.entries.len()), (0, 3));
+
+        let first = block_on(state.page(id, 0, 2)).unwrap();
+        assert_eq!(first.entries.len(), 2);
+        let cursor = first.cursor.unwrap();
+        let (_, complete) = state.finish(id, Ok(())).unwrap();
+        assert_eq!(complete.unwrap().len(), 4);
+        let (page_id, offset) = parse_cursor(&cursor).unwrap();
+        let last = block_on(state.page(page_id, offset, 2)).unwrap();
+        assert_eq!(last.entries[0].name, "c.txt");
+        assert_eq!(last.cursor, None);
+        // Read to the end, so gone
+        assert!(matches!(block_on(state.page(id, 0, 2)), Err(CommandError::ListingExpired(_))));
+    }
+
+    #[test]
+    fn test_small_folder_is_sorted_and_files_rejected() {
+        let state = BrowserState::new();
+        let id = state.start("/Docs");
+        state.append(id, vec![entry("/Docs/b.txt", false), entry("/Docs/a.txt", false), entry("/Docs/z", true)]).unwrap().unwrap();
+        state.finish(id, Ok(())).unwrap();
+        let page = block_on(state.page(id, 0, 10)).unwrap();
+        let names: Vec<&str> = page.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["z", "a.txt", "b.txt"]);
+
+        let id = state.start("/a.txt");
+        assert!(state.append(id, vec![entry("/a.txt", false)]).unwrap().is_err());
+    }
+}
@@ -0,0 +1,651 @@
+use bytes::Bytes;
+use hyper::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::dav::{normalize_path, parent_path, DavEntry};
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Metadata cache
+// ============================================================================
+//
+// The sidecar only keeps a short-lived in-memory cache that cannot be
+// inspected from outside, so the gateway caches PROPFIND responses itself,
+// governed by the `cache` section of config.json (`enabled`, `ttlSeconds`,
+// `maxSizeMB`). Entries are dropped when a request through the gateway
+// modifies the resource, its parent or anything below it.
+//
+// Folder listings are also kept, parsed, in `metadata-cache.json` under the
+// app data dir, so they survive restarts of the app and the sidecar. The
+// prefetcher, search and the sync engine record every listing they fetch;
+// search answers from a recorded listing up to a day old when the
+// in-memory entry has expired. The sync engine always lists afresh, since a
+// stale listing could make it overwrite a newer remote file. Recorded
+// listings are invalidated with the in-memory entries.
+//
+// A scheduler task evicts expired entries every minute, writes recorded
+// listings to disk when they changed and, when `purgeSchedule` is set,
+// purges both this cache and the sidecar's at that interval.
+
+/// PROPFIND request bodies larger than this are forwarded uncached.
+pub const MAX_REQUEST_BODY: u64 = 64 * 1024;
+
+/// Responses larger than this are never cached.
+pub const MAX_CACHED_RESPONSE: u64 = 8 * 1024 * 1024;
+
+const DEFAULT_LIST_LIMIT: usize = 200;
+
+/// How often the scheduler evicts expired entries and checks for a purge.
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// Shortest accepted `purgeSchedule` interval.
+const MIN_PURGE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// File under the app data dir holding recorded listings.
+const PERSISTED_FILE: &str = "metadata-cache.json";
+
+/// Recorded listings older than this are neither served nor loaded.
+const PERSISTED_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Recorded listings kept; the oldest are dropped first.
+const MAX_PERSISTED_LISTINGS: usize = 20_000;
+
+/// The `cache` section shared with the sidecar.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CacheSettings {
+    pub enabled: bool,
+    pub ttl_seconds: u64,
+    #[serde(rename = "maxSizeMB")]
+    pub max_size_mb: u64,
+    /// `hourly`, `daily`, `weekly` or `every <n>m|h|d`; unset or `never`
+    /// turns scheduled purges off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purge_schedule: Option<String>,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self { enabled: true, ttl_seconds: 60, max_size_mb: 100, purge_schedule: None }
+    }
+}
+
+/// The user-facing part of the `cache` section.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CachePolicy {
+    pub ttl_seconds: u64,
+    #[serde(rename = "maxSizeMB")]
+    pub max_size_mb: u64,
+    pub purge_schedule: Option<String>,
+}
+
+impl From<&CacheSettings> for CachePolicy {
+    fn from(settings: &CacheSettings) -> Self {
+        Self {
+            ttl_seconds: settings.ttl_seconds,
+            max_size_mb: settings.max_size_mb,
+            purge_schedule: settings.purge_schedule.clone(),
+        }
+    }
+}
+
+/// Interval of a schedule such as `daily` or `every 6h`; `None` for
+/// `never`. Also used for scheduled backups.
+pub(crate) fn parse_schedule(schedule: &str) -> Result<Option<Duration>, String> {
+    let invalid = || format!("Unsupported schedule: {:?}", schedule);
+    let schedule = schedule.trim().to_lowercase();
+    let interval = match schedule.as_str() {
+        "" | "never" => return Ok(None),
+        "hourly" | "@hourly" => Duration::from_secs(60 * 60),
+        "daily" | "@daily" => Duration::from_secs(24 * 60 * 60),
+        "weekly" | "@weekly" => Duration::from_secs(7 * 24 * 60 * 60),
+        other => {
+            let spec = other
+                .strip_prefix("@every")
+                .or_else(|| other.strip_prefix("every"))
+                .map(str::trim)
+                .ok_or_else(invalid)?;
+            let (count, unit) = if let Some(n) = spec.strip_suffix('m') {
+                (n, 60)
+            } else if let Some(n) = spec.strip_suffix('h') {
+                (n, 60 * 60)
+            } else if let Some(n) = spec.strip_suffix('d') {
+                (n, 24 * 60 * 60)
+            } else {
+                return Err(invalid());
+            };
+            let count: u64 = count.trim().parse().map_err(|_| invalid())?;
+            Duration::from_secs(count.saturating_mul(unit))
+        }
+    };
+    Ok(Some(interval))
+}
+
+/// Interval of a `purgeSchedule` value; `None` when purges are off.
+fn parse_purge_schedule(schedule: &str) -> Result<Option<Duration>, CommandError> {
+    let interval = parse_schedule(schedule).map_err(CommandError::InvalidCachePolicy)?;
+    if interval.is_some_and(|i| i < MIN_PURGE_INTERVAL) {
+        return Err(CommandError::InvalidCachePolicy("Purges cannot be scheduled more often than every 5 minutes".into()));
+    }
+    Ok(interval)
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+pub struct CacheKey {
+    path: String,
+    depth: u8,
+    body: [u8; 32],
+}
+
+#[derive(Clone, Debug)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+struct CacheEntry {
+    response: CachedResponse,
+    stored: Instant,
+    stored_at: u64,
+    hits: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PersistedListing {
+    /// Unix timestamp (seconds)
+    stored_at: u64,
+    /// The folder itself and its children
+    entries: Vec<DavEntry>,
+}
+
+/// Recorded listings by folder path, and where they are saved.
+#[derive(Default)]
+struct Persisted {
+    file: Option<PathBuf>,
+    listings: HashMap<String, PersistedListing>,
+    dirty: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    pub total_bytes: u64,
+    pub max_bytes: u64,
+    pub ttl_seconds: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// `hits / (hits + misses)`, or `None` before the first lookup
+    pub hit_ratio: Option<f64>,
+    /// Unix timestamp (seconds) of the oldest entry
+    pub oldest_entry: Option<u64>,
+    /// Folder listings recorded on disk
+    pub persisted_listings: usize,
+    /// Size of the file holding them
+    pub persisted_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntryInfo {
+    pub path: String,
+    pub depth: u8,
+    pub size_bytes: u64,
+    /// Unix timestamp (seconds)
+    pub stored_at: u64,
+    pub expires_in_seconds: u64,
+    pub hits: u64,
+}
+
+#[derive(Default)]
+pub struct MetadataCache {
+    settings: Mutex<CacheSettings>,
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+    persisted: Mutex<Persisted>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl MetadataCache {
+    pub fn from_config() -> Self {
+        Self { settings: Mutex::new(read_config_section("cache")), ..Default::default() }
+    }
+
+    /// Pick up changes to the `cache` section; called when the gateway starts.
+    pub fn reload_settings(&self) {
+        *self.settings.lock().unwrap() = read_config_section("cache");
+        self.evict_to_fit();
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.settings.lock().unwrap().ttl_seconds)
+    }
+
+    fn max_bytes(&self) -> u64 {
+        self.settings.lock().unwrap().max_size_mb.saturating_mul(1024 * 1024)
+    }
+
+    /// Key for a PROPFIND on `path` with the given `Depth` header and body, or
+    /// `None` if the request must not be cached.
+    pub fn key(&self, path: &str, headers: &HeaderMap, body: &[u8]) -> Option<CacheKey> {
+        let settings = self.settings.lock().unwrap();
+        if !settings.enabled || settings.ttl_seconds == 0 {
+            return None;
+        }
+        let depth = match headers.get("depth").map(|v| v.as_bytes()) {
+            Some(b"0") => 0,
+            Some(b"1") => 1,
+            // Depth: infinity (the default) is too expensive to keep around
+            _ => return None,
+        };
+        Some(CacheKey { path: normalize_path(path), depth, body: Sha256::digest(body).into() })
+    }
+
+    /// Whether caching is on at all, so callers can skip buffering bodies.
+    pub fn enabled(&self) -> bool {
+        let settings = self.settings.lock().unwrap();
+        settings.enabled && settings.ttl_seconds > 0
+    }
+
+    pub fn lookup(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let ttl = self.ttl();
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get_mut(key) {
+            Some(entry) if entry.stored.elapsed() < ttl => {
+                entry.hits += 1;
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.response.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn store(&self, key: CacheKey, response: CachedResponse) {
+        let size = response.body.len() as u64;
+        if size > MAX_CACHED_RESPONSE || size > self.max_bytes() {
+            return;
+        }
+        let entry = CacheEntry { response, stored: Instant::now(), stored_at: crate::sidecar::unix_now(), hits: 0 };
+        self.entries.lock().unwrap().insert(key, entry);
+        self.evict_to_fit();
+    }
+
+    /// Drop oldest entries until the cache fits in `maxSizeMB`.
+    fn evict_to_fit(&self) {
+        let max = self.max_bytes();
+        let mut entries = self.entries.lock().unwrap();
+        let mut total: u64 = entries.values().map(|e| e.response.body.len() as u64).sum();
+        while total > max {
+            let Some(oldest) = entries.iter().min_by_key(|(_, e)| e.stored).map(|(k, _)| k.clone()) else {
+                break;
+            };
+            if let Some(removed) = entries.remove(&oldest) {
+                total -= removed.response.body.len() as u64;
+            }
+        }
+    }
+
+    /// Forget everything that may describe `path`: the path itself, its
+    /// parent's listing and anything below it. Returns how many entries
+    /// were dropped.
+    pub fn invalidate(&self, path: &str) -> usize {
+        let path = normalize_path(path);
+        let parent = parent_path(&path).to_string();
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        let mut persisted = self.persisted.lock().unwrap();
+        let recorded = persisted.listings.len();
+        persisted.listings.retain(|dir, _| *dir != path && *dir != parent && !dir.starts_with(&prefix));
+        persisted.dirty |= persisted.listings.len() != recorded;
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|k, _| k.path != path && k.path != parent && !k.path.starts_with(&prefix));
+        before - entries.len()
+    }
+
+    /// Forget everything, recorded listings included.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+        let mut persisted = self.persisted.lock().unwrap();
+        persisted.dirty |= !persisted.listings.is_empty();
+        persisted.listings.clear();
+    }
+
+    /// Load the listings recorded in `file` and save them there from now on;
+    /// called once from `setup`.
+    pub fn load_persisted(&self, file: PathBuf) {
+        let listings: HashMap<String, PersistedListing> = match std::fs::read_to_string(&file) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("Ignoring unreadable {}: {}", file.display(), e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        let oldest = crate::sidecar::unix_now().saturating_sub(PERSISTED_MAX_AGE.as_secs());
+        let mut persisted = self.persisted.lock().unwrap();
+        persisted.listings = listings.into_iter().filter(|(_, l)| l.stored_at >= oldest).collect();
+        persisted.file = Some(file);
+        log::debug!("Loaded {} recorded folder listing(s)", persisted.listings.len());
+    }
+
+    /// Record the depth-1 listing of `dir` (the folder and its children).
+    pub fn remember_listing(&self, dir: &str, entries: &[DavEntry]) {
+        if !self.settings.lock().unwrap().enabled {
+            return;
+        }
+        let mut persisted = self.persisted.lock().unwrap();
+        let listing = PersistedListing { stored_at: crate::sidecar::unix_now(), entries: entries.to_vec() };
+        persisted.listings.insert(normalize_path(dir), listing);
+        if persisted.listings.len() > MAX_PERSISTED_LISTINGS {
+            if let Some(oldest) = persisted.listings.iter().min_by_key(|(_, l)| l.stored_at).map(|(k, _)| k.clone()) {
+                persisted.listings.remove(&oldest);
+            }
+        }
+        persisted.dirty = true;
+    }
+
+    /// The recorded listing of `dir`, if there is one young enough.
+    pub fn remembered_listing(&self, dir: &str) -> Option<Vec<DavEntry>> {
+        if !self.settings.lock().unwrap().enabled {
+            return None;
+        }
+        let oldest = crate::sidecar::unix_now().saturating_sub(PERSISTED_MAX_AGE.as_secs());
+        let persisted = self.persisted.lock().unwrap();
+        let listing = persisted.listings.get(&normalize_path(dir)).filter(|l| l.stored_at >= oldest)?;
+        Some(listing.entries.clone())
+    }
+
+    /// Write recorded listings to disk if they changed since the last write.
+    pub fn flush(&self) {
+        let (file, json) = {
+            let mut persisted = self.persisted.lock().unwrap();
+            let Some(file) = persisted.file.clone().filter(|_| persisted.dirty) else {
+                return;
+            };
+            persisted.dirty = false;
+            (file, serde_json::to_vec(&persisted.listings))
+        };
+        let result = match json {
+            Ok(json) => std::fs::write(&file, json).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to save recorded listings to {}: {}", file.display(), e);
+        }
+    }
+
+    fn persisted_stats(&self) -> (usize, u64) {
+        let persisted = self.persisted.lock().unwrap();
+        let bytes = persisted.file.as_ref().and_then(|f| std::fs::metadata(f).ok()).map_or(0, |m| m.len());
+        (persisted.listings.len(), bytes)
+    }
+
+    /// Drop entries past their TTL. Returns how many were dropped.
+    fn evict_expired(&self) -> usize {
+        let ttl = self.ttl();
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|_, e| e.stored.elapsed() < ttl);
+        before - entries.len()
+    }
+
+    fn purge_interval(&self) -> Option<Duration> {
+        let schedule = self.settings.lock().unwrap().purge_schedule.clone()?;
+        parse_purge_schedule(&schedule).ok().flatten()
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let (persisted_listings, persisted_bytes) = self.persisted_stats();
+        let settings = self.settings.lock().unwrap().clone();
+        let entries = self.entries.lock().unwrap();
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        CacheStats {
+            enabled: settings.enabled,
+            entries: entries.len(),
+            total_bytes: entries.values().map(|e| e.response.body.len() as u64).sum(),
+            max_bytes: settings.max_size_mb.saturating_mul(1024 * 1024),
+            ttl_seconds: settings.ttl_seconds,
+            hits,
+            misses,
+            hit_ratio: (hits + misses > 0).then(|| hits as f64 / (hits + misses) as f64),
+            oldest_entry: entries.values().map(|e| e.stored_at).min(),
+            persisted_listings,
+            persisted_bytes,
+        }
+    }
+
+    fn list(&self, prefix: &str, limit: usize) -> Vec<CacheEntryInfo> {
+        let ttl = self.ttl();
+        let prefix = normalize_path(prefix);
+        let entries = self.entries.lock().unwrap();
+        let mut list: Vec<CacheEntryInfo> = entries
+            .iter()
+            .filter(|(k, _)| prefix == "/" || k.path == prefix || k.path.starts_with(&format!("{}/", prefix)))
+            .map(|(k, e)| CacheEntryInfo {
+                path: k.path.clone(),
+                depth: k.depth,
+                size_bytes: e.response.body.len() as u64,
+                stored_at: e.stored_at,
+                expires_in_seconds: ttl.saturating_sub(e.stored.elapsed()).as_secs(),
+                hits: e.hits,
+            })
+            .collect();
+        list.sort_by(|a, b| a.path.cmp(&b.path).then(a.depth.cmp(&b.depth)));
+        list.truncate(limit);
+        list
+    }
+}
+
+/// Load recorded listings, then evict expired entries, save recorded
+/// listings and run scheduled purges; spawned once from `setup`. The first
+/// purge happens one interval after startup or after the schedule was set.
+pub async fn run_scheduler(app: AppHandle) {
+    match app.path().app_data_dir() {
+        Ok(dir) => {
+            let _ = std::fs::create_dir_all(&dir);
+            app.state::<MetadataCache>().load_persisted(dir.join(PERSISTED_FILE));
+        }
+        Err(e) => log::warn!("Recorded listings are not kept: {}", e),
+    }
+    let mut last_purge = Instant::now();
+    loop {
+        tokio::time::sleep(SCHEDULER_TICK).await;
+        let cache = app.state::<MetadataCache>();
+        let evicted = cache.evict_expired();
+        if evicted > 0 {
+            log::debug!("Evicted {} expired cache entries", evicted);
+        }
+        cache.flush();
+        let Some(interval) = cache.purge_interval() else {
+            last_purge = Instant::now();
+            continue;
+        };
+        if last_purge.elapsed() < interval {
+            continue;
+        }
+        last_purge = Instant::now();
+        match crate::sidecar::purge_cache(app.clone()).await {
+            Ok(()) => {
+                log::info!("Scheduled cache purge done");
+                let _ = app.emit("cache:purged", crate::sidecar::unix_now());
+            }
+            Err(e) => log::warn!("Scheduled cache purge failed: {}", e),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_cache_policy() -> Result<CachePolicy, CommandError> {
+    Ok(CachePolicy::from(&read_config_section::<CacheSettings>("cache")))
+}
+
+/// Update TTL, size limit and purge schedule. The gateway's cache and the
+/// schedule follow immediately; the sidecar picks up the TTL and size on its
+/// next start.
+#[tauri::command]
+pub async fn set_cache_policy(cache: State<'_, MetadataCache>, policy: CachePolicy) -> Result<CachePolicy, CommandError> {
+    if policy.max_size_mb == 0 {
+        return Err(CommandError::InvalidCachePolicy("maxSizeMB must be positive".into()));
+    }
+    let purge_schedule = match &policy.purge_schedule {
+        Some(schedule) if parse_purge_schedule(schedule)?.is_some() => Some(schedule.trim().to_lowercase()),
+        _ => None,
+    };
+    let mut settings: CacheSettings = read_config_section("cache");
+    settings.ttl_seconds = policy.ttl_seconds;
+    settings.max_size_mb = policy.max_size_mb;
+    settings.purge_schedule = purge_schedule;
+    write_config_section("cache", &settings)?;
+    cache.reload_settings();
+    Ok(CachePolicy::from(&settings))
+}
+
+#[tauri::command]
+pub async fn get_cache_stats(cache: State<'_, MetadataCache>) -> Result<CacheStats, CommandError> {
+    Ok(cache.stats())
+}
+
+/// Drop cached listings for `remote_path` and everything below it, e.g.
+/// after changing files through the Proton web app. Returns the number of
+/// entries removed.
+#[tauri::command]
+pub async fn purge_cache_path(cache: State<'_, MetadataCache>, remote_path: String) -> Result<usize, CommandError> {
+    let removed = cache.invalidate(&remote_path);
+    log::info!("Purged {} cached listing(s) under {}", removed, normalize_path(&remote_path));
+    Ok(removed)
+}
+
+/// Drop every cached and recorded listing, so the next ones come from the
+/// bridge. Unlike `purge_cache`, the sidecar's own cache is left alone.
+#[tauri::command]
+pub async fn clear_metadata_cache(cache: State<'_, MetadataCache>) -> Result<(), CommandError> {
+    cache.clear();
+    cache.flush();
+    log::info!("Cleared the metadata cache");
+    Ok(())
+}
+
+/// Cached listings under `prefix` (default `/`), sorted by path.
+#[tauri::command]
+pub async fn list_cache_entries(
+    cache: State<'_, MetadataCache>,
+    prefix: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CacheEntryInfo>, CommandError> {
+    Ok(cache.list(prefix.as_deref().unwrap_or("/"), limit.unwrap_or(DEFAULT_LIST_LIMIT)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    fn depth(d: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("depth", HeaderValue::from_static(d));
+        headers
+    }
+
+    fn response(size: usize) -> CachedResponse {
+        CachedResponse { status: 207, headers: HeaderMap::new(), body: Bytes::from(vec![b'x'; size]) }
+    }
+
+    #[test]
+    fn test_parse_purge_schedule() {
+        assert_eq!(parse_purge_schedule("never").unwrap(), None);
+        assert_eq!(parse_purge_schedule("Daily").unwrap(), Some(Duration::from_secs(86_400)));
+        assert_eq!(parse_purge_schedule("every 6h").unwrap(), Some(Duration::from_secs(6 * 3600)));
+        assert_eq!(parse_purge_schedule("@every 30m").unwrap(), Some(Duration::from_secs(1800)));
+        assert!(parse_purge_schedule("every 1m").is_err());
+        assert!(parse_purge_schedule("0 3 * * *").is_err());
+    }
+
+    #[test]
+    fn test_key_only_for_shallow_propfind() {
+        let cache = MetadataCache::default();
+        assert!(cache.key("/a", &depth("1"), b"").is_some());
+        assert!(cache.key("/a", &depth("infinity"), b"").is_none());
+        assert!(cache.key("/a", &HeaderMap::new(), b"").is_none());
+        assert_ne!(cache.key("/a", &depth("1"), b"x"), cache.key("/a", &depth("1"), b"y"));
+    }
+
+    #[test]
+    fn test_lookup_counts_hits_and_misses() {
+        let cache = MetadataCache::default();
+        let key = cache.key("/Docs", &depth("1"), b"").unwrap();
+        assert!(cache.lookup(&key).is_none());
+        cache.store(key.clone(), response(10));
+        assert_eq!(cache.lookup(&key).unwrap().status, 207);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert_eq!(stats.hit_ratio, Some(0.5));
+    }
+
+    #[test]
+    fn test_invalidate_parent_and_descendants() {
+        let cache = MetadataCache::default();
+        for path in ["/Docs", "/Docs/a", "/Docs/a/b", "/Photos"] {
+            cache.store(cache.key(path, &depth("1"), b"").unwrap(), response(1));
+        }
+        assert_eq!(cache.invalidate("/Docs/a"), 3);
+        let paths: Vec<String> = cache.list("/", 10).into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, vec!["/Photos"]);
+    }
+
+    #[test]
+    fn test_eviction_respects_max_size() {
+        let cache = MetadataCache::default();
+        cache.settings.lock().unwrap().max_size_mb = 1;
+        cache.store(cache.key("/a", &depth("1"), b"").unwrap(), response(600 * 1024));
+        cache.store(cache.key("/b", &depth("1"), b"").unwrap(), response(600 * 1024));
+        let paths: Vec<String> = cache.list("/", 10).into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, vec!["/b"]);
+    }
+
+    #[test]
+    fn test_recorded_listings_survive_a_restart() {
+        let file = std::env::temp_dir().join(format!("metadata-cache-test-{}.json", std::process::id()));
+        let entry = |path: &str, is_dir| DavEntry {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap_or_default().to_string(),
+            is_dir,
+            size: (!is_dir).then_some(3),
+            modified: None,
+            etag: Some("e1".into()),
+            content_type: None,
+        };
+        let cache = MetadataCache::default();
+        cache.load_persisted(file.clone());
+        cache.remember_listing("/Docs", &[entry("/Docs", true), entry("/Docs/a.txt", false)]);
+        cache.remember_listing("/Photos", &[entry("/Photos", true)]);
+        cache.invalidate("/Photos/new.jpg");
+        cache.flush();
+
+        let restarted = MetadataCache::default();
+        restarted.load_persisted(file.clone());
+        let _ = std::fs::remove_file(&file);
+        let listing = restarted.remembered_listing("/Docs").unwrap();
+        assert_eq!(listing[1].etag.as_deref(), Some("e1"));
+        assert!(restarted.remembered_listing("/Photos").is_none());
+        assert_eq!(restarted.stats().persisted_listings, 1);
+    }
+}
@@ -0,0 +1,295 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::cache::CacheSettings;
+use crate::sidecar::{get_config_file_path, CommandError};
+
+// ============================================================================
+// Config schema
+// ============================================================================
+//
+// config.json is shared with the sidecar and read section by section, with
+// each section falling back to its default when it is missing or doesn't
+// parse. `configVersion` records the layout of the file: at startup, files
+// from older versions are migrated step by step (the previous file is kept
+// as `config.json.v<n>.bak`), and files from newer versions are left alone.
+// Writes go to a temporary file that replaces config.json, so neither the
+// app nor the sidecar ever reads a half-written file.
+//
+// `validate_config` checks the keys the sidecar owns and every section the
+// app has read since it started, and reports what would be replaced by a
+// default instead of used.
+
+/// Layout written by this version.
+pub const CONFIG_VERSION: u64 = 1;
+
+const VERSION_KEY: &str = "configVersion";
+
+type SectionCheck = fn(&Value) -> Result<(), String>;
+
+/// Sections read through `read_config_section`, with how to check them.
+static SECTIONS: Mutex<BTreeMap<String, SectionCheck>> = Mutex::new(BTreeMap::new());
+
+/// Sections already reported as malformed in the log.
+static WARNED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+fn check<T: DeserializeOwned>(value: &Value) -> Result<(), String> {
+    T::deserialize(value).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Remember that `key` is read as a `T`, so `validate_config` can check it.
+pub(crate) fn register_section<T: DeserializeOwned>(key: &str) {
+    let mut sections = SECTIONS.lock().unwrap();
+    if !sections.contains_key(key) {
+        sections.insert(key.to_string(), check::<T>);
+    }
+}
+
+/// Log that section `key` is malformed, once per run.
+pub(crate) fn warn_malformed(key: &str, error: &serde_json::Error) {
+    if WARNED.lock().unwrap().get_or_insert_with(HashSet::new).insert(key.to_string()) {
+        log::warn!("Ignoring malformed config section {}: {}", key, error);
+    }
+}
+
+/// The `webdav` section as the sidecar reads it.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct WebdavKeys {
+    host: Option<String>,
+    port: Option<u16>,
+    require_auth: Option<bool>,
+    username: Option<String>,
+    password_hash: Option<String>,
+    https: Option<bool>,
+    cert_path: Option<String>,
+    key_path: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigProblem {
+    /// Top-level key, or empty for the file as a whole
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    fn new(key: &str, message: impl Into<String>) -> Self {
+        Self { key: key.to_string(), message: message.into() }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReport {
+    pub path: String,
+    pub exists: bool,
+    /// `configVersion` of the file; 0 for files from before it was recorded
+    pub version: u64,
+    pub supported_version: u64,
+    pub problems: Vec<ConfigProblem>,
+}
+
+fn version_of(config: &Value) -> u64 {
+    config.get(VERSION_KEY).and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Turn `"true"`/`"false"` at `pointer` into a boolean.
+fn coerce_bool(config: &mut Value, pointer: &str) {
+    if let Some(value) = config.pointer_mut(pointer) {
+        match value.as_str().map(str::trim) {
+            Some("true") => *value = Value::Bool(true),
+            Some("false") => *value = Value::Bool(false),
+            _ => {}
+        }
+    }
+}
+
+/// Turn a numeric string at `pointer` into a number.
+fn coerce_number(config: &mut Value, pointer: &str) {
+    if let Some(value) = config.pointer_mut(pointer) {
+        if let Some(n) = value.as_str().and_then(|s| s.trim().parse::<u64>().ok()) {
+            *value = n.into();
+        }
+    }
+}
+
+/// Version 0 to 1: early versions stored some flags and numbers as strings.
+fn migrate_v0(config: &mut Value) {
+    for pointer in ["/autoStart", "/autoMount", "/readOnly", "/debug", "/webdav/requireAuth", "/webdav/https", "/cache/enabled"] {
+        coerce_bool(config, pointer);
+    }
+    for pointer in ["/webdav/port", "/cache/ttlSeconds", "/cache/maxSizeMB"] {
+        coerce_number(config, pointer);
+    }
+}
+
+/// Migrations by the version they start from.
+const MIGRATIONS: &[fn(&mut Value)] = &[migrate_v0];
+
+/// Bring `config` up to `CONFIG_VERSION`. Returns the version it had, or
+/// `None` if nothing needed to change.
+pub(crate) fn migrate_value(config: &mut Value) -> Option<u64> {
+    let from = version_of(config);
+    if !config.is_object() || from >= CONFIG_VERSION {
+        return None;
+    }
+    for migration in &MIGRATIONS[from as usize..] {
+        migration(config);
+    }
+    config[VERSION_KEY] = CONFIG_VERSION.into();
+    Some(from)
+}
+
+/// Migrate config.json if it comes from an older version; called once at
+/// startup, before anything reads it. Returns the version it was migrated
+/// from.
+pub(crate) fn migrate() -> Result<Option<u64>, CommandError> {
+    let path = get_config_file_path()?;
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let Ok(mut config) = serde_json::from_str::<Value>(&contents) else {
+        // Left for `validate_config` to report
+        return Ok(None);
+    };
+    let Some(from) = migrate_value(&mut config) else {
+        return Ok(None);
+    };
+    let mut backup = path.clone().into_os_string();
+    backup.push(format!(".v{}.bak", from));
+    std::fs::copy(&path, &backup)?;
+    let json = serde_json::to_string_pretty(&config).map_err(|e| CommandError::Unknown(e.to_string()))?;
+    write_atomically(&path, json.as_bytes())?;
+    Ok(Some(from))
+}
+
+/// Replace `path` with `contents` through a temporary file in the same
+/// directory, readable only by the user like the sidecar's own writes.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), CommandError> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&temp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+pub(crate) fn validate(config: &Value) -> Vec<ConfigProblem> {
+    let Some(object) = config.as_object() else {
+        return vec![ConfigProblem::new("", "The file must hold a JSON object")];
+    };
+    let mut problems = Vec::new();
+    match object.get(VERSION_KEY) {
+        Some(v) if v.as_u64().is_none() => problems.push(ConfigProblem::new(VERSION_KEY, "Must be a whole number")),
+        Some(v) if v.as_u64() > Some(CONFIG_VERSION) => problems.push(ConfigProblem::new(
+            VERSION_KEY,
+            format!("Written by a newer version (layout {}, this version knows {})", v, CONFIG_VERSION),
+        )),
+        _ => {}
+    }
+
+    let mut checks: BTreeMap<String, SectionCheck> = SECTIONS.lock().unwrap().clone();
+    checks.insert("webdav".into(), check::<WebdavKeys>);
+    checks.insert("cache".into(), check::<CacheSettings>);
+    checks.insert("remotePath".into(), check::<String>);
+    checks.insert("debug".into(), check::<bool>);
+    for (key, check) in &checks {
+        if let Some(Err(e)) = object.get(key).map(check) {
+            problems.push(ConfigProblem::new(key, format!("{}; the default is used instead", e)));
+        }
+    }
+
+    if config.pointer("/webdav/port").and_then(Value::as_u64) == Some(0) {
+        problems.push(ConfigProblem::new("webdav", "port must not be 0"));
+    }
+    if let Some(path) = object.get("remotePath").and_then(Value::as_str) {
+        if !path.starts_with('/') {
+            problems.push(ConfigProblem::new("remotePath", "Must start with /"));
+        }
+    }
+    problems
+}
+
+/// Check config.json and report every problem found, without changing it.
+#[tauri::command]
+pub async fn validate_config() -> Result<ConfigReport, CommandError> {
+    let path = get_config_file_path()?;
+    let mut report = ConfigReport {
+        path: path.display().to_string(),
+        exists: path.exists(),
+        version: 0,
+        supported_version: CONFIG_VERSION,
+        problems: Vec::new(),
+    };
+    if !report.exists {
+        return Ok(report);
+    }
+    let contents = std::fs::read_to_string(&path)?;
+    match serde_json::from_str::<Value>(&contents) {
+        Ok(config) => {
+            report.version = version_of(&config);
+            report.problems = validate(&config);
+        }
+        Err(e) => report.problems.push(ConfigProblem::new("", format!("Not valid JSON: {}", e))),
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_migrate_from_v0() {
+        let mut config = json!({ "autoStart": "true", "webdav": { "port": "8081", "https": "false" }, "cache": { "maxSizeMB": 50 } });
+        assert_eq!(migrate_value(&mut config), Some(0));
+        assert_eq!(config, json!({
+            "autoStart": true,
+            "webdav": { "port": 8081, "https": false },
+            "cache": { "maxSizeMB": 50 },
+            "configVersion": CONFIG_VERSION,
+        }));
+        assert_eq!(migrate_value(&mut config), None);
+        assert_eq!(migrate_value(&mut json!({ "configVersion": CONFIG_VERSION + 1 })), None);
+    }
+
+    #[test]
+    fn test_validate_reports_what_falls_back() {
+        register_section::<bool>("readOnly");
+        let config = json!({
+            "configVersion": CONFIG_VERSION + 1,
+            "webdav": { "port": "x" },
+            "remotePath": "Documents",
+            "readOnly": "yes",
+            "unknownKey": 1,
+        });
+        let keys: Vec<String> = validate(&config).into_iter().map(|p| p.key).collect();
+        assert_eq!(keys, vec!["configVersion", "readOnly", "webdav", "remotePath"]);
+        assert!(validate(&json!({ "configVersion": CONFIG_VERSION, "webdav": { "port": 8080 } })).is_empty());
+        assert_eq!(validate(&json!([])).len(), 1);
+    }
+
+    #[test]
+    fn test_write_atomically_replaces_the_file() {
+        let path = std::env::temp_dir().join(format!("config-schema-test-{}.json", std::process::id()));
+        std::fs::write(&path, "old").unwrap();
+        write_atomically(&path, b"new").unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(contents, "new");
+    }
+}
@@ -0,0 +1,230 @@
+use rusqlite::{Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::db;
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Conflicts
+// ============================================================================
+//
+// When the sync engine or the drop folder finds that both the local file and
+// the remote one changed, neither is overwritten. The collision is recorded
+// here instead, announced with a `conflict:detected` event and left for the
+// user to settle with `resolve_conflict`: keep the local version, keep the
+// remote one, or keep both side by side. The registry is kept in the state
+// database so open conflicts survive restarts; the feature that raised a
+// conflict carries out its resolution.
+
+const ID_COUNTER: &str = "conflicts";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase", tag = "kind")]
+pub enum ConflictSource {
+    /// A file of a sync pair, by path relative to the pair's folders
+    Sync { pair_id: String, path: String },
+    /// A file of the drop folder, by path relative to it
+    DropFolder { path: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Conflict {
+    pub id: u64,
+    pub source: ConflictSource,
+    pub local_path: String,
+    pub remote_path: String,
+    pub local_size: Option<u64>,
+    /// Unix timestamp (seconds)
+    pub local_modified: Option<u64>,
+    pub remote_size: Option<u64>,
+    /// `getlastmodified` as sent by the server
+    pub remote_modified: Option<String>,
+    /// Unix timestamp (seconds)
+    pub detected_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::enum_variant_names)]
+pub enum Resolution {
+    /// Replace the remote file with the local one
+    KeepLocal,
+    /// Replace the local file with the remote one
+    KeepRemote,
+    /// Keep the remote file and store the local one next to it under a new
+    /// name
+    KeepBoth,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ConflictResolved {
+    id: u64,
+    resolution: Resolution,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    conflicts: Vec<Conflict>,
+}
+
+#[derive(Default)]
+pub struct ConflictRegistry {
+    /// Loaded from the state database on first use
+    registry: Mutex<Option<Registry>>,
+}
+
+/// "name (conflicted copy <timestamp>).ext" next to `path`, which may be a
+/// `/`-separated relative or remote path.
+pub(crate) fn conflicted_copy_name(path: &str, timestamp: u64) -> String {
+    let (dir, name) = match path.rfind('/') {
+        Some(i) => (&path[..=i], &path[i + 1..]),
+        None => ("", path),
+    };
+    let renamed = match name.rfind('.') {
+        Some(i) if i > 0 => format!("{} (conflicted copy {}){}", &name[..i], timestamp, &name[i..]),
+        _ => format!("{} (conflicted copy {})", name, timestamp),
+    };
+    format!("{}{}", dir, renamed)
+}
+
+fn load(conn: &Connection) -> rusqlite::Result<Registry> {
+    let mut stmt = conn.prepare("SELECT data FROM conflicts ORDER BY id")?;
+    let conflicts = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|json| db::from_json(&json?))
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(Registry { next_id: db::counter(conn, ID_COUNTER)?, conflicts })
+}
+
+fn save(tx: &Transaction, registry: &Registry) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM conflicts", [])?;
+    for conflict in &registry.conflicts {
+        tx.execute("INSERT INTO conflicts (id, data) VALUES (?1, ?2)", rusqlite::params![conflict.id as i64, db::to_json(conflict)?])?;
+    }
+    db::set_counter(tx, ID_COUNTER, registry.next_id)
+}
+
+impl ConflictRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` on the registry, loading it first if needed, and save it
+    /// afterwards when `f` says it changed something. Failing to save is
+    /// only logged; the registry in memory stays current.
+    fn update<T>(&self, app: &AppHandle, f: impl FnOnce(&mut Registry) -> (T, bool)) -> T {
+        let mut guard = self.registry.lock().unwrap();
+        if guard.is_none() {
+            *guard = Some(db::with(app, |conn| load(conn)).unwrap_or_else(|e| {
+                log::warn!("Failed to load conflicts: {}", e);
+                Registry::default()
+            }));
+        }
+        let registry = guard.as_mut().expect("registry was just loaded");
+        let (value, changed) = f(registry);
+        if changed {
+            if let Err(e) = db::transaction(app, |tx| save(tx, registry)) {
+                log::warn!("Failed to save conflicts: {}", e);
+            }
+        }
+        value
+    }
+
+    /// Record a conflict, or refresh the one already open for the same file.
+    /// Only new conflicts raise `conflict:detected`.
+    pub fn record(&self, app: &AppHandle, mut conflict: Conflict) {
+        let is_new = self.update(app, |registry| {
+            if let Some(open) = registry.conflicts.iter_mut().find(|c| c.source == conflict.source) {
+                conflict.id = open.id;
+                conflict.detected_at = open.detected_at;
+                *open = conflict.clone();
+                return (false, true);
+            }
+            registry.next_id += 1;
+            conflict.id = registry.next_id;
+            conflict.detected_at = crate::sidecar::unix_now();
+            registry.conflicts.push(conflict.clone());
+            (true, true)
+        });
+        if is_new {
+            log::warn!("Conflict on {}: both the local and the remote file changed", conflict.remote_path);
+            let _ = app.emit("conflict:detected", conflict);
+        }
+    }
+
+    /// Forget the conflict for `source`, e.g. once the file no longer
+    /// conflicts.
+    pub fn dismiss(&self, app: &AppHandle, source: &ConflictSource) {
+        self.update(app, |registry| {
+            let before = registry.conflicts.len();
+            registry.conflicts.retain(|c| c.source != *source);
+            ((), registry.conflicts.len() != before)
+        });
+    }
+
+    /// Forget every conflict of a sync pair.
+    pub fn dismiss_pair(&self, app: &AppHandle, pair_id: &str) {
+        self.update(app, |registry| {
+            registry.conflicts.retain(|c| !matches!(&c.source, ConflictSource::Sync { pair_id: id, .. } if id == pair_id));
+            ((), true)
+        });
+    }
+
+    fn get(&self, app: &AppHandle, id: u64) -> Option<Conflict> {
+        self.update(app, |registry| (registry.conflicts.iter().find(|c| c.id == id).cloned(), false))
+    }
+
+    fn remove(&self, app: &AppHandle, id: u64) {
+        self.update(app, |registry| {
+            registry.conflicts.retain(|c| c.id != id);
+            ((), true)
+        });
+    }
+
+    fn list(&self, app: &AppHandle) -> Vec<Conflict> {
+        self.update(app, |registry| (registry.conflicts.clone(), false))
+    }
+}
+
+#[tauri::command]
+pub async fn list_conflicts(app: AppHandle, registry: State<'_, ConflictRegistry>) -> Result<Vec<Conflict>, CommandError> {
+    Ok(registry.list(&app))
+}
+
+/// Settle a conflict. The feature that raised it carries out the
+/// resolution, after which the conflict is gone from the registry.
+#[tauri::command]
+pub async fn resolve_conflict(app: AppHandle, registry: State<'_, ConflictRegistry>, id: u64, resolution: Resolution) -> Result<(), CommandError> {
+    let conflict = registry.get(&app, id).ok_or(CommandError::ConflictNotFound(id))?;
+    match &conflict.source {
+        ConflictSource::Sync { pair_id, path } => crate::sync::resolve(&app, pair_id, path, resolution).await?,
+        ConflictSource::DropFolder { path } => crate::drop_folder::resolve(&app, &conflict, path, resolution).await?,
+    }
+    registry.remove(&app, id);
+    log::info!("Resolved conflict on {} ({:?})", conflict.remote_path, resolution);
+    let _ = app.emit("conflict:resolved", ConflictResolved { id, resolution });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicted_copy_name() {
+        assert_eq!(conflicted_copy_name("Docs/report.pdf", 42), "Docs/report (conflicted copy 42).pdf");
+        assert_eq!(conflicted_copy_name("/Docs/Makefile", 42), "/Docs/Makefile (conflicted copy 42)");
+        assert_eq!(conflicted_copy_name(".env", 1), ".env (conflicted copy 1)");
+    }
+
+    #[test]
+    fn test_source_serialization() {
+        let source = ConflictSource::Sync { pair_id: "ab12".into(), path: "a.txt".into() };
+        assert_eq!(serde_json::to_value(&source).unwrap(), serde_json::json!({"kind": "sync", "pairId": "ab12", "path": "a.txt"}));
+    }
+}
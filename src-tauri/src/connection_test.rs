@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Connection test
+// ============================================================================
+//
+// `run_connection_test` walks the path a file takes, one stage at a time,
+// for the troubleshooting page: resolving proton.me, a TLS connection to the
+// Drive API (through the configured proxy), a PROPFIND against the local
+// bridge, and whether GVFS can mount WebDAV. A stage that cannot run because
+// an earlier one failed, or that does not apply on this platform, is
+// reported as skipped rather than failed.
+
+const DNS_HOST: &str = "proton.me:443";
+
+const STAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    Dns,
+    ProtonApi,
+    LocalWebdav,
+    Gvfs,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StageStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StageResult {
+    pub stage: Stage,
+    pub status: StageStatus,
+    pub elapsed_ms: u64,
+    /// What was found, or why the stage failed or was skipped
+    pub detail: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionReport {
+    /// Whether no stage failed
+    pub passed: bool,
+    pub stages: Vec<StageResult>,
+}
+
+impl ConnectionReport {
+    fn new(stages: Vec<StageResult>) -> Self {
+        let passed = stages.iter().all(|s| s.status != StageStatus::Failed);
+        Self { passed, stages }
+    }
+}
+
+fn skipped(stage: Stage, detail: &str) -> StageResult {
+    StageResult { stage, status: StageStatus::Skipped, elapsed_ms: 0, detail: detail.to_string() }
+}
+
+/// Run `check` with a timeout and record its outcome.
+async fn run_stage<F>(stage: Stage, check: F) -> StageResult
+where
+    F: std::future::Future<Output = Result<String, String>>,
+{
+    let started = Instant::now();
+    let outcome = match tokio::time::timeout(STAGE_TIMEOUT, check).await {
+        Ok(outcome) => outcome,
+        Err(_) => Err(format!("No answer within {}s", STAGE_TIMEOUT.as_secs())),
+    };
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    let (status, detail) = match outcome {
+        Ok(detail) => (StageStatus::Passed, detail),
+        Err(detail) => (StageStatus::Failed, detail),
+    };
+    if status == StageStatus::Failed {
+        log::warn!("Connection test: {:?} failed: {}", stage, detail);
+    }
+    StageResult { stage, status, elapsed_ms, detail }
+}
+
+async fn check_dns() -> Result<String, String> {
+    let addrs: Vec<_> = tokio::net::lookup_host(DNS_HOST).await.map_err(|e| e.to_string())?.collect();
+    match addrs.first() {
+        Some(addr) => Ok(format!("proton.me resolves to {}", addr.ip())),
+        None => Err("proton.me has no addresses".to_string()),
+    }
+}
+
+async fn check_api(app: &AppHandle) -> Result<String, String> {
+    let builder = crate::proxy::configure(app, reqwest::Client::builder().timeout(STAGE_TIMEOUT))
+        .await
+        .map_err(|e| e.to_string())?;
+    let client = builder.build().map_err(|e| e.to_string())?;
+    let response = client.get(crate::proxy::TEST_URL).send().await.map_err(|e| e.to_string())?;
+    Ok(format!("Drive API answered with HTTP {}", response.status().as_u16()))
+}
+
+async fn check_webdav(app: &AppHandle) -> Result<String, String> {
+    let client = crate::dav::DavClient::for_app(app).map_err(|e| e.to_string())?;
+    let entries = client.propfind("/", 1).await.map_err(|e| e.to_string())?;
+    // The root itself is the first entry
+    Ok(format!("Listed {} item(s) in the drive root", entries.len().saturating_sub(1)))
+}
+
+#[cfg(target_os = "linux")]
+fn check_gvfs() -> Result<String, String> {
+    use gio::prelude::*;
+
+    let schemes = gio::Vfs::default().supported_uri_schemes();
+    if !schemes.iter().any(|s| s == "dav") {
+        let remediation = crate::system_requirements::probe()
+            .requirements
+            .into_iter()
+            .find(|r| r.id == "gvfsd-dav")
+            .and_then(|r| r.remediation);
+        return Err(match remediation {
+            Some(hint) => format!("GVFS cannot mount dav:// locations. {}", hint),
+            None => "GVFS cannot mount dav:// locations".to_string(),
+        });
+    }
+    Ok("GVFS supports dav:// locations".to_string())
+}
+
+#[tauri::command]
+pub async fn run_connection_test(app: AppHandle) -> Result<ConnectionReport, CommandError> {
+    let mut stages = Vec::new();
+    let dns = run_stage(Stage::Dns, check_dns()).await;
+    let dns_failed = dns.status == StageStatus::Failed;
+    stages.push(dns);
+    // A proxy resolves names on its own
+    stages.push(if dns_failed && !crate::proxy::is_enabled() {
+        skipped(Stage::ProtonApi, "Needs proton.me to resolve")
+    } else {
+        run_stage(Stage::ProtonApi, check_api(&app)).await
+    });
+    stages.push(run_stage(Stage::LocalWebdav, check_webdav(&app)).await);
+    #[cfg(target_os = "linux")]
+    stages.push(run_stage(Stage::Gvfs, async { check_gvfs() }).await);
+    #[cfg(not(target_os = "linux"))]
+    stages.push(skipped(Stage::Gvfs, "GVFS is only used on Linux"));
+    let report = ConnectionReport::new(stages);
+    log::info!("Connection test {}", if report.passed { "passed" } else { "failed" });
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skipped_stages_do_not_fail_the_report() {
+        let report = ConnectionReport::new(vec![
+            StageResult { stage: Stage::Dns, status: StageStatus::Passed, elapsed_ms: 3, detail: String::new() },
+            skipped(Stage::Gvfs, "n/a"),
+        ]);
+        assert!(report.passed);
+        let report = ConnectionReport::new(vec![
+            StageResult { stage: Stage::Dns, status: StageStatus::Failed, elapsed_ms: 3, detail: String::new() },
+            skipped(Stage::ProtonApi, "n/a"),
+        ]);
+        assert!(!report.passed);
+    }
+
+    #[test]
+    fn test_report_serializes_camel_case() {
+        let report = ConnectionReport::new(vec![skipped(Stage::LocalWebdav, "Not running")]);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["stages"][0]["stage"], "localWebdav");
+        assert_eq!(json["stages"][0]["status"], "skipped");
+        assert_eq!(json["stages"][0]["elapsedMs"], 0);
+    }
+}
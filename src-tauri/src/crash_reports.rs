@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Crash reports
+// ============================================================================
+//
+// Panics in the app and unexpected exits of the bridge are written as JSON
+// reports to `crash-reports/` in the app data dir, where the user can review
+// and delete them. Nothing leaves the machine unless the user opts in with
+// `set_telemetry(true)` and the build sets `PDWB_TELEMETRY_URL`; reports are
+// then posted there after the home directory and the local part of email
+// addresses have been stripped from them. A panic report is written from the
+// panic hook and sent on the next launch.
+
+const CONFIG_KEY: &str = "telemetry";
+
+const REPORT_DIR: &str = "crash-reports";
+
+const TELEMETRY_URL: Option<&str> = option_env!("PDWB_TELEMETRY_URL");
+
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Reports kept; older ones are deleted when a new one is written.
+const MAX_REPORTS: usize = 50;
+
+/// Lines of the bridge's stderr kept for a report on its exit.
+pub const STDERR_LINES: usize = 40;
+
+/// Report directory, known to the panic hook once `install` ran.
+static REPORT_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct TelemetryConfig {
+    #[serde(default)]
+    enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CrashKind {
+    /// The app panicked
+    Panic,
+    /// The bridge exited on its own with an error
+    SidecarCrash,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub id: String,
+    pub kind: CrashKind,
+    /// Unix seconds
+    pub timestamp: u64,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub message: String,
+    /// Backtrace of a panic, or the bridge's last stderr lines
+    pub details: Option<String>,
+    #[serde(default)]
+    pub submitted: bool,
+}
+
+impl CrashReport {
+    fn new(kind: CrashKind, app_version: String, message: String, details: Option<String>) -> Self {
+        let timestamp = crate::sidecar::unix_now();
+        Self {
+            // Random suffix, so two reports in the same second get their own file
+            id: format!("{}-{:09}", timestamp, rand::random::<u32>() % 1_000_000_000),
+            kind,
+            timestamp,
+            app_version,
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            message,
+            details,
+            submitted: false,
+        }
+    }
+
+    /// Copy safe to send: no home directory or account names.
+    fn anonymized(&self) -> Self {
+        let home = std::env::var("HOME").ok().filter(|h| h.len() > 1);
+        let scrub = |text: &str| {
+            let text = match &home {
+                Some(home) => text.replace(home.as_str(), "~"),
+                None => text.to_string(),
+            };
+            crate::diagnostics::mask_emails(&text)
+        };
+        Self {
+            message: scrub(&self.message),
+            details: self.details.as_deref().map(scrub),
+            ..self.clone()
+        }
+    }
+}
+
+/// Report ids are generated here; anything else is refused so an id can't
+/// name a file outside the report directory.
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_digit() || c == '-')
+}
+
+fn report_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    Ok(app.path().app_data_dir().map_err(|e| CommandError::IoError(e.to_string()))?.join(REPORT_DIR))
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let json = serde_json::to_string_pretty(report).expect("report serializes");
+    std::fs::write(dir.join(format!("{}.json", report.id)), json)?;
+    prune(dir);
+    Ok(())
+}
+
+fn read_reports(dir: &Path) -> Vec<CrashReport> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut reports: Vec<CrashReport> = entries
+        .flatten()
+        .filter_map(|entry| serde_json::from_str(&std::fs::read_to_string(entry.path()).ok()?).ok())
+        .collect();
+    reports.sort_by(|a, b| b.id.cmp(&a.id));
+    reports
+}
+
+fn prune(dir: &Path) {
+    for report in read_reports(dir).into_iter().skip(MAX_REPORTS) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", report.id)));
+    }
+}
+
+fn telemetry_enabled() -> bool {
+    read_config_section::<TelemetryConfig>(CONFIG_KEY).enabled
+}
+
+/// Send the reports not sent yet, if the user opted in.
+async fn submit_pending(dir: PathBuf) {
+    let Some(url) = TELEMETRY_URL else { return };
+    if !telemetry_enabled() {
+        return;
+    }
+    let client = match reqwest::Client::builder().timeout(SUBMIT_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("Cannot submit crash reports: {}", e);
+            return;
+        }
+    };
+    for mut report in read_reports(&dir).into_iter().filter(|r| !r.submitted) {
+        let sent = client.post(url).json(&report.anonymized()).send().await.and_then(|r| r.error_for_status());
+        match sent {
+            Ok(_) => {
+                report.submitted = true;
+                let _ = write_report(&dir, &report);
+                log::info!("Submitted crash report {}", report.id);
+            }
+            Err(e) => {
+                log::warn!("Failed to submit crash report {}: {}", report.id, e);
+                break;
+            }
+        }
+    }
+}
+
+/// Install the panic hook and send reports left by earlier runs. Called from
+/// `setup`.
+pub fn install(app: &AppHandle) {
+    let Ok(dir) = report_dir(app) else { return };
+    let _ = REPORT_PATH.set(dir.clone());
+    let app_version = app.package_info().version.to_string();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(dir) = REPORT_PATH.get() {
+            let location = info.location().map(|l| format!(" at {}:{}", l.file(), l.line())).unwrap_or_default();
+            let payload = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic".to_string());
+            let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+            let report = CrashReport::new(CrashKind::Panic, app_version.clone(), format!("{}{}", payload, location), Some(backtrace));
+            let _ = write_report(dir, &report);
+        }
+        previous(info);
+    }));
+    tauri::async_runtime::spawn(submit_pending(dir));
+}
+
+/// Record an unexpected exit of the bridge with its last stderr lines.
+pub fn record_sidecar_crash(app: &AppHandle, reason: &str, stderr: Vec<String>) {
+    let Ok(dir) = report_dir(app) else { return };
+    let details = (!stderr.is_empty()).then(|| stderr.join("\n"));
+    let report = CrashReport::new(
+        CrashKind::SidecarCrash,
+        app.package_info().version.to_string(),
+        format!("The bridge exited ({})", reason),
+        details,
+    );
+    match write_report(&dir, &report) {
+        Ok(()) => {
+            log::info!("Wrote crash report {}", report.id);
+            tauri::async_runtime::spawn(submit_pending(dir));
+        }
+        Err(e) => log::warn!("Failed to write crash report: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn list_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, CommandError> {
+    Ok(read_reports(&report_dir(&app)?))
+}
+
+#[tauri::command]
+pub async fn delete_crash_report(app: AppHandle, id: String) -> Result<(), CommandError> {
+    let path = report_dir(&app)?.join(format!("{}.json", id));
+    if !valid_id(&id) || !path.is_file() {
+        return Err(CommandError::CrashReportNotFound(id));
+    }
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_telemetry() -> Result<bool, CommandError> {
+    Ok(telemetry_enabled())
+}
+
+/// Opt in to or out of sending crash reports. Opting in sends the reports
+/// already collected.
+#[tauri::command]
+pub async fn set_telemetry(app: AppHandle, enabled: bool) -> Result<bool, CommandError> {
+    write_config_section(CONFIG_KEY, &TelemetryConfig { enabled })?;
+    log::info!("Crash report submission {}", if enabled { "enabled" } else { "disabled" });
+    if enabled {
+        tauri::async_runtime::spawn(submit_pending(report_dir(&app)?));
+    }
+    Ok(enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_id() {
+        let report = CrashReport::new(CrashKind::Panic, "0.1.0".into(), "boom".into(), None);
+        assert!(valid_id(&report.id));
+        assert!(!valid_id("../config"));
+        assert!(!valid_id(""));
+    }
+
+    #[test]
+    fn test_anonymized_strips_home_and_emails() {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/home/alice".into());
+        let report = CrashReport::new(
+            CrashKind::SidecarCrash,
+            "0.1.0".into(),
+            format!("Failed to open {}/Documents", home),
+            Some("Session for alice@proton.me expired".into()),
+        );
+        let anonymized = report.anonymized();
+        if home.len() > 1 {
+            assert_eq!(anonymized.message, "Failed to open ~/Documents");
+        }
+        assert_eq!(anonymized.details.as_deref(), Some("Session for a***@proton.me expired"));
+        assert_eq!(anonymized.id, report.id);
+    }
+
+    #[test]
+    fn test_reports_round_trip_and_prune() {
+        let dir = std::env::temp_dir().join(format!("crash-reports-test-{}", std::process::id()));
+        for i in 0..MAX_REPORTS + 2 {
+            let mut report = CrashReport::new(CrashKind::Panic, "0.1.0".into(), format!("panic {}", i), None);
+            report.id = format!("{:04}", i);
+            write_report(&dir, &report).unwrap();
+        }
+        let reports = read_reports(&dir);
+        assert_eq!(reports.len(), MAX_REPORTS);
+        assert_eq!(reports[0].message, format!("panic {}", MAX_REPORTS + 1));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,669 @@
+use bytes::Bytes;
+use futures_util::Stream;
+use hyper::header::{HeaderMap, HeaderValue};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::cache::{CachedResponse, MetadataCache};
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// WebDAV client for the local bridge
+// ============================================================================
+//
+// Backend features that operate on remote files talk WebDAV to the sidecar's
+// private loopback port or Unix socket, bypassing the gateway (and therefore
+// its TLS and client-facing policies). Paths are remote paths such as
+// `/Documents/a.txt`. Because of that, callers that modify the drive must
+// invalidate the gateway's `MetadataCache` themselves, and the client
+// refuses writes itself while the share is read-only or travel mode is on.
+
+/// Characters left unescaped in a path segment (RFC 3986 unreserved).
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+pub(crate) const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop>
+    <D:resourcetype/>
+    <D:getcontentlength/>
+    <D:getlastmodified/>
+    <D:getetag/>
+    <D:getcontenttype/>
+  </D:prop>
+</D:propfind>"#;
+
+/// RFC 4331 quota properties.
+const QUOTA_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop>
+    <D:quota-used-bytes/>
+    <D:quota-available-bytes/>
+  </D:prop>
+</D:propfind>"#;
+
+/// ownCloud's checksum property, the de facto way WebDAV servers publish
+/// content hashes.
+const CHECKSUM_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:" xmlns:oc="http://owncloud.org/ns">
+  <D:prop>
+    <oc:checksums/>
+  </D:prop>
+</D:propfind>"#;
+
+/// Storage usage reported for a collection, in bytes.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DavQuota {
+    pub used: u64,
+    pub available: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DavEntry {
+    /// Remote path, decoded, without a trailing slash (except for `/`)
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    /// `getlastmodified` as sent by the server (RFC 1123)
+    pub modified: Option<String>,
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+}
+
+pub struct DavClient {
+    http: reqwest::Client,
+    base: String,
+    app: AppHandle,
+}
+
+fn method(name: &'static str) -> Method {
+    Method::from_bytes(name.as_bytes()).expect("valid WebDAV method name")
+}
+
+/// Normalise a user-supplied remote path to `/a/b` form.
+pub fn normalize_path(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty() && *s != ".").collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// Parent directory of a normalized remote path (`/` for the root).
+pub fn parent_path(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+/// Join a child name onto a remote directory path.
+pub fn join_path(dir: &str, name: &str) -> String {
+    if dir == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// Percent-encode a remote path for use in a URL.
+pub fn encode_path(path: &str) -> String {
+    normalize_path(path)
+        .split('/')
+        .map(|s| utf8_percent_encode(s, PATH_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Turn an `href` from a multistatus response into a decoded remote path.
+fn href_to_path(href: &str) -> String {
+    let path = match href.find("://") {
+        Some(i) => href[i + 3..].find('/').map(|j| &href[i + 3 + j..]).unwrap_or("/"),
+        None => href,
+    };
+    normalize_path(&percent_decode_str(path).decode_utf8_lossy())
+}
+
+pub(crate) fn parse_multistatus(xml: &str) -> Result<Vec<DavEntry>, CommandError> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| CommandError::WebDavError(format!("Invalid PROPFIND response: {}", e)))?;
+    let is_dav = |n: &roxmltree::Node, name: &str| n.is_element() && n.tag_name().name() == name && n.tag_name().namespace() == Some("DAV:");
+    let child_text = |prop: &roxmltree::Node, name: &str| {
+        prop.children().find(|c| is_dav(c, name)).and_then(|c| c.text()).map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+    };
+
+    let mut entries = Vec::new();
+    for response in doc.descendants().filter(|n| is_dav(n, "response")) {
+        let Some(href) = response.children().find(|c| is_dav(c, "href")).and_then(|h| h.text()) else {
+            continue;
+        };
+        let Some(prop) = response.descendants().find(|n| is_dav(n, "prop")) else {
+            continue;
+        };
+        let is_dir = prop
+            .children()
+            .find(|c| is_dav(c, "resourcetype"))
+            .is_some_and(|rt| rt.children().any(|c| is_dav(&c, "collection")));
+        let path = href_to_path(href.trim());
+        let name = path.rsplit('/').next().unwrap_or("").to_string();
+        entries.push(DavEntry {
+            name,
+            is_dir,
+            size: child_text(&prop, "getcontentlength").and_then(|s| s.parse().ok()),
+            modified: child_text(&prop, "getlastmodified"),
+            etag: child_text(&prop, "getetag"),
+            content_type: child_text(&prop, "getcontenttype"),
+            path,
+        });
+    }
+    Ok(entries)
+}
+
+/// The name of the tag starting at `buf[lt]` (a `<`), without a leading
+/// `/`; `None` until the name is complete.
+fn tag_name(buf: &[u8], lt: usize) -> Option<&[u8]> {
+    let start = if buf.get(lt + 1) == Some(&b'/') { lt + 2 } else { lt + 1 };
+    let len = buf.get(start..)?.iter().position(|b| b.is_ascii_whitespace() || matches!(b, b'>' | b'/'))?;
+    Some(&buf[start..start + len])
+}
+
+/// Whether the qualified tag name `name` is `local` in any namespace.
+fn is_local_name(name: &[u8], local: &str) -> bool {
+    name.rsplit(|b| *b == b':').next() == Some(local.as_bytes())
+}
+
+/// Parses a multistatus response as it arrives, so the entries of a large
+/// folder can be used before the whole body is in. Each complete `response`
+/// element is parsed on its own, inside a copy of the root element's start
+/// tag so its namespace declarations still apply.
+#[derive(Default)]
+pub(crate) struct MultistatusReader {
+    buf: Vec<u8>,
+    /// Start tag and end tag of the root `multistatus` element
+    root: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl MultistatusReader {
+    /// Add the next chunk of the body and return the entries it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<DavEntry>, CommandError> {
+        self.buf.extend_from_slice(chunk);
+        if self.root.is_none() && !self.find_root() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        while let Some(end) = self.next_response() {
+            let (start_tag, end_tag) = self.root.as_ref().expect("root found");
+            let mut doc = start_tag.clone();
+            doc.extend_from_slice(&self.buf[..end]);
+            doc.extend_from_slice(end_tag);
+            entries.extend(parse_multistatus(&String::from_utf8_lossy(&doc))?);
+            self.buf.drain(..end);
+        }
+        Ok(entries)
+    }
+
+    /// Fail if the body ended before its root element started.
+    pub fn finish(self) -> Result<(), CommandError> {
+        match self.root {
+            Some(_) => Ok(()),
+            None => Err(CommandError::WebDavError("Invalid PROPFIND response: no multistatus element".into())),
+        }
+    }
+
+    /// Consume everything up to and including the root start tag, if it is
+    /// all there.
+    fn find_root(&mut self) -> bool {
+        let mut from = 0;
+        while let Some(lt) = self.buf[from..].iter().position(|b| *b == b'<').map(|i| from + i) {
+            let Some(name) = tag_name(&self.buf, lt) else { return false };
+            let Some(gt) = self.buf[lt..].iter().position(|b| *b == b'>').map(|i| lt + i) else { return false };
+            if is_local_name(name, "multistatus") {
+                let end_tag = [b"</".as_slice(), name, b">"].concat();
+                self.root = Some((self.buf[lt..=gt].to_vec(), end_tag));
+                self.buf.drain(..=gt);
+                return true;
+            }
+            from = gt + 1;
+        }
+        false
+    }
+
+    /// Drop what precedes the next `response` element and return where it
+    /// ends, if it is complete.
+    fn next_response(&mut self) -> Option<usize> {
+        let mut from = 0;
+        let start = loop {
+            let lt = self.buf[from..].iter().position(|b| *b == b'<').map(|i| from + i)?;
+            let name = tag_name(&self.buf, lt)?;
+            if self.buf.get(lt + 1) != Some(&b'/') && is_local_name(name, "response") {
+                break lt;
+            }
+            from = lt + 1;
+        };
+        self.buf.drain(..start);
+        let name = tag_name(&self.buf, 0)?.to_vec();
+        let close = [b"</".as_slice(), &name].concat();
+        let mut from = 0;
+        loop {
+            let at = self.buf[from..].windows(close.len()).position(|w| w == close.as_slice()).map(|i| from + i)?;
+            let after = at + close.len();
+            let gt = self.buf[after..].iter().position(|b| !b.is_ascii_whitespace()).map(|i| after + i)?;
+            if self.buf[gt] == b'>' {
+                return Some(gt + 1);
+            }
+            from = after;
+        }
+    }
+}
+
+/// Quota properties of the first response; `None` when the server does not
+/// report them (they come back in a 404 propstat, or not at all).
+fn parse_quota(xml: &str) -> Result<Option<DavQuota>, CommandError> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| CommandError::WebDavError(format!("Invalid PROPFIND response: {}", e)))?;
+    let value = |name: &str| -> Option<u64> {
+        doc.descendants()
+            .find(|n| n.is_element() && n.tag_name().name() == name && n.tag_name().namespace() == Some("DAV:"))
+            .and_then(|n| n.text())
+            .and_then(|t| t.trim().parse().ok())
+    };
+    Ok(value("quota-used-bytes").zip(value("quota-available-bytes")).map(|(used, available)| DavQuota { used, available }))
+}
+
+/// The SHA-256 among the first response's `oc:checksum` values, which look
+/// like `SHA1:... MD5:... SHA256:...`, in lowercase hex.
+fn parse_sha256_checksum(xml: &str) -> Result<Option<String>, CommandError> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| CommandError::WebDavError(format!("Invalid PROPFIND response: {}", e)))?;
+    Ok(doc
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "checksum")
+        .filter_map(|n| n.text())
+        .flat_map(str::split_whitespace)
+        .find_map(|token| {
+            let (algorithm, hex) = token.split_once(':')?;
+            let algorithm = algorithm.to_ascii_uppercase().replace('-', "");
+            (algorithm == "SHA256").then(|| hex.to_ascii_lowercase())
+        }))
+}
+
+/// Map an unexpected response status to a command error.
+fn status_error(status: StatusCode, path: &str) -> CommandError {
+    if status == StatusCode::NOT_FOUND {
+        CommandError::RemotePathNotFound(path.to_string())
+    } else {
+        CommandError::WebDavError(format!("{} for {}", status, path))
+    }
+}
+
+fn request_error(e: reqwest::Error) -> CommandError {
+    CommandError::WebDavError(e.to_string())
+}
+
+impl DavClient {
+    /// Client for the bridge started by this app. Fails when it isn't running.
+    pub fn for_app(app: &AppHandle) -> Result<Self, CommandError> {
+        let upstream = crate::gateway::upstream(app).ok_or(CommandError::ServerNotRunning)?;
+        let mut headers = HeaderMap::new();
+        if let Some(credential) = app.state::<crate::webdav_auth::WebdavAuthState>().run_credential() {
+            headers.insert(hyper::header::AUTHORIZATION, credential.header_value());
+        }
+        let http = upstream
+            .client_builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .default_headers(headers)
+            .build()
+            .map_err(request_error)?;
+        Ok(Self { http, base: upstream.base_url(), app: app.clone() })
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, encode_path(path))
+    }
+
+    /// A bare `name` request on `path`, for callers that need to inspect the
+    /// raw response. Only for methods that don't write; writes go through
+    /// the methods below, which check the read-only share.
+    pub(crate) fn request(&self, name: &'static str, path: &str) -> reqwest::RequestBuilder {
+        debug_assert!(!crate::read_only::writes(&method(name)), "{} must go through a checked method", name);
+        self.http.request(method(name), self.url(path))
+    }
+
+    /// Fail while the share is read-only or travel mode is on.
+    fn check_writable(&self) -> Result<(), CommandError> {
+        self.app.state::<crate::read_only::ReadOnlyState>().check_writable()
+    }
+
+    /// Raw multistatus body of a PROPFIND on `path`.
+    async fn propfind_xml(&self, path: &str, depth: u8) -> Result<String, CommandError> {
+        let resp = self
+            .http
+            .request(method("PROPFIND"), self.url(path))
+            .header("Depth", depth.to_string())
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send()
+            .await
+            .map_err(request_error)?;
+        if resp.status() != StatusCode::MULTI_STATUS {
+            return Err(status_error(resp.status(), path));
+        }
+        resp.text().await.map_err(request_error)
+    }
+
+    /// List `path` itself (depth 0) or it and its children (depth 1).
+    pub async fn propfind(&self, path: &str, depth: u8) -> Result<Vec<DavEntry>, CommandError> {
+        parse_multistatus(&self.propfind_xml(path, depth).await?)
+    }
+
+    /// List the children of `path` (and `path` itself) like a depth 1
+    /// `propfind`, handing the entries to `each` in batches as the response
+    /// arrives. `each` returns `false` to stop reading.
+    pub async fn propfind_each(&self, path: &str, mut each: impl FnMut(Vec<DavEntry>) -> bool) -> Result<(), CommandError> {
+        use futures_util::StreamExt;
+
+        let resp = self
+            .http
+            .request(method("PROPFIND"), self.url(path))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send()
+            .await
+            .map_err(request_error)?;
+        if resp.status() != StatusCode::MULTI_STATUS {
+            return Err(status_error(resp.status(), path));
+        }
+        let mut reader = MultistatusReader::default();
+        let mut body = resp.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let entries = reader.push(&chunk.map_err(request_error)?)?;
+            if !entries.is_empty() && !each(entries) {
+                return Ok(());
+            }
+        }
+        reader.finish()
+    }
+
+    /// Like `propfind`, but answered from the metadata cache when possible
+    /// and stored there otherwise, so repeated crawls stay cheap. Folder
+    /// listings also come from, and go to, the listings the cache keeps
+    /// across restarts. Entries are invalidated with the gateway's own.
+    pub async fn propfind_cached(&self, cache: &MetadataCache, path: &str, depth: u8) -> Result<Vec<DavEntry>, CommandError> {
+        let mut headers = HeaderMap::new();
+        headers.insert("depth", HeaderValue::from(u16::from(depth)));
+        let key = cache.key(path, &headers, PROPFIND_BODY.as_bytes());
+        if let Some(cached) = key.as_ref().and_then(|k| cache.lookup(k)) {
+            return parse_multistatus(&String::from_utf8_lossy(&cached.body));
+        }
+        if depth == 1 {
+            if let Some(entries) = cache.remembered_listing(path) {
+                return Ok(entries);
+            }
+        }
+        let xml = self.propfind_xml(path, depth).await?;
+        let entries = parse_multistatus(&xml)?;
+        if depth == 1 {
+            cache.remember_listing(path, &entries);
+        }
+        if let Some(key) = key {
+            let response = CachedResponse { status: StatusCode::MULTI_STATUS.as_u16(), headers: HeaderMap::new(), body: Bytes::from(xml) };
+            cache.store(key, response);
+        }
+        Ok(entries)
+    }
+
+    /// Storage quota of the drive as seen from `path`, if the server reports
+    /// it.
+    pub async fn quota(&self, path: &str) -> Result<Option<DavQuota>, CommandError> {
+        let resp = self
+            .http
+            .request(method("PROPFIND"), self.url(path))
+            .header("Depth", "0")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(QUOTA_BODY)
+            .send()
+            .await
+            .map_err(request_error)?;
+        if resp.status() != StatusCode::MULTI_STATUS {
+            return Err(status_error(resp.status(), path));
+        }
+        parse_quota(&resp.text().await.map_err(request_error)?)
+    }
+
+    /// SHA-256 of the file at `path`, if the server publishes one.
+    pub async fn sha256(&self, path: &str) -> Result<Option<String>, CommandError> {
+        let resp = self
+            .http
+            .request(method("PROPFIND"), self.url(path))
+            .header("Depth", "0")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(CHECKSUM_BODY)
+            .send()
+            .await
+            .map_err(request_error)?;
+        if resp.status() != StatusCode::MULTI_STATUS {
+            return Err(status_error(resp.status(), path));
+        }
+        parse_sha256_checksum(&resp.text().await.map_err(request_error)?)
+    }
+
+    /// Metadata for a single resource, or `None` if it doesn't exist.
+    pub async fn stat(&self, path: &str) -> Result<Option<DavEntry>, CommandError> {
+        match self.propfind(path, 0).await {
+            Ok(entries) => Ok(entries.into_iter().next()),
+            Err(CommandError::RemotePathNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn mkcol(&self, path: &str) -> Result<(), CommandError> {
+        self.check_writable()?;
+        let resp = self.http.request(method("MKCOL"), self.url(path)).send().await.map_err(request_error)?;
+        match resp.status() {
+            s if s.is_success() => Ok(()),
+            // Already exists
+            StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            s => Err(status_error(s, path)),
+        }
+    }
+
+    /// Ask the server to copy `src` to `dest` itself. Returns `false` when the
+    /// server does not implement COPY so the caller can fall back.
+    pub async fn copy(&self, src: &str, dest: &str, overwrite: bool) -> Result<bool, CommandError> {
+        self.check_writable()?;
+        let resp = self
+            .http
+            .request(method("COPY"), self.url(src))
+            .header("Destination", self.url(dest))
+            .header("Overwrite", if overwrite { "T" } else { "F" })
+            .header("Depth", "infinity")
+            .send()
+            .await
+            .map_err(request_error)?;
+        match resp.status() {
+            s if s.is_success() => Ok(true),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => Ok(false),
+            StatusCode::PRECONDITION_FAILED => Err(CommandError::WebDavError(format!("{} already exists", dest))),
+            s => Err(status_error(s, src)),
+        }
+    }
+
+    /// Delete the file or folder `path`; a missing one counts as deleted.
+    pub async fn delete(&self, path: &str) -> Result<(), CommandError> {
+        self.check_writable()?;
+        let resp = self.http.delete(self.url(path)).send().await.map_err(request_error)?;
+        match resp.status() {
+            s if s.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Ok(()),
+            s => Err(status_error(s, path)),
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> Result<reqwest::Response, CommandError> {
+        let resp = self.http.get(self.url(path)).send().await.map_err(request_error)?;
+        if !resp.status().is_success() {
+            return Err(status_error(resp.status(), path));
+        }
+        Ok(resp)
+    }
+
+    /// The first `len` bytes of `path`, asking for only those with a Range
+    /// request. From a server that ignores it the rest is not read.
+    pub async fn get_head(&self, path: &str, len: usize) -> Result<Vec<u8>, CommandError> {
+        use futures_util::StreamExt;
+
+        let resp = self
+            .http
+            .get(self.url(path))
+            .header(hyper::header::RANGE, format!("bytes=0-{}", len.saturating_sub(1)))
+            .send()
+            .await
+            .map_err(request_error)?;
+        if !resp.status().is_success() {
+            return Err(status_error(resp.status(), path));
+        }
+        let mut head = Vec::with_capacity(len);
+        let mut body = resp.bytes_stream();
+        while head.len() < len {
+            match body.next().await {
+                Some(chunk) => head.extend_from_slice(&chunk.map_err(request_error)?),
+                None => break,
+            }
+        }
+        head.truncate(len);
+        Ok(head)
+    }
+
+    pub async fn put<S>(&self, path: &str, body: S, length: Option<u64>) -> Result<(), CommandError>
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        self.check_writable()?;
+        let mut req = self.http.put(self.url(path)).body(reqwest::Body::wrap_stream(body));
+        if let Some(len) = length {
+            req = req.header("Content-Length", len.to_string());
+        }
+        let resp = req.send().await.map_err(request_error)?;
+        if !resp.status().is_success() {
+            return Err(status_error(resp.status(), path));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(""), "/");
+        assert_eq!(normalize_path("Documents//a/"), "/Documents/a");
+        assert_eq!(normalize_path("/./x"), "/x");
+    }
+
+    #[test]
+    fn test_encode_path_escapes_segments() {
+        assert_eq!(encode_path("/My Files/a#1.txt"), "/My%20Files/a%231.txt");
+        assert_eq!(encode_path("/"), "/");
+    }
+
+    #[test]
+    fn test_href_to_path() {
+        assert_eq!(href_to_path("/My%20Files/"), "/My Files");
+        assert_eq!(href_to_path("http://127.0.0.1:8080/a/b.txt"), "/a/b.txt");
+        assert_eq!(href_to_path("http://127.0.0.1:8080"), "/");
+    }
+
+    #[test]
+    fn test_parse_multistatus() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/Docs/</d:href>
+    <d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/Docs/report%201.pdf</d:href>
+    <d:propstat><d:prop>
+      <d:resourcetype/>
+      <d:getcontentlength>1234</d:getcontentlength>
+      <d:getetag>"abc"</d:getetag>
+      <d:getlastmodified>Mon, 12 Jan 2026 10:00:00 GMT</d:getlastmodified>
+    </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let entries = parse_multistatus(xml).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].is_dir);
+        assert_eq!(entries[0].path, "/Docs");
+        assert_eq!(entries[1].name, "report 1.pdf");
+        assert_eq!(entries[1].size, Some(1234));
+        assert_eq!(entries[1].etag.as_deref(), Some("\"abc\""));
+        assert!(!entries[1].is_dir);
+    }
+
+    #[test]
+    fn test_multistatus_reader_matches_whole_parse() {
+        let xml = r#"<?xml version="1.0"?>
+<D:multistatus xmlns:D="DAV:"><D:response><D:href>/Docs/</D:href><D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat></D:response>
+<D:response><D:href>/Docs/a.txt</D:href><D:propstat><D:prop><D:getcontentlength>5</D:getcontentlength></D:prop></D:propstat><D:responsedescription>ok</D:responsedescription></D:response>
+<D:response ><D:href>/Docs/%C3%A9t%C3%A9.txt</D:href><D:propstat><D:prop><D:resourcetype/></D:prop></D:propstat></D:response ></D:multistatus>"#;
+        let whole = parse_multistatus(xml).unwrap();
+        for chunk_len in [1, 7, 64, xml.len()] {
+            let mut reader = MultistatusReader::default();
+            let mut entries = Vec::new();
+            for chunk in xml.as_bytes().chunks(chunk_len) {
+                entries.extend(reader.push(chunk).unwrap());
+            }
+            reader.finish().unwrap();
+            assert_eq!(entries, whole, "chunks of {}", chunk_len);
+        }
+        assert_eq!(whole.len(), 3);
+        assert!(MultistatusReader::default().finish().is_err());
+    }
+
+    #[test]
+    fn test_parse_quota() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/</d:href>
+    <d:propstat><d:prop>
+      <d:quota-used-bytes>1073741824</d:quota-used-bytes>
+      <d:quota-available-bytes>4294967296</d:quota-available-bytes>
+    </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        assert_eq!(parse_quota(xml).unwrap(), Some(DavQuota { used: 1 << 30, available: 1 << 32 }));
+
+        let missing = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/</d:href>
+    <d:propstat><d:prop><d:quota-used-bytes/><d:quota-available-bytes/></d:prop><d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        assert_eq!(parse_quota(missing).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_sha256_checksum() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns">
+  <d:response>
+    <d:href>/a.txt</d:href>
+    <d:propstat><d:prop><oc:checksums><oc:checksum>SHA1:da39a3ee MD5:d41d8cd9 SHA256:E3B0C442</oc:checksum></oc:checksums></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        assert_eq!(parse_sha256_checksum(xml).unwrap().as_deref(), Some("e3b0c442"));
+
+        let missing = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns">
+  <d:response>
+    <d:href>/a.txt</d:href>
+    <d:propstat><d:prop><oc:checksums/></d:prop><d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        assert_eq!(parse_sha256_checksum(missing).unwrap(), None);
+    }
+}
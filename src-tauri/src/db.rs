@@ -0,0 +1,210 @@
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// State database
+// ============================================================================
+//
+// Durable app state lives in `state.db`, a SQLite database under the app
+// data dir: the upload spool's queue, the per-pair sync state, the offline
+// mirror's record of pins and files, open conflicts, the activity
+// history, the recently used files, the media photo backup has uploaded,
+// the last run of each backup job and the files stored under a mapped name.
+// Updates are transactions, so a crash leaves either the old state
+// or the new one rather than a half-written JSON file. Settings, including
+// which sync pairs and pins exist, stay in config.json.
+//
+// The schema is versioned with `PRAGMA user_version`: every entry of
+// `MIGRATIONS` runs once, in order, in its own transaction.
+
+const DB_FILE: &str = "state.db";
+
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE counters (
+        name TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    CREATE TABLE pending_uploads (
+        id INTEGER PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE sync_pair_state (
+        pair_id TEXT PRIMARY KEY,
+        last_synced INTEGER,
+        last_error TEXT
+    );
+    CREATE TABLE sync_records (
+        pair_id TEXT NOT NULL,
+        path TEXT NOT NULL,
+        local_size INTEGER NOT NULL,
+        local_modified INTEGER NOT NULL,
+        remote_size INTEGER,
+        remote_modified TEXT,
+        remote_etag TEXT,
+        PRIMARY KEY (pair_id, path)
+    );
+    CREATE TABLE offline_pins (
+        path TEXT PRIMARY KEY,
+        last_synced INTEGER,
+        error TEXT
+    );
+    CREATE TABLE offline_files (
+        path TEXT PRIMARY KEY,
+        size INTEGER,
+        modified TEXT,
+        etag TEXT
+    );
+    CREATE TABLE conflicts (
+        id INTEGER PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE activity (
+        id INTEGER PRIMARY KEY,
+        data TEXT NOT NULL
+    );",
+    // 2: recently used files
+    "CREATE TABLE recent_files (
+        path TEXT PRIMARY KEY,
+        used_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );",
+    // 3: photo backup
+    "CREATE TABLE backup_hashes (
+        sha256 TEXT PRIMARY KEY,
+        remote_path TEXT NOT NULL,
+        backed_up_at INTEGER NOT NULL
+    );
+    CREATE TABLE backup_local_files (
+        local_path TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        modified INTEGER NOT NULL,
+        sha256 TEXT NOT NULL
+    );",
+    // 4: scheduled backups
+    "CREATE TABLE backup_job_runs (
+        job_id TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );",
+    // 5: files stored under a mapped name
+    "CREATE TABLE renamed_items (
+        source TEXT NOT NULL,
+        local_path TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (source, local_path)
+    );",
+];
+
+/// The connection, opened on first use.
+#[derive(Default)]
+pub struct Database {
+    conn: Mutex<Option<Connection>>,
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Run `f` on the state database, opening and migrating it first if needed.
+pub(crate) fn with<T>(app: &AppHandle, f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T, CommandError> {
+    let db = app.state::<Database>();
+    let mut guard = db.conn.lock().unwrap();
+    if guard.is_none() {
+        *guard = Some(open(app)?);
+    }
+    Ok(f(guard.as_mut().expect("connection was just opened"))?)
+}
+
+/// Like `with`, inside a transaction that is committed when `f` succeeds.
+pub(crate) fn transaction<T>(app: &AppHandle, f: impl FnOnce(&Transaction) -> rusqlite::Result<T>) -> Result<T, CommandError> {
+    with(app, |conn| {
+        let tx = conn.transaction()?;
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    })
+}
+
+fn open(app: &AppHandle) -> Result<Connection, CommandError> {
+    let data_dir = app.path().app_data_dir().map_err(|e| CommandError::IoError(e.to_string()))?;
+    std::fs::create_dir_all(&data_dir)?;
+    let mut conn = Connection::open(data_dir.join(DB_FILE))?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+/// Bring the schema up to date.
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+        log::info!("State database migrated to version {}", i + 1);
+    }
+    Ok(())
+}
+
+pub(crate) fn to_json<T: Serialize>(value: &T) -> rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+pub(crate) fn from_json<T: DeserializeOwned>(json: &str) -> rusqlite::Result<T> {
+    serde_json::from_str(json).map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+/// Last value handed out by the counter `name`, 0 if none was.
+pub(crate) fn counter(conn: &Connection, name: &str) -> rusqlite::Result<u64> {
+    let value: Option<i64> = conn.query_row("SELECT value FROM counters WHERE name = ?1", [name], |row| row.get(0)).optional()?;
+    Ok(value.unwrap_or(0) as u64)
+}
+
+pub(crate) fn set_counter(conn: &Connection, name: &str, value: u64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO counters (name, value) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+        rusqlite::params![name, value as i64],
+    )?;
+    Ok(())
+}
+
+/// A migrated in-memory database, for tests.
+#[cfg(test)]
+pub(crate) fn open_in_memory() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    migrate(&mut conn).unwrap();
+    conn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_run_once() {
+        let mut conn = open_in_memory();
+        let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        migrate(&mut conn).unwrap();
+        let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_counters() {
+        let conn = open_in_memory();
+        assert_eq!(counter(&conn, "conflicts").unwrap(), 0);
+        set_counter(&conn, "conflicts", 3).unwrap();
+        set_counter(&conn, "conflicts", 4).unwrap();
+        assert_eq!(counter(&conn, "conflicts").unwrap(), 4);
+    }
+}
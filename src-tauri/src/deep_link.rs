@@ -0,0 +1,142 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::sidecar::SidecarState;
+
+// ============================================================================
+// Deep links
+// ============================================================================
+//
+// `protondrive-bridge://` links let other apps, desktop file actions and the
+// docs drive the running instance:
+//
+//   protondrive-bridge://mount                      start the bridge and mount
+//   protondrive-bridge://open?path=/Documents       open a folder of the drive
+//   protondrive-bridge://settings                   show the settings
+//
+// On Linux and Windows the system launches the app with the link as its only
+// argument; the single-instance plugin hands it to the running instance and
+// tauri-plugin-deep-link reports it here. Every link raises the main window
+// and is also emitted as `deep-link:open` so the UI can follow it (e.g. to
+// switch to the settings view). Links can come from any web page, so they
+// only trigger actions the user could take with one click anyway.
+
+pub const SCHEME: &str = "protondrive-bridge";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum DeepLinkAction {
+    Mount,
+    Open { path: String },
+    Settings,
+}
+
+/// The action a link asks for, or why it is refused.
+fn parse(url: &Url) -> Result<DeepLinkAction, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("unexpected scheme {}", url.scheme()));
+    }
+    match url.host_str().unwrap_or_default() {
+        "mount" => Ok(DeepLinkAction::Mount),
+        "settings" => Ok(DeepLinkAction::Settings),
+        "open" => {
+            let path = url
+                .query_pairs()
+                .find(|(key, _)| key == "path")
+                .map(|(_, value)| value.into_owned())
+                .unwrap_or_else(|| "/".to_string());
+            if path.split('/').any(|segment| segment == "..") {
+                return Err(format!("invalid path {}", path));
+            }
+            Ok(DeepLinkAction::Open { path: crate::dav::normalize_path(&path) })
+        }
+        other => Err(format!("unknown action {:?}", other)),
+    }
+}
+
+async fn perform(app: AppHandle, action: DeepLinkAction) {
+    match action {
+        DeepLinkAction::Mount => {
+            let args = crate::launch_args::LaunchArgs { mount: true, ..Default::default() };
+            crate::launch_args::apply(app, args).await;
+        }
+        DeepLinkAction::Open { path } => {
+            let opened = crate::sidecar::open_in_files(app.clone(), app.state::<SidecarState>(), None, Some(path.clone())).await;
+            if let Err(e) = opened {
+                log::warn!("Failed to open {} from a link: {}", path, e);
+            }
+        }
+        DeepLinkAction::Settings => {}
+    }
+}
+
+fn handle(app: &AppHandle, url: &Url) {
+    let action = match parse(url) {
+        Ok(action) => action,
+        Err(e) => {
+            log::warn!("Ignoring link {}: {}", url, e);
+            return;
+        }
+    };
+    log::info!("Opening link {}", url);
+    crate::instance::focus_main_window(app);
+    let _ = app.emit("deep-link:open", action.clone());
+    tauri::async_runtime::spawn(perform(app.clone(), action));
+}
+
+/// Register the scheme with the desktop and follow links, including the one
+/// the app was launched with. Called from `setup`.
+pub fn install(app: &AppHandle) {
+    // Installed packages declare the scheme in their desktop entry; this
+    // covers AppImages and development builds
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        log::warn!("Failed to register the {}:// scheme: {}", SCHEME, e);
+    }
+
+    let handle_app = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle(&handle_app, &url);
+        }
+    });
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            handle(app, &url);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(s: &str) -> Result<DeepLinkAction, String> {
+        parse(&Url::parse(s).unwrap())
+    }
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(parse_str("protondrive-bridge://mount"), Ok(DeepLinkAction::Mount));
+        assert_eq!(parse_str("protondrive-bridge://settings/"), Ok(DeepLinkAction::Settings));
+        assert_eq!(
+            parse_str("protondrive-bridge://open?path=/Documents/My%20Files/"),
+            Ok(DeepLinkAction::Open { path: "/Documents/My Files".into() })
+        );
+        assert_eq!(parse_str("protondrive-bridge://open"), Ok(DeepLinkAction::Open { path: "/".into() }));
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_links() {
+        assert!(parse_str("protondrive-bridge://format").is_err());
+        assert!(parse_str("https://mount").is_err());
+        assert!(parse_str("protondrive-bridge://open?path=/a/../../etc").is_err());
+    }
+
+    #[test]
+    fn test_action_serializes_tagged() {
+        let json = serde_json::to_value(DeepLinkAction::Open { path: "/Documents".into() }).unwrap();
+        assert_eq!(json, serde_json::json!({"action": "open", "path": "/Documents"}));
+    }
+}
@@ -0,0 +1,274 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::{CommandError, SidecarState};
+
+// ============================================================================
+// Diagnostics bundle
+// ============================================================================
+//
+// `export_diagnostics` writes a zip users can attach to bug reports: the
+// app and bridge versions, system details (distribution, desktop, GVFS), the
+// current status and mounts, config.json and the tail of the bridge's logs.
+// Values under keys that look like secrets are replaced and the local part
+// of email addresses is masked everywhere, so the bundle can be shared
+// without leaking passwords or the account name. Recent failed operations
+// go into `traces.txt`, each with the bridge's log lines for its trace ID.
+
+/// Lines kept from the end of the newest bridge log.
+const LOG_TAIL_LINES: usize = 2000;
+
+/// Entries kept from the end of the newest error log.
+const ERROR_TAIL_LINES: usize = 100;
+
+/// Prefix of the bridge's daily log files.
+const LOG_PREFIX: &str = "proton-drive-webdav-bridge-";
+
+const REDACTED: &str = "[redacted]";
+
+/// Lowercase fragments of config keys whose values are never exported.
+const SECRET_KEYS: &[&str] = &["password", "secret", "token", "hash", "credential", "privatekey", "apikey"];
+
+/// Where the GVFS daemon lives across distributions.
+const GVFSD_PATHS: &[&str] = &["/usr/libexec/gvfsd", "/usr/lib/gvfs/gvfsd", "/usr/libexec/gvfs/gvfsd", "/usr/lib64/gvfs/gvfsd"];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemInfo {
+    os: &'static str,
+    arch: &'static str,
+    distribution: Option<String>,
+    kernel: Option<String>,
+    desktop: Option<String>,
+    flatpak: bool,
+    gvfs_version: Option<String>,
+    requirements: crate::system_requirements::SystemReport,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    generated_at: u64,
+    versions: crate::versions::Versions,
+    system: SystemInfo,
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEYS.iter().any(|fragment| key.contains(fragment))
+}
+
+fn is_local_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._%+-".contains(c)
+}
+
+/// Replace the local part of email addresses in `text` with its first
+/// character and `***`.
+pub(crate) fn mask_emails(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '@' {
+            let start = out.chars().rev().take_while(|c| is_local_char(*c)).count();
+            let domain: String = chars[i + 1..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == '.' || **c == '-').collect();
+            if start > 0 && domain.contains('.') {
+                let kept: usize = out.len() - out.chars().rev().take(start).map(char::len_utf8).sum::<usize>();
+                let first = out[kept..].chars().next().expect("local part is non-empty");
+                out.truncate(kept);
+                out.push(first);
+                out.push_str("***");
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Redact secret values and mask email addresses in a JSON document.
+fn sanitize(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if is_secret_key(key) && !v.is_null() {
+                    *v = Value::String(REDACTED.to_string());
+                } else {
+                    sanitize(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(sanitize),
+        Value::String(s) => *s = mask_emails(s),
+        _ => {}
+    }
+}
+
+fn sanitized_json(value: impl Serialize) -> String {
+    let mut value = serde_json::to_value(value).unwrap_or(Value::Null);
+    sanitize(&mut value);
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+/// The last `n` lines of a file, with email addresses masked.
+fn tail(path: &Path, n: usize) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let kept = lines[lines.len().saturating_sub(n)..].join("\n");
+    Some(mask_emails(&kept))
+}
+
+/// The most recently written bridge log in `dir`, either the full log or
+/// the error-only one.
+fn newest_log(dir: &Path, errors: bool) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.starts_with(LOG_PREFIX) && name.ends_with(".log") && name.contains("-error-") == errors
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+fn os_release() -> Option<String> {
+    let content = std::fs::read_to_string("/etc/os-release").ok()?;
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+fn gvfs_version() -> Option<String> {
+    let gvfsd = GVFSD_PATHS.iter().find(|p| Path::new(p).exists())?;
+    let output = std::process::Command::new(gvfsd).arg("--version").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.split_whitespace().last().map(str::to_string)
+}
+
+fn system_info() -> SystemInfo {
+    SystemInfo {
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        distribution: os_release(),
+        kernel: std::fs::read_to_string("/proc/sys/kernel/osrelease").ok().map(|s| s.trim().to_string()),
+        desktop: std::env::var("XDG_CURRENT_DESKTOP").ok(),
+        flatpak: crate::sandbox::is_flatpak(),
+        gvfs_version: gvfs_version(),
+        requirements: crate::system_requirements::probe(),
+    }
+}
+
+/// WebDAV and FUSE entries of the kernel mount table.
+fn mount_table() -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    let relevant: Vec<&str> = mounts.lines().filter(|l| l.contains("davfs") || l.contains("fuse") || l.contains("gvfs")).collect();
+    Some(mask_emails(&relevant.join("\n")))
+}
+
+fn write_bundle(path: &Path, files: Vec<(String, String)>) -> Result<(), CommandError> {
+    let zip_error = |e: zip::result::ZipError| CommandError::IoError(e.to_string());
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path)?);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, content) in files {
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    zip.finish().map_err(zip_error)?;
+    Ok(())
+}
+
+/// Write a diagnostics bundle to `path` (a `.zip` file) and return its path.
+#[tauri::command]
+pub async fn export_diagnostics(app: AppHandle, path: String) -> Result<String, CommandError> {
+    let path = PathBuf::from(path);
+    let status = crate::sidecar::get_status(app.clone(), app.state::<SidecarState>()).await?;
+    let main_mount = crate::sidecar::check_mount_status(app.clone(), app.state::<SidecarState>()).await.ok().flatten();
+    let mounts = crate::mounts::list_mounts(app.clone()).await.unwrap_or_default();
+    let summary = Summary {
+        generated_at: crate::sidecar::unix_now(),
+        versions: crate::versions::versions(&app).await,
+        system: tauri::async_runtime::spawn_blocking(system_info).await.map_err(|e| CommandError::Unknown(e.to_string()))?,
+    };
+
+    let mut files = vec![
+        ("summary.json".to_string(), sanitized_json(&summary)),
+        ("status.json".to_string(), sanitized_json(&status)),
+        ("mounts.json".to_string(), sanitized_json(serde_json::json!({ "main": main_mount, "mounts": mounts }))),
+        ("config.json".to_string(), sanitized_json(crate::sidecar::read_config_json().unwrap_or(Value::Null))),
+    ];
+    if let Some(table) = mount_table() {
+        files.push(("mount-table.txt".to_string(), table));
+    }
+    let log_dir = Path::new(&status.log_file).parent().map(Path::to_path_buf);
+    if let Some(dir) = &log_dir {
+        if let Some(log) = newest_log(dir, false).and_then(|p| tail(&p, LOG_TAIL_LINES)) {
+            files.push(("logs/bridge.log".to_string(), log));
+        }
+        if let Some(errors) = newest_log(dir, true).and_then(|p| tail(&p, ERROR_TAIL_LINES)) {
+            files.push(("logs/errors.log".to_string(), errors));
+        }
+    }
+    let failures = app.state::<crate::trace::TraceState>().failures();
+    if !failures.is_empty() {
+        let bridge_log = log_dir
+            .as_deref()
+            .and_then(|dir| newest_log(dir, false))
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .unwrap_or_default();
+        files.push(("traces.txt".to_string(), mask_emails(&crate::trace::report(&failures, &bridge_log))));
+    }
+
+    let target = path.clone();
+    tauri::async_runtime::spawn_blocking(move || write_bundle(&target, files))
+        .await
+        .map_err(|e| CommandError::Unknown(e.to_string()))??;
+    log::info!("Wrote diagnostics bundle to {}", path.display());
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_emails() {
+        assert_eq!(mask_emails("Logged in as alice.smith@proton.me"), "Logged in as a***@proton.me");
+        assert_eq!(mask_emails("dav://bridge@127.0.0.1:8080"), "dav://b***@127.0.0.1:8080");
+        assert_eq!(mask_emails("no address @ here"), "no address @ here");
+        assert_eq!(mask_emails("é user@example.org é"), "é u***@example.org é");
+    }
+
+    #[test]
+    fn test_sanitize_redacts_secrets() {
+        let mut config = serde_json::json!({
+            "username": "alice@proton.me",
+            "webdav": {"port": 8080, "passwordHash": "5e88", "requireAuth": true, "certPath": "/etc/cert.pem"},
+            "networkSharing": {"password": "hunter22", "token": null},
+        });
+        sanitize(&mut config);
+        assert_eq!(config["username"], "a***@proton.me");
+        assert_eq!(config["webdav"]["passwordHash"], REDACTED);
+        assert_eq!(config["webdav"]["requireAuth"], true);
+        assert_eq!(config["webdav"]["certPath"], "/etc/cert.pem");
+        assert_eq!(config["networkSharing"]["password"], REDACTED);
+        assert_eq!(config["networkSharing"]["token"], Value::Null);
+    }
+
+    #[test]
+    fn test_newest_log_and_tail() {
+        let dir = std::env::temp_dir().join(format!("diagnostics-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join(format!("{}2024-01-01.log", LOG_PREFIX));
+        let errors = dir.join(format!("{}error-2024-01-01.log", LOG_PREFIX));
+        std::fs::write(&log, "one\ntwo\nthree by bob@example.com\n").unwrap();
+        std::fs::write(&errors, "boom\n").unwrap();
+
+        assert_eq!(newest_log(&dir, false), Some(log.clone()));
+        assert_eq!(newest_log(&dir, true), Some(errors));
+        assert_eq!(tail(&log, 2).unwrap(), "two\nthree by b***@example.com");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,342 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+
+use crate::conflicts::{conflicted_copy_name, Conflict, ConflictRegistry, ConflictSource, Resolution};
+use crate::dav::{join_path, normalize_path, DavClient, DavEntry};
+use crate::ignore::{IgnoreRules, IgnoreScope};
+use crate::name_mapping::{self, NameMapper};
+use crate::read_only::ReadOnlyState;
+use crate::remote::FileTransferResult;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+use crate::special_files::{self, LocalKind, SkippedItem};
+
+// ============================================================================
+// Drop folder
+// ============================================================================
+//
+// A local folder whose new and changed files are uploaded automatically to a
+// remote folder, keeping the layout of subfolders. The folder is watched with
+// inotify/FSEvents through `notify`; a file is uploaded once it has been
+// quiet for `debounceSeconds`, so files still being written aren't sent
+// half-done. Files matching the drop folder's ignore rules (see `ignore`) or
+// its own `exclude` patterns are left out, and symlinks and special files
+// are handled by `special_files`; local names encoded by `name_mapping`
+// are uploaded under the names they stand for. Uploads wait while the
+// bridge is down, travel mode is on or the share is read-only. Results are
+// reported as `drop-folder:uploaded`, `drop-folder:failed` and
+// `drop-folder:skipped` events.
+//
+// A remote file is only replaced if it is still the version the drop folder
+// uploaded last; otherwise the upload is recorded as a conflict.
+
+const CONFIG_KEY: &str = "dropFolder";
+
+/// How often pending files are checked for being quiet long enough.
+const DEBOUNCE_TICK: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DropFolderSettings {
+    /// Watched local folder; `None` turns the drop folder off
+    pub path: Option<String>,
+    pub remote_dest: String,
+    pub debounce_seconds: u64,
+    /// Ignore patterns applied after the `dropFolder` ignore scope
+    pub exclude: Vec<String>,
+}
+
+impl Default for DropFolderSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            remote_dest: "/".into(),
+            debounce_seconds: 5,
+            exclude: vec![".*".into(), "*.part".into(), "*.tmp".into(), "*~".into()],
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DropFolderUpload {
+    pub local_path: String,
+    pub remote_path: String,
+    pub bytes: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct DropFolderState {
+    /// The running watcher; dropping it stops the upload task too
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    /// ETag of each remote file as last uploaded, by remote path
+    uploaded: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl DropFolderState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// `local` relative to `root` with `/` separators, if it is inside it.
+fn relative(root: &Path, local: &Path) -> Option<String> {
+    let rel = local.strip_prefix(root).ok()?;
+    let parts: Vec<String> = rel.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Whether uploads have to wait.
+fn paused(app: &AppHandle) -> bool {
+    app.state::<ReadOnlyState>().status().effective
+        || app.state::<crate::power::PowerState>().is_suspended()
+        || !app.state::<crate::network::NetworkState>().is_online()
+}
+
+/// Upload `local` to `remote` and remember the version written.
+async fn put(app: &AppHandle, client: &DavClient, local: &Path, remote: &str) -> Result<FileTransferResult, CommandError> {
+    let result = special_files::upload(app, client, local, remote, true).await?;
+    let etag = client.stat(&result.path).await?.and_then(|e| e.etag);
+    app.state::<DropFolderState>().uploaded.lock().unwrap().insert(result.path.clone(), etag);
+    Ok(result)
+}
+
+/// Whether `existing` is a remote version the drop folder didn't write.
+fn changed_remotely(app: &AppHandle, existing: &DavEntry) -> bool {
+    match app.state::<DropFolderState>().uploaded.lock().unwrap().get(&existing.path) {
+        None => true,
+        Some(Some(etag)) => existing.etag.as_ref() != Some(etag),
+        // No ETag to compare; trust our own upload
+        Some(None) => false,
+    }
+}
+
+/// Upload a changed file to `rel` (in remote names) under the destination,
+/// or record a conflict (and return `None`) when the remote copy changed
+/// since the last upload.
+async fn upload(app: &AppHandle, settings: &DropFolderSettings, root: &Path, local: &Path, rel: &str) -> Result<Option<DropFolderUpload>, CommandError> {
+    let client = DavClient::for_app(app)?;
+    let remote = join_path(&settings.remote_dest, rel);
+    if let Some(existing) = client.stat(&remote).await? {
+        if !existing.is_dir && changed_remotely(app, &existing) {
+            let metadata = tokio::fs::metadata(local).await?;
+            let conflict = Conflict {
+                id: 0,
+                source: ConflictSource::DropFolder { path: rel.to_string() },
+                local_path: local.display().to_string(),
+                remote_path: remote,
+                local_size: Some(metadata.len()),
+                local_modified: metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs()),
+                remote_size: existing.size,
+                remote_modified: existing.modified,
+                detected_at: 0,
+            };
+            app.state::<ConflictRegistry>().record(app, conflict);
+            return Ok(None);
+        }
+    }
+    let segments: Vec<&str> = rel.split('/').collect();
+    let mut dir = settings.remote_dest.clone();
+    for segment in &segments[..segments.len() - 1] {
+        dir = join_path(&dir, segment);
+        client.mkcol(&dir).await?;
+    }
+    let result = put(app, &client, local, &remote).await?;
+    log::info!("Drop folder: uploaded {} to {}", local.strip_prefix(root).unwrap_or(local).display(), result.path);
+    Ok(Some(DropFolderUpload { local_path: local.display().to_string(), remote_path: result.path, bytes: Some(result.bytes), error: None }))
+}
+
+/// Carry out the user's resolution of a drop folder conflict.
+pub(crate) async fn resolve(app: &AppHandle, conflict: &Conflict, rel: &str, resolution: Resolution) -> Result<(), CommandError> {
+    let client = DavClient::for_app(app)?;
+    let local = Path::new(&conflict.local_path);
+    match resolution {
+        Resolution::KeepLocal => {
+            put(app, &client, local, &conflict.remote_path).await?;
+        }
+        Resolution::KeepRemote => {
+            let entry = client
+                .stat(&conflict.remote_path)
+                .await?
+                .ok_or_else(|| CommandError::RemotePathNotFound(conflict.remote_path.clone()))?;
+            crate::remote::download_to_path(app, &client, &entry.path, entry.size, local).await?;
+            app.state::<DropFolderState>().uploaded.lock().unwrap().insert(entry.path, entry.etag);
+        }
+        Resolution::KeepBoth => {
+            let copy = conflicted_copy_name(&conflict.remote_path, crate::sidecar::unix_now());
+            put(app, &client, local, &copy).await?;
+        }
+    }
+    log::info!("Drop folder: resolved conflict on {}", rel);
+    Ok(())
+}
+
+/// Collect changed paths from the watcher and upload each once it is quiet.
+async fn run(app: AppHandle, settings: DropFolderSettings, root: PathBuf, mut rx: mpsc::UnboundedReceiver<PathBuf>) {
+    let quiet = Duration::from_secs(settings.debounce_seconds);
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+    loop {
+        match tokio::time::timeout(DEBOUNCE_TICK, rx.recv()).await {
+            Ok(Some(path)) => {
+                pending.insert(path, Instant::now());
+                continue;
+            }
+            // The watcher was dropped: the drop folder was changed or turned off
+            Ok(None) => break,
+            Err(_) => {}
+        }
+        if pending.is_empty() || paused(&app) {
+            continue;
+        }
+        let ready: Vec<PathBuf> = pending.iter().filter(|(_, t)| t.elapsed() >= quiet).map(|(p, _)| p.clone()).collect();
+        let ignore = crate::ignore::rules_with(IgnoreScope::DropFolder, &settings.exclude);
+        let special = special_files::settings();
+        let names = NameMapper::current();
+        for local in ready {
+            pending.remove(&local);
+            let Some(rel) = relative(&root, &local) else { continue };
+            // Gone again, or left out on purpose
+            if ignore.is_ignored(&rel) || local.symlink_metadata().is_err() {
+                continue;
+            }
+            match special_files::classify(&local, &special) {
+                Ok(LocalKind::File | LocalKind::Link) => {}
+                Ok(LocalKind::Dir) => continue,
+                Err(reason) => {
+                    log::info!("Drop folder: skipping {} ({:?})", local.display(), reason);
+                    let _ = app.emit("drop-folder:skipped", SkippedItem { path: local.display().to_string(), reason });
+                    continue;
+                }
+            }
+            let remote_rel = names.remote_rel(&rel);
+            match upload(&app, &settings, &root, &local, &remote_rel).await {
+                Ok(Some(uploaded)) => {
+                    if remote_rel != rel {
+                        let item = name_mapping::renamed("dropFolder", uploaded.local_path.clone(), uploaded.remote_path.clone());
+                        name_mapping::record(&app, item);
+                    }
+                    let _ = app.emit("drop-folder:uploaded", uploaded);
+                }
+                Ok(None) => {}
+                Err(CommandError::ServerNotRunning) => {
+                    // Try again once the bridge is back
+                    pending.insert(local, Instant::now());
+                }
+                Err(e) => {
+                    log::warn!("Drop folder: upload of {} failed: {}", local.display(), e);
+                    let failed = DropFolderUpload {
+                        local_path: local.display().to_string(),
+                        remote_path: join_path(&settings.remote_dest, &remote_rel),
+                        bytes: None,
+                        error: Some(e.to_string()),
+                    };
+                    let _ = app.emit("drop-folder:failed", failed);
+                }
+            }
+        }
+    }
+}
+
+/// Watch the configured folder, replacing the previous watcher. Turns the
+/// drop folder off when no folder is set.
+fn start(app: &AppHandle, settings: &DropFolderSettings) -> Result<(), CommandError> {
+    let state = app.state::<DropFolderState>();
+    let Some(path) = settings.path.as_deref() else {
+        *state.watcher.lock().unwrap() = None;
+        return Ok(());
+    };
+    let root = PathBuf::from(path);
+    if !root.is_dir() {
+        return Err(CommandError::InvalidDropFolder(format!("{} is not a folder", path)));
+    }
+    IgnoreRules::parse(&settings.exclude)?;
+    let mut current = state.watcher.lock().unwrap();
+    *current = None;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+        Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+            let unique: HashSet<PathBuf> = event.paths.into_iter().collect();
+            unique.into_iter().for_each(|p| {
+                let _ = tx.send(p);
+            });
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Drop folder watch error: {}", e),
+    })
+    .map_err(|e| CommandError::InvalidDropFolder(e.to_string()))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| CommandError::InvalidDropFolder(format!("Cannot watch {}: {}", root.display(), e)))?;
+    *current = Some(watcher);
+
+    log::info!("Drop folder: watching {} -> {}", root.display(), settings.remote_dest);
+    tauri::async_runtime::spawn(run(app.clone(), settings.clone(), root, rx));
+    Ok(())
+}
+
+/// Start the configured drop folder; called once from `setup`.
+pub fn spawn(app: &AppHandle) {
+    let settings: DropFolderSettings = read_config_section(CONFIG_KEY);
+    if let Err(e) = start(app, &settings) {
+        log::warn!("Drop folder not started: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_drop_folder() -> Result<DropFolderSettings, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+/// Watch `path` and upload into `remote_dest`; a `null` path turns the drop
+/// folder off. `exclude` and `debounce_seconds` keep their current values
+/// when omitted.
+#[tauri::command]
+pub async fn set_drop_folder(
+    app: AppHandle,
+    path: Option<String>,
+    remote_dest: String,
+    exclude: Option<Vec<String>>,
+    debounce_seconds: Option<u64>,
+) -> Result<DropFolderSettings, CommandError> {
+    let current: DropFolderSettings = read_config_section(CONFIG_KEY);
+    let settings = DropFolderSettings {
+        path,
+        remote_dest: normalize_path(&remote_dest),
+        exclude: exclude.unwrap_or(current.exclude),
+        debounce_seconds: debounce_seconds.unwrap_or(current.debounce_seconds),
+    };
+    start(&app, &settings)?;
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_exclusions() {
+        let exclusions = IgnoreRules::parse(&DropFolderSettings::default().exclude).unwrap();
+        assert!(exclusions.is_ignored(".DS_Store"));
+        assert!(exclusions.is_ignored("photos/.hidden/a.jpg"));
+        assert!(exclusions.is_ignored("video.mp4.part"));
+        assert!(exclusions.is_ignored("notes.txt~"));
+        assert!(!exclusions.is_ignored("photos/a.jpg"));
+        assert!(IgnoreRules::parse(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let root = Path::new("/home/u/Drop");
+        assert_eq!(relative(root, Path::new("/home/u/Drop/a/b.txt")).as_deref(), Some("a/b.txt"));
+        assert_eq!(relative(root, Path::new("/home/u/Drop")), None);
+        assert_eq!(relative(root, Path::new("/elsewhere/x")), None);
+    }
+}
@@ -0,0 +1,304 @@
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Feature flags
+// ============================================================================
+//
+// Experimental subsystems are gated behind flags so they can ship disabled
+// and be opted into as previews. A flag's value is resolved in order from:
+// the user's override (config.json `featureFlags`), the remote defaults
+// published by the maintainers, and the built-in default.
+//
+// Remote defaults are only fetched when the build sets both
+// `PDWB_FEATURE_FLAGS_URL` and `PDWB_FEATURE_FLAGS_PUBLIC_KEY`. The document
+// must carry an Ed25519 signature from that key; the last verified copy is
+// cached next to config.json and re-verified on every load.
+
+const CONFIG_KEY: &str = "featureFlags";
+
+const REMOTE_DEFAULTS_FILE: &str = "feature-defaults.json";
+
+const REMOTE_DEFAULTS_URL: Option<&str> = option_env!("PDWB_FEATURE_FLAGS_URL");
+
+/// Hex-encoded Ed25519 public key the remote defaults must be signed with.
+const REMOTE_DEFAULTS_PUBLIC_KEY: Option<&str> = option_env!("PDWB_FEATURE_FLAGS_PUBLIC_KEY");
+
+const REMOTE_FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum FeatureFlag {
+    /// WebDAV server implemented in the backend instead of the sidecar
+    NativeServer,
+    /// Mount through FUSE instead of GVFS/davfs
+    FuseBackend,
+    /// Two-way folder sync
+    SyncEngine,
+}
+
+impl FeatureFlag {
+    pub const ALL: [FeatureFlag; 3] = [FeatureFlag::NativeServer, FeatureFlag::FuseBackend, FeatureFlag::SyncEngine];
+
+    fn description(self) -> &'static str {
+        match self {
+            FeatureFlag::NativeServer => "Serve WebDAV from the app itself instead of the bundled bridge",
+            FeatureFlag::FuseBackend => "Mount Proton Drive with FUSE instead of the desktop's WebDAV support",
+            FeatureFlag::SyncEngine => "Keep local folders in two-way sync with Proton Drive",
+        }
+    }
+
+    fn default_enabled(self) -> bool {
+        false
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FlagSource {
+    BuiltIn,
+    Remote,
+    User,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlagInfo {
+    pub name: FeatureFlag,
+    pub description: String,
+    pub enabled: bool,
+    /// Where `enabled` came from
+    pub source: FlagSource,
+    /// The value that applies when the user override is cleared
+    pub default_enabled: bool,
+}
+
+/// Signed remote defaults as published and cached.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct SignedDefaults {
+    /// Base64 of the JSON `RemoteDefaults` document
+    payload: String,
+    /// Base64 Ed25519 signature over the decoded payload bytes
+    signature: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct RemoteDefaults {
+    /// Keyed by flag name; names this build doesn't know are ignored
+    #[serde(default)]
+    flags: HashMap<String, bool>,
+}
+
+#[derive(Default)]
+pub struct FeatureFlagState {
+    overrides: Mutex<HashMap<FeatureFlag, bool>>,
+    remote: Mutex<HashMap<FeatureFlag, bool>>,
+}
+
+fn parse_flag(name: &str) -> Option<FeatureFlag> {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+}
+
+fn load_overrides() -> HashMap<FeatureFlag, bool> {
+    let overrides: HashMap<String, bool> = read_config_section(CONFIG_KEY);
+    overrides.iter().filter_map(|(name, enabled)| Some((parse_flag(name)?, *enabled))).collect()
+}
+
+/// Check the signature on `signed` and return the flags it sets.
+fn verify_defaults(signed: &SignedDefaults, key: &VerifyingKey) -> Result<HashMap<FeatureFlag, bool>, CommandError> {
+    let invalid = |what: &str| CommandError::Unknown(format!("Invalid remote feature defaults: {}", what));
+    let engine = base64::engine::general_purpose::STANDARD;
+    let payload = engine.decode(&signed.payload).map_err(|_| invalid("payload is not base64"))?;
+    let signature = engine.decode(&signed.signature).map_err(|_| invalid("signature is not base64"))?;
+    let signature = Signature::from_slice(&signature).map_err(|_| invalid("malformed signature"))?;
+    key.verify_strict(&payload, &signature).map_err(|_| invalid("signature mismatch"))?;
+
+    let defaults: RemoteDefaults = serde_json::from_slice(&payload).map_err(|e| invalid(&e.to_string()))?;
+    Ok(defaults.flags.iter().filter_map(|(name, enabled)| Some((parse_flag(name)?, *enabled))).collect())
+}
+
+fn remote_public_key() -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(REMOTE_DEFAULTS_PUBLIC_KEY?).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+fn remote_defaults_path() -> Result<PathBuf, CommandError> {
+    let config = crate::sidecar::get_config_file_path()?;
+    Ok(config.with_file_name(REMOTE_DEFAULTS_FILE))
+}
+
+fn load_cached_defaults(key: &VerifyingKey) -> HashMap<FeatureFlag, bool> {
+    let cached = remote_defaults_path()
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str::<SignedDefaults>(&s).ok());
+    match cached.map(|signed| verify_defaults(&signed, key)) {
+        Some(Ok(flags)) => flags,
+        Some(Err(e)) => {
+            log::warn!("Ignoring cached feature defaults: {}", e);
+            HashMap::new()
+        }
+        None => HashMap::new(),
+    }
+}
+
+impl FeatureFlagState {
+    pub fn from_config() -> Self {
+        Self {
+            overrides: Mutex::new(load_overrides()),
+            remote: Mutex::new(remote_public_key().map(|key| load_cached_defaults(&key)).unwrap_or_default()),
+        }
+    }
+
+    /// Pick up the user's overrides after config.json was replaced.
+    pub fn reload_settings(&self) {
+        *self.overrides.lock().unwrap() = load_overrides();
+    }
+
+    fn resolve(&self, flag: FeatureFlag) -> (bool, FlagSource) {
+        if let Some(enabled) = self.overrides.lock().unwrap().get(&flag) {
+            return (*enabled, FlagSource::User);
+        }
+        match self.remote.lock().unwrap().get(&flag) {
+            Some(enabled) => (*enabled, FlagSource::Remote),
+            None => (flag.default_enabled(), FlagSource::BuiltIn),
+        }
+    }
+
+    /// Whether an experimental subsystem may run.
+    pub fn is_enabled(&self, flag: FeatureFlag) -> bool {
+        self.resolve(flag).0
+    }
+
+    fn list(&self) -> Vec<FeatureFlagInfo> {
+        let remote = self.remote.lock().unwrap().clone();
+        FeatureFlag::ALL
+            .iter()
+            .map(|&flag| {
+                let (enabled, source) = self.resolve(flag);
+                FeatureFlagInfo {
+                    name: flag,
+                    description: flag.description().to_string(),
+                    enabled,
+                    source,
+                    default_enabled: remote.get(&flag).copied().unwrap_or(flag.default_enabled()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Fetch the published defaults, verify them and cache them for the next
+/// launch. Does nothing unless the build configured a URL and key.
+pub async fn refresh_remote_defaults(app: AppHandle) {
+    let (Some(url), Some(key)) = (REMOTE_DEFAULTS_URL, remote_public_key()) else {
+        return;
+    };
+    let fetched = async {
+        let client = reqwest::Client::builder()
+            .timeout(REMOTE_FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| CommandError::Unknown(e.to_string()))?;
+        let signed: SignedDefaults = client
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| CommandError::Unknown(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| CommandError::Unknown(e.to_string()))?;
+        let flags = verify_defaults(&signed, &key)?;
+        let json = serde_json::to_string_pretty(&signed).map_err(|e| CommandError::Unknown(e.to_string()))?;
+        std::fs::write(remote_defaults_path()?, json)?;
+        Ok::<_, CommandError>(flags)
+    };
+
+    match fetched.await {
+        Ok(flags) => {
+            let state = app.state::<FeatureFlagState>();
+            *state.remote.lock().unwrap() = flags;
+            let _ = app.emit("feature-flags:changed", state.list());
+        }
+        Err(e) => log::warn!("Failed to refresh remote feature defaults: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn list_feature_flags(state: State<'_, FeatureFlagState>) -> Result<Vec<FeatureFlagInfo>, CommandError> {
+    Ok(state.list())
+}
+
+/// Override a flag. Passing `None` clears the override so the remote or
+/// built-in default applies again.
+#[tauri::command]
+pub async fn set_feature_flag(
+    app: AppHandle,
+    state: State<'_, FeatureFlagState>,
+    name: FeatureFlag,
+    enabled: Option<bool>,
+) -> Result<Vec<FeatureFlagInfo>, CommandError> {
+    let overrides = {
+        let mut overrides = state.overrides.lock().unwrap();
+        match enabled {
+            Some(enabled) => overrides.insert(name, enabled),
+            None => overrides.remove(&name),
+        };
+        overrides.clone()
+    };
+    write_config_section(CONFIG_KEY, &overrides)?;
+    log::info!("Feature flag {:?} set to {:?}", name, enabled);
+
+    let flags = state.list();
+    let _ = app.emit("feature-flags:changed", flags.clone());
+    Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn sign(key: &SigningKey, payload: &str) -> SignedDefaults {
+        let engine = base64::engine::general_purpose::STANDARD;
+        SignedDefaults {
+            payload: engine.encode(payload),
+            signature: engine.encode(key.sign(payload.as_bytes()).to_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_verify_defaults_accepts_signed_payload() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let signed = sign(&key, r#"{"flags":{"syncEngine":true,"somethingNew":true}}"#);
+        let flags = verify_defaults(&signed, &key.verifying_key()).unwrap();
+        assert_eq!(flags.get(&FeatureFlag::SyncEngine), Some(&true));
+        assert_eq!(flags.len(), 1);
+    }
+
+    #[test]
+    fn test_verify_defaults_rejects_other_key() {
+        let signed = sign(&SigningKey::from_bytes(&[7u8; 32]), r#"{"flags":{"syncEngine":true}}"#);
+        let other = SigningKey::from_bytes(&[8u8; 32]).verifying_key();
+        assert!(verify_defaults(&signed, &other).is_err());
+    }
+
+    #[test]
+    fn test_resolution_order() {
+        let state = FeatureFlagState::default();
+        assert_eq!(state.resolve(FeatureFlag::FuseBackend), (false, FlagSource::BuiltIn));
+        state.remote.lock().unwrap().insert(FeatureFlag::FuseBackend, true);
+        assert_eq!(state.resolve(FeatureFlag::FuseBackend), (true, FlagSource::Remote));
+        state.overrides.lock().unwrap().insert(FeatureFlag::FuseBackend, false);
+        assert_eq!(state.resolve(FeatureFlag::FuseBackend), (false, FlagSource::User));
+        assert!(state.list().iter().any(|f| f.name == FeatureFlag::FuseBackend && f.default_enabled));
+    }
+}
@@ -0,0 +1,225 @@
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Opening locations in the file manager
+// ============================================================================
+//
+// Handing a dav:// location to the default opener works on GNOME, but on KDE
+// and XFCE it regularly ends up in a web browser. On Linux outside Flatpak,
+// `open` therefore tries the ways of reaching a file manager one after the
+// other until one works:
+//
+// 1. the FileManager1 D-Bus service, which most file managers implement;
+// 2. `xdg-open` on the local directory GVFS exposes for a mounted location,
+//    which no desktop mistakes for a web page;
+// 3. `gio open`, which resolves dav:// through GVFS's own handlers;
+// 4. a file manager found on `PATH`, the desktop's own first.
+//
+// Inside Flatpak only FileManager1 and the OpenURI portal are reachable, and
+// elsewhere the opener plugin is used. The caller gets back which way worked
+// and why the earlier ones did not, for diagnostics.
+
+/// How a location was opened.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "method")]
+pub enum OpenMethod {
+    FileManager1,
+    /// `xdg-open` on the local directory `path`
+    XdgOpen { path: String },
+    GioOpen,
+    /// A file manager started directly
+    Program { program: String },
+    /// The desktop portal's OpenURI
+    Portal,
+    /// The opener plugin, outside Linux or for web URLs
+    Opener,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenReport {
+    pub location: String,
+    #[serde(flatten)]
+    pub method: OpenMethod,
+    /// Why the methods tried before `method` did not work
+    pub skipped: Vec<String>,
+}
+
+/// File managers tried by name, in order, when nothing else worked.
+const FILE_MANAGERS: &[&str] = &["nautilus", "dolphin", "thunar", "nemo", "caja", "pcmanfm-qt", "pcmanfm"];
+
+/// The file manager of a desktop named in `XDG_CURRENT_DESKTOP`.
+fn desktop_file_manager(desktop: &str) -> Option<&'static str> {
+    match desktop.to_ascii_lowercase().as_str() {
+        "gnome" | "unity" | "pantheon" | "budgie" => Some("nautilus"),
+        "kde" => Some("dolphin"),
+        "xfce" => Some("thunar"),
+        "x-cinnamon" | "cinnamon" => Some("nemo"),
+        "mate" => Some("caja"),
+        "lxqt" => Some("pcmanfm-qt"),
+        "lxde" => Some("pcmanfm"),
+        _ => None,
+    }
+}
+
+/// `FILE_MANAGERS`, with the ones belonging to the desktops listed in
+/// `current_desktop` (colon-separated) first.
+fn file_manager_candidates(current_desktop: Option<&str>) -> Vec<&'static str> {
+    let mut candidates: Vec<&'static str> =
+        current_desktop.unwrap_or("").split(':').filter_map(desktop_file_manager).collect();
+    for program in FILE_MANAGERS {
+        if !candidates.contains(program) {
+            candidates.push(program);
+        }
+    }
+    candidates
+}
+
+/// What to pass `program` for `uri`: the local directory if there is one,
+/// else the location in a scheme the program understands. Dolphin goes
+/// through KIO, which calls WebDAV `webdav://`.
+fn program_argument(program: &str, uri: &str, local_path: Option<&str>) -> String {
+    if let Some(path) = local_path {
+        return path.to_string();
+    }
+    if program == "dolphin" {
+        if let Some(rest) = uri.strip_prefix("davs://") {
+            return format!("webdavs://{}", rest);
+        }
+        if let Some(rest) = uri.strip_prefix("dav://") {
+            return format!("webdav://{}", rest);
+        }
+    }
+    uri.to_string()
+}
+
+/// Run a launcher that exits once it has handed the location on.
+#[cfg(target_os = "linux")]
+fn run_launcher(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = std::process::Command::new(program).args(args).status().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {}", status))
+    }
+}
+
+/// The local directory of `uri`, when it is a path or a mounted GVFS
+/// location.
+#[cfg(target_os = "linux")]
+fn local_path(uri: &str) -> Option<String> {
+    use gio::prelude::*;
+
+    if uri.starts_with('/') {
+        return Some(uri.to_string());
+    }
+    let path = gio::File::for_uri(uri).path()?;
+    path.exists().then(|| path.display().to_string())
+}
+
+/// Work through the fallback chain for `uri`. Blocks.
+#[cfg(target_os = "linux")]
+fn open_with_fallbacks(uri: &str) -> Result<(OpenMethod, Vec<String>), CommandError> {
+    use crate::system_requirements::find_in_path;
+
+    let mut skipped = Vec::new();
+    match crate::sandbox::show_folders(uri) {
+        Ok(()) => return Ok((OpenMethod::FileManager1, skipped)),
+        Err(e) => skipped.push(format!("FileManager1: {}", e)),
+    }
+
+    let local = local_path(uri);
+    match (&local, find_in_path("xdg-open")) {
+        (Some(path), Some(_)) => match run_launcher("xdg-open", &[path]) {
+            Ok(()) => return Ok((OpenMethod::XdgOpen { path: path.clone() }, skipped)),
+            Err(e) => skipped.push(format!("xdg-open: {}", e)),
+        },
+        (None, _) => skipped.push("xdg-open: no local directory for the location".into()),
+        (_, None) => skipped.push("xdg-open: not installed".into()),
+    }
+
+    if find_in_path("gio").is_some() {
+        match run_launcher("gio", &["open", uri]) {
+            Ok(()) => return Ok((OpenMethod::GioOpen, skipped)),
+            Err(e) => skipped.push(format!("gio open: {}", e)),
+        }
+    } else {
+        skipped.push("gio open: not installed".into());
+    }
+
+    let desktop = std::env::var("XDG_CURRENT_DESKTOP").ok();
+    for program in file_manager_candidates(desktop.as_deref()) {
+        let Some(path) = find_in_path(program) else {
+            continue;
+        };
+        let argument = program_argument(program, uri, local.as_deref());
+        match std::process::Command::new(path).arg(argument).spawn() {
+            Ok(_) => return Ok((OpenMethod::Program { program: program.to_string() }, skipped)),
+            Err(e) => skipped.push(format!("{}: {}", program, e)),
+        }
+    }
+    Err(CommandError::NoFileManager(format!("{} ({})", uri, skipped.join("; "))))
+}
+
+/// Open `uri`, a local path or a dav:// location, in the file manager.
+pub(crate) async fn open(app: &AppHandle, uri: String) -> Result<OpenReport, CommandError> {
+    let location = uri.strip_prefix("file://").map(str::to_string).unwrap_or(uri);
+
+    #[cfg(target_os = "linux")]
+    if crate::sidecar::should_open_with_path(&location) {
+        let target = location.clone();
+        let flatpak = crate::sandbox::is_flatpak();
+        let opened = tauri::async_runtime::spawn_blocking(move || {
+            if !flatpak {
+                return open_with_fallbacks(&target);
+            }
+            // Inside Flatpak the host's file manager is reached through
+            // D-Bus and the desktop portal only
+            match crate::sandbox::show_folders(&target) {
+                Ok(()) => Ok((OpenMethod::FileManager1, Vec::new())),
+                Err(e) => crate::sandbox::portal_open_uri(&target).map(|()| (OpenMethod::Portal, vec![format!("FileManager1: {}", e)])),
+            }
+        })
+        .await
+        .map_err(|e| CommandError::Unknown(e.to_string()))?;
+        let (method, skipped) = opened?;
+        log::info!("Opened {} with {:?}", location, method);
+        return Ok(OpenReport { location, method, skipped });
+    }
+
+    let opener = app.opener();
+    crate::sidecar::open_uri_with(
+        &location,
+        |p: &str| opener.open_path(p, None::<&str>).map_err(|e| CommandError::Unknown(e.to_string())),
+        |u: &str| opener.open_url(u, None::<&str>).map_err(|e| CommandError::Unknown(e.to_string())),
+    )?;
+    Ok(OpenReport { location, method: OpenMethod::Opener, skipped: Vec::new() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_file_manager_comes_first() {
+        let kde = file_manager_candidates(Some("KDE"));
+        assert_eq!(kde[0], "dolphin");
+        assert_eq!(kde.len(), FILE_MANAGERS.len());
+        assert_eq!(file_manager_candidates(Some("ubuntu:GNOME"))[0], "nautilus");
+        assert_eq!(file_manager_candidates(Some("XFCE"))[..2], ["thunar", "nautilus"]);
+        assert_eq!(file_manager_candidates(None), FILE_MANAGERS);
+    }
+
+    #[test]
+    fn test_program_argument_prefers_local_path() {
+        let uri = "dav://localhost:8080/Docs";
+        assert_eq!(program_argument("thunar", uri, Some("/run/user/1000/gvfs/x")), "/run/user/1000/gvfs/x");
+        assert_eq!(program_argument("thunar", uri, None), uri);
+        assert_eq!(program_argument("dolphin", uri, None), "webdav://localhost:8080/Docs");
+        assert_eq!(program_argument("dolphin", "davs://localhost:8443/", None), "webdavs://localhost:8443/");
+    }
+}
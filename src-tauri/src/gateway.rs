@@ -0,0 +1,661 @@
+use bytes::Bytes;
+use futures_util::TryStreamExt;
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full, StreamBody};
+use hyper::body::{Body as _, Frame, Incoming};
+use hyper::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, HOST, RETRY_AFTER, STRICT_TRANSPORT_SECURITY, WWW_AUTHENTICATE,
+};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+use crate::activity::ActivityFeed;
+use crate::auth_guard::AuthGuardState;
+use crate::bandwidth::{BandwidthState, ThrottledStream};
+use crate::cache::{CachedResponse, MetadataCache, MAX_CACHED_RESPONSE, MAX_REQUEST_BODY};
+use crate::dav::parse_multistatus;
+use crate::metrics::MetricsState;
+use crate::network_sharing::{parse_basic_auth, NetworkSharingState};
+use crate::read_only::ReadOnlyState;
+use crate::readahead::ReadaheadState;
+use crate::sidecar::CommandError;
+use crate::tls::TlsState;
+use crate::transfers::{MeteredStream, TransferDirection, TransferState};
+use crate::upload_spool::UploadSpool;
+use crate::webdav_auth::WebdavAuthState;
+
+// ============================================================================
+// Local WebDAV gateway
+// ============================================================================
+//
+// When the GUI spawns the sidecar, the sidecar listens on a private loopback
+// port (or a Unix socket, see `transport`) and this gateway owns the
+// user-facing port. Every request is forwarded unchanged, which gives the
+// backend a single place to observe and shape WebDAV traffic (transfer
+// progress, bandwidth limits, TLS, metadata caching, read-only mode,
+// per-application access) without any support from the sidecar. It may
+// listen on several addresses at once when LAN sharing is enabled (see
+// `network_sharing`). The one header it rewrites is `Authorization`, when
+// WebDAV authentication is on (see `webdav_auth`).
+
+pub type GatewayBody = UnsyncBoxBody<Bytes, std::io::Error>;
+
+/// Time allowed for a client to complete the TLS handshake.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sent with 401 responses to clients that must log in (see `network_sharing`).
+const AUTH_CHALLENGE: &str = "Basic realm=\"Proton Drive\", charset=\"UTF-8\"";
+
+/// Headers that only apply to a single connection and must not be forwarded.
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Where the sidecar behind the gateway listens.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Upstream {
+    Tcp(u16),
+    UnixSocket(PathBuf),
+}
+
+impl Upstream {
+    /// Base URL of requests to the sidecar. Over a Unix socket the authority
+    /// is only sent along in the `Host` header.
+    pub fn base_url(&self) -> String {
+        match self {
+            Upstream::Tcp(port) => format!("http://127.0.0.1:{}", port),
+            Upstream::UnixSocket(_) => "http://localhost".to_string(),
+        }
+    }
+
+    /// HTTP client builder that connects to the sidecar.
+    pub fn client_builder(&self) -> reqwest::ClientBuilder {
+        let builder = reqwest::Client::builder();
+        match self {
+            #[cfg(unix)]
+            Upstream::UnixSocket(path) => builder.unix_socket(path.clone()),
+            _ => builder,
+        }
+    }
+}
+
+impl std::fmt::Display for Upstream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Upstream::Tcp(port) => write!(f, "127.0.0.1:{}", port),
+            Upstream::UnixSocket(path) => write!(f, "{}", crate::transport::display_socket_path(path)),
+        }
+    }
+}
+
+struct GatewayHandle {
+    hosts: Vec<String>,
+    port: u16,
+    upstream: Upstream,
+    shutdown: watch::Sender<bool>,
+}
+
+#[derive(Default)]
+pub struct GatewayState {
+    handle: Mutex<Option<GatewayHandle>>,
+}
+
+impl GatewayState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+struct GatewayContext {
+    app: AppHandle,
+    client: reqwest::Client,
+    upstream_base: String,
+}
+
+/// Pick a free loopback port for the sidecar to listen on.
+pub fn reserve_upstream_port() -> Result<u16, CommandError> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Bind the public port on every address in `hosts` and start forwarding to
+/// the sidecar at `upstream`. Port 0 picks a free port, the same one on
+/// every address. Fails with `PortInUse` if the public port is taken on any
+/// of them.
+pub async fn start(app: &AppHandle, hosts: &[String], port: u16, upstream: Upstream) -> Result<(), CommandError> {
+    let mut port = port;
+    let mut listeners = Vec::with_capacity(hosts.len());
+    for host in hosts {
+        let listener = TcpListener::bind((host.as_str(), port)).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::AddrInUse {
+                CommandError::PortInUse(port)
+            } else {
+                CommandError::IoError(e.to_string())
+            }
+        })?;
+        port = listener.local_addr()?.port();
+        listeners.push(listener);
+    }
+
+    let client = upstream
+        .client_builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| CommandError::Unknown(e.to_string()))?;
+    app.state::<TlsState>().load()?;
+    app.state::<MetadataCache>().reload_settings();
+    app.state::<ReadaheadState>().reset();
+
+    let ctx = Arc::new(GatewayContext {
+        app: app.clone(),
+        client,
+        upstream_base: upstream.base_url(),
+    });
+
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let state = app.state::<GatewayState>();
+    let handle = GatewayHandle { hosts: hosts.to_vec(), port, upstream: upstream.clone(), shutdown };
+    if let Some(previous) = state.handle.lock().unwrap().replace(handle) {
+        let _ = previous.shutdown.send(true);
+    }
+
+    for listener in listeners {
+        log::info!("WebDAV gateway listening on {} -> {}", listener.local_addr()?, upstream);
+        tauri::async_runtime::spawn(accept_loop(listener, ctx.clone(), shutdown_rx.clone()));
+    }
+    let https = app.state::<TlsState>().acceptor().is_some();
+    app.state::<NetworkSharingState>().announce(port, https);
+    Ok(())
+}
+
+/// Addresses the running gateway is bound to.
+pub fn bound_hosts(app: &AppHandle) -> Option<Vec<String>> {
+    app.state::<GatewayState>().handle.lock().unwrap().as_ref().map(|h| h.hosts.clone())
+}
+
+/// User-facing port of the running gateway.
+pub fn public_port(app: &AppHandle) -> Option<u16> {
+    app.state::<GatewayState>().handle.lock().unwrap().as_ref().map(|h| h.port)
+}
+
+/// Policies only the gateway enforces that are currently on. A bridge
+/// serving the share without the gateway would bypass them.
+pub fn enforced_policies(app: &AppHandle) -> Vec<&'static str> {
+    let mut policies = Vec::new();
+    if app.state::<ReadOnlyState>().status().effective {
+        policies.push("read-only share");
+    }
+    if app.state::<crate::app_access::AppAccessState>().is_enabled() {
+        policies.push("app access control");
+    }
+    if app.state::<NetworkSharingState>().is_enabled() {
+        policies.push("network sharing login and bans");
+    }
+    if app.state::<BandwidthState>().is_limited() {
+        policies.push("bandwidth limits");
+    }
+    policies
+}
+
+pub fn is_running(app: &AppHandle) -> bool {
+    upstream(app).is_some()
+}
+
+/// Where the sidecar behind the gateway listens, while it is running.
+pub fn upstream(app: &AppHandle) -> Option<Upstream> {
+    app.state::<GatewayState>().handle.lock().unwrap().as_ref().map(|h| h.upstream.clone())
+}
+
+/// Stop accepting connections and drop the ones in flight.
+pub fn stop(app: &AppHandle) {
+    let state = app.state::<GatewayState>();
+    let handle = state.handle.lock().unwrap().take();
+    if let Some(handle) = handle {
+        log::info!("Stopping WebDAV gateway (upstream {})", handle.upstream);
+        let _ = handle.shutdown.send(true);
+    }
+    app.state::<NetworkSharingState>().withdraw();
+    app.state::<TlsState>().unload();
+    app.state::<WebdavAuthState>().end_run();
+}
+
+async fn accept_loop(listener: TcpListener, ctx: Arc<GatewayContext>, mut shutdown: watch::Receiver<bool>) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer)) => {
+                    let ctx = ctx.clone();
+                    let conn_shutdown = shutdown.clone();
+                    let acceptor = ctx.app.state::<TlsState>().acceptor();
+                    let sharing_app = ctx.app.clone();
+                    let local = stream.local_addr();
+                    tauri::async_runtime::spawn(async move {
+                        let admitted = match local {
+                            Ok(local) => crate::app_access::admit(&sharing_app, peer, local).await,
+                            // Without the local address the client can't be
+                            // identified, so refuse it when access is checked
+                            Err(e) => {
+                                let checked = sharing_app.state::<crate::app_access::AppAccessState>().is_enabled();
+                                if checked {
+                                    log::warn!("Refused gateway connection from {}: no local address: {}", peer, e);
+                                }
+                                !checked
+                            }
+                        };
+                        if !admitted {
+                            return;
+                        }
+                        sharing_app.state::<NetworkSharingState>().connection_opened(&sharing_app, peer);
+                        match acceptor {
+                            Some(acceptor) => {
+                                match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                                    Ok(Ok(tls)) => serve_connection(tls, ctx, peer, conn_shutdown).await,
+                                    Ok(Err(e)) => log::debug!("TLS handshake with {} failed: {}", peer, e),
+                                    Err(_) => log::debug!("TLS handshake with {} timed out", peer),
+                                }
+                            }
+                            None => serve_connection(stream, ctx, peer, conn_shutdown).await,
+                        }
+                        sharing_app.state::<NetworkSharingState>().connection_closed(peer);
+                    });
+                }
+                Err(e) => log::warn!("Gateway failed to accept connection: {}", e),
+            },
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+async fn serve_connection<I>(io: I, ctx: Arc<GatewayContext>, peer: SocketAddr, mut shutdown: watch::Receiver<bool>)
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = service_fn(move |req| proxy_request(ctx.clone(), peer, req));
+    let conn = http1::Builder::new().serve_connection(TokioIo::new(io), service);
+    tokio::select! {
+        res = conn => {
+            if let Err(e) = res {
+                log::debug!("Gateway connection from {} ended with error: {}", peer, e);
+            }
+        }
+        _ = shutdown.changed() => {}
+    }
+}
+
+async fn proxy_request(
+    ctx: Arc<GatewayContext>,
+    peer: SocketAddr,
+    mut req: Request<Incoming>,
+) -> Result<Response<GatewayBody>, Infallible> {
+    let metrics = ctx.app.state::<MetricsState>();
+    metrics.record_request();
+    let sharing = ctx.app.state::<NetworkSharingState>();
+    sharing.record_request(peer);
+    let lan_login_required = sharing.checks_credentials(peer);
+    let guard = ctx.app.state::<AuthGuardState>();
+    if lan_login_required {
+        if let Some(retry_after) = guard.retry_after(peer.ip()) {
+            let mut resp = text_response(StatusCode::TOO_MANY_REQUESTS, "Too many failed logins");
+            resp.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after));
+            return Ok(resp);
+        }
+    }
+    let shared = sharing.authorize(peer, req.headers());
+    if lan_login_required && req.headers().contains_key(AUTHORIZATION) {
+        if shared {
+            guard.record_success(peer.ip());
+        } else {
+            guard.record_failure(&ctx.app, peer.ip(), parse_basic_auth(req.headers()).map(|(user, _)| user));
+        }
+    }
+    if !shared || !ctx.app.state::<WebdavAuthState>().authorize(req.headers_mut(), lan_login_required) {
+        let mut resp = text_response(StatusCode::UNAUTHORIZED, "Authentication required");
+        resp.headers_mut().insert(WWW_AUTHENTICATE, HeaderValue::from_static(AUTH_CHALLENGE));
+        return Ok(resp);
+    }
+    if ctx.app.state::<ReadOnlyState>().rejects(req.method()) {
+        log::info!("Rejected {} {} from {}: share is read-only", req.method(), req.uri().path(), peer);
+        return Ok(text_response(StatusCode::FORBIDDEN, "The share is read-only"));
+    }
+    match forward(&ctx, req).await {
+        Ok(resp) => Ok(resp),
+        Err(e) => {
+            log::warn!("Gateway request from {} failed: {}", peer, e);
+            metrics.record_request_error();
+            Ok(text_response(StatusCode::BAD_GATEWAY, "WebDAV bridge is not reachable"))
+        }
+    }
+}
+
+async fn forward(ctx: &GatewayContext, req: Request<Incoming>) -> Result<Response<GatewayBody>, reqwest::Error> {
+    let (parts, body) = req.into_parts();
+    let display_path = percent_encoding::percent_decode_str(parts.uri.path()).decode_utf8_lossy().to_string();
+    let content_length = parse_content_length(&parts.headers);
+
+    let spool = ctx.app.state::<UploadSpool>();
+    if parts.method == Method::PUT && spool.accepts(content_length) {
+        return Ok(stage_put(ctx, &display_path, content_length.unwrap_or(0), body).await);
+    }
+    if parts.method == Method::GET {
+        if let Some((staged, size)) = spool.staged(&ctx.app, &display_path) {
+            return Ok(staged_response(ctx, &display_path, &staged, size).await);
+        }
+    }
+    if modifies_resources(&parts.method) {
+        spool.wait_for(&ctx.app, &display_path).await;
+        if let Some(dest) = parts.headers.get("destination").and_then(|v| v.to_str().ok()).and_then(destination_path) {
+            spool.wait_for(&ctx.app, &dest).await;
+        }
+    }
+
+    let cache = ctx.app.state::<MetadataCache>();
+    if parts.method.as_str() == "PROPFIND" && cache.enabled() && content_length.unwrap_or(0) <= MAX_REQUEST_BODY {
+        return forward_propfind(ctx, &parts, body, &display_path).await;
+    }
+
+    if parts.method == Method::GET {
+        let readahead = ctx.app.state::<ReadaheadState>();
+        if let Some(ranged) = readahead.serve(&ctx.client, upstream_url(ctx, &parts), &display_path, &forwardable_headers(&parts.headers)).await {
+            return Ok(stream_response(ctx, &parts.method, &display_path, StatusCode::PARTIAL_CONTENT, ranged.headers, ranged.body));
+        }
+    }
+
+    let transfers = ctx.app.state::<TransferState>();
+    let bandwidth = ctx.app.state::<BandwidthState>();
+    let metrics = ctx.app.state::<MetricsState>();
+    let mut request = upstream_request(ctx, &parts);
+    if !body.is_end_stream() {
+        let received = metrics.bytes_received.clone();
+        let stream = body
+            .into_data_stream()
+            .map_err(std::io::Error::other)
+            .inspect_ok(move |chunk| count_bytes(&received, chunk));
+        let stream = ThrottledStream::new(stream, bandwidth.upload.clone());
+        request = if parts.method == Method::PUT {
+            let ticket = transfers.begin(&ctx.app, &display_path, TransferDirection::Upload, content_length);
+            request.body(reqwest::Body::wrap_stream(MeteredStream::new(stream, ticket)))
+        } else {
+            request.body(reqwest::Body::wrap_stream(stream))
+        };
+    }
+
+    let upstream = request.send().await?;
+    let status = upstream.status();
+    if modifies_resources(&parts.method) && !status.is_client_error() && !status.is_server_error() {
+        let readahead = ctx.app.state::<ReadaheadState>();
+        cache.invalidate(&display_path);
+        readahead.invalidate(&display_path);
+        if let Some(dest) = parts.headers.get("destination").and_then(|v| v.to_str().ok()).and_then(destination_path) {
+            cache.invalidate(&dest);
+            readahead.invalidate(&dest);
+        }
+    }
+
+    let headers = forwardable_headers(upstream.headers());
+    let stream = upstream.bytes_stream().map_err(std::io::Error::other);
+    Ok(stream_response(ctx, &parts.method, &display_path, status, headers, stream))
+}
+
+/// Stream `body` to the client, counted and throttled; successful GETs are
+/// tracked as downloads.
+fn stream_response<S>(
+    ctx: &GatewayContext,
+    method: &Method,
+    display_path: &str,
+    status: StatusCode,
+    mut headers: HeaderMap,
+    body: S,
+) -> Response<GatewayBody>
+where
+    S: futures_util::Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+{
+    let transfers = ctx.app.state::<TransferState>();
+    let bandwidth = ctx.app.state::<BandwidthState>();
+    add_gateway_headers(ctx, &mut headers);
+    let total = parse_content_length(&headers);
+    let mut response = Response::builder().status(status);
+    if let Some(h) = response.headers_mut() {
+        *h = headers;
+    }
+
+    let sent = ctx.app.state::<MetricsState>().bytes_sent.clone();
+    let stream = body.inspect_ok(move |chunk| count_bytes(&sent, chunk));
+    let stream = ThrottledStream::new(stream, bandwidth.download.clone());
+    let body = if method == Method::GET && status.is_success() {
+        let ticket = transfers.begin(&ctx.app, display_path, TransferDirection::Download, total);
+        BodyExt::boxed_unsync(StreamBody::new(MeteredStream::new(stream, ticket).map_ok(Frame::data)))
+    } else {
+        BodyExt::boxed_unsync(StreamBody::new(stream.map_ok(Frame::data)))
+    };
+
+    response
+        .body(body)
+        .unwrap_or_else(|_| text_response(StatusCode::BAD_GATEWAY, "Invalid upstream response"))
+}
+
+/// Stage a PUT for the background uploader and answer once it is on disk.
+async fn stage_put(ctx: &GatewayContext, display_path: &str, size: u64, body: Incoming) -> Response<GatewayBody> {
+    let received = ctx.app.state::<MetricsState>().bytes_received.clone();
+    let stream = body
+        .into_data_stream()
+        .map_err(std::io::Error::other)
+        .inspect_ok(move |chunk| count_bytes(&received, chunk));
+    match ctx.app.state::<UploadSpool>().stage(&ctx.app, display_path, size, stream).await {
+        Ok(_) => text_response(StatusCode::CREATED, "Queued for upload"),
+        Err(e) => {
+            log::warn!("Failed to stage the upload of {}: {}", display_path, e);
+            text_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to stage the upload")
+        }
+    }
+}
+
+/// Answer a GET with a staged file whose upload is still pending.
+async fn staged_response(ctx: &GatewayContext, display_path: &str, staged: &std::path::Path, size: u64) -> Response<GatewayBody> {
+    let file = match tokio::fs::File::open(staged).await {
+        Ok(file) => file,
+        Err(e) => {
+            log::warn!("Failed to read the staged upload of {}: {}", display_path, e);
+            return text_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read the staged upload");
+        }
+    };
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(size));
+    stream_response(ctx, &Method::GET, display_path, StatusCode::OK, headers, crate::remote::local_file_stream(file, ctx.app.state::<crate::transfer_concurrency::TransferConcurrencyState>().chunk_size()))
+}
+
+/// Answer a PROPFIND from the metadata cache, or forward it and cache the
+/// multistatus response. Bodies are small enough to buffer.
+async fn forward_propfind(
+    ctx: &GatewayContext,
+    parts: &hyper::http::request::Parts,
+    body: Incoming,
+    display_path: &str,
+) -> Result<Response<GatewayBody>, reqwest::Error> {
+    let cache = ctx.app.state::<MetadataCache>();
+    let body = match body.collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(_) => return Ok(text_response(StatusCode::BAD_REQUEST, "Failed to read request body")),
+    };
+    count_bytes(&ctx.app.state::<MetricsState>().bytes_received, &body);
+    let key = cache.key(display_path, &parts.headers, &body);
+    if let Some(hit) = key.as_ref().and_then(|k| cache.lookup(k)) {
+        return Ok(buffered_response(ctx, hit));
+    }
+
+    let upstream = upstream_request(ctx, parts).body(body).send().await?;
+    let cacheable = upstream.status() == StatusCode::MULTI_STATUS
+        && parse_content_length(upstream.headers()).is_none_or(|len| len <= MAX_CACHED_RESPONSE);
+    let status = upstream.status().as_u16();
+    let headers = forwardable_headers(upstream.headers());
+    let response = CachedResponse { status, headers, body: upstream.bytes().await? };
+    if let (Some(key), true) = (key, cacheable) {
+        cache.store(key, response.clone());
+    }
+    if response.status == StatusCode::MULTI_STATUS.as_u16() && parts.headers.get("depth").is_some_and(|d| d == "1") {
+        if let Ok(entries) = parse_multistatus(&String::from_utf8_lossy(&response.body)) {
+            ctx.app.state::<ActivityFeed>().observe(&ctx.app, display_path, &entries);
+        }
+    }
+    Ok(buffered_response(ctx, response))
+}
+
+/// The sidecar's URL for the client's path and query.
+fn upstream_url(ctx: &GatewayContext, parts: &hyper::http::request::Parts) -> String {
+    let path_and_query = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    format!("{}{}", ctx.upstream_base, path_and_query)
+}
+
+/// Request to the sidecar carrying the client's method, path and headers.
+fn upstream_request(ctx: &GatewayContext, parts: &hyper::http::request::Parts) -> reqwest::RequestBuilder {
+    let url = upstream_url(ctx, parts);
+    let mut headers = forwardable_headers(&parts.headers);
+    if let Some(dest) = parts.headers.get("destination").and_then(|v| v.to_str().ok()) {
+        if let Some(value) = rewrite_destination(dest, &ctx.upstream_base).and_then(|d| HeaderValue::from_str(&d).ok()) {
+            headers.insert(HeaderName::from_static("destination"), value);
+        }
+    }
+    ctx.client.request(parts.method.clone(), url).headers(headers)
+}
+
+fn add_gateway_headers(ctx: &GatewayContext, headers: &mut HeaderMap) {
+    if let Some(hsts) = ctx.app.state::<TlsState>().hsts_header() {
+        headers.insert(STRICT_TRANSPORT_SECURITY, HeaderValue::from_static(hsts));
+    }
+}
+
+fn count_bytes(counter: &AtomicU64, chunk: &Bytes) {
+    counter.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+}
+
+fn buffered_response(ctx: &GatewayContext, cached: CachedResponse) -> Response<GatewayBody> {
+    count_bytes(&ctx.app.state::<MetricsState>().bytes_sent, &cached.body);
+    let mut resp = Response::new(Full::new(cached.body).map_err(|e| match e {}).boxed_unsync());
+    *resp.status_mut() = StatusCode::from_u16(cached.status).unwrap_or(StatusCode::BAD_GATEWAY);
+    *resp.headers_mut() = cached.headers;
+    add_gateway_headers(ctx, resp.headers_mut());
+    resp
+}
+
+/// Methods after which cached listings of the target may be stale.
+fn modifies_resources(method: &Method) -> bool {
+    !matches!(method.as_str(), "GET" | "HEAD" | "OPTIONS" | "PROPFIND")
+}
+
+fn text_response(status: StatusCode, message: &'static str) -> Response<GatewayBody> {
+    let mut resp = Response::new(Full::new(Bytes::from_static(message.as_bytes())).map_err(|e| match e {}).boxed_unsync());
+    *resp.status_mut() = status;
+    resp
+}
+
+fn is_hop_by_hop(name: &HeaderName) -> bool {
+    HOP_BY_HOP_HEADERS.contains(&name.as_str())
+}
+
+fn forwardable_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut out = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers.iter() {
+        if is_hop_by_hop(name) || name == HOST {
+            continue;
+        }
+        out.append(name.clone(), value.clone());
+    }
+    out
+}
+
+fn parse_content_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+/// Decoded path of a `Destination` header (absolute URL or path).
+fn destination_path(dest: &str) -> Option<String> {
+    let path = match dest.find("://") {
+        Some(i) => &dest[i + 3 + dest[i + 3..].find('/')?..],
+        None => dest,
+    };
+    Some(percent_encoding::percent_decode_str(path).decode_utf8_lossy().to_string())
+}
+
+/// Point an absolute `Destination` header (COPY/MOVE) at the sidecar, which
+/// rejects destinations outside its own base URL.
+fn rewrite_destination(dest: &str, upstream_base: &str) -> Option<String> {
+    let scheme_end = dest.find("://")? + 3;
+    let path_start = dest[scheme_end..].find('/').map(|i| scheme_end + i).unwrap_or(dest.len());
+    let path = &dest[path_start..];
+    Some(format!("{}{}", upstream_base, if path.is_empty() { "/" } else { path }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_destination_replaces_authority() {
+        assert_eq!(
+            rewrite_destination("http://localhost:8080/a/b.txt", "http://127.0.0.1:40000"),
+            Some("http://127.0.0.1:40000/a/b.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rewrite_destination_without_path() {
+        assert_eq!(
+            rewrite_destination("http://localhost:8080", "http://127.0.0.1:40000"),
+            Some("http://127.0.0.1:40000/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rewrite_destination_leaves_relative_paths() {
+        assert_eq!(rewrite_destination("/a/b.txt", "http://127.0.0.1:40000"), None);
+    }
+
+    #[test]
+    fn test_forwardable_headers_drops_hop_by_hop() {
+        let mut headers = HeaderMap::new();
+        headers.insert("connection", HeaderValue::from_static("keep-alive"));
+        headers.insert("transfer-encoding", HeaderValue::from_static("chunked"));
+        headers.insert("host", HeaderValue::from_static("localhost:8080"));
+        headers.insert("depth", HeaderValue::from_static("1"));
+        let out = forwardable_headers(&headers);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out.get("depth").unwrap(), "1");
+    }
+
+    #[test]
+    fn test_destination_path() {
+        assert_eq!(destination_path("http://localhost:8080/My%20Files/a.txt").as_deref(), Some("/My Files/a.txt"));
+        assert_eq!(destination_path("/b.txt").as_deref(), Some("/b.txt"));
+        assert_eq!(destination_path("http://localhost:8080"), None);
+    }
+
+    #[test]
+    fn test_modifies_resources() {
+        assert!(modifies_resources(&Method::PUT));
+        assert!(modifies_resources(&Method::from_bytes(b"MOVE").unwrap()));
+        assert!(!modifies_resources(&Method::from_bytes(b"PROPFIND").unwrap()));
+        assert!(!modifies_resources(&Method::GET));
+    }
+
+    #[test]
+    fn test_reserve_upstream_port_is_nonzero() {
+        assert_ne!(reserve_upstream_port().unwrap(), 0);
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use gio::prelude::*;
+
+use crate::sidecar::MOUNT_TIMED_OUT;
+
+// ============================================================================
+// GIO mount worker
+// ============================================================================
+//
+// GIO's asynchronous mount calls complete on a GLib main context, so they
+// need a thread running one. Rather than spawning a thread and a main loop
+// per mount or unmount attempt, every such job runs on a single long-lived
+// worker thread that owns one context. Jobs are handed over with `run`; a
+// job starts its GIO call and returns, and the per-attempt timeout is a
+// timer source on the same context. `Attempt` ties the timer and the link to
+// the caller's cancellable to one call and removes both when it completes,
+// so nothing outlives the attempt. `shutdown` stops the loop and joins the
+// thread when the app exits.
+
+/// What a job answers: `Ok`, or the GIO error message.
+type Outcome = Result<(), String>;
+
+struct Worker {
+    context: glib::MainContext,
+    main_loop: glib::MainLoop,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+static WORKER: OnceLock<Worker> = OnceLock::new();
+
+fn worker() -> &'static Worker {
+    WORKER.get_or_init(|| {
+        let context = glib::MainContext::new();
+        let main_loop = glib::MainLoop::new(Some(&context), false);
+        let thread = {
+            let context = context.clone();
+            let main_loop = main_loop.clone();
+            std::thread::Builder::new().name("gio-mount-worker".into()).spawn(move || {
+                if let Err(e) = context.with_thread_default(|| main_loop.run()) {
+                    log::error!("GIO mount worker could not acquire its context: {}", e);
+                }
+            })
+        };
+        let thread = match thread {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                log::error!("Failed to start the GIO mount worker: {}", e);
+                None
+            }
+        };
+        Worker { context, main_loop, thread: Mutex::new(thread) }
+    })
+}
+
+/// Run `job` on the worker thread, with its context as thread default.
+/// After `shutdown` the job is dropped, which drops its `Reply`.
+pub(crate) fn run<F: FnOnce() + Send + 'static>(job: F) {
+    let worker = worker();
+    if worker.thread.lock().unwrap().is_none() {
+        log::warn!("GIO mount worker is not running; dropping job");
+        return;
+    }
+    worker.context.invoke(job);
+}
+
+/// Stop the worker's loop and wait for its thread to exit.
+pub fn shutdown() {
+    let Some(worker) = WORKER.get() else { return };
+    let Some(handle) = worker.thread.lock().unwrap().take() else { return };
+    worker.main_loop.quit();
+    if handle.join().is_err() {
+        log::warn!("GIO mount worker panicked");
+    }
+}
+
+/// The sending half of a job's outcome. Whichever of completion and timeout
+/// comes first answers; later answers are dropped.
+#[derive(Clone)]
+pub(crate) struct Reply(Arc<Mutex<Option<mpsc::Sender<Outcome>>>>);
+
+impl Reply {
+    pub fn channel() -> (Self, mpsc::Receiver<Outcome>) {
+        let (tx, rx) = mpsc::channel();
+        (Self(Arc::new(Mutex::new(Some(tx)))), rx)
+    }
+
+    /// Send `result` unless an answer was sent already.
+    pub fn send(&self, result: Outcome) -> bool {
+        match self.0.lock().unwrap().take() {
+            Some(tx) => tx.send(result).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/// One GIO call on the worker. It gets its own cancellable, so a timeout
+/// aborts only this attempt, linked to the caller's so cancelling that aborts
+/// it too.
+pub(crate) struct Attempt {
+    cancellable: gio::Cancellable,
+    parent: gio::Cancellable,
+    link: Option<gio::CancelledHandlerId>,
+    timer: glib::Source,
+    timed_out: Arc<AtomicBool>,
+}
+
+impl Attempt {
+    /// Start timing an attempt. Must be called from a job; once `timeout`
+    /// passes the attempt is cancelled and `reply` answers `MOUNT_TIMED_OUT`.
+    pub fn start(parent: &gio::Cancellable, timeout: Duration, reply: &Reply) -> Self {
+        let cancellable = gio::Cancellable::new();
+        let link = {
+            let cancellable = cancellable.clone();
+            parent.connect_cancelled(move |_| cancellable.cancel())
+        };
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let timer = {
+            let cancellable = cancellable.clone();
+            let timed_out = timed_out.clone();
+            let reply = reply.clone();
+            glib::timeout_source_new(timeout, None, glib::Priority::DEFAULT, move || {
+                timed_out.store(true, Ordering::Relaxed);
+                cancellable.cancel();
+                reply.send(Err(MOUNT_TIMED_OUT.to_string()));
+                glib::ControlFlow::Break
+            })
+        };
+        timer.attach(Some(&worker().context));
+        Self { cancellable, parent: parent.clone(), link, timer, timed_out }
+    }
+
+    pub fn cancellable(&self) -> &gio::Cancellable {
+        &self.cancellable
+    }
+
+    /// Remove the timer and the link to the caller's cancellable. Returns
+    /// whether the attempt timed out.
+    pub fn finish(self) -> bool {
+        self.timer.destroy();
+        if let Some(id) = self.link {
+            self.parent.disconnect_cancelled(id);
+        }
+        self.timed_out.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reply_answers_once() {
+        let (reply, rx) = Reply::channel();
+        let timer = reply.clone();
+        assert!(timer.send(Err(MOUNT_TIMED_OUT.to_string())));
+        assert!(!reply.send(Ok(())));
+        assert_eq!(rx.recv().unwrap(), Err(MOUNT_TIMED_OUT.to_string()));
+        // Both senders are gone, so the receiver doesn't block
+        drop((reply, timer));
+        assert!(rx.recv().is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use tauri::{AppHandle, Emitter};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Localization
+// ============================================================================
+//
+// Strings the backend shows to the user (`mount:status` payloads, mount
+// notifications, the tray menu and the `message` of command errors) are
+// looked up by key in JSON catalogs embedded from `locales/`. The active
+// language is the one set with `set_locale`, otherwise the system's
+// (`LC_ALL`, `LC_MESSAGES`, `LANG`), otherwise English. A key missing from a
+// catalog falls back to English; errors fall back to their English `Display`
+// text, so only other languages need `error.<CODE>` entries. Placeholders
+// are written `{name}`.
+
+const CONFIG_KEY: &str = "locale";
+
+const DEFAULT_LOCALE: &str = "en";
+
+/// Embedded catalogs, by language code.
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("fr", include_str!("../locales/fr.json")),
+];
+
+static PARSED: OnceLock<HashMap<&'static str, HashMap<String, String>>> = OnceLock::new();
+
+static ACTIVE: RwLock<&'static str> = RwLock::new(DEFAULT_LOCALE);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct LocaleConfig {
+    /// Chosen language; `None` follows the system
+    language: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleInfo {
+    /// Language strings are currently resolved in
+    pub locale: String,
+    /// Language chosen in the app, if any
+    pub preference: Option<String>,
+    pub available: Vec<String>,
+}
+
+fn catalogs() -> &'static HashMap<&'static str, HashMap<String, String>> {
+    PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(lang, json)| {
+                let entries = serde_json::from_str(json).unwrap_or_else(|e| {
+                    log::error!("Invalid {} catalog: {}", lang, e);
+                    HashMap::new()
+                });
+                (*lang, entries)
+            })
+            .collect()
+    })
+}
+
+/// The supported language `tag` names: `de`, `de-AT` and `de_DE.UTF-8` all
+/// resolve to `de`.
+fn supported(tag: &str) -> Option<&'static str> {
+    let lang = tag.split(['_', '-', '.', '@']).next()?.to_ascii_lowercase();
+    CATALOGS.iter().map(|(code, _)| *code).find(|code| *code == lang)
+}
+
+fn system_locale() -> Option<&'static str> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| supported(&value))
+}
+
+fn resolve(preference: Option<&str>) -> &'static str {
+    preference.and_then(supported).or_else(system_locale).unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Pick the active language from the config. Called once at startup.
+pub fn init() {
+    let config: LocaleConfig = read_config_section(CONFIG_KEY);
+    let locale = resolve(config.language.as_deref());
+    *ACTIVE.write().unwrap() = locale;
+    log::info!("Using the {} locale", locale);
+}
+
+pub fn active() -> &'static str {
+    *ACTIVE.read().unwrap()
+}
+
+fn lookup(locale: &str, key: &str) -> Option<&'static str> {
+    catalogs().get(locale)?.get(key).map(String::as_str)
+}
+
+fn fill(template: &str, params: &[(&str, &str)]) -> String {
+    params.iter().fold(template.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+/// Translate `key` into the active language.
+pub fn t(key: &str) -> String {
+    t_with(key, &[])
+}
+
+/// Translate `key` into the active language and fill in its placeholders.
+pub fn t_with(key: &str, params: &[(&str, &str)]) -> String {
+    let template = lookup(active(), key).or_else(|| lookup(DEFAULT_LOCALE, key)).unwrap_or(key);
+    fill(template, params)
+}
+
+/// `error`'s message in the active language. English messages read
+/// `<summary>` or `<summary>: <detail>`; the detail is kept as is.
+pub(crate) fn error_message(error: &CommandError) -> String {
+    let english = error.to_string();
+    let key = format!("error.{}", error.code());
+    match lookup(active(), &key) {
+        Some(template) => {
+            let detail = english.split_once(": ").map(|(_, detail)| detail).unwrap_or("");
+            fill(template, &[("detail", detail)])
+        }
+        None => english,
+    }
+}
+
+fn info(preference: Option<String>) -> LocaleInfo {
+    LocaleInfo {
+        locale: active().to_string(),
+        preference,
+        available: CATALOGS.iter().map(|(code, _)| code.to_string()).collect(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_locale() -> Result<LocaleInfo, CommandError> {
+    let config: LocaleConfig = read_config_section(CONFIG_KEY);
+    Ok(info(config.language))
+}
+
+/// Choose the language for backend strings; `None` follows the system.
+#[tauri::command]
+pub async fn set_locale(app: AppHandle, language: Option<String>) -> Result<LocaleInfo, CommandError> {
+    let language = match language {
+        Some(tag) => Some(supported(&tag).ok_or(CommandError::UnsupportedLocale(tag))?.to_string()),
+        None => None,
+    };
+    write_config_section(CONFIG_KEY, &LocaleConfig { language: language.clone() })?;
+    let locale = resolve(language.as_deref());
+    *ACTIVE.write().unwrap() = locale;
+    log::info!("Switched to the {} locale", locale);
+    let info = info(language);
+    let _ = app.emit("locale:changed", &info);
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_normalizes_tags() {
+        assert_eq!(supported("de"), Some("de"));
+        assert_eq!(supported("de_DE.UTF-8"), Some("de"));
+        assert_eq!(supported("fr-CA"), Some("fr"));
+        assert_eq!(supported("EN"), Some("en"));
+        assert_eq!(supported("xx_YY"), None);
+        assert_eq!(supported(""), None);
+    }
+
+    #[test]
+    fn test_fill_placeholders() {
+        assert_eq!(fill("Checking mount: {uri}", &[("uri", "dav://localhost:8080/")]), "Checking mount: dav://localhost:8080/");
+        assert_eq!(fill("No {placeholder}", &[]), "No {placeholder}");
+    }
+
+    #[test]
+    fn test_catalogs_cover_english_keys() {
+        let english = &catalogs()[DEFAULT_LOCALE];
+        assert!(!english.is_empty());
+        for (lang, _) in CATALOGS {
+            let catalog = &catalogs()[lang];
+            for key in english.keys() {
+                assert!(catalog.contains_key(key), "{} catalog lacks {}", lang, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_error_translations_keep_detail() {
+        let german = &catalogs()["de"];
+        let template = &german["error.INVALID_PORT"];
+        let english = CommandError::InvalidPort("70000".into()).to_string();
+        let detail = english.split_once(": ").unwrap().1;
+        assert_eq!(fill(template, &[("detail", detail)]), "Ungültige Portnummer: 70000");
+    }
+}
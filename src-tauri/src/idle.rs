@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::metrics::MetricsState;
+use crate::sidecar::{read_config_section, write_config_section, CommandError, SidecarState};
+
+// ============================================================================
+// Idle policy
+// ============================================================================
+//
+// With an idle policy set, the drive is unmounted once no WebDAV request has
+// reached the gateway for `minutes`, and with the `stop` action the bridge
+// is stopped too, so an unattended machine does not keep a signed-in drive
+// open. Activity is what the metrics count: requests through the gateway,
+// plus transfers the app itself is running. The clock restarts whenever
+// something comes up to act on again (a mount, or the bridge for `stop`), so
+// a drive mounted by hand gets the full timeout. `idle:triggered` is emitted
+// when the policy acts. Nothing happens while the machine is suspended.
+
+const CONFIG_KEY: &str = "idle";
+
+const TICK: Duration = Duration::from_secs(30);
+
+/// Longest timeout accepted: one week.
+const MAX_MINUTES: u32 = 7 * 24 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IdleAction {
+    /// Unmount the drive and the extra mounts
+    #[default]
+    Unmount,
+    /// Unmount, then stop the bridge
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IdlePolicy {
+    /// Minutes without activity before acting; 0 disables the policy
+    pub minutes: u32,
+    pub action: IdleAction,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleTriggeredEvent {
+    pub action: IdleAction,
+    pub idle_minutes: u64,
+}
+
+/// Time since the last activity, as seen by the watcher.
+#[derive(Debug, Default)]
+struct IdleClock {
+    requests: u64,
+    /// Time idle so far
+    idle: Duration,
+    /// Whether there was something to act on at the last tick
+    armed: bool,
+}
+
+impl IdleClock {
+    /// Advance by `tick`. `requests` is the gateway's request count, `busy`
+    /// whether transfers are running and `armed` whether there is anything
+    /// the policy could act on. Returns how long it has been idle.
+    fn observe(&mut self, tick: Duration, requests: u64, busy: bool, armed: bool) -> Duration {
+        let active = requests != self.requests || busy || (armed && !self.armed);
+        self.requests = requests;
+        self.armed = armed;
+        self.idle = if active || !armed { Duration::ZERO } else { self.idle + tick };
+        self.idle
+    }
+}
+
+/// Unmount everything the app mounted and, for `Stop`, stop the bridge.
+async fn act(app: &AppHandle, action: IdleAction) {
+    let state = app.state::<SidecarState>();
+    if matches!(crate::sidecar::check_mount_status(app.clone(), state.clone()).await, Ok(Some(_))) {
+        if let Err(e) = crate::sidecar::unmount_drive(app.clone(), state.clone()).await {
+            log::warn!("Idle unmount failed: {}", e);
+        }
+    }
+    let mounted = crate::mounts::list_mounts(app.clone()).await.unwrap_or_default();
+    for mount in mounted.into_iter().filter(|m| m.mounted) {
+        if let Err(e) = crate::mounts::unmount_by_id(app.clone(), mount.definition.id.clone()).await {
+            log::warn!("Idle unmount of {} failed: {}", mount.definition.id, e);
+        }
+    }
+    if action == IdleAction::Stop {
+        if let Err(e) = crate::sidecar::stop_sidecar(app.clone(), state).await {
+            log::warn!("Idle stop failed: {}", e);
+        }
+    }
+}
+
+/// Whether there is something `action` could act on.
+async fn has_target(app: &AppHandle, action: IdleAction) -> bool {
+    let state = app.state::<SidecarState>();
+    if !state.is_running().await {
+        return false;
+    }
+    if action == IdleAction::Stop {
+        return true;
+    }
+    if matches!(crate::sidecar::check_mount_status(app.clone(), state).await, Ok(Some(_))) {
+        return true;
+    }
+    crate::mounts::list_mounts(app.clone()).await.unwrap_or_default().iter().any(|m| m.mounted)
+}
+
+/// Watch for inactivity and apply the policy; runs for the life of the app.
+pub async fn run(app: AppHandle) {
+    let mut clock = IdleClock::default();
+    let mut interval = tokio::time::interval(TICK);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let policy: IdlePolicy = read_config_section(CONFIG_KEY);
+        if policy.minutes == 0 || app.state::<crate::power::PowerState>().is_suspended() {
+            clock = IdleClock::default();
+            continue;
+        }
+        let requests = app.state::<MetricsState>().request_count();
+        let busy = !app.state::<crate::transfers::TransferState>().list().is_empty();
+        let armed = has_target(&app, policy.action).await;
+        let idle = clock.observe(TICK, requests, busy, armed);
+        if idle < Duration::from_secs(u64::from(policy.minutes) * 60) {
+            continue;
+        }
+        log::info!("No activity for {} minute(s), applying idle policy {:?}", idle.as_secs() / 60, policy.action);
+        act(&app, policy.action).await;
+        clock = IdleClock::default();
+        let _ = app.emit("idle:triggered", IdleTriggeredEvent { action: policy.action, idle_minutes: idle.as_secs() / 60 });
+    }
+}
+
+#[tauri::command]
+pub async fn get_idle_policy() -> Result<IdlePolicy, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+/// Set the idle policy; `minutes` of 0 turns it off. Takes effect at the
+/// watcher's next tick.
+#[tauri::command]
+pub async fn set_idle_policy(policy: IdlePolicy) -> Result<IdlePolicy, CommandError> {
+    if policy.minutes > MAX_MINUTES {
+        return Err(CommandError::InvalidIdlePolicy(format!("minutes must be at most {}", MAX_MINUTES)));
+    }
+    write_config_section(CONFIG_KEY, &policy)?;
+    log::info!("Idle policy set to {:?} after {} minute(s)", policy.action, policy.minutes);
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_restarts_on_activity_and_rearm() {
+        let tick = Duration::from_secs(30);
+        let mut clock = IdleClock::default();
+        assert_eq!(clock.observe(tick, 0, false, true), Duration::ZERO);
+        assert_eq!(clock.observe(tick, 0, false, true), tick);
+        assert_eq!(clock.observe(tick, 0, false, true), tick * 2);
+        // A request, then a running transfer
+        assert_eq!(clock.observe(tick, 1, false, true), Duration::ZERO);
+        assert_eq!(clock.observe(tick, 1, true, true), Duration::ZERO);
+        assert_eq!(clock.observe(tick, 1, false, true), tick);
+        // Unmounted, then mounted again
+        assert_eq!(clock.observe(tick, 1, false, false), Duration::ZERO);
+        assert_eq!(clock.observe(tick, 1, false, true), Duration::ZERO);
+        assert_eq!(clock.observe(tick, 1, false, true), tick);
+    }
+
+    #[test]
+    fn test_policy_defaults_to_off() {
+        let policy: IdlePolicy = serde_json::from_str("{}").unwrap();
+        assert_eq!(policy, IdlePolicy { minutes: 0, action: IdleAction::Unmount });
+        let policy: IdlePolicy = serde_json::from_str(r#"{"minutes": 15, "action": "stop"}"#).unwrap();
+        assert_eq!(policy.action, IdleAction::Stop);
+    }
+}
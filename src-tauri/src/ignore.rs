@@ -0,0 +1,279 @@
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Ignore rules
+// ============================================================================
+//
+// Sync, the backups and the drop folder leave out files matching ignore
+// patterns, so editor lock files, temporary files and desktop metadata are
+// not uploaded (and don't churn the metadata cache with every save). The
+// patterns follow .gitignore: a pattern without a slash matches a name at
+// any depth, one with a slash matches the path from the folder's root,
+// `**` spans folders, a trailing `/` only matches folders, `!` re-includes
+// what an earlier pattern ignored, and `#` starts a comment. Everything
+// inside an ignored folder is ignored.
+//
+// Each feature applies, in order, the built-in `DEFAULT_PATTERNS` (unless
+// turned off), the global patterns and its own scope's patterns, all kept
+// in the `ignore` config section. Changes apply from the next file a
+// feature looks at.
+
+const CONFIG_KEY: &str = "ignore";
+
+/// Junk every feature leaves out unless `useDefaults` is off.
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    "*.tmp",
+    "*.part",
+    "*.crdownload",
+    "*.swp",
+    "*~",
+    ".~lock.*#",
+    "~$*",
+    ".DS_Store",
+    "._*",
+    "Thumbs.db",
+    "desktop.ini",
+];
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions { case_sensitive: true, require_literal_separator: true, require_literal_leading_dot: false };
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IgnoreScope {
+    /// Applies to every feature
+    Global,
+    Sync,
+    /// Scheduled backups and photo backup
+    Backup,
+    DropFolder,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IgnoreSettings {
+    pub use_defaults: bool,
+    pub global: Vec<String>,
+    pub sync: Vec<String>,
+    pub backup: Vec<String>,
+    pub drop_folder: Vec<String>,
+}
+
+impl Default for IgnoreSettings {
+    fn default() -> Self {
+        Self { use_defaults: true, global: Vec::new(), sync: Vec::new(), backup: Vec::new(), drop_folder: Vec::new() }
+    }
+}
+
+impl IgnoreSettings {
+    fn scope(&self, scope: IgnoreScope) -> &[String] {
+        match scope {
+            IgnoreScope::Global => &self.global,
+            IgnoreScope::Sync => &self.sync,
+            IgnoreScope::Backup => &self.backup,
+            IgnoreScope::DropFolder => &self.drop_folder,
+        }
+    }
+
+    fn scope_mut(&mut self, scope: IgnoreScope) -> &mut Vec<String> {
+        match scope {
+            IgnoreScope::Global => &mut self.global,
+            IgnoreScope::Sync => &mut self.sync,
+            IgnoreScope::Backup => &mut self.backup,
+            IgnoreScope::DropFolder => &mut self.drop_folder,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnorePatterns {
+    #[serde(flatten)]
+    pub settings: IgnoreSettings,
+    pub defaults: Vec<String>,
+}
+
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    /// Matched against the whole relative path rather than the name
+    anchored: bool,
+}
+
+/// Parsed ignore patterns; the last pattern matching a path decides.
+pub struct IgnoreRules(Vec<Rule>);
+
+impl IgnoreRules {
+    /// Parse `patterns`, failing on the first invalid one.
+    pub fn parse<S: AsRef<str>>(patterns: &[S]) -> Result<Self, CommandError> {
+        let mut rules = Vec::new();
+        for raw in patterns {
+            if let Some(rule) = parse_rule(raw.as_ref())? {
+                rules.push(rule);
+            }
+        }
+        Ok(Self(rules))
+    }
+
+    /// Whether `path`, a file or folder relative to the root, decides to be
+    /// ignored on its own.
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let mut ignored = false;
+        for rule in &self.0 {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let target = if rule.anchored { path } else { name };
+            if rule.pattern.matches_with(target, MATCH_OPTIONS) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+
+    /// Whether any folder on the way to `rel` is ignored.
+    fn ancestor_ignored(&self, rel: &str) -> bool {
+        rel.match_indices('/').any(|(i, _)| self.matches(&rel[..i], true))
+    }
+
+    /// Whether the file at `rel` (relative to the root, `/`-separated) is
+    /// ignored.
+    pub fn is_ignored(&self, rel: &str) -> bool {
+        self.ancestor_ignored(rel) || self.matches(rel, false)
+    }
+
+    /// Whether the folder at `rel` is ignored, and need not be descended
+    /// into.
+    pub fn is_ignored_dir(&self, rel: &str) -> bool {
+        self.ancestor_ignored(rel) || self.matches(rel, true)
+    }
+}
+
+/// One line of a pattern list; `None` for blank lines and comments.
+fn parse_rule(line: &str) -> Result<Option<Rule>, CommandError> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (negated, rest) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, rest) = match rest.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let anchored = rest.contains('/');
+    let rest = rest.strip_prefix('/').unwrap_or(rest);
+    if rest.is_empty() {
+        return Err(CommandError::InvalidIgnorePattern(format!("{:?} matches nothing", line)));
+    }
+    let pattern = Pattern::new(rest).map_err(|e| CommandError::InvalidIgnorePattern(format!("{:?}: {}", line, e)))?;
+    Ok(Some(Rule { pattern, negated, dir_only, anchored }))
+}
+
+/// The patterns applying to `scope`, in order: the defaults, the global
+/// ones, then the scope's own.
+fn patterns(settings: &IgnoreSettings, scope: IgnoreScope) -> Vec<String> {
+    let mut patterns: Vec<String> =
+        if settings.use_defaults { DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect() } else { Vec::new() };
+    patterns.extend(settings.global.iter().cloned());
+    if scope != IgnoreScope::Global {
+        patterns.extend(settings.scope(scope).iter().cloned());
+    }
+    patterns
+}
+
+/// The rules of `scope` followed by `extra`. Patterns that don't parse are
+/// skipped; they are checked when set.
+pub(crate) fn rules_with(scope: IgnoreScope, extra: &[String]) -> IgnoreRules {
+    let settings: IgnoreSettings = read_config_section(CONFIG_KEY);
+    let mut all = patterns(&settings, scope);
+    all.extend(extra.iter().cloned());
+    let rules = all
+        .iter()
+        .filter_map(|p| match parse_rule(p) {
+            Ok(rule) => rule,
+            Err(e) => {
+                log::warn!("Ignoring invalid ignore pattern: {}", e);
+                None
+            }
+        })
+        .collect();
+    IgnoreRules(rules)
+}
+
+pub(crate) fn rules(scope: IgnoreScope) -> IgnoreRules {
+    rules_with(scope, &[])
+}
+
+#[tauri::command]
+pub async fn get_ignore_patterns() -> Result<IgnorePatterns, CommandError> {
+    Ok(IgnorePatterns {
+        settings: read_config_section(CONFIG_KEY),
+        defaults: DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect(),
+    })
+}
+
+/// Replace the patterns of `scope`. `use_defaults` keeps its current value
+/// when omitted.
+#[tauri::command]
+pub async fn set_ignore_patterns(scope: IgnoreScope, patterns: Vec<String>, use_defaults: Option<bool>) -> Result<IgnoreSettings, CommandError> {
+    IgnoreRules::parse(&patterns)?;
+    let mut settings: IgnoreSettings = read_config_section(CONFIG_KEY);
+    *settings.scope_mut(scope) = patterns.into_iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+    if let Some(use_defaults) = use_defaults {
+        settings.use_defaults = use_defaults;
+    }
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_patterns() {
+        let rules = IgnoreRules::parse(DEFAULT_PATTERNS).unwrap();
+        assert!(rules.is_ignored("Docs/.~lock.report.odt#"));
+        assert!(rules.is_ignored("Docs/~$report.docx"));
+        assert!(rules.is_ignored(".DS_Store"));
+        assert!(rules.is_ignored("Photos/2024/Thumbs.db"));
+        assert!(rules.is_ignored("download.iso.part"));
+        assert!(!rules.is_ignored("Docs/report.odt"));
+    }
+
+    #[test]
+    fn test_gitignore_semantics() {
+        let rules = IgnoreRules::parse(&["# build output", "node_modules/", "/build", "logs/**/*.log", "*.bak", "!keep.bak"]).unwrap();
+        assert!(rules.is_ignored("app/node_modules/x/index.js"));
+        assert!(rules.is_ignored_dir("app/node_modules"));
+        // A file named like a folder-only pattern
+        assert!(!rules.is_ignored("node_modules"));
+        assert!(rules.is_ignored("build/out.o"));
+        assert!(!rules.is_ignored("src/build/out.o"));
+        assert!(rules.is_ignored("logs/a/b/today.log"));
+        assert!(rules.is_ignored("logs/today.log"));
+        assert!(!rules.is_ignored("other/today.log"));
+        assert!(rules.is_ignored("notes.bak"));
+        assert!(!rules.is_ignored("keep.bak"));
+        assert!(IgnoreRules::parse(&["["]).is_err());
+        assert!(IgnoreRules::parse(&["/"]).is_err());
+    }
+
+    #[test]
+    fn test_scope_patterns_follow_defaults_and_global() {
+        let settings = IgnoreSettings { global: vec!["*.log".into()], sync: vec!["!debug.log".into()], ..Default::default() };
+        let sync = patterns(&settings, IgnoreScope::Sync);
+        assert_eq!(sync.len(), DEFAULT_PATTERNS.len() + 2);
+        assert_eq!(sync.last().map(String::as_str), Some("!debug.log"));
+        assert_eq!(patterns(&settings, IgnoreScope::Backup).len(), DEFAULT_PATTERNS.len() + 1);
+        let settings = IgnoreSettings { use_defaults: false, ..settings };
+        assert_eq!(patterns(&settings, IgnoreScope::Global), vec!["*.log"]);
+    }
+}
@@ -0,0 +1,42 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+// ============================================================================
+// Single-instance handling
+// ============================================================================
+
+/// Payload forwarded to the running instance when the app is launched again.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SecondInstancePayload {
+    pub args: Vec<String>,
+    pub cwd: String,
+}
+
+/// Bring the main window to the foreground, restoring it if it was minimized
+/// or hidden, or creating it if the app runs headless.
+pub fn focus_main_window(app: &AppHandle) {
+    match crate::windows::main_window(app) {
+        Ok(window) => {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        Err(e) => log::error!("Failed to open the main window: {}", e),
+    }
+}
+
+/// Called by the single-instance plugin inside the already running instance.
+/// The second process exits right away, so it never spawns its own sidecar;
+/// its launch flags are applied here and its arguments forwarded to the UI.
+pub fn on_second_instance(app: &AppHandle, args: Vec<String>, cwd: String) {
+    log::info!("Second instance launched with args {:?}", args);
+    let launch = crate::launch_args::LaunchArgs::parse(args.iter().skip(1).cloned());
+    if !launch.in_background() {
+        focus_main_window(app);
+    }
+    if launch.has_actions() {
+        tauri::async_runtime::spawn(crate::launch_args::apply(app.clone(), launch));
+    }
+    let _ = app.emit("app:second-instance", SecondInstancePayload { args, cwd });
+}
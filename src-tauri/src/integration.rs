@@ -0,0 +1,452 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError, SidecarState};
+
+// ============================================================================
+// File manager integration
+// ============================================================================
+//
+// Optional shortcuts to the drive outside the app, installed and removed on
+// request:
+//
+// - a GTK bookmark (`gtk-3.0/bookmarks`), shown in the sidebar of Nautilus,
+//   Nemo, Thunar and the GTK file chooser;
+// - a KDE place (`user-places.xbel`) for Dolphin and the KDE file dialogs,
+//   only when that file already exists;
+// - an "Open Proton Drive" launcher in the applications menu, which opens
+//   the drive through a `protondrive-bridge://open` link.
+//
+// Entries are recognised by their label (and, for KDE, an ID) so installing
+// again replaces them, e.g. after the port changed. In Flatpak the host's
+// files under the home directory are used, not the sandbox's.
+//
+// GVFS names WebDAV mounts itself ("WebDAV on localhost") and ignores mount
+// options and `.xdg-volume-info` for network mounts, so the name and icon
+// set with `set_mount_label` go on these entries instead: by default
+// "Proton Drive – <account>" and the `folder-remote` icon. The label is kept
+// per account (see `accounts`), and installed entries are relabelled when it
+// changes. GTK bookmarks have no icon of their own.
+
+const LABEL: &str = "Proton Drive";
+
+/// Config key of the drive's label, swapped with the account.
+pub(crate) const LABEL_KEY: &str = "mountLabel";
+
+/// Remembers the label of the installed entries.
+const CONFIG_KEY: &str = "fileManagerIntegration";
+
+const DEFAULT_ICON: &str = "folder-remote";
+
+const MAX_LABEL_LEN: usize = 100;
+
+/// Marks the KDE place as ours.
+const KDE_PLACE_ID: &str = "proton-drive-webdav-bridge";
+
+const DESKTOP_FILE: &str = "proton-drive-webdav-bridge-open.desktop";
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrationStatus {
+    pub gtk_bookmark: bool,
+    pub kde_place: bool,
+    pub desktop_entry: bool,
+}
+
+/// Name and icon of the drive as set by the user; unset fields use the
+/// defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MountLabel {
+    pub name: Option<String>,
+    /// Icon theme name or absolute path of an image
+    pub icon: Option<String>,
+}
+
+impl MountLabel {
+    /// Trim the fields, dropping empty ones, and check what is left.
+    pub(crate) fn validate(&mut self) -> Result<(), CommandError> {
+        let invalid = |message: String| CommandError::InvalidMountSettings(message);
+        self.name = self.name.take().map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+        self.icon = self.icon.take().map(|i| i.trim().to_string()).filter(|i| !i.is_empty());
+        if let Some(name) = &self.name {
+            if name.chars().count() > MAX_LABEL_LEN || name.chars().any(char::is_control) {
+                return Err(invalid(format!("name must be at most {} characters on one line", MAX_LABEL_LEN)));
+            }
+        }
+        if let Some(icon) = &self.icon {
+            let themed = icon.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+            if (!themed && !Path::new(icon).is_absolute()) || icon.chars().any(char::is_control) {
+                return Err(invalid(format!("{:?} is not an icon name or absolute path", icon)));
+            }
+        }
+        Ok(())
+    }
+
+    /// The label in effect for the account `account`.
+    fn resolve(&self, account: Option<&str>) -> EffectiveLabel {
+        EffectiveLabel {
+            name: self.name.clone().unwrap_or_else(|| match account {
+                Some(account) => format!("{} – {}", LABEL, account),
+                None => LABEL.to_string(),
+            }),
+            icon: self.icon.clone().unwrap_or_else(|| DEFAULT_ICON.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveLabel {
+    pub name: String,
+    pub icon: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MountLabelInfo {
+    /// What the user set
+    pub label: MountLabel,
+    /// What file managers are shown
+    pub effective: EffectiveLabel,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct IntegrationConfig {
+    /// Name the installed entries carry
+    installed_label: Option<String>,
+}
+
+fn current_label() -> EffectiveLabel {
+    read_config_section::<MountLabel>(LABEL_KEY).resolve(crate::accounts::active_account_id().as_deref())
+}
+
+/// Labels that mark bookmarks as ours.
+fn known_labels() -> Vec<String> {
+    let mut labels = vec![LABEL.to_string()];
+    labels.extend(read_config_section::<IntegrationConfig>(CONFIG_KEY).installed_label);
+    labels
+}
+
+fn home() -> Result<PathBuf, CommandError> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| CommandError::IoError("HOME is not set".into()))
+}
+
+/// `$XDG_CONFIG_HOME` or `$XDG_DATA_HOME` with its default, except in
+/// Flatpak where those point into the sandbox.
+fn xdg_dir(var: &str, default: &str) -> Result<PathBuf, CommandError> {
+    match std::env::var_os(var) {
+        Some(dir) if !crate::sandbox::is_flatpak() && !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => Ok(home()?.join(default)),
+    }
+}
+
+fn gtk_bookmarks_path() -> Result<PathBuf, CommandError> {
+    Ok(xdg_dir("XDG_CONFIG_HOME", ".config")?.join("gtk-3.0").join("bookmarks"))
+}
+
+fn kde_places_path() -> Result<PathBuf, CommandError> {
+    Ok(xdg_dir("XDG_DATA_HOME", ".local/share")?.join("user-places.xbel"))
+}
+
+fn desktop_entry_path() -> Result<PathBuf, CommandError> {
+    Ok(xdg_dir("XDG_DATA_HOME", ".local/share")?.join("applications").join(DESKTOP_FILE))
+}
+
+fn is_our_bookmark(line: &str, labels: &[String]) -> bool {
+    match line.split_once(' ') {
+        Some((uri, label)) => labels.iter().any(|l| l == label) && (uri.starts_with("dav://") || uri.starts_with("davs://")),
+        None => false,
+    }
+}
+
+fn remove_gtk_bookmark(content: &str, labels: &[String]) -> String {
+    content.lines().filter(|line| !is_our_bookmark(line, labels)).map(|line| format!("{}\n", line)).collect()
+}
+
+fn add_gtk_bookmark(content: &str, uri: &str, labels: &[String], label: &str) -> String {
+    format!("{}{} {}\n", remove_gtk_bookmark(content, labels), uri, label)
+}
+
+/// Our bookmarks with their label replaced by `label`.
+fn relabel_gtk_bookmarks(content: &str, labels: &[String], label: &str) -> String {
+    content
+        .lines()
+        .map(|line| match line.split_once(' ') {
+            Some((uri, _)) if is_our_bookmark(line, labels) => format!("{} {}\n", uri, label),
+            _ => format!("{}\n", line),
+        })
+        .collect()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Byte ranges of the `<bookmark>` elements carrying our ID.
+fn kde_place_ranges(xml: &str) -> Vec<(usize, usize)> {
+    let marker = format!("<ID>{}</ID>", KDE_PLACE_ID);
+    let mut ranges = Vec::new();
+    let mut from = 0;
+    while let Some(start) = xml[from..].find("<bookmark ").map(|i| from + i) {
+        let Some(end) = xml[start..].find("</bookmark>").map(|i| start + i + "</bookmark>".len()) else { break };
+        if xml[start..end].contains(&marker) {
+            // Take the indentation and line break with the element
+            let line_start = xml[..start].rfind('\n').map(|i| i + 1).unwrap_or(start);
+            let line_start = if xml[line_start..start].trim().is_empty() { line_start } else { start };
+            let end = if xml[end..].starts_with('\n') { end + 1 } else { end };
+            ranges.push((line_start, end));
+        }
+        from = end;
+    }
+    ranges
+}
+
+fn remove_kde_place(xml: &str) -> String {
+    let mut out = xml.to_string();
+    for (start, end) in kde_place_ranges(xml).into_iter().rev() {
+        out.replace_range(start..end, "");
+    }
+    out
+}
+
+/// KIO names the WebDAV schemes differently from GIO.
+fn kio_uri(uri: &str) -> String {
+    if let Some(rest) = uri.strip_prefix("davs://") {
+        format!("webdavs://{}", rest)
+    } else if let Some(rest) = uri.strip_prefix("dav://") {
+        format!("webdav://{}", rest)
+    } else {
+        uri.to_string()
+    }
+}
+
+/// `href` of our KDE place, if there is one.
+fn kde_place_href(xml: &str) -> Option<String> {
+    let (start, end) = *kde_place_ranges(xml).first()?;
+    let element = &xml[start..end];
+    let href = element.split_once("href=\"")?.1.split_once('"')?.0;
+    Some(href.replace("&quot;", "\"").replace("&gt;", ">").replace("&lt;", "<").replace("&amp;", "&"))
+}
+
+fn add_kde_place(xml: &str, uri: &str, label: &EffectiveLabel) -> Option<String> {
+    let mut xml = remove_kde_place(xml);
+    let close = xml.rfind("</xbel>")?;
+    let place = format!(
+        concat!(
+            " <bookmark href=\"{}\">\n",
+            "  <title>{}</title>\n",
+            "  <info>\n",
+            "   <metadata owner=\"http://freedesktop.org\">\n",
+            "    <bookmark:icon name=\"{}\"/>\n",
+            "   </metadata>\n",
+            "   <metadata owner=\"http://www.kde.org\">\n",
+            "    <ID>{}</ID>\n",
+            "   </metadata>\n",
+            "  </info>\n",
+            " </bookmark>\n",
+        ),
+        escape_xml(&kio_uri(uri)),
+        escape_xml(&label.name),
+        escape_xml(&label.icon),
+        KDE_PLACE_ID
+    );
+    xml.insert_str(close, &place);
+    Some(xml)
+}
+
+fn desktop_entry(label: &EffectiveLabel) -> String {
+    format!(
+        concat!(
+            "[Desktop Entry]\n",
+            "Type=Application\n",
+            "Name=Open {}\n",
+            "Comment=Open your Proton Drive in the file manager\n",
+            "Icon={}\n",
+            "Exec=xdg-open {}://open\n",
+            "Terminal=false\n",
+            "Categories=Network;FileManager;\n",
+        ),
+        label.name,
+        label.icon,
+        crate::deep_link::SCHEME
+    )
+}
+
+/// Rewrite `path` with `edit` applied to its content, if it exists.
+fn edit_file(path: &Path, edit: impl FnOnce(&str) -> Option<String>) -> Result<bool, CommandError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    match edit(&content) {
+        Some(updated) if updated != content => {
+            std::fs::write(path, updated)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn status() -> Result<IntegrationStatus, CommandError> {
+    let read = |path: PathBuf| std::fs::read_to_string(path).unwrap_or_default();
+    Ok(IntegrationStatus {
+        gtk_bookmark: read(gtk_bookmarks_path()?).lines().any(|line| is_our_bookmark(line, &known_labels())),
+        kde_place: !kde_place_ranges(&read(kde_places_path()?)).is_empty(),
+        desktop_entry: desktop_entry_path()?.is_file(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_file_manager_integration() -> Result<IntegrationStatus, CommandError> {
+    status()
+}
+
+/// Add the bookmark, KDE place and launcher for the drive, replacing earlier
+/// ones.
+#[tauri::command]
+pub async fn install_file_manager_integration(app: AppHandle) -> Result<IntegrationStatus, CommandError> {
+    let status = crate::sidecar::get_status(app.clone(), app.state::<SidecarState>()).await?;
+    let uri = crate::sidecar::local_dav_uri(&status);
+    let label = current_label();
+    let labels = known_labels();
+
+    let bookmarks = gtk_bookmarks_path()?;
+    if let Some(dir) = bookmarks.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let content = std::fs::read_to_string(&bookmarks).unwrap_or_default();
+    std::fs::write(&bookmarks, add_gtk_bookmark(&content, &uri, &labels, &label.name))?;
+
+    edit_file(&kde_places_path()?, |xml| add_kde_place(xml, &uri, &label))?;
+
+    let launcher = desktop_entry_path()?;
+    if let Some(dir) = launcher.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&launcher, desktop_entry(&label))?;
+    write_config_section(CONFIG_KEY, &IntegrationConfig { installed_label: Some(label.name.clone()) })?;
+
+    log::info!("Installed file manager integration for {}", uri);
+    self::status()
+}
+
+/// Give the installed entries the current label, keeping where they point.
+pub(crate) fn relabel() -> Result<(), CommandError> {
+    let label = current_label();
+    let labels = known_labels();
+    if labels.last() == Some(&label.name) {
+        return Ok(());
+    }
+    let installed = status()?;
+    edit_file(&gtk_bookmarks_path()?, |content| Some(relabel_gtk_bookmarks(content, &labels, &label.name)))?;
+    edit_file(&kde_places_path()?, |xml| add_kde_place(xml, &kde_place_href(xml)?, &label))?;
+    if installed.desktop_entry {
+        std::fs::write(desktop_entry_path()?, desktop_entry(&label))?;
+    }
+    if installed.gtk_bookmark || installed.kde_place || installed.desktop_entry {
+        write_config_section(CONFIG_KEY, &IntegrationConfig { installed_label: Some(label.name.clone()) })?;
+        log::info!("Relabelled file manager integration as {:?}", label.name);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_mount_label() -> Result<MountLabelInfo, CommandError> {
+    let label: MountLabel = read_config_section(LABEL_KEY);
+    Ok(MountLabelInfo { effective: current_label(), label })
+}
+
+/// Set the name and icon of the drive for the active account; unset fields
+/// go back to the defaults.
+#[tauri::command]
+pub async fn set_mount_label(mut label: MountLabel) -> Result<MountLabelInfo, CommandError> {
+    label.validate()?;
+    write_config_section(LABEL_KEY, &label)?;
+    relabel()?;
+    Ok(MountLabelInfo { effective: current_label(), label })
+}
+
+#[tauri::command]
+pub async fn remove_file_manager_integration() -> Result<IntegrationStatus, CommandError> {
+    let labels = known_labels();
+    edit_file(&gtk_bookmarks_path()?, |content| Some(remove_gtk_bookmark(content, &labels)))?;
+    edit_file(&kde_places_path()?, |xml| Some(remove_kde_place(xml)))?;
+    match std::fs::remove_file(desktop_entry_path()?) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    log::info!("Removed file manager integration");
+    status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_label() -> EffectiveLabel {
+        MountLabel::default().resolve(None)
+    }
+
+    #[test]
+    fn test_gtk_bookmark_replaces_previous() {
+        let labels = vec![LABEL.to_string()];
+        let content = "file:///home/u/Music Music\ndav://localhost:8080 Proton Drive\nsftp://host Proton Drive\n";
+        let updated = add_gtk_bookmark(content, "dav://localhost:9000", &labels, LABEL);
+        assert_eq!(updated, "file:///home/u/Music Music\nsftp://host Proton Drive\ndav://localhost:9000 Proton Drive\n");
+        assert_eq!(remove_gtk_bookmark(&updated, &labels), "file:///home/u/Music Music\nsftp://host Proton Drive\n");
+        assert_eq!(add_gtk_bookmark("", "dav://localhost:8080", &labels, LABEL), "dav://localhost:8080 Proton Drive\n");
+    }
+
+    #[test]
+    fn test_gtk_bookmark_relabel() {
+        let labels = vec![LABEL.to_string(), "Proton Drive – me@proton.me".to_string()];
+        let content = "file:///home/u/Music Music\ndav://localhost:8080 Proton Drive – me@proton.me\n";
+        let relabelled = relabel_gtk_bookmarks(content, &labels, "Work drive");
+        assert_eq!(relabelled, "file:///home/u/Music Music\ndav://localhost:8080 Work drive\n");
+        let labels = vec![LABEL.to_string(), "Work drive".to_string()];
+        assert_eq!(remove_gtk_bookmark(&relabelled, &labels), "file:///home/u/Music Music\n");
+    }
+
+    #[test]
+    fn test_kde_place_round_trip() {
+        let xml = "<?xml version=\"1.0\"?>\n<xbel>\n <bookmark href=\"file:///home/u\">\n  <title>Home</title>\n </bookmark>\n</xbel>\n";
+        let added = add_kde_place(xml, "davs://localhost:8443/Docs", &default_label()).unwrap();
+        assert!(added.contains("<bookmark href=\"webdavs://localhost:8443/Docs\">"));
+        assert!(added.contains("<bookmark:icon name=\"folder-remote\"/>"));
+        assert!(added.ends_with(" </bookmark>\n</xbel>\n"));
+        assert_eq!(kde_place_ranges(&added).len(), 1);
+        assert_eq!(kde_place_href(&added).as_deref(), Some("webdavs://localhost:8443/Docs"));
+
+        let label = EffectiveLabel { name: "Work & play".into(), icon: "drive-harddisk".into() };
+        let again = add_kde_place(&added, "dav://localhost:8080", &label).unwrap();
+        assert_eq!(kde_place_ranges(&again).len(), 1);
+        assert!(again.contains("webdav://localhost:8080"));
+        assert!(again.contains("<title>Work &amp; play</title>"));
+        assert_eq!(remove_kde_place(&again), xml);
+        assert_eq!(add_kde_place("not xbel", "dav://localhost:8080", &label), None);
+    }
+
+    #[test]
+    fn test_desktop_entry_opens_deep_link() {
+        let entry = desktop_entry(&default_label());
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains("Exec=xdg-open protondrive-bridge://open\n"));
+        assert!(entry.contains("Icon=folder-remote\n"));
+    }
+
+    #[test]
+    fn test_mount_label_defaults_and_validation() {
+        assert_eq!(MountLabel::default().resolve(Some("me@proton.me")).name, "Proton Drive – me@proton.me");
+        let mut label = MountLabel { name: Some("  ".into()), icon: Some(" /usr/share/icons/drive.svg ".into()) };
+        label.validate().unwrap();
+        assert_eq!(label, MountLabel { name: None, icon: Some("/usr/share/icons/drive.svg".into()) });
+        assert!(MountLabel { name: Some("a\nb".into()), icon: None }.validate().is_err());
+        assert!(MountLabel { name: None, icon: Some("../icon.png".into()) }.validate().is_err());
+    }
+}
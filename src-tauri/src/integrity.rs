@@ -0,0 +1,249 @@
+use bytes::Bytes;
+use futures_util::{Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::dav::DavClient;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Transfer verification
+// ============================================================================
+//
+// With `verifyTransfers` on, uploads and downloads made by the app itself
+// (file commands, drop uploads, sync, staged uploads) hash the data they
+// move with SHA-256. Once a transfer completes, its size is compared with
+// the size the bridge reports, and its hash with the SHA-256 the bridge
+// publishes in ownCloud's `oc:checksums` property when it does. A mismatch
+// does not undo the transfer; it is kept as an integrity issue, listed by
+// `list_integrity_issues` and announced with `integrity:issue`, so it can be
+// checked by hand. Transfers through the mount are not verified.
+
+const CONFIG_KEY: &str = "integrity";
+
+const ISSUES_FILE: &str = "integrity-issues.json";
+
+/// Issues kept; older ones are dropped first.
+const MAX_ISSUES: usize = 500;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IntegritySettings {
+    pub verify_transfers: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueKind {
+    SizeMismatch,
+    HashMismatch,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    pub id: u64,
+    pub direction: Direction,
+    pub remote_path: String,
+    pub local_path: Option<String>,
+    pub kind: IssueKind,
+    /// What the app sent or received: a byte count or a SHA-256
+    pub local: String,
+    /// What the bridge reports
+    pub remote: String,
+    /// Unix seconds
+    pub detected_at: u64,
+}
+
+#[derive(Default)]
+pub struct IntegrityState {
+    /// Loaded from disk on first use
+    issues: Mutex<Option<Vec<IntegrityIssue>>>,
+}
+
+impl IntegrityState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update<T>(&self, app: &AppHandle, f: impl FnOnce(&mut Vec<IntegrityIssue>) -> (T, bool)) -> Result<T, CommandError> {
+        let file = issues_file(app)?;
+        let mut guard = self.issues.lock().unwrap();
+        let issues = guard.get_or_insert_with(|| {
+            std::fs::read_to_string(&file)
+                .ok()
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default()
+        });
+        let (value, changed) = f(issues);
+        if changed {
+            let json = serde_json::to_string_pretty(issues).map_err(|e| CommandError::Unknown(e.to_string()))?;
+            std::fs::write(&file, json)?;
+        }
+        Ok(value)
+    }
+}
+
+fn issues_file(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    let dir = app.path().app_data_dir().map_err(|e| CommandError::IoError(e.to_string()))?;
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(ISSUES_FILE))
+}
+
+/// Whether transfers should be verified; callers only hash when it is set.
+pub(crate) fn enabled() -> bool {
+    read_config_section::<IntegritySettings>(CONFIG_KEY).verify_transfers
+}
+
+/// SHA-256 of the data of one transfer, fed as it streams.
+#[derive(Clone, Default)]
+pub(crate) struct TransferDigest(Arc<Mutex<Sha256>>);
+
+impl TransferDigest {
+    pub fn update(&self, chunk: &[u8]) {
+        self.0.lock().unwrap().update(chunk);
+    }
+
+    /// `stream`, hashing every chunk that passes.
+    pub fn wrap<S>(&self, stream: S) -> impl Stream<Item = std::io::Result<Bytes>> + Send + 'static
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        let digest = self.clone();
+        stream.inspect_ok(move |chunk| digest.update(chunk))
+    }
+
+    fn finish(self) -> String {
+        hex::encode(self.0.lock().unwrap().clone().finalize())
+    }
+}
+
+/// What disagrees between the two sides of a transfer, as `(kind, local,
+/// remote)`. Sides the bridge does not report are not compared.
+fn compare(size: u64, sha256: &str, remote_size: Option<u64>, remote_sha256: Option<&str>) -> Vec<(IssueKind, String, String)> {
+    let mut found = Vec::new();
+    if let Some(remote_size) = remote_size.filter(|s| *s != size) {
+        found.push((IssueKind::SizeMismatch, size.to_string(), remote_size.to_string()));
+    }
+    if let Some(remote_sha256) = remote_sha256.filter(|h| !h.eq_ignore_ascii_case(sha256)) {
+        found.push((IssueKind::HashMismatch, sha256.to_string(), remote_sha256.to_string()));
+    }
+    found
+}
+
+/// Check a completed transfer of `size` bytes hashed by `digest` against
+/// what the bridge reports for `remote`, and record any mismatch. Failing to
+/// ask the bridge is only logged.
+pub(crate) async fn verify(
+    app: &AppHandle,
+    client: &DavClient,
+    direction: Direction,
+    remote: &str,
+    local: Option<&Path>,
+    size: u64,
+    digest: TransferDigest,
+) {
+    let sha256 = digest.finish();
+    let remote_size = match client.stat(remote).await {
+        Ok(entry) => entry.and_then(|e| e.size),
+        Err(e) => {
+            log::warn!("Could not verify {}: {}", remote, e);
+            return;
+        }
+    };
+    let remote_sha256 = client.sha256(remote).await.unwrap_or_else(|e| {
+        log::debug!("No checksum for {}: {}", remote, e);
+        None
+    });
+    let found = compare(size, &sha256, remote_size, remote_sha256.as_deref());
+    if found.is_empty() {
+        log::debug!("Verified {} ({} bytes, sha256 {})", remote, size, sha256);
+        return;
+    }
+
+    let state = app.state::<IntegrityState>();
+    for (kind, local_value, remote_value) in found {
+        log::warn!("Integrity check of {} failed: {:?} (local {}, remote {})", remote, kind, local_value, remote_value);
+        let recorded = state.update(app, |issues| {
+            let issue = IntegrityIssue {
+                id: issues.last().map_or(1, |i| i.id + 1),
+                direction,
+                remote_path: remote.to_string(),
+                local_path: local.map(|p| p.display().to_string()),
+                kind,
+                local: local_value,
+                remote: remote_value,
+                detected_at: crate::sidecar::unix_now(),
+            };
+            issues.push(issue.clone());
+            let excess = issues.len().saturating_sub(MAX_ISSUES);
+            issues.drain(..excess);
+            (issue, true)
+        });
+        match recorded {
+            Ok(issue) => {
+                let _ = app.emit("integrity:issue", issue);
+            }
+            Err(e) => log::warn!("Failed to record an integrity issue: {}", e),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_verify_transfers() -> Result<bool, CommandError> {
+    Ok(enabled())
+}
+
+#[tauri::command]
+pub async fn set_verify_transfers(enabled: bool) -> Result<bool, CommandError> {
+    write_config_section(CONFIG_KEY, &IntegritySettings { verify_transfers: enabled })?;
+    log::info!("Transfer verification {}", if enabled { "enabled" } else { "disabled" });
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub async fn list_integrity_issues(app: AppHandle, state: State<'_, IntegrityState>) -> Result<Vec<IntegrityIssue>, CommandError> {
+    state.update(&app, |issues| (issues.clone(), false))
+}
+
+#[tauri::command]
+pub async fn clear_integrity_issues(app: AppHandle, state: State<'_, IntegrityState>) -> Result<(), CommandError> {
+    state.update(&app, |issues| {
+        issues.clear();
+        ((), true)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_of_streamed_chunks() {
+        let digest = TransferDigest::default();
+        let chunks = vec![Ok(Bytes::from_static(b"ab")), Ok(Bytes::from_static(b"c"))];
+        let stream = digest.wrap(futures_util::stream::iter(chunks));
+        let collected: Vec<Bytes> = tauri::async_runtime::block_on(stream.try_collect()).unwrap();
+        assert_eq!(collected.len(), 2);
+        assert_eq!(digest.finish(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn test_compare_only_what_the_bridge_reports() {
+        assert!(compare(3, "abc", None, None).is_empty());
+        assert!(compare(3, "abc", Some(3), Some("ABC")).is_empty());
+        let found = compare(3, "abc", Some(4), Some("def"));
+        assert_eq!(found[0], (IssueKind::SizeMismatch, "3".to_string(), "4".to_string()));
+        assert_eq!(found[1].0, IssueKind::HashMismatch);
+    }
+}
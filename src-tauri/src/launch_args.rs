@@ -0,0 +1,150 @@
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::{CommandError, SidecarState};
+
+// ============================================================================
+// Launch arguments
+// ============================================================================
+//
+// The GUI binary understands a few flags so it can be scripted or started
+// into the background at login:
+//
+//   --minimized       start with the main window minimized
+//   --headless        run without a window: start the bridge, auto-mount and
+//                     the background tasks, but no WebView
+//   --mount           start the bridge and mount the drive
+//   --account <id>    switch to this account first
+//
+// They are applied once the app is set up, without waiting for the UI. A
+// second launch with flags hands them to the running instance, which applies
+// them the same way instead of only raising its window. Unknown arguments are
+// logged and ignored; a `protondrive-bridge://` link is left to `deep_link`.
+//
+// A headless instance keeps running with no window open. Launching the app
+// again without `--headless` or `--minimized` opens the main window in it.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LaunchArgs {
+    pub minimized: bool,
+    pub headless: bool,
+    pub mount: bool,
+    pub account: Option<String>,
+}
+
+impl LaunchArgs {
+    /// Parse arguments, without the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--minimized" => parsed.minimized = true,
+                "--headless" => parsed.headless = true,
+                "--mount" => parsed.mount = true,
+                "--account" => match args.next() {
+                    Some(id) => parsed.account = Some(id),
+                    None => log::warn!("--account needs an account id"),
+                },
+                other if other.starts_with(&format!("{}:", crate::deep_link::SCHEME)) => {}
+                other => match other.strip_prefix("--account=") {
+                    Some(id) => parsed.account = Some(id.to_string()),
+                    None => log::warn!("Ignoring unknown argument {:?}", other),
+                },
+            }
+        }
+        parsed.account = parsed.account.map(|id| id.trim().to_string()).filter(|id| !id.is_empty());
+        parsed
+    }
+
+    /// Whether the arguments ask for anything beyond a plain launch.
+    pub fn has_actions(&self) -> bool {
+        self.minimized || self.headless || self.mount || self.account.is_some()
+    }
+
+    /// Whether the main window should stay out of the way.
+    pub fn in_background(&self) -> bool {
+        self.minimized || self.headless
+    }
+}
+
+/// Arguments the app was started with.
+pub fn from_env() -> LaunchArgs {
+    LaunchArgs::parse(std::env::args().skip(1))
+}
+
+/// Create the main window unless running headless, minimized if asked.
+/// Called from `setup`.
+pub fn apply_window(app: &AppHandle, args: &LaunchArgs) {
+    if args.headless {
+        log::info!("Running headless, without a window");
+        return;
+    }
+    match crate::windows::main_window(app) {
+        Ok(window) if args.minimized => {
+            let _ = window.minimize();
+        }
+        Ok(_) => {}
+        Err(e) => log::error!("Failed to create the main window: {}", e),
+    }
+}
+
+async fn ensure_sidecar(app: &AppHandle) -> Result<(), CommandError> {
+    match crate::sidecar::start_sidecar(app.clone(), app.state::<SidecarState>(), None).await {
+        Ok(pid) => {
+            log::info!("Started the bridge from the command line (pid {})", pid);
+            Ok(())
+        }
+        Err(CommandError::SidecarAlreadyRunning) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Run the backend actions the arguments ask for: switch account, start the
+/// bridge, mount the drive.
+pub async fn apply(app: AppHandle, args: LaunchArgs) {
+    if let Some(id) = &args.account {
+        if let Err(e) = crate::accounts::switch_account(app.clone(), app.state::<SidecarState>(), id.clone()).await {
+            log::error!("--account {}: {}", id, e);
+            return;
+        }
+    }
+    if args.headless || args.mount {
+        if let Err(e) = ensure_sidecar(&app).await {
+            log::error!("Failed to start the bridge from the command line: {}", e);
+            return;
+        }
+    }
+    if args.mount {
+        crate::auto_mount::mount_when_ready(app).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> LaunchArgs {
+        LaunchArgs::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        assert_eq!(parse(&[]), LaunchArgs::default());
+        assert!(!parse(&[]).has_actions());
+        let args = parse(&["--minimized", "--mount", "--account", "work"]);
+        assert_eq!(
+            args,
+            LaunchArgs { minimized: true, headless: false, mount: true, account: Some("work".into()) }
+        );
+        assert!(args.in_background());
+        assert_eq!(parse(&["--account=home", "--headless"]).account.as_deref(), Some("home"));
+    }
+
+    #[test]
+    fn test_parse_ignores_unknown_and_incomplete() {
+        assert_eq!(parse(&["--verbose", "file.txt"]), LaunchArgs::default());
+        assert_eq!(parse(&["protondrive-bridge://mount"]), LaunchArgs::default());
+        assert_eq!(parse(&["--account"]).account, None);
+        assert_eq!(parse(&["--account", "  "]).account, None);
+    }
+}
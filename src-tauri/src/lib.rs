@@ -1,4 +1,92 @@
+mod accounts;
+mod activity;
+mod app_access;
+mod app_update;
+mod auth_guard;
+mod auto_mount;
+mod autostart;
+mod backup_jobs;
+mod bandwidth;
+mod benchmark;
+mod browser;
+mod cache;
+mod config_schema;
+mod conflicts;
+mod connection_test;
+mod crash_reports;
+mod dav;
+mod db;
+mod deep_link;
+mod diagnostics;
+mod drop_folder;
+mod feature_flags;
+mod file_manager;
+mod gateway;
+#[cfg(target_os = "linux")]
+mod gio_worker;
+mod i18n;
+mod idle;
+mod ignore;
+mod instance;
+mod integration;
+mod integrity;
+mod launch_args;
+mod lifecycle;
+mod logout;
+mod metrics;
+mod mount_health;
+mod mount_operation;
+mod mount_provider;
+mod mounts;
+mod name_mapping;
+mod network;
+mod network_sharing;
+mod notifications;
+mod offline;
+mod onboarding;
+mod operations;
+mod photo_backup;
+mod power;
+mod prefetch;
+mod process;
+mod proxy;
+mod quota;
+mod read_only;
+mod readahead;
+mod recent;
+mod remote;
+mod sandbox;
+mod search;
+mod settings_backup;
+mod share_links;
 mod sidecar;
+mod sidecar_client;
+mod sidecar_commands;
+mod special_files;
+mod status;
+mod status_schema;
+mod supervisor;
+mod sync;
+mod system_requirements;
+#[cfg(feature = "mock-sidecar")]
+pub mod test_support;
+mod thumbnails;
+mod tls;
+mod trace;
+mod transfer_concurrency;
+mod transfers;
+mod transport;
+mod trash;
+mod travel;
+mod tray;
+mod updater;
+mod upload_queue;
+mod upload_spool;
+mod versions;
+mod volume_monitor;
+mod webdav_auth;
+mod webdav_compat;
+mod windows;
 
 #[cfg(debug_assertions)]
 use crate::sidecar::emit_test_log;
@@ -6,15 +94,104 @@ use crate::sidecar::emit_test_log;
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   use crate::sidecar::{
-    SidecarState, start_sidecar, stop_sidecar, get_status, login,
-    set_network_port, purge_cache, open_in_files,
-    mount_drive, unmount_drive, check_mount_status, logout, get_autostart, set_autostart,
+    SidecarState, start_sidecar, stop_sidecar, restart_sidecar, get_status, login,
+    set_network_port, purge_cache, open_in_files, reveal_in_files, get_mount_local_path,
+    mount_drive, unmount_drive, check_mount_status,
     list_accounts, get_account
   };
+  use crate::travel::{get_travel_mode, enable_travel_mode, disable_travel_mode};
+  use crate::logout::logout;
+  use crate::transfers::{list_active_transfers, cancel_transfer};
+  use crate::bandwidth::{get_bandwidth_limit, set_bandwidth_limit};
+  use crate::tls::{get_tls_settings, set_tls_settings, rotate_certificate, enable_https};
+  use crate::windows::open_window;
+  use crate::browser::{list_remote_directory, cancel_remote_listing};
+  use crate::remote::{server_side_copy, list_remote_folders, set_remote_path, stat_remote_file, create_remote_directory, upload_file, download_file};
+  use crate::network_sharing::{get_network_sharing, set_network_sharing, set_sharing_credentials, list_network_interfaces, list_connected_clients, set_bind_address};
+  use crate::feature_flags::{list_feature_flags, set_feature_flag};
+  use crate::onboarding::{get_onboarding_state, advance_onboarding};
+  use crate::system_requirements::check_system_requirements;
+  use crate::notifications::{get_notification_settings, set_notification_settings};
+  use crate::mounts::{list_mounts, add_mount, remove_mount, mount_by_id, unmount_by_id};
+  use crate::cache::{get_cache_stats, clear_metadata_cache, list_cache_entries, purge_cache_path, get_cache_policy, set_cache_policy};
+  use crate::auto_mount::{get_auto_mount, set_auto_mount};
+  use crate::mount_operation::{get_mount_retry_settings, set_mount_retry_settings, cancel_mount};
+  use crate::sandbox::{get_sandbox_info, set_sandbox_settings};
+  use crate::accounts::{switch_account, get_account_config, set_account_config};
+  use crate::read_only::{get_read_only, set_read_only};
+  use crate::quota::{get_quota, get_quota_settings, set_quota_settings};
+  use crate::upload_queue::{enqueue_upload, get_drop_upload_settings, set_drop_upload_settings};
+  use crate::search::search_remote;
+  use crate::prefetch::prefetch_path;
+  use crate::offline::{pin_path, unpin_path, list_pinned};
+  use crate::sync::{list_sync_pairs, add_sync_pair, remove_sync_pair, sync_now};
+  use crate::drop_folder::{get_drop_folder, set_drop_folder};
+  use crate::conflicts::{list_conflicts, resolve_conflict};
+  use crate::trash::{list_trash, restore_from_trash, empty_trash};
+  use crate::share_links::{create_share_link, revoke_share_link};
+  use crate::activity::get_recent_activity;
+  use crate::webdav_auth::{get_webdav_credentials, set_webdav_auth};
+  use crate::transport::{get_transport, set_transport};
+  use crate::sidecar_client::{get_bridge_config, set_bridge_config};
+  use crate::versions::get_versions;
+  use crate::updater::{apply_sidecar_update, check_for_sidecar_update};
+  use crate::app_update::{check_app_update, download_app_update, get_update_channel, set_update_channel};
+  use crate::autostart::{get_autostart, set_autostart};
+  use crate::diagnostics::export_diagnostics;
+  use crate::crash_reports::{delete_crash_report, get_telemetry, list_crash_reports, set_telemetry};
+  use crate::metrics::{get_metrics, get_metrics_endpoint, set_metrics_endpoint};
+  use crate::integration::{get_file_manager_integration, install_file_manager_integration, remove_file_manager_integration, get_mount_label, set_mount_label};
+  use crate::network::get_network_status;
+  use crate::proxy::{get_proxy, set_proxy, test_proxy};
+  use crate::connection_test::run_connection_test;
+  use crate::benchmark::run_benchmark;
+  use crate::i18n::{get_locale, set_locale};
+  use crate::lifecycle::get_lifecycle_state;
+  use crate::sidecar_commands::{list_running_commands, cancel_command, get_command_timeouts, set_command_timeout};
+  use crate::operations::list_pending_operations;
+  use crate::webdav_compat::probe_webdav_compat;
+  use crate::readahead::{get_readahead, set_readahead};
+  use crate::upload_spool::{list_pending_uploads, retry_pending_upload, discard_pending_upload, get_upload_spool_settings, set_upload_spool_settings};
+  use crate::integrity::{get_verify_transfers, set_verify_transfers, list_integrity_issues, clear_integrity_issues};
+  use crate::config_schema::validate_config;
+  use crate::settings_backup::{export_settings, import_settings};
+  use crate::idle::{get_idle_policy, set_idle_policy};
+  use crate::app_access::{get_app_access, set_app_access, list_app_access_denials};
+  use crate::auth_guard::{get_auth_audit_log, unban_auth_client};
+  use crate::mount_health::{get_mount_health_settings, set_mount_health_settings, get_mount_health};
+  use crate::recent::{get_recent_files, open_recent_file};
+  use crate::thumbnails::get_thumbnail;
+  use crate::photo_backup::{get_photo_backup, set_photo_backup, get_photo_backup_status, pause_photo_backup, resume_photo_backup};
+  use crate::backup_jobs::{list_backup_jobs, add_backup_job, remove_backup_job, run_backup_now};
+  use crate::ignore::{get_ignore_patterns, set_ignore_patterns};
+  use crate::special_files::{get_special_file_settings, set_special_file_settings};
+  use crate::name_mapping::{list_renamed_items, get_name_mapping, set_name_mapping};
+  use crate::transfer_concurrency::{get_transfer_concurrency, set_transfer_concurrency};
 
   let builder = tauri::Builder::default()
-    .plugin(tauri_plugin_autostart::Builder::new().build())
+    // Must be registered first so a second launch exits before any other
+    // plugin or the sidecar is initialised.
+    .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+      crate::instance::on_second_instance(app, args, cwd);
+    }))
+    .plugin(crate::autostart::plugin())
+    .plugin(tauri_plugin_deep_link::init())
     .setup(|app| {
+      // Before anything reads the config, and only once a second launch
+      // has exited
+      let config_migration = crate::config_schema::migrate();
+      {
+        use tauri::Manager;
+        app.manage(crate::bandwidth::BandwidthState::from_config());
+        app.manage(crate::transfer_concurrency::TransferConcurrencyState::from_config());
+        app.manage(crate::read_only::ReadOnlyState::from_config());
+        app.manage(crate::app_access::AppAccessState::from_config());
+        app.manage(crate::network_sharing::NetworkSharingState::from_config());
+        app.manage(crate::feature_flags::FeatureFlagState::from_config());
+        app.manage(crate::cache::MetadataCache::from_config());
+        app.manage(crate::readahead::ReadaheadState::from_config());
+        app.manage(crate::upload_spool::UploadSpool::from_config());
+      }
       if cfg!(debug_assertions) {
         app.handle().plugin(
           tauri_plugin_log::Builder::default()
@@ -22,18 +199,109 @@ pub fn run() {
             .build(),
         )?;
       }
+      crate::crash_reports::install(app.handle());
+      match config_migration {
+        Ok(Some(from)) => log::info!("Migrated config.json from layout {} to {}", from, crate::config_schema::CONFIG_VERSION),
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to migrate config.json: {}", e),
+      }
+      crate::i18n::init();
+      {
+        use tauri::Manager;
+        app.manage(crate::mount_provider::for_platform(app.handle()));
+      }
+      let launch = crate::launch_args::from_env();
+      crate::launch_args::apply_window(app.handle(), &launch);
+      crate::deep_link::install(app.handle());
+      crate::autostart::reconcile(app.handle());
+      tauri::async_runtime::spawn(crate::feature_flags::refresh_remote_defaults(app.handle().clone()));
+      let handle = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+        crate::process::recover_orphan(&handle).await;
+        // Refused adoption is logged; `start_sidecar` reports it to the user
+        let _ = crate::process::adopt_running(&handle).await;
+      });
+      tauri::async_runtime::spawn(crate::status::watch(app.handle().clone()));
+      crate::volume_monitor::spawn(app.handle().clone());
+      crate::power::spawn(app.handle().clone());
+      crate::network::spawn(app.handle().clone());
+      let proxy_app = app.handle().clone();
+      tauri::async_runtime::spawn(async move { crate::proxy::prepare(&proxy_app).await });
+      // `--mount` mounts on its own once it has started the bridge
+      if !launch.mount {
+        tauri::async_runtime::spawn(crate::auto_mount::run(app.handle().clone()));
+      }
+      tauri::async_runtime::spawn(crate::quota::watch(app.handle().clone()));
+      tauri::async_runtime::spawn(crate::cache::run_scheduler(app.handle().clone()));
+      tauri::async_runtime::spawn(crate::offline::run(app.handle().clone()));
+      tauri::async_runtime::spawn(crate::sync::run(app.handle().clone()));
+      tauri::async_runtime::spawn(crate::upload_spool::run(app.handle().clone()));
+      tauri::async_runtime::spawn(crate::activity::run(app.handle().clone()));
+      crate::drop_folder::spawn(app.handle());
+      crate::photo_backup::spawn(app.handle());
+      tauri::async_runtime::spawn(crate::backup_jobs::run(app.handle().clone()));
+      crate::tray::install(app.handle());
+      tauri::async_runtime::spawn(crate::metrics::run(app.handle().clone()));
+      tauri::async_runtime::spawn(crate::idle::run(app.handle().clone()));
+      tauri::async_runtime::spawn(crate::mount_health::run(app.handle().clone()));
+      if launch.has_actions() {
+        tauri::async_runtime::spawn(crate::launch_args::apply(app.handle().clone(), launch));
+      }
       Ok(())
     })
     .plugin(tauri_plugin_shell::init())
     .plugin(tauri_plugin_opener::init())
-    .plugin(tauri_plugin_autostart::Builder::new().build())
-    .manage(SidecarState::new());
+    .plugin(tauri_plugin_notification::init())
+    .plugin(crate::app_update::plugin())
+    .manage(SidecarState::new())
+    .manage(crate::lifecycle::LifecycleManager::new())
+    .manage(crate::sidecar_commands::RunningCommands::new())
+    .manage(crate::operations::OperationRegistry::new())
+    .manage(crate::browser::BrowserState::new())
+    .manage(crate::status::StatusCache::new())
+    .manage(crate::gateway::GatewayState::new())
+    .manage(crate::transfers::TransferState::new())
+    .manage(crate::auth_guard::AuthGuardState::new())
+    .manage(crate::travel::TravelModeState::new())
+    .manage(crate::mount_health::MountHealthState::new())
+    .manage(crate::tls::TlsState::new())
+    .manage(crate::mount_operation::MountOperations::new())
+    .manage(crate::upload_queue::UploadQueue::new())
+    .manage(crate::integrity::IntegrityState::new())
+    .manage(crate::prefetch::PrefetchState::new())
+    .manage(crate::offline::OfflineState::new())
+    .manage(crate::sync::SyncState::new())
+    .manage(crate::drop_folder::DropFolderState::new())
+    .manage(crate::photo_backup::PhotoBackupState::new())
+    .manage(crate::backup_jobs::BackupJobsState::new())
+    .manage(crate::db::Database::new())
+    .manage(crate::conflicts::ConflictRegistry::new())
+    .manage(crate::activity::ActivityFeed::new())
+    .manage(crate::thumbnails::ThumbnailState::new())
+    .manage(crate::webdav_auth::WebdavAuthState::new())
+    .manage(crate::sidecar_client::SidecarClient::new())
+    .manage(crate::versions::VersionState::new())
+    .manage(crate::updater::UpdaterState::new())
+    .manage(crate::app_update::AppUpdateState::new())
+    .manage(crate::metrics::MetricsState::new())
+    .manage(crate::power::PowerState::new())
+    .manage(crate::network::NetworkState::new())
+    .manage(crate::proxy::ProxyState::new())
+    .manage(crate::trace::TraceState::new())
+    .on_window_event(|window, event| {
+      // Files dropped on any window are uploaded to the drop destination
+      if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+        use tauri::Manager;
+        crate::upload_queue::enqueue(window.app_handle(), paths.clone(), None);
+      }
+    });
 
   // Conditionally include dev-only commands in debug builds
   #[cfg(debug_assertions)]
   let builder = builder.invoke_handler(tauri::generate_handler![
       start_sidecar,
       stop_sidecar,
+      restart_sidecar,
       get_status,
       login,
       set_network_port,
@@ -48,12 +316,183 @@ pub fn run() {
       list_accounts,
       get_account,
       emit_test_log,
+      get_travel_mode,
+      enable_travel_mode,
+      disable_travel_mode,
+      list_active_transfers,
+      cancel_transfer,
+      get_bandwidth_limit,
+      set_bandwidth_limit,
+      get_tls_settings,
+      set_tls_settings,
+      rotate_certificate,
+      enable_https,
+      open_window,
+      server_side_copy,
+      get_network_sharing,
+      set_network_sharing,
+      set_sharing_credentials,
+      list_network_interfaces,
+      list_connected_clients,
+      list_feature_flags,
+      set_feature_flag,
+      get_onboarding_state,
+      advance_onboarding,
+      check_system_requirements,
+      get_notification_settings,
+      set_notification_settings,
+      list_remote_folders,
+      set_remote_path,
+      list_mounts,
+      add_mount,
+      remove_mount,
+      mount_by_id,
+      unmount_by_id,
+      get_cache_stats,
+      clear_metadata_cache,
+      list_cache_entries,
+      purge_cache_path,
+      get_auto_mount,
+      set_auto_mount,
+      get_mount_retry_settings,
+      set_mount_retry_settings,
+      cancel_mount,
+      get_sandbox_info,
+      set_sandbox_settings,
+      switch_account,
+      get_account_config,
+      set_account_config,
+      get_read_only,
+      set_read_only,
+      get_quota,
+      get_quota_settings,
+      set_quota_settings,
+      list_remote_directory,
+      cancel_remote_listing,
+      stat_remote_file,
+      create_remote_directory,
+      upload_file,
+      download_file,
+      enqueue_upload,
+      get_drop_upload_settings,
+      set_drop_upload_settings,
+      search_remote,
+      prefetch_path,
+      get_cache_policy,
+      set_cache_policy,
+      pin_path,
+      unpin_path,
+      list_pinned,
+      list_sync_pairs,
+      add_sync_pair,
+      remove_sync_pair,
+      sync_now,
+      get_drop_folder,
+      set_drop_folder,
+      list_conflicts,
+      resolve_conflict,
+      list_trash,
+      restore_from_trash,
+      empty_trash,
+      create_share_link,
+      revoke_share_link,
+      get_recent_activity,
+      set_webdav_auth,
+      get_webdav_credentials,
+      get_transport,
+      set_transport,
+      set_bind_address,
+      get_bridge_config,
+      set_bridge_config,
+      get_versions,
+      check_for_sidecar_update,
+      apply_sidecar_update,
+      check_app_update,
+      download_app_update,
+      get_update_channel,
+      set_update_channel,
+      export_diagnostics,
+      list_crash_reports,
+      delete_crash_report,
+      get_telemetry,
+      set_telemetry,
+      get_metrics,
+      get_metrics_endpoint,
+      set_metrics_endpoint,
+      get_file_manager_integration,
+      install_file_manager_integration,
+      remove_file_manager_integration,
+      get_network_status,
+      get_proxy,
+      set_proxy,
+      test_proxy,
+      run_connection_test,
+      run_benchmark,
+      get_locale,
+      set_locale,
+      get_lifecycle_state,
+      list_running_commands,
+      cancel_command,
+      get_command_timeouts,
+      set_command_timeout,
+      list_pending_operations,
+      probe_webdav_compat,
+      get_readahead,
+      set_readahead,
+      list_pending_uploads,
+      retry_pending_upload,
+      discard_pending_upload,
+      get_upload_spool_settings,
+      set_upload_spool_settings,
+      get_verify_transfers,
+      set_verify_transfers,
+      list_integrity_issues,
+      clear_integrity_issues,
+      validate_config,
+      export_settings,
+      import_settings,
+      get_idle_policy,
+      set_idle_policy,
+      get_app_access,
+      set_app_access,
+      list_app_access_denials,
+      get_auth_audit_log,
+      unban_auth_client,
+      get_mount_health_settings,
+      set_mount_health_settings,
+      get_mount_health,
+      get_mount_label,
+      set_mount_label,
+      reveal_in_files,
+      get_mount_local_path,
+      get_recent_files,
+      open_recent_file,
+      get_thumbnail,
+      get_photo_backup,
+      set_photo_backup,
+      get_photo_backup_status,
+      pause_photo_backup,
+      resume_photo_backup,
+      list_backup_jobs,
+      add_backup_job,
+      remove_backup_job,
+      run_backup_now,
+      get_ignore_patterns,
+      set_ignore_patterns,
+      get_special_file_settings,
+      set_special_file_settings,
+      list_renamed_items,
+      get_name_mapping,
+      set_name_mapping,
+      get_transfer_concurrency,
+      set_transfer_concurrency,
   ]);
 
   #[cfg(not(debug_assertions))]
   let builder = builder.invoke_handler(tauri::generate_handler![
       start_sidecar,
       stop_sidecar,
+      restart_sidecar,
       get_status,
       login,
       set_network_port,
@@ -67,9 +506,190 @@ pub fn run() {
       set_autostart,
       list_accounts,
       get_account,
+      get_travel_mode,
+      enable_travel_mode,
+      disable_travel_mode,
+      list_active_transfers,
+      cancel_transfer,
+      get_bandwidth_limit,
+      set_bandwidth_limit,
+      get_tls_settings,
+      set_tls_settings,
+      rotate_certificate,
+      enable_https,
+      open_window,
+      server_side_copy,
+      get_network_sharing,
+      set_network_sharing,
+      set_sharing_credentials,
+      list_network_interfaces,
+      list_connected_clients,
+      list_feature_flags,
+      set_feature_flag,
+      get_onboarding_state,
+      advance_onboarding,
+      check_system_requirements,
+      get_notification_settings,
+      set_notification_settings,
+      list_remote_folders,
+      set_remote_path,
+      list_mounts,
+      add_mount,
+      remove_mount,
+      mount_by_id,
+      unmount_by_id,
+      get_cache_stats,
+      clear_metadata_cache,
+      list_cache_entries,
+      purge_cache_path,
+      get_auto_mount,
+      set_auto_mount,
+      get_mount_retry_settings,
+      set_mount_retry_settings,
+      cancel_mount,
+      get_sandbox_info,
+      set_sandbox_settings,
+      switch_account,
+      get_account_config,
+      set_account_config,
+      get_read_only,
+      set_read_only,
+      get_quota,
+      get_quota_settings,
+      set_quota_settings,
+      list_remote_directory,
+      cancel_remote_listing,
+      stat_remote_file,
+      create_remote_directory,
+      upload_file,
+      download_file,
+      enqueue_upload,
+      get_drop_upload_settings,
+      set_drop_upload_settings,
+      search_remote,
+      prefetch_path,
+      get_cache_policy,
+      set_cache_policy,
+      pin_path,
+      unpin_path,
+      list_pinned,
+      list_sync_pairs,
+      add_sync_pair,
+      remove_sync_pair,
+      sync_now,
+      get_drop_folder,
+      set_drop_folder,
+      list_conflicts,
+      resolve_conflict,
+      list_trash,
+      restore_from_trash,
+      empty_trash,
+      create_share_link,
+      revoke_share_link,
+      get_recent_activity,
+      set_webdav_auth,
+      get_webdav_credentials,
+      get_transport,
+      set_transport,
+      set_bind_address,
+      get_bridge_config,
+      set_bridge_config,
+      get_versions,
+      check_for_sidecar_update,
+      apply_sidecar_update,
+      check_app_update,
+      download_app_update,
+      get_update_channel,
+      set_update_channel,
+      export_diagnostics,
+      list_crash_reports,
+      delete_crash_report,
+      get_telemetry,
+      set_telemetry,
+      get_metrics,
+      get_metrics_endpoint,
+      set_metrics_endpoint,
+      get_file_manager_integration,
+      install_file_manager_integration,
+      remove_file_manager_integration,
+      get_network_status,
+      get_proxy,
+      set_proxy,
+      test_proxy,
+      run_connection_test,
+      run_benchmark,
+      get_locale,
+      set_locale,
+      get_lifecycle_state,
+      list_running_commands,
+      cancel_command,
+      get_command_timeouts,
+      set_command_timeout,
+      list_pending_operations,
+      probe_webdav_compat,
+      get_readahead,
+      set_readahead,
+      list_pending_uploads,
+      retry_pending_upload,
+      discard_pending_upload,
+      get_upload_spool_settings,
+      set_upload_spool_settings,
+      get_verify_transfers,
+      set_verify_transfers,
+      list_integrity_issues,
+      clear_integrity_issues,
+      validate_config,
+      export_settings,
+      import_settings,
+      get_idle_policy,
+      set_idle_policy,
+      get_app_access,
+      set_app_access,
+      list_app_access_denials,
+      get_auth_audit_log,
+      unban_auth_client,
+      get_mount_health_settings,
+      set_mount_health_settings,
+      get_mount_health,
+      get_mount_label,
+      set_mount_label,
+      reveal_in_files,
+      get_mount_local_path,
+      get_recent_files,
+      open_recent_file,
+      get_thumbnail,
+      get_photo_backup,
+      set_photo_backup,
+      get_photo_backup_status,
+      pause_photo_backup,
+      resume_photo_backup,
+      list_backup_jobs,
+      add_backup_job,
+      remove_backup_job,
+      run_backup_now,
+      get_ignore_patterns,
+      set_ignore_patterns,
+      get_special_file_settings,
+      set_special_file_settings,
+      list_renamed_items,
+      get_name_mapping,
+      set_name_mapping,
+      get_transfer_concurrency,
+      set_transfer_concurrency,
   ]);
 
+  // A headless instance outlives its windows; a plain one quits with the last
+  let headless = crate::launch_args::from_env().headless;
   builder
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(move |app, event| match event {
+      tauri::RunEvent::ExitRequested { code: None, api, .. } if headless => api.prevent_exit(),
+      tauri::RunEvent::Exit => {
+        tauri::Manager::state::<crate::cache::MetadataCache>(app).flush();
+        #[cfg(target_os = "linux")]
+        crate::gio_worker::shutdown();
+      }
+      _ => {}
+    });
 }
@@ -0,0 +1,235 @@
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Server and mount lifecycle
+// ============================================================================
+//
+// Starting, stopping, mounting, unmounting and moving the share to another
+// port each take seconds and touch the same process and mounts, so they must
+// not overlap: mounting while the bridge is still spawning, or changing the
+// port halfway through a mount, leaves things in a state nobody asked for.
+// Those commands run through `guarded`, which moves the tracked state to an
+// in-progress one (`starting`, `mounting`, ...) and rejects any other
+// command with `LIFECYCLE_BUSY` until it settles. Calls made from within a
+// guarded command (the unmount and remount around a port change) are part
+// of it and are not checked again.
+//
+// Settled states only track what was last seen: the bridge can exit or the
+// mount disappear on their own, so status probes and mount checks correct
+// them through `observe_server` and `observe_mount`. Every change is emitted
+// as `lifecycle:transition`.
+
+tokio::task_local! {
+    static IN_TRANSITION: Operation;
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LifecycleState {
+    Stopped,
+    Starting,
+    Running,
+    Mounting,
+    Mounted,
+    Unmounting,
+    /// The share is moving to another port
+    Reconfiguring,
+    Stopping,
+}
+
+impl LifecycleState {
+    fn in_progress(self) -> bool {
+        matches!(self, Self::Starting | Self::Mounting | Self::Unmounting | Self::Reconfiguring | Self::Stopping)
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Stopped => "stopped",
+            Self::Starting => "starting the bridge",
+            Self::Running => "running",
+            Self::Mounting => "mounting the drive",
+            Self::Mounted => "mounted",
+            Self::Unmounting => "unmounting the drive",
+            Self::Reconfiguring => "changing the port",
+            Self::Stopping => "stopping the bridge",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    Start,
+    Stop,
+    /// Stop and start again, as one transition
+    Restart,
+    Mount,
+    Unmount,
+    Reconfigure,
+}
+
+impl Operation {
+    fn state(self) -> LifecycleState {
+        match self {
+            Self::Start | Self::Restart => LifecycleState::Starting,
+            Self::Stop => LifecycleState::Stopping,
+            Self::Mount => LifecycleState::Mounting,
+            Self::Unmount => LifecycleState::Unmounting,
+            Self::Reconfigure => LifecycleState::Reconfiguring,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Transition {
+    pub from: LifecycleState,
+    pub to: LifecycleState,
+    /// The command behind the change; `None` when it was observed
+    pub operation: Option<Operation>,
+}
+
+pub struct LifecycleManager {
+    state: Mutex<LifecycleState>,
+}
+
+impl LifecycleManager {
+    pub fn new() -> Self {
+        Self { state: Mutex::new(LifecycleState::Stopped) }
+    }
+
+    pub fn state(&self) -> LifecycleState {
+        *self.state.lock().unwrap()
+    }
+}
+
+/// Check that `operation` may start from `current`.
+fn begin(current: LifecycleState, operation: Operation) -> Result<LifecycleState, CommandError> {
+    if current.in_progress() {
+        return Err(CommandError::LifecycleBusy(format!("Still {}", current.describe())));
+    }
+    Ok(operation.state())
+}
+
+/// Where `operation`, started from `previous`, leaves things.
+fn settle<T>(previous: LifecycleState, operation: Operation, result: &Result<T, CommandError>) -> LifecycleState {
+    match (operation, result) {
+        (Operation::Start | Operation::Restart, Ok(_) | Err(CommandError::SidecarAlreadyRunning)) => LifecycleState::Running,
+        (Operation::Restart, Err(_)) => LifecycleState::Stopped,
+        (Operation::Stop, Ok(_) | Err(CommandError::SidecarNotRunning)) => LifecycleState::Stopped,
+        (Operation::Mount, Ok(_)) => LifecycleState::Mounted,
+        (Operation::Mount, Err(_)) if previous == LifecycleState::Mounted => LifecycleState::Running,
+        (Operation::Unmount, Ok(_)) => LifecycleState::Running,
+        _ => previous,
+    }
+}
+
+fn set(app: &AppHandle, to: LifecycleState, operation: Option<Operation>) {
+    let from = std::mem::replace(&mut *app.state::<LifecycleManager>().state.lock().unwrap(), to);
+    if from != to {
+        log::debug!("Lifecycle: {:?} -> {:?}", from, to);
+        let _ = app.emit("lifecycle:transition", Transition { from, to, operation });
+    }
+}
+
+/// Run `future` as `operation`, or fail with `LIFECYCLE_BUSY` while another
+/// one is in progress.
+pub async fn guarded<T, F>(app: &AppHandle, operation: Operation, future: F) -> Result<T, CommandError>
+where
+    F: Future<Output = Result<T, CommandError>>,
+{
+    if IN_TRANSITION.try_with(|_| ()).is_ok() {
+        return future.await;
+    }
+    let manager = app.state::<LifecycleManager>();
+    let previous = {
+        let mut state = manager.state.lock().unwrap();
+        let previous = *state;
+        *state = begin(previous, operation)?;
+        previous
+    };
+    let _ = app.emit("lifecycle:transition", Transition { from: previous, to: operation.state(), operation: Some(operation) });
+    let result = IN_TRANSITION.scope(operation, future).await;
+    set(app, settle(previous, operation, &result), Some(operation));
+    result
+}
+
+/// Update a settled state from something observed outside a command.
+fn observe(app: &AppHandle, next: impl FnOnce(LifecycleState) -> Option<LifecycleState>) {
+    let manager = app.state::<LifecycleManager>();
+    let current = manager.state();
+    if current.in_progress() {
+        return;
+    }
+    if let Some(to) = next(current).filter(|to| *to != current) {
+        set(app, to, None);
+    }
+}
+
+/// The latest status probe found the bridge running or not.
+pub fn observe_server(app: &AppHandle, running: bool) {
+    observe(app, |current| match (current, running) {
+        (LifecycleState::Stopped, true) => Some(LifecycleState::Running),
+        (LifecycleState::Running | LifecycleState::Mounted, false) => Some(LifecycleState::Stopped),
+        _ => None,
+    });
+}
+
+/// A mount check found the drive mounted or not.
+pub fn observe_mount(app: &AppHandle, mounted: bool) {
+    observe(app, |current| match (current, mounted) {
+        (LifecycleState::Running, true) => Some(LifecycleState::Mounted),
+        (LifecycleState::Mounted, false) => Some(LifecycleState::Running),
+        _ => None,
+    });
+}
+
+#[tauri::command]
+pub async fn get_lifecycle_state(app: AppHandle) -> Result<LifecycleState, CommandError> {
+    Ok(app.state::<LifecycleManager>().state())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_begin_rejects_while_in_progress() {
+        assert_eq!(begin(LifecycleState::Stopped, Operation::Start).unwrap(), LifecycleState::Starting);
+        assert_eq!(begin(LifecycleState::Running, Operation::Reconfigure).unwrap(), LifecycleState::Reconfiguring);
+        assert_eq!(begin(LifecycleState::Mounted, Operation::Unmount).unwrap(), LifecycleState::Unmounting);
+        for busy in [LifecycleState::Starting, LifecycleState::Mounting, LifecycleState::Reconfiguring, LifecycleState::Stopping] {
+            let err = begin(busy, Operation::Mount).unwrap_err();
+            assert_eq!(err.code(), "LIFECYCLE_BUSY");
+        }
+        assert_eq!(begin(LifecycleState::Starting, Operation::Mount).unwrap_err().to_string(), "Another operation is in progress: Still starting the bridge");
+    }
+
+    #[test]
+    fn test_settle() {
+        let ok: Result<(), CommandError> = Ok(());
+        let failed: Result<(), CommandError> = Err(CommandError::MountTimeout);
+        assert_eq!(settle(LifecycleState::Stopped, Operation::Start, &ok), LifecycleState::Running);
+        assert_eq!(settle(LifecycleState::Stopped, Operation::Start, &failed), LifecycleState::Stopped);
+        assert_eq!(settle::<()>(LifecycleState::Stopped, Operation::Start, &Err(CommandError::SidecarAlreadyRunning)), LifecycleState::Running);
+        assert_eq!(settle(LifecycleState::Running, Operation::Mount, &ok), LifecycleState::Mounted);
+        assert_eq!(settle(LifecycleState::Running, Operation::Mount, &failed), LifecycleState::Running);
+        assert_eq!(settle(LifecycleState::Mounted, Operation::Mount, &failed), LifecycleState::Running);
+        assert_eq!(settle(LifecycleState::Mounted, Operation::Unmount, &ok), LifecycleState::Running);
+        assert_eq!(settle(LifecycleState::Mounted, Operation::Reconfigure, &ok), LifecycleState::Mounted);
+        assert_eq!(settle(LifecycleState::Mounted, Operation::Stop, &ok), LifecycleState::Stopped);
+        assert_eq!(settle(LifecycleState::Running, Operation::Stop, &failed), LifecycleState::Running);
+    }
+
+    #[test]
+    fn test_transition_serializes_camel_case() {
+        let transition = Transition { from: LifecycleState::Running, to: LifecycleState::Reconfiguring, operation: Some(Operation::Reconfigure) };
+        let json = serde_json::to_value(&transition).unwrap();
+        assert_eq!(json, serde_json::json!({"from": "running", "to": "reconfiguring", "operation": "reconfigure"}));
+    }
+}
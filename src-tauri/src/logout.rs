@@ -0,0 +1,132 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::sidecar::{CommandError, SidecarState};
+
+// ============================================================================
+// Logout
+// ============================================================================
+//
+// Signing out has to leave nothing of the account behind, so `logout` runs a
+// pipeline: unmount the drive and any extra mounts, stop the bridge, purge
+// cached metadata and file data (the gateway's and the sidecar's caches, the
+// offline mirror, staged uploads, thumbnails and recent files), and finally
+// clear the stored credentials and sessions. Each step is reported as a
+// `logout:progress` event. A failing step stops the pipeline unless `force`
+// is set, in which case the failure is reported and the remaining steps
+// still run.
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LogoutPhase {
+    Unmounting,
+    StoppingServer,
+    PurgingCache,
+    ClearingCredentials,
+    Completed,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutProgress {
+    pub phase: LogoutPhase,
+    /// Set on `failed`, and on a step that failed but was passed with `force`
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutStepFailure {
+    pub phase: LogoutPhase,
+    pub error: String,
+}
+
+/// Steps that failed but were passed over because of `force`.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutReport {
+    pub failures: Vec<LogoutStepFailure>,
+}
+
+fn emit(app: &AppHandle, phase: LogoutPhase, error: Option<String>) {
+    let _ = app.emit("logout:progress", LogoutProgress { phase, error });
+}
+
+/// Settle the outcome of a step: record it and carry on with `force`,
+/// otherwise fail the logout.
+fn check(app: &AppHandle, report: &mut LogoutReport, phase: LogoutPhase, result: Result<(), CommandError>, force: bool) -> Result<(), CommandError> {
+    let Err(error) = result else { return Ok(()) };
+    if force {
+        log::warn!("Logout: {:?} failed, continuing: {}", phase, error);
+        emit(app, phase, Some(error.to_string()));
+        report.failures.push(LogoutStepFailure { phase, error: error.to_string() });
+        return Ok(());
+    }
+    log::error!("Logout: {:?} failed: {}", phase, error);
+    emit(app, LogoutPhase::Failed, Some(error.to_string()));
+    Err(error)
+}
+
+async fn unmount_all(app: &AppHandle, state: &State<'_, SidecarState>) -> Result<(), CommandError> {
+    if matches!(crate::sidecar::check_mount_status(app.clone(), state.clone()).await, Ok(Some(_))) {
+        crate::sidecar::unmount_drive(app.clone(), state.clone()).await?;
+    }
+    for mount in crate::mounts::list_mounts(app.clone()).await? {
+        if mount.mounted {
+            crate::mounts::unmount_by_id(app.clone(), mount.definition.id).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn purge_caches(app: &AppHandle) -> Result<(), CommandError> {
+    crate::sidecar::purge_cache(app.clone()).await?;
+    crate::offline::purge_mirror(app)?;
+    crate::upload_spool::purge_account(app)?;
+    crate::travel::purge_local_traces(app);
+    Ok(())
+}
+
+async fn clear_credentials(app: &AppHandle) -> Result<(), CommandError> {
+    let result = app.state::<crate::sidecar_client::SidecarClient>().logout(app).await?;
+    if !result.logged_out {
+        log::info!("Logout: no stored credentials to clear");
+    }
+    Ok(())
+}
+
+/// Sign out: unmount, stop the bridge, purge caches and clear the stored
+/// credentials. With `force`, failing steps are skipped over and listed in
+/// the returned report instead of aborting the logout.
+#[tauri::command]
+pub async fn logout(app: AppHandle, state: State<'_, SidecarState>, force: Option<bool>) -> Result<LogoutReport, CommandError> {
+    let force = force.unwrap_or(false);
+    let mut report = LogoutReport::default();
+
+    emit(&app, LogoutPhase::Unmounting, None);
+    let result = unmount_all(&app, &state).await;
+    check(&app, &mut report, LogoutPhase::Unmounting, result, force)?;
+
+    emit(&app, LogoutPhase::StoppingServer, None);
+    let result = match crate::sidecar::probe_status(&app).await {
+        Ok(status) if status.server.running => crate::sidecar::stop_sidecar(app.clone(), state.clone()).await,
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    };
+    check(&app, &mut report, LogoutPhase::StoppingServer, result, force)?;
+
+    emit(&app, LogoutPhase::PurgingCache, None);
+    let result = purge_caches(&app).await;
+    check(&app, &mut report, LogoutPhase::PurgingCache, result, force)?;
+
+    emit(&app, LogoutPhase::ClearingCredentials, None);
+    let result = clear_credentials(&app).await;
+    check(&app, &mut report, LogoutPhase::ClearingCredentials, result, force)?;
+
+    app.state::<crate::activity::ActivityFeed>().clear(&app);
+    crate::status::invalidate(&app);
+    log::info!("Logged out ({} step(s) failed)", report.failures.len());
+    emit(&app, LogoutPhase::Completed, None);
+    Ok(report)
+}
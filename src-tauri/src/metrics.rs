@@ -0,0 +1,315 @@
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Metrics
+// ============================================================================
+//
+// Counters for people running the bridge as a service: requests through the
+// gateway, bytes moved, metadata cache hits and misses, bridge starts and
+// crashes, and the state of the mounts. `get_metrics` returns them to the UI;
+// with the endpoint enabled in the `metrics` config section they are also
+// served in the Prometheus text format at `http://127.0.0.1:<port>/metrics`.
+// The endpoint only listens on loopback and has no authentication, so
+// remote scrapers need a reverse proxy or an SSH tunnel. Counters start at
+// zero with each launch of the app.
+
+const CONFIG_KEY: &str = "metrics";
+
+/// Default port of the endpoint, from the range used by Prometheus exporters.
+const DEFAULT_PORT: u16 = 9464;
+
+const PREFIX: &str = "pdwb";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsEndpoint {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+impl Default for MetricsEndpoint {
+    fn default() -> Self {
+        Self { enabled: false, port: DEFAULT_PORT }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Metrics {
+    pub uptime_seconds: u64,
+    /// Seconds since the gateway last received a request, or since launch
+    pub idle_seconds: u64,
+    pub requests: u64,
+    /// Requests answered with 502 because the bridge was unreachable
+    pub request_errors: u64,
+    /// Bytes received from WebDAV clients
+    pub bytes_received: u64,
+    /// Bytes sent to WebDAV clients
+    pub bytes_sent: u64,
+    pub active_transfers: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub cache_entries: u64,
+    pub cache_bytes: u64,
+    pub sidecar_running: bool,
+    pub sidecar_starts: u64,
+    /// Unexpected exits of the bridge
+    pub sidecar_crashes: u64,
+    pub mounts_defined: u64,
+    pub mounts_mounted: u64,
+}
+
+pub struct MetricsState {
+    started: Instant,
+    requests: AtomicU64,
+    /// Milliseconds after `started` of the last request
+    last_request: AtomicU64,
+    request_errors: AtomicU64,
+    pub(crate) bytes_received: Arc<AtomicU64>,
+    pub(crate) bytes_sent: Arc<AtomicU64>,
+    sidecar_starts: AtomicU64,
+    sidecar_crashes: AtomicU64,
+    /// Shutdown signal of the running endpoint
+    endpoint: Mutex<Option<watch::Sender<bool>>>,
+}
+
+impl MetricsState {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            requests: AtomicU64::new(0),
+            last_request: AtomicU64::new(0),
+            request_errors: AtomicU64::new(0),
+            bytes_received: Arc::new(AtomicU64::new(0)),
+            bytes_sent: Arc::new(AtomicU64::new(0)),
+            sidecar_starts: AtomicU64::new(0),
+            sidecar_crashes: AtomicU64::new(0),
+            endpoint: Mutex::new(None),
+        }
+    }
+
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.last_request.store(self.started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Requests received since launch.
+    pub fn request_count(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Time since the last request, or since launch if there was none.
+    pub fn idle_for(&self) -> Duration {
+        self.started.elapsed().saturating_sub(Duration::from_millis(self.last_request.load(Ordering::Relaxed)))
+    }
+
+    pub fn record_request_error(&self) {
+        self.request_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_sidecar_start(&self) {
+        self.sidecar_starts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_sidecar_crash(&self) {
+        self.sidecar_crashes.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Default for MetricsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Current values of every metric.
+pub async fn collect(app: &AppHandle) -> Metrics {
+    let state = app.state::<MetricsState>();
+    let cache = app.state::<crate::cache::MetadataCache>().stats();
+    let status_cache = app.state::<crate::status::StatusCache>();
+    let status = status_cache.get(app).await.ok().or_else(|| status_cache.last_good());
+    let mounts = crate::mounts::list_mounts(app.clone()).await.unwrap_or_default();
+    Metrics {
+        uptime_seconds: state.started.elapsed().as_secs(),
+        idle_seconds: state.idle_for().as_secs(),
+        requests: state.requests.load(Ordering::Relaxed),
+        request_errors: state.request_errors.load(Ordering::Relaxed),
+        bytes_received: state.bytes_received.load(Ordering::Relaxed),
+        bytes_sent: state.bytes_sent.load(Ordering::Relaxed),
+        active_transfers: app.state::<crate::transfers::TransferState>().list().len() as u64,
+        cache_hits: cache.hits,
+        cache_misses: cache.misses,
+        cache_entries: cache.entries as u64,
+        cache_bytes: cache.total_bytes,
+        sidecar_running: status.is_some_and(|s| s.server.running),
+        sidecar_starts: state.sidecar_starts.load(Ordering::Relaxed),
+        sidecar_crashes: state.sidecar_crashes.load(Ordering::Relaxed),
+        mounts_defined: mounts.len() as u64,
+        mounts_mounted: mounts.iter().filter(|m| m.mounted).count() as u64,
+    }
+}
+
+/// The metrics in the Prometheus text exposition format.
+fn render(metrics: &Metrics) -> String {
+    let families: [(&str, &str, &str, u64); 16] = [
+        ("uptime_seconds", "gauge", "Seconds since the app started", metrics.uptime_seconds),
+        ("idle_seconds", "gauge", "Seconds since the last WebDAV request", metrics.idle_seconds),
+        ("requests_total", "counter", "WebDAV requests received by the gateway", metrics.requests),
+        ("request_errors_total", "counter", "WebDAV requests that could not reach the bridge", metrics.request_errors),
+        ("received_bytes_total", "counter", "Bytes received from WebDAV clients", metrics.bytes_received),
+        ("sent_bytes_total", "counter", "Bytes sent to WebDAV clients", metrics.bytes_sent),
+        ("active_transfers", "gauge", "Uploads and downloads in progress", metrics.active_transfers),
+        ("cache_hits_total", "counter", "PROPFIND requests answered from the metadata cache", metrics.cache_hits),
+        ("cache_misses_total", "counter", "PROPFIND requests forwarded to the bridge", metrics.cache_misses),
+        ("cache_entries", "gauge", "Listings held in the metadata cache", metrics.cache_entries),
+        ("cache_bytes", "gauge", "Size of the metadata cache in bytes", metrics.cache_bytes),
+        ("sidecar_up", "gauge", "Whether the bridge is running", u64::from(metrics.sidecar_running)),
+        ("sidecar_starts_total", "counter", "Times the app started the bridge", metrics.sidecar_starts),
+        ("sidecar_crashes_total", "counter", "Unexpected exits of the bridge", metrics.sidecar_crashes),
+        ("mounts", "gauge", "Mounts defined in the app", metrics.mounts_defined),
+        ("mounts_mounted", "gauge", "Mounts currently mounted", metrics.mounts_mounted),
+    ];
+    let mut out = String::new();
+    for (name, kind, help, value) in families {
+        let _ = writeln!(out, "# HELP {}_{} {}", PREFIX, name, help);
+        let _ = writeln!(out, "# TYPE {}_{} {}", PREFIX, name, kind);
+        let _ = writeln!(out, "{}_{} {}", PREFIX, name, value);
+    }
+    out
+}
+
+async fn serve(app: AppHandle, req: Request<hyper::body::Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        let mut resp = Response::new(Full::new(Bytes::from_static(b"Not found\n")));
+        *resp.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(resp);
+    }
+    let body = render(&collect(&app).await);
+    let mut resp = Response::new(Full::new(Bytes::from(body)));
+    resp.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"));
+    Ok(resp)
+}
+
+fn stop_endpoint(app: &AppHandle) {
+    if let Some(shutdown) = app.state::<MetricsState>().endpoint.lock().unwrap().take() {
+        let _ = shutdown.send(true);
+        log::info!("Stopped metrics endpoint");
+    }
+}
+
+async fn start_endpoint(app: &AppHandle, port: u16) -> Result<(), CommandError> {
+    stop_endpoint(app);
+    let listener = TcpListener::bind(("127.0.0.1", port)).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AddrInUse {
+            CommandError::PortInUse(port)
+        } else {
+            CommandError::IoError(e.to_string())
+        }
+    })?;
+    let (tx, mut shutdown) = watch::channel(false);
+    *app.state::<MetricsState>().endpoint.lock().unwrap() = Some(tx);
+    log::info!("Serving metrics at http://127.0.0.1:{}/metrics", port);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let app = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            let service = service_fn(move |req| serve(app.clone(), req));
+                            if let Err(e) = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                                log::debug!("Metrics connection ended with error: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => log::warn!("Metrics endpoint failed to accept connection: {}", e),
+                },
+                _ = shutdown.changed() => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Start the endpoint if it is enabled. Called from `setup`.
+pub async fn run(app: AppHandle) {
+    let endpoint: MetricsEndpoint = read_config_section(CONFIG_KEY);
+    if endpoint.enabled {
+        if let Err(e) = start_endpoint(&app, endpoint.port).await {
+            log::warn!("Failed to start metrics endpoint on port {}: {}", endpoint.port, e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_metrics(app: AppHandle) -> Result<Metrics, CommandError> {
+    Ok(collect(&app).await)
+}
+
+#[tauri::command]
+pub async fn get_metrics_endpoint() -> Result<MetricsEndpoint, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+/// Enable, disable or move the endpoint. Takes effect immediately; the
+/// setting is kept only if the port could be bound.
+#[tauri::command]
+pub async fn set_metrics_endpoint(app: AppHandle, endpoint: MetricsEndpoint) -> Result<MetricsEndpoint, CommandError> {
+    if endpoint.enabled {
+        start_endpoint(&app, endpoint.port).await?;
+    } else {
+        stop_endpoint(&app);
+    }
+    write_config_section(CONFIG_KEY, &endpoint)?;
+    Ok(endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics { requests: 12, bytes_sent: 4096, sidecar_running: true, ..Default::default() };
+        let text = render(&metrics);
+        assert!(text.contains("# TYPE pdwb_requests_total counter\npdwb_requests_total 12\n"));
+        assert!(text.contains("\npdwb_sent_bytes_total 4096\n"));
+        assert!(text.contains("\npdwb_sidecar_up 1\n"));
+        assert!(text.contains("# HELP pdwb_cache_hits_total "));
+        assert_eq!(text.lines().filter(|l| !l.starts_with('#')).count(), 16);
+    }
+
+    #[test]
+    fn test_endpoint_config_defaults() {
+        let endpoint: MetricsEndpoint = serde_json::from_str("{}").unwrap();
+        assert_eq!(endpoint, MetricsEndpoint { enabled: false, port: DEFAULT_PORT });
+        let endpoint: MetricsEndpoint = serde_json::from_str(r#"{"enabled": true, "port": 9100}"#).unwrap();
+        assert_eq!(endpoint, MetricsEndpoint { enabled: true, port: 9100 });
+    }
+}
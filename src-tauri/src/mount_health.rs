@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError, SidecarState};
+
+// ============================================================================
+// Mount health
+// ============================================================================
+//
+// A mount can go stale: the GVFS backend dies, GIO still lists the mount,
+// and every access through its FUSE directory fails or hangs. While the
+// bridge runs, the local path of the drive and of every extra mount is
+// stat'ed every `intervalSeconds`. After `FAILURES_BEFORE_REPAIR` failed
+// probes in a row the mount is unmounted and mounted again when `autoRepair`
+// is on; `mount:repaired` is emitted if it works afterwards, and
+// `mount:degraded` if it doesn't or repairs are off. A degraded mount is
+// announced once, until a probe succeeds again. Only mounts with a local
+// path (GVFS on Linux) can be probed.
+
+const CONFIG_KEY: &str = "mountHealth";
+
+/// How long a probe may take before the mount counts as hung.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Failed probes in a row before the mount is repaired.
+const FAILURES_BEFORE_REPAIR: u32 = 2;
+
+const MIN_INTERVAL_SECONDS: u64 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MountHealthSettings {
+    pub enabled: bool,
+    pub auto_repair: bool,
+    pub interval_seconds: u64,
+}
+
+impl Default for MountHealthSettings {
+    fn default() -> Self {
+        Self { enabled: true, auto_repair: true, interval_seconds: 60 }
+    }
+}
+
+/// Last probe of one mount.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MountHealth {
+    /// Extra mount id, or `None` for the drive
+    pub mount_id: Option<String>,
+    pub path: String,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Unix seconds
+    pub checked_at: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MountHealthEvent {
+    pub mount_id: Option<String>,
+    pub path: String,
+    /// What the failing probe reported
+    pub error: String,
+}
+
+/// What to do after a probe.
+#[derive(Debug, PartialEq, Eq)]
+enum Verdict {
+    Fine,
+    Repair,
+    /// Announce the mount as degraded
+    Degraded,
+    /// Still failing, already announced
+    Waiting,
+}
+
+/// Decide on the outcome of a probe of a mount last seen as `previous`.
+fn judge(previous: Option<&MountHealth>, healthy: bool, auto_repair: bool) -> (u32, Verdict) {
+    if healthy {
+        return (0, Verdict::Fine);
+    }
+    let failures = previous.map_or(0, |p| p.consecutive_failures) + 1;
+    let verdict = match failures {
+        n if n < FAILURES_BEFORE_REPAIR => Verdict::Waiting,
+        n if n == FAILURES_BEFORE_REPAIR && auto_repair => Verdict::Repair,
+        n if n == FAILURES_BEFORE_REPAIR => Verdict::Degraded,
+        _ => Verdict::Waiting,
+    };
+    (failures, verdict)
+}
+
+#[derive(Default)]
+pub struct MountHealthState {
+    /// By mount id; the drive is under the empty id
+    health: Mutex<HashMap<String, MountHealth>>,
+}
+
+impl MountHealthState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Stat `path`, giving up after `PROBE_TIMEOUT`.
+async fn probe(path: PathBuf) -> Result<(), String> {
+    match tokio::time::timeout(PROBE_TIMEOUT, tokio::fs::metadata(&path)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("No answer within {} seconds", PROBE_TIMEOUT.as_secs())),
+    }
+}
+
+/// Mounted locations with a local path, as `(mount id, path)`.
+async fn mounted_paths(app: &AppHandle) -> Vec<(Option<String>, String)> {
+    let mut paths = Vec::new();
+    if let Ok(status) = crate::sidecar::get_status(app.clone(), app.state::<SidecarState>()).await {
+        if let (true, Some(path)) = crate::mounts::mount_status(&crate::sidecar::local_dav_uri(&status)) {
+            paths.push((None, path));
+        }
+    }
+    for mount in crate::mounts::list_mounts(app.clone()).await.unwrap_or_default() {
+        if let Some(path) = mount.mount_point.filter(|_| mount.mounted) {
+            paths.push((Some(mount.definition.id), path));
+        }
+    }
+    paths
+}
+
+/// Unmount and mount again.
+async fn repair(app: &AppHandle, mount_id: Option<&str>) -> Result<(), CommandError> {
+    match mount_id {
+        None => {
+            let state = app.state::<SidecarState>();
+            if let Err(e) = crate::sidecar::unmount_drive(app.clone(), state.clone()).await {
+                log::warn!("Unmounting the stale drive failed, mounting anyway: {}", e);
+            }
+            crate::sidecar::mount_drive(app.clone(), state).await
+        }
+        Some(id) => {
+            if let Err(e) = crate::mounts::unmount_by_id(app.clone(), id.to_string()).await {
+                log::warn!("Unmounting stale mount {} failed, mounting anyway: {}", id, e);
+            }
+            crate::mounts::mount_by_id(app.clone(), id.to_string()).await.map(|_| ())
+        }
+    }
+}
+
+/// Probe every mount once and act on the results.
+async fn check(app: &AppHandle, settings: &MountHealthSettings) {
+    let paths = mounted_paths(app).await;
+    let state = app.state::<MountHealthState>();
+    let mut seen = Vec::with_capacity(paths.len());
+    for (mount_id, path) in paths {
+        let key = mount_id.clone().unwrap_or_default();
+        seen.push(key.clone());
+        let result = probe(PathBuf::from(&path)).await;
+        let previous = state.health.lock().unwrap().get(&key).cloned();
+        let (failures, verdict) = judge(previous.as_ref(), result.is_ok(), settings.auto_repair);
+        let error = result.err();
+        let event = |error: String| MountHealthEvent { mount_id: mount_id.clone(), path: path.clone(), error };
+        let mut health = MountHealth {
+            mount_id: mount_id.clone(),
+            path: path.clone(),
+            healthy: error.is_none(),
+            consecutive_failures: failures,
+            last_error: error.clone(),
+            checked_at: crate::sidecar::unix_now(),
+        };
+        match verdict {
+            Verdict::Fine | Verdict::Waiting => {}
+            Verdict::Repair => {
+                let error = error.unwrap_or_default();
+                log::warn!("Mount {} is not responding ({}), remounting", path, error);
+                let repaired = match repair(app, mount_id.as_deref()).await {
+                    Ok(()) => probe(PathBuf::from(&path)).await.map_err(CommandError::IoError),
+                    Err(e) => Err(e),
+                };
+                match repaired {
+                    Ok(()) => {
+                        log::info!("Mount {} repaired", path);
+                        health = MountHealth { healthy: true, consecutive_failures: 0, last_error: None, ..health };
+                        let _ = app.emit("mount:repaired", event(error));
+                    }
+                    Err(e) => {
+                        log::warn!("Repairing mount {} failed: {}", path, e);
+                        let _ = app.emit("mount:degraded", event(e.to_string()));
+                    }
+                }
+            }
+            Verdict::Degraded => {
+                let error = error.unwrap_or_default();
+                log::warn!("Mount {} is not responding: {}", path, error);
+                let _ = app.emit("mount:degraded", event(error));
+            }
+        }
+        state.health.lock().unwrap().insert(key, health);
+    }
+    state.health.lock().unwrap().retain(|key, _| seen.contains(key));
+}
+
+/// Probe mounts periodically; runs for the life of the app.
+pub async fn run(app: AppHandle) {
+    loop {
+        let settings: MountHealthSettings = read_config_section(CONFIG_KEY);
+        tokio::time::sleep(Duration::from_secs(settings.interval_seconds.max(MIN_INTERVAL_SECONDS))).await;
+        let idle = !settings.enabled
+            || app.state::<crate::power::PowerState>().is_suspended()
+            || !app.state::<SidecarState>().is_running().await;
+        if idle {
+            app.state::<MountHealthState>().health.lock().unwrap().clear();
+            continue;
+        }
+        check(&app, &settings).await;
+    }
+}
+
+#[tauri::command]
+pub async fn get_mount_health_settings() -> Result<MountHealthSettings, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+#[tauri::command]
+pub async fn set_mount_health_settings(settings: MountHealthSettings) -> Result<MountHealthSettings, CommandError> {
+    if settings.interval_seconds < MIN_INTERVAL_SECONDS {
+        return Err(CommandError::InvalidMountSettings(format!(
+            "intervalSeconds must be at least {}",
+            MIN_INTERVAL_SECONDS
+        )));
+    }
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Result of the last probe of every mount.
+#[tauri::command]
+pub async fn get_mount_health(state: State<'_, MountHealthState>) -> Result<Vec<MountHealth>, CommandError> {
+    let mut health: Vec<MountHealth> = state.health.lock().unwrap().values().cloned().collect();
+    health.sort_by(|a, b| a.mount_id.cmp(&b.mount_id));
+    Ok(health)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failing(failures: u32) -> MountHealth {
+        MountHealth {
+            mount_id: None,
+            path: "/run/user/1000/gvfs/dav:host=localhost".into(),
+            healthy: false,
+            consecutive_failures: failures,
+            last_error: Some("Transport endpoint is not connected".into()),
+            checked_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_repair_after_repeated_failures_only() {
+        assert_eq!(judge(None, true, true), (0, Verdict::Fine));
+        assert_eq!(judge(None, false, true), (1, Verdict::Waiting));
+        assert_eq!(judge(Some(&failing(1)), false, true), (2, Verdict::Repair));
+        assert_eq!(judge(Some(&failing(2)), false, true), (3, Verdict::Waiting));
+        assert_eq!(judge(Some(&failing(5)), true, true), (0, Verdict::Fine));
+    }
+
+    #[test]
+    fn test_degraded_once_without_repair() {
+        assert_eq!(judge(Some(&failing(1)), false, false), (2, Verdict::Degraded));
+        assert_eq!(judge(Some(&failing(2)), false, false), (3, Verdict::Waiting));
+    }
+
+    #[test]
+    fn test_probe_reports_missing_paths() {
+        let missing = std::env::temp_dir().join(format!("mount-health-missing-{}", std::process::id()));
+        assert!(tauri::async_runtime::block_on(probe(missing)).is_err());
+        assert!(tauri::async_runtime::block_on(probe(std::env::temp_dir())).is_ok());
+    }
+}
@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Mount retries and cancellation
+// ============================================================================
+//
+// GVFS often rejects the first mount right after the bridge starts, so GIO
+// mounts are retried with exponential backoff as configured in the
+// `mountRetry` config section. Each running mount or unmount holds a
+// `gio::Cancellable` that `cancel_mount` triggers, which aborts both the GIO
+// call and any pending retry. Mount progress is reported as `mount:progress`
+// events.
+
+const CONFIG_KEY: &str = "mountRetry";
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How often a backoff sleep checks whether the mount was cancelled.
+#[cfg(target_os = "linux")]
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MountRetrySettings {
+    /// Total attempts, including the first
+    pub attempts: u32,
+    /// Delay before the second attempt; doubles after each failure
+    pub backoff_seconds: u64,
+    /// How long a single attempt may take
+    pub timeout_seconds: u64,
+}
+
+impl Default for MountRetrySettings {
+    fn default() -> Self {
+        Self { attempts: 3, backoff_seconds: 2, timeout_seconds: 20 }
+    }
+}
+
+impl MountRetrySettings {
+    /// Delay after the given failed attempt (1-based).
+    fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_secs(self.backoff_seconds)
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(MAX_BACKOFF)
+    }
+
+    fn validate(&self) -> Result<(), CommandError> {
+        if self.attempts == 0 || self.attempts > 10 {
+            return Err(CommandError::InvalidMountSettings("attempts must be between 1 and 10".into()));
+        }
+        if self.timeout_seconds == 0 {
+            return Err(CommandError::InvalidMountSettings("timeoutSeconds must be positive".into()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MountProgressState {
+    Attempting,
+    Retrying,
+    Mounted,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MountProgress {
+    pub uri: String,
+    pub state: MountProgressState,
+    /// 1-based attempt number
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// Delay before the next attempt when `state` is `retrying`
+    pub retry_in_seconds: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Cancellables of the mounts in progress, by GIO location.
+#[derive(Default)]
+pub struct MountOperations {
+    pending: Mutex<HashMap<String, gio::Cancellable>>,
+}
+
+impl MountOperations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a mount of `uri`, cancelling an earlier one for the same
+    /// location.
+    #[cfg(target_os = "linux")]
+    fn begin(&self, uri: &str) -> gio::Cancellable {
+        use gio::prelude::*;
+
+        let cancellable = gio::Cancellable::new();
+        if let Some(previous) = self.pending.lock().unwrap().insert(uri.to_string(), cancellable.clone()) {
+            previous.cancel();
+        }
+        cancellable
+    }
+
+    #[cfg(target_os = "linux")]
+    fn finish(&self, uri: &str, cancellable: &gio::Cancellable) {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.get(uri) == Some(cancellable) {
+            pending.remove(uri);
+        }
+    }
+
+    /// Cancel the mount of `uri`, or every mount in progress. Returns
+    /// whether anything was cancelled.
+    fn cancel(&self, uri: Option<&str>) -> bool {
+        use gio::prelude::*;
+
+        let mut pending = self.pending.lock().unwrap();
+        let cancelled: Vec<gio::Cancellable> = match uri {
+            Some(uri) => pending.remove(uri).into_iter().collect(),
+            None => pending.drain().map(|(_, c)| c).collect(),
+        };
+        cancelled.iter().for_each(|c| c.cancel());
+        !cancelled.is_empty()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn emit_progress(app: &AppHandle, uri: &str, state: MountProgressState, attempt: u32, max_attempts: u32, retry_in: Option<Duration>, error: Option<String>) {
+    let progress = MountProgress {
+        uri: uri.to_string(),
+        state,
+        attempt,
+        max_attempts,
+        retry_in_seconds: retry_in.map(|d| d.as_secs()),
+        error,
+    };
+    let _ = app.emit("mount:progress", progress);
+}
+
+/// Sleep for `delay` unless cancelled first. Returns whether it was cancelled.
+#[cfg(target_os = "linux")]
+async fn sleep_unless_cancelled(delay: Duration, cancellable: &gio::Cancellable) -> bool {
+    use gio::prelude::*;
+
+    let deadline = tokio::time::Instant::now() + delay;
+    while tokio::time::Instant::now() < deadline {
+        if cancellable.is_cancelled() {
+            return true;
+        }
+        tokio::time::sleep(CANCEL_POLL_INTERVAL.min(deadline - tokio::time::Instant::now())).await;
+    }
+    cancellable.is_cancelled()
+}
+
+/// Mount `uri` through GIO, retrying per the `mountRetry` settings.
+/// Fails with `MountTimeout` when the last attempt timed out and with
+/// `OperationCancelled` after `cancel_mount`.
+#[cfg(target_os = "linux")]
+pub async fn mount_with_retry(app: &AppHandle, uri: &str, trust_local_certificate: bool) -> Result<(), CommandError> {
+    use gio::prelude::*;
+    use tauri::Manager;
+
+    let settings: MountRetrySettings = read_config_section(CONFIG_KEY);
+    let attempts = settings.attempts.max(1);
+    let timeout = Duration::from_secs(settings.timeout_seconds.max(1));
+    let operations = app.state::<MountOperations>();
+    let cancellable = operations.begin(uri);
+
+    let mut result = Err(CommandError::MountTimeout);
+    let mut last_attempt = 0;
+    for attempt in 1..=attempts {
+        last_attempt = attempt;
+        emit_progress(app, uri, MountProgressState::Attempting, attempt, attempts, None, None);
+        let credential = app.state::<crate::webdav_auth::WebdavAuthState>().run_credential();
+        let rx = crate::sidecar::spawn_gio_mount(uri.to_string(), trust_local_certificate, credential, cancellable.clone(), timeout);
+        let outcome = tauri::async_runtime::spawn_blocking(move || rx.recv()).await;
+        let error = match outcome {
+            Ok(Ok(Ok(()))) => {
+                emit_progress(app, uri, MountProgressState::Mounted, attempt, attempts, None, None);
+                result = Ok(());
+                break;
+            }
+            Ok(Ok(Err(e))) => e,
+            _ => "Mount thread exited".to_string(),
+        };
+        if cancellable.is_cancelled() {
+            result = Err(CommandError::OperationCancelled);
+            break;
+        }
+        result = Err(if error == crate::sidecar::MOUNT_TIMED_OUT {
+            CommandError::MountTimeout
+        } else {
+            CommandError::GioError(format!("Failed to mount: {}", error))
+        });
+        if attempt == attempts {
+            emit_progress(app, uri, MountProgressState::Failed, attempt, attempts, None, Some(error));
+            break;
+        }
+        let delay = settings.backoff(attempt);
+        log::warn!("Mount attempt {}/{} of {} failed: {}; retrying in {}s", attempt, attempts, uri, error, delay.as_secs());
+        emit_progress(app, uri, MountProgressState::Retrying, attempt, attempts, Some(delay), Some(error));
+        if sleep_unless_cancelled(delay, &cancellable).await {
+            result = Err(CommandError::OperationCancelled);
+            break;
+        }
+    }
+
+    if matches!(result, Err(CommandError::OperationCancelled)) {
+        emit_progress(app, uri, MountProgressState::Cancelled, last_attempt, attempts, None, None);
+    }
+    operations.finish(uri, &cancellable);
+    result
+}
+
+/// Unmount the GIO mount rooted at `uri`, forcing it if a regular unmount
+/// fails. Uses the per-attempt timeout from `mountRetry` and can be aborted
+/// with `cancel_mount` like a mount.
+#[cfg(target_os = "linux")]
+pub async fn unmount_with_timeout(app: &AppHandle, uri: &str) -> Result<(), CommandError> {
+    use gio::prelude::*;
+    use tauri::Manager;
+
+    let settings: MountRetrySettings = read_config_section(CONFIG_KEY);
+    let timeout = Duration::from_secs(settings.timeout_seconds.max(1));
+    let operations = app.state::<MountOperations>();
+    let cancellable = operations.begin(uri);
+
+    let rx = crate::sidecar::spawn_gio_unmount(uri.to_string(), cancellable.clone(), timeout);
+    let outcome = tauri::async_runtime::spawn_blocking(move || rx.recv()).await;
+    let result = match outcome {
+        Ok(Ok(Ok(()))) => Ok(()),
+        _ if cancellable.is_cancelled() => Err(CommandError::OperationCancelled),
+        Ok(Ok(Err(e))) if e == crate::sidecar::MOUNT_TIMED_OUT => Err(CommandError::MountTimeout),
+        Ok(Ok(Err(e))) => Err(CommandError::GioError(format!("Failed to unmount: {}", e))),
+        _ => Err(CommandError::GioError("Failed to unmount: unmount thread exited".into())),
+    };
+    operations.finish(uri, &cancellable);
+    result
+}
+
+#[tauri::command]
+pub async fn get_mount_retry_settings() -> Result<MountRetrySettings, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+#[tauri::command]
+pub async fn set_mount_retry_settings(settings: MountRetrySettings) -> Result<MountRetrySettings, CommandError> {
+    settings.validate()?;
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+/// Abort the mount of `uri` in progress, or all of them when no location is
+/// given. Returns whether a mount was cancelled.
+#[tauri::command]
+pub async fn cancel_mount(state: State<'_, MountOperations>, uri: Option<String>) -> Result<bool, CommandError> {
+    Ok(state.cancel(uri.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let settings = MountRetrySettings::default();
+        assert_eq!(settings.backoff(1), Duration::from_secs(2));
+        assert_eq!(settings.backoff(3), Duration::from_secs(8));
+        assert_eq!(settings.backoff(30), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        assert!(MountRetrySettings::default().validate().is_ok());
+        assert!(MountRetrySettings { attempts: 0, ..Default::default() }.validate().is_err());
+        assert!(MountRetrySettings { timeout_seconds: 0, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_cancel_without_pending_mounts() {
+        let operations = MountOperations::new();
+        assert!(!operations.cancel(None));
+        assert!(!operations.cancel(Some("dav://localhost:8080")));
+    }
+}
@@ -0,0 +1,312 @@
+use futures_util::future::BoxFuture;
+use tauri::AppHandle;
+
+use crate::i18n::{t, t_with};
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Mount providers
+// ============================================================================
+//
+// `mount_drive`, `unmount_drive` and `check_mount_status` follow the same
+// steps everywhere: find the share among the mounts, check it may be
+// unmounted, and report each step as `mount:status`. What differs between
+// platforms is how a location gets mounted and whether mounts can be listed
+// at all; that part sits behind `MountProvider`. Linux uses GIO (or the
+// desktop portal inside Flatpak), macOS and Windows hand the location to
+// Finder or Explorer. The provider for the platform is picked at startup and
+// managed as `MountProviderState`, and the steps below take it as a
+// `&dyn MountProvider`, so tests run them against a mock.
+
+/// A mount as the platform reports it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MountEntry {
+    /// Location of the mount root, e.g. `dav://localhost:8080/`
+    pub uri: String,
+    pub name: String,
+    pub can_unmount: bool,
+}
+
+pub trait MountProvider: Send + Sync {
+    /// Mount `uri`. `trust_local_certificate` accepts the gateway's
+    /// self-signed certificate.
+    fn mount<'a>(&'a self, uri: &'a str, trust_local_certificate: bool) -> BoxFuture<'a, Result<(), CommandError>>;
+
+    /// Unmount the mount rooted at `uri`.
+    fn unmount<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Result<(), CommandError>>;
+
+    /// The current mounts; fails where they cannot be listed.
+    fn list(&self) -> Result<Vec<MountEntry>, CommandError>;
+
+    /// Catalog key of the `mount:status` message shown while mounting.
+    fn mounting_status(&self) -> &'static str {
+        "mount.mounting"
+    }
+}
+
+pub struct MountProviderState {
+    provider: Box<dyn MountProvider>,
+}
+
+impl MountProviderState {
+    pub fn new(provider: impl MountProvider + 'static) -> Self {
+        Self { provider: Box::new(provider) }
+    }
+
+    pub fn get(&self) -> &dyn MountProvider {
+        self.provider.as_ref()
+    }
+}
+
+/// GIO mounts, mounted through the desktop portal inside Flatpak.
+#[cfg(target_os = "linux")]
+struct GioMounts {
+    app: AppHandle,
+    via_portal: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl MountProvider for GioMounts {
+    fn mount<'a>(&'a self, uri: &'a str, trust_local_certificate: bool) -> BoxFuture<'a, Result<(), CommandError>> {
+        Box::pin(async move {
+            if self.via_portal {
+                // GVFS is not reachable from the sandbox; the host's file
+                // manager mounts the location
+                let uri = uri.to_string();
+                return tauri::async_runtime::spawn_blocking(move || crate::sandbox::portal_open_uri(&uri))
+                    .await
+                    .map_err(|e| CommandError::Unknown(e.to_string()))?;
+            }
+            crate::mount_operation::mount_with_retry(&self.app, uri, trust_local_certificate).await
+        })
+    }
+
+    fn unmount<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Result<(), CommandError>> {
+        Box::pin(crate::mount_operation::unmount_with_timeout(&self.app, uri))
+    }
+
+    fn list(&self) -> Result<Vec<MountEntry>, CommandError> {
+        use gio::prelude::*;
+
+        Ok(crate::sidecar::get_cached_mounts()
+            .iter()
+            .map(|m| MountEntry { uri: m.root().uri().to_string(), name: m.name().to_string(), can_unmount: m.can_unmount() })
+            .collect())
+    }
+
+    fn mounting_status(&self) -> &'static str {
+        if self.via_portal {
+            "mount.openingInFileManager"
+        } else {
+            "mount.mounting"
+        }
+    }
+}
+
+/// Opens locations with the system file manager, which mounts them; its
+/// mounts cannot be listed.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+struct FileManagerMounts {
+    program: &'static str,
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+impl MountProvider for FileManagerMounts {
+    fn mount<'a>(&'a self, uri: &'a str, _trust_local_certificate: bool) -> BoxFuture<'a, Result<(), CommandError>> {
+        let result = std::process::Command::new(self.program).arg(uri).spawn().map(|_| ()).map_err(|e| CommandError::IoError(e.to_string()));
+        Box::pin(std::future::ready(result))
+    }
+
+    fn unmount<'a>(&'a self, _uri: &'a str) -> BoxFuture<'a, Result<(), CommandError>> {
+        Box::pin(std::future::ready(Err(CommandError::Unknown("Platform not supported".into()))))
+    }
+
+    fn list(&self) -> Result<Vec<MountEntry>, CommandError> {
+        Err(CommandError::Unknown("Platform not supported".into()))
+    }
+
+    fn mounting_status(&self) -> &'static str {
+        "mount.openingInFileManager"
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+struct Unsupported;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+impl MountProvider for Unsupported {
+    fn mount<'a>(&'a self, _uri: &'a str, _trust_local_certificate: bool) -> BoxFuture<'a, Result<(), CommandError>> {
+        Box::pin(std::future::ready(Err(CommandError::Unknown("Platform not supported".into()))))
+    }
+
+    fn unmount<'a>(&'a self, _uri: &'a str) -> BoxFuture<'a, Result<(), CommandError>> {
+        Box::pin(std::future::ready(Err(CommandError::Unknown("Platform not supported".into()))))
+    }
+
+    fn list(&self) -> Result<Vec<MountEntry>, CommandError> {
+        Err(CommandError::Unknown("Platform not supported".into()))
+    }
+}
+
+/// The provider for the platform the app runs on.
+#[cfg(target_os = "linux")]
+pub fn for_platform(app: &AppHandle) -> MountProviderState {
+    MountProviderState::new(GioMounts { app: app.clone(), via_portal: crate::sandbox::is_flatpak() })
+}
+
+#[cfg(target_os = "macos")]
+pub fn for_platform(_app: &AppHandle) -> MountProviderState {
+    MountProviderState::new(FileManagerMounts { program: "open" })
+}
+
+#[cfg(target_os = "windows")]
+pub fn for_platform(_app: &AppHandle) -> MountProviderState {
+    MountProviderState::new(FileManagerMounts { program: "explorer" })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn for_platform(_app: &AppHandle) -> MountProviderState {
+    MountProviderState::new(Unsupported)
+}
+
+fn with_trailing_slash(uri: &str) -> String {
+    if uri.ends_with('/') {
+        uri.to_string()
+    } else {
+        format!("{}/", uri)
+    }
+}
+
+/// The mount of `target` among `mounts`: the one rooted there (GIO adds a
+/// trailing slash), otherwise one on the same port, since GIO may report
+/// `127.0.0.1` for `localhost` or an `http` scheme.
+pub(crate) fn find_mount<'a>(mounts: &'a [MountEntry], target: &str) -> Option<&'a MountEntry> {
+    let normalized_target = with_trailing_slash(target);
+    if let Some(mount) = mounts.iter().find(|m| with_trailing_slash(&m.uri) == normalized_target) {
+        return Some(mount);
+    }
+    let port = target.rsplit_once(':')?.1.trim_end_matches('/');
+    if port.is_empty() || !port.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    mounts.iter().find(|m| m.uri.contains(&format!(":{}", port)))
+}
+
+/// The text shown for a failed mount or unmount.
+pub(crate) fn failure_message(error: &CommandError) -> String {
+    match error {
+        CommandError::GioError(msg) => msg.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Mount `uri`, reporting each step to `status`.
+pub async fn mount(provider: &dyn MountProvider, uri: &str, trust_local_certificate: bool, status: impl Fn(String)) -> Result<(), CommandError> {
+    status(t(provider.mounting_status()));
+    let result = provider.mount(uri, trust_local_certificate).await;
+    status(match &result {
+        Ok(()) => t("mount.mounted"),
+        Err(CommandError::OperationCancelled) => t("mount.cancelled"),
+        Err(CommandError::MountTimeout) => t("mount.timedOut"),
+        Err(e) => failure_message(e),
+    });
+    result
+}
+
+/// Unmount the mount of `target`, reporting each step to `status`.
+pub async fn unmount(provider: &dyn MountProvider, target: &str, status: impl Fn(String)) -> Result<(), CommandError> {
+    let mounts = provider.list()?;
+    let refuse = |key: &str| {
+        let msg = t(key);
+        status(msg.clone());
+        Err(CommandError::GioError(msg))
+    };
+    let mount = match find_mount(&mounts, target) {
+        Some(mount) if mount.can_unmount => mount,
+        Some(_) => return refuse("mount.cannotUnmount"),
+        None => return refuse("mount.notFound"),
+    };
+    status(t("mount.unmounting"));
+    let result = provider.unmount(&mount.uri).await;
+    status(match &result {
+        Ok(()) => t("mount.unmounted"),
+        Err(e) => failure_message(e),
+    });
+    result
+}
+
+/// The mount rooted exactly at `target`, reporting each mount checked to
+/// `status`.
+pub fn locate(mounts: Vec<MountEntry>, target: &str, status: impl Fn(String)) -> Option<MountEntry> {
+    let normalized_target = with_trailing_slash(target);
+    for mount in mounts {
+        status(t_with("mount.checking", &[("uri", &mount.uri)]));
+        if with_trailing_slash(&mount.uri) == normalized_target {
+            return Some(mount);
+        }
+    }
+    status(t("mount.noMatch"));
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidecar::test_utils::MockGioEnv;
+    use std::sync::Mutex;
+    use tauri::async_runtime::block_on;
+
+    const TARGET: &str = "dav://localhost:8080";
+
+    #[test]
+    fn test_mount_reports_progress() {
+        let env = MockGioEnv::new();
+        let log = Mutex::new(Vec::new());
+        block_on(mount(&env, TARGET, false, |msg| log.lock().unwrap().push(msg))).unwrap();
+        assert_eq!(log.into_inner().unwrap(), vec![t("mount.mounting"), t("mount.mounted")]);
+        assert_eq!(env.list().unwrap()[0].uri, "dav://localhost:8080/");
+    }
+
+    #[test]
+    fn test_mount_failure_is_reported() {
+        let mut env = MockGioEnv::new();
+        env.set_mount_failure(TARGET.to_string(), "Connection refused".to_string());
+        let log = Mutex::new(Vec::new());
+        let err = block_on(mount(&env, TARGET, false, |msg| log.lock().unwrap().push(msg))).unwrap_err();
+        assert_eq!(err.code(), "GIO_ERROR");
+        assert_eq!(log.into_inner().unwrap().last().map(String::as_str), Some("Failed to mount: Connection refused"));
+        assert!(env.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unmount_checks_the_mount() {
+        let mut env = MockGioEnv::new();
+        let log = Mutex::new(Vec::new());
+        let status = |msg| log.lock().unwrap().push(msg);
+        assert!(matches!(block_on(unmount(&env, TARGET, status)), Err(CommandError::GioError(msg)) if msg == t("mount.notFound")));
+
+        env.add_mount("dav://localhost:8080/".to_string(), false);
+        assert!(matches!(block_on(unmount(&env, TARGET, status)), Err(CommandError::GioError(msg)) if msg == t("mount.cannotUnmount")));
+
+        env.clear_mounts();
+        // Found through the port when GIO reports another host
+        env.add_mount("dav://127.0.0.1:8080/".to_string(), true);
+        block_on(unmount(&env, TARGET, status)).unwrap();
+        assert!(env.list().unwrap().is_empty());
+        assert_eq!(log.lock().unwrap().last(), Some(&t("mount.unmounted")));
+    }
+
+    #[test]
+    fn test_locate_needs_exact_root() {
+        let mut env = MockGioEnv::new();
+        env.add_mount("dav://127.0.0.1:8080/".to_string(), true);
+        env.add_mount("dav://localhost:8080/".to_string(), true);
+        let log = Mutex::new(Vec::new());
+        let status = |msg| log.lock().unwrap().push(msg);
+        assert_eq!(locate(env.list().unwrap(), TARGET, status).unwrap().uri, "dav://localhost:8080/");
+        assert_eq!(log.lock().unwrap().len(), 2);
+
+        assert!(locate(env.list().unwrap(), "dav://localhost:9090", status).is_none());
+        assert_eq!(log.lock().unwrap().last(), Some(&t("mount.noMatch")));
+    }
+}
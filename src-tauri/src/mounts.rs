@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::dav::{normalize_path, DavClient};
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Mount definitions
+// ============================================================================
+//
+// Besides the main mount driven by `remotePath`, users can define extra
+// mounts that each expose one Proton Drive folder at its own GIO location
+// (`dav://localhost:<port>/<folder>`). Definitions live in the `mounts`
+// config section and are mounted independently; every state change is
+// reported with a `mount:changed` event carrying the mount id.
+
+const CONFIG_KEY: &str = "mounts";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MountDefinition {
+    pub id: String,
+    pub name: String,
+    pub remote_path: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MountInfo {
+    #[serde(flatten)]
+    pub definition: MountDefinition,
+    /// GIO location the definition mounts
+    pub uri: String,
+    pub mounted: bool,
+    /// Local path of the mount (GVFS FUSE directory), when mounted
+    pub mount_point: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MountState {
+    Mounting,
+    Mounted,
+    Unmounted,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MountChangedEvent {
+    pub id: String,
+    pub state: MountState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn load_definitions() -> Vec<MountDefinition> {
+    read_config_section(CONFIG_KEY)
+}
+
+fn find_definition(id: &str) -> Result<MountDefinition, CommandError> {
+    load_definitions()
+        .into_iter()
+        .find(|d| d.id == id)
+        .ok_or_else(|| CommandError::MountNotFound(id.to_string()))
+}
+
+/// Port and scheme of the share: the running gateway's, else the configured.
+pub(crate) fn share_uri(app: &AppHandle, remote_path: &str) -> String {
+    let port = crate::gateway::public_port(app).unwrap_or_else(|| crate::sidecar::configured_listen_addr().1);
+    crate::sidecar::dav_uri(crate::tls::enabled_certificate().is_some(), port, remote_path)
+}
+
+/// Location of the extra mount `id`.
+pub(crate) fn mount_uri(app: &AppHandle, id: &str) -> Result<String, CommandError> {
+    Ok(share_uri(app, &find_definition(id)?.remote_path))
+}
+
+fn with_trailing_slash(uri: &str) -> String {
+    if uri.ends_with('/') {
+        uri.to_string()
+    } else {
+        format!("{}/", uri)
+    }
+}
+
+/// GIO mount whose root is exactly `uri`. Unlike `mount_provider::find_mount`
+/// there is no port-only fallback, since several mounts share the same port.
+#[cfg(target_os = "linux")]
+fn find_gio_mount(uri: &str) -> Option<gio::Mount> {
+    use gio::prelude::*;
+
+    let target = with_trailing_slash(uri);
+    crate::sidecar::get_cached_mounts()
+        .into_iter()
+        .find(|m| with_trailing_slash(&m.root().uri()) == target)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn mount_status(uri: &str) -> (bool, Option<String>) {
+    use gio::prelude::*;
+
+    match find_gio_mount(uri) {
+        Some(m) => (true, m.root().path().map(|p| p.display().to_string())),
+        None => (false, None),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn mount_status(_uri: &str) -> (bool, Option<String>) {
+    (false, None)
+}
+
+fn info(app: &AppHandle, definition: MountDefinition) -> MountInfo {
+    let uri = share_uri(app, &definition.remote_path);
+    let (mounted, mount_point) = mount_status(&uri);
+    MountInfo { definition, uri, mounted, mount_point }
+}
+
+fn emit_state(app: &AppHandle, id: &str, state: MountState, error: Option<String>) {
+    let _ = app.emit("mount:changed", MountChangedEvent { id: id.to_string(), state, error });
+}
+
+fn new_mount_id() -> String {
+    format!("{:08x}", rand::random::<u32>())
+}
+
+/// Reject a second definition for the same folder.
+fn check_unique(definitions: &[MountDefinition], remote_path: &str) -> Result<(), CommandError> {
+    match definitions.iter().find(|d| d.remote_path == remote_path) {
+        Some(existing) => Err(CommandError::InvalidRemotePath(format!("{} is already mounted as \"{}\"", remote_path, existing.name))),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn list_mounts(app: AppHandle) -> Result<Vec<MountInfo>, CommandError> {
+    Ok(load_definitions().into_iter().map(|d| info(&app, d)).collect())
+}
+
+/// Define a new mount for `remote_path`. When the bridge is running the
+/// folder must exist.
+#[tauri::command]
+pub async fn add_mount(app: AppHandle, name: String, remote_path: String) -> Result<MountInfo, CommandError> {
+    let remote_path = normalize_path(&remote_path);
+    let mut definitions = load_definitions();
+    check_unique(&definitions, &remote_path)?;
+    if let Ok(client) = DavClient::for_app(&app) {
+        match client.stat(&remote_path).await? {
+            Some(entry) if entry.is_dir => {}
+            Some(_) => return Err(CommandError::InvalidRemotePath(format!("{} is not a folder", remote_path))),
+            None => return Err(CommandError::RemotePathNotFound(remote_path)),
+        }
+    }
+
+    let name = match name.trim() {
+        "" => remote_path.rsplit('/').find(|s| !s.is_empty()).unwrap_or("Proton Drive").to_string(),
+        n => n.to_string(),
+    };
+    let definition = MountDefinition { id: new_mount_id(), name, remote_path };
+    definitions.push(definition.clone());
+    write_config_section(CONFIG_KEY, &definitions)?;
+    emit_state(&app, &definition.id, MountState::Unmounted, None);
+    Ok(info(&app, definition))
+}
+
+/// Delete a mount definition, unmounting it first if needed.
+#[tauri::command]
+pub async fn remove_mount(app: AppHandle, id: String) -> Result<(), CommandError> {
+    let definition = find_definition(&id)?;
+    if mount_status(&share_uri(&app, &definition.remote_path)).0 {
+        unmount_by_id(app.clone(), id.clone()).await?;
+    }
+    let definitions: Vec<MountDefinition> = load_definitions().into_iter().filter(|d| d.id != id).collect();
+    write_config_section(CONFIG_KEY, &definitions)
+}
+
+#[tauri::command]
+pub async fn mount_by_id(app: AppHandle, id: String) -> Result<MountInfo, CommandError> {
+    let definition = find_definition(&id)?;
+    if !crate::gateway::is_running(&app) {
+        return Err(CommandError::ServerNotRunning);
+    }
+    let uri = share_uri(&app, &definition.remote_path);
+    emit_state(&app, &id, MountState::Mounting, None);
+
+    #[cfg(target_os = "linux")]
+    let result = crate::mount_operation::mount_with_retry(&app, &uri, crate::tls::enabled_certificate().is_some()).await;
+
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("open").arg(&uri).spawn().map(|_| ()).map_err(CommandError::from);
+
+    #[cfg(target_os = "windows")]
+    let result = std::process::Command::new("explorer").arg(&uri).spawn().map(|_| ()).map_err(CommandError::from);
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let result: Result<(), CommandError> = Err(CommandError::Unknown("Platform not supported".into()));
+
+    match result {
+        Ok(()) => {
+            emit_state(&app, &id, MountState::Mounted, None);
+            crate::onboarding::record_first_mount(&app);
+            Ok(info(&app, definition))
+        }
+        Err(e) => {
+            log::error!("Mount {} ({}) failed: {}", id, uri, e);
+            emit_state(&app, &id, MountState::Failed, Some(e.to_string()));
+            crate::notifications::notify(
+                &app,
+                crate::notifications::NotificationCategory::MountFailure,
+                &format!("Failed to mount {}", definition.name),
+                &e.to_string(),
+            );
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn unmount_by_id(app: AppHandle, id: String) -> Result<MountInfo, CommandError> {
+    let definition = find_definition(&id)?;
+
+    #[cfg(target_os = "linux")]
+    {
+        use gio::prelude::*;
+
+        let uri = share_uri(&app, &definition.remote_path);
+        // gio::Mount isn't Send, so only its location is kept across awaits
+        let root_uri = {
+            let mount = find_gio_mount(&uri).ok_or_else(|| CommandError::GioError("Mount not found".into()))?;
+            if !mount.can_unmount() {
+                return Err(CommandError::GioError("Mount cannot be unmounted via GIO".into()));
+            }
+            mount.root().uri().to_string()
+        };
+        if let Err(e) = crate::mount_operation::unmount_with_timeout(&app, &root_uri).await {
+            emit_state(&app, &id, MountState::Failed, Some(e.to_string()));
+            return Err(e);
+        }
+        emit_state(&app, &id, MountState::Unmounted, None);
+        Ok(info(&app, definition))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = definition;
+        Err(CommandError::Unknown("Platform not supported".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_unique_rejects_same_folder() {
+        let definitions = vec![MountDefinition { id: "a".into(), name: "Docs".into(), remote_path: "/Documents".into() }];
+        assert!(check_unique(&definitions, "/Documents").is_err());
+        assert!(check_unique(&definitions, "/Photos").is_ok());
+    }
+
+    #[test]
+    fn test_mount_info_flattens_definition() {
+        let info = MountInfo {
+            definition: MountDefinition { id: "a".into(), name: "Docs".into(), remote_path: "/Documents".into() },
+            uri: "dav://localhost:8080/Documents".into(),
+            mounted: false,
+            mount_point: None,
+        };
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["remotePath"], "/Documents");
+        assert_eq!(json["uri"], "dav://localhost:8080/Documents");
+    }
+}
@@ -0,0 +1,293 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::db;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// File name mapping
+// ============================================================================
+//
+// Names that are fine on the drive can be impossible on a local filesystem:
+// Windows (and FAT, exFAT and SMB volumes elsewhere) rejects `< > : " \ | ?
+// *`, control characters, trailing dots and spaces, and device names such as
+// `CON` or `lpt1.txt`. Sync, the backups, photo backup and the drop folder
+// therefore store such names locally with the problem characters
+// percent-encoded (`a:b` becomes `a%3Ab`, `notes.` becomes `notes%2E`) and
+// decode them again on the way up, so the remote name survives the round
+// trip. A `%` that would read as one of these escapes is encoded as `%25`.
+//
+// A local name that looks encoded but isn't what encoding produces (say a
+// file really called `a%2Eb`) cannot be told apart and is skipped by sync
+// and the backups rather than uploaded under a name that would come back
+// different. Mapping is on for Windows rules on Windows by default
+// (`auto`); `windows` turns it on everywhere, `off` everywhere. Mapped names
+// seen by the latest runs are kept in the state database for
+// `list_renamed_items`.
+
+const CONFIG_KEY: &str = "nameMapping";
+
+/// Characters Windows does not allow in names, besides control characters.
+const RESERVED_CHARS: &[char] = &['<', '>', ':', '"', '\\', '|', '?', '*'];
+
+/// Device names Windows reserves, with or without an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2", "LPT3",
+    "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MappingMode {
+    /// Windows rules on Windows, none elsewhere
+    #[default]
+    Auto,
+    Windows,
+    Off,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NameMappingSettings {
+    pub mode: MappingMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedItem {
+    /// The feature that stored the file: `sync:<pair id>`, `backup:<job
+    /// id>`, `photoBackup` or `dropFolder`
+    pub source: String,
+    pub local_path: String,
+    pub remote_path: String,
+    /// Unix seconds
+    pub seen_at: u64,
+}
+
+/// Maps names between the drive and the local filesystem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct NameMapper {
+    windows: bool,
+}
+
+/// Whether `c` is encoded: a character Windows rejects, or one that only
+/// needs it in some positions.
+fn is_escaped_char(c: char) -> bool {
+    RESERVED_CHARS.contains(&c) || c.is_ascii_control() || matches!(c, '%' | '.' | ' ')
+}
+
+/// Whether `c` is the first letter of a reserved device name.
+fn is_device_initial(c: char) -> bool {
+    matches!(c.to_ascii_uppercase(), 'C' | 'P' | 'A' | 'N' | 'L')
+}
+
+/// The character a `%XX` escape at the start of `s` stands for, if it is one
+/// this mapping produces.
+fn escape_at(s: &str) -> Option<char> {
+    let hex = s.strip_prefix('%')?.get(..2)?;
+    if hex.chars().any(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    let c = char::from(u8::from_str_radix(hex, 16).ok()?);
+    (is_escaped_char(c) || is_device_initial(c)).then_some(c)
+}
+
+fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem))
+}
+
+fn push_escape(out: &mut String, c: char) {
+    out.push_str(&format!("%{:02X}", c as u32));
+}
+
+impl NameMapper {
+    pub fn current() -> Self {
+        let settings: NameMappingSettings = read_config_section(CONFIG_KEY);
+        let windows = match settings.mode {
+            MappingMode::Auto => cfg!(windows),
+            MappingMode::Windows => true,
+            MappingMode::Off => false,
+        };
+        Self { windows }
+    }
+
+    #[cfg(test)]
+    pub fn windows() -> Self {
+        Self { windows: true }
+    }
+
+    #[cfg(test)]
+    pub fn off() -> Self {
+        Self { windows: false }
+    }
+
+    /// The local name for the remote name `name`.
+    pub fn local_name(&self, name: &str) -> String {
+        if !self.windows {
+            return name.to_string();
+        }
+        let trailing = name.trim_end_matches(['.', ' ']).len();
+        let reserved = is_reserved_name(name);
+        let looks_encoded = name.match_indices('%').any(|(i, _)| escape_at(&name[i..]).is_some());
+        if trailing == name.len() && !reserved && !looks_encoded && !name.chars().any(|c| RESERVED_CHARS.contains(&c) || c.is_ascii_control()) {
+            return name.to_string();
+        }
+        let mut out = String::with_capacity(name.len() + 6);
+        for (i, c) in name.char_indices() {
+            let device_initial = reserved && i == 0;
+            if RESERVED_CHARS.contains(&c) || c.is_ascii_control() || i >= trailing || device_initial {
+                push_escape(&mut out, c);
+            } else if c == '%' && escape_at(&name[i..]).is_some() {
+                out.push_str("%25");
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    /// The remote name for the local name `name`, or `None` when `name`
+    /// looks encoded without being what encoding produces.
+    pub fn remote_name(&self, name: &str) -> Option<String> {
+        if !self.windows {
+            return Some(name.to_string());
+        }
+        let mut decoded = String::with_capacity(name.len());
+        let mut rest = name;
+        while let Some(c) = rest.chars().next() {
+            match escape_at(rest) {
+                Some(escaped) => {
+                    decoded.push(escaped);
+                    rest = &rest[3..];
+                }
+                None => {
+                    decoded.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        (self.local_name(&decoded) == name).then_some(decoded)
+    }
+
+    /// `rel`, a `/`-separated remote path, with every name mapped to local.
+    pub fn local_rel(&self, rel: &str) -> String {
+        rel.split('/').map(|name| self.local_name(name)).collect::<Vec<_>>().join("/")
+    }
+
+    /// `rel`, a `/`-separated local path, with every name mapped to remote;
+    /// names that don't decode are kept as they are.
+    pub fn remote_rel(&self, rel: &str) -> String {
+        rel.split('/').map(|name| self.remote_name(name).unwrap_or_else(|| name.to_string())).collect::<Vec<_>>().join("/")
+    }
+}
+
+fn store(conn: &mut Connection, source: &str, items: &[RenamedItem], replace: bool) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    if replace {
+        tx.execute("DELETE FROM renamed_items WHERE source = ?1", [source])?;
+    }
+    for item in items {
+        tx.execute(
+            "INSERT INTO renamed_items (source, local_path, data) VALUES (?1, ?2, ?3)
+             ON CONFLICT (source, local_path) DO UPDATE SET data = excluded.data",
+            rusqlite::params![source, item.local_path, db::to_json(item)?],
+        )?;
+    }
+    tx.commit()
+}
+
+fn load(conn: &Connection, source: Option<&str>) -> rusqlite::Result<Vec<RenamedItem>> {
+    let mut stmt = conn.prepare("SELECT data FROM renamed_items WHERE ?1 IS NULL OR source = ?1 ORDER BY source, local_path")?;
+    let items = stmt
+        .query_map([source], |row| row.get::<_, String>(0))?
+        .map(|json| db::from_json(&json?))
+        .collect();
+    items
+}
+
+/// A renamed item of `source` seen now.
+pub(crate) fn renamed(source: &str, local_path: String, remote_path: String) -> RenamedItem {
+    RenamedItem { source: source.to_string(), local_path, remote_path, seen_at: crate::sidecar::unix_now() }
+}
+
+/// Replace the renamed items recorded for `source` with those of its latest
+/// run.
+pub(crate) fn replace(app: &AppHandle, source: &str, items: &[RenamedItem]) {
+    if let Err(e) = db::with(app, |conn| store(conn, source, items, true)) {
+        log::warn!("Failed to record renamed items of {}: {}", source, e);
+    }
+}
+
+/// Record one more renamed item of `source`.
+pub(crate) fn record(app: &AppHandle, item: RenamedItem) {
+    if let Err(e) = db::with(app, |conn| store(conn, &item.source, std::slice::from_ref(&item), false)) {
+        log::warn!("Failed to record renamed item {}: {}", item.local_path, e);
+    }
+}
+
+/// Files stored locally under a different name than on the drive, as seen by
+/// the latest runs; only those of `source` when given.
+#[tauri::command]
+pub async fn list_renamed_items(app: AppHandle, source: Option<String>) -> Result<Vec<RenamedItem>, CommandError> {
+    db::with(&app, |conn| load(conn, source.as_deref()))
+}
+
+#[tauri::command]
+pub async fn get_name_mapping() -> Result<NameMappingSettings, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+/// Takes effect from the next run; files already stored locally keep their
+/// names until then.
+#[tauri::command]
+pub async fn set_name_mapping(settings: NameMappingSettings) -> Result<NameMappingSettings, CommandError> {
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_names_round_trip() {
+        let names = NameMapper::windows();
+        for (remote, local) in [
+            ("report.odt", "report.odt"),
+            ("a:b?.txt", "a%3Ab%3F.txt"),
+            ("notes. ", "notes%2E%20"),
+            ("con.txt", "%63on.txt"),
+            ("LPT1", "%4CPT1"),
+            ("100% done", "100% done"),
+            ("a%3Ab", "a%253Ab"),
+            ("tab\there", "tab%09here"),
+        ] {
+            assert_eq!(names.local_name(remote), local, "{}", remote);
+            assert_eq!(names.remote_name(local).as_deref(), Some(remote), "{}", local);
+        }
+        // Looks encoded, but encoding "a.b" leaves it alone
+        assert_eq!(names.remote_name("a%2Eb"), None);
+        assert_eq!(names.local_rel("x:/y/z."), "x%3A/y/z%2E");
+        assert_eq!(names.remote_rel("x%3A/a%2Eb"), "x:/a%2Eb");
+    }
+
+    #[test]
+    fn test_mapping_off_keeps_names() {
+        let names = NameMapper::off();
+        assert_eq!(names.local_name("a:b."), "a:b.");
+        assert_eq!(names.remote_name("a%2Eb").as_deref(), Some("a%2Eb"));
+    }
+
+    #[test]
+    fn test_renamed_items_replaced_per_source() {
+        let mut conn = db::open_in_memory();
+        let item = |source: &str, local: &str| renamed(source, local.into(), "/r".into());
+        store(&mut conn, "sync:a", &[item("sync:a", "/l/1"), item("sync:a", "/l/2")], true).unwrap();
+        store(&mut conn, "dropFolder", &[item("dropFolder", "/d/1")], false).unwrap();
+        store(&mut conn, "sync:a", &[item("sync:a", "/l/3")], true).unwrap();
+        assert_eq!(load(&conn, Some("sync:a")).unwrap().len(), 1);
+        assert_eq!(load(&conn, None).unwrap().len(), 2);
+    }
+}
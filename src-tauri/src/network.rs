@@ -0,0 +1,203 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::sidecar::{CommandError, SidecarState};
+
+// ============================================================================
+// Network connectivity
+// ============================================================================
+//
+// The bridge only works while Proton's API is reachable. On Linux the GIO
+// network monitor reports connectivity (including captive portals) on a
+// dedicated GLib thread; elsewhere a TCP connection to the API host is tried
+// every `PROBE_INTERVAL`. Going offline emits `network:offline` and pauses
+// the background loops and auto-mount retries; coming back emits
+// `network:online` and remounts the drive if the mount was lost meanwhile.
+
+/// Probed by the portable reachability check.
+#[cfg(not(target_os = "linux"))]
+const PROBE_ADDR: &str = "drive-api.proton.me:443";
+
+#[cfg(not(target_os = "linux"))]
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[cfg(not(target_os = "linux"))]
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time for DNS and routes to settle before the mount is checked.
+const ONLINE_SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OfflineReason {
+    Disconnected,
+    /// Connected, but a captive portal intercepts traffic
+    CaptivePortal,
+    /// Connected without internet access
+    Limited,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub online: bool,
+    pub reason: Option<OfflineReason>,
+}
+
+impl NetworkStatus {
+    const ONLINE: Self = Self { online: true, reason: None };
+
+    fn offline(reason: OfflineReason) -> Self {
+        Self { online: false, reason: Some(reason) }
+    }
+}
+
+pub struct NetworkState {
+    status: Mutex<NetworkStatus>,
+    /// Whether the drive was mounted when the connection dropped
+    mounted_before: Mutex<bool>,
+}
+
+impl NetworkState {
+    pub fn new() -> Self {
+        Self { status: Mutex::new(NetworkStatus::ONLINE), mounted_before: Mutex::new(false) }
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.status.lock().unwrap().online
+    }
+}
+
+/// Wait until the network is back. Returns at once when online.
+pub async fn wait_until_online(app: &AppHandle) {
+    let state = app.state::<NetworkState>();
+    if state.is_online() {
+        return;
+    }
+    log::info!("Waiting for the network to come back");
+    while !state.is_online() {
+        tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+    }
+}
+
+async fn update(app: AppHandle, status: NetworkStatus) {
+    let state = app.state::<NetworkState>();
+    let previous = std::mem::replace(&mut *state.status.lock().unwrap(), status);
+    if previous == status {
+        return;
+    }
+    crate::status::invalidate(&app);
+    if !status.online {
+        if previous.online {
+            log::warn!("Network is offline ({:?})", status.reason);
+            let mounted = matches!(crate::sidecar::check_mount_status(app.clone(), app.state::<SidecarState>()).await, Ok(Some(_)));
+            *state.mounted_before.lock().unwrap() = mounted;
+        }
+        let _ = app.emit("network:offline", status);
+        return;
+    }
+    log::info!("Network is back online");
+    let _ = app.emit("network:online", status);
+    let mounted_before = std::mem::take(&mut *state.mounted_before.lock().unwrap());
+    if !mounted_before {
+        return;
+    }
+    tokio::time::sleep(ONLINE_SETTLE_DELAY).await;
+    if !state.is_online() {
+        return;
+    }
+    if !matches!(crate::sidecar::check_mount_status(app.clone(), app.state::<SidecarState>()).await, Ok(Some(_))) {
+        log::info!("Remounting the drive after the network came back");
+        crate::auto_mount::mount_when_ready(app).await;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn status_from(available: bool, connectivity: gio::NetworkConnectivity) -> NetworkStatus {
+    match connectivity {
+        _ if !available => NetworkStatus::offline(OfflineReason::Disconnected),
+        gio::NetworkConnectivity::Full => NetworkStatus::ONLINE,
+        gio::NetworkConnectivity::Portal => NetworkStatus::offline(OfflineReason::CaptivePortal),
+        gio::NetworkConnectivity::Limited => NetworkStatus::offline(OfflineReason::Limited),
+        _ => NetworkStatus::offline(OfflineReason::Disconnected),
+    }
+}
+
+/// Follow the GIO network monitor on a dedicated GLib thread.
+#[cfg(target_os = "linux")]
+pub fn spawn(app: AppHandle) {
+    use gio::prelude::*;
+
+    let spawned = std::thread::Builder::new().name("gio-network-monitor".into()).spawn(move || {
+        let context = glib::MainContext::new();
+        let result = context.with_thread_default(|| {
+            // Like the volume monitor, signals arrive on the thread-default
+            // context the monitor was obtained on.
+            let monitor = gio::NetworkMonitor::default();
+            let initial = status_from(monitor.is_network_available(), monitor.connectivity());
+            tauri::async_runtime::spawn(update(app.clone(), initial));
+            let changed_app = app.clone();
+            monitor.connect_network_changed(move |monitor, available| {
+                let status = status_from(available, monitor.connectivity());
+                tauri::async_runtime::spawn(update(changed_app.clone(), status));
+            });
+            glib::MainLoop::new(Some(&context), false).run();
+        });
+        if let Err(e) = result {
+            log::error!("Network monitor stopped: {}", e);
+        }
+    });
+    if let Err(e) = spawned {
+        log::error!("Failed to start the network monitor: {}", e);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn probe() -> NetworkStatus {
+    match tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(PROBE_ADDR)).await {
+        Ok(Ok(_)) => NetworkStatus::ONLINE,
+        _ => NetworkStatus::offline(OfflineReason::Disconnected),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            update(app.clone(), probe().await).await;
+            tokio::time::sleep(PROBE_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_network_status(app: AppHandle) -> Result<NetworkStatus, CommandError> {
+    Ok(*app.state::<NetworkState>().status.lock().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_serializes_camel_case() {
+        let json = serde_json::to_value(NetworkStatus::offline(OfflineReason::CaptivePortal)).unwrap();
+        assert_eq!(json, serde_json::json!({"online": false, "reason": "captivePortal"}));
+        let json = serde_json::to_value(NetworkStatus::ONLINE).unwrap();
+        assert_eq!(json, serde_json::json!({"online": true, "reason": null}));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_status_from_connectivity() {
+        assert_eq!(status_from(true, gio::NetworkConnectivity::Full), NetworkStatus::ONLINE);
+        assert_eq!(status_from(false, gio::NetworkConnectivity::Full), NetworkStatus::offline(OfflineReason::Disconnected));
+        assert_eq!(status_from(true, gio::NetworkConnectivity::Portal), NetworkStatus::offline(OfflineReason::CaptivePortal));
+        assert_eq!(status_from(true, gio::NetworkConnectivity::Limited), NetworkStatus::offline(OfflineReason::Limited));
+        assert_eq!(status_from(true, gio::NetworkConnectivity::Local), NetworkStatus::offline(OfflineReason::Disconnected));
+    }
+}
@@ -0,0 +1,542 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::Engine;
+use hyper::header::{HeaderMap, AUTHORIZATION};
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::sidecar::{read_config_section, set_webdav_key, update_config_json, write_config_section, CommandError};
+use crate::webdav_auth::MIN_PASSWORD_LEN;
+
+// ============================================================================
+// Network sharing
+// ============================================================================
+//
+// Exposes the gateway on a LAN interface so other machines can use this box
+// as a Proton Drive WebDAV server. Clients on other hosts must present HTTP
+// Basic credentials (enable HTTPS to keep them off the wire in clear text),
+// and clients that keep failing to log in are banned for a while (see
+// `auth_guard`); loopback clients such as the local GVFS mount are always
+// admitted. The gateway reports every request here so the UI can show who
+// is connected. While sharing is off the gateway only ever listens on
+// loopback, whatever `webdav.host` says.
+
+const CONFIG_KEY: &str = "networkSharing";
+
+const MDNS_INSTANCE_NAME: &str = "Proton Drive WebDAV Bridge";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct SharingConfig {
+    enabled: bool,
+    /// Address to listen on; `0.0.0.0` means every interface
+    bind_address: String,
+    require_auth: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    /// Argon2id PHC string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password_hash: Option<String>,
+    mdns: bool,
+}
+
+impl Default for SharingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "0.0.0.0".to_string(),
+            require_auth: true,
+            username: None,
+            password_hash: None,
+            mdns: false,
+        }
+    }
+}
+
+impl SharingConfig {
+    fn has_credentials(&self) -> bool {
+        self.username.is_some() && self.password_hash.is_some()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkSharingStatus {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub require_auth: bool,
+    pub username: Option<String>,
+    pub has_password: bool,
+    pub mdns: bool,
+    /// Whether mDNS is currently announcing the share
+    pub mdns_active: bool,
+    /// The running gateway listens on different addresses than configured;
+    /// restart the server to apply
+    pub restart_required: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkInterface {
+    pub name: String,
+    pub address: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientInfo {
+    pub address: String,
+    /// Unix timestamps (seconds)
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub requests: u64,
+    pub active_connections: u32,
+    pub auth_failures: u64,
+}
+
+#[derive(Default)]
+pub struct NetworkSharingState {
+    config: Mutex<SharingConfig>,
+    clients: Mutex<HashMap<IpAddr, ClientInfo>>,
+    /// Digest of the last Authorization header that passed verification, so
+    /// the password hash isn't recomputed for every request.
+    verified: Mutex<Option<[u8; 32]>>,
+    mdns: Mutex<Option<ServiceDaemon>>,
+}
+
+fn hash_password(password: &str, params: argon2::Params) -> Result<String, CommandError> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|e| CommandError::Unknown(e.to_string()))?;
+    Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| CommandError::Unknown(e.to_string()))
+}
+
+/// Check `password` against a stored hash, with the parameters it was made
+/// with. Only runs when `verified` doesn't already know the header.
+fn verify_password(hash: &str, password: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// Split a `Basic` Authorization header into user and password.
+pub(crate) fn parse_basic_auth(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, encoded) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    let (user, pass) = std::str::from_utf8(&decoded).ok()?.split_once(':')?;
+    Some((user.to_string(), pass.to_string()))
+}
+
+fn is_unspecified(addr: &str) -> bool {
+    addr.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified())
+}
+
+/// Whether `host` only accepts connections from this machine.
+fn is_loopback_host(host: &str) -> bool {
+    host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.to_canonical().is_loopback())
+}
+
+/// Warning for a share listening on `hosts` that other machines can reach
+/// without logging in.
+pub(crate) fn exposure_warning(hosts: &[String], auth_required: bool) -> Option<String> {
+    let exposed: Vec<&str> = hosts.iter().map(String::as_str).filter(|h| !is_loopback_host(h)).collect();
+    if exposed.is_empty() || auth_required {
+        return None;
+    }
+    Some(format!("The drive is reachable from the network on {} without authentication", exposed.join(", ")))
+}
+
+/// Anything but loopback needs sharing to be enabled with a login.
+fn check_bind_address(config: &SharingConfig, address: &str) -> Result<(), CommandError> {
+    if address != "localhost" && address.parse::<IpAddr>().is_err() {
+        return Err(CommandError::InvalidBindAddress(format!("Not an IP address: {}", address)));
+    }
+    let shared_with_login = config.enabled && config.require_auth && config.has_credentials();
+    if !(is_loopback_host(address) || shared_with_login) {
+        return Err(CommandError::InvalidBindAddress(
+            "Only loopback addresses are allowed unless network sharing is enabled with a username and password".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Host name for the mDNS record, which must end in `.local.`.
+fn mdns_host_name() -> String {
+    let name = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "proton-drive-bridge".to_string());
+    format!("{}.local.", name.trim_end_matches(".local"))
+}
+
+impl NetworkSharingState {
+    pub fn from_config() -> Self {
+        Self { config: Mutex::new(read_config_section(CONFIG_KEY)), ..Default::default() }
+    }
+
+    /// Pick up the `networkSharing` section after config.json was replaced.
+    /// The bind address still takes effect on the next start.
+    pub fn reload_settings(&self) {
+        *self.config.lock().unwrap() = read_config_section(CONFIG_KEY);
+        *self.verified.lock().unwrap() = None;
+    }
+
+    /// Addresses the gateway should listen on. With sharing enabled on a
+    /// specific interface, loopback is kept so the local mount still works;
+    /// without sharing a non-loopback `default_host` is replaced by loopback.
+    pub fn listen_hosts(&self, default_host: &str) -> Vec<String> {
+        let config = self.config.lock().unwrap();
+        if !config.enabled {
+            if !is_loopback_host(default_host) {
+                log::warn!("Not exposing the share on {} while network sharing is off", default_host);
+                return vec!["127.0.0.1".to_string()];
+            }
+            return vec![default_host.to_string()];
+        }
+        if is_unspecified(&config.bind_address) {
+            vec![config.bind_address.clone()]
+        } else {
+            vec!["127.0.0.1".to_string(), config.bind_address.clone()]
+        }
+    }
+
+    /// Whether [`Self::authorize`] demands credentials from this peer, i.e.
+    /// it is a LAN client and the share requires a login.
+    pub fn checks_credentials(&self, peer: SocketAddr) -> bool {
+        !peer.ip().to_canonical().is_loopback() && self.config.lock().unwrap().require_auth
+    }
+
+    /// Check a request against the sharing policy. Returns `false` if the
+    /// client must authenticate first.
+    pub fn authorize(&self, peer: SocketAddr, headers: &HeaderMap) -> bool {
+        let ip = peer.ip().to_canonical();
+        if ip.is_loopback() {
+            return true;
+        }
+        let config = self.config.lock().unwrap().clone();
+        if !config.require_auth {
+            return true;
+        }
+
+        let header_digest: Option<[u8; 32]> =
+            headers.get(AUTHORIZATION).map(|v| Sha256::digest(v.as_bytes()).into());
+        if header_digest.is_some() && *self.verified.lock().unwrap() == header_digest {
+            return true;
+        }
+
+        let ok = match (parse_basic_auth(headers), &config.username, &config.password_hash) {
+            (Some((user, pass)), Some(expected_user), Some(hash)) => user == *expected_user && verify_password(hash, &pass),
+            _ => false,
+        };
+        if ok {
+            *self.verified.lock().unwrap() = header_digest;
+        } else if headers.contains_key(AUTHORIZATION) {
+            if let Some(client) = self.clients.lock().unwrap().get_mut(&ip) {
+                client.auth_failures += 1;
+            }
+        }
+        ok
+    }
+
+    /// Record a new connection. Emits `network-sharing:client-connected` the
+    /// first time a remote address is seen.
+    pub fn connection_opened(&self, app: &AppHandle, peer: SocketAddr) {
+        let ip = peer.ip().to_canonical();
+        let now = crate::sidecar::unix_now();
+        let mut clients = self.clients.lock().unwrap();
+        let is_new = !clients.contains_key(&ip);
+        let client = clients.entry(ip).or_insert_with(|| ClientInfo {
+            address: ip.to_string(),
+            first_seen: now,
+            last_seen: now,
+            requests: 0,
+            active_connections: 0,
+            auth_failures: 0,
+        });
+        client.active_connections += 1;
+        client.last_seen = now;
+        if is_new && !ip.is_loopback() {
+            let _ = app.emit("network-sharing:client-connected", client.clone());
+        }
+    }
+
+    pub fn connection_closed(&self, peer: SocketAddr) {
+        if let Some(client) = self.clients.lock().unwrap().get_mut(&peer.ip().to_canonical()) {
+            client.active_connections = client.active_connections.saturating_sub(1);
+        }
+    }
+
+    pub fn record_request(&self, peer: SocketAddr) {
+        if let Some(client) = self.clients.lock().unwrap().get_mut(&peer.ip().to_canonical()) {
+            client.requests += 1;
+            client.last_seen = crate::sidecar::unix_now();
+        }
+    }
+
+    /// Start or stop the mDNS announcement to match the configuration.
+    pub fn announce(&self, port: u16, https: bool) {
+        self.withdraw();
+        let config = self.config.lock().unwrap().clone();
+        if !config.enabled || !config.mdns {
+            return;
+        }
+        let service_type = if https { "_webdavs._tcp.local." } else { "_webdav._tcp.local." };
+        let host_name = mdns_host_name();
+        let daemon = match ServiceDaemon::new() {
+            Ok(d) => d,
+            Err(e) => {
+                log::warn!("Failed to start mDNS responder: {}", e);
+                return;
+            }
+        };
+        let addresses = if is_unspecified(&config.bind_address) { "" } else { config.bind_address.as_str() };
+        let registered = ServiceInfo::new(service_type, MDNS_INSTANCE_NAME, &host_name, addresses, port, &[("path", "/")][..])
+            .map(|info| if addresses.is_empty() { info.enable_addr_auto() } else { info })
+            .and_then(|info| daemon.register(info));
+        match registered {
+            Ok(()) => {
+                log::info!("Announcing {} on {} port {}", service_type, host_name, port);
+                *self.mdns.lock().unwrap() = Some(daemon);
+            }
+            Err(e) => {
+                log::warn!("Failed to announce WebDAV share via mDNS: {}", e);
+                let _ = daemon.shutdown();
+            }
+        }
+    }
+
+    /// Whether the share is offered to other machines.
+    pub fn is_enabled(&self) -> bool {
+        self.config.lock().unwrap().enabled
+    }
+
+    /// Whether clients on other machines have to log in.
+    pub fn requires_auth(&self) -> bool {
+        self.config.lock().unwrap().require_auth
+    }
+
+    pub fn withdraw(&self) {
+        if let Some(daemon) = self.mdns.lock().unwrap().take() {
+            let _ = daemon.shutdown();
+        }
+    }
+
+    fn status(&self, app: &AppHandle) -> NetworkSharingStatus {
+        let config = self.config.lock().unwrap().clone();
+        let (default_host, _) = crate::sidecar::configured_listen_addr();
+        let restart_required = crate::gateway::bound_hosts(app).is_some_and(|bound| bound != self.listen_hosts(&default_host));
+        NetworkSharingStatus {
+            enabled: config.enabled,
+            bind_address: config.bind_address.clone(),
+            require_auth: config.require_auth,
+            username: config.username.clone(),
+            has_password: config.password_hash.is_some(),
+            mdns: config.mdns,
+            mdns_active: self.mdns.lock().unwrap().is_some(),
+            restart_required,
+        }
+    }
+
+    fn save(&self, config: SharingConfig) -> Result<(), CommandError> {
+        write_config_section(CONFIG_KEY, &config)?;
+        *self.config.lock().unwrap() = config;
+        *self.verified.lock().unwrap() = None;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub async fn get_network_sharing(app: AppHandle, state: State<'_, NetworkSharingState>) -> Result<NetworkSharingStatus, CommandError> {
+    Ok(state.status(&app))
+}
+
+/// Configure LAN sharing. The bind address takes effect the next time the
+/// server starts; authentication and mDNS apply immediately.
+#[tauri::command]
+pub async fn set_network_sharing(
+    app: AppHandle,
+    state: State<'_, NetworkSharingState>,
+    enabled: bool,
+    bind_address: String,
+    require_auth: bool,
+    mdns: bool,
+) -> Result<NetworkSharingStatus, CommandError> {
+    let bind_address = bind_address.trim().to_string();
+    if bind_address.parse::<IpAddr>().is_err() {
+        return Err(CommandError::InvalidSharingConfig(format!("Not an IP address: {}", bind_address)));
+    }
+    let current = state.config.lock().unwrap().clone();
+    if enabled && require_auth && !current.has_credentials() {
+        return Err(CommandError::InvalidSharingConfig("Set a username and password before enabling sharing".into()));
+    }
+
+    state.save(SharingConfig { enabled, bind_address, require_auth, mdns, ..current })?;
+    if let Some(port) = crate::gateway::public_port(&app) {
+        state.announce(port, app.state::<crate::tls::TlsState>().acceptor().is_some());
+    }
+
+    let status = state.status(&app);
+    let _ = app.emit("network-sharing:changed", status.clone());
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn set_sharing_credentials(
+    app: AppHandle,
+    state: State<'_, NetworkSharingState>,
+    username: String,
+    password: String,
+) -> Result<NetworkSharingStatus, CommandError> {
+    let username = username.trim().to_string();
+    if username.is_empty() || username.contains(':') {
+        return Err(CommandError::InvalidSharingConfig("Username must be non-empty and must not contain ':'".into()));
+    }
+    if password.len() < MIN_PASSWORD_LEN {
+        return Err(CommandError::InvalidSharingConfig(format!("Password must be at least {} characters", MIN_PASSWORD_LEN)));
+    }
+
+    // Argon2 takes a noticeable moment
+    let hash = tauri::async_runtime::spawn_blocking(move || hash_password(&password, argon2::Params::default()))
+        .await
+        .map_err(|e| CommandError::IoError(e.to_string()))??;
+    let current = state.config.lock().unwrap().clone();
+    state.save(SharingConfig { username: Some(username), password_hash: Some(hash), ..current })?;
+
+    let status = state.status(&app);
+    let _ = app.emit("network-sharing:changed", status.clone());
+    Ok(status)
+}
+
+/// Set the address the bridge listens on (`webdav.host`). Only loopback is
+/// accepted unless network sharing is enabled with a username and password.
+/// Takes effect the next time the server starts.
+#[tauri::command]
+pub async fn set_bind_address(state: State<'_, NetworkSharingState>, address: String) -> Result<String, CommandError> {
+    let address = address.trim().to_string();
+    check_bind_address(&state.config.lock().unwrap(), &address)?;
+    update_config_json(|v| {
+        set_webdav_key(v, "host", serde_json::json!(address));
+        Ok(())
+    })?;
+    log::info!("WebDAV bind address set to {}", address);
+    Ok(address)
+}
+
+/// Non-loopback IPv4/IPv6 addresses the share can be bound to.
+#[tauri::command]
+pub async fn list_network_interfaces() -> Result<Vec<NetworkInterface>, CommandError> {
+    let interfaces = if_addrs::get_if_addrs()?;
+    Ok(interfaces
+        .into_iter()
+        .filter(|i| !i.is_loopback())
+        .map(|i| NetworkInterface { name: i.name.clone(), address: i.ip().to_string() })
+        .collect())
+}
+
+/// Clients seen by the gateway, most recently active first.
+#[tauri::command]
+pub async fn list_connected_clients(state: State<'_, NetworkSharingState>) -> Result<Vec<ClientInfo>, CommandError> {
+    let mut clients: Vec<ClientInfo> = state.clients.lock().unwrap().values().cloned().collect();
+    clients.sort_by_key(|c| std::cmp::Reverse(c.last_seen));
+    Ok(clients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    fn state_with_credentials(user: &str, password: &str) -> NetworkSharingState {
+        let state = NetworkSharingState::default();
+        *state.config.lock().unwrap() = SharingConfig {
+            enabled: true,
+            username: Some(user.to_string()),
+            password_hash: Some(hash_password(password, argon2::Params::new(1024, 1, 1, None).unwrap()).unwrap()),
+            ..Default::default()
+        };
+        state
+    }
+
+    fn basic(user: &str, pass: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, pass));
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Basic {}", token)).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_parse_basic_auth() {
+        assert_eq!(parse_basic_auth(&basic("alice", "p:w")), Some(("alice".into(), "p:w".into())));
+        assert_eq!(parse_basic_auth(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_authorize_requires_credentials_for_lan_clients() {
+        let state = state_with_credentials("alice", "secret123");
+        let lan: SocketAddr = "192.168.1.20:5000".parse().unwrap();
+        assert!(!state.authorize(lan, &HeaderMap::new()));
+        assert!(!state.authorize(lan, &basic("alice", "wrong")));
+        assert!(state.authorize(lan, &basic("alice", "secret123")));
+        // Served from the verified cache the second time
+        assert!(state.authorize(lan, &basic("alice", "secret123")));
+    }
+
+    #[test]
+    fn test_authorize_admits_loopback() {
+        let state = state_with_credentials("alice", "secret123");
+        assert!(state.authorize("127.0.0.1:5000".parse().unwrap(), &HeaderMap::new()));
+        assert!(state.authorize("[::ffff:127.0.0.1]:5000".parse().unwrap(), &HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_listen_hosts() {
+        let state = NetworkSharingState::default();
+        assert_eq!(state.listen_hosts("127.0.0.1"), vec!["127.0.0.1"]);
+        assert_eq!(state.listen_hosts("0.0.0.0"), vec!["127.0.0.1"]);
+        state.config.lock().unwrap().enabled = true;
+        assert_eq!(state.listen_hosts("127.0.0.1"), vec!["0.0.0.0"]);
+        state.config.lock().unwrap().bind_address = "192.168.1.5".into();
+        assert_eq!(state.listen_hosts("127.0.0.1"), vec!["127.0.0.1", "192.168.1.5"]);
+    }
+
+    #[test]
+    fn test_check_bind_address() {
+        let mut config = SharingConfig::default();
+        assert!(check_bind_address(&config, "127.0.0.1").is_ok());
+        assert!(check_bind_address(&config, "::1").is_ok());
+        assert!(check_bind_address(&config, "localhost").is_ok());
+        assert!(check_bind_address(&config, "not an address").is_err());
+        assert!(check_bind_address(&config, "0.0.0.0").is_err());
+
+        config.enabled = true;
+        assert!(check_bind_address(&config, "0.0.0.0").is_err());
+        config.username = Some("alice".into());
+        config.password_hash = Some("hash".into());
+        assert!(check_bind_address(&config, "0.0.0.0").is_ok());
+        config.require_auth = false;
+        assert!(check_bind_address(&config, "192.168.1.5").is_err());
+    }
+
+    #[test]
+    fn test_exposure_warning() {
+        assert_eq!(exposure_warning(&["127.0.0.1".into()], false), None);
+        assert_eq!(exposure_warning(&["127.0.0.1".into(), "0.0.0.0".into()], true), None);
+        assert!(exposure_warning(&["127.0.0.1".into(), "0.0.0.0".into()], false).is_some_and(|w| w.contains("0.0.0.0")));
+    }
+
+    #[test]
+    fn test_mdns_host_name_is_local() {
+        assert!(mdns_host_name().ends_with(".local."));
+    }
+}
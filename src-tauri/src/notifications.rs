@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+use crate::transfers::{TransferDirection, TransferFinishedEvent, TransferOutcome};
+
+// ============================================================================
+// Desktop notifications
+// ============================================================================
+//
+// Problems that happen while the window is closed (the bridge crashing, the
+// Proton session expiring, a mount failing, storage running low) and the end
+// of long transfers raise native notifications. Each category can be
+// switched off in the `notifications` config section. Nothing is shown while
+// the main window has focus, since the UI already reports the same events.
+
+const CONFIG_KEY: &str = "notifications";
+
+/// Markers the sidecar logs when the stored session can no longer be used
+/// (see `src/auth.ts`).
+const SESSION_EXPIRED_MARKERS: &[&str] = &["Parent session expired", "INVALID_REFRESH_TOKEN", "re-authentication required"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationCategory {
+    SidecarCrash,
+    SessionExpired,
+    MountFailure,
+    TransferComplete,
+    LowStorage,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    pub sidecar_crash: bool,
+    pub session_expired: bool,
+    pub mount_failure: bool,
+    pub transfer_complete: bool,
+    /// Storage usage crossed the quota warning threshold
+    pub low_storage: bool,
+    /// Only transfers at least this large are announced
+    pub large_transfer_mb: u64,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            sidecar_crash: true,
+            session_expired: true,
+            mount_failure: true,
+            transfer_complete: true,
+            low_storage: true,
+            large_transfer_mb: 100,
+        }
+    }
+}
+
+impl NotificationSettings {
+    fn allows(&self, category: NotificationCategory) -> bool {
+        match category {
+            NotificationCategory::SidecarCrash => self.sidecar_crash,
+            NotificationCategory::SessionExpired => self.session_expired,
+            NotificationCategory::MountFailure => self.mount_failure,
+            NotificationCategory::TransferComplete => self.transfer_complete,
+            NotificationCategory::LowStorage => self.low_storage,
+        }
+    }
+}
+
+fn main_window_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main").and_then(|w| w.is_focused().ok()).unwrap_or(false)
+}
+
+/// Show a notification unless its category is disabled or the user is
+/// already looking at the app.
+pub fn notify(app: &AppHandle, category: NotificationCategory, title: &str, body: &str) {
+    let settings: NotificationSettings = read_config_section(CONFIG_KEY);
+    if !settings.allows(category) || main_window_focused(app) {
+        return;
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show {:?} notification: {}", category, e);
+    }
+}
+
+/// Whether a sidecar log line means the user has to log in again.
+pub fn is_session_expired_line(line: &str) -> bool {
+    SESSION_EXPIRED_MARKERS.iter().any(|m| line.contains(m))
+}
+
+fn is_large_completed_transfer(event: &TransferFinishedEvent, threshold_mb: u64) -> bool {
+    event.outcome == TransferOutcome::Completed && event.bytes_transferred >= threshold_mb.saturating_mul(1024 * 1024)
+}
+
+pub(crate) fn human_size(bytes: u64) -> String {
+    let mb = bytes as f64 / (1024.0 * 1024.0);
+    if mb >= 1024.0 {
+        format!("{:.1} GB", mb / 1024.0)
+    } else {
+        format!("{:.0} MB", mb)
+    }
+}
+
+/// Announce a finished transfer if it is large enough to be worth it.
+pub fn transfer_finished(app: &AppHandle, event: &TransferFinishedEvent) {
+    let settings: NotificationSettings = read_config_section(CONFIG_KEY);
+    if !is_large_completed_transfer(event, settings.large_transfer_mb) {
+        return;
+    }
+    let title = match event.direction {
+        TransferDirection::Upload => "Upload complete",
+        TransferDirection::Download => "Download complete",
+        TransferDirection::Copy => "Copy complete",
+    };
+    let name = event.path.rsplit('/').find(|s| !s.is_empty()).unwrap_or(&event.path);
+    notify(app, NotificationCategory::TransferComplete, title, &format!("{} ({})", name, human_size(event.bytes_transferred)));
+}
+
+#[tauri::command]
+pub async fn get_notification_settings() -> Result<NotificationSettings, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+#[tauri::command]
+pub async fn set_notification_settings(app: AppHandle, settings: NotificationSettings) -> Result<NotificationSettings, CommandError> {
+    write_config_section(CONFIG_KEY, &settings)?;
+    let _ = app.emit("notifications:changed", settings.clone());
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished(outcome: TransferOutcome, bytes: u64) -> TransferFinishedEvent {
+        TransferFinishedEvent {
+            id: 1,
+            path: "/Videos/clip.mp4".into(),
+            direction: TransferDirection::Upload,
+            bytes_transferred: bytes,
+            outcome,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_large_transfer_threshold() {
+        assert!(is_large_completed_transfer(&finished(TransferOutcome::Completed, 200 * 1024 * 1024), 100));
+        assert!(!is_large_completed_transfer(&finished(TransferOutcome::Completed, 1024), 100));
+        assert!(!is_large_completed_transfer(&finished(TransferOutcome::Failed, 200 * 1024 * 1024), 100));
+    }
+
+    #[test]
+    fn test_session_expired_markers() {
+        assert!(is_session_expired_line("[error] Failed to fork child session: Parent session expired - re-authentication required"));
+        assert!(!is_session_expired_line("[info] Access token expired, attempting refresh..."));
+    }
+
+    #[test]
+    fn test_settings_default_missing_fields() {
+        let settings: NotificationSettings = serde_json::from_str(r#"{"transferComplete":false}"#).unwrap();
+        assert!(!settings.allows(NotificationCategory::TransferComplete));
+        assert!(settings.allows(NotificationCategory::SidecarCrash));
+        assert_eq!(settings.large_transfer_mb, 100);
+    }
+}
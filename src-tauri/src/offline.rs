@@ -0,0 +1,466 @@
+use rusqlite::{Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::dav::{normalize_path, DavClient, DavEntry};
+use crate::db;
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Offline pins
+// ============================================================================
+//
+// Pinned remote files and folders are mirrored into `offline/` under the app
+// data dir, laid out like the drive, so they stay available without the
+// bridge. A background task refreshes every pin on the interval from the
+// `offline` config section: new and changed files (by ETag, else size and
+// modification time) are downloaded and files deleted remotely are removed
+// locally. The state database records what was downloaded. Refreshes
+// are skipped while travel mode is on. Progress is reported as
+// `offline:sync` events.
+
+const CONFIG_KEY: &str = "offline";
+
+/// Mirror directory under the app data dir.
+const MIRROR_DIR: &str = "offline";
+
+/// Delay before the first scheduled refresh so it doesn't compete with
+/// startup and mounting.
+const INITIAL_DELAY: Duration = Duration::from_secs(120);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct OfflineConfig {
+    /// Pinned remote paths
+    pinned: Vec<String>,
+    refresh_interval_minutes: u64,
+}
+
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        Self { pinned: Vec::new(), refresh_interval_minutes: 60 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct MirroredFile {
+    size: Option<u64>,
+    modified: Option<String>,
+    etag: Option<String>,
+}
+
+impl MirroredFile {
+    fn from_entry(entry: &DavEntry) -> Self {
+        Self { size: entry.size, modified: entry.modified.clone(), etag: entry.etag.clone() }
+    }
+
+    /// Whether the mirrored copy still matches `entry`.
+    fn matches(&self, entry: &DavEntry) -> bool {
+        match (&self.etag, &entry.etag) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.size == entry.size && self.modified == entry.modified,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct PinRecord {
+    last_synced: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Manifest {
+    /// Mirrored files by remote path
+    files: BTreeMap<String, MirroredFile>,
+    /// Outcome of the last refresh by pinned path
+    pins: BTreeMap<String, PinRecord>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedPath {
+    pub path: String,
+    pub local_path: String,
+    /// Files currently mirrored for this pin
+    pub files: usize,
+    pub bytes: u64,
+    /// Unix timestamp (seconds) of the last successful refresh
+    pub last_synced: Option<u64>,
+    /// Error of the last refresh, if it failed
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OfflineSyncPhase {
+    Started,
+    Downloading,
+    Completed,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineSyncProgress {
+    pub path: String,
+    pub phase: OfflineSyncPhase,
+    pub files_total: usize,
+    /// Files checked so far, downloaded or already current
+    pub files_done: usize,
+    pub downloaded: usize,
+    pub removed: usize,
+    pub error: Option<String>,
+}
+
+/// Serializes refreshes and manifest updates.
+#[derive(Default)]
+pub struct OfflineState {
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl OfflineState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Whether `path` is `root` or below it.
+fn is_under(path: &str, root: &str) -> bool {
+    root == "/" || path == root || path.starts_with(&format!("{}/", root))
+}
+
+/// Where `remote` is mirrored, or `None` for paths that would escape the
+/// mirror.
+fn local_path(mirror: &Path, remote: &str) -> Option<PathBuf> {
+    let relative = Path::new(remote.trim_start_matches('/'));
+    if relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        Some(mirror.join(relative))
+    } else {
+        None
+    }
+}
+
+fn mirror_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    let dir = app.path().app_data_dir().map_err(|e| CommandError::IoError(e.to_string()))?.join(MIRROR_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Delete the mirror, everything in it and its record; pins stay configured
+/// and are mirrored again on the next refresh.
+pub(crate) fn purge_mirror(app: &AppHandle) -> Result<(), CommandError> {
+    let dir = app.path().app_data_dir().map_err(|e| CommandError::IoError(e.to_string()))?.join(MIRROR_DIR);
+    match std::fs::remove_dir_all(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    db::transaction(app, |tx| write_manifest(tx, &Manifest::default()))
+}
+
+fn read_manifest(conn: &Connection) -> rusqlite::Result<Manifest> {
+    let mut stmt = conn.prepare("SELECT path, size, modified, etag FROM offline_files")?;
+    let files = stmt
+        .query_map([], |row| {
+            let file = MirroredFile { size: row.get::<_, Option<i64>>(1)?.map(|s| s as u64), modified: row.get(2)?, etag: row.get(3)? };
+            Ok((row.get(0)?, file))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let mut stmt = conn.prepare("SELECT path, last_synced, error FROM offline_pins")?;
+    let pins = stmt
+        .query_map([], |row| {
+            let record = PinRecord { last_synced: row.get::<_, Option<i64>>(1)?.map(|t| t as u64), error: row.get(2)? };
+            Ok((row.get(0)?, record))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(Manifest { files, pins })
+}
+
+fn write_manifest(tx: &Transaction, manifest: &Manifest) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM offline_files", [])?;
+    tx.execute("DELETE FROM offline_pins", [])?;
+    let mut stmt = tx.prepare("INSERT INTO offline_files (path, size, modified, etag) VALUES (?1, ?2, ?3, ?4)")?;
+    for (path, file) in &manifest.files {
+        stmt.execute(rusqlite::params![path, file.size.map(|s| s as i64), file.modified, file.etag])?;
+    }
+    let mut stmt = tx.prepare("INSERT INTO offline_pins (path, last_synced, error) VALUES (?1, ?2, ?3)")?;
+    for (path, record) in &manifest.pins {
+        stmt.execute(rusqlite::params![path, record.last_synced.map(|t| t as i64), record.error])?;
+    }
+    Ok(())
+}
+
+fn load_manifest(app: &AppHandle) -> Result<Manifest, CommandError> {
+    db::with(app, |conn| read_manifest(conn))
+}
+
+fn save_manifest(app: &AppHandle, manifest: &Manifest) -> Result<(), CommandError> {
+    db::transaction(app, |tx| write_manifest(tx, manifest))
+}
+
+/// Drop manifest entries under `root` that aren't in `keep` and not covered
+/// by another pin, deleting their local copies. Returns how many went.
+fn remove_stale(mirror: &Path, manifest: &mut Manifest, root: &str, keep: &HashSet<String>, other_pins: &[String]) -> usize {
+    let stale: Vec<String> = manifest
+        .files
+        .keys()
+        .filter(|p| is_under(p, root) && !keep.contains(*p) && !other_pins.iter().any(|pin| is_under(p, pin)))
+        .cloned()
+        .collect();
+    for path in &stale {
+        manifest.files.remove(path);
+        if let Some(local) = local_path(mirror, path) {
+            if let Err(e) = std::fs::remove_file(&local) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove offline copy {}: {}", local.display(), e);
+                }
+            }
+        }
+    }
+    stale.len()
+}
+
+/// Files below `root` (or `root` itself when it is a file).
+async fn remote_files(client: &DavClient, root: &str) -> Result<Vec<DavEntry>, CommandError> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_string()];
+    while let Some(dir) = pending.pop() {
+        for entry in client.propfind(&dir, 1).await? {
+            if entry.path == dir {
+                if !entry.is_dir {
+                    files.push(entry);
+                }
+            } else if entry.is_dir {
+                pending.push(entry.path);
+            } else {
+                files.push(entry);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Bring the mirror of `root` up to date.
+async fn sync_pin(app: &AppHandle, client: &DavClient, mirror: &Path, manifest: &mut Manifest, root: &str, other_pins: &[String]) -> Result<(), CommandError> {
+    let mut progress = OfflineSyncProgress {
+        path: root.to_string(),
+        phase: OfflineSyncPhase::Started,
+        files_total: 0,
+        files_done: 0,
+        downloaded: 0,
+        removed: 0,
+        error: None,
+    };
+    let _ = app.emit("offline:sync", progress.clone());
+
+    let files = remote_files(client, root).await?;
+    progress.files_total = files.len();
+    progress.phase = OfflineSyncPhase::Downloading;
+    let mut seen = HashSet::new();
+    for entry in files {
+        seen.insert(entry.path.clone());
+        let Some(local) = local_path(mirror, &entry.path) else {
+            log::warn!("Not mirroring {}: unsafe path", entry.path);
+            continue;
+        };
+        let current = manifest.files.get(&entry.path).is_some_and(|f| f.matches(&entry)) && local.is_file();
+        if !current {
+            if let Some(parent) = local.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            crate::remote::download_to_path(app, client, &entry.path, entry.size, &local).await?;
+            manifest.files.insert(entry.path.clone(), MirroredFile::from_entry(&entry));
+            progress.downloaded += 1;
+        }
+        progress.files_done += 1;
+        let _ = app.emit("offline:sync", progress.clone());
+    }
+    progress.removed = remove_stale(mirror, manifest, root, &seen, other_pins);
+
+    progress.phase = OfflineSyncPhase::Completed;
+    log::info!("Offline copy of {} refreshed: {} downloaded, {} removed", root, progress.downloaded, progress.removed);
+    let _ = app.emit("offline:sync", progress);
+    Ok(())
+}
+
+/// Refresh the given pins, or all of them.
+async fn sync(app: &AppHandle, only: Option<&str>) -> Result<(), CommandError> {
+    if app.state::<ReadOnlyState>().status().travel_mode {
+        return Err(CommandError::TravelModeActive);
+    }
+    let state = app.state::<OfflineState>();
+    let _guard = state.lock.lock().await;
+    let client = DavClient::for_app(app)?;
+    let config: OfflineConfig = read_config_section(CONFIG_KEY);
+    let mirror = mirror_dir(app)?;
+    let mut manifest = load_manifest(app)?;
+
+    for root in config.pinned.iter().filter(|p| only.is_none_or(|o| o == p.as_str())) {
+        let others: Vec<String> = config.pinned.iter().filter(|p| *p != root).cloned().collect();
+        let result = sync_pin(app, &client, &mirror, &mut manifest, root, &others).await;
+        let record = manifest.pins.entry(root.clone()).or_default();
+        match result {
+            Ok(()) => *record = PinRecord { last_synced: Some(crate::sidecar::unix_now()), error: None },
+            Err(e) => {
+                log::warn!("Offline refresh of {} failed: {}", root, e);
+                record.error = Some(e.to_string());
+                let progress = OfflineSyncProgress {
+                    path: root.clone(),
+                    phase: OfflineSyncPhase::Failed,
+                    files_total: 0,
+                    files_done: 0,
+                    downloaded: 0,
+                    removed: 0,
+                    error: Some(e.to_string()),
+                };
+                let _ = app.emit("offline:sync", progress);
+            }
+        }
+        save_manifest(app, &manifest)?;
+    }
+    Ok(())
+}
+
+/// Periodic refresh of all pins; spawned once from `setup`.
+pub async fn run(app: AppHandle) {
+    tokio::time::sleep(INITIAL_DELAY).await;
+    loop {
+        let config: OfflineConfig = read_config_section(CONFIG_KEY);
+        let paused = app.state::<crate::power::PowerState>().is_suspended() || !app.state::<crate::network::NetworkState>().is_online();
+        if !config.pinned.is_empty() && !paused {
+            match sync(&app, None).await {
+                Ok(()) | Err(CommandError::ServerNotRunning) | Err(CommandError::TravelModeActive) => {}
+                Err(e) => log::warn!("Offline refresh failed: {}", e),
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(config.refresh_interval_minutes.max(1) * 60)).await;
+    }
+}
+
+fn pinned_paths(app: &AppHandle) -> Result<Vec<PinnedPath>, CommandError> {
+    let config: OfflineConfig = read_config_section(CONFIG_KEY);
+    let mirror = mirror_dir(app)?;
+    let manifest = load_manifest(app)?;
+    Ok(config
+        .pinned
+        .iter()
+        .map(|path| {
+            let mirrored: Vec<&MirroredFile> = manifest.files.iter().filter(|(p, _)| is_under(p, path)).map(|(_, f)| f).collect();
+            let record = manifest.pins.get(path).cloned().unwrap_or_default();
+            PinnedPath {
+                path: path.clone(),
+                local_path: local_path(&mirror, path).unwrap_or_else(|| mirror.clone()).display().to_string(),
+                files: mirrored.len(),
+                bytes: mirrored.iter().filter_map(|f| f.size).sum(),
+                last_synced: record.last_synced,
+                error: record.error,
+            }
+        })
+        .collect())
+}
+
+/// Keep `path` available offline. It is downloaded right away in the
+/// background and refreshed on the configured interval.
+#[tauri::command]
+pub async fn pin_path(app: AppHandle, path: String) -> Result<Vec<PinnedPath>, CommandError> {
+    if app.state::<ReadOnlyState>().status().travel_mode {
+        return Err(CommandError::TravelModeActive);
+    }
+    let path = normalize_path(&path);
+    DavClient::for_app(&app)?.stat(&path).await?.ok_or_else(|| CommandError::RemotePathNotFound(path.clone()))?;
+    let mut config: OfflineConfig = read_config_section(CONFIG_KEY);
+    if !config.pinned.contains(&path) {
+        config.pinned.push(path.clone());
+        config.pinned.sort();
+        write_config_section(CONFIG_KEY, &config)?;
+    }
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = sync(&handle, Some(&path)).await {
+            log::warn!("Initial offline download of {} failed: {}", path, e);
+        }
+    });
+    pinned_paths(&app)
+}
+
+/// Stop keeping `path` offline and delete its local copies, except those
+/// still covered by another pin.
+#[tauri::command]
+pub async fn unpin_path(app: AppHandle, state: State<'_, OfflineState>, path: String) -> Result<Vec<PinnedPath>, CommandError> {
+    let path = normalize_path(&path);
+    let guard = state.lock.lock().await;
+    let mut config: OfflineConfig = read_config_section(CONFIG_KEY);
+    if !config.pinned.contains(&path) {
+        return Err(CommandError::InvalidRemotePath(format!("{} is not pinned", path)));
+    }
+    config.pinned.retain(|p| *p != path);
+    write_config_section(CONFIG_KEY, &config)?;
+
+    let mirror = mirror_dir(&app)?;
+    let mut manifest = load_manifest(&app)?;
+    let removed = remove_stale(&mirror, &mut manifest, &path, &HashSet::new(), &config.pinned);
+    manifest.pins.remove(&path);
+    save_manifest(&app, &manifest)?;
+    log::info!("Unpinned {}; removed {} offline file(s)", path, removed);
+    drop(guard);
+    pinned_paths(&app)
+}
+
+#[tauri::command]
+pub async fn list_pinned(app: AppHandle) -> Result<Vec<PinnedPath>, CommandError> {
+    pinned_paths(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, size: u64, etag: Option<&str>) -> DavEntry {
+        DavEntry {
+            path: path.into(),
+            name: path.rsplit('/').next().unwrap().into(),
+            is_dir: false,
+            size: Some(size),
+            modified: Some("Mon, 01 Jan 2024 00:00:00 GMT".into()),
+            etag: etag.map(Into::into),
+            content_type: None,
+        }
+    }
+
+    #[test]
+    fn test_local_path_stays_in_mirror() {
+        let mirror = Path::new("/data/offline");
+        assert_eq!(local_path(mirror, "/Docs/a.txt"), Some(PathBuf::from("/data/offline/Docs/a.txt")));
+        assert_eq!(local_path(mirror, "/Docs/../../etc/passwd"), None);
+    }
+
+    #[test]
+    fn test_mirrored_file_matches_by_etag_then_size() {
+        let mirrored = MirroredFile::from_entry(&entry("/a", 10, Some("e1")));
+        assert!(mirrored.matches(&entry("/a", 10, Some("e1"))));
+        assert!(!mirrored.matches(&entry("/a", 10, Some("e2"))));
+
+        let without_etag = MirroredFile::from_entry(&entry("/a", 10, None));
+        assert!(without_etag.matches(&entry("/a", 10, None)));
+        assert!(!without_etag.matches(&entry("/a", 11, None)));
+    }
+
+    #[test]
+    fn test_remove_stale_keeps_other_pins() {
+        let mirror = std::env::temp_dir().join(format!("offline-stale-{}", std::process::id()));
+        let mut manifest = Manifest::default();
+        for path in ["/A/x", "/A/B/y", "/A/z"] {
+            manifest.files.insert(path.into(), MirroredFile::from_entry(&entry(path, 1, None)));
+        }
+        let keep: HashSet<String> = ["/A/z".to_string()].into();
+        assert_eq!(remove_stale(&mirror, &mut manifest, "/A", &keep, &["/A/B".into()]), 1);
+        assert_eq!(manifest.files.keys().collect::<Vec<_>>(), vec!["/A/B/y", "/A/z"]);
+    }
+}
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError, SidecarState};
+
+// ============================================================================
+// First-run onboarding
+// ============================================================================
+//
+// Tracks what a fresh install still needs before the drive is usable, in the
+// order the user has to fix it: the bundled bridge binary, a WebDAV mount
+// implementation on the system, a Proton login and one successful mount.
+// Everything that can be probed is probed on each call; only outcomes the
+// backend cannot observe (skips, mounts done outside `mount_drive`) are
+// persisted in the `onboarding` config section.
+
+const CONFIG_KEY: &str = "onboarding";
+
+/// Binary name of the sidecar, as bundled by `tauri.conf.json`.
+const SIDECAR_NAME: &str = "proton-drive-webdav-bridge";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum OnboardingStep {
+    SidecarBinary,
+    MountSupport,
+    Login,
+    FirstMount,
+}
+
+impl OnboardingStep {
+    const ORDER: [OnboardingStep; 4] =
+        [OnboardingStep::SidecarBinary, OnboardingStep::MountSupport, OnboardingStep::Login, OnboardingStep::FirstMount];
+
+    /// Steps the user may skip, e.g. to use the share from another client.
+    fn skippable(self) -> bool {
+        matches!(self, OnboardingStep::MountSupport | OnboardingStep::FirstMount)
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StepStatus {
+    Complete,
+    Skipped,
+    Pending,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StepState {
+    pub step: OnboardingStep,
+    pub status: StepStatus,
+    /// Last reported failure, or a hint for what is missing
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    pub steps: Vec<StepState>,
+    /// First step that is neither complete nor skipped
+    pub current_step: Option<OnboardingStep>,
+    pub complete: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StepOutcome {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+/// What the frontend reports after the user acted on a step.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StepResult {
+    pub step: OnboardingStep,
+    pub outcome: StepOutcome,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct OnboardingProgress {
+    first_mount_succeeded: bool,
+    skipped: Vec<OnboardingStep>,
+    failures: HashMap<OnboardingStep, String>,
+}
+
+/// Results of probing the system for the steps that can be observed.
+#[derive(Clone, Debug)]
+struct Probes {
+    sidecar_binary: bool,
+    mount_support: bool,
+    /// Install hint from `system_requirements` when mounting isn't possible
+    mount_hint: Option<String>,
+    logged_in: bool,
+}
+
+/// Where the shell plugin looks for the bundled sidecar.
+fn sidecar_binary_path() -> Option<PathBuf> {
+    let dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    Some(dir.join(if cfg!(windows) { format!("{}.exe", SIDECAR_NAME) } else { SIDECAR_NAME.to_string() }))
+}
+
+fn missing_hint(step: OnboardingStep) -> &'static str {
+    match step {
+        OnboardingStep::SidecarBinary => "The bundled WebDAV bridge was not found next to the app; reinstall the app",
+        OnboardingStep::MountSupport => "No WebDAV mount support found; install gvfs-backends (GNOME) or davfs2",
+        OnboardingStep::Login => "Sign in to your Proton account",
+        OnboardingStep::FirstMount => "Mount the drive once to finish setup",
+    }
+}
+
+fn evaluate(probes: &Probes, progress: &OnboardingProgress) -> OnboardingState {
+    let steps: Vec<StepState> = OnboardingStep::ORDER
+        .iter()
+        .map(|&step| {
+            let done = match step {
+                OnboardingStep::SidecarBinary => probes.sidecar_binary,
+                OnboardingStep::MountSupport => probes.mount_support,
+                OnboardingStep::Login => probes.logged_in,
+                OnboardingStep::FirstMount => progress.first_mount_succeeded,
+            };
+            let failure = progress.failures.get(&step).cloned();
+            let (status, detail) = if done {
+                (StepStatus::Complete, None)
+            } else if progress.skipped.contains(&step) {
+                (StepStatus::Skipped, None)
+            } else if failure.is_some() {
+                (StepStatus::Failed, failure)
+            } else {
+                let hint = match (step, &probes.mount_hint) {
+                    (OnboardingStep::MountSupport, Some(hint)) => hint.clone(),
+                    _ => missing_hint(step).to_string(),
+                };
+                (StepStatus::Pending, Some(hint))
+            };
+            StepState { step, status, detail }
+        })
+        .collect();
+
+    let current_step = steps
+        .iter()
+        .find(|s| matches!(s.status, StepStatus::Pending | StepStatus::Failed))
+        .map(|s| s.step);
+    OnboardingState { complete: current_step.is_none(), current_step, steps }
+}
+
+fn apply_result(progress: &mut OnboardingProgress, result: &StepResult) -> Result<(), CommandError> {
+    match result.outcome {
+        StepOutcome::Succeeded => {
+            progress.failures.remove(&result.step);
+            progress.skipped.retain(|s| *s != result.step);
+            if result.step == OnboardingStep::FirstMount {
+                progress.first_mount_succeeded = true;
+            }
+        }
+        StepOutcome::Failed => {
+            let message = result.message.clone().unwrap_or_else(|| "Step failed".to_string());
+            progress.failures.insert(result.step, message);
+        }
+        StepOutcome::Skipped => {
+            if !result.step.skippable() {
+                return Err(CommandError::InvalidOnboardingStep(format!("{:?} cannot be skipped", result.step)));
+            }
+            progress.failures.remove(&result.step);
+            if !progress.skipped.contains(&result.step) {
+                progress.skipped.push(result.step);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn current_state(app: &AppHandle, state: State<'_, SidecarState>) -> OnboardingState {
+    let logged_in = crate::sidecar::get_status(app.clone(), state).await.is_ok_and(|s| s.auth.logged_in);
+    let report = tauri::async_runtime::spawn_blocking(crate::system_requirements::probe).await.ok();
+    let probes = Probes {
+        sidecar_binary: sidecar_binary_path().is_some_and(|p| p.exists()),
+        mount_support: report.as_ref().is_some_and(|r| r.can_mount),
+        mount_hint: report.as_ref().and_then(crate::system_requirements::mount_remediation),
+        logged_in,
+    };
+    evaluate(&probes, &read_config_section(CONFIG_KEY))
+}
+
+/// Called by `mount_drive` so the first mount is recorded even if the
+/// frontend never reports it.
+pub fn record_first_mount(app: &AppHandle) {
+    let mut progress: OnboardingProgress = read_config_section(CONFIG_KEY);
+    if progress.first_mount_succeeded {
+        return;
+    }
+    progress.first_mount_succeeded = true;
+    progress.failures.remove(&OnboardingStep::FirstMount);
+    if let Err(e) = write_config_section(CONFIG_KEY, &progress) {
+        log::warn!("Failed to record first mount: {}", e);
+        return;
+    }
+    let _ = app.emit("onboarding:changed", OnboardingStep::FirstMount);
+}
+
+#[tauri::command]
+pub async fn get_onboarding_state(app: AppHandle, state: State<'_, SidecarState>) -> Result<OnboardingState, CommandError> {
+    Ok(current_state(&app, state).await)
+}
+
+/// Record the outcome of a step the user just acted on and return the
+/// re-evaluated state. Emits `onboarding:changed`.
+#[tauri::command]
+pub async fn advance_onboarding(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    step_result: StepResult,
+) -> Result<OnboardingState, CommandError> {
+    let mut progress: OnboardingProgress = read_config_section(CONFIG_KEY);
+    apply_result(&mut progress, &step_result)?;
+    write_config_section(CONFIG_KEY, &progress)?;
+
+    let onboarding = current_state(&app, state).await;
+    let _ = app.emit("onboarding:changed", step_result.step);
+    Ok(onboarding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_PRESENT: Probes = Probes { sidecar_binary: true, mount_support: true, mount_hint: None, logged_in: true };
+
+    #[test]
+    fn test_evaluate_stops_at_first_missing_step() {
+        let probes = Probes { logged_in: false, ..ALL_PRESENT };
+        let state = evaluate(&probes, &OnboardingProgress::default());
+        assert_eq!(state.current_step, Some(OnboardingStep::Login));
+        assert!(!state.complete);
+        assert_eq!(state.steps[0].status, StepStatus::Complete);
+    }
+
+    #[test]
+    fn test_skipped_steps_complete_onboarding() {
+        let mut progress = OnboardingProgress::default();
+        let skip = StepResult { step: OnboardingStep::FirstMount, outcome: StepOutcome::Skipped, message: None };
+        apply_result(&mut progress, &skip).unwrap();
+        let state = evaluate(&ALL_PRESENT, &progress);
+        assert!(state.complete);
+        assert_eq!(state.steps[3].status, StepStatus::Skipped);
+    }
+
+    #[test]
+    fn test_required_steps_cannot_be_skipped() {
+        let mut progress = OnboardingProgress::default();
+        let skip = StepResult { step: OnboardingStep::Login, outcome: StepOutcome::Skipped, message: None };
+        assert!(apply_result(&mut progress, &skip).is_err());
+    }
+
+    #[test]
+    fn test_failure_is_reported_until_success() {
+        let mut progress = OnboardingProgress::default();
+        let failed = StepResult {
+            step: OnboardingStep::FirstMount,
+            outcome: StepOutcome::Failed,
+            message: Some("volume doesn't implement mount".into()),
+        };
+        apply_result(&mut progress, &failed).unwrap();
+        let state = evaluate(&ALL_PRESENT, &progress);
+        assert_eq!(state.steps[3].status, StepStatus::Failed);
+        assert_eq!(state.steps[3].detail.as_deref(), Some("volume doesn't implement mount"));
+
+        let ok = StepResult { step: OnboardingStep::FirstMount, outcome: StepOutcome::Succeeded, message: None };
+        apply_result(&mut progress, &ok).unwrap();
+        assert!(evaluate(&ALL_PRESENT, &progress).complete);
+    }
+}
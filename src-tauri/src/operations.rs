@@ -0,0 +1,160 @@
+use serde::Serialize;
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// In-flight operations
+// ============================================================================
+//
+// Impatient clicks and polling hooks fire the same command again while the
+// first call is still running. `dedup` runs one call per key (the command
+// name plus any arguments that change its outcome) and hands its result to
+// every identical call that arrives meanwhile, instead of spawning another
+// process or tripping the lifecycle guard. `list_pending_operations` shows
+// what is in flight and how many callers wait on it.
+
+type Outcome = Box<dyn Any + Send>;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingOperation {
+    pub id: u64,
+    pub key: String,
+    /// Unix timestamp (seconds) of the first call
+    pub started_at: u64,
+    /// Calls that joined the first one
+    pub joined: usize,
+}
+
+struct InFlight {
+    operation: PendingOperation,
+    waiters: Vec<oneshot::Sender<Outcome>>,
+}
+
+#[derive(Default)]
+pub struct OperationRegistry {
+    next_id: AtomicU64,
+    in_flight: Mutex<HashMap<String, InFlight>>,
+}
+
+impl OperationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a call of `key`. Returns `None` for the first call, which
+    /// runs the operation, or a receiver for its result.
+    fn join(&self, key: &str) -> Option<oneshot::Receiver<Outcome>> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(entry) = in_flight.get_mut(key) {
+            let (tx, rx) = oneshot::channel();
+            entry.waiters.push(tx);
+            entry.operation.joined += 1;
+            return Some(rx);
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let operation = PendingOperation { id, key: key.to_string(), started_at: crate::sidecar::unix_now(), joined: 0 };
+        in_flight.insert(key.to_string(), InFlight { operation, waiters: Vec::new() });
+        None
+    }
+
+    /// Remove `key` and return the callers waiting for it.
+    fn complete(&self, key: &str) -> Vec<oneshot::Sender<Outcome>> {
+        self.in_flight.lock().unwrap().remove(key).map(|entry| entry.waiters).unwrap_or_default()
+    }
+
+    fn list(&self) -> Vec<PendingOperation> {
+        let mut operations: Vec<_> = self.in_flight.lock().unwrap().values().map(|e| e.operation.clone()).collect();
+        operations.sort_by_key(|o| o.id);
+        operations
+    }
+}
+
+/// Clears the entry if the first call is dropped before it finishes, so its
+/// waiters fail instead of hanging.
+struct Leader<'a> {
+    registry: &'a OperationRegistry,
+    key: &'a str,
+    done: bool,
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.registry.complete(self.key);
+        }
+    }
+}
+
+/// Run `future` unless an identical operation (same `key`) is in flight, in
+/// which case wait for that one's result.
+pub async fn dedup<T, F>(app: &AppHandle, key: &str, future: F) -> Result<T, CommandError>
+where
+    T: Clone + Send + 'static,
+    F: Future<Output = Result<T, CommandError>>,
+{
+    let registry = app.state::<OperationRegistry>();
+    if let Some(rx) = registry.join(key) {
+        log::debug!("Joining in-flight {}", key);
+        return match rx.await {
+            Ok(outcome) => match outcome.downcast::<Result<T, CommandError>>() {
+                Ok(result) => *result,
+                Err(_) => Err(CommandError::Unknown(format!("{} returned an unexpected result", key))),
+            },
+            Err(_) => Err(CommandError::OperationCancelled),
+        };
+    }
+    let mut leader = Leader { registry: &registry, key, done: false };
+    let result = future.await;
+    leader.done = true;
+    for waiter in registry.complete(key) {
+        let _ = waiter.send(Box::new(result.clone()));
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn list_pending_operations(app: AppHandle) -> Result<Vec<PendingOperation>, CommandError> {
+    Ok(app.state::<OperationRegistry>().list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_calls_join_the_first() {
+        let registry = OperationRegistry::new();
+        assert!(registry.join("mount_drive").is_none());
+        let mut joined = registry.join("mount_drive").expect("second call joins");
+        assert!(registry.join("get_status").is_none());
+
+        let pending = registry.list();
+        assert_eq!(pending.iter().map(|o| (o.key.as_str(), o.joined)).collect::<Vec<_>>(), vec![("mount_drive", 1), ("get_status", 0)]);
+
+        for waiter in registry.complete("mount_drive") {
+            let _ = waiter.send(Box::new(Ok::<u32, CommandError>(7)));
+        }
+        let outcome = joined.try_recv().unwrap();
+        assert_eq!(outcome.downcast::<Result<u32, CommandError>>().unwrap().unwrap(), 7);
+        // A later call starts a new operation
+        assert!(registry.join("mount_drive").is_none());
+    }
+
+    #[test]
+    fn test_dropped_leader_releases_waiters() {
+        let registry = OperationRegistry::new();
+        assert!(registry.join("stop_sidecar").is_none());
+        let mut joined = registry.join("stop_sidecar").unwrap();
+        drop(Leader { registry: &registry, key: "stop_sidecar", done: false });
+        assert!(joined.try_recv().is_err());
+        assert!(registry.list().is_empty());
+    }
+}
@@ -0,0 +1,543 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use crate::dav::{join_path, normalize_path, DavClient};
+use crate::db;
+use crate::ignore::{IgnoreRules, IgnoreScope};
+use crate::name_mapping::{self, NameMapper};
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Photo backup
+// ============================================================================
+//
+// Camera-roll style backup: photos and videos in one or more local folders
+// (typically ~/Pictures and wherever the phone sync drops them) are uploaded
+// to `remoteRoot/YYYY/MM`, by the month the file was last modified. Files
+// are deduplicated by SHA-256, so a picture copied into a second folder, or
+// renamed, is not uploaded again; hashes are kept in the state database
+// together with the size and time each local file had when it was hashed,
+// so unchanged files are not read twice. A name already taken remotely gets
+// the start of the hash appended instead of replacing anything. Files
+// matching the backup ignore rules are left out, and local names encoded by
+// `name_mapping` are uploaded under the names they stand for.
+//
+// A pass scans all folders, then uploads what is new. Passes run at startup,
+// shortly after the folders change (watched through `notify`) and every
+// `RESCAN_INTERVAL`. Files modified in the last `QUIET` seconds are left for
+// the next pass, as they may still be being written. Uploads wait while the
+// bridge is down, travel mode is on, the share is read-only, the machine is
+// suspending or offline, and while the user has paused the backup; a paused
+// backup finishes the current file and stops. Progress is reported as
+// `backup:progress` events.
+
+const CONFIG_KEY: &str = "photoBackup";
+
+/// Extensions of the files backed up, lower case.
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "heic", "heif", "webp", "gif", "tif", "tiff", "dng", "cr2", "cr3", "nef", "arw", "orf", "rw2", "raf",
+    "mp4", "mov", "m4v", "3gp", "mkv", "webm", "avi",
+];
+
+/// Time between full passes when nothing changed.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// Time before an interrupted pass is tried again.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time for a burst of changes to the folders to settle before scanning.
+const SETTLE: Duration = Duration::from_secs(10);
+
+/// Seconds a file must have been left alone to be backed up.
+const QUIET: u64 = 10;
+
+/// Hex digits of the hash appended to a name that is taken remotely.
+const HASH_SUFFIX_LEN: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PhotoBackupSettings {
+    pub enabled: bool,
+    /// Local folders backed up, with their subfolders
+    pub folders: Vec<String>,
+    pub remote_root: String,
+    /// Paused by the user; kept across restarts
+    pub paused: bool,
+}
+
+impl Default for PhotoBackupSettings {
+    fn default() -> Self {
+        Self { enabled: false, folders: Vec::new(), remote_root: "/Photos".into(), paused: false }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupPhase {
+    #[default]
+    Idle,
+    Scanning,
+    Uploading,
+    Paused,
+    /// Waiting for the bridge, the network or the share to become writable
+    Waiting,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupProgress {
+    pub phase: BackupPhase,
+    /// New files found by the current or last pass
+    pub total: usize,
+    pub uploaded: usize,
+    pub failed: usize,
+    /// Local file being uploaded
+    pub current: Option<String>,
+    pub last_error: Option<String>,
+    /// Unix seconds when a pass last went through all new files
+    pub last_completed: Option<u64>,
+}
+
+pub struct PhotoBackupState {
+    /// Watches all configured folders; dropped when the backup is off
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    progress: Mutex<BackupProgress>,
+    /// Wakes the backup task for a pass
+    wake: Notify,
+}
+
+impl PhotoBackupState {
+    pub fn new() -> Self {
+        Self { watcher: Mutex::new(None), progress: Mutex::new(BackupProgress::default()), wake: Notify::new() }
+    }
+
+    fn progress(&self) -> BackupProgress {
+        self.progress.lock().unwrap().clone()
+    }
+}
+
+/// A file found by a scan that has not been backed up yet.
+struct NewMedia {
+    local: PathBuf,
+    sha256: String,
+    modified: u64,
+}
+
+fn is_media(path: &Path) -> bool {
+    let hidden = path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+    let extension = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase());
+    !hidden && extension.is_some_and(|e| MEDIA_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// The remote folder for a file last modified at `modified` (Unix seconds,
+/// taken as UTC): `root/YYYY/MM`.
+fn date_folder(root: &str, modified: u64) -> String {
+    let date = time::OffsetDateTime::from_unix_timestamp(modified as i64).unwrap_or(time::OffsetDateTime::UNIX_EPOCH);
+    join_path(&join_path(root, &date.year().to_string()), &format!("{:02}", u8::from(date.month())))
+}
+
+/// `name` with the start of `sha256` appended before the extension.
+fn hashed_name(name: &str, sha256: &str) -> String {
+    let suffix = &sha256[..HASH_SUFFIX_LEN.min(sha256.len())];
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{}-{}.{}", stem, suffix, extension),
+        _ => format!("{}-{}", name, suffix),
+    }
+}
+
+/// Media files under `folders`, skipping hidden folders, symlinks and what
+/// `ignore` matches. Blocks.
+fn collect_media(folders: &[String], ignore: &IgnoreRules) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut stack: Vec<(PathBuf, String)> = folders.iter().map(|f| (PathBuf::from(f), String::new())).collect();
+    while let Some((dir, rel)) = stack.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Photo backup: cannot read {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let rel_path = if rel.is_empty() { name.clone() } else { format!("{}/{}", rel, name) };
+            match entry.file_type() {
+                Ok(t) if t.is_dir() && !name.starts_with('.') && !ignore.is_ignored_dir(&rel_path) => stack.push((path, rel_path)),
+                Ok(t) if t.is_file() && is_media(&path) && !ignore.is_ignored(&rel_path) => found.push(path),
+                _ => {}
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// SHA-256 of the file at `path`, hex encoded. Blocks.
+pub(crate) fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 256 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// The hash recorded for `local` if it still has the given size and time.
+fn known_hash(conn: &Connection, local: &str, size: u64, modified: u64) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT sha256 FROM backup_local_files WHERE local_path = ?1 AND size = ?2 AND modified = ?3",
+        rusqlite::params![local, size as i64, modified as i64],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+fn remember_hash(conn: &Connection, local: &str, size: u64, modified: u64, sha256: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO backup_local_files (local_path, size, modified, sha256) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (local_path) DO UPDATE SET size = excluded.size, modified = excluded.modified, sha256 = excluded.sha256",
+        rusqlite::params![local, size as i64, modified as i64, sha256],
+    )?;
+    Ok(())
+}
+
+fn is_backed_up(conn: &Connection, sha256: &str) -> rusqlite::Result<bool> {
+    conn.query_row("SELECT 1 FROM backup_hashes WHERE sha256 = ?1", [sha256], |_| Ok(()))
+        .optional()
+        .map(|found| found.is_some())
+}
+
+fn record_backup(conn: &Connection, sha256: &str, remote: &str, at: u64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO backup_hashes (sha256, remote_path, backed_up_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![sha256, remote, at as i64],
+    )?;
+    Ok(())
+}
+
+/// Whether uploads have to wait.
+fn blocked(app: &AppHandle) -> bool {
+    app.state::<ReadOnlyState>().status().effective
+        || app.state::<crate::power::PowerState>().is_suspended()
+        || !app.state::<crate::network::NetworkState>().is_online()
+}
+
+fn settings() -> PhotoBackupSettings {
+    read_config_section(CONFIG_KEY)
+}
+
+/// Change the progress and announce it.
+fn update_progress(app: &AppHandle, f: impl FnOnce(&mut BackupProgress)) {
+    let progress = {
+        let state = app.state::<PhotoBackupState>();
+        let mut progress = state.progress.lock().unwrap();
+        f(&mut progress);
+        progress.clone()
+    };
+    let _ = app.emit("backup:progress", progress);
+}
+
+/// Hash the media under the configured folders and return the files whose
+/// content has not been backed up, oldest first. The second value is whether
+/// some files were too fresh to take.
+async fn scan(app: &AppHandle, folders: Vec<String>) -> Result<(Vec<NewMedia>, bool), CommandError> {
+    let ignore = crate::ignore::rules(IgnoreScope::Backup);
+    let files = tauri::async_runtime::spawn_blocking(move || collect_media(&folders, &ignore))
+        .await
+        .map_err(|e| CommandError::Unknown(e.to_string()))?;
+    let now = crate::sidecar::unix_now();
+    let mut new = Vec::new();
+    let mut fresh = false;
+    for local in files {
+        let Ok(metadata) = tokio::fs::metadata(&local).await else { continue };
+        let modified = metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_secs());
+        if now.saturating_sub(modified) < QUIET {
+            fresh = true;
+            continue;
+        }
+        let key = local.display().to_string();
+        let sha256 = match db::with(app, |conn| known_hash(conn, &key, metadata.len(), modified))? {
+            Some(sha256) => sha256,
+            None => {
+                let path = local.clone();
+                let hashed = tauri::async_runtime::spawn_blocking(move || hash_file(&path))
+                    .await
+                    .map_err(|e| CommandError::Unknown(e.to_string()))?;
+                let Ok(sha256) = hashed else {
+                    log::warn!("Photo backup: cannot read {}", local.display());
+                    continue;
+                };
+                db::with(app, |conn| remember_hash(conn, &key, metadata.len(), modified, &sha256))?;
+                sha256
+            }
+        };
+        // The same content may appear twice in one scan
+        if db::with(app, |conn| is_backed_up(conn, &sha256))? || new.iter().any(|m: &NewMedia| m.sha256 == sha256) {
+            continue;
+        }
+        new.push(NewMedia { local, sha256, modified });
+    }
+    new.sort_by_key(|m| m.modified);
+    Ok((new, fresh))
+}
+
+/// Upload one new file into its date folder and record its hash.
+async fn upload(app: &AppHandle, client: &DavClient, root: &str, media: &NewMedia) -> Result<String, CommandError> {
+    let dir = date_folder(root, media.modified);
+    let mut parent = String::from("/");
+    for segment in dir.split('/').filter(|s| !s.is_empty()) {
+        parent = join_path(&parent, segment);
+        client.mkcol(&parent).await?;
+    }
+    let local_name = media.local.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    // Nothing comes back down, so a name that doesn't decode goes up as it is
+    let name = NameMapper::current().remote_name(&local_name).unwrap_or_else(|| local_name.clone());
+    let mut remote = join_path(&dir, &name);
+    if client.stat(&remote).await?.is_some() {
+        remote = join_path(&dir, &hashed_name(&name, &media.sha256));
+    }
+    let remote = match client.stat(&remote).await? {
+        // Uploaded before under this hash, but not recorded
+        Some(_) => remote,
+        None => crate::remote::upload_local_file(app, client, &media.local, &remote, false).await?.path,
+    };
+    db::with(app, |conn| record_backup(conn, &media.sha256, &remote, crate::sidecar::unix_now()))?;
+    if name != local_name {
+        name_mapping::record(app, name_mapping::renamed("photoBackup", media.local.display().to_string(), remote.clone()));
+    }
+    Ok(remote)
+}
+
+/// Run one pass. Returns whether it went through all new files.
+async fn pass(app: &AppHandle) -> bool {
+    let settings = settings();
+    if !settings.enabled || settings.folders.is_empty() {
+        update_progress(app, |p| p.phase = BackupPhase::Idle);
+        return true;
+    }
+    if settings.paused {
+        update_progress(app, |p| p.phase = BackupPhase::Paused);
+        return true;
+    }
+    update_progress(app, |p| *p = BackupProgress { phase: BackupPhase::Scanning, last_completed: p.last_completed, ..Default::default() });
+    let (new, fresh) = match scan(app, settings.folders.clone()).await {
+        Ok(found) => found,
+        Err(e) => {
+            log::warn!("Photo backup: scan failed: {}", e);
+            update_progress(app, |p| {
+                p.phase = BackupPhase::Idle;
+                p.last_error = Some(e.to_string());
+            });
+            return false;
+        }
+    };
+    update_progress(app, |p| {
+        p.phase = BackupPhase::Uploading;
+        p.total = new.len();
+    });
+
+    let client = DavClient::for_app(app);
+    for media in &new {
+        if self::settings().paused {
+            update_progress(app, |p| {
+                p.phase = BackupPhase::Paused;
+                p.current = None;
+            });
+            return true;
+        }
+        let client = match (&client, blocked(app)) {
+            (Ok(client), false) => client,
+            _ => {
+                update_progress(app, |p| {
+                    p.phase = BackupPhase::Waiting;
+                    p.current = None;
+                });
+                return false;
+            }
+        };
+        update_progress(app, |p| p.current = Some(media.local.display().to_string()));
+        match upload(app, client, &settings.remote_root, media).await {
+            Ok(remote) => {
+                log::info!("Photo backup: uploaded {} to {}", media.local.display(), remote);
+                update_progress(app, |p| p.uploaded += 1);
+            }
+            Err(CommandError::ServerNotRunning) => {
+                update_progress(app, |p| {
+                    p.phase = BackupPhase::Waiting;
+                    p.current = None;
+                });
+                return false;
+            }
+            Err(e) => {
+                log::warn!("Photo backup: upload of {} failed: {}", media.local.display(), e);
+                update_progress(app, |p| {
+                    p.failed += 1;
+                    p.last_error = Some(format!("{}: {}", media.local.display(), e));
+                });
+            }
+        }
+    }
+    update_progress(app, |p| {
+        p.phase = BackupPhase::Idle;
+        p.current = None;
+        p.last_completed = Some(crate::sidecar::unix_now());
+    });
+    !fresh
+}
+
+/// Wait for a reason to back up, then run a pass; forever.
+async fn run(app: AppHandle) {
+    let mut interval = RESCAN_INTERVAL;
+    loop {
+        let state = app.state::<PhotoBackupState>();
+        if tokio::time::timeout(interval, state.wake.notified()).await.is_ok() {
+            tokio::time::sleep(SETTLE).await;
+        }
+        interval = if pass(&app).await { RESCAN_INTERVAL } else { RETRY_INTERVAL };
+    }
+}
+
+/// Watch the configured folders, replacing the previous watcher, and wake
+/// the backup task.
+fn start(app: &AppHandle, settings: &PhotoBackupSettings) -> Result<(), CommandError> {
+    let state = app.state::<PhotoBackupState>();
+    let mut current = state.watcher.lock().unwrap();
+    *current = None;
+    if settings.enabled && !settings.folders.is_empty() {
+        let handle = app.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                if event.paths.iter().any(|p| is_media(p)) {
+                    handle.state::<PhotoBackupState>().wake.notify_one();
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Photo backup watch error: {}", e),
+        })
+        .map_err(|e| CommandError::InvalidBackupFolder(e.to_string()))?;
+        for folder in &settings.folders {
+            watcher
+                .watch(Path::new(folder), RecursiveMode::Recursive)
+                .map_err(|e| CommandError::InvalidBackupFolder(format!("Cannot watch {}: {}", folder, e)))?;
+        }
+        *current = Some(watcher);
+        log::info!("Photo backup: watching {} -> {}", settings.folders.join(", "), settings.remote_root);
+    }
+    state.wake.notify_one();
+    Ok(())
+}
+
+/// Start the backup task and watch the configured folders; called once from
+/// `setup`.
+pub fn spawn(app: &AppHandle) {
+    if let Err(e) = start(app, &settings()) {
+        log::warn!("Photo backup not watching: {}", e);
+    }
+    tauri::async_runtime::spawn(run(app.clone()));
+}
+
+#[tauri::command]
+pub async fn get_photo_backup() -> Result<PhotoBackupSettings, CommandError> {
+    Ok(settings())
+}
+
+/// Back up `folders` into `remote_root`. The paused state is kept.
+#[tauri::command]
+pub async fn set_photo_backup(
+    app: AppHandle,
+    enabled: bool,
+    folders: Vec<String>,
+    remote_root: Option<String>,
+) -> Result<PhotoBackupSettings, CommandError> {
+    if let Some(folder) = folders.iter().find(|f| !Path::new(f).is_dir()) {
+        return Err(CommandError::InvalidBackupFolder(format!("{} is not a folder", folder)));
+    }
+    let current = settings();
+    let settings = PhotoBackupSettings {
+        enabled,
+        folders,
+        remote_root: remote_root.map(|r| normalize_path(&r)).unwrap_or(current.remote_root),
+        paused: current.paused,
+    };
+    if settings.remote_root == "/" {
+        return Err(CommandError::InvalidRemotePath("Back up into a folder, not the drive root".into()));
+    }
+    start(&app, &settings)?;
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_photo_backup_status(state: State<'_, PhotoBackupState>) -> Result<BackupProgress, CommandError> {
+    Ok(state.progress())
+}
+
+/// Stop uploading after the current file, until resumed.
+#[tauri::command]
+pub async fn pause_photo_backup(app: AppHandle) -> Result<BackupProgress, CommandError> {
+    write_config_section(CONFIG_KEY, &PhotoBackupSettings { paused: true, ..settings() })?;
+    let uploading = app.state::<PhotoBackupState>().progress().phase == BackupPhase::Uploading;
+    if !uploading {
+        update_progress(&app, |p| p.phase = BackupPhase::Paused);
+    }
+    Ok(app.state::<PhotoBackupState>().progress())
+}
+
+#[tauri::command]
+pub async fn resume_photo_backup(app: AppHandle) -> Result<BackupProgress, CommandError> {
+    write_config_section(CONFIG_KEY, &PhotoBackupSettings { paused: false, ..settings() })?;
+    let state = app.state::<PhotoBackupState>();
+    if state.progress().phase == BackupPhase::Paused {
+        update_progress(&app, |p| p.phase = BackupPhase::Idle);
+    }
+    state.wake.notify_one();
+    Ok(state.progress())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_files_by_extension() {
+        assert!(is_media(Path::new("/home/u/Pictures/IMG_0001.JPG")));
+        assert!(is_media(Path::new("/home/u/Pictures/clip.mov")));
+        assert!(!is_media(Path::new("/home/u/Pictures/notes.txt")));
+        assert!(!is_media(Path::new("/home/u/Pictures/.IMG_0001.jpg")));
+        assert!(!is_media(Path::new("/home/u/Pictures/README")));
+    }
+
+    #[test]
+    fn test_remote_names() {
+        // 2024-03-05 12:00 UTC
+        assert_eq!(date_folder("/Photos", 1_709_640_000), "/Photos/2024/03");
+        assert_eq!(hashed_name("IMG_0001.jpg", "0123456789abcdef"), "IMG_0001-01234567.jpg");
+        assert_eq!(hashed_name("clip", "0123456789abcdef"), "clip-01234567");
+    }
+
+    #[test]
+    fn test_hashes_are_reused_until_the_file_changes() {
+        let conn = db::open_in_memory();
+        remember_hash(&conn, "/p/a.jpg", 10, 100, "aa").unwrap();
+        assert_eq!(known_hash(&conn, "/p/a.jpg", 10, 100).unwrap().as_deref(), Some("aa"));
+        assert_eq!(known_hash(&conn, "/p/a.jpg", 11, 100).unwrap(), None);
+        assert!(!is_backed_up(&conn, "aa").unwrap());
+        record_backup(&conn, "aa", "/Photos/2024/03/a.jpg", 200).unwrap();
+        assert!(is_backed_up(&conn, "aa").unwrap());
+    }
+}
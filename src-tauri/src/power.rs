@@ -0,0 +1,289 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::sidecar::SidecarState;
+use crate::transfers::TransferState;
+
+// ============================================================================
+// Suspend and resume
+// ============================================================================
+//
+// On Linux the app listens for logind's `PrepareForSleep` signal and holds a
+// "delay" inhibitor lock, so before the machine sleeps it can let running
+// transfers finish (cancelling what is left after `QUIESCE_TIMEOUT`, within
+// logind's default five-second delay) and pause the background sync, offline
+// and drop folder loops. After resume it restarts the bridge if it died and
+// remounts the drive if the mount was lost. `power:suspending` and
+// `power:resumed` are emitted around the sleep.
+//
+// Elsewhere, or when logind is not reachable, only the resume is noticed: a
+// timer compares wall-clock time with the time it slept, and a jump means
+// the machine was asleep. The bridge is restarted then, but the mount is
+// left alone.
+
+/// How long running transfers get to finish before suspend.
+const QUIESCE_TIMEOUT: Duration = Duration::from_secs(4);
+
+const QUIESCE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time for the network to come back before the bridge is checked.
+const RESUME_SETTLE_DELAY: Duration = Duration::from_secs(3);
+
+/// Tick of the clock-jump fallback.
+const CLOCK_TICK: Duration = Duration::from_secs(15);
+
+/// Wall-clock time beyond the tick that counts as a sleep.
+const CLOCK_JUMP_THRESHOLD: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumedEvent {
+    /// Roughly how long the machine slept, when known
+    pub slept_seconds: Option<u64>,
+}
+
+/// What was up before suspend, to be restored after resume.
+#[derive(Clone, Copy, Debug, Default)]
+struct Snapshot {
+    sidecar_running: bool,
+    mounted: bool,
+    suspended_at: u64,
+}
+
+#[derive(Default)]
+pub struct PowerState {
+    suspended: AtomicBool,
+    snapshot: Mutex<Option<Snapshot>>,
+    /// logind delay inhibitor; closing it lets the suspend proceed
+    #[cfg(target_os = "linux")]
+    inhibitor: Mutex<Option<std::os::fd::OwnedFd>>,
+}
+
+impl PowerState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether background work should wait for the machine to wake up.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::Relaxed)
+    }
+}
+
+/// Wall-clock time that passed beyond `expected`, if it counts as a sleep.
+fn sleep_gap(expected: Duration, wall: Duration) -> Option<Duration> {
+    let gap = wall.checked_sub(expected)?;
+    (gap >= CLOCK_JUMP_THRESHOLD).then_some(gap)
+}
+
+async fn snapshot(app: &AppHandle) -> Snapshot {
+    let sidecar_running = app.state::<SidecarState>().is_running().await;
+    let mounted = sidecar_running
+        && matches!(crate::sidecar::check_mount_status(app.clone(), app.state::<SidecarState>()).await, Ok(Some(_)));
+    Snapshot { sidecar_running, mounted, suspended_at: crate::sidecar::unix_now() }
+}
+
+/// Let transfers finish for a while, then cancel the rest.
+async fn quiesce_transfers(app: &AppHandle) {
+    let transfers = app.state::<TransferState>();
+    let deadline = tokio::time::Instant::now() + QUIESCE_TIMEOUT;
+    while !transfers.list().is_empty() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(QUIESCE_POLL_INTERVAL).await;
+    }
+    let remaining = transfers.list();
+    for transfer in &remaining {
+        transfers.cancel(transfer.id);
+    }
+    if !remaining.is_empty() {
+        log::info!("Cancelled {} transfer(s) before suspend", remaining.len());
+    }
+}
+
+async fn on_suspend(app: AppHandle) {
+    let state = app.state::<PowerState>();
+    if state.suspended.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    log::info!("System is suspending");
+    let _ = app.emit("power:suspending", ());
+    let (snapshot, ()) = tokio::join!(snapshot(&app), quiesce_transfers(&app));
+    *state.snapshot.lock().unwrap() = Some(snapshot);
+    #[cfg(target_os = "linux")]
+    release_inhibitor(&app);
+}
+
+async fn on_resume(app: AppHandle, slept: Option<Duration>) {
+    let state = app.state::<PowerState>();
+    state.suspended.store(false, Ordering::Relaxed);
+    let snapshot = state.snapshot.lock().unwrap().take();
+    let slept_seconds = slept
+        .map(|d| d.as_secs())
+        .or_else(|| snapshot.map(|s| crate::sidecar::unix_now().saturating_sub(s.suspended_at)));
+    log::info!("System resumed (slept {:?}s)", slept_seconds);
+    let _ = app.emit("power:resumed", ResumedEvent { slept_seconds });
+
+    tokio::time::sleep(RESUME_SETTLE_DELAY).await;
+    crate::status::invalidate(&app);
+    let Some(snapshot) = snapshot else { return };
+    if snapshot.sidecar_running && !app.state::<SidecarState>().is_running().await {
+        log::info!("Restarting the bridge after resume");
+        if let Err(e) = crate::sidecar::start_sidecar(app.clone(), app.state::<SidecarState>(), None).await {
+            log::error!("Failed to restart the bridge after resume: {}", e);
+            return;
+        }
+    }
+    if snapshot.mounted {
+        match crate::sidecar::check_mount_status(app.clone(), app.state::<SidecarState>()).await {
+            Ok(Some(_)) => {}
+            _ => {
+                log::info!("Remounting the drive after resume");
+                crate::auto_mount::mount_when_ready(app).await;
+            }
+        }
+    }
+}
+
+/// Notice a resume by wall-clock time jumping ahead of the timer. Only the
+/// bridge is restored then: checking the mount on every tick would be too
+/// costly.
+async fn watch_clock(app: AppHandle) {
+    let mut last = SystemTime::now();
+    loop {
+        let sidecar_running = app.state::<SidecarState>().is_running().await;
+        *app.state::<PowerState>().snapshot.lock().unwrap() =
+            Some(Snapshot { sidecar_running, mounted: false, suspended_at: crate::sidecar::unix_now() });
+        tokio::time::sleep(CLOCK_TICK).await;
+        let now = SystemTime::now();
+        let wall = now.duration_since(last).unwrap_or_default();
+        last = now;
+        if let Some(gap) = sleep_gap(CLOCK_TICK, wall) {
+            on_resume(app.clone(), Some(gap)).await;
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod logind {
+    use gio::prelude::*;
+    use std::os::fd::OwnedFd;
+
+    const BUS_NAME: &str = "org.freedesktop.login1";
+    const OBJECT_PATH: &str = "/org/freedesktop/login1";
+    const INTERFACE: &str = "org.freedesktop.login1.Manager";
+
+    pub fn system_bus() -> Result<gio::DBusConnection, glib::Error> {
+        gio::bus_get_sync(gio::BusType::System, None::<&gio::Cancellable>)
+    }
+
+    /// Take a delay lock on sleep; suspend waits until the descriptor closes.
+    pub fn inhibit(connection: &gio::DBusConnection) -> Result<OwnedFd, glib::Error> {
+        let args = ("sleep", "Proton Drive WebDAV Bridge", "Finishing transfers", "delay").to_variant();
+        let (_, fds) = connection.call_with_unix_fd_list_sync(
+            Some(BUS_NAME),
+            OBJECT_PATH,
+            INTERFACE,
+            "Inhibit",
+            Some(&args),
+            Some(glib::VariantTy::new("(h)").expect("valid type")),
+            gio::DBusCallFlags::NONE,
+            -1,
+            None::<&gio::UnixFDList>,
+            None::<&gio::Cancellable>,
+        )?;
+        fds.ok_or_else(|| glib::Error::new(gio::IOErrorEnum::Failed, "no inhibitor descriptor"))?.get(0)
+    }
+
+    pub fn subscribe(connection: &gio::DBusConnection, callback: impl Fn(bool) + 'static) -> gio::SignalSubscription {
+        connection.subscribe_to_signal(
+            Some(BUS_NAME),
+            Some(INTERFACE),
+            Some("PrepareForSleep"),
+            Some(OBJECT_PATH),
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |signal| {
+                if let Some((start,)) = signal.parameters.get::<(bool,)>() {
+                    callback(start);
+                }
+            },
+        )
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn take_inhibitor(app: &AppHandle, connection: &gio::DBusConnection) {
+    match logind::inhibit(connection) {
+        Ok(fd) => *app.state::<PowerState>().inhibitor.lock().unwrap() = Some(fd),
+        Err(e) => log::warn!("Cannot delay suspend: {}", e),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn release_inhibitor(app: &AppHandle) {
+    app.state::<PowerState>().inhibitor.lock().unwrap().take();
+}
+
+/// Follow logind on a dedicated GLib thread; falls back to the clock when
+/// the system bus is not reachable (e.g. in a container).
+#[cfg(target_os = "linux")]
+pub fn spawn(app: AppHandle) {
+    let spawned = std::thread::Builder::new().name("logind-sleep-monitor".into()).spawn(move || {
+        let context = glib::MainContext::new();
+        let result = context.with_thread_default(|| {
+            let connection = match logind::system_bus() {
+                Ok(connection) => connection,
+                Err(e) => {
+                    log::warn!("logind unavailable ({}); detecting resume from the clock", e);
+                    tauri::async_runtime::spawn(watch_clock(app.clone()));
+                    return;
+                }
+            };
+            take_inhibitor(&app, &connection);
+            let signal_app = app.clone();
+            let signal_connection = connection.clone();
+            let _subscription = logind::subscribe(&connection, move |start| {
+                if start {
+                    tauri::async_runtime::spawn(on_suspend(signal_app.clone()));
+                } else {
+                    take_inhibitor(&signal_app, &signal_connection);
+                    tauri::async_runtime::spawn(on_resume(signal_app.clone(), None));
+                }
+            });
+            glib::MainLoop::new(Some(&context), false).run();
+        });
+        if let Err(e) = result {
+            log::error!("Sleep monitor stopped: {}", e);
+        }
+    });
+    if let Err(e) = spawned {
+        log::error!("Failed to start the sleep monitor: {}", e);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(watch_clock(app));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_gap() {
+        assert_eq!(sleep_gap(CLOCK_TICK, CLOCK_TICK), None);
+        assert_eq!(sleep_gap(CLOCK_TICK, CLOCK_TICK + Duration::from_secs(2)), None);
+        assert_eq!(sleep_gap(CLOCK_TICK, Duration::from_secs(15 + 600)), Some(Duration::from_secs(600)));
+        // The clock was set back
+        assert_eq!(sleep_gap(CLOCK_TICK, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_resumed_event_serializes_camel_case() {
+        let json = serde_json::to_value(ResumedEvent { slept_seconds: Some(42) }).unwrap();
+        assert_eq!(json, serde_json::json!({"sleptSeconds": 42}));
+    }
+}
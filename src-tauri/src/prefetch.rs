@@ -0,0 +1,155 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::cache::MetadataCache;
+use crate::dav::{normalize_path, DavClient};
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Directory prefetch
+// ============================================================================
+//
+// Browsing a cold mount is slow because GVFS lists folders one by one as the
+// file manager asks for them, and each listing makes the bridge fetch and
+// decrypt metadata from Proton. `prefetch_path` walks a folder tree through
+// the bridge in the background, level by level with a few listings in
+// flight, so the bridge's metadata cache is warm by the time the mount is
+// opened. Progress is reported as `prefetch:progress` events.
+
+const DEFAULT_DEPTH: u32 = 3;
+const MAX_DEPTH: u32 = 20;
+
+/// Stop after listing this many folders.
+const MAX_FOLDERS: usize = 10_000;
+
+/// Listings requested from the bridge at the same time.
+const CONCURRENCY: usize = 4;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PrefetchPhase {
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PrefetchProgress {
+    /// Root of the prefetch
+    pub path: String,
+    pub phase: PrefetchPhase,
+    /// 1-based level being listed; 1 is `path` itself
+    pub level: u32,
+    pub folders_listed: usize,
+    /// Files and folders seen so far
+    pub entries: usize,
+    pub error: Option<String>,
+}
+
+/// Roots being prefetched, so the same tree isn't walked twice at once.
+#[derive(Default)]
+pub struct PrefetchState {
+    running: Mutex<HashSet<String>>,
+}
+
+impl PrefetchState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// List `root` and the folders below it down to `depth` levels.
+async fn walk(app: &AppHandle, client: &DavClient, root: &str, depth: u32) -> Result<PrefetchProgress, CommandError> {
+    let mut progress = PrefetchProgress {
+        path: root.to_string(),
+        phase: PrefetchPhase::Running,
+        level: 0,
+        folders_listed: 0,
+        entries: 0,
+        error: None,
+    };
+    let mut level_dirs = vec![root.to_string()];
+    while !level_dirs.is_empty() && progress.level < depth && progress.folders_listed < MAX_FOLDERS {
+        progress.level += 1;
+        level_dirs.truncate(MAX_FOLDERS - progress.folders_listed);
+        let mut listings = futures_util::stream::iter(level_dirs.into_iter().map(|dir| async move {
+            // Parsed as it streams in, so a huge folder isn't held as one
+            // response body and document tree on top of its entries
+            let mut entries = Vec::new();
+            let result = client
+                .propfind_each(&dir, |batch| {
+                    entries.extend(batch);
+                    true
+                })
+                .await
+                .map(|()| entries);
+            (dir, result)
+        }))
+        .buffer_unordered(CONCURRENCY);
+
+        let mut next = Vec::new();
+        while let Some((dir, result)) = listings.next().await {
+            let entries = match result {
+                Ok(entries) => entries,
+                Err(e) if dir == root => return Err(e),
+                Err(e) => {
+                    log::debug!("Prefetch skipped {}: {}", dir, e);
+                    continue;
+                }
+            };
+            progress.folders_listed += 1;
+            app.state::<MetadataCache>().remember_listing(&dir, &entries);
+            for entry in entries.into_iter().filter(|e| e.path != dir) {
+                progress.entries += 1;
+                if entry.is_dir {
+                    next.push(entry.path);
+                }
+            }
+            let _ = app.emit("prefetch:progress", progress.clone());
+        }
+        level_dirs = next;
+    }
+    progress.phase = PrefetchPhase::Completed;
+    Ok(progress)
+}
+
+/// Warm the bridge's metadata cache for `remote_path` and the folders below
+/// it, `depth` levels deep (default 3; 1 lists only `remote_path`). Runs in
+/// the background and returns `false` if the same folder is already being
+/// prefetched.
+#[tauri::command]
+pub async fn prefetch_path(app: AppHandle, state: State<'_, PrefetchState>, remote_path: String, depth: Option<u32>) -> Result<bool, CommandError> {
+    let root = normalize_path(&remote_path);
+    let depth = depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH);
+    let client = DavClient::for_app(&app)?;
+    if !state.running.lock().unwrap().insert(root.clone()) {
+        return Ok(false);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let progress = match walk(&app, &client, &root, depth).await {
+            Ok(progress) => {
+                log::info!("Prefetched {} folders ({} entries) under {}", progress.folders_listed, progress.entries, root);
+                progress
+            }
+            Err(e) => {
+                log::warn!("Prefetch of {} failed: {}", root, e);
+                PrefetchProgress {
+                    path: root.clone(),
+                    phase: PrefetchPhase::Failed,
+                    level: 0,
+                    folders_listed: 0,
+                    entries: 0,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        app.state::<PrefetchState>().running.lock().unwrap().remove(&root);
+        let _ = app.emit("prefetch:progress", progress);
+    });
+    Ok(true)
+}
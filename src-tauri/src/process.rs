@@ -0,0 +1,437 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::OnceCell;
+
+use crate::gateway::Upstream;
+use crate::sidecar::{CommandError, LogEvent, SidecarState};
+
+// ============================================================================
+// Externally started bridges
+// ============================================================================
+//
+// Users who also run the bridge from a terminal end up with an instance the
+// app did not spawn. Instead of failing on `start_sidecar` (the CLI refuses
+// to start twice because of its PID file), the app adopts it: the PID comes
+// from the sidecar's `status`, log lines are tailed from the bridge's log file
+// since there is no stdout to read, and the process is polled so its exit
+// is reported like a child's. An adopted bridge listens on the public port
+// itself, so it runs without the gateway. It is therefore not adopted while
+// any policy the gateway enforces is on (see `gateway::enforced_policies`);
+// the user has to stop it so the app can start one behind the gateway.
+
+/// How often the log file and the process are checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Binary name of the bridge, as it appears in its command line.
+const SIDECAR_NAME: &str = "proton-drive-webdav-bridge";
+
+const PID_RECORD_FILE: &str = "sidecar-pid.json";
+
+/// How long a killed orphan gets to exit.
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+static ORPHAN_CHECK: OnceCell<()> = OnceCell::const_new();
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AdoptedEvent {
+    pub pid: u32,
+    pub log_file: String,
+}
+
+/// Whether a process with this PID still exists.
+pub fn process_alive(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        std::path::Path::new(&format!("/proc/{}", pid)).exists()
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .is_ok_and(|o| o.status.success())
+    }
+
+    #[cfg(windows)]
+    {
+        std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+    }
+}
+
+/// Command line of a running process, as far as the platform exposes it.
+fn command_line(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let raw = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+        Some(String::from_utf8_lossy(&raw).replace('\0', " ").trim_end().to_string())
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        let output = std::process::Command::new("ps").args(["-p", &pid.to_string(), "-o", "command="]).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    #[cfg(windows)]
+    {
+        // Only the image name is available without WMI
+        let output = std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+            .output()
+            .ok()?;
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+pub(crate) fn terminate(pid: u32) {
+    #[cfg(unix)]
+    let result = std::process::Command::new("kill").args(["-TERM", &pid.to_string()]).output();
+
+    #[cfg(windows)]
+    let result = std::process::Command::new("taskkill").args(["/PID", &pid.to_string(), "/T", "/F"]).output();
+
+    if let Err(e) = result {
+        log::warn!("Failed to stop process {}: {}", pid, e);
+    }
+}
+
+/// Level of a line written by the bridge's file logger
+/// (`2024-01-01 12:00:00 [ERROR] message`).
+fn line_level(line: &str) -> String {
+    line.split_once(" [")
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(level, _)| level.to_lowercase())
+        .filter(|level| matches!(level.as_str(), "error" | "warn" | "info" | "debug"))
+        .unwrap_or_else(|| "info".to_string())
+}
+
+/// Append `chunk` to `partial` and take out every complete line.
+fn split_lines(partial: &mut String, chunk: &str) -> Vec<String> {
+    partial.push_str(chunk);
+    let Some(end) = partial.rfind('\n') else {
+        return Vec::new();
+    };
+    let rest = partial.split_off(end + 1);
+    let lines = partial.lines().filter(|l| !l.is_empty()).map(str::to_string).collect();
+    *partial = rest;
+    lines
+}
+
+/// Follows a log file from its current end, like `tail -f`.
+struct LogTail {
+    path: PathBuf,
+    offset: u64,
+    partial: String,
+}
+
+impl LogTail {
+    fn open(path: PathBuf) -> Self {
+        let offset = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Self { path, offset, partial: String::new() }
+    }
+
+    fn read_lines(&mut self) -> Vec<String> {
+        let Ok(mut file) = std::fs::File::open(&self.path) else {
+            return Vec::new();
+        };
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        if len < self.offset {
+            // Truncated or replaced
+            self.offset = 0;
+            self.partial.clear();
+        }
+        let mut buf = Vec::new();
+        if file.seek(SeekFrom::Start(self.offset)).is_err() || file.read_to_end(&mut buf).is_err() {
+            return Vec::new();
+        }
+        self.offset += buf.len() as u64;
+        split_lines(&mut self.partial, &String::from_utf8_lossy(&buf))
+    }
+}
+
+// ============================================================================
+// PID record
+// ============================================================================
+//
+// The supervisor's handle dies with the GUI, and a bridge we spawned keeps running
+// on its loopback port with nothing in front of it. The spawned PID is
+// therefore also written to a runtime file; on the next start the process is
+// checked against its command line (PIDs get reused) and either put back
+// behind a gateway on the recorded ports or stopped.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PidRecord {
+    pub pid: u32,
+    /// Unix timestamp (seconds) of the spawn
+    pub started_at: u64,
+    /// 0 when the bridge listens on a Unix socket
+    pub upstream_port: u16,
+    /// Socket the bridge listens on, in its configured form
+    #[serde(default)]
+    pub socket: Option<String>,
+    pub public_port: u16,
+}
+
+impl PidRecord {
+    pub fn new(pid: u32, upstream: &Upstream, public_port: u16) -> Self {
+        let started_at = crate::sidecar::unix_now();
+        let (upstream_port, socket) = match upstream {
+            Upstream::Tcp(port) => (*port, None),
+            Upstream::UnixSocket(path) => (0, Some(crate::transport::display_socket_path(path))),
+        };
+        Self { pid, started_at, upstream_port, socket, public_port }
+    }
+
+    pub fn upstream(&self) -> Upstream {
+        match &self.socket {
+            Some(socket) => Upstream::UnixSocket(crate::transport::socket_path(socket)),
+            None => Upstream::Tcp(self.upstream_port),
+        }
+    }
+
+    /// Whether `cmdline` belongs to the bridge this record describes. The
+    /// port or socket is only checked when the platform shows arguments at
+    /// all.
+    fn matches(&self, cmdline: &str) -> bool {
+        let (flag, value) = match &self.socket {
+            Some(socket) => ("--socket", socket.clone()),
+            None => ("--port", self.upstream_port.to_string()),
+        };
+        cmdline.contains(SIDECAR_NAME) && (!cmdline.contains(flag) || cmdline.contains(&format!("{} {}", flag, value)))
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/proton-drive-webdav-bridge`, or the config directory
+/// where there is no runtime directory.
+fn record_path() -> Option<PathBuf> {
+    if let Some(runtime) = std::env::var_os("XDG_RUNTIME_DIR") {
+        let dir = PathBuf::from(runtime).join(SIDECAR_NAME);
+        if std::fs::create_dir_all(&dir).is_ok() {
+            return Some(dir.join(PID_RECORD_FILE));
+        }
+    }
+    crate::sidecar::get_config_file_path().ok().map(|p| p.with_file_name(PID_RECORD_FILE))
+}
+
+fn load_record() -> Option<PidRecord> {
+    let raw = std::fs::read_to_string(record_path()?).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub fn save_record(record: &PidRecord) {
+    let Some(path) = record_path() else {
+        return;
+    };
+    let written = serde_json::to_vec(record)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        log::warn!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// Record that the gateway in front of `pid` moved to `public_port`.
+pub fn update_public_port(pid: u32, public_port: u16) {
+    if let Some(record) = load_record().filter(|r| r.pid == pid) {
+        save_record(&PidRecord { public_port, ..record });
+    }
+}
+
+/// Remove the record if it still describes `pid`.
+pub fn clear_record(pid: u32) {
+    if load_record().is_some_and(|r| r.pid == pid) {
+        if let Some(path) = record_path() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Deal with a bridge left behind by a previous run of the app. Runs once;
+/// concurrent callers wait for the first to finish.
+pub async fn recover_orphan(app: &AppHandle) {
+    ORPHAN_CHECK.get_or_init(|| recover_recorded(app)).await;
+}
+
+async fn recover_recorded(app: &AppHandle) {
+    let Some(record) = load_record() else {
+        return;
+    };
+    let state = app.state::<SidecarState>();
+    if state.is_running().await {
+        return;
+    }
+    let is_bridge = process_alive(record.pid) && command_line(record.pid).is_some_and(|c| record.matches(&c));
+    if !is_bridge {
+        log::info!("Discarding stale PID record for {}", record.pid);
+        clear_record(record.pid);
+        return;
+    }
+
+    let (host, _) = crate::sidecar::configured_listen_addr();
+    let hosts = app.state::<crate::network_sharing::NetworkSharingState>().listen_hosts(&host);
+    // It was spawned with the install's WebDAV credential
+    crate::webdav_auth::begin_run(app).await;
+    if let Err(e) = crate::gateway::start(app, &hosts, record.public_port, record.upstream()).await {
+        log::warn!("Cannot put orphaned bridge {} back behind the gateway ({}); stopping it", record.pid, e);
+        app.state::<crate::webdav_auth::WebdavAuthState>().end_run();
+        terminate(record.pid);
+        let deadline = std::time::Instant::now() + KILL_TIMEOUT;
+        while process_alive(record.pid) && std::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        clear_record(record.pid);
+        return;
+    }
+
+    if !state.claim(record.pid, false).await {
+        log::warn!("Another bridge was started while re-adopting {}", record.pid);
+        return;
+    }
+    let log_file = crate::sidecar::get_status(app.clone(), app.state::<SidecarState>())
+        .await
+        .map(|s| s.log_file)
+        .unwrap_or_default();
+    log::info!("Re-adopted bridge {} started at {} on {}", record.pid, record.started_at, record.upstream());
+    crate::status::invalidate(app);
+    let _ = app.emit("sidecar:adopted", AdoptedEvent { pid: record.pid, log_file: log_file.clone() });
+    tauri::async_runtime::spawn(watch(app.clone(), record.pid, PathBuf::from(log_file)));
+}
+
+/// Adopt a bridge that is running without the app having spawned it.
+/// Returns its PID, or `None` if nothing was adopted. Fails when there is
+/// one but serving through it would bypass policies of the gateway.
+pub async fn adopt_running(app: &AppHandle) -> Result<Option<u32>, CommandError> {
+    let state = app.state::<SidecarState>();
+    if state.is_running().await {
+        return Ok(None);
+    }
+    let status = match crate::sidecar::probe_status(app).await {
+        Ok(status) => status,
+        // Without a readable status there is nothing to adopt safely
+        Err(e) => {
+            log::warn!("Not looking for a running bridge: {}", e);
+            return Ok(None);
+        }
+    };
+    let Some(pid) = status.server.pid.filter(|_| status.server.running) else {
+        return Ok(None);
+    };
+    let policies = crate::gateway::enforced_policies(app);
+    if !policies.is_empty() {
+        log::warn!("Not adopting bridge {}: it would bypass {}", pid, policies.join(", "));
+        return Err(CommandError::AdoptionRefused(policies.join(", ")));
+    }
+    if !state.claim(pid, true).await {
+        return Ok(None);
+    }
+
+    log::info!("Adopted running bridge (PID {}), following {}", pid, status.log_file);
+    crate::status::invalidate(app);
+    let _ = app.emit("sidecar:adopted", AdoptedEvent { pid, log_file: status.log_file.clone() });
+    tauri::async_runtime::spawn(watch(app.clone(), pid, PathBuf::from(status.log_file)));
+    Ok(Some(pid))
+}
+
+/// Whether the running bridge was adopted and serves without the gateway.
+pub(crate) async fn serving_adopted(app: &AppHandle) -> bool {
+    app.state::<SidecarState>().tracked().await.is_some_and(|t| t.adopted)
+}
+
+/// Stream the adopted bridge's log and report its exit.
+async fn watch(app: AppHandle, pid: u32, log_file: PathBuf) {
+    use crate::notifications::{is_session_expired_line, notify, NotificationCategory};
+
+    let state = app.state::<SidecarState>();
+    let mut tail = LogTail::open(log_file);
+    let mut session_expiry_notified = false;
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        for line in tail.read_lines() {
+            if crate::status::affects_status(&line) {
+                crate::status::invalidate(&app);
+            }
+            if !session_expiry_notified && is_session_expired_line(&line) {
+                session_expiry_notified = true;
+                notify(
+                    &app,
+                    NotificationCategory::SessionExpired,
+                    "Proton session expired",
+                    "Sign in again to keep accessing your drive.",
+                );
+            }
+            let _ = app.emit("sidecar:log", LogEvent { level: line_level(&line), message: line });
+        }
+
+        if !process_alive(pid) {
+            if state.pid().await == Some(pid) {
+                crate::gateway::stop(&app);
+            }
+            let stop_requested = state.exited(pid).await;
+            clear_record(pid);
+            crate::status::invalidate(&app);
+            if !stop_requested {
+                notify(
+                    &app,
+                    NotificationCategory::SidecarCrash,
+                    "WebDAV bridge stopped unexpectedly",
+                    "The bridge exited. Your drive is unavailable until it is restarted.",
+                );
+            }
+            let _ = app.emit("sidecar:terminated", serde_json::json!({ "code": null, "signal": null }));
+            return;
+        }
+        // Replaced by a bridge the app started itself
+        if state.pid().await != Some(pid) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_level() {
+        assert_eq!(line_level("2024-01-01 12:00:00 [ERROR] Upload failed"), "error");
+        assert_eq!(line_level("2024-01-01 12:00:00 [WARN] Slow response"), "warn");
+        assert_eq!(line_level("    at Object.<anonymous> (index.js:1:1)"), "info");
+    }
+
+    #[test]
+    fn test_split_lines_keeps_partial_line() {
+        let mut partial = String::new();
+        assert_eq!(split_lines(&mut partial, "first\nsec"), vec!["first"]);
+        assert_eq!(partial, "sec");
+        assert_eq!(split_lines(&mut partial, "ond\n\nthird\n"), vec!["second", "third"]);
+        assert!(partial.is_empty());
+    }
+
+    #[test]
+    fn test_pid_record_matches_command_line() {
+        let record = PidRecord { pid: 42, started_at: 0, upstream_port: 40123, socket: None, public_port: 8080 };
+        assert!(record.matches("/usr/bin/proton-drive-webdav-bridge start --no-auth --no-daemon --host 127.0.0.1 --port 40123"));
+        assert!(!record.matches("/usr/bin/proton-drive-webdav-bridge start --port 40999"));
+        assert!(!record.matches("/usr/bin/python3 -m http.server"));
+        assert!(record.matches("\"proton-drive-webdav-bridge.exe\",\"42\",\"Console\""));
+
+        let record = PidRecord::new(42, &Upstream::UnixSocket("\0bridge".into()), 8080);
+        assert_eq!(record.upstream(), Upstream::UnixSocket("\0bridge".into()));
+        assert!(record.matches("/usr/bin/proton-drive-webdav-bridge start --no-daemon --socket @bridge"));
+        assert!(!record.matches("/usr/bin/proton-drive-webdav-bridge start --no-daemon --socket @other"));
+    }
+
+    #[test]
+    fn test_current_process_is_alive() {
+        assert!(process_alive(std::process::id()));
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::dav::DavClient;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Storage quota
+// ============================================================================
+//
+// The bridge reports the account's storage through the RFC 4331 quota
+// properties of its root collection. A background task checks them
+// periodically and raises a `quota:warning` event (and a notification) once
+// usage crosses the configured threshold; it warns again only after usage
+// dropped below the threshold in between.
+
+const CONFIG_KEY: &str = "quota";
+
+/// Delay before the first check so it doesn't compete with startup.
+const INITIAL_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuotaSettings {
+    /// Usage (percent of total) at which to warn; 0 disables the check
+    pub warning_percent: u8,
+    pub check_interval_minutes: u64,
+}
+
+impl Default for QuotaSettings {
+    fn default() -> Self {
+        Self { warning_percent: 90, check_interval_minutes: 30 }
+    }
+}
+
+impl QuotaSettings {
+    fn validate(&self) -> Result<(), CommandError> {
+        if self.warning_percent > 100 {
+            return Err(CommandError::InvalidQuotaSettings("warningPercent must be between 0 and 100".into()));
+        }
+        if self.check_interval_minutes == 0 {
+            return Err(CommandError::InvalidQuotaSettings("checkIntervalMinutes must be positive".into()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageQuota {
+    /// Bytes in use
+    pub used: u64,
+    /// Total storage in bytes
+    pub total: u64,
+    /// Usage as a percentage of `total`
+    pub percent: f64,
+}
+
+impl StorageQuota {
+    fn new(used: u64, available: u64) -> Self {
+        let total = used.saturating_add(available);
+        let percent = if total == 0 { 0.0 } else { used as f64 * 100.0 / total as f64 };
+        Self { used, total, percent }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaWarning {
+    #[serde(flatten)]
+    pub quota: StorageQuota,
+    pub threshold_percent: u8,
+}
+
+async fn fetch(app: &AppHandle) -> Result<StorageQuota, CommandError> {
+    let quota = DavClient::for_app(app)?
+        .quota("/")
+        .await?
+        .ok_or_else(|| CommandError::WebDavError("The bridge does not report storage quota".into()))?;
+    Ok(StorageQuota::new(quota.used, quota.available))
+}
+
+/// Whether to warn now, given whether the previous check was already over
+/// the threshold.
+fn crossed(quota: &StorageQuota, threshold: u8, was_over: bool) -> (bool, bool) {
+    let over = threshold > 0 && quota.percent >= f64::from(threshold);
+    (over && !was_over, over)
+}
+
+/// Periodic quota check; spawned once from `setup`.
+pub async fn watch(app: AppHandle) {
+    tokio::time::sleep(INITIAL_DELAY).await;
+    let mut was_over = false;
+    loop {
+        let settings: QuotaSettings = read_config_section(CONFIG_KEY);
+        if settings.warning_percent > 0 {
+            match fetch(&app).await {
+                Ok(quota) => {
+                    let (warn, over) = crossed(&quota, settings.warning_percent, was_over);
+                    was_over = over;
+                    if warn {
+                        log::warn!("Proton Drive storage is {:.0}% full", quota.percent);
+                        crate::notifications::notify(
+                            &app,
+                            crate::notifications::NotificationCategory::LowStorage,
+                            "Proton Drive is almost full",
+                            &format!("{} of {} used ({:.0}%)", crate::notifications::human_size(quota.used), crate::notifications::human_size(quota.total), quota.percent),
+                        );
+                        let _ = app.emit("quota:warning", QuotaWarning { quota, threshold_percent: settings.warning_percent });
+                    }
+                }
+                Err(CommandError::ServerNotRunning) => {}
+                Err(e) => log::debug!("Quota check failed: {}", e),
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(settings.check_interval_minutes.max(1) * 60)).await;
+    }
+}
+
+#[tauri::command]
+pub async fn get_quota(app: AppHandle) -> Result<StorageQuota, CommandError> {
+    fetch(&app).await
+}
+
+#[tauri::command]
+pub async fn get_quota_settings() -> Result<QuotaSettings, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+/// The new interval applies after the next check.
+#[tauri::command]
+pub async fn set_quota_settings(settings: QuotaSettings) -> Result<QuotaSettings, CommandError> {
+    settings.validate()?;
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_percent() {
+        let quota = StorageQuota::new(1 << 30, 3 << 30);
+        assert_eq!(quota.total, 4 << 30);
+        assert_eq!(quota.percent, 25.0);
+        assert_eq!(StorageQuota::new(0, 0).percent, 0.0);
+    }
+
+    #[test]
+    fn test_warns_once_per_crossing() {
+        let full = StorageQuota::new(95, 5);
+        let low = StorageQuota::new(50, 50);
+        assert_eq!(crossed(&full, 90, false), (true, true));
+        assert_eq!(crossed(&full, 90, true), (false, true));
+        assert_eq!(crossed(&low, 90, true), (false, false));
+        assert_eq!(crossed(&full, 0, false), (false, false));
+    }
+}
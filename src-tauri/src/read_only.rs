@@ -0,0 +1,146 @@
+use hyper::Method;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::sidecar::{read_config_json, write_config_section, CommandError};
+
+// ============================================================================
+// Read-only share
+// ============================================================================
+//
+// For machines where the drive must never be modified, the gateway rejects
+// every WebDAV method that writes (uploads, deletes, moves, copies, new
+// folders, property changes) with 403 while reads keep working. The share is
+// also read-only while travel mode is on. Enforcement lives in the gateway
+// and in `DavClient`, whose writes bypass the gateway. A bridge started
+// outside the app runs without the gateway, so it is not adopted while the
+// share is read-only, and the share can't be made read-only while one is
+// adopted.
+
+const CONFIG_KEY: &str = "readOnly";
+
+#[derive(Default)]
+pub struct ReadOnlyState {
+    /// The `readOnly` setting
+    setting: AtomicBool,
+    /// Mirrors travel mode so the gateway need not read config.json
+    travel: AtomicBool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadOnlyStatus {
+    /// The `readOnly` setting
+    pub enabled: bool,
+    /// Whether travel mode currently forces the share read-only
+    pub travel_mode: bool,
+    /// Whether writes are rejected right now
+    pub effective: bool,
+}
+
+impl ReadOnlyState {
+    pub fn from_config() -> Self {
+        let enabled = read_config_json()
+            .ok()
+            .and_then(|v| v.get(CONFIG_KEY).and_then(|x| x.as_bool()))
+            .unwrap_or(false);
+        Self { setting: AtomicBool::new(enabled), travel: AtomicBool::new(crate::travel::is_enabled()) }
+    }
+
+    /// Pick up the `readOnly` setting after config.json was replaced.
+    pub fn reload_settings(&self) {
+        let enabled = read_config_json()
+            .ok()
+            .and_then(|v| v.get(CONFIG_KEY).and_then(|x| x.as_bool()))
+            .unwrap_or(false);
+        self.setting.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn set_travel(&self, enabled: bool) {
+        self.travel.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> ReadOnlyStatus {
+        let enabled = self.setting.load(Ordering::Relaxed);
+        let travel_mode = self.travel.load(Ordering::Relaxed);
+        ReadOnlyStatus { enabled, travel_mode, effective: enabled || travel_mode }
+    }
+
+    /// Whether the gateway must reject a request with `method`.
+    pub fn rejects(&self, method: &Method) -> bool {
+        writes(method) && self.status().effective
+    }
+
+    /// Fail unless the app may write to the drive now.
+    pub fn check_writable(&self) -> Result<(), CommandError> {
+        let status = self.status();
+        if status.travel_mode {
+            return Err(CommandError::TravelModeActive);
+        }
+        if status.effective {
+            return Err(CommandError::ReadOnlyShare);
+        }
+        Ok(())
+    }
+}
+
+/// WebDAV methods that change the drive. LOCK/UNLOCK are left alone since
+/// clients lock files they only open.
+pub(crate) fn writes(method: &Method) -> bool {
+    matches!(method.as_str(), "PUT" | "DELETE" | "MOVE" | "COPY" | "MKCOL" | "PROPPATCH" | "POST" | "PATCH")
+}
+
+#[tauri::command]
+pub async fn get_read_only(state: State<'_, ReadOnlyState>) -> Result<ReadOnlyStatus, CommandError> {
+    Ok(state.status())
+}
+
+/// Takes effect immediately for the gateway and persists for later launches.
+/// Fails to enable while an adopted bridge serves the share past the
+/// gateway.
+#[tauri::command]
+pub async fn set_read_only(app: AppHandle, state: State<'_, ReadOnlyState>, enabled: bool) -> Result<ReadOnlyStatus, CommandError> {
+    if enabled && crate::process::serving_adopted(&app).await {
+        return Err(CommandError::AdoptionRefused("read-only share".into()));
+    }
+    write_config_section(CONFIG_KEY, &enabled)?;
+    state.setting.store(enabled, Ordering::Relaxed);
+    log::info!("Read-only share {}", if enabled { "enabled" } else { "disabled" });
+    let status = state.status();
+    let _ = app.emit("read-only:changed", status.clone());
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_only_writes_when_effective() {
+        let state = ReadOnlyState::default();
+        assert!(!state.rejects(&Method::PUT));
+
+        state.setting.store(true, Ordering::Relaxed);
+        assert!(state.rejects(&Method::PUT));
+        assert!(state.rejects(&Method::DELETE));
+        assert!(state.rejects(&Method::from_bytes(b"MKCOL").unwrap()));
+        assert!(state.rejects(&Method::from_bytes(b"MOVE").unwrap()));
+        assert!(!state.rejects(&Method::GET));
+        assert!(!state.rejects(&Method::from_bytes(b"PROPFIND").unwrap()));
+        assert!(!state.rejects(&Method::from_bytes(b"LOCK").unwrap()));
+    }
+
+    #[test]
+    fn test_travel_mode_forces_read_only() {
+        let state = ReadOnlyState::default();
+        state.set_travel(true);
+        assert_eq!(state.status(), ReadOnlyStatus { enabled: false, travel_mode: true, effective: true });
+        assert!(state.rejects(&Method::PUT));
+        assert!(matches!(state.check_writable(), Err(CommandError::TravelModeActive)));
+        state.set_travel(false);
+        assert!(state.check_writable().is_ok());
+        state.setting.store(true, Ordering::Relaxed);
+        assert!(matches!(state.check_writable(), Err(CommandError::ReadOnlyShare)));
+    }
+}
@@ -0,0 +1,501 @@
+use bytes::Bytes;
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use hyper::header::{HeaderMap, HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, LAST_MODIFIED, RANGE};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::{Entry, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::State;
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Read-ahead
+// ============================================================================
+//
+// Players seek around a file with ranged GETs, and a mount that loses range
+// support ends up downloading the whole file for every seek. When read-ahead
+// is on, the gateway answers single-range GETs itself from a per-file window
+// of 1 MiB chunks fetched from the bridge with ranged requests of its own.
+// Seeks within the window are served from memory, and once reads look
+// sequential the chunks after the read position are prefetched so the next
+// request finds them ready. Whether the bridge honours ranges is verified
+// with the first fetch: if it answers with the whole file, read-ahead steps
+// aside and requests pass through untouched until it is reconfigured or the
+// bridge restarts. Windows are dropped when their file is written, moved or
+// deleted through the gateway.
+
+const CONFIG_KEY: &str = "readahead";
+
+const CHUNK_SIZE: u64 = 1024 * 1024;
+
+const MIN_WINDOW_MB: u32 = 2;
+
+const MAX_WINDOW_MB: u32 = 256;
+
+/// Files with a window at once; the least recently read one is dropped.
+const MAX_FILES: usize = 4;
+
+/// Requests the bridge must evaluate itself against the current file.
+const CONDITIONAL_HEADERS: &[&str] = &["if", "if-match", "if-none-match", "if-modified-since", "if-unmodified-since", "if-range"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReadaheadSettings {
+    pub enabled: bool,
+    /// Memory per file, in MiB; half of it is read ahead of the position
+    pub window_mb: u32,
+}
+
+impl Default for ReadaheadSettings {
+    fn default() -> Self {
+        Self { enabled: false, window_mb: 16 }
+    }
+}
+
+impl ReadaheadSettings {
+    fn validate(&self) -> Result<(), CommandError> {
+        if !(MIN_WINDOW_MB..=MAX_WINDOW_MB).contains(&self.window_mb) {
+            return Err(CommandError::InvalidReadaheadSettings(format!(
+                "window must be between {} and {} MB",
+                MIN_WINDOW_MB, MAX_WINDOW_MB
+            )));
+        }
+        Ok(())
+    }
+
+    fn window_chunks(&self) -> u64 {
+        u64::from(self.window_mb) * 1024 * 1024 / CHUNK_SIZE
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadaheadStatus {
+    pub settings: ReadaheadSettings,
+    /// Whether the bridge answered the last ranged fetch with a range;
+    /// `None` until read-ahead has fetched anything
+    pub upstream_ranges: Option<bool>,
+    /// Reads that found their chunk buffered or already being fetched
+    pub hits: u64,
+    pub misses: u64,
+    pub buffered_bytes: u64,
+}
+
+/// One chunk as the bridge returned it.
+#[derive(Clone)]
+struct Fetched {
+    data: Bytes,
+    /// Size of the whole file
+    total: u64,
+    /// Validators and type of the file, copied to responses
+    meta: Arc<HeaderMap>,
+}
+
+#[derive(Clone, Debug)]
+enum FetchError {
+    /// The bridge answered a ranged GET with the whole file
+    RangesIgnored,
+    Upstream(String),
+}
+
+type ChunkFuture = Shared<BoxFuture<'static, Result<Fetched, FetchError>>>;
+
+struct FileWindow {
+    chunks: HashMap<u64, ChunkFuture>,
+    /// Where the last read of the file ended, to recognise sequential reads
+    next_offset: u64,
+    last_used: Instant,
+}
+
+#[derive(Default)]
+struct Windows {
+    files: Mutex<HashMap<String, FileWindow>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Windows {
+    fn is_sequential(&self, path: &str, offset: u64) -> bool {
+        self.files.lock().unwrap().get(path).is_some_and(|f| f.next_offset == offset && offset > 0)
+    }
+
+    fn advance(&self, path: &str, offset: u64) {
+        if let Some(file) = self.files.lock().unwrap().get_mut(path) {
+            file.next_offset = offset;
+        }
+    }
+
+    fn forget(&self, path: &str) {
+        self.files.lock().unwrap().remove(path);
+    }
+}
+
+fn load_settings() -> ReadaheadSettings {
+    let settings: ReadaheadSettings = read_config_section(CONFIG_KEY);
+    if settings.validate().is_ok() {
+        settings
+    } else {
+        ReadaheadSettings::default()
+    }
+}
+
+pub struct ReadaheadState {
+    settings: Mutex<ReadaheadSettings>,
+    upstream_ranges: Mutex<Option<bool>>,
+    windows: Arc<Windows>,
+}
+
+/// A ranged GET answered by read-ahead, always `206 Partial Content`.
+pub(crate) struct RangedResponse {
+    pub headers: HeaderMap,
+    pub body: BoxStream<'static, std::io::Result<Bytes>>,
+}
+
+impl ReadaheadState {
+    /// Build the state from the settings persisted in `config.json`.
+    pub fn from_config() -> Self {
+        Self { settings: Mutex::new(load_settings()), upstream_ranges: Mutex::new(None), windows: Arc::default() }
+    }
+
+    /// Pick up the `readahead` section after config.json was replaced.
+    pub fn reload_settings(&self) {
+        *self.settings.lock().unwrap() = load_settings();
+        self.reset();
+    }
+
+    /// Drop every window and forget what was learnt about the bridge, for a
+    /// new run or new settings.
+    pub fn reset(&self) {
+        self.windows.files.lock().unwrap().clear();
+        *self.upstream_ranges.lock().unwrap() = None;
+    }
+
+    /// Drop the windows of `path` and anything below it.
+    pub fn invalidate(&self, path: &str) {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        self.windows.files.lock().unwrap().retain(|p, _| p != path && !p.starts_with(&prefix));
+    }
+
+    fn status(&self) -> ReadaheadStatus {
+        let buffered_bytes = self
+            .windows
+            .files
+            .lock()
+            .unwrap()
+            .values()
+            .flat_map(|f| f.chunks.values())
+            .filter_map(|c| c.peek().and_then(|r| r.as_ref().ok()).map(|f| f.data.len() as u64))
+            .sum();
+        ReadaheadStatus {
+            settings: *self.settings.lock().unwrap(),
+            upstream_ranges: *self.upstream_ranges.lock().unwrap(),
+            hits: self.windows.hits.load(Ordering::Relaxed),
+            misses: self.windows.misses.load(Ordering::Relaxed),
+            buffered_bytes,
+        }
+    }
+
+    /// Answer a GET for `path` from its window. `None` when read-ahead is
+    /// off or does not apply to the request, in which case the gateway
+    /// forwards it as usual. `headers` are the ones to send upstream.
+    pub(crate) async fn serve(&self, client: &reqwest::Client, url: String, path: &str, headers: &HeaderMap) -> Option<RangedResponse> {
+        let settings = *self.settings.lock().unwrap();
+        if !settings.enabled || *self.upstream_ranges.lock().unwrap() == Some(false) {
+            return None;
+        }
+        if CONDITIONAL_HEADERS.iter().any(|h| headers.contains_key(*h)) {
+            return None;
+        }
+        let (start, end) = parse_range(headers.get(RANGE)?.to_str().ok()?)?;
+
+        let mut upstream_headers = headers.clone();
+        upstream_headers.remove(RANGE);
+        let mut reader = Reader {
+            windows: self.windows.clone(),
+            client: client.clone(),
+            url,
+            headers: upstream_headers,
+            path: path.to_string(),
+            window: settings.window_chunks(),
+            sequential: self.windows.is_sequential(path, start),
+            total: None,
+        };
+        let first = match reader.request(start / CHUNK_SIZE).await {
+            Ok(first) => first,
+            Err(FetchError::RangesIgnored) => {
+                log::warn!("The WebDAV bridge ignores Range requests; read-ahead is bypassed");
+                *self.upstream_ranges.lock().unwrap() = Some(false);
+                self.windows.forget(path);
+                return None;
+            }
+            // Past the end, or the file is gone: the bridge answers that best
+            Err(FetchError::Upstream(e)) => {
+                log::debug!("Read-ahead for {} fell back to the bridge: {}", path, e);
+                self.windows.forget(path);
+                return None;
+            }
+        };
+        *self.upstream_ranges.lock().unwrap() = Some(true);
+        if start >= first.total {
+            return None;
+        }
+        let end = end.map_or(first.total - 1, |e| e.min(first.total - 1));
+        reader.total = Some(first.total);
+
+        let mut headers = HeaderMap::new();
+        for name in [ETAG, LAST_MODIFIED, CONTENT_TYPE] {
+            if let Some(value) = first.meta.get(&name) {
+                headers.insert(name, value.clone());
+            }
+        }
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(end - start + 1));
+        if let Ok(range) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, first.total)) {
+            headers.insert(CONTENT_RANGE, range);
+        }
+        Some(RangedResponse { headers, body: reader.into_body(first, start, end + 1) })
+    }
+}
+
+/// Reads one ranged request's chunks through the file's window.
+struct Reader {
+    windows: Arc<Windows>,
+    client: reqwest::Client,
+    url: String,
+    headers: HeaderMap,
+    path: String,
+    /// Window size in chunks
+    window: u64,
+    sequential: bool,
+    /// Size of the file, once the first chunk arrived
+    total: Option<u64>,
+}
+
+impl Reader {
+    /// The chunk at `index`, fetched unless the window holds it. Sequential
+    /// reads also start fetching the chunks ahead of it, and chunks that
+    /// fell out of the window are dropped.
+    fn request(&self, index: u64) -> ChunkFuture {
+        let ahead = match self.total {
+            Some(total) if self.sequential => (self.window / 2).min(((total - 1) / CHUNK_SIZE).saturating_sub(index)),
+            _ => 0,
+        };
+        let behind = self.window - self.window / 2;
+
+        let mut files = self.windows.files.lock().unwrap();
+        if !files.contains_key(&self.path) && files.len() >= MAX_FILES {
+            if let Some(oldest) = files.iter().min_by_key(|(_, f)| f.last_used).map(|(p, _)| p.clone()) {
+                files.remove(&oldest);
+            }
+        }
+        let file = files.entry(self.path.clone()).or_insert_with(|| FileWindow {
+            chunks: HashMap::new(),
+            next_offset: 0,
+            last_used: Instant::now(),
+        });
+        file.last_used = Instant::now();
+        file.chunks.retain(|&i, _| i + behind >= index && i <= index + ahead);
+
+        let counter = if file.chunks.contains_key(&index) { &self.windows.hits } else { &self.windows.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        for i in index + 1..=index + ahead {
+            if let Entry::Vacant(slot) = file.chunks.entry(i) {
+                let chunk = slot.insert(self.fetch(i)).clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = chunk.await;
+                });
+            }
+        }
+        file.chunks.entry(index).or_insert_with(|| self.fetch(index)).clone()
+    }
+
+    fn fetch(&self, index: u64) -> ChunkFuture {
+        let request = self
+            .client
+            .get(&self.url)
+            .headers(self.headers.clone())
+            .header(RANGE, format!("bytes={}-{}", index * CHUNK_SIZE, (index + 1) * CHUNK_SIZE - 1));
+        fetch_chunk(request, index * CHUNK_SIZE).boxed().shared()
+    }
+
+    /// Stream bytes `start..end` of the file, beginning with `first`.
+    fn into_body(self, first: Fetched, start: u64, end: u64) -> BoxStream<'static, std::io::Result<Bytes>> {
+        let etag = first.meta.get(ETAG).cloned();
+        futures_util::stream::unfold((self, start, Some(first)), move |(reader, pos, first)| {
+            let etag = etag.clone();
+            async move {
+                if pos >= end {
+                    return None;
+                }
+                let index = pos / CHUNK_SIZE;
+                let chunk = match first {
+                    Some(first) => Ok(first),
+                    None => reader.request(index).await,
+                };
+                let slice = chunk
+                    .map_err(|e| match e {
+                        FetchError::RangesIgnored => "the bridge stopped honouring ranges".to_string(),
+                        FetchError::Upstream(e) => e,
+                    })
+                    .and_then(|chunk| {
+                        if chunk.meta.get(ETAG) != etag.as_ref() {
+                            return Err("the file changed while it was read".to_string());
+                        }
+                        let offset = (pos - index * CHUNK_SIZE) as usize;
+                        let take = (chunk.data.len().saturating_sub(offset) as u64).min(end - pos) as usize;
+                        if take == 0 {
+                            return Err("the bridge returned a short chunk".to_string());
+                        }
+                        Ok(chunk.data.slice(offset..offset + take))
+                    });
+                match slice {
+                    Ok(slice) => {
+                        let pos = pos + slice.len() as u64;
+                        reader.windows.advance(&reader.path, pos);
+                        let mut reader = reader;
+                        reader.sequential = true;
+                        Some((Ok(slice), (reader, pos, None)))
+                    }
+                    Err(e) => {
+                        log::warn!("Read-ahead for {} failed: {}", reader.path, e);
+                        reader.windows.forget(&reader.path);
+                        Some((Err(std::io::Error::other(e)), (reader, end, None)))
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Fetch the chunk starting at `offset` and check the bridge answered with
+/// exactly that range.
+async fn fetch_chunk(request: reqwest::RequestBuilder, offset: u64) -> Result<Fetched, FetchError> {
+    let upstream = |e: reqwest::Error| FetchError::Upstream(e.to_string());
+    let resp = request.send().await.map_err(upstream)?;
+    match resp.status() {
+        StatusCode::PARTIAL_CONTENT => {}
+        StatusCode::OK => return Err(FetchError::RangesIgnored),
+        s => return Err(FetchError::Upstream(format!("ranged GET answered {}", s))),
+    }
+    let range = resp.headers().get(CONTENT_RANGE).and_then(|v| v.to_str().ok()).and_then(parse_content_range);
+    let Some((first, last, total)) = range.filter(|(first, _, _)| *first == offset) else {
+        return Err(FetchError::Upstream("ranged GET answered a different range".to_string()));
+    };
+    let mut meta = HeaderMap::new();
+    for name in [ETAG, LAST_MODIFIED, CONTENT_TYPE] {
+        if let Some(value) = resp.headers().get(&name) {
+            meta.insert(name, value.clone());
+        }
+    }
+    let data = resp.bytes().await.map_err(upstream)?;
+    if data.len() as u64 != last - first + 1 {
+        return Err(FetchError::Upstream("ranged GET body does not match its Content-Range".to_string()));
+    }
+    Ok(Fetched { data, total, meta: Arc::new(meta) })
+}
+
+/// The single range of a `Range` header, as `(first, last)` byte positions.
+/// Suffix and multi-part ranges are left to the bridge.
+fn parse_range(header: &str) -> Option<(u64, Option<u64>)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (first, last) = spec.split_once('-')?;
+    let first = first.trim().parse().ok()?;
+    let last = match last.trim() {
+        "" => None,
+        last => Some(last.parse().ok()?),
+    };
+    match last {
+        Some(last) if last < first => None,
+        _ => Some((first, last)),
+    }
+}
+
+/// `(first, last, total)` from a `Content-Range: bytes first-last/total`.
+fn parse_content_range(header: &str) -> Option<(u64, u64, u64)> {
+    let (range, total) = header.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (first, last) = range.split_once('-')?;
+    let (first, last, total) = (first.parse().ok()?, last.parse().ok()?, total.parse().ok()?);
+    (first <= last && last < total).then_some((first, last, total))
+}
+
+#[tauri::command]
+pub async fn get_readahead(state: State<'_, ReadaheadState>) -> Result<ReadaheadStatus, CommandError> {
+    Ok(state.status())
+}
+
+#[tauri::command]
+pub async fn set_readahead(state: State<'_, ReadaheadState>, enabled: bool, window_mb: u32) -> Result<ReadaheadStatus, CommandError> {
+    let settings = ReadaheadSettings { enabled, window_mb };
+    settings.validate()?;
+    write_config_section(CONFIG_KEY, &settings)?;
+    *state.settings.lock().unwrap() = settings;
+    state.reset();
+    log::info!("Read-ahead {} ({} MB window)", if enabled { "enabled" } else { "disabled" }, window_mb);
+    Ok(state.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ready_chunk(data: &'static [u8]) -> ChunkFuture {
+        let fetched = Fetched { data: Bytes::from_static(data), total: 10, meta: Arc::default() };
+        futures_util::future::ready(Ok(fetched)).boxed().shared()
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99"), Some((0, Some(99))));
+        assert_eq!(parse_range("bytes=1048576-"), Some((1048576, None)));
+        assert_eq!(parse_range("bytes=-500"), None);
+        assert_eq!(parse_range("bytes=0-1,5-9"), None);
+        assert_eq!(parse_range("bytes=9-1"), None);
+        assert_eq!(parse_range("items=0-1"), None);
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 0-1048575/5000000"), Some((0, 1048575, 5000000)));
+        assert_eq!(parse_content_range("bytes */5000000"), None);
+        assert_eq!(parse_content_range("bytes 10-20/15"), None);
+    }
+
+    #[test]
+    fn test_window_bounds() {
+        assert!(ReadaheadSettings { enabled: true, window_mb: 1 }.validate().is_err());
+        assert!(ReadaheadSettings { enabled: true, window_mb: 512 }.validate().is_err());
+        assert_eq!(ReadaheadSettings { enabled: true, window_mb: 16 }.window_chunks(), 16);
+    }
+
+    #[test]
+    fn test_invalidate_drops_path_and_descendants() {
+        let state = ReadaheadState {
+            settings: Mutex::new(ReadaheadSettings::default()),
+            upstream_ranges: Mutex::new(Some(true)),
+            windows: Arc::default(),
+        };
+        for path in ["/Videos/a.mkv", "/Videos/b.mkv", "/Videos2/c.mkv"] {
+            let chunks = HashMap::from([(0, ready_chunk(b"0123456789"))]);
+            let window = FileWindow { chunks, next_offset: 10, last_used: Instant::now() };
+            state.windows.files.lock().unwrap().insert(path.to_string(), window);
+        }
+        assert_eq!(state.status().buffered_bytes, 0);
+        let chunk = state.windows.files.lock().unwrap()["/Videos/a.mkv"].chunks[&0].clone();
+        assert!(tauri::async_runtime::block_on(chunk).is_ok());
+        assert_eq!(state.status().buffered_bytes, 10);
+        assert!(state.windows.is_sequential("/Videos/a.mkv", 10));
+
+        state.invalidate("/Videos");
+        let files = state.windows.files.lock().unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["/Videos2/c.mkv"]);
+    }
+}
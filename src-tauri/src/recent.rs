@@ -0,0 +1,211 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::dav::normalize_path;
+use crate::db;
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{CommandError, SidecarState};
+use crate::transfers::{TransferDirection, TransferFinishedEvent, TransferOutcome};
+
+// ============================================================================
+// Recent files
+// ============================================================================
+//
+// Files read or written through the bridge are remembered as recent files,
+// like a desktop's recent documents but for the drive: a completed download
+// through the gateway counts as opening the file, a completed upload
+// (streamed, or staged and uploaded in the background) as modifying it. Only
+// the latest use of a path is kept, up to `MAX_ENTRIES`, in the state
+// database. Editor lock files, backups and hidden files are left out, and
+// nothing is recorded in travel mode; enabling it or signing out clears the
+// list. `recent:updated` is emitted on every change, and the tray menu lists
+// the newest ones; Tauri has no jump list API, so on Windows the tray is the
+// shortcut too. Files are opened with the system's default application,
+// through the mount's local directory when GVFS has one.
+
+/// Files remembered; the oldest are dropped first.
+const MAX_ENTRIES: usize = 100;
+
+const DEFAULT_LIMIT: usize = 20;
+
+/// Seconds within which another use of the same kind is not recorded again;
+/// a file being read in ranges finishes many downloads.
+const REPEAT_INTERVAL: u64 = 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RecentUse {
+    Opened,
+    Modified,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentFile {
+    pub path: String,
+    /// How the file was last used
+    pub last_use: RecentUse,
+    /// Unix seconds
+    pub used_at: u64,
+}
+
+/// Whether `path` is a by-product of editing rather than a document: lock
+/// files, backups, temporary and hidden files.
+fn is_noise(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.is_empty()
+        || name.starts_with('.')
+        || name.starts_with("~$")
+        || name.ends_with('~')
+        || [".tmp", ".swp", ".part", ".crdownload"].iter().any(|ext| name.ends_with(ext))
+}
+
+/// Remember `file`, replacing an earlier use of the same path, and drop what
+/// no longer fits. Returns whether anything changed.
+fn store(conn: &Connection, file: &RecentFile) -> rusqlite::Result<bool> {
+    let previous: Option<String> = conn
+        .query_row("SELECT data FROM recent_files WHERE path = ?1", [&file.path], |row| row.get(0))
+        .optional()?;
+    if let Some(previous) = previous {
+        let previous: RecentFile = db::from_json(&previous)?;
+        if previous.last_use == file.last_use && file.used_at.saturating_sub(previous.used_at) < REPEAT_INTERVAL {
+            return Ok(false);
+        }
+    }
+    conn.execute(
+        "INSERT INTO recent_files (path, used_at, data) VALUES (?1, ?2, ?3)
+         ON CONFLICT (path) DO UPDATE SET used_at = excluded.used_at, data = excluded.data",
+        rusqlite::params![file.path, file.used_at as i64, db::to_json(file)?],
+    )?;
+    conn.execute(
+        "DELETE FROM recent_files WHERE path NOT IN
+         (SELECT path FROM recent_files ORDER BY used_at DESC, path LIMIT ?1)",
+        [MAX_ENTRIES as i64],
+    )?;
+    Ok(true)
+}
+
+/// The `limit` most recently used files, newest first.
+fn load(conn: &Connection, limit: usize) -> rusqlite::Result<Vec<RecentFile>> {
+    let mut stmt = conn.prepare("SELECT data FROM recent_files ORDER BY used_at DESC, path LIMIT ?1")?;
+    let files = stmt
+        .query_map([limit as i64], |row| row.get::<_, String>(0))?
+        .map(|json| db::from_json(&json?))
+        .collect();
+    files
+}
+
+/// Record a use of the file at `path`.
+pub(crate) fn record(app: &AppHandle, path: &str, last_use: RecentUse) {
+    let path = normalize_path(path);
+    if is_noise(&path) || app.state::<ReadOnlyState>().status().travel_mode {
+        return;
+    }
+    let file = RecentFile { path, last_use, used_at: crate::sidecar::unix_now() };
+    match db::with(app, |conn| store(conn, &file)) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            log::warn!("Failed to remember recent file {}: {}", file.path, e);
+            return;
+        }
+    }
+    let _ = app.emit("recent:updated", file);
+    crate::tray::refresh(app);
+}
+
+/// Record the file of a completed gateway transfer.
+pub(crate) fn transfer_finished(app: &AppHandle, event: &TransferFinishedEvent) {
+    if event.outcome != TransferOutcome::Completed {
+        return;
+    }
+    match event.direction {
+        TransferDirection::Download => record(app, &event.path, RecentUse::Opened),
+        TransferDirection::Upload => record(app, &event.path, RecentUse::Modified),
+        TransferDirection::Copy => {}
+    }
+}
+
+/// The most recently used files, newest first; empty if they cannot be read.
+pub(crate) fn recent(app: &AppHandle, limit: usize) -> Vec<RecentFile> {
+    db::with(app, |conn| load(conn, limit)).unwrap_or_else(|e| {
+        log::warn!("Failed to read recent files: {}", e);
+        Vec::new()
+    })
+}
+
+/// Forget all recent files, e.g. when the account signs out.
+pub(crate) fn clear(app: &AppHandle) {
+    if let Err(e) = db::with(app, |conn| conn.execute("DELETE FROM recent_files", []).map(|_| ())) {
+        log::warn!("Failed to clear recent files: {}", e);
+    }
+    crate::tray::refresh(app);
+}
+
+/// Open the file at `path` with its default application.
+pub(crate) async fn open(app: &AppHandle, state: State<'_, SidecarState>, path: &str) -> Result<(), CommandError> {
+    let location = crate::sidecar::locate_remote_path(app, state, path).await;
+    log::info!("Opening recent file {}", location);
+    app.opener().open_path(location, None::<&str>).map_err(|e| CommandError::Unknown(e.to_string()))
+}
+
+/// Recently opened or modified files, newest first (default 20, at most
+/// 100).
+#[tauri::command]
+pub async fn get_recent_files(app: AppHandle, limit: Option<usize>) -> Result<Vec<RecentFile>, CommandError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_ENTRIES);
+    db::with(&app, |conn| load(conn, limit))
+}
+
+#[tauri::command]
+pub async fn open_recent_file(app: AppHandle, state: State<'_, SidecarState>, path: String) -> Result<(), CommandError> {
+    open(&app, state, &path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn used(path: &str, used_at: u64) -> RecentFile {
+        RecentFile { path: path.into(), last_use: RecentUse::Opened, used_at }
+    }
+
+    #[test]
+    fn test_latest_use_per_path_newest_first() {
+        let conn = db::open_in_memory();
+        assert!(store(&conn, &used("/Docs/a.odt", 10)).unwrap());
+        assert!(store(&conn, &used("/Docs/b.odt", 20)).unwrap());
+        assert!(store(&conn, &RecentFile { last_use: RecentUse::Modified, ..used("/Docs/a.odt", 30) }).unwrap());
+        // Used the same way right after: nothing new
+        assert!(!store(&conn, &RecentFile { last_use: RecentUse::Modified, ..used("/Docs/a.odt", 40) }).unwrap());
+        let files = load(&conn, 10).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "/Docs/a.odt");
+        assert_eq!(files[0].last_use, RecentUse::Modified);
+        assert_eq!(load(&conn, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_oldest_dropped_beyond_limit() {
+        let conn = db::open_in_memory();
+        for i in 0..MAX_ENTRIES as u64 + 5 {
+            store(&conn, &used(&format!("/f{}", i), i)).unwrap();
+        }
+        let files = load(&conn, MAX_ENTRIES + 10).unwrap();
+        assert_eq!(files.len(), MAX_ENTRIES);
+        assert_eq!(files.last().unwrap().path, "/f5");
+    }
+
+    #[test]
+    fn test_editing_by_products_are_noise() {
+        assert!(is_noise("/Docs/.~lock.report.odt#"));
+        assert!(is_noise("/Docs/~$report.docx"));
+        assert!(is_noise("/Docs/report.odt~"));
+        assert!(is_noise("/Docs/.report.txt.swp"));
+        assert!(is_noise("/Docs/.hidden"));
+        assert!(!is_noise("/Docs/report.odt"));
+        assert!(!is_noise("/Docs/.config/report.odt"));
+    }
+}
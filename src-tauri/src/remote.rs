@@ -0,0 +1,481 @@
+use bytes::{Bytes, BytesMut};
+use futures_util::{Stream, TryStreamExt};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::cache::MetadataCache;
+use crate::dav::{join_path, normalize_path, parent_path, DavClient, DavEntry};
+use crate::integrity::{self, TransferDigest};
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{write_config_section, CommandError};
+use crate::transfer_concurrency::TransferConcurrencyState;
+use crate::transfers::{TransferDirection, TransferState, TransferTicket};
+
+// ============================================================================
+// Remote file operations
+// ============================================================================
+//
+// Commands that act on files in Proton Drive directly, without going through
+// the mount: copies inside the drive, and uploads and downloads of local
+// files. They use the WebDAV client in `dav` against the running bridge and
+// report progress through the transfer registry. Writes are refused while
+// the share is read-only or travel mode is on.
+
+/// How often long-running operations check whether they were cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTransferResult {
+    /// Where the file ended up: remote path for uploads, local for downloads
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CopyMethod {
+    /// The bridge copied the data itself (WebDAV COPY)
+    Server,
+    /// COPY is unsupported; files were streamed GET -> PUT through memory
+    Streamed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyResult {
+    pub method: CopyMethod,
+    /// Only counted for streamed copies
+    pub files_copied: u64,
+    pub bytes_copied: u64,
+}
+
+/// Reject copies of the root and copies into the source itself.
+fn validate_copy_paths(src: &str, dest: &str) -> Result<(), CommandError> {
+    if src == "/" || dest == "/" {
+        return Err(CommandError::InvalidRemotePath("Cannot copy to or from the drive root".into()));
+    }
+    if dest == src || dest.starts_with(&format!("{}/", src)) {
+        return Err(CommandError::InvalidRemotePath("Destination cannot be the source or inside it".into()));
+    }
+    Ok(())
+}
+
+async fn wait_for_cancel(ticket: &TransferTicket) {
+    while !ticket.is_cancelled() {
+        tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+    }
+}
+
+/// PUT `body` to `dest`, reporting bytes to `ticket` as they are sent.
+async fn put_metered<S>(
+    client: &DavClient,
+    dest: &str,
+    body: S,
+    size: Option<u64>,
+    ticket: &mut TransferTicket,
+) -> Result<u64, CommandError>
+where
+    S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+{
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<u64>();
+    let body = body.inspect_ok(move |chunk| {
+        let _ = tx.send(chunk.len() as u64);
+    });
+    let put = client.put(dest, body, size);
+    tokio::pin!(put);
+
+    let mut copied = 0;
+    let mut cancel_poll = tokio::time::interval(CANCEL_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            res = &mut put => {
+                while let Ok(n) = rx.try_recv() {
+                    ticket.add_bytes(n);
+                    copied += n;
+                }
+                res?;
+                return Ok(copied);
+            }
+            Some(n) = rx.recv() => {
+                ticket.add_bytes(n);
+                copied += n;
+            }
+            _ = cancel_poll.tick() => {
+                if ticket.is_cancelled() {
+                    return Err(CommandError::OperationCancelled);
+                }
+            }
+        }
+    }
+}
+
+/// Stream one file from `src` to `dest`, reporting bytes to `ticket`.
+async fn stream_file(
+    client: &DavClient,
+    src: &str,
+    dest: &str,
+    size: Option<u64>,
+    ticket: &mut TransferTicket,
+) -> Result<u64, CommandError> {
+    let resp = client.get(src).await?;
+    put_metered(client, dest, resp.bytes_stream().map_err(std::io::Error::other), size, ticket).await
+}
+
+/// Client-side copy used when the server does not implement COPY.
+async fn streamed_copy(
+    client: &DavClient,
+    src: &str,
+    dest: &str,
+    overwrite: bool,
+    ticket: &mut TransferTicket,
+) -> Result<CopyResult, CommandError> {
+    let root = client.stat(src).await?.ok_or_else(|| CommandError::RemotePathNotFound(src.to_string()))?;
+    if !overwrite && client.stat(dest).await?.is_some() {
+        return Err(CommandError::WebDavError(format!("{} already exists", dest)));
+    }
+
+    // Walk the source tree first so progress can show a total.
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    if root.is_dir {
+        dirs.push(dest.to_string());
+        let mut pending = vec![(src.to_string(), dest.to_string())];
+        while let Some((from, to)) = pending.pop() {
+            for entry in client.propfind(&from, 1).await? {
+                if entry.path == from {
+                    continue;
+                }
+                let target = join_path(&to, &entry.name);
+                if entry.is_dir {
+                    dirs.push(target.clone());
+                    pending.push((entry.path, target));
+                } else {
+                    files.push((entry.path, target, entry.size));
+                }
+            }
+        }
+    } else {
+        files.push((src.to_string(), dest.to_string(), root.size));
+    }
+    ticket.set_total_bytes(files.iter().filter_map(|(_, _, size)| *size).sum());
+
+    // Parents are always discovered before their children.
+    for dir in &dirs {
+        client.mkcol(dir).await?;
+    }
+    let mut bytes_copied = 0;
+    for (from, to, size) in &files {
+        bytes_copied += stream_file(client, from, to, *size, ticket).await?;
+    }
+
+    Ok(CopyResult { method: CopyMethod::Streamed, files_copied: files.len() as u64, bytes_copied })
+}
+
+async fn copy_with_fallback(
+    client: &DavClient,
+    src: &str,
+    dest: &str,
+    overwrite: bool,
+    ticket: &mut TransferTicket,
+) -> Result<CopyResult, CommandError> {
+    let honored = tokio::select! {
+        res = client.copy(src, dest, overwrite) => res?,
+        _ = wait_for_cancel(ticket) => return Err(CommandError::OperationCancelled),
+    };
+    if honored {
+        return Ok(CopyResult { method: CopyMethod::Server, files_copied: 0, bytes_copied: 0 });
+    }
+    log::info!("COPY not supported by the bridge, streaming {} -> {}", src, dest);
+    streamed_copy(client, src, dest, overwrite, ticket).await
+}
+
+/// Copy a file or folder within Proton Drive without routing the data
+/// through the mount. Uses WebDAV COPY so the bridge does the work, and falls
+/// back to streaming file by file when the server doesn't support it.
+/// Progress and cancellation go through the transfer registry.
+#[tauri::command]
+pub async fn server_side_copy(
+    app: AppHandle,
+    transfers: State<'_, TransferState>,
+    src: String,
+    dest: String,
+    overwrite: Option<bool>,
+) -> Result<CopyResult, CommandError> {
+    let src = normalize_path(&src);
+    let dest = normalize_path(&dest);
+    validate_copy_paths(&src, &dest)?;
+    app.state::<ReadOnlyState>().check_writable()?;
+    let client = DavClient::for_app(&app)?;
+
+    let mut ticket = transfers.begin(&app, &src, TransferDirection::Copy, None);
+    let result = copy_with_fallback(&client, &src, &dest, overwrite.unwrap_or(false), &mut ticket).await;
+    match &result {
+        Ok(_) => {
+            app.state::<MetadataCache>().invalidate(&dest);
+            ticket.complete()
+        }
+        Err(e) => ticket.fail(e.to_string()),
+    }
+    result
+}
+
+/// Subfolders of `path` (default `/`) on the running bridge, sorted by name,
+/// for picking the folder to expose.
+#[tauri::command]
+pub async fn list_remote_folders(app: AppHandle, path: Option<String>) -> Result<Vec<DavEntry>, CommandError> {
+    let path = normalize_path(path.as_deref().unwrap_or("/"));
+    let client = DavClient::for_app(&app)?;
+    let mut folders: Vec<DavEntry> = client
+        .propfind(&path, 1)
+        .await?
+        .into_iter()
+        .filter(|e| e.is_dir && e.path != path)
+        .collect();
+    folders.sort_by_key(|e| e.name.to_lowercase());
+    Ok(folders)
+}
+
+/// Metadata of a single file or folder.
+#[tauri::command]
+pub async fn stat_remote_file(app: AppHandle, path: String) -> Result<DavEntry, CommandError> {
+    let path = normalize_path(&path);
+    DavClient::for_app(&app)?.stat(&path).await?.ok_or(CommandError::RemotePathNotFound(path))
+}
+
+/// Create the folder `path`. Its parent must exist; an existing folder is
+/// returned as is.
+#[tauri::command]
+pub async fn create_remote_directory(app: AppHandle, path: String) -> Result<DavEntry, CommandError> {
+    let path = normalize_path(&path);
+    if path == "/" {
+        return Err(CommandError::InvalidRemotePath("Cannot create the drive root".into()));
+    }
+    app.state::<ReadOnlyState>().check_writable()?;
+    let client = DavClient::for_app(&app)?;
+    let parent = parent_path(&path).to_string();
+    match client.stat(&parent).await? {
+        Some(entry) if entry.is_dir => {}
+        Some(_) => return Err(CommandError::InvalidRemotePath(format!("{} is not a folder", parent))),
+        None => return Err(CommandError::RemotePathNotFound(parent)),
+    }
+
+    client.mkcol(&path).await?;
+    app.state::<MetadataCache>().invalidate(&path);
+    match client.stat(&path).await? {
+        Some(entry) if entry.is_dir => Ok(entry),
+        Some(_) => Err(CommandError::InvalidRemotePath(format!("{} already exists as a file", path))),
+        None => Err(CommandError::RemotePathNotFound(path)),
+    }
+}
+
+/// Contents of a local file as a byte stream of `chunk_size` byte chunks.
+pub(crate) fn local_file_stream(file: tokio::fs::File, chunk_size: usize) -> impl Stream<Item = std::io::Result<Bytes>> + Send + 'static {
+    futures_util::stream::try_unfold(file, move |mut file| async move {
+        let mut buf = BytesMut::with_capacity(chunk_size);
+        let n = file.read_buf(&mut buf).await?;
+        Ok((n > 0).then(|| (buf.freeze(), file)))
+    })
+}
+
+/// Where an upload of `file_name` to `remote` lands: inside `remote` when it
+/// is a folder, else `remote` itself.
+async fn upload_target(client: &DavClient, remote: &str, file_name: &str) -> Result<String, CommandError> {
+    Ok(match client.stat(remote).await? {
+        Some(entry) if entry.is_dir => join_path(remote, file_name),
+        _ => remote.to_string(),
+    })
+}
+
+/// Upload the local file `local` to `remote`, tracked in the transfer
+/// registry. Fails if the target exists unless `overwrite` is set.
+pub(crate) async fn upload_local_file(
+    app: &AppHandle,
+    client: &DavClient,
+    local: &Path,
+    remote: &str,
+    overwrite: bool,
+) -> Result<FileTransferResult, CommandError> {
+    let metadata = tokio::fs::metadata(local).await?;
+    if !metadata.is_file() {
+        return Err(CommandError::IoError(format!("{} is not a file", local.display())));
+    }
+    let file_name = local.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let target = upload_target(client, remote, &file_name).await?;
+    if target == "/" {
+        return Err(CommandError::InvalidRemotePath("Cannot upload to the drive root itself".into()));
+    }
+    match client.stat(&target).await? {
+        Some(entry) if entry.is_dir => return Err(CommandError::InvalidRemotePath(format!("{} is a folder", target))),
+        Some(_) if !overwrite => return Err(CommandError::WebDavError(format!("{} already exists", target))),
+        _ => {}
+    }
+
+    let concurrency = app.state::<TransferConcurrencyState>();
+    let _slot = concurrency.slot(TransferDirection::Upload).await;
+    let file = tokio::fs::File::open(local).await?;
+    let mut ticket = app.state::<TransferState>().begin(app, &target, TransferDirection::Upload, Some(metadata.len()));
+    let digest = integrity::enabled().then(TransferDigest::default);
+    let body = local_file_stream(file, concurrency.chunk_size());
+    let result = match &digest {
+        Some(digest) => put_metered(client, &target, digest.wrap(body), Some(metadata.len()), &mut ticket).await,
+        None => put_metered(client, &target, body, Some(metadata.len()), &mut ticket).await,
+    };
+    app.state::<MetadataCache>().invalidate(&target);
+    match result {
+        Ok(bytes) => {
+            ticket.complete();
+            if let Some(digest) = digest {
+                integrity::verify(app, client, integrity::Direction::Upload, &target, Some(local), bytes, digest).await;
+            }
+            Ok(FileTransferResult { path: target, bytes })
+        }
+        Err(e) => {
+            ticket.fail(e.to_string());
+            Err(e)
+        }
+    }
+}
+
+/// Write the body of `resp` to `dest`, reporting bytes to `ticket`.
+async fn write_metered(
+    resp: reqwest::Response,
+    dest: &Path,
+    ticket: &mut TransferTicket,
+    digest: Option<&TransferDigest>,
+) -> Result<u64, CommandError> {
+    let mut file = tokio::fs::File::create(dest).await?;
+    let mut stream = resp.bytes_stream();
+    let mut written = 0;
+    while let Some(chunk) = stream.try_next().await.map_err(|e| CommandError::WebDavError(e.to_string()))? {
+        if ticket.is_cancelled() {
+            return Err(CommandError::OperationCancelled);
+        }
+        file.write_all(&chunk).await?;
+        if let Some(digest) = digest {
+            digest.update(&chunk);
+        }
+        ticket.add_bytes(chunk.len() as u64);
+        written += chunk.len() as u64;
+    }
+    file.sync_all().await?;
+    Ok(written)
+}
+
+/// Upload a local file to Proton Drive through the bridge, without a mount.
+/// When `remote_path` is a folder the file keeps its name inside it.
+/// Progress and cancellation go through the transfer registry.
+#[tauri::command]
+pub async fn upload_file(
+    app: AppHandle,
+    local_path: String,
+    remote_path: String,
+    overwrite: Option<bool>,
+) -> Result<FileTransferResult, CommandError> {
+    app.state::<ReadOnlyState>().check_writable()?;
+    let client = DavClient::for_app(&app)?;
+    upload_local_file(&app, &client, Path::new(&local_path), &normalize_path(&remote_path), overwrite.unwrap_or(false)).await
+}
+
+/// Download `remote` to `local` through a `.part` file that replaces
+/// `local` only once complete, tracked in the transfer registry.
+pub(crate) async fn download_to_path(
+    app: &AppHandle,
+    client: &DavClient,
+    remote: &str,
+    size: Option<u64>,
+    local: &Path,
+) -> Result<u64, CommandError> {
+    let file_name = local.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let partial = local.with_file_name(format!("{}.part", file_name));
+
+    let _slot = app.state::<TransferConcurrencyState>().slot(TransferDirection::Download).await;
+    let mut ticket = app.state::<TransferState>().begin(app, remote, TransferDirection::Download, size);
+    let digest = integrity::enabled().then(TransferDigest::default);
+    let result = match client.get(remote).await {
+        Ok(resp) => write_metered(resp, &partial, &mut ticket, digest.as_ref()).await,
+        Err(e) => Err(e),
+    };
+    let result = match result {
+        Ok(bytes) => tokio::fs::rename(&partial, local).await.map(|_| bytes).map_err(CommandError::from),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(bytes) => {
+            ticket.complete();
+            if let Some(digest) = digest {
+                integrity::verify(app, client, integrity::Direction::Download, remote, Some(local), bytes, digest).await;
+            }
+            Ok(bytes)
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            ticket.fail(e.to_string());
+            Err(e)
+        }
+    }
+}
+
+/// Download a file from Proton Drive to `local_path`, or into it when it is
+/// a directory. Data goes to a `.part` file that replaces the target only
+/// once complete.
+#[tauri::command]
+pub async fn download_file(
+    app: AppHandle,
+    remote_path: String,
+    local_path: String,
+    overwrite: Option<bool>,
+) -> Result<FileTransferResult, CommandError> {
+    let remote = normalize_path(&remote_path);
+    let client = DavClient::for_app(&app)?;
+    let entry = client.stat(&remote).await?.ok_or_else(|| CommandError::RemotePathNotFound(remote.clone()))?;
+    if entry.is_dir {
+        return Err(CommandError::InvalidRemotePath(format!("{} is a folder", remote)));
+    }
+    let mut local = PathBuf::from(local_path);
+    if local.is_dir() {
+        local.push(&entry.name);
+    }
+    if local.exists() && !overwrite.unwrap_or(false) {
+        return Err(CommandError::IoError(format!("{} already exists", local.display())));
+    }
+    let bytes = download_to_path(&app, &client, &remote, entry.size, &local).await?;
+    Ok(FileTransferResult { path: local.display().to_string(), bytes })
+}
+
+/// Set the Proton Drive folder that is mounted (`remotePath` in config.json).
+/// When the bridge is running the folder must exist. Takes effect on the
+/// next mount.
+#[tauri::command]
+pub async fn set_remote_path(app: AppHandle, path: String) -> Result<String, CommandError> {
+    let path = normalize_path(&path);
+    if let Ok(client) = DavClient::for_app(&app) {
+        match client.stat(&path).await? {
+            Some(entry) if entry.is_dir => {}
+            Some(_) => return Err(CommandError::InvalidRemotePath(format!("{} is not a folder", path))),
+            None => return Err(CommandError::RemotePathNotFound(path)),
+        }
+    }
+
+    write_config_section("remotePath", &path)?;
+    log::info!("Remote path set to {}", path);
+    let _ = app.emit("remote-path:changed", path.clone());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_copy_paths() {
+        assert!(validate_copy_paths("/a", "/b").is_ok());
+        assert!(validate_copy_paths("/a", "/ab").is_ok());
+        assert!(validate_copy_paths("/a", "/a").is_err());
+        assert!(validate_copy_paths("/a", "/a/b").is_err());
+        assert!(validate_copy_paths("/", "/b").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::process::Command;
+use tauri_plugin_shell::ShellExt;
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Flatpak sandbox
+// ============================================================================
+//
+// Inside Flatpak the app cannot rely on host tools (`gio`, `xdg-open`) or on
+// GVFS being reachable, so opening and mounting locations goes through the
+// XDG desktop portal (OpenURI) and the FileManager1 D-Bus interface, which
+// hand the location to the host's file manager. The bundled bridge runs
+// inside the sandbox by default; with `sandbox.hostSidecar` set it is
+// started on the host through `flatpak-spawn --host` instead, with the
+// sandbox's XDG directories passed along so both sides share config.json.
+
+const CONFIG_KEY: &str = "sandbox";
+
+/// Name of the bundled sidecar, as declared in `tauri.conf.json`.
+const SIDECAR_NAME: &str = "proton-drive-webdav-bridge";
+
+#[cfg(target_os = "linux")]
+const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+#[cfg(target_os = "linux")]
+const PORTAL_OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+#[cfg(target_os = "linux")]
+const FILE_MANAGER_BUS_NAME: &str = "org.freedesktop.FileManager1";
+#[cfg(target_os = "linux")]
+const FILE_MANAGER_OBJECT_PATH: &str = "/org/freedesktop/FileManager1";
+
+/// Directories Flatpak redirects into `~/.var/app/<id>`, forwarded to a
+/// bridge running on the host.
+const FORWARDED_ENV: &[&str] = &["XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_STATE_HOME", "XDG_CACHE_HOME"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SandboxSettings {
+    /// Run the bridge on the host via `flatpak-spawn --host`
+    pub host_sidecar: bool,
+    /// Bridge executable on the host; `None` means the one on the host's PATH
+    pub host_sidecar_path: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxInfo {
+    pub flatpak: bool,
+    pub app_id: Option<String>,
+    #[serde(flatten)]
+    pub settings: SandboxSettings,
+}
+
+/// `[Application] name=` from the contents of `/.flatpak-info`.
+fn app_id_from_info(info: &str) -> Option<String> {
+    let mut in_application = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_application = line == "[Application]";
+        } else if in_application {
+            if let Some(name) = line.strip_prefix("name=") {
+                return Some(name.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Flatpak app id when running inside a Flatpak sandbox.
+fn flatpak_app_id() -> Option<&'static str> {
+    static APP_ID: OnceLock<Option<String>> = OnceLock::new();
+    APP_ID
+        .get_or_init(|| match std::fs::read_to_string("/.flatpak-info") {
+            Ok(info) => app_id_from_info(&info).or_else(|| std::env::var("FLATPAK_ID").ok()),
+            Err(_) => None,
+        })
+        .as_deref()
+}
+
+pub fn is_flatpak() -> bool {
+    flatpak_app_id().is_some()
+}
+
+/// Arguments for `flatpak-spawn` that start `program` on the host.
+fn host_spawn_args(program: &str, env: &[(String, String)]) -> Vec<String> {
+    let mut args = vec!["--host".to_string(), "--watch-bus".to_string()];
+    args.extend(env.iter().map(|(k, v)| format!("--env={}={}", k, v)));
+    args.push(program.to_string());
+    args
+}
+
+/// Command running the bridge CLI: the bundled sidecar (or its downloaded
+/// update), or the host's bridge when configured inside Flatpak. Either runs
+/// with the active account profile.
+pub fn sidecar_command(app: &AppHandle) -> Result<Command, CommandError> {
+    sidecar_command_with_env(app, Vec::new())
+}
+
+/// [`sidecar_command`] with extra environment variables, which have to be
+/// passed on the command line when the bridge runs on the host.
+pub fn sidecar_command_with_env(app: &AppHandle, mut env: Vec<(String, String)>) -> Result<Command, CommandError> {
+    let settings: SandboxSettings = read_config_section(CONFIG_KEY);
+    env.extend(crate::accounts::active_profile().map(|p| (crate::accounts::ACCOUNT_ENV.to_string(), p)));
+    env.extend(crate::proxy::env(app));
+    if !env.iter().any(|(k, _)| k == crate::trace::TRACE_ENV) {
+        env.extend(crate::trace::current().map(|id| (crate::trace::TRACE_ENV.to_string(), id)));
+    }
+    if is_flatpak() && settings.host_sidecar {
+        let mut host_env: Vec<(String, String)> = FORWARDED_ENV
+            .iter()
+            .filter_map(|k| std::env::var(k).ok().map(|v| (k.to_string(), v)))
+            .collect();
+        host_env.extend(env);
+        let program = settings.host_sidecar_path.as_deref().unwrap_or(SIDECAR_NAME);
+        return Ok(app.shell().command("flatpak-spawn").args(host_spawn_args(program, &host_env)));
+    }
+    #[cfg(feature = "mock-sidecar")]
+    if let Some(path) = std::env::var_os(crate::test_support::BINARY_ENV) {
+        return Ok(app.shell().command(path).envs(env));
+    }
+    // A bridge update downloaded by `updater` replaces the bundled binary
+    let command = match crate::updater::installed_binary(app) {
+        Some(path) => app.shell().command(path),
+        None => app.shell().sidecar(SIDECAR_NAME).map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?,
+    };
+    Ok(command.envs(env))
+}
+
+#[cfg(target_os = "linux")]
+fn dbus_call(bus_name: &str, object_path: &str, interface: &str, method: &str, args: glib::Variant) -> Result<(), CommandError> {
+    let connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>)
+        .map_err(|e| CommandError::GioError(format!("Session bus unavailable: {}", e)))?;
+    connection
+        .call_sync(
+            Some(bus_name),
+            object_path,
+            interface,
+            method,
+            Some(&args),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            None::<&gio::Cancellable>,
+        )
+        .map(|_| ())
+        .map_err(|e| CommandError::GioError(format!("{}.{} failed: {}", interface, method, e)))
+}
+
+/// Ask the desktop portal to open `uri` with the host's default handler; for
+/// `dav://` locations that is the file manager, which mounts them.
+#[cfg(target_os = "linux")]
+pub fn portal_open_uri(uri: &str) -> Result<(), CommandError> {
+    use glib::prelude::*;
+    use std::collections::HashMap;
+
+    let options: HashMap<String, glib::Variant> = HashMap::new();
+    dbus_call(
+        PORTAL_BUS_NAME,
+        PORTAL_OBJECT_PATH,
+        "org.freedesktop.portal.OpenURI",
+        "OpenURI",
+        ("", uri, options).to_variant(),
+    )
+}
+
+/// Show a folder in the host's file manager through FileManager1's
+/// `ShowFolders`. Fails when no FileManager1 implementation is reachable.
+#[cfg(target_os = "linux")]
+pub fn show_folders(uri: &str) -> Result<(), CommandError> {
+    use gio::prelude::*;
+
+    let uri = if uri.starts_with('/') {
+        gio::File::for_path(uri).uri().to_string()
+    } else {
+        uri.to_string()
+    };
+    dbus_call(
+        FILE_MANAGER_BUS_NAME,
+        FILE_MANAGER_OBJECT_PATH,
+        FILE_MANAGER_BUS_NAME,
+        "ShowFolders",
+        (vec![uri], "").to_variant(),
+    )
+}
+
+/// Open the folder containing `uri` in the file manager with the item
+/// selected, through FileManager1's `ShowItems`. Fails when no FileManager1
+/// implementation is reachable; there is no portal equivalent.
+#[cfg(target_os = "linux")]
+pub fn show_items(uri: &str) -> Result<(), CommandError> {
+    use gio::prelude::*;
+
+    let uri = if uri.starts_with('/') {
+        gio::File::for_path(uri).uri().to_string()
+    } else {
+        uri.to_string()
+    };
+    dbus_call(
+        FILE_MANAGER_BUS_NAME,
+        FILE_MANAGER_OBJECT_PATH,
+        FILE_MANAGER_BUS_NAME,
+        "ShowItems",
+        (vec![uri], "").to_variant(),
+    )
+}
+
+#[tauri::command]
+pub async fn get_sandbox_info() -> Result<SandboxInfo, CommandError> {
+    Ok(SandboxInfo {
+        flatpak: is_flatpak(),
+        app_id: flatpak_app_id().map(str::to_string),
+        settings: read_config_section(CONFIG_KEY),
+    })
+}
+
+/// Takes effect the next time the bridge is started.
+#[tauri::command]
+pub async fn set_sandbox_settings(app: AppHandle, settings: SandboxSettings) -> Result<SandboxSettings, CommandError> {
+    write_config_section(CONFIG_KEY, &settings)?;
+    // Requests may now go to a different bridge binary
+    app.state::<crate::versions::VersionState>().reset();
+    app.state::<crate::sidecar_client::SidecarClient>().reset();
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_id_from_flatpak_info() {
+        let info = "[Application]\nname=me.proletarius101.ProtonDriveWebdavBridge\nruntime=runtime/org.gnome.Platform/x86_64/47\n\n[Instance]\nname=other\n";
+        assert_eq!(app_id_from_info(info).as_deref(), Some("me.proletarius101.ProtonDriveWebdavBridge"));
+        assert_eq!(app_id_from_info("[Instance]\nname=other\n"), None);
+    }
+
+    #[test]
+    fn test_host_spawn_args_forward_env() {
+        let env = vec![("XDG_CONFIG_HOME".to_string(), "/home/u/.var/app/x/config".to_string())];
+        assert_eq!(
+            host_spawn_args("proton-drive-webdav-bridge", &env),
+            vec!["--host", "--watch-bus", "--env=XDG_CONFIG_HOME=/home/u/.var/app/x/config", "proton-drive-webdav-bridge"]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+use tauri::{AppHandle, State};
+
+use crate::cache::MetadataCache;
+use crate::dav::{normalize_path, DavClient, DavEntry};
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Remote search
+// ============================================================================
+//
+// The bridge has no search API, so `search_remote` crawls the folder tree
+// breadth-first with depth-1 PROPFINDs and matches entry names against the
+// query. Listings go through the metadata cache, so searching again shortly
+// afterwards (or after browsing the same folders) costs few requests. This is
+// much faster than GVFS search over a mount, which fetches far more per file.
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+/// Stop crawling after listing this many folders.
+const MAX_FOLDERS: usize = 5000;
+
+/// Lowercased terms of `query`; a name matches when it contains all of them.
+fn terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(str::to_lowercase).collect()
+}
+
+fn matches(name: &str, terms: &[String]) -> bool {
+    let name = name.to_lowercase();
+    terms.iter().all(|t| name.contains(t.as_str()))
+}
+
+/// Files and folders under `path` (default `/`) whose name contains every
+/// word of `query`, ignoring case. Shallower matches come first; at most
+/// `limit` (default 100, max 1000) are returned.
+#[tauri::command]
+pub async fn search_remote(
+    app: AppHandle,
+    cache: State<'_, MetadataCache>,
+    query: String,
+    path: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<DavEntry>, CommandError> {
+    let terms = terms(&query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let root = normalize_path(path.as_deref().unwrap_or("/"));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let client = DavClient::for_app(&app)?;
+
+    let mut pending = VecDeque::from([root.clone()]);
+    let mut results = Vec::new();
+    let mut listed = 0;
+    while let Some(dir) = pending.pop_front() {
+        if listed == MAX_FOLDERS {
+            log::info!("Search for {:?} stopped after {} folders", query, MAX_FOLDERS);
+            break;
+        }
+        listed += 1;
+        let entries = match client.propfind_cached(&cache, &dir, 1).await {
+            Ok(entries) => entries,
+            Err(e) if dir == root => return Err(e),
+            Err(e) => {
+                log::debug!("Skipping {} while searching: {}", dir, e);
+                continue;
+            }
+        };
+        if dir == root && entries.iter().any(|e| e.path == root && !e.is_dir) {
+            return Err(CommandError::InvalidRemotePath(format!("{} is not a folder", root)));
+        }
+        let mut children: Vec<DavEntry> = entries.into_iter().filter(|e| e.path != dir).collect();
+        children.sort_by_cached_key(|e| e.name.to_lowercase());
+        for entry in children {
+            if entry.is_dir {
+                pending.push_back(entry.path.clone());
+            }
+            if matches(&entry.name, &terms) {
+                results.push(entry);
+                if results.len() == limit {
+                    return Ok(results);
+                }
+            }
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_all_terms_ignoring_case() {
+        let terms = terms("  tax 2024 ");
+        assert_eq!(terms, vec!["tax", "2024"]);
+        assert!(matches("Tax-Return-2024.pdf", &terms));
+        assert!(!matches("Tax-Return-2023.pdf", &terms));
+        assert!(matches("anything", &[]));
+    }
+}
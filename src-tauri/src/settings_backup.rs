@@ -0,0 +1,424 @@
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::accounts::AccountsConfig;
+use crate::config_schema::ConfigProblem;
+use crate::sidecar::{get_config_file_path, read_config_json, update_config_json, CommandError, SidecarState};
+
+// ============================================================================
+// Settings backup
+// ============================================================================
+//
+// `export_settings` writes config.json to a single file that
+// `import_settings` can restore on another machine. The account list (the
+// `accounts` section) is only included on request; without it, the
+// importing machine keeps its own. Travel mode and the read-only setting
+// always stay as they are on the importing machine, and nothing is imported
+// while travel mode is on.
+//
+// Secrets never go into config.json, they live in the keyring: the proxy
+// password and, for each exported account, the bridge's session. They are
+// only exported when a passphrase is given, sealed with ChaCha20-Poly1305
+// under a key derived from the passphrase with Argon2id. Importing without
+// the passphrase restores the settings alone. Sessions the bridge keeps in
+// its encrypted file instead of the keyring are not exported; those accounts
+// sign in again.
+
+/// Marks a file as a settings backup.
+const FORMAT: &str = "proton-drive-webdav-bridge-settings";
+
+/// Layout written by this version.
+const FORMAT_VERSION: u64 = 1;
+
+const KEYRING_SERVICE: &str = "proton-drive-webdav-bridge";
+
+/// Keyring entry of the proxy password (see `proxy`).
+const PROXY_ENTRY: &str = "proxy";
+
+/// Keyring entry of the bridge's session for the default profile; other
+/// profiles append `:<profile>`, as `keyringAccountName` in keychain.ts does.
+const CREDENTIALS_ENTRY: &str = "proton-drive-webdav-bridge:credentials";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Backup {
+    format: String,
+    version: u64,
+    app_version: String,
+    /// Unix seconds
+    exported_at: u64,
+    config: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secrets: Option<SealedSecrets>,
+}
+
+/// Keyring secrets by entry name, encrypted as JSON.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SealedSecrets {
+    /// Argon2id parameters the key was derived with
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub path: String,
+    pub accounts: usize,
+    /// Keyring entries included
+    pub secrets: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub accounts: usize,
+    /// Keyring entries restored
+    pub secrets: Vec<String>,
+    /// The backup holds secrets but no passphrase was given
+    pub secrets_skipped: bool,
+    /// What `validate_config` reports for the imported settings
+    pub problems: Vec<ConfigProblem>,
+}
+
+fn invalid(message: impl Into<String>) -> CommandError {
+    CommandError::InvalidSettingsBackup(message.into())
+}
+
+fn credentials_entry(profile: &str) -> String {
+    if profile.is_empty() {
+        CREDENTIALS_ENTRY.to_string()
+    } else {
+        format!("{}:{}", CREDENTIALS_ENTRY, profile)
+    }
+}
+
+/// Keyring entries that belong with `config`.
+fn secret_entries(config: &Value) -> Vec<String> {
+    let mut entries = vec![PROXY_ENTRY.to_string()];
+    if let Some(accounts) = config.get("accounts").and_then(|a| AccountsConfig::deserialize(a).ok()) {
+        entries.extend(accounts.known.iter().map(|a| credentials_entry(&a.profile)));
+    }
+    entries.sort();
+    entries.dedup();
+    entries
+}
+
+fn account_count(config: &Value) -> usize {
+    config
+        .get("accounts")
+        .and_then(|a| AccountsConfig::deserialize(a).ok())
+        .map_or(0, |a| a.known.len())
+}
+
+fn keyring_error(e: keyring::Error) -> CommandError {
+    CommandError::IoError(format!("keyring: {}", e))
+}
+
+/// The entries of `names` that are set. Blocks on the keyring service.
+fn read_secrets(names: Vec<String>) -> Result<BTreeMap<String, String>, CommandError> {
+    let mut secrets = BTreeMap::new();
+    for name in names {
+        match keyring::Entry::new(KEYRING_SERVICE, &name).and_then(|e| e.get_password()) {
+            Ok(secret) => {
+                secrets.insert(name, secret);
+            }
+            Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(keyring_error(e)),
+        }
+    }
+    Ok(secrets)
+}
+
+/// Store `secrets` in the keyring. Blocks on the keyring service.
+fn write_secrets(secrets: BTreeMap<String, String>) -> Result<Vec<String>, CommandError> {
+    for (name, secret) in &secrets {
+        keyring::Entry::new(KEYRING_SERVICE, name)
+            .and_then(|e| e.set_password(secret))
+            .map_err(keyring_error)?;
+    }
+    Ok(secrets.into_keys().collect())
+}
+
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, CommandError> + Send + 'static) -> Result<T, CommandError> {
+    tauri::async_runtime::spawn_blocking(f).await.map_err(|e| CommandError::IoError(e.to_string()))?
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], m_cost: u32, t_cost: u32, p_cost: u32) -> Result<[u8; 32], CommandError> {
+    let params = argon2::Params::new(m_cost, t_cost, p_cost, Some(32)).map_err(|e| invalid(e.to_string()))?;
+    let argon2 = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| invalid(e.to_string()))?;
+    Ok(key)
+}
+
+fn seal(secrets: &BTreeMap<String, String>, passphrase: &str, params: &argon2::Params) -> Result<SealedSecrets, CommandError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let key = derive_key(passphrase, &salt, params.m_cost(), params.t_cost(), params.p_cost())?;
+    let plaintext = serde_json::to_vec(secrets).map_err(|e| CommandError::Unknown(e.to_string()))?;
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|e| CommandError::Unknown(e.to_string()))?;
+    let b64 = base64::engine::general_purpose::STANDARD;
+    Ok(SealedSecrets {
+        m_cost: params.m_cost(),
+        t_cost: params.t_cost(),
+        p_cost: params.p_cost(),
+        salt: b64.encode(salt),
+        nonce: b64.encode(nonce),
+        ciphertext: b64.encode(ciphertext),
+    })
+}
+
+fn open(sealed: &SealedSecrets, passphrase: &str) -> Result<BTreeMap<String, String>, CommandError> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let decode = |field: &str| b64.decode(field).map_err(|e| invalid(format!("secrets: {}", e)));
+    let (salt, nonce, ciphertext) = (decode(&sealed.salt)?, decode(&sealed.nonce)?, decode(&sealed.ciphertext)?);
+    if nonce.len() != NONCE_LEN {
+        return Err(invalid("secrets: bad nonce"));
+    }
+    let key = derive_key(passphrase, &salt, sealed.m_cost, sealed.t_cost, sealed.p_cost)?;
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| CommandError::InvalidPassphrase("Passphrase does not match".into()))?;
+    serde_json::from_slice(&plaintext).map_err(|e| invalid(format!("secrets: {}", e)))
+}
+
+/// Check that `json` is a backup this version can import.
+fn parse(json: &str) -> Result<Backup, CommandError> {
+    let backup: Backup = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    if backup.format != FORMAT {
+        return Err(invalid("Not a settings backup"));
+    }
+    if backup.version > FORMAT_VERSION {
+        return Err(invalid(format!("Written by a newer version (format {})", backup.version)));
+    }
+    if !backup.config.is_object() {
+        return Err(invalid("The settings must be a JSON object"));
+    }
+    Ok(backup)
+}
+
+/// Sections an import never changes.
+const LOCAL_SECTIONS: &[&str] = &["travelMode", "readOnly"];
+
+/// The settings to write on import: `imported`, brought up to the current
+/// config layout, with the local account list kept when it has none and
+/// the local travel mode and read-only settings kept always.
+fn merge_config(mut imported: Value, current: &Value) -> Value {
+    crate::config_schema::migrate_value(&mut imported);
+    if imported.get("accounts").is_none() {
+        if let Some(accounts) = current.get("accounts") {
+            imported["accounts"] = accounts.clone();
+        }
+    }
+    if let Some(object) = imported.as_object_mut() {
+        for key in LOCAL_SECTIONS {
+            match current.get(*key) {
+                Some(section) => object.insert(key.to_string(), section.clone()),
+                None => object.remove(*key),
+            };
+        }
+    }
+    imported
+}
+
+/// Rebuild what the app keeps in memory from config.json after an import,
+/// so the gateway enforces the new settings without a restart.
+fn reload_states(app: &AppHandle) {
+    app.state::<crate::read_only::ReadOnlyState>().reload_settings();
+    app.state::<crate::app_access::AppAccessState>().reload_settings();
+    app.state::<crate::bandwidth::BandwidthState>().reload_settings();
+    app.state::<crate::transfer_concurrency::TransferConcurrencyState>().reload_settings();
+    app.state::<crate::feature_flags::FeatureFlagState>().reload_settings();
+    app.state::<crate::cache::MetadataCache>().reload_settings();
+    app.state::<crate::readahead::ReadaheadState>().reload_settings();
+    app.state::<crate::upload_spool::UploadSpool>().reload_settings();
+    let sharing = app.state::<crate::network_sharing::NetworkSharingState>();
+    sharing.reload_settings();
+    if let Some(port) = crate::gateway::public_port(app) {
+        sharing.announce(port, app.state::<crate::tls::TlsState>().acceptor().is_some());
+    }
+    crate::status::invalidate(app);
+}
+
+fn passphrase_of(passphrase: Option<String>) -> Result<Option<String>, CommandError> {
+    match passphrase {
+        Some(p) if p.is_empty() => Err(CommandError::InvalidPassphrase("Passphrase must not be empty".into())),
+        other => Ok(other),
+    }
+}
+
+/// Write the app's settings to `path`. With a passphrase, keyring secrets
+/// are included, encrypted with it.
+#[tauri::command]
+pub async fn export_settings(path: String, include_accounts: bool, passphrase: Option<String>) -> Result<ExportSummary, CommandError> {
+    let passphrase = passphrase_of(passphrase)?;
+    let mut config = read_config_json()?;
+    if !config.is_object() {
+        return Err(CommandError::InvalidConfig("config.json must hold a JSON object".into()));
+    }
+    if !include_accounts {
+        config.as_object_mut().expect("checked above").remove("accounts");
+    }
+    let accounts = account_count(&config);
+
+    let (secrets, names) = match passphrase {
+        Some(passphrase) => {
+            let secrets = blocking({
+                let names = secret_entries(&config);
+                move || read_secrets(names)
+            })
+            .await?;
+            let names: Vec<String> = secrets.keys().cloned().collect();
+            let sealed = blocking(move || seal(&secrets, &passphrase, &argon2::Params::default())).await?;
+            (Some(sealed), names)
+        }
+        None => (None, Vec::new()),
+    };
+
+    let backup = Backup {
+        format: FORMAT.into(),
+        version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").into(),
+        exported_at: crate::sidecar::unix_now(),
+        config,
+        secrets,
+    };
+    let json = serde_json::to_string_pretty(&backup).map_err(|e| CommandError::Unknown(e.to_string()))?;
+    let path = PathBuf::from(path);
+    crate::config_schema::write_atomically(&path, json.as_bytes())?;
+    log::info!("Exported settings to {} ({} account(s), {} secret(s))", path.display(), accounts, names.len());
+    Ok(ExportSummary { path: path.display().to_string(), accounts, secrets: names })
+}
+
+/// Replace the app's settings with the backup at `path`. The bridge must be
+/// stopped and travel mode off; the previous config.json is kept as
+/// `config.json.pre-import.bak`.
+#[tauri::command]
+pub async fn import_settings(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    path: String,
+    passphrase: Option<String>,
+) -> Result<ImportSummary, CommandError> {
+    let passphrase = passphrase_of(passphrase)?;
+    if state.is_running().await {
+        return Err(CommandError::SidecarAlreadyRunning);
+    }
+    if app.state::<crate::read_only::ReadOnlyState>().status().travel_mode {
+        return Err(CommandError::TravelModeActive);
+    }
+    let json = std::fs::read_to_string(&path)?;
+    let mut backup = parse(&json)?;
+
+    // Decrypt first, so a wrong passphrase changes nothing
+    let sealed = backup.secrets.take();
+    let secrets_skipped = sealed.is_some() && passphrase.is_none();
+    let secrets = match (sealed, passphrase) {
+        (Some(sealed), Some(passphrase)) => Some(blocking(move || open(&sealed, &passphrase)).await?),
+        _ => None,
+    };
+
+    // Secrets go in before config.json, so a keyring failure leaves the
+    // previous settings in place
+    let restored = match secrets {
+        Some(secrets) => blocking(move || write_secrets(secrets)).await?,
+        None => Vec::new(),
+    };
+
+    let config_path = get_config_file_path()?;
+    let config = update_config_json(|config| {
+        *config = merge_config(backup.config, config);
+        if config_path.exists() {
+            let mut previous = config_path.clone().into_os_string();
+            previous.push(".pre-import.bak");
+            std::fs::copy(&config_path, &previous)?;
+        }
+        Ok(config.clone())
+    })?;
+    crate::proxy::reload_password(&app).await;
+    reload_states(&app);
+
+    let accounts = account_count(&config);
+    let problems = crate::config_schema::validate(&config);
+    log::info!("Imported settings from {} ({} secret(s) restored)", path, restored.len());
+    let _ = app.emit("settings:imported", ());
+    Ok(ImportSummary { accounts, secrets: restored, secrets_skipped, problems })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sealed_secrets_need_the_passphrase() {
+        let params = argon2::Params::new(1024, 1, 1, Some(32)).unwrap();
+        let secrets = BTreeMap::from([(PROXY_ENTRY.to_string(), "hunter2".to_string())]);
+        let sealed = seal(&secrets, "correct horse", &params).unwrap();
+        assert!(!sealed.ciphertext.contains("hunter2"));
+        assert_eq!(open(&sealed, "correct horse").unwrap(), secrets);
+        assert!(matches!(open(&sealed, "wrong"), Err(CommandError::InvalidPassphrase(_))));
+    }
+
+    #[test]
+    fn test_import_keeps_local_accounts_and_migrates() {
+        let backup = json!({ "format": FORMAT, "version": 1, "appVersion": "0.1.0", "exportedAt": 0, "config": { "autoStart": "true" } });
+        let backup = parse(&backup.to_string()).unwrap();
+        let current = json!({ "accounts": { "active": "a@proton.me", "known": [] } });
+        let config = merge_config(backup.config, &current);
+        assert_eq!(config["autoStart"], json!(true));
+        assert_eq!(config["accounts"], current["accounts"]);
+
+        assert!(parse(r#"{"format":"other","version":1,"appVersion":"","exportedAt":0,"config":{}}"#).is_err());
+        let newer = json!({ "format": FORMAT, "version": FORMAT_VERSION + 1, "appVersion": "", "exportedAt": 0, "config": {} });
+        assert!(matches!(parse(&newer.to_string()), Err(CommandError::InvalidSettingsBackup(_))));
+    }
+
+    #[test]
+    fn test_import_keeps_local_travel_mode_and_read_only() {
+        let imported = json!({ "travelMode": { "enabled": false }, "readOnly": false, "autoStart": true });
+        let current = json!({ "travelMode": { "enabled": true, "passphraseHash": "x" }, "readOnly": true });
+        let config = merge_config(imported, &current);
+        assert_eq!(config["travelMode"], current["travelMode"]);
+        assert_eq!(config["readOnly"], json!(true));
+
+        let config = merge_config(json!({ "travelMode": { "enabled": true }, "readOnly": true }), &json!({}));
+        assert!(config.get("travelMode").is_none());
+        assert!(config.get("readOnly").is_none());
+    }
+
+    #[test]
+    fn test_secret_entries_follow_account_profiles() {
+        let config = json!({ "accounts": { "known": [
+            { "id": "a", "profile": "" },
+            { "id": "b", "profile": "b1" },
+            { "id": "c", "profile": "" },
+        ] } });
+        assert_eq!(
+            secret_entries(&config),
+            vec![CREDENTIALS_ENTRY.to_string(), format!("{}:b1", CREDENTIALS_ENTRY), PROXY_ENTRY.to_string()]
+        );
+    }
+}
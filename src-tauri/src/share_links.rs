@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::dav::normalize_path;
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{parse_json_output, sidecar_output, CommandError};
+
+// ============================================================================
+// Share links
+// ============================================================================
+//
+// Public links are a Proton Drive feature WebDAV knows nothing about, so they
+// are made through the sidecar's `share create` and `share revoke`
+// subcommands. A link may expire and may be protected by a password; the
+// password is handed to the sidecar through its environment rather than the
+// command line, where other users could read it from the process list.
+// Publishing a link writes to the drive, so it is refused in travel mode and
+// on a read-only share; revoking one is always allowed.
+
+const SHARE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Environment variable the sidecar reads the link password from.
+const PASSWORD_ENV: &str = "SHARE_LINK_PASSWORD";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareLink {
+    /// Passed to `revoke_share_link`
+    pub id: String,
+    pub url: String,
+    /// ISO 8601 expiry, if the link expires
+    pub expiration_time: Option<String>,
+    pub has_password: bool,
+}
+
+fn validate(remote_path: &str, expiry: Option<u64>, password: Option<&str>, now: u64) -> Result<(), CommandError> {
+    if remote_path == "/" {
+        return Err(CommandError::InvalidShareLink("The drive root cannot be shared".into()));
+    }
+    if expiry.is_some_and(|e| e <= now) {
+        return Err(CommandError::InvalidShareLink("Expiry must be in the future".into()));
+    }
+    if password.is_some_and(|p| p.is_empty()) {
+        return Err(CommandError::InvalidShareLink("Password must not be empty".into()));
+    }
+    Ok(())
+}
+
+/// Create a public link to `remote_path`, optionally expiring at `expiry`
+/// (Unix timestamp, seconds) and protected by `password`. Sharing an item
+/// that already has a link updates that link.
+#[tauri::command]
+pub async fn create_share_link(app: AppHandle, remote_path: String, expiry: Option<u64>, password: Option<String>) -> Result<ShareLink, CommandError> {
+    app.state::<ReadOnlyState>().check_writable()?;
+    let remote_path = normalize_path(&remote_path);
+    validate(&remote_path, expiry, password.as_deref(), crate::sidecar::unix_now())?;
+
+    let mut args = vec!["share".to_string(), "create".to_string(), remote_path.clone(), "--json".to_string()];
+    if let Some(expiry) = expiry {
+        args.extend(["--expires".to_string(), expiry.to_string()]);
+    }
+    let stdout = crate::trace::traced(&app, "share create", async {
+        let mut command = crate::sandbox::sidecar_command(&app)?.args(args);
+        if let Some(password) = password {
+            command = command.env(PASSWORD_ENV, password);
+        }
+        sidecar_output(&app, command, "share create", SHARE_TIMEOUT).await
+    })
+    .await?;
+    let link: ShareLink = parse_json_output(&stdout, "share link")?;
+    log::info!("Created share link for {}", remote_path);
+    Ok(link)
+}
+
+/// Remove a public link made with `create_share_link`.
+#[tauri::command]
+pub async fn revoke_share_link(app: AppHandle, id: String) -> Result<(), CommandError> {
+    crate::trace::traced(&app, "share revoke", async {
+        let command = crate::sandbox::sidecar_command(&app)?.args(["share", "revoke", &id]);
+        sidecar_output(&app, command, "share revoke", SHARE_TIMEOUT).await
+    })
+    .await?;
+    log::info!("Revoked share link {}", id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("/Docs/a.pdf", None, None, 100).is_ok());
+        assert!(validate("/Docs/a.pdf", Some(200), Some("secret"), 100).is_ok());
+        assert!(validate("/", None, None, 100).is_err());
+        assert!(validate("/Docs/a.pdf", Some(100), None, 100).is_err());
+        assert!(validate("/Docs/a.pdf", None, Some(""), 100).is_err());
+    }
+}
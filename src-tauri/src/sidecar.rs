@@ -1,8 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter, State};
-use tauri_plugin_shell::ShellExt;
-use tauri_plugin_opener::OpenerExt;
+use tauri::{AppHandle, Emitter, Manager, State};
 use thiserror::Error;
 
 // GIO prelude brings methods like `mounts`, `root`, `uri`, etc. into scope
@@ -55,6 +52,165 @@ pub enum CommandError {
     #[error("IO error: {0}")]
     IoError(String),
 
+    #[error("Invalid passphrase: {0}")]
+    InvalidPassphrase(String),
+
+    #[error("Travel mode is active")]
+    TravelModeActive,
+
+    #[error("Transfer not found: {0}")]
+    TransferNotFound(u64),
+
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
+    #[error("WebDAV error: {0}")]
+    WebDavError(String),
+
+    #[error("Remote path not found: {0}")]
+    RemotePathNotFound(String),
+
+    #[error("Invalid remote path: {0}")]
+    InvalidRemotePath(String),
+
+    #[error("Operation cancelled")]
+    OperationCancelled,
+
+    #[error("Invalid network sharing settings: {0}")]
+    InvalidSharingConfig(String),
+
+    #[error("Invalid onboarding step: {0}")]
+    InvalidOnboardingStep(String),
+
+    #[error("Mount not found: {0}")]
+    MountNotFound(String),
+
+    #[error("Invalid mount settings: {0}")]
+    InvalidMountSettings(String),
+
+    #[error("Account not found: {0}")]
+    AccountNotFound(String),
+
+    #[error("Invalid quota settings: {0}")]
+    InvalidQuotaSettings(String),
+
+    #[error("Invalid cache policy: {0}")]
+    InvalidCachePolicy(String),
+
+    #[error("Feature disabled: {0}")]
+    FeatureDisabled(String),
+
+    #[error("Sync pair not found: {0}")]
+    SyncPairNotFound(String),
+
+    #[error("Invalid sync pair: {0}")]
+    InvalidSyncPair(String),
+
+    #[error("Invalid drop folder: {0}")]
+    InvalidDropFolder(String),
+
+    #[error("Conflict not found: {0}")]
+    ConflictNotFound(u64),
+
+    #[error("Invalid share link: {0}")]
+    InvalidShareLink(String),
+
+    #[error("Invalid WebDAV credentials: {0}")]
+    InvalidWebdavCredentials(String),
+
+    #[error("Invalid transport: {0}")]
+    InvalidTransport(String),
+
+    #[error("Invalid bind address: {0}")]
+    InvalidBindAddress(String),
+
+    #[error("Unreadable bridge status: {0}")]
+    StatusParseError(Box<crate::status_schema::StatusParseError>),
+
+    #[error("Incompatible bridge version: {0}")]
+    IncompatibleSidecar(String),
+
+    #[error("Bridge update failed: {0}")]
+    SidecarUpdateFailed(String),
+
+    #[error("App update failed: {0}")]
+    AppUpdateFailed(String),
+
+    #[error("Crash report not found: {0}")]
+    CrashReportNotFound(String),
+
+    #[error("Invalid proxy settings: {0}")]
+    InvalidProxyConfig(String),
+
+    #[error("Invalid benchmark size: {0}")]
+    InvalidBenchmarkSize(String),
+
+    #[error("Unsupported language: {0}")]
+    UnsupportedLocale(String),
+
+    #[error("Another operation is in progress: {0}")]
+    LifecycleBusy(String),
+
+    #[error("Command timed out: {0}")]
+    CommandTimedOut(String),
+
+    #[error("Command not found: {0}")]
+    CommandNotFound(u64),
+
+    #[error("Invalid command timeout: {0}")]
+    InvalidCommandTimeout(String),
+
+    #[error("Invalid read-ahead settings: {0}")]
+    InvalidReadaheadSettings(String),
+
+    #[error("No pending upload with id {0}")]
+    PendingUploadNotFound(u64),
+
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    #[error("Invalid settings backup: {0}")]
+    InvalidSettingsBackup(String),
+
+    #[error("Invalid idle policy: {0}")]
+    InvalidIdlePolicy(String),
+
+    #[error("Invalid app access settings: {0}")]
+    InvalidAppAccess(String),
+
+    #[error("No file manager could open the location: {0}")]
+    NoFileManager(String),
+
+    #[error("No thumbnail: {0}")]
+    ThumbnailUnavailable(String),
+
+    #[error("Invalid backup folder: {0}")]
+    InvalidBackupFolder(String),
+
+    #[error("Backup job not found: {0}")]
+    BackupJobNotFound(String),
+
+    #[error("Invalid backup job: {0}")]
+    InvalidBackupJob(String),
+
+    #[error("Invalid ignore pattern: {0}")]
+    InvalidIgnorePattern(String),
+
+    #[error("Listing expired: {0}")]
+    ListingExpired(String),
+
+    #[error("A bridge started outside the app is running, and it would bypass: {0}. Stop it first.")]
+    AdoptionRefused(String),
+
+    #[error("Too many wrong passphrases; seconds until the next attempt: {0}")]
+    TravelModeLocked(u64),
+
+    #[error("The share is read-only")]
+    ReadOnlyShare,
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
@@ -76,6 +232,59 @@ impl CommandError {
             CommandError::ServerNotRunning => "SERVER_NOT_RUNNING",
             CommandError::GioError(_) => "GIO_ERROR",
             CommandError::IoError(_) => "IO_ERROR",
+            CommandError::InvalidPassphrase(_) => "INVALID_PASSPHRASE",
+            CommandError::TravelModeActive => "TRAVEL_MODE_ACTIVE",
+            CommandError::TransferNotFound(_) => "TRANSFER_NOT_FOUND",
+            CommandError::TlsError(_) => "TLS_ERROR",
+            CommandError::WebDavError(_) => "WEBDAV_ERROR",
+            CommandError::RemotePathNotFound(_) => "REMOTE_PATH_NOT_FOUND",
+            CommandError::InvalidRemotePath(_) => "INVALID_REMOTE_PATH",
+            CommandError::OperationCancelled => "OPERATION_CANCELLED",
+            CommandError::InvalidSharingConfig(_) => "INVALID_SHARING_CONFIG",
+            CommandError::InvalidOnboardingStep(_) => "INVALID_ONBOARDING_STEP",
+            CommandError::MountNotFound(_) => "MOUNT_NOT_FOUND",
+            CommandError::InvalidMountSettings(_) => "INVALID_MOUNT_SETTINGS",
+            CommandError::AccountNotFound(_) => "ACCOUNT_NOT_FOUND",
+            CommandError::InvalidQuotaSettings(_) => "INVALID_QUOTA_SETTINGS",
+            CommandError::InvalidCachePolicy(_) => "INVALID_CACHE_POLICY",
+            CommandError::FeatureDisabled(_) => "FEATURE_DISABLED",
+            CommandError::SyncPairNotFound(_) => "SYNC_PAIR_NOT_FOUND",
+            CommandError::InvalidSyncPair(_) => "INVALID_SYNC_PAIR",
+            CommandError::InvalidDropFolder(_) => "INVALID_DROP_FOLDER",
+            CommandError::ConflictNotFound(_) => "CONFLICT_NOT_FOUND",
+            CommandError::InvalidShareLink(_) => "INVALID_SHARE_LINK",
+            CommandError::InvalidWebdavCredentials(_) => "INVALID_WEBDAV_CREDENTIALS",
+            CommandError::InvalidTransport(_) => "INVALID_TRANSPORT",
+            CommandError::InvalidBindAddress(_) => "INVALID_BIND_ADDRESS",
+            CommandError::StatusParseError(_) => "STATUS_PARSE_ERROR",
+            CommandError::IncompatibleSidecar(_) => "INCOMPATIBLE_SIDECAR",
+            CommandError::SidecarUpdateFailed(_) => "SIDECAR_UPDATE_FAILED",
+            CommandError::AppUpdateFailed(_) => "APP_UPDATE_FAILED",
+            CommandError::CrashReportNotFound(_) => "CRASH_REPORT_NOT_FOUND",
+            CommandError::InvalidProxyConfig(_) => "INVALID_PROXY_CONFIG",
+            CommandError::InvalidBenchmarkSize(_) => "INVALID_BENCHMARK_SIZE",
+            CommandError::UnsupportedLocale(_) => "UNSUPPORTED_LOCALE",
+            CommandError::LifecycleBusy(_) => "LIFECYCLE_BUSY",
+            CommandError::CommandTimedOut(_) => "COMMAND_TIMED_OUT",
+            CommandError::CommandNotFound(_) => "COMMAND_NOT_FOUND",
+            CommandError::InvalidCommandTimeout(_) => "INVALID_COMMAND_TIMEOUT",
+            CommandError::InvalidReadaheadSettings(_) => "INVALID_READAHEAD_SETTINGS",
+            CommandError::PendingUploadNotFound(_) => "PENDING_UPLOAD_NOT_FOUND",
+            CommandError::DatabaseError(_) => "DATABASE_ERROR",
+            CommandError::InvalidConfig(_) => "INVALID_CONFIG",
+            CommandError::InvalidSettingsBackup(_) => "INVALID_SETTINGS_BACKUP",
+            CommandError::InvalidIdlePolicy(_) => "INVALID_IDLE_POLICY",
+            CommandError::InvalidAppAccess(_) => "INVALID_APP_ACCESS",
+            CommandError::NoFileManager(_) => "NO_FILE_MANAGER",
+            CommandError::ThumbnailUnavailable(_) => "THUMBNAIL_UNAVAILABLE",
+            CommandError::InvalidBackupFolder(_) => "INVALID_BACKUP_FOLDER",
+            CommandError::BackupJobNotFound(_) => "BACKUP_JOB_NOT_FOUND",
+            CommandError::InvalidBackupJob(_) => "INVALID_BACKUP_JOB",
+            CommandError::InvalidIgnorePattern(_) => "INVALID_IGNORE_PATTERN",
+            CommandError::ListingExpired(_) => "LISTING_EXPIRED",
+            CommandError::AdoptionRefused(_) => "ADOPTION_REFUSED",
+            CommandError::TravelModeLocked(_) => "TRAVEL_MODE_LOCKED",
+            CommandError::ReadOnlyShare => "READ_ONLY_SHARE",
             CommandError::Unknown(_) => "UNKNOWN_ERROR",
         }
     }
@@ -88,32 +297,38 @@ impl From<std::io::Error> for CommandError {
     }
 }
 
-// Custom serialize to include error code in JSON response
+impl From<rusqlite::Error> for CommandError {
+    fn from(err: rusqlite::Error) -> Self {
+        CommandError::DatabaseError(err.to_string())
+    }
+}
+
+// Custom serialize to include error code in JSON response; the message is
+// in the active language (see `i18n.rs`)
 impl Serialize for CommandError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("CommandError", 2)?;
+        // An unreadable status also carries the payload, for the UI to show
+        let details = match self {
+            CommandError::StatusParseError(e) => Some(e),
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("CommandError", 2 + usize::from(details.is_some()))?;
         state.serialize_field("code", self.code())?;
-        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("message", &crate::i18n::error_message(self))?;
+        if let Some(details) = details {
+            state.serialize_field("details", details)?;
+        }
         state.end()
     }
 } 
 
-#[derive(Default)]
-pub struct SidecarState {
-    pid: Arc<Mutex<Option<u32>>>,
-}
-
-impl SidecarState {
-    pub fn new() -> Self {
-        Self::default()
-    }
-}
+pub use crate::supervisor::SidecarState;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct StatusResponse {
     pub server: ServerStatus,
@@ -121,17 +336,26 @@ pub struct StatusResponse {
     pub config: ConfigStatus,
     #[serde(rename = "logFile")]
     pub log_file: String,
+    /// Certificate served by the gateway when HTTPS is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<crate::tls::CertificateInfo>,
+    /// Set when the drive is reachable from the network without a login
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_warning: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerStatus {
     pub running: bool,
     pub pid: Option<u32>,
     pub url: Option<String>,
+    /// Started outside the app and adopted; served without the gateway
+    #[serde(default)]
+    pub adopted: bool,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthStatus {
     #[serde(rename = "loggedIn")]
@@ -139,7 +363,7 @@ pub struct AuthStatus {
     pub username: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigStatus {
     pub webdav: WebdavConfig,
@@ -151,7 +375,7 @@ pub struct ConfigStatus {
     pub auto_start: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WebdavConfig {
     pub host: String,
@@ -164,7 +388,7 @@ pub struct WebdavConfig {
     pub password_hash: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CacheConfig {
     pub enabled: bool,
@@ -191,47 +415,132 @@ pub struct AccountInfo {
     pub status: Option<String>,
 }
 
+/// Host and port the WebDAV share is exposed on, as configured in
+/// `config.json` (defaults mirror the sidecar's).
+pub(crate) fn configured_listen_addr() -> (String, u16) {
+    let v = read_config_json().unwrap_or_else(|_| serde_json::json!({}));
+    let webdav = v.get("webdav");
+    let host = webdav
+        .and_then(|w| w.get("host"))
+        .and_then(|h| h.as_str())
+        .unwrap_or("127.0.0.1")
+        .to_string();
+    let port = webdav
+        .and_then(|w| w.get("port"))
+        .and_then(|p| p.as_u64())
+        .and_then(|p| u16::try_from(p).ok())
+        .unwrap_or(8080);
+    (host, port)
+}
+
 #[tauri::command]
 pub async fn start_sidecar(
     app: AppHandle,
     state: State<'_, SidecarState>,
     port: Option<u16>,
 ) -> Result<u32, CommandError> {
-    let mut lock = state.pid.lock().unwrap();
-    if lock.is_some() {
+    let start = crate::lifecycle::guarded(&app, crate::lifecycle::Operation::Start, start(app.clone(), state, port));
+    crate::operations::dedup(&app, &format!("start_sidecar:{:?}", port), start).await
+}
+
+async fn start(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    port: Option<u16>,
+) -> Result<u32, CommandError> {
+    crate::process::recover_orphan(&app).await;
+    if state.is_running().await {
         return Err(CommandError::SidecarAlreadyRunning);
     }
+    // A bridge started from a terminal would make ours exit with "already
+    // running"; take it over instead.
+    if let Some(pid) = crate::process::adopt_running(&app).await? {
+        return Ok(pid);
+    }
+    crate::versions::check_sidecar(&app).await?;
+
+    // The sidecar listens on a private loopback port or Unix socket while
+    // the gateway owns the user-facing port, so WebDAV traffic can be
+    // observed from Rust. In front of a socket any free port will do.
+    let (host, configured_port) = configured_listen_addr();
+    let (public_port, upstream) = match crate::transport::configured() {
+        crate::transport::Transport::Tcp { .. } => {
+            (port.unwrap_or(configured_port), crate::gateway::Upstream::Tcp(crate::gateway::reserve_upstream_port()?))
+        }
+        crate::transport::Transport::UnixSocket { path } => {
+            (port.unwrap_or(0), crate::gateway::Upstream::UnixSocket(crate::transport::socket_path(&path)))
+        }
+    };
+    let hosts = app.state::<crate::network_sharing::NetworkSharingState>().listen_hosts(&host);
+    crate::gateway::start(&app, &hosts, public_port, upstream.clone()).await?;
+    let public_port = crate::gateway::public_port(&app).unwrap_or(public_port);
 
     let mut args = vec!["start".to_string()];
-    // In dev/GUI context, prefer starting without auth to allow mounting
-    // even before the user completes login; credentials can be added later.
-    args.push("--no-auth".to_string());
+    // The sidecar always enforces the install's credential from its
+    // environment (see `webdav_auth`). Unless the user configured their own
+    // credentials, `--no-auth` still lets it start before login completes.
+    let credential = crate::webdav_auth::begin_run(&app).await;
+    if !app.state::<crate::webdav_auth::WebdavAuthState>().has_configured_credentials() {
+        args.push("--no-auth".to_string());
+    }
     // Explicitly run in foreground so stdout/stderr are captured
     args.push("--no-daemon".to_string());
-    if let Some(p) = port {
-        args.push("--port".to_string());
-        args.push(p.to_string());
+    match &upstream {
+        crate::gateway::Upstream::Tcp(upstream_port) => {
+            args.push("--host".to_string());
+            args.push("127.0.0.1".to_string());
+            args.push("--port".to_string());
+            args.push(upstream_port.to_string());
+        }
+        crate::gateway::Upstream::UnixSocket(path) => {
+            args.push("--socket".to_string());
+            args.push(crate::transport::display_socket_path(path));
+        }
     }
 
-    let sidecar_cmd = app
-        .shell()
-        .sidecar("proton-drive-webdav-bridge")
-        .map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?
-        .args(&args);
-
-    let (mut rx, child) = sidecar_cmd.spawn().map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?;
-    let pid = child.pid();
-    *lock = Some(pid);
+    let mut env: Vec<(String, String)> = credential.env().into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    // The whole run is one trace; a crash is recorded under it
+    let trace_id = crate::trace::current().unwrap_or_else(crate::trace::new_id);
+    env.push((crate::trace::TRACE_ENV.to_string(), trace_id.clone()));
+    crate::proxy::prepare(&app).await;
+    let spawned = crate::sandbox::sidecar_command_with_env(&app, env)
+        .and_then(|cmd| {
+            cmd.args(&args)
+                .spawn()
+                .map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))
+        });
+    let (mut rx, child) = match spawned {
+        Ok(s) => s,
+        Err(e) => {
+            crate::gateway::stop(&app);
+            return Err(e);
+        }
+    };
+    let pid = state.track_child(child).await;
+    crate::process::save_record(&crate::process::PidRecord::new(pid, &upstream, public_port));
+    app.state::<crate::metrics::MetricsState>().record_sidecar_start();
+    log::info!("{} Started the bridge (pid {})", crate::trace::tag(&trace_id), pid);
+    crate::status::invalidate(&app);
 
     // Spawn async task to stream stdout/stderr
     let app_handle = app.clone();
+    let supervisor = state.inner().clone();
+    let started_at = crate::sidecar::unix_now();
+    let started = std::time::Instant::now();
     tauri::async_runtime::spawn(async move {
+        use crate::notifications::{is_session_expired_line, notify, NotificationCategory};
         use tauri_plugin_shell::process::CommandEvent;
 
+        let mut session_expiry_notified = false;
+        // Kept for a crash report if the bridge dies
+        let mut recent_stderr = std::collections::VecDeque::with_capacity(crate::crash_reports::STDERR_LINES);
         while let Some(event) = rx.recv().await {
             match event {
                 CommandEvent::Stdout(bytes) => {
                     let line = String::from_utf8_lossy(&bytes);
+                    if crate::status::affects_status(&line) {
+                        crate::status::invalidate(&app_handle);
+                    }
                     let _ = app_handle.emit(
                         "sidecar:log",
                         LogEvent {
@@ -242,6 +551,22 @@ pub async fn start_sidecar(
                 }
                 CommandEvent::Stderr(bytes) => {
                     let line = String::from_utf8_lossy(&bytes);
+                    if recent_stderr.len() == crate::crash_reports::STDERR_LINES {
+                        recent_stderr.pop_front();
+                    }
+                    recent_stderr.push_back(line.trim_end().to_string());
+                    if crate::status::affects_status(&line) {
+                        crate::status::invalidate(&app_handle);
+                    }
+                    if !session_expiry_notified && is_session_expired_line(&line) {
+                        session_expiry_notified = true;
+                        notify(
+                            &app_handle,
+                            NotificationCategory::SessionExpired,
+                            "Proton session expired",
+                            "Sign in again to keep accessing your drive.",
+                        );
+                    }
                     let _ = app_handle.emit(
                         "sidecar:log",
                         LogEvent {
@@ -251,6 +576,33 @@ pub async fn start_sidecar(
                     );
                 }
                 CommandEvent::Terminated(payload) => {
+                    let stop_requested = supervisor.exited(pid).await;
+                    crate::gateway::stop(&app_handle);
+                    crate::process::clear_record(pid);
+                    crate::status::invalidate(&app_handle);
+                    let clean_exit = payload.code == Some(0);
+                    if !clean_exit && !stop_requested {
+                        let reason = match (payload.code, payload.signal) {
+                            (Some(code), _) => format!("exit code {}", code),
+                            (None, Some(signal)) => format!("signal {}", signal),
+                            (None, None) => "unknown reason".to_string(),
+                        };
+                        app_handle.state::<crate::metrics::MetricsState>().record_sidecar_crash();
+                        app_handle.state::<crate::trace::TraceState>().record_failure(crate::trace::FailedOperation {
+                            trace_id: trace_id.clone(),
+                            operation: "bridge run".to_string(),
+                            started_at,
+                            elapsed_ms: started.elapsed().as_millis() as u64,
+                            error: format!("The bridge exited ({})", reason),
+                        });
+                        crate::crash_reports::record_sidecar_crash(&app_handle, &reason, recent_stderr.drain(..).collect());
+                        notify(
+                            &app_handle,
+                            NotificationCategory::SidecarCrash,
+                            "WebDAV bridge stopped unexpectedly",
+                            &format!("The bridge exited ({}). Your drive is unavailable until it is restarted.", reason),
+                        );
+                    }
                     let _ = app_handle.emit("sidecar:terminated", payload);
                     break;
                 }
@@ -262,100 +614,115 @@ pub async fn start_sidecar(
     Ok(pid)
 }
 
+/// How long the CLI's `stop` may take to stop a bridge the app didn't spawn.
+const STOP_COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 #[tauri::command]
 pub async fn stop_sidecar(
     app: AppHandle,
     state: State<'_, SidecarState>,
 ) -> Result<(), CommandError> {
-    let output = app
-        .shell()
-        .sidecar("proton-drive-webdav-bridge")
-        .map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?
-        .args(["stop"])
-        .output()
-        .await
-        .map_err(|e| CommandError::IoError(e.to_string()))?;
+    let stop = crate::trace::traced(&app, "stop", async {
+        let tracked = state.request_stop().await;
+        if let Some(tracked) = tracked.filter(|t| t.owned) {
+            state.stop_child(tracked.pid).await?;
+            crate::process::clear_record(tracked.pid);
+            crate::gateway::stop(&app);
+            crate::status::invalidate(&app);
+            return Ok(());
+        }
 
-    if output.status.success() {
-        let mut lock = state.pid.lock().unwrap();
-        *lock = None;
-        Ok(())
-    } else {
-        Err(CommandError::SidecarCommandFailed(
-            String::from_utf8_lossy(&output.stderr).to_string(),
-        ))
-    }
+        // A bridge the app didn't spawn is stopped through the CLI
+        let command = crate::sandbox::sidecar_command(&app)?.args(["stop"]);
+        let output = crate::sidecar_commands::run_sidecar_command(&app, command, "stop", STOP_COMMAND_TIMEOUT).await?;
+
+        if output.success() {
+            if let Some(tracked) = tracked {
+                state.exited(tracked.pid).await;
+                crate::process::clear_record(tracked.pid);
+            }
+            crate::gateway::stop(&app);
+            crate::status::invalidate(&app);
+            Ok(())
+        } else {
+            Err(CommandError::SidecarCommandFailed(output.stderr))
+        }
+    });
+    let stop = crate::lifecycle::guarded(&app, crate::lifecycle::Operation::Stop, stop);
+    crate::operations::dedup(&app, "stop_sidecar", stop).await
 }
 
+/// Stop the bridge if it is running and start it again.
 #[tauri::command]
-pub async fn get_status(
+pub async fn restart_sidecar(
     app: AppHandle,
     state: State<'_, SidecarState>,
-) -> Result<StatusResponse, CommandError> {
-    use tokio::time::{timeout, Duration};
+    port: Option<u16>,
+) -> Result<u32, CommandError> {
+    crate::lifecycle::guarded(&app, crate::lifecycle::Operation::Restart, async {
+        if state.is_running().await {
+            stop_sidecar(app.clone(), state.clone()).await?;
+        }
+        start(app.clone(), state, port).await
+    })
+    .await
+}
 
-    // Emit an intermediate "loading" status to the UI
-    let _ = app.emit("status:update", default_status_response());
+/// Latest status snapshot; see `status.rs` for when it is refreshed.
+#[tauri::command]
+pub async fn get_status(
+    app: AppHandle,
+    _state: State<'_, SidecarState>,
+) -> Result<StatusResponse, CommandError> {
+    crate::operations::dedup(&app, "get_status", async { app.state::<crate::status::StatusCache>().get(&app).await }).await
+}
 
-    let sidecar = app.shell().sidecar("proton-drive-webdav-bridge");
+/// Ask the sidecar for its status over the control channel and overlay what
+/// only the app knows: the tracked PID and whether the gateway terminates TLS.
+pub(crate) async fn probe_status(app: &AppHandle) -> Result<StatusResponse, CommandError> {
+    let state = app.state::<SidecarState>();
+    let mut status = app.state::<crate::sidecar_client::SidecarClient>().status(app).await.inspect_err(|e| {
+        log::warn!("Failed to get sidecar status: {}", e);
+    })?;
 
-    if let Err(e) = sidecar {
-        log::warn!("Sidecar not available: {}", e);
-        return Ok(default_status_response());
+    let tracked = state.tracked().await;
+    if status.server.pid.is_none() {
+        status.server.pid = tracked.map(|t| t.pid);
     }
+    status.server.adopted = tracked.is_some_and(|t| t.adopted);
 
-    let status_future = sidecar.unwrap().args(["status", "--json"]).output();
-
-    let output = match timeout(Duration::from_secs(5), status_future).await {
-        Ok(Ok(o)) => o,
-        Ok(Err(e)) => {
-            log::warn!("Failed to execute sidecar status: {}", e);
-            return Ok(default_status_response());
-        }
-        Err(_) => {
-            log::warn!("Sidecar status command timed out");
-            return Ok(default_status_response());
-        }
-    };
-
-    if !output.status.success() {
-        log::warn!("Sidecar status command failed: {}", String::from_utf8_lossy(&output.stderr));
-        return Ok(default_status_response());
+    // The gateway's port differs from the configured one in front of a Unix
+    // socket.
+    if let Some(port) = crate::gateway::public_port(app) {
+        status.config.webdav.port = port;
     }
 
-    let stdout_str = String::from_utf8_lossy(&output.stdout);
-    let json_str = stdout_str
-        .lines()
-        .find(|line| line.trim_start().starts_with('{'))
-        .and_then(|_| {
-            let start_idx = stdout_str.find('{')?;
-            Some(stdout_str[start_idx..].to_string())
-        });
-
-    if json_str.is_none() {
-        log::warn!("No JSON found in status output: {}", stdout_str);
-        return Ok(default_status_response());
+    // TLS is terminated by the gateway, so the sidecar reports a plain URL.
+    // An adopted bridge serves the share itself, without the gateway.
+    status.tls = crate::tls::enabled_certificate().filter(|_| !status.server.adopted);
+    if status.tls.is_some() {
+        status.config.webdav.https = true;
+        status.server.url = status.server.url.map(|u| u.replacen("http://", "https://", 1));
     }
 
-    let mut status: StatusResponse = match serde_json::from_str(&json_str.unwrap()) {
-        Ok(s) => s,
-        Err(e) => {
-            log::warn!("Failed to parse status JSON: {}", e);
-            return Ok(default_status_response());
-        }
+    // Behind the gateway LAN clients need the sharing login or a WebDAV
+    // login; an adopted bridge only has its own setting.
+    status.security_warning = if status.server.adopted {
+        crate::network_sharing::exposure_warning(&[status.config.webdav.host.clone()], status.config.webdav.require_auth)
+    } else if let Some(hosts) = crate::gateway::bound_hosts(app) {
+        let auth_required = app.state::<crate::network_sharing::NetworkSharingState>().requires_auth()
+            || app.state::<crate::webdav_auth::WebdavAuthState>().run_credential().is_some();
+        crate::network_sharing::exposure_warning(&hosts, auth_required)
+    } else {
+        None
     };
 
-    if status.server.pid.is_none() {
-        let lock = state.pid.lock().unwrap();
-        status.server.pid = *lock;
-    }
-
     Ok(status)
 }
 
 fn default_status_response() -> StatusResponse {
     StatusResponse {
-        server: ServerStatus { running: false, pid: None, url: None },
+        server: ServerStatus { running: false, pid: None, url: None, adopted: false },
         auth: AuthStatus { logged_in: false, username: None },
         config: ConfigStatus {
             webdav: WebdavConfig {
@@ -373,127 +740,208 @@ fn default_status_response() -> StatusResponse {
             auto_start: None,
         },
         log_file: String::new(),
+        tls: None,
+        security_warning: None,
     }
 }
 
-// Pure helper (module-level) to make mount URI matching testable
-fn find_mount_by_uri(mounts: impl IntoIterator<Item = (String, bool)>, target: &str) -> Option<bool> {
-    // Normalize target URI by ensuring it has a trailing slash (GIO adds this)
-    let normalized_target = if target.ends_with('/') {
-        target.to_string()
-    } else {
-        format!("{}/", target)
-    };
-
-    // Materialize mounts into a Vec so we can iterate multiple times
-    let mounts_vec: Vec<(String, bool)> = mounts.into_iter().collect();
-
-    // First try exact match (with trailing slash normalization)
-    for (uri, can_unmount) in mounts_vec.iter() {
-        let normalized_uri = if uri.ends_with('/') { uri.clone() } else { format!("{}/", uri) };
-        if normalized_uri == normalized_target {
-            return Some(*can_unmount);
-        }
-    }
-
-    // Fallback: try matching by port only. This handles cases where GIO
-    // normalizes hostnames (e.g. 127.0.0.1 vs localhost) or uses http(s) scheme.
-    // Extract port from the target (if any) and look for mounts that contain
-    // ":<port>" in their URI.
-    if let Some(colon_pos) = target.rfind(':') {
-        // get substring after last ':' up to optional trailing '/'
-        let after = &target[colon_pos + 1..];
-        let port_str = after.trim_end_matches('/');
-        if port_str.chars().all(|c| c.is_ascii_digit()) {
-            for (uri, can_unmount) in mounts_vec.iter() {
-                if uri.contains(&format!(":{}", port_str)) {
-                    return Some(*can_unmount);
-                }
-            }
-        }
-    }
+/// GIO location of the local share: `davs://` when the gateway serves HTTPS,
+/// pointing at the configured remote folder so only that subtree is mounted.
+pub(crate) fn local_dav_uri(status: &StatusResponse) -> String {
+    dav_uri(status.config.webdav.https, status.config.webdav.port, &status.config.remote_path)
+}
 
-    None
+/// GIO location of `remote_path` on the local share.
+pub(crate) fn dav_uri(https: bool, port: u16, remote_path: &str) -> String {
+    let scheme = if https { "davs" } else { "dav" };
+    let path = crate::dav::encode_path(remote_path);
+    let path = if path == "/" { "" } else { path.as_str() };
+    format!("{}://localhost:{}{}", scheme, port, path)
 }
 
+/// How long `auth login` may take, 2FA included.
+const LOGIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
 #[tauri::command]
 pub async fn login(app: AppHandle, email: String) -> Result<(), CommandError> {
-    let output = app
-        .shell()
-        .sidecar("proton-drive-webdav-bridge")
-        .map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?
-        // Use the correct CLI signature: auth login --username <email>
-        .args(["auth", "login", "--username", &email])
-        .output()
-        .await
-        .map_err(|e| CommandError::IoError(e.to_string()))?;
+    crate::trace::traced(&app, "login", async {
+        let command = crate::sandbox::sidecar_command(&app)?
+            // Use the correct CLI signature: auth login --username <email>
+            .args(["auth", "login", "--username", &email]);
+        let output = crate::sidecar_commands::run_sidecar_command(&app, command, "auth login", LOGIN_TIMEOUT).await?;
+        login_result(output)?;
+        crate::accounts::record_sign_in(&email);
+        crate::status::invalidate(&app);
+        Ok(())
+    })
+    .await
+}
 
-    if output.status.success() {
+/// Outcome of an `auth login` run; the CLI explains failures on stderr.
+pub(crate) fn login_result(output: crate::sidecar_commands::CommandOutput) -> Result<(), CommandError> {
+    if output.success() {
         Ok(())
     } else {
-        Err(CommandError::AuthFailed(
-            String::from_utf8_lossy(&output.stderr).to_string(),
-        ))
+        Err(CommandError::AuthFailed(output.stderr))
     }
 }
 
-#[tauri::command]
-pub async fn logout(app: AppHandle, state: State<'_, SidecarState>) -> Result<(), CommandError> {
-    // Stop sidecar first if running
-    let _ = stop_sidecar(app.clone(), state).await;
-
-    let output = app
-        .shell()
-        .sidecar("proton-drive-webdav-bridge")
-        .map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?
-        .args(["auth", "--logout"])
-        .output()
-        .await
-        .map_err(|e| CommandError::IoError(e.to_string()))?;
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PortChange {
+    /// Port the share is served on now; `None` while the server is stopped
+    pub live_port: Option<u16>,
+    pub previous_port: u16,
+    /// The new port could not be bound and the share stayed on the old one
+    pub rolled_back: bool,
+    pub error: Option<String>,
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err(CommandError::AuthFailed(
-            String::from_utf8_lossy(&output.stderr).to_string(),
-        ))
+pub(crate) fn validate_port(port: u16) -> Result<(), CommandError> {
+    if port < 1024 {
+        return Err(CommandError::InvalidPort(format!("{} is a privileged port; use 1024 or above", port)));
     }
+    Ok(())
+}
+
+/// Whether `port` can be bound on every address in `hosts`.
+fn port_available(hosts: &[String], port: u16) -> Result<(), CommandError> {
+    for host in hosts {
+        std::net::TcpListener::bind((host.as_str(), port)).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AddrInUse => CommandError::PortInUse(port),
+            _ => CommandError::IoError(e.to_string()),
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn save_port(port: u16) -> Result<(), CommandError> {
+    update_config_json(|config| {
+        set_webdav_key(config, "port", serde_json::json!(port));
+        Ok(())
+    })
 }
 
+/// Move the share to `port`. Only the gateway owns the public port, so the
+/// bridge keeps running: the gateway is rebound and mounts are moved over.
+/// If the new port cannot be bound, the gateway stays on the old one and the
+/// mounts are restored there. The port is saved to config.json once it is
+/// live (or right away while the server is stopped).
 #[tauri::command]
 pub async fn set_network_port(
     app: AppHandle,
     state: State<'_, SidecarState>,
     port: u16,
-) -> Result<(), CommandError> {
-    // Restart sidecar with new port
-    let _ = stop_sidecar(app.clone(), state.clone()).await;
-    start_sidecar(app, state, Some(port)).await?;
-    Ok(())
+) -> Result<PortChange, CommandError> {
+    crate::lifecycle::guarded(&app, crate::lifecycle::Operation::Reconfigure, change_port(app.clone(), state, port)).await
+}
+
+async fn change_port(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    port: u16,
+) -> Result<PortChange, CommandError> {
+    validate_port(port)?;
+    let (host, configured_port) = configured_listen_addr();
+    let hosts = app.state::<crate::network_sharing::NetworkSharingState>().listen_hosts(&host);
+    let (Some(previous_port), Some(upstream)) = (crate::gateway::public_port(&app), crate::gateway::upstream(&app)) else {
+        // Adopted bridges serve the share themselves; the port applies on
+        // their next start like it does while the server is stopped
+        if port != configured_port {
+            port_available(&hosts, port)?;
+            save_port(port)?;
+        }
+        let live_port = state.tracked().await.is_some_and(|t| t.adopted).then_some(configured_port);
+        return Ok(PortChange { live_port, previous_port: configured_port, rolled_back: false, error: None });
+    };
+    if port == previous_port {
+        save_port(port)?;
+        return Ok(PortChange { live_port: Some(port), previous_port, rolled_back: false, error: None });
+    }
+    port_available(&hosts, port)?;
+
+    // Mount locations include the port, so mounts have to move along
+    let main_mounted = matches!(check_mount_status(app.clone(), state.clone()).await, Ok(Some(_)));
+    let extra_mounted: Vec<String> = crate::mounts::list_mounts(app.clone())
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| m.mounted)
+        .map(|m| m.definition.id)
+        .collect();
+    if main_mounted {
+        unmount_drive(app.clone(), state.clone()).await?;
+    }
+    for id in &extra_mounted {
+        if let Err(e) = crate::mounts::unmount_by_id(app.clone(), id.clone()).await {
+            log::warn!("Failed to unmount {} before the port change: {}", id, e);
+        }
+    }
+
+    let result = crate::gateway::start(&app, &hosts, port, upstream).await;
+    let change = match result {
+        Ok(()) => {
+            save_port(port)?;
+            if let Some(pid) = state.pid().await {
+                crate::process::update_public_port(pid, port);
+            }
+            log::info!("Moved the WebDAV share from port {} to {}", previous_port, port);
+            PortChange { live_port: Some(port), previous_port, rolled_back: false, error: None }
+        }
+        Err(e) => {
+            // Binding failed before the running gateway was replaced
+            log::warn!("Could not move the WebDAV share to port {}, staying on {}: {}", port, previous_port, e);
+            PortChange { live_port: Some(previous_port), previous_port, rolled_back: true, error: Some(e.to_string()) }
+        }
+    };
+    crate::status::invalidate(&app);
+
+    if main_mounted {
+        if let Err(e) = mount_drive(app.clone(), state.clone()).await {
+            log::warn!("Failed to remount after the port change: {}", e);
+        }
+    }
+    for id in extra_mounted {
+        if let Err(e) = crate::mounts::mount_by_id(app.clone(), id.clone()).await {
+            log::warn!("Failed to remount {} after the port change: {}", id, e);
+        }
+    }
+    Ok(change)
 }
 
 #[tauri::command]
 pub async fn purge_cache(app: AppHandle) -> Result<(), CommandError> {
-    let output = app
-        .shell()
-        .sidecar("proton-drive-webdav-bridge")
-        .map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?
-        .args(["config", "--purge-cache"])
-        .output()
-        .await
-        .map_err(|e| CommandError::IoError(e.to_string()))?;
+    app.state::<crate::cache::MetadataCache>().clear();
+    app.state::<crate::sidecar_client::SidecarClient>().purge_cache(&app).await
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err(CommandError::SidecarCommandFailed(
-            String::from_utf8_lossy(&output.stderr).to_string(),
-        ))
+/// Run a one-shot bridge CLI command (e.g. `trash list`) and return its
+/// stdout, failing if it exits unsuccessfully or takes longer than `limit`
+/// (or its configured timeout; see `sidecar_commands`).
+pub(crate) async fn sidecar_output(app: &AppHandle, command: tauri_plugin_shell::process::Command, what: &str, limit: std::time::Duration) -> Result<String, CommandError> {
+    let output = crate::sidecar_commands::run_sidecar_command(app, command, what, limit).await?;
+    if !output.success() {
+        return Err(CommandError::SidecarCommandFailed(output.stderr.trim().to_string()));
+    }
+    Ok(output.stdout)
+}
+
+/// Parse the JSON document a `--json` CLI command printed, skipping any log
+/// lines before it (which may contain brackets in color codes).
+pub(crate) fn parse_json_output<T: serde::de::DeserializeOwned>(stdout: &str, what: &str) -> Result<T, CommandError> {
+    let mut offset = 0;
+    for line in stdout.split_inclusive('\n') {
+        if line.starts_with('{') || line.starts_with('[') {
+            return serde_json::from_str(&stdout[offset..])
+                .map_err(|e| CommandError::SidecarCommandFailed(format!("Invalid {}: {}", what, e)));
+        }
+        offset += line.len();
     }
+    Err(CommandError::SidecarCommandFailed(format!("No JSON in {}", what)))
 }
 
 // Helper to compute config file path similar to the JS side
-fn get_config_file_path() -> Result<std::path::PathBuf, CommandError> {
+pub(crate) fn get_config_file_path() -> Result<std::path::PathBuf, CommandError> {
     use std::path::PathBuf;
 
     // Respect XDG_CONFIG_HOME if present, fallback to $HOME/.config
@@ -510,45 +958,84 @@ fn get_config_file_path() -> Result<std::path::PathBuf, CommandError> {
     Ok(app_dir.join("config.json"))
 }
 
-#[tauri::command]
-pub async fn get_autostart() -> Result<bool, CommandError> {
+/// Read `config.json` as loose JSON. A missing file yields an empty object so
+/// callers can treat unset keys as defaults.
+pub(crate) fn read_config_json() -> Result<serde_json::Value, CommandError> {
     let path = get_config_file_path()?;
     if !path.exists() {
-        return Ok(false);
+        return Ok(serde_json::json!({}));
     }
     let contents = std::fs::read_to_string(&path).map_err(|e| CommandError::IoError(e.to_string()))?;
-    let v: serde_json::Value = serde_json::from_str(&contents).map_err(|e| CommandError::Unknown(e.to_string()))?;
-    Ok(v.get("autoStart").and_then(|x| x.as_bool()).unwrap_or(false))
+    serde_json::from_str(&contents).map_err(|e| CommandError::Unknown(e.to_string()))
 }
 
-#[tauri::command]
-pub async fn set_autostart(enabled: bool) -> Result<bool, CommandError> {
+/// Serializes read-modify-write cycles on `config.json` within the app.
+static CONFIG_WRITE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Modify `config.json` in place with `f` and write it back atomically. The
+/// whole cycle holds [`CONFIG_WRITE_LOCK`], so concurrent updates to other
+/// keys are not lost. Refuses to touch a file that isn't a valid JSON
+/// object rather than replace it; nothing is written if `f` fails.
+pub(crate) fn update_config_json<R>(f: impl FnOnce(&mut serde_json::Value) -> Result<R, CommandError>) -> Result<R, CommandError> {
+    let _guard = CONFIG_WRITE_LOCK.lock().unwrap();
+    let mut v = read_config_json().map_err(|e| CommandError::InvalidConfig(format!("config.json is unreadable ({}); not overwriting it", e)))?;
+    if !v.is_object() {
+        return Err(CommandError::InvalidConfig("config.json does not hold a JSON object; not overwriting it".into()));
+    }
+    let result = f(&mut v)?;
     let path = get_config_file_path()?;
-    let mut v = if path.exists() {
-        let contents = std::fs::read_to_string(&path).map_err(|e| CommandError::IoError(e.to_string()))?;
-        serde_json::from_str::<serde_json::Value>(&contents).unwrap_or(serde_json::json!({}))
-    } else {
-        serde_json::json!({})
+    let s = serde_json::to_string_pretty(&v).map_err(|e| CommandError::Unknown(e.to_string()))?;
+    crate::config_schema::write_atomically(&path, s.as_bytes())?;
+    Ok(result)
+}
+
+/// Deserialize a top-level config section, falling back to its default when
+/// the key is missing or malformed. Malformed sections are logged and
+/// reported by `validate_config`.
+pub(crate) fn read_config_section<T: serde::de::DeserializeOwned + Default>(key: &str) -> T {
+    crate::config_schema::register_section::<T>(key);
+    let Some(section) = read_config_json().ok().and_then(|v| v.get(key).cloned()) else {
+        return T::default();
     };
+    serde_json::from_value(section).unwrap_or_else(|e| {
+        crate::config_schema::warn_malformed(key, &e);
+        T::default()
+    })
+}
 
-    v["autoStart"] = serde_json::json!(enabled);
+/// Replace a top-level config section, leaving the rest of the file intact.
+pub(crate) fn write_config_section<T: Serialize>(key: &str, value: &T) -> Result<(), CommandError> {
+    let value = serde_json::to_value(value).map_err(|e| CommandError::Unknown(e.to_string()))?;
+    update_config_json(|v| {
+        v[key] = value;
+        Ok(())
+    })
+}
 
-    let s = serde_json::to_string_pretty(&v).map_err(|e| CommandError::Unknown(e.to_string()))?;
-    std::fs::write(&path, s).map_err(|e| CommandError::IoError(e.to_string()))?;
-    Ok(enabled)
+/// Set `webdav.<key>`, creating the `webdav` object if needed.
+pub(crate) fn set_webdav_key(config: &mut serde_json::Value, key: &str, value: serde_json::Value) {
+    if !config.get("webdav").is_some_and(|w| w.is_object()) {
+        config["webdav"] = serde_json::json!({});
+    }
+    config["webdav"][key] = value;
+}
+
+/// Current time in Unix seconds; 0 if the clock is before 1970.
+pub(crate) fn unix_now() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
 }
 
-fn should_open_with_path(uri: &str) -> bool {
+pub(crate) fn should_open_with_path(uri: &str) -> bool {
     // Treat absolute filesystem paths, file:// URIs (converted to paths),
-    // and dav:// URIs as paths that should be opened with `open_path` so the
-    // system file manager / GIO can mount or open them. This preserves the
-    // dav:// scheme when passed to `open_path`.
-    uri.starts_with('/') || uri.starts_with("file://") || uri.starts_with("dav://")
+    // and dav:// or davs:// URIs as paths that should be opened with
+    // `open_path` so the system file manager / GIO can mount or open them.
+    // This preserves the dav:// scheme when passed to `open_path`.
+    uri.starts_with('/') || uri.starts_with("file://") || uri.starts_with("dav://") || uri.starts_with("davs://")
 }
 
 // Testable helper that accepts callbacks to perform the actual open actions.
 // This avoids the need to mock `AppHandle`/`Opener` in unit tests.
-fn open_uri_with<FPath, FUrl>(uri: &str, mut open_path: FPath, mut open_url: FUrl) -> Result<(), CommandError>
+pub(crate) fn open_uri_with<FPath, FUrl>(uri: &str, mut open_path: FPath, mut open_url: FUrl) -> Result<(), CommandError>
 where
     FPath: FnMut(&str) -> Result<(), CommandError>,
     FUrl: FnMut(&str) -> Result<(), CommandError>,
@@ -563,262 +1050,311 @@ where
     }
 }
 
+/// Local path of `remote_path` under a mount of `root` at `mount_point`, or
+/// `None` when it lies outside the mounted root.
+fn path_in_mount(root: &str, mount_point: &str, remote_path: &str) -> Option<String> {
+    let root = crate::dav::normalize_path(root);
+    let path = crate::dav::normalize_path(remote_path);
+    let relative = if root == "/" { path.as_str() } else { path.strip_prefix(&root)? };
+    if !relative.is_empty() && !relative.starts_with('/') {
+        return None;
+    }
+    let relative = relative.trim_start_matches('/');
+    if relative.is_empty() {
+        return Some(mount_point.to_string());
+    }
+    Some(std::path::Path::new(mount_point).join(relative).display().to_string())
+}
+
+/// Where the file manager finds `remote_path`: inside the mounted drive's
+/// local directory when GVFS exposes one, else its dav:// location.
+pub(crate) async fn locate_remote_path(app: &AppHandle, state: State<'_, SidecarState>, remote_path: &str) -> String {
+    if let Ok(status) = get_status(app.clone(), state).await {
+        if let (true, Some(mount_point)) = crate::mounts::mount_status(&local_dav_uri(&status)) {
+            if let Some(path) = path_in_mount(&status.config.remote_path, &mount_point, remote_path) {
+                return path;
+            }
+        }
+    }
+    crate::mounts::share_uri(app, remote_path)
+}
+
+/// Open the drive in the file manager: `mount_path` as given, else the
+/// folder `remote_path` resolved inside the mount, else the drive's root.
+/// Reports how the file manager was reached.
 #[tauri::command]
 pub async fn open_in_files(
     app: AppHandle,
     state: State<'_, SidecarState>,
     mount_path: Option<String>,
-) -> Result<(), CommandError> {
+    remote_path: Option<String>,
+) -> Result<crate::file_manager::OpenReport, CommandError> {
     // If a specific path is provided, open it. Otherwise construct a DAV URI
     // using the sidecar config (preferred) or convert the server URL to a
     // dav:// form if necessary so the file manager is used instead of a
     // browser.
     let uri = if let Some(p) = mount_path {
         p
+    } else if let Some(remote_path) = remote_path {
+        locate_remote_path(&app, state, &remote_path).await
     } else {
         // Ask for status and obtain the server URL (if available)
         let status = get_status(app.clone(), state).await.unwrap_or_else(|_| default_status_response());
         // Prefer a dav:// URI. If `server.url` is present and looks like http(s),
         // convert to dav://host:port. Otherwise prefer `server.url` if it's already
         // dav://, or fall back to config-derived dav://localhost:port.
-        if let Some(surl) = status.server.url {
-            if surl.starts_with("dav://") {
+        if let Some(surl) = status.server.url.clone() {
+            if surl.starts_with("dav://") || surl.starts_with("davs://") {
                 surl
             } else if surl.starts_with("http://") || surl.starts_with("https://") {
                 // crude parse: extract host[:port] from the authority component
                 // e.g., http://127.0.0.1:8080/ -> host_port = 127.0.0.1:8080
+                let scheme = if surl.starts_with("https://") { "davs" } else { "dav" };
                 let stripped = surl.splitn(3, '/').nth(2).unwrap_or("");
                 let host_port = stripped.split('/').next().unwrap_or("");
                 if !host_port.is_empty() {
-                    format!("dav://{}", host_port)
+                    format!("{}://{}", scheme, host_port)
                 } else {
-                    format!("dav://localhost:{}", status.config.webdav.port)
+                    local_dav_uri(&status)
                 }
             } else {
                 // Unknown scheme: fall back to config-derived dav://
-                format!("dav://localhost:{}", status.config.webdav.port)
+                local_dav_uri(&status)
             }
         } else {
-            format!("dav://localhost:{}", status.config.webdav.port)
+            local_dav_uri(&status)
         }
     };
+    crate::file_manager::open(&app, uri).await
+}
 
-    // Use the Tauri opener plugin to open files/URLs with the system default app
-    let opener = app.opener();
+/// Show `remote_path` selected in its folder in the file manager. Without a
+/// FileManager1 service the folder is opened instead.
+#[tauri::command]
+pub async fn reveal_in_files(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    remote_path: String,
+) -> Result<crate::file_manager::OpenReport, CommandError> {
+    let remote_path = crate::dav::normalize_path(&remote_path);
+    let parent = crate::dav::parent_path(&remote_path).to_string();
+    let item = locate_remote_path(&app, state.clone(), &remote_path).await;
+    let folder = locate_remote_path(&app, state, &parent).await;
 
-    // Delegate to the testable helper which accepts callbacks for path/url
-    open_uri_with(
-        &uri,
-        |p: &str| opener.open_path(p, None::<&str>).map_err(|e| CommandError::Unknown(e.to_string())),
-        |u: &str| opener.open_url(u, None::<&str>).map_err(|e| CommandError::Unknown(e.to_string())),
-    )?;
+    #[cfg(target_os = "linux")]
+    let skipped = {
+        let target = item.clone();
+        let shown = tauri::async_runtime::spawn_blocking(move || crate::sandbox::show_items(&target))
+            .await
+            .map_err(|e| CommandError::Unknown(e.to_string()))?;
+        match shown {
+            Ok(()) => {
+                let method = crate::file_manager::OpenMethod::FileManager1;
+                return Ok(crate::file_manager::OpenReport { location: item, method, skipped: Vec::new() });
+            }
+            Err(e) => {
+                log::info!("Cannot select {} in the file manager ({}), opening its folder", item, e);
+                vec![format!("FileManager1 ShowItems: {}", e)]
+            }
+        }
+    };
+    #[cfg(not(target_os = "linux"))]
+    let skipped = {
+        let _ = item;
+        Vec::new()
+    };
 
-    Ok(())
+    let mut report = crate::file_manager::open(&app, folder).await?;
+    report.skipped.splice(0..0, skipped);
+    Ok(report)
 }
 
 #[tauri::command]
 pub async fn mount_drive(app: AppHandle, state: State<'_, SidecarState>) -> Result<(), CommandError> {
+    let mount = crate::lifecycle::guarded(&app, crate::lifecycle::Operation::Mount, mount(app.clone(), state));
+    crate::operations::dedup(&app, "mount_drive", mount).await
+}
+
+async fn mount(app: AppHandle, state: State<'_, SidecarState>) -> Result<(), CommandError> {
     let status = get_status(app.clone(), state).await.unwrap_or_else(|_| default_status_response());
 
     // Check if server is actually running
     if !status.server.running {
-        let msg = "WebDAV server is not running. Start the server first.";
-        let _ = app.emit("mount:status", msg);
-        return Err(CommandError::GioError(msg.to_string()));
+        let msg = crate::i18n::t("mount.serverNotRunning");
+        let _ = app.emit("mount:status", &msg);
+        return Err(CommandError::GioError(msg));
     }
 
-    // Always construct dav:// URI for mounting (status.server.url is http://)
-    let port = status.config.webdav.port;
-    let uri = format!("dav://localhost:{}", port);
+    // Always construct a dav:// (or davs://) URI for mounting (status.server.url is http(s)://)
+    let uri = local_dav_uri(&status);
+    let trust_local_certificate = status.tls.is_some();
 
-    #[cfg(target_os = "linux")]
-    {
-        // GIO operations need to run in a thread with a GLib main context
-        use std::sync::mpsc::channel;
-        use std::time::Duration;
-
-        let (tx, rx) = channel();
-        let uri_clone = uri.clone();
-
-        // Emit mounting start event to UI and then spawn blocking operation
-        let _ = app.emit("mount:status", "Mounting...");
-
-        // Spawn blocking operation in a thread with its own GLib context
-        std::thread::spawn(move || {
-            // Create a new MainContext and run everything within it
-            let context = glib::MainContext::new();
-            
-            let result = context.with_thread_default(|| {
-                // Create the mount operation
-                let file = gio::File::for_uri(&uri_clone);
-                let mount_op = gio::MountOperation::new();
-                mount_op.set_anonymous(true);
-
-                let (inner_tx, inner_rx) = channel();
-                
-                // Use callback-based API
-                file.mount_enclosing_volume(
-                    gio::MountMountFlags::NONE,
-                    Some(&mount_op),
-                    None::<&gio::Cancellable>,
-                    move |result| {
-                        let r = match result {
-                            Ok(()) => Ok(()),
-                            Err(e) => {
-                                let err_msg = e.to_string();
-                                // If already mounted, treat as success
-                                if err_msg.contains("already mounted") || err_msg.contains("Already mounted") {
-                                    Ok(())
-                                } else {
-                                    Err(err_msg)
-                                }
-                            }
-                        };
-                        let _ = inner_tx.send(r);
-                    },
-                );
-
-                // Run the main loop until we get a result or timeout
-                let loop_obj = glib::MainLoop::new(Some(&context), false);
-                let loop_clone = loop_obj.clone();
-                
-                // Timeout handler
-                std::thread::spawn(move || {
-                    std::thread::sleep(Duration::from_secs(5)); // Reduced timeout to 5 seconds
-                    loop_clone.quit();
-                });
+    let provider = app.state::<crate::mount_provider::MountProviderState>();
+    let result = crate::mount_provider::mount(provider.get(), &uri, trust_local_certificate, |msg| {
+        let _ = app.emit("mount:status", msg);
+    })
+    .await;
+    match &result {
+        Ok(()) => crate::onboarding::record_first_mount(&app),
+        Err(CommandError::OperationCancelled) => {}
+        Err(CommandError::MountTimeout) => crate::notifications::notify(
+            &app,
+            crate::notifications::NotificationCategory::MountFailure,
+            &crate::i18n::t("mount.failedTitle"),
+            &crate::i18n::t("mount.timedOutBody"),
+        ),
+        Err(e) => {
+            let msg = crate::mount_provider::failure_message(e);
+            log::error!("Mount failed: {}", msg);
+            crate::notifications::notify(&app, crate::notifications::NotificationCategory::MountFailure, &crate::i18n::t("mount.failedTitle"), &msg);
+        }
+    }
+    result
+}
 
-                // Run the loop - this blocks until quit() is called
-                loop_obj.run();
+/// Error reported by `spawn_gio_mount` when an attempt exceeds its timeout.
+#[cfg(target_os = "linux")]
+pub(crate) const MOUNT_TIMED_OUT: &str = "Mount timed out";
 
-                inner_rx.try_recv().unwrap_or_else(|_| Err("Mount timed out".into()))
+#[cfg(target_os = "linux")]
+/// Mount `uri` through GIO. GIO operations need a GLib main context, so this
+/// runs on the shared GIO worker (see `gio_worker.rs`); the receiver yields
+/// the outcome (or nothing if the worker is gone). Cancelling `cancellable`
+/// aborts the attempt, as does running past `timeout`. `credential` answers
+/// gvfs's password prompt for the app's own bridge.
+pub(crate) fn spawn_gio_mount(
+    uri: String,
+    trust_local_certificate: bool,
+    credential: Option<crate::webdav_auth::InstallCredential>,
+    cancellable: gio::Cancellable,
+    timeout: std::time::Duration,
+) -> std::sync::mpsc::Receiver<Result<(), String>> {
+    use crate::gio_worker::{Attempt, Reply};
+
+    let (reply, rx) = Reply::channel();
+    crate::gio_worker::run(move || {
+        let file = gio::File::for_uri(&uri);
+        let mount_op = gio::MountOperation::new();
+        match credential {
+            Some(credential) => {
+                mount_op.connect_ask_password(move |op, _, _, _, _| {
+                    op.set_username(Some(&credential.username));
+                    op.set_password(Some(&credential.password));
+                    op.set_password_save(gio::PasswordSave::Never);
+                    op.reply(gio::MountOperationResult::Handled);
+                });
+            }
+            None => mount_op.set_anonymous(true),
+        }
+        if trust_local_certificate {
+            // gvfs asks whether to trust the gateway's self-signed
+            // certificate; the URI always points at localhost.
+            // gio-rs has no typed binding for "ask-question".
+            mount_op.connect("ask-question", false, |args| {
+                if let Ok(op) = args[0].get::<gio::MountOperation>() {
+                    op.set_choice(0);
+                    op.reply(gio::MountOperationResult::Handled);
+                }
+                None
             });
+        }
 
-            let final_result = result.unwrap_or_else(|e| Err(format!("Context error: {}", e)));
-            let _ = tx.send(final_result);
+        let attempt = Attempt::start(&cancellable, timeout, &reply);
+        let attempt_cancellable = attempt.cancellable().clone();
+        file.mount_enclosing_volume(gio::MountMountFlags::NONE, Some(&mount_op), Some(&attempt_cancellable), move |result| {
+            if attempt.finish() {
+                return;
+            }
+            reply.send(match result {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    let err_msg = e.to_string();
+                    // If already mounted, treat as success
+                    if err_msg.contains("already mounted") || err_msg.contains("Already mounted") {
+                        Ok(())
+                    } else {
+                        Err(err_msg)
+                    }
+                }
+            });
         });
+    });
+    rx
+}
 
-        match rx.recv_timeout(Duration::from_secs(20)) {
-            Ok(Ok(())) => {
-                let _ = app.emit("mount:status", "Mounted");
-                Ok(())
+/// Start one GIO unmount of `mount` on the worker, giving up after
+/// `timeout`. A regular unmount that fails for another reason than a timeout
+/// or cancellation is retried with `FORCE`, e.g. when files are still open.
+#[cfg(target_os = "linux")]
+fn start_gio_unmount(
+    mount: gio::Mount,
+    flags: gio::MountUnmountFlags,
+    cancellable: gio::Cancellable,
+    timeout: std::time::Duration,
+    reply: crate::gio_worker::Reply,
+) {
+    let attempt = crate::gio_worker::Attempt::start(&cancellable, timeout, &reply);
+    let attempt_cancellable = attempt.cancellable().clone();
+    mount.clone().unmount_with_operation(flags, None::<&gio::MountOperation>, Some(&attempt_cancellable), move |result| {
+        if attempt.finish() {
+            return;
+        }
+        match result {
+            Err(e) if flags == gio::MountUnmountFlags::NONE && !cancellable.is_cancelled() => {
+                log::warn!("Unmounting {} failed ({}), forcing", mount.root().uri(), e);
+                start_gio_unmount(mount, gio::MountUnmountFlags::FORCE, cancellable, timeout, reply);
             }
-            Ok(Err(e)) => {
-                let msg = format!("Failed to mount: {}", e);
-                log::error!("Mount failed: {}", msg);
-                let _ = app.emit("mount:status", msg.clone());
-                Err(CommandError::GioError(msg))
+            result => {
+                reply.send(result.map_err(|e| e.to_string()));
             }
-            Err(_) => {
-                let _ = app.emit("mount:status", "Mount operation timed out");
-                Err(CommandError::MountTimeout)
-            },
         }
+    });
+}
 
-
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(&uri)
-            .spawn()
-            .map_err(|e| CommandError::IoError(e.to_string()))?;
-        Ok(())
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("explorer")
-            .arg(&uri)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-        Ok(())
-    }
-
-    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
-    {
-        Err("Platform not supported".into())
-    }
+#[cfg(target_os = "linux")]
+/// Unmount the GIO mount whose root is `root_uri` through the GIO API, so
+/// no `gio` binary is needed (it is missing in Flatpak sandboxes). If the
+/// regular unmount fails, e.g. because files are still open, it is retried
+/// with `FORCE`. Like `spawn_gio_mount` this runs on the GIO worker.
+pub(crate) fn spawn_gio_unmount(
+    root_uri: String,
+    cancellable: gio::Cancellable,
+    timeout: std::time::Duration,
+) -> std::sync::mpsc::Receiver<Result<(), String>> {
+    let (reply, rx) = crate::gio_worker::Reply::channel();
+    crate::gio_worker::run(move || {
+        // Mounts are bound to the context the monitor was obtained on
+        let mount = gio::VolumeMonitor::get().mounts().into_iter().find(|m| m.root().uri() == root_uri.as_str());
+        match mount {
+            Some(mount) => start_gio_unmount(mount, gio::MountUnmountFlags::NONE, cancellable, timeout, reply),
+            None => {
+                reply.send(Err("Mount not found".to_string()));
+            }
+        }
+    });
+    rx
 }
 
 #[cfg(target_os = "linux")]
 // Helper function to retrieve and cache mounts
-fn get_cached_mounts() -> Vec<gio::Mount> {
+pub(crate) fn get_cached_mounts() -> Vec<gio::Mount> {
     gio::VolumeMonitor::get().mounts()
 }
 
 #[tauri::command]
 pub async fn unmount_drive(app: AppHandle, state: State<'_, SidecarState>) -> Result<(), CommandError> {
-    let status = get_status(app.clone(), state.clone()).await.unwrap_or_else(|_| default_status_response());
-    let target_uri = format!("dav://localhost:{}", status.config.webdav.port);
-
-    #[cfg(target_os = "linux")]
-    {
-        let mounts = get_cached_mounts();
-        let mounts_vec: Vec<(String, bool)> = mounts
-            .iter()
-            .map(|m| {
-                let root_file: gio::File = m.root();
-                let uri = root_file.uri().to_string();
-                let unmountable = m.can_unmount();
-                (uri, unmountable)
-            })
-            .collect();
-
-        match find_mount_by_uri(mounts_vec.clone(), &target_uri) {
-            Some(false) => {
-                let _ = app.emit("mount:status", "Mount cannot be unmounted via GIO");
-                return Err(CommandError::GioError("Mount cannot be unmounted via GIO".into()))
-            }
-            Some(true) => {
-                let normalized_target = if target_uri.ends_with('/') {
-                    target_uri.clone()
-                } else {
-                    format!("{}/", target_uri)
-                };
-
-                for m in mounts.iter() {
-                    let root_file: gio::File = m.root();
-                    let uri = root_file.uri().to_string();
-                    let normalized_uri = if uri.ends_with('/') {
-                        uri.clone()
-                    } else {
-                        format!("{}/", uri)
-                    };
-
-                    if normalized_uri == normalized_target {
-                        // Use `gio mount -u` command as a fallback
-                        let output = std::process::Command::new("gio")
-                            .arg("mount")
-                            .arg("-u")
-                            .arg(&uri)
-                            .output()
-                            .map_err(|e| CommandError::IoError(format!("Failed to execute gio command: {}", e)))?;
-
-                        if !output.status.success() {
-                            let msg = format!("Failed to unmount: {}", String::from_utf8_lossy(&output.stderr));
-                            let _ = app.emit("mount:status", msg.clone());
-                            return Err(CommandError::GioError(msg));
-                        }
-
-                        let _ = app.emit("mount:status", "Unmounted");
-                        return Ok(());
-                    }
-                }
-            }
-            None => {
-                let _ = app.emit("mount:status", "Mount not found");
-                return Err(CommandError::GioError("Mount not found".into()))
-            }
-        }
-        Ok(())
-    }
+    let unmount = crate::lifecycle::guarded(&app, crate::lifecycle::Operation::Unmount, unmount(app.clone(), state));
+    crate::operations::dedup(&app, "unmount_drive", unmount).await
+}
 
-    #[cfg(not(target_os = "linux"))]
-    {
-        Err(CommandError::Unknown("Platform not supported".into()))
-    }
+async fn unmount(app: AppHandle, state: State<'_, SidecarState>) -> Result<(), CommandError> {
+    let status = get_status(app.clone(), state.clone()).await.unwrap_or_else(|_| default_status_response());
+    let target_uri = local_dav_uri(&status);
+    let provider = app.state::<crate::mount_provider::MountProviderState>();
+    crate::mount_provider::unmount(provider.get(), &target_uri, |msg| {
+        let _ = app.emit("mount:status", msg);
+    })
+    .await
 }
 
 // Dev helper: emit a test sidecar log event. Only compiled in debug builds.
@@ -837,10 +1373,14 @@ pub async fn emit_test_log(app: AppHandle, level: Option<String>, message: Optio
 
 #[tauri::command]
 pub async fn list_accounts(app: AppHandle, state: State<'_, SidecarState>) -> Result<Vec<AccountInfo>, CommandError> {
+    if let Some(accounts) = crate::accounts::known_accounts() {
+        return Ok(accounts);
+    }
+
     // Get the current auth status via get_status
     let status = get_status(app, state).await.unwrap_or_else(|_| default_status_response());
     
-    // For now, we support a single account. Return account if username is available.
+    // Before any account was recorded, report the bridge's signed-in account. Return account if username is available.
     // We check for username presence rather than logged_in flag since the backend
     // may provide username even before full auth is complete.
     if let Some(username) = &status.auth.username {
@@ -860,10 +1400,14 @@ pub async fn list_accounts(app: AppHandle, state: State<'_, SidecarState>) -> Re
 
 #[tauri::command]
 pub async fn get_account(app: AppHandle, state: State<'_, SidecarState>, id: String) -> Result<Option<AccountInfo>, CommandError> {
+    if let Some(accounts) = crate::accounts::known_accounts() {
+        return Ok(accounts.into_iter().find(|a| a.id.eq_ignore_ascii_case(&id)));
+    }
+
     // Get the current auth status
     let status = get_status(app, state).await.unwrap_or_else(|_| default_status_response());
     
-    // For single-account mode, return the account if username matches the requested ID
+    // Before any account was recorded, return the account if username matches the requested ID
     if let Some(username) = &status.auth.username {
         if !username.is_empty() && (id == *username) {
             let account = AccountInfo {
@@ -884,45 +1428,42 @@ pub async fn get_account(app: AppHandle, state: State<'_, SidecarState>, id: Str
 #[tauri::command]
 #[allow(dead_code)]
 pub async fn check_mount_status(app: AppHandle, state: State<'_, SidecarState>) -> Result<Option<String>, CommandError> {
-    let status = get_status(app.clone(), state).await.unwrap_or_else(|_| default_status_response());
-    let target_uri = format!("dav://localhost:{}", status.config.webdav.port);
-
-    #[cfg(target_os = "linux")]
-    {
-        let normalized_target = if target_uri.ends_with('/') {
-            target_uri.clone()
-        } else {
-            format!("{}/", target_uri)
-        };
-
-        let mounts = get_cached_mounts();
-        for m in mounts.iter() {
-            let root_file: gio::File = m.root();
-            let uri = root_file.uri().to_string();
-            let normalized_uri = if uri.ends_with('/') {
-                uri.clone()
-            } else {
-                format!("{}/", uri)
-            };
+    crate::operations::dedup(&app, "check_mount_status", find_mount(app.clone(), state)).await
+}
 
-            // Emit intermediate results to the UI
-            app.emit("mount:status", format!("Checking mount: {}", uri.clone())).unwrap();
+async fn find_mount(app: AppHandle, state: State<'_, SidecarState>) -> Result<Option<String>, CommandError> {
+    let status = get_status(app.clone(), state).await.unwrap_or_else(|_| default_status_response());
+    let target_uri = local_dav_uri(&status);
+    // Where mounts cannot be listed, the drive isn't known to be mounted
+    let Ok(mounts) = app.state::<crate::mount_provider::MountProviderState>().get().list() else {
+        return Ok(None);
+    };
+    let found = crate::mount_provider::locate(mounts, &target_uri, |msg| {
+        let _ = app.emit("mount:status", msg);
+    });
+    crate::lifecycle::observe_mount(&app, found.is_some());
+    Ok(found.map(|m| m.name))
+}
 
-            if normalized_uri == normalized_target {
-                let name = m.name();
-                return Ok(Some(name.to_string()));
-            }
+/// Local directory GVFS exposes for the drive, or for the extra mount
+/// `mount_id`, e.g. `/run/user/1000/gvfs/dav:host=localhost,port=8080`, so
+/// it can be used from a terminal or by programs that need a real path.
+/// `None` while it is not mounted or GVFS's FUSE daemon is not running.
+#[tauri::command]
+pub async fn get_mount_local_path(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    mount_id: Option<String>,
+) -> Result<Option<String>, CommandError> {
+    let uri = match mount_id {
+        Some(id) => crate::mounts::mount_uri(&app, &id)?,
+        None => {
+            let status = get_status(app.clone(), state).await.unwrap_or_else(|_| default_status_response());
+            local_dav_uri(&status)
         }
-
-        // Emit final result to the UI
-        app.emit("mount:status", "No matching mount found").unwrap();
-        Ok(None)
-    }
-
-    #[cfg(not(target_os = "linux"))]
-    {
-        Ok(None)
-    }
+    };
+    let (_, path) = crate::mounts::mount_status(&uri);
+    Ok(path)
 }
 
 
@@ -932,7 +1473,7 @@ pub async fn check_mount_status(app: AppHandle, state: State<'_, SidecarState>)
 
 #[cfg(test)]
 #[allow(dead_code)]
-mod test_utils {
+pub(crate) mod test_utils {
     use super::*;
     use std::sync::{Arc, Mutex};
     use std::collections::HashMap;
@@ -989,21 +1530,21 @@ mod test_utils {
 
     /// Mock GIO environment for testing mount operations
     pub struct MockGioEnv {
-        pub mounts: Vec<MockMount>,
+        pub mounts: Mutex<Vec<MockMount>>,
         pub mount_failures: HashMap<String, String>, // URI -> error message
     }
 
     impl MockGioEnv {
         pub fn new() -> Self {
             Self {
-                mounts: Vec::new(),
+                mounts: Mutex::new(Vec::new()),
                 mount_failures: HashMap::new(),
             }
         }
 
         /// Add a mock mount
         pub fn add_mount(&mut self, uri: String, unmountable: bool) {
-            self.mounts.push(MockMount { uri, unmountable });
+            self.mounts.get_mut().unwrap().push(MockMount { uri, unmountable });
         }
 
         /// Mark a URI as unable to mount
@@ -1013,7 +1554,43 @@ mod test_utils {
 
         /// Clear all mounts
         pub fn clear_mounts(&mut self) {
-            self.mounts.clear();
+            self.mounts.get_mut().unwrap().clear();
+        }
+    }
+
+    /// Mounting adds a mount rooted at the URI, as GIO reports it
+    impl crate::mount_provider::MountProvider for MockGioEnv {
+        fn mount<'a>(&'a self, uri: &'a str, _trust_local_certificate: bool) -> futures_util::future::BoxFuture<'a, Result<(), CommandError>> {
+            let result = match self.mount_failures.get(uri) {
+                Some(error) => Err(CommandError::GioError(format!("Failed to mount: {}", error))),
+                None => {
+                    self.mounts.lock().unwrap().push(MockMount { uri: format!("{}/", uri.trim_end_matches('/')), unmountable: true });
+                    Ok(())
+                }
+            };
+            Box::pin(std::future::ready(result))
+        }
+
+        fn unmount<'a>(&'a self, uri: &'a str) -> futures_util::future::BoxFuture<'a, Result<(), CommandError>> {
+            let mut mounts = self.mounts.lock().unwrap();
+            let before = mounts.len();
+            mounts.retain(|m| m.uri != uri);
+            let result = if mounts.len() < before {
+                Ok(())
+            } else {
+                Err(CommandError::GioError("Failed to unmount: Mount not found".into()))
+            };
+            Box::pin(std::future::ready(result))
+        }
+
+        fn list(&self) -> Result<Vec<crate::mount_provider::MountEntry>, CommandError> {
+            Ok(self
+                .mounts
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|m| crate::mount_provider::MountEntry { uri: m.uri.clone(), name: m.uri.clone(), can_unmount: m.unmountable })
+                .collect())
         }
     }
 
@@ -1033,6 +1610,7 @@ mod test_utils {
                 } else {
                     None
                 },
+                adopted: false,
             },
             auth: AuthStatus {
                 logged_in: false,
@@ -1057,6 +1635,8 @@ mod test_utils {
                 auto_start: Some(false),
             },
             log_file: "/tmp/test.log".to_string(),
+            tls: None,
+            security_warning: None,
         }
     }
 }
@@ -1069,6 +1649,16 @@ mod test_utils {
 mod tests {
     use super::*;
 
+    /// Whether `mount_provider::find_mount` picks a mount of `target`, and
+    /// if so whether it can be unmounted.
+    fn find_mount_by_uri(mounts: Vec<(String, bool)>, target: &str) -> Option<bool> {
+        let mounts: Vec<_> = mounts
+            .into_iter()
+            .map(|(uri, can_unmount)| crate::mount_provider::MountEntry { name: uri.clone(), uri, can_unmount })
+            .collect();
+        crate::mount_provider::find_mount(&mounts, target).map(|m| m.can_unmount)
+    }
+
     // ========================================================================
     // Test: Find Mount by URI
     // ========================================================================
@@ -1130,6 +1720,7 @@ mod tests {
         assert!(should_open_with_path("/some/path"));
         assert!(should_open_with_path("file:///some/path"));
         assert!(should_open_with_path("dav://localhost:12345"));
+        assert!(should_open_with_path("davs://localhost:12345"));
         assert!(!should_open_with_path("http://example.com"));
     }
 
@@ -1155,6 +1746,16 @@ mod tests {
         assert_eq!(v[0], "path:dav://localhost:12345");
     }
 
+    #[test]
+    fn test_path_in_mount_resolves_below_the_mounted_root() {
+        let mount = "/run/user/1000/gvfs/dav:host=localhost,port=8080";
+        assert_eq!(path_in_mount("/", mount, "/Docs/a.txt"), Some(format!("{}/Docs/a.txt", mount)));
+        assert_eq!(path_in_mount("/Docs", mount, "/Docs/a.txt"), Some(format!("{}/a.txt", mount)));
+        assert_eq!(path_in_mount("/Docs/", mount, "Docs"), Some(mount.to_string()));
+        assert_eq!(path_in_mount("/Docs", mount, "/Docsx/a.txt"), None);
+        assert_eq!(path_in_mount("/Docs", mount, "/Photos"), None);
+    }
+
     #[test]
     fn test_choose_open_uri_prefers_converted_dav_for_http_server_url() {
         let mut status = default_status_response();
@@ -1226,6 +1827,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_port_change_checks() {
+        assert!(matches!(validate_port(80), Err(CommandError::InvalidPort(_))));
+        assert!(validate_port(8080).is_ok());
+
+        let taken = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let hosts = vec!["127.0.0.1".to_string()];
+        assert!(matches!(port_available(&hosts, port), Err(CommandError::PortInUse(p)) if p == port));
+        drop(taken);
+        assert!(port_available(&hosts, port).is_ok());
+    }
+
+    #[test]
+    fn test_local_dav_uri_follows_https() {
+        use crate::sidecar::test_utils::create_test_status;
+
+        let mut status = create_test_status(true, Some(1));
+        assert_eq!(local_dav_uri(&status), "dav://localhost:8080");
+        status.config.webdav.https = true;
+        assert_eq!(local_dav_uri(&status), "davs://localhost:8080");
+    }
+
+    #[test]
+    fn test_local_dav_uri_includes_remote_path() {
+        use crate::sidecar::test_utils::create_test_status;
+
+        let mut status = create_test_status(true, Some(1));
+        status.config.remote_path = "/My Files/".to_string();
+        assert_eq!(local_dav_uri(&status), "dav://localhost:8080/My%20Files");
+        status.config.remote_path = String::new();
+        assert_eq!(local_dav_uri(&status), "dav://localhost:8080");
+    }
+
     #[test]
     fn test_mount_error_handling_already_mounted() {
         // Test that "already mounted" errors are treated as success
@@ -1342,9 +1977,8 @@ mod tests {
         let state = create_test_state();
         
         // Initially, no PID should be stored
-        let lock = state.pid.lock().unwrap();
-        assert!(lock.is_none(), "SidecarState should start with no PID");
-        drop(lock);
+        let pid = tauri::async_runtime::block_on(state.pid());
+        assert!(pid.is_none(), "SidecarState should start with no PID");
     }
 
     /// Test: SidecarState allows setting and retrieving PID
@@ -1358,16 +1992,11 @@ mod tests {
         
         // Store a test PID
         let test_pid = 12345u32;
-        {
-            let mut lock = state.pid.lock().unwrap();
-            *lock = Some(test_pid);
-        }
+        assert!(tauri::async_runtime::block_on(state.claim(test_pid, false)));
         
         // Retrieve and verify
-        {
-            let lock = state.pid.lock().unwrap();
-            assert_eq!(*lock, Some(test_pid), "PID should be retrievable from state");
-        }
+        let pid = tauri::async_runtime::block_on(state.pid());
+        assert_eq!(pid, Some(test_pid), "PID should be retrievable from state");
     }
 
     /// Test: CommandError serializes correctly for client transmission
@@ -1400,6 +2029,63 @@ mod tests {
             CommandError::ServerNotRunning,
             CommandError::GioError("test".to_string()),
             CommandError::IoError("test".to_string()),
+            CommandError::InvalidPassphrase("test".to_string()),
+            CommandError::TravelModeActive,
+            CommandError::TransferNotFound(1),
+            CommandError::TlsError("test".to_string()),
+            CommandError::WebDavError("test".to_string()),
+            CommandError::RemotePathNotFound("/test".to_string()),
+            CommandError::InvalidRemotePath("test".to_string()),
+            CommandError::OperationCancelled,
+            CommandError::InvalidSharingConfig("test".to_string()),
+            CommandError::InvalidOnboardingStep("test".to_string()),
+            CommandError::MountNotFound("test".to_string()),
+            CommandError::InvalidMountSettings("test".to_string()),
+            CommandError::AccountNotFound("test".to_string()),
+            CommandError::InvalidQuotaSettings("test".to_string()),
+            CommandError::InvalidCachePolicy("test".to_string()),
+            CommandError::FeatureDisabled("test".to_string()),
+            CommandError::SyncPairNotFound("test".to_string()),
+            CommandError::InvalidSyncPair("test".to_string()),
+            CommandError::InvalidDropFolder("test".to_string()),
+            CommandError::ConflictNotFound(1),
+            CommandError::InvalidShareLink("test".to_string()),
+            CommandError::InvalidWebdavCredentials("test".to_string()),
+            CommandError::InvalidTransport("test".to_string()),
+            CommandError::InvalidBindAddress("test".to_string()),
+            CommandError::StatusParseError(Box::new(crate::status_schema::StatusParseError {
+                message: "test".to_string(),
+                schema_version: 3,
+                raw: "{}".to_string(),
+            })),
+            CommandError::IncompatibleSidecar("test".to_string()),
+            CommandError::SidecarUpdateFailed("test".to_string()),
+            CommandError::AppUpdateFailed("test".to_string()),
+            CommandError::CrashReportNotFound("test".to_string()),
+            CommandError::InvalidProxyConfig("test".to_string()),
+            CommandError::InvalidBenchmarkSize("test".to_string()),
+            CommandError::UnsupportedLocale("test".to_string()),
+            CommandError::LifecycleBusy("test".to_string()),
+            CommandError::CommandTimedOut("test".to_string()),
+            CommandError::CommandNotFound(1),
+            CommandError::InvalidCommandTimeout("test".to_string()),
+            CommandError::InvalidReadaheadSettings("x".into()),
+            CommandError::PendingUploadNotFound(1),
+            CommandError::DatabaseError("test".to_string()),
+            CommandError::InvalidConfig("test".to_string()),
+            CommandError::InvalidSettingsBackup("test".to_string()),
+            CommandError::InvalidIdlePolicy("test".to_string()),
+            CommandError::InvalidAppAccess("test".to_string()),
+            CommandError::NoFileManager("dav://localhost:8080".to_string()),
+            CommandError::ThumbnailUnavailable("/a.txt".to_string()),
+            CommandError::InvalidBackupFolder("test".to_string()),
+            CommandError::BackupJobNotFound("test".to_string()),
+            CommandError::InvalidBackupJob("test".to_string()),
+            CommandError::InvalidIgnorePattern("test".to_string()),
+            CommandError::ListingExpired("test".to_string()),
+            CommandError::AdoptionRefused("read-only share".to_string()),
+            CommandError::TravelModeLocked(60),
+            CommandError::ReadOnlyShare,
         ];
         
         // Each error should have a non-empty error code
@@ -0,0 +1,314 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tokio::sync::oneshot;
+
+use crate::sidecar::{CommandError, ConfigStatus, StatusResponse};
+
+// ============================================================================
+// Sidecar control channel
+// ============================================================================
+//
+// Status, account, config and cache requests go to a long-lived `rpc`
+// process of the bridge CLI, which answers JSON-RPC 2.0 requests on its
+// stdin/stdout, one message per line. It is started on the first request and
+// restarted on the next one if it exits; switching accounts resets it so it
+// picks up the new profile. Lines on stdout that are not responses (stray
+// log output) are skipped rather than parsed, and requests still waiting
+// when the process exits fail instead of hanging until their timeout. Error
+// responses become the `CommandError` matching the bridge's error code.
+// Requests are supervised like CLI runs (see `sidecar_commands`), so their
+// timeout is configurable per method and they can be cancelled.
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Reply = Result<Value, RpcFailure>;
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Reply>>>>;
+
+#[derive(Serialize)]
+struct Request<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: Value,
+    /// Trace ID of the operation, for the bridge's logs (an extension)
+    #[serde(rename = "traceId", skip_serializing_if = "Option::is_none")]
+    trace_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorObject {
+    message: String,
+    #[serde(default)]
+    data: Option<ErrorData>,
+}
+
+#[derive(Deserialize)]
+struct ErrorData {
+    code: Option<String>,
+}
+
+/// An error response: the bridge's message and, for application errors,
+/// its error code (`error.data.code`).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RpcFailure {
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl From<RpcFailure> for CommandError {
+    /// The matching `CommandError` for the bridge's error code, so callers
+    /// can tell e.g. a lost session from a broken bridge.
+    fn from(failure: RpcFailure) -> Self {
+        let RpcFailure { code, message } = failure;
+        match code.as_deref() {
+            Some("AUTH_FAILED" | "AUTHENTICATION_ERROR" | "NOT_AUTHENTICATED" | "INVALID_CREDENTIALS" | "TOKEN_EXPIRED") => {
+                CommandError::AuthFailed(message)
+            }
+            Some("INVALID_EMAIL") => CommandError::InvalidEmail(message),
+            Some("INVALID_PORT") => CommandError::InvalidPort(message),
+            Some("INVALID_CONFIG" | "VALIDATION_ERROR") => CommandError::InvalidConfig(message),
+            Some("INVALID_PATH") => CommandError::InvalidRemotePath(message),
+            Some("NOT_FOUND") => CommandError::RemotePathNotFound(message),
+            Some("SERVER_NOT_RUNNING") => CommandError::ServerNotRunning,
+            Some("SIDECAR_NOT_RUNNING") => CommandError::SidecarNotRunning,
+            Some("TIMEOUT") => CommandError::CommandTimedOut(message),
+            Some("IO_ERROR") => CommandError::IoError(message),
+            _ => CommandError::SidecarCommandFailed(message),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: Option<u64>,
+    #[serde(default)]
+    result: Value,
+    error: Option<ErrorObject>,
+}
+
+/// The request id and outcome of a response line, or `None` for anything
+/// that is not a JSON-RPC response to one of our requests.
+pub(crate) fn parse_response(line: &str) -> Option<(u64, Reply)> {
+    let response: Response = serde_json::from_str(line.trim()).ok()?;
+    if response.jsonrpc != "2.0" {
+        return None;
+    }
+    let id = response.id?;
+    Some(match response.error {
+        Some(error) => (id, Err(RpcFailure { code: error.data.and_then(|d| d.code), message: error.message })),
+        None => (id, Ok(response.result)),
+    })
+}
+
+struct Connection {
+    id: u64,
+    child: CommandChild,
+    pending: Pending,
+}
+
+#[derive(Default)]
+pub struct SidecarClient {
+    connection: Mutex<Option<Connection>>,
+    next_id: AtomicU64,
+}
+
+/// Result of `auth.logout`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutResult {
+    pub logged_out: bool,
+}
+
+impl SidecarClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop the control process; the next request starts a new one.
+    pub fn reset(&self) {
+        if let Some(connection) = self.connection.lock().unwrap().take() {
+            let _ = connection.child.kill();
+        }
+    }
+
+    fn connect(&self, app: &AppHandle) -> Result<Connection, CommandError> {
+        let (mut rx, child) = crate::sandbox::sidecar_command(app)?
+            .args(["rpc"])
+            .spawn()
+            .map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let pending: Pending = Arc::default();
+        log::debug!("Started sidecar control process (pid {})", child.pid());
+
+        let app = app.clone();
+        let replies = pending.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    CommandEvent::Stdout(bytes) => {
+                        let line = String::from_utf8_lossy(&bytes);
+                        match parse_response(&line) {
+                            Some((request, reply)) => {
+                                if let Some(tx) = replies.lock().unwrap().remove(&request) {
+                                    let _ = tx.send(reply);
+                                }
+                            }
+                            None => log::debug!("sidecar rpc: {}", line.trim_end()),
+                        }
+                    }
+                    CommandEvent::Stderr(bytes) => {
+                        log::debug!("sidecar rpc: {}", String::from_utf8_lossy(&bytes).trim_end());
+                    }
+                    CommandEvent::Terminated(payload) => {
+                        log::debug!("Sidecar control process exited ({:?})", payload.code);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            // Dropping the senders fails every request still waiting
+            replies.lock().unwrap().clear();
+            let state = app.state::<SidecarClient>();
+            let mut connection = state.connection.lock().unwrap();
+            if connection.as_ref().is_some_and(|c| c.id == id) {
+                *connection = None;
+            }
+        });
+
+        Ok(Connection { id, child, pending })
+    }
+
+    /// Send a request and wait for its result.
+    pub async fn call<T: DeserializeOwned>(&self, app: &AppHandle, method: &str, params: Value) -> Result<T, CommandError> {
+        crate::trace::traced(app, method, self.request(app, method, params)).await
+    }
+
+    async fn request<T: DeserializeOwned>(&self, app: &AppHandle, method: &str, params: Value) -> Result<T, CommandError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        let pending = {
+            let mut connection = self.connection.lock().unwrap();
+            if connection.is_none() {
+                *connection = Some(self.connect(app)?);
+            }
+            let conn = connection.as_mut().expect("connection was just set");
+            conn.pending.lock().unwrap().insert(id, tx);
+
+            let mut line = serde_json::to_vec(&Request { jsonrpc: "2.0", id, method, params, trace_id: crate::trace::current() }).expect("request serializes");
+            line.push(b'\n');
+            if let Err(e) = conn.child.write(&line) {
+                if let Some(conn) = connection.take() {
+                    let _ = conn.child.kill();
+                }
+                return Err(CommandError::SidecarCommandFailed(format!("Failed to send {}: {}", method, e)));
+            }
+            conn.pending.clone()
+        };
+
+        let value = match crate::sidecar_commands::supervise(app, method, REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(Ok(value))) => value,
+            Ok(Ok(Err(failure))) => return Err(failure.into()),
+            Ok(Err(_)) => {
+                return Err(CommandError::SidecarCommandFailed(format!("The bridge exited while handling {}", method)))
+            }
+            Err(e) => {
+                pending.lock().unwrap().remove(&id);
+                return Err(e);
+            }
+        };
+        serde_json::from_value(value)
+            .map_err(|e| CommandError::SidecarCommandFailed(format!("Unexpected {} response: {}", method, e)))
+    }
+
+    /// The sidecar's status, in any schema version it may speak; see
+    /// `status_schema`.
+    pub async fn status(&self, app: &AppHandle) -> Result<StatusResponse, CommandError> {
+        let payload = self.call(app, "status", Value::Null).await?;
+        Ok(crate::status_schema::parse_and_report(app, payload)?)
+    }
+
+    pub async fn logout(&self, app: &AppHandle) -> Result<LogoutResult, CommandError> {
+        self.call(app, "auth.logout", Value::Null).await
+    }
+
+    pub async fn config(&self, app: &AppHandle) -> Result<ConfigStatus, CommandError> {
+        self.call(app, "config.get", Value::Null).await
+    }
+
+    pub async fn set_config(&self, app: &AppHandle, key: &str, value: &str) -> Result<(), CommandError> {
+        self.call::<Value>(app, "config.set", serde_json::json!({ "key": key, "value": value })).await?;
+        Ok(())
+    }
+
+    /// Ask the running bridge to drop its folder and path caches.
+    pub async fn purge_cache(&self, app: &AppHandle) -> Result<(), CommandError> {
+        self.call::<Value>(app, "cache.purge", Value::Null).await?;
+        Ok(())
+    }
+}
+
+/// The bridge's own configuration (`config.json` as the CLI sees it).
+#[tauri::command]
+pub async fn get_bridge_config(app: AppHandle, client: State<'_, SidecarClient>) -> Result<ConfigStatus, CommandError> {
+    client.config(&app).await
+}
+
+/// Set one bridge setting by dotted key, e.g. `cache.ttlSeconds`, as
+/// `config set` does. Settings the bridge reads at startup apply the next
+/// time it starts.
+#[tauri::command]
+pub async fn set_bridge_config(app: AppHandle, client: State<'_, SidecarClient>, key: String, value: String) -> Result<(), CommandError> {
+    client.set_config(&app, &key, &value).await?;
+    crate::status::invalidate(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let (id, reply) = parse_response(r#"{"jsonrpc":"2.0","id":7,"result":{"loggedOut":true}}"#).unwrap();
+        assert_eq!(id, 7);
+        assert_eq!(reply, Ok(serde_json::json!({"loggedOut": true})));
+
+        let (_, reply) = parse_response("{\"jsonrpc\":\"2.0\",\"id\":3,\"result\":null}\n").unwrap();
+        assert_eq!(reply, Ok(Value::Null));
+
+        let (id, reply) =
+            parse_response(r#"{"jsonrpc":"2.0","id":2,"error":{"code":-32000,"message":"Not logged in","data":{"code":"AUTH"}}}"#)
+                .unwrap();
+        assert_eq!(id, 2);
+        assert_eq!(reply, Err(RpcFailure { code: Some("AUTH".into()), message: "Not logged in".into() }));
+
+        let (_, reply) = parse_response(r#"{"jsonrpc":"2.0","id":4,"error":{"code":-32601,"message":"Method not found: x"}}"#).unwrap();
+        assert_eq!(reply, Err(RpcFailure { code: None, message: "Method not found: x".into() }));
+    }
+
+    #[test]
+    fn test_rpc_failure_keeps_the_error_code() {
+        let failure = |code: Option<&str>| RpcFailure { code: code.map(str::to_string), message: "failed".into() };
+        assert!(matches!(CommandError::from(failure(Some("NOT_AUTHENTICATED"))), CommandError::AuthFailed(m) if m == "failed"));
+        assert!(matches!(CommandError::from(failure(Some("INVALID_CONFIG"))), CommandError::InvalidConfig(_)));
+        assert!(matches!(CommandError::from(failure(Some("SERVER_NOT_RUNNING"))), CommandError::ServerNotRunning));
+        assert!(matches!(CommandError::from(failure(Some("UNKNOWN_ERROR"))), CommandError::SidecarCommandFailed(_)));
+        assert!(matches!(CommandError::from(failure(None)), CommandError::SidecarCommandFailed(_)));
+    }
+
+    #[test]
+    fn test_parse_response_skips_other_output() {
+        assert!(parse_response("info: Debug mode enabled").is_none());
+        assert!(parse_response(r#"{"level":"info","message":"{not a response}"}"#).is_none());
+        assert!(parse_response(r#"{"jsonrpc":"1.0","id":1,"result":null}"#).is_none());
+        assert!(parse_response(r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}}"#).is_none());
+    }
+}
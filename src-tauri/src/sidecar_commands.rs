@@ -0,0 +1,225 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::process::{Command, CommandEvent};
+use tokio::sync::oneshot;
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Sidecar command runs
+// ============================================================================
+//
+// Every request to the bridge, whether a one-shot CLI invocation (`auth
+// login`, `trash list`, ...) or a call on the `rpc` control process, runs
+// through `supervise`: it gets an id listed by `list_running_commands`, a
+// timeout, and can be aborted with `cancel_command`. A one-shot process that
+// times out or is cancelled is killed rather than left running. Callers pass
+// a default timeout; the user can override it per command name in the
+// `commandTimeouts` config section (seconds).
+
+const CONFIG_KEY: &str = "commandTimeouts";
+
+const MAX_TIMEOUT_SECS: u64 = 3600;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningCommand {
+    pub id: u64,
+    /// e.g. `auth login` or `status`
+    pub name: String,
+    /// Unix timestamp (seconds) of the start
+    pub started_at: u64,
+    pub timeout_seconds: u64,
+}
+
+#[derive(Default)]
+pub struct RunningCommands {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, (RunningCommand, oneshot::Sender<()>)>>,
+}
+
+impl RunningCommands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn register(&self, name: &str, timeout: Duration) -> (u64, oneshot::Receiver<()>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (cancel, cancelled) = oneshot::channel();
+        let command = RunningCommand {
+            id,
+            name: name.to_string(),
+            started_at: crate::sidecar::unix_now(),
+            timeout_seconds: timeout.as_secs(),
+        };
+        self.running.lock().unwrap().insert(id, (command, cancel));
+        (id, cancelled)
+    }
+
+    fn finish(&self, id: u64) {
+        self.running.lock().unwrap().remove(&id);
+    }
+
+    fn cancel(&self, id: u64) -> bool {
+        match self.running.lock().unwrap().remove(&id) {
+            Some((_, cancel)) => cancel.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    fn list(&self) -> Vec<RunningCommand> {
+        let mut commands: Vec<_> = self.running.lock().unwrap().values().map(|(c, _)| c.clone()).collect();
+        commands.sort_by_key(|c| c.id);
+        commands
+    }
+}
+
+/// Output of a one-shot CLI invocation.
+#[derive(Debug, Default)]
+pub(crate) struct CommandOutput {
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+fn overrides() -> BTreeMap<String, u64> {
+    read_config_section(CONFIG_KEY)
+}
+
+fn effective_timeout(overrides: &BTreeMap<String, u64>, name: &str, default: Duration) -> Duration {
+    overrides.get(name).map(|secs| Duration::from_secs(*secs)).unwrap_or(default)
+}
+
+/// Run `future` as command `name`, giving up after its timeout or when it is
+/// cancelled. The future is dropped then.
+pub(crate) async fn supervise<F: Future>(app: &AppHandle, name: &str, default_timeout: Duration, future: F) -> Result<F::Output, CommandError> {
+    let timeout = effective_timeout(&overrides(), name, default_timeout);
+    let commands = app.state::<RunningCommands>();
+    let (id, cancelled) = commands.register(name, timeout);
+    let result = tokio::select! {
+        output = future => Ok(output),
+        _ = tokio::time::sleep(timeout) => {
+            log::warn!("{} timed out after {}s", name, timeout.as_secs());
+            Err(CommandError::CommandTimedOut(format!("{} after {}s", name, timeout.as_secs())))
+        }
+        Ok(()) = cancelled => {
+            log::info!("Cancelled {}", name);
+            Err(CommandError::OperationCancelled)
+        }
+    };
+    commands.finish(id);
+    result
+}
+
+/// Spawn a one-shot bridge CLI `command` and collect its output. The process
+/// is killed if it outlives its timeout or is cancelled.
+pub(crate) async fn run_sidecar_command(app: &AppHandle, command: Command, name: &str, default_timeout: Duration) -> Result<CommandOutput, CommandError> {
+    let (mut rx, child) = command.spawn().map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?;
+    let collect = async move {
+        let mut output = CommandOutput::default();
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(line) => {
+                    output.stdout.push_str(&String::from_utf8_lossy(&line));
+                    output.stdout.push('\n');
+                }
+                CommandEvent::Stderr(line) => {
+                    output.stderr.push_str(&String::from_utf8_lossy(&line));
+                    output.stderr.push('\n');
+                }
+                CommandEvent::Terminated(payload) => output.code = payload.code,
+                _ => {}
+            }
+        }
+        output
+    };
+    match supervise(app, name, default_timeout, collect).await {
+        Ok(output) => Ok(output),
+        Err(e) => {
+            if let Err(kill) = child.kill() {
+                log::warn!("Failed to kill {}: {}", name, kill);
+            }
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_running_commands(app: AppHandle) -> Result<Vec<RunningCommand>, CommandError> {
+    Ok(app.state::<RunningCommands>().list())
+}
+
+/// Abort a running command; fails if it already finished.
+#[tauri::command]
+pub async fn cancel_command(app: AppHandle, id: u64) -> Result<(), CommandError> {
+    if !app.state::<RunningCommands>().cancel(id) {
+        return Err(CommandError::CommandNotFound(id));
+    }
+    Ok(())
+}
+
+/// Timeout overrides, in seconds, by command name.
+#[tauri::command]
+pub async fn get_command_timeouts() -> Result<BTreeMap<String, u64>, CommandError> {
+    Ok(overrides())
+}
+
+/// Override the timeout of command `name`; `None` restores its default.
+#[tauri::command]
+pub async fn set_command_timeout(name: String, seconds: Option<u64>) -> Result<BTreeMap<String, u64>, CommandError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(CommandError::InvalidCommandTimeout("No command name".into()));
+    }
+    let mut overrides = overrides();
+    match seconds {
+        Some(secs) if secs == 0 || secs > MAX_TIMEOUT_SECS => {
+            return Err(CommandError::InvalidCommandTimeout(format!("{}s is not between 1 and {}s", secs, MAX_TIMEOUT_SECS)));
+        }
+        Some(secs) => {
+            overrides.insert(name, secs);
+        }
+        None => {
+            overrides.remove(&name);
+        }
+    }
+    write_config_section(CONFIG_KEY, &overrides)?;
+    Ok(overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_timeout_prefers_override() {
+        let overrides = BTreeMap::from([("auth login".to_string(), 600)]);
+        assert_eq!(effective_timeout(&overrides, "auth login", Duration::from_secs(120)), Duration::from_secs(600));
+        assert_eq!(effective_timeout(&overrides, "status", Duration::from_secs(10)), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_cancel_signals_and_unregisters() {
+        let commands = RunningCommands::new();
+        let (first, mut cancelled) = commands.register("auth login", Duration::from_secs(120));
+        let (second, _) = commands.register("status", Duration::from_secs(10));
+        assert_eq!(commands.list().iter().map(|c| c.id).collect::<Vec<_>>(), vec![first, second]);
+
+        assert!(commands.cancel(first));
+        assert_eq!(cancelled.try_recv(), Ok(()));
+        assert!(!commands.cancel(first));
+        commands.finish(second);
+        assert!(commands.list().is_empty());
+    }
+}
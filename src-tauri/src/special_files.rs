@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::dav::DavClient;
+use crate::remote::FileTransferResult;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Symlinks and special files
+// ============================================================================
+//
+// Proton Drive stores regular files and folders only, so sync, the drop
+// folder and the scheduled backups decide explicitly what to do with
+// everything else instead of failing half way:
+//
+// - symlinks follow `symlinks`: `skip` leaves them out, `follow` uploads
+//   what they point to (a folder link is descended into, once), and
+//   `materialize` uploads the link itself as a small file holding its
+//   target path. Either way a link replaced by a remote change becomes a
+//   regular file, except that `follow` writes through a link to a file;
+// - sockets, FIFOs and devices are always left out;
+// - names longer than `maxNameLength` bytes, which the drive would reject,
+//   are left out.
+//
+// Whatever is left out is listed with the reason in the run's report (the
+// sync report, the backup summary) or, for the drop folder, announced as
+// `drop-folder:skipped`.
+
+const CONFIG_KEY: &str = "specialFiles";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SymlinkPolicy {
+    #[default]
+    Skip,
+    Follow,
+    Materialize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SpecialFileSettings {
+    pub symlinks: SymlinkPolicy,
+    /// Longest file name uploaded, in bytes of UTF-8
+    pub max_name_length: usize,
+}
+
+impl Default for SpecialFileSettings {
+    fn default() -> Self {
+        Self { symlinks: SymlinkPolicy::Skip, max_name_length: 255 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    Symlink,
+    /// A followed link whose target is missing
+    BrokenSymlink,
+    /// A followed folder link leading back into a folder already visited
+    SymlinkLoop,
+    /// A socket, FIFO or device
+    Special,
+    NameTooLong,
+    /// A name that reads as encoded by `name_mapping` without being so
+    UnmappableName,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedItem {
+    /// Path relative to the folder being uploaded, or the local path
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+/// What a local directory entry is uploaded as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LocalKind {
+    File,
+    Dir,
+    /// A symlink uploaded as a file holding its target
+    Link,
+}
+
+pub(crate) fn settings() -> SpecialFileSettings {
+    read_config_section(CONFIG_KEY)
+}
+
+fn kind_of(file_type: std::fs::FileType) -> Result<LocalKind, SkipReason> {
+    if file_type.is_file() {
+        Ok(LocalKind::File)
+    } else if file_type.is_dir() {
+        Ok(LocalKind::Dir)
+    } else {
+        Err(SkipReason::Special)
+    }
+}
+
+/// How to treat the entry at `path`, or why it is left out.
+pub(crate) fn classify(path: &Path, settings: &SpecialFileSettings) -> Result<LocalKind, SkipReason> {
+    let name_length = path.file_name().map_or(0, |n| n.to_string_lossy().len());
+    if name_length > settings.max_name_length {
+        return Err(SkipReason::NameTooLong);
+    }
+    let metadata = std::fs::symlink_metadata(path).map_err(|_| SkipReason::BrokenSymlink)?;
+    if !metadata.file_type().is_symlink() {
+        return kind_of(metadata.file_type());
+    }
+    match settings.symlinks {
+        SymlinkPolicy::Skip => Err(SkipReason::Symlink),
+        SymlinkPolicy::Follow => std::fs::metadata(path).map_err(|_| SkipReason::BrokenSymlink).and_then(|m| kind_of(m.file_type())),
+        SymlinkPolicy::Materialize => Ok(LocalKind::Link),
+    }
+}
+
+/// Metadata of the local file `path` as uploaded: the link's own for a
+/// materialized link, else the file's.
+pub(crate) fn metadata(path: &Path, settings: &SpecialFileSettings) -> std::io::Result<std::fs::Metadata> {
+    match settings.symlinks {
+        SymlinkPolicy::Materialize => std::fs::symlink_metadata(path),
+        _ => std::fs::metadata(path),
+    }
+}
+
+/// Where a download to `path` is written: through a followed link to a
+/// file, else `path` itself.
+pub(crate) fn download_target(path: &Path, settings: &SpecialFileSettings) -> std::path::PathBuf {
+    let is_link = std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    if is_link && settings.symlinks == SymlinkPolicy::Follow {
+        if let Ok(target) = std::fs::canonicalize(path) {
+            if target.is_file() {
+                return target;
+            }
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Upload `local` to `remote` the way the policy says: a materialized link
+/// as its target path, anything else through the regular upload.
+pub(crate) async fn upload(
+    app: &AppHandle,
+    client: &DavClient,
+    local: &Path,
+    remote: &str,
+    overwrite: bool,
+) -> Result<FileTransferResult, CommandError> {
+    let settings = settings();
+    if classify(local, &settings) != Ok(LocalKind::Link) {
+        return crate::remote::upload_local_file(app, client, local, remote, overwrite).await;
+    }
+    if !overwrite && client.stat(remote).await?.is_some() {
+        return Err(CommandError::WebDavError(format!("{} already exists", remote)));
+    }
+    let target = tokio::fs::read_link(local).await?.to_string_lossy().into_owned().into_bytes();
+    let bytes = target.len() as u64;
+    let body = futures_util::stream::once(async move { Ok::<_, std::io::Error>(bytes::Bytes::from(target)) });
+    client.put(remote, body, Some(bytes)).await?;
+    Ok(FileTransferResult { path: remote.to_string(), bytes })
+}
+
+#[tauri::command]
+pub async fn get_special_file_settings() -> Result<SpecialFileSettings, CommandError> {
+    Ok(settings())
+}
+
+/// Takes effect from the next sync, backup or drop folder upload.
+#[tauri::command]
+pub async fn set_special_file_settings(settings: SpecialFileSettings) -> Result<SpecialFileSettings, CommandError> {
+    if settings.max_name_length == 0 {
+        return Err(CommandError::InvalidConfig("The name length limit must be at least 1".into()));
+    }
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_by_policy() {
+        let dir = std::env::temp_dir().join(format!("special-files-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.txt");
+        std::fs::write(&file, b"a").unwrap();
+        let link = dir.join("link");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+        let broken = dir.join("broken");
+        std::os::unix::fs::symlink(dir.join("missing"), &broken).unwrap();
+
+        let with = |symlinks| SpecialFileSettings { symlinks, ..Default::default() };
+        assert_eq!(classify(&file, &with(SymlinkPolicy::Skip)), Ok(LocalKind::File));
+        assert_eq!(classify(&dir, &with(SymlinkPolicy::Skip)), Ok(LocalKind::Dir));
+        assert_eq!(classify(&link, &with(SymlinkPolicy::Skip)), Err(SkipReason::Symlink));
+        assert_eq!(classify(&link, &with(SymlinkPolicy::Follow)), Ok(LocalKind::File));
+        assert_eq!(classify(&broken, &with(SymlinkPolicy::Follow)), Err(SkipReason::BrokenSymlink));
+        assert_eq!(classify(&broken, &with(SymlinkPolicy::Materialize)), Ok(LocalKind::Link));
+        let short = SpecialFileSettings { max_name_length: 4, ..Default::default() };
+        assert_eq!(classify(&file, &short), Err(SkipReason::NameTooLong));
+        assert_eq!(download_target(&link, &with(SymlinkPolicy::Follow)), std::fs::canonicalize(&file).unwrap());
+        assert_eq!(download_target(&link, &with(SymlinkPolicy::Materialize)), link);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::sidecar::{CommandError, StatusResponse};
+
+// ============================================================================
+// Status snapshot
+// ============================================================================
+//
+// Asking the sidecar for its status means a round trip to the bridge CLI, so
+// `get_status` answers from a snapshot instead. The snapshot goes stale when something
+// that shows up in the status happens: the bridge is started, stopped or
+// adopted, the user logs in or out, config.json is written, or the bridge
+// logs a line announcing one of those. A watcher task then re-probes and
+// emits `status:update`, but only when the status actually changed. A slow
+// periodic probe catches changes made from a terminal. A probe that fails
+// leaves the last good snapshot in place, still stale, and the failure is
+// what `get` returns.
+
+/// Fallback probe interval when nothing marked the snapshot stale.
+const IDLE_REFRESH: Duration = Duration::from_secs(30);
+
+/// Coalesces bursts of changes (e.g. stop followed by start) into one probe.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Log lines after which the status is likely to differ.
+const STATUS_MARKERS: &[&str] = &["WebDAV server started", "WebDAV server stopped", "authenticated successfully", "User logged out"];
+
+struct Snapshot {
+    status: StatusResponse,
+    /// Modification time of config.json when the snapshot was taken
+    config_modified: Option<SystemTime>,
+    stale: bool,
+}
+
+#[derive(Default)]
+pub struct StatusCache {
+    snapshot: Mutex<Option<Snapshot>>,
+    /// Serializes probes so concurrent callers share one request
+    probing: tokio::sync::Mutex<()>,
+    changed: Notify,
+}
+
+fn config_modified() -> Option<SystemTime> {
+    let path = crate::sidecar::get_config_file_path().ok()?;
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Whether a sidecar log line means the status is likely to have changed.
+pub fn affects_status(line: &str) -> bool {
+    STATUS_MARKERS.iter().any(|m| line.contains(m)) || crate::notifications::is_session_expired_line(line)
+}
+
+impl StatusCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn fresh(&self) -> Option<StatusResponse> {
+        let snapshot = self.snapshot.lock().unwrap();
+        let snapshot = snapshot.as_ref()?;
+        (!snapshot.stale && snapshot.config_modified == config_modified()).then(|| snapshot.status.clone())
+    }
+
+    /// The current status, probing the sidecar only if the snapshot is stale.
+    pub async fn get(&self, app: &AppHandle) -> Result<StatusResponse, CommandError> {
+        if let Some(status) = self.fresh() {
+            return Ok(status);
+        }
+        let _probing = self.probing.lock().await;
+        if let Some(status) = self.fresh() {
+            return Ok(status);
+        }
+        self.refresh(app).await
+    }
+
+    /// The last status that was probed successfully, however old.
+    pub fn last_good(&self) -> Option<StatusResponse> {
+        self.snapshot.lock().unwrap().as_ref().map(|s| s.status.clone())
+    }
+
+    /// Probe now and emit `status:update` if the result differs from the
+    /// previous snapshot.
+    async fn refresh(&self, app: &AppHandle) -> Result<StatusResponse, CommandError> {
+        let config_modified = config_modified();
+        let status = crate::sidecar::probe_status(app).await?;
+        let previous = self.snapshot.lock().unwrap().replace(Snapshot {
+            status: status.clone(),
+            config_modified,
+            stale: false,
+        });
+        crate::lifecycle::observe_server(app, status.server.running);
+        if previous.is_none_or(|p| p.status != status) {
+            let _ = app.emit("status:update", status.clone());
+        }
+        Ok(status)
+    }
+
+    fn mark_stale(&self) {
+        if let Some(snapshot) = self.snapshot.lock().unwrap().as_mut() {
+            snapshot.stale = true;
+        }
+        self.changed.notify_one();
+    }
+}
+
+/// Note that something affecting the status happened. Cheap; the probe runs
+/// on the watcher task.
+pub fn invalidate(app: &AppHandle) {
+    app.state::<StatusCache>().mark_stale();
+}
+
+/// Long-running task spawned from `setup` that keeps the snapshot current.
+pub async fn watch(app: AppHandle) {
+    let cache = app.state::<StatusCache>();
+    loop {
+        let _ = tokio::time::timeout(IDLE_REFRESH, cache.changed.notified()).await;
+        tokio::time::sleep(DEBOUNCE).await;
+        let _probing = cache.probing.lock().await;
+        let _ = cache.refresh(&app).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affects_status() {
+        assert!(affects_status("2024-01-01 12:00:00 [INFO] WebDAV server started on http://127.0.0.1:40123"));
+        assert!(affects_status("[error] Parent session expired - re-authentication required"));
+        assert!(!affects_status("2024-01-01 12:00:00 [INFO] Handling LOCK /Documents/a.txt"));
+    }
+}
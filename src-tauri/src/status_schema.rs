@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter};
+
+use crate::sidecar::{CommandError, StatusResponse};
+
+// ============================================================================
+// Status schema
+// ============================================================================
+//
+// The sidecar tags its status with a `schemaVersion` (sidecars that predate
+// the tag speak version 1). Versions this app understands are parsed as
+// usual, ignoring fields it does not know. A sidecar outside that range, which
+// happens when the app and the bridge on the host are updated separately,
+// is still parsed on a best-effort basis, and `status:schema_mismatch` tells
+// the UI which side needs updating. A payload that cannot be parsed fails
+// with a `StatusParseError` instead of passing for a stopped bridge:
+// `get_status` returns `STATUS_PARSE_ERROR` with the raw payload in its
+// `details`, and the status snapshot keeps the last status that parsed.
+
+/// Oldest status schema this app parses.
+pub const MIN_SCHEMA_VERSION: u64 = 1;
+/// Newest status schema this app knows.
+pub const SCHEMA_VERSION: u64 = 2;
+
+/// Longest raw payload kept in an error.
+const MAX_RAW_LEN: usize = 4096;
+
+/// Last version reported through `status:schema_mismatch`, so a skewed
+/// sidecar is reported once rather than on every probe.
+static REPORTED_VERSION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusParseError {
+    pub message: String,
+    pub schema_version: u64,
+    /// The payload as received, truncated to a few KiB
+    pub raw: String,
+}
+
+impl std::fmt::Display for StatusParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "schema v{}: {}", self.schema_version, self.message)
+    }
+}
+
+impl From<StatusParseError> for CommandError {
+    fn from(e: StatusParseError) -> Self {
+        CommandError::StatusParseError(Box::new(e))
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Skew {
+    /// The sidecar is older than this app supports
+    SidecarTooOld,
+    /// The sidecar is newer than this app
+    SidecarTooNew,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SchemaMismatch {
+    schema_version: u64,
+    min_supported: u64,
+    max_supported: u64,
+    skew: Skew,
+    /// Set when the payload could not be parsed at all
+    error: Option<StatusParseError>,
+}
+
+/// Schema version of a status payload.
+pub fn schema_version(payload: &Value) -> u64 {
+    payload.get("schemaVersion").and_then(Value::as_u64).unwrap_or(1)
+}
+
+fn skew(version: u64) -> Option<Skew> {
+    if version < MIN_SCHEMA_VERSION {
+        Some(Skew::SidecarTooOld)
+    } else if version > SCHEMA_VERSION {
+        Some(Skew::SidecarTooNew)
+    } else {
+        None
+    }
+}
+
+fn truncate(raw: String) -> String {
+    if raw.len() <= MAX_RAW_LEN {
+        return raw;
+    }
+    let mut end = MAX_RAW_LEN;
+    while !raw.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &raw[..end])
+}
+
+/// Parse a status payload of any schema version, returning the skew if the
+/// version is outside the supported range.
+pub fn parse(payload: Value) -> (Result<StatusResponse, StatusParseError>, Option<Skew>) {
+    let version = schema_version(&payload);
+    let raw = payload.to_string();
+    let result = serde_json::from_value(payload).map_err(|e| StatusParseError {
+        message: e.to_string(),
+        schema_version: version,
+        raw: truncate(raw),
+    });
+    (result, skew(version))
+}
+
+/// [`parse`], reporting version skew to the UI.
+pub fn parse_and_report(app: &AppHandle, payload: Value) -> Result<StatusResponse, StatusParseError> {
+    let version = schema_version(&payload);
+    let (result, skew) = parse(payload);
+    if let Some(skew) = skew {
+        if REPORTED_VERSION.swap(version, Ordering::Relaxed) != version {
+            log::warn!(
+                "Bridge status schema v{} is outside the supported range v{}-v{} ({:?})",
+                version,
+                MIN_SCHEMA_VERSION,
+                SCHEMA_VERSION,
+                skew
+            );
+            let _ = app.emit(
+                "status:schema_mismatch",
+                SchemaMismatch {
+                    schema_version: version,
+                    min_supported: MIN_SCHEMA_VERSION,
+                    max_supported: SCHEMA_VERSION,
+                    skew,
+                    error: result.as_ref().err().cloned(),
+                },
+            );
+        }
+    }
+    if let Err(e) = &result {
+        log::error!("Unreadable bridge status ({}); payload: {}", e, e.raw);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> Value {
+        serde_json::json!({
+            "server": {"running": true, "pid": 42, "url": "http://127.0.0.1:8080"},
+            "auth": {"loggedIn": true, "username": "alice@proton.me"},
+            "config": {
+                "webdav": {"host": "127.0.0.1", "port": 8080, "https": false, "requireAuth": true},
+                "remotePath": "/"
+            },
+            "logFile": "/tmp/bridge.log"
+        })
+    }
+
+    #[test]
+    fn test_parse_supported_versions() {
+        let (status, skew) = parse(payload());
+        assert_eq!(status.unwrap().server.pid, Some(42));
+        assert_eq!(skew, None);
+
+        let mut v2 = payload();
+        v2["schemaVersion"] = serde_json::json!(2);
+        v2["server"]["uptime"] = serde_json::json!(12);
+        let (status, skew) = parse(v2);
+        assert!(status.unwrap().auth.logged_in);
+        assert_eq!(skew, None);
+    }
+
+    #[test]
+    fn test_parse_reports_skew() {
+        let mut newer = payload();
+        newer["schemaVersion"] = serde_json::json!(SCHEMA_VERSION + 1);
+        let (status, skew) = parse(newer);
+        assert!(status.is_ok());
+        assert_eq!(skew, Some(Skew::SidecarTooNew));
+
+        let mut older = payload();
+        older["schemaVersion"] = serde_json::json!(0);
+        assert_eq!(parse(older).1, Some(Skew::SidecarTooOld));
+    }
+
+    #[test]
+    fn test_parse_error_keeps_raw_payload() {
+        let mut broken = payload();
+        broken["server"] = serde_json::json!("running");
+        let error = parse(broken).0.err().unwrap();
+        assert_eq!(error.schema_version, 1);
+        assert!(error.raw.contains("\"server\":\"running\""));
+
+        let long = truncate("é".repeat(MAX_RAW_LEN));
+        assert!(long.len() <= MAX_RAW_LEN + '…'.len_utf8());
+    }
+
+    #[test]
+    fn test_command_error_carries_raw_payload() {
+        let mut broken = payload();
+        broken["server"] = serde_json::json!("running");
+        let error: CommandError = parse(broken).0.err().unwrap().into();
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], "STATUS_PARSE_ERROR");
+        assert!(json["details"]["raw"].as_str().unwrap().contains("\"server\":\"running\""));
+    }
+}
@@ -0,0 +1,252 @@
+use std::time::Duration;
+use tauri_plugin_shell::process::CommandChild;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Sidecar supervisor
+// ============================================================================
+//
+// One task owns the bridge process the app tracks: the `CommandChild` when
+// the app spawned it, or just the PID of a bridge it adopted. Everything
+// else talks to that task over a channel through `SidecarState`, so no lock
+// is held across an await and a spawned bridge can be stopped by signalling
+// and awaiting the very child that was started, rather than by shelling out
+// to `stop`. The task learns of exits from whoever reads the process's
+// output (`exited`), and wakes up anyone waiting on that PID.
+
+/// How long a signalled bridge gets to exit before it is killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the supervisor tracks, as seen from outside.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tracked {
+    pub pid: u32,
+    /// Started outside the app (see `process.rs`)
+    pub adopted: bool,
+    /// The supervisor holds the child handle
+    pub owned: bool,
+}
+
+struct Process {
+    pid: u32,
+    child: Option<CommandChild>,
+    adopted: bool,
+    /// Set before stopping so the exit isn't reported as a crash
+    stop_requested: bool,
+}
+
+impl Process {
+    fn tracked(&self) -> Tracked {
+        Tracked { pid: self.pid, adopted: self.adopted, owned: self.child.is_some() }
+    }
+}
+
+enum Message {
+    Get(oneshot::Sender<Option<Tracked>>),
+    /// Track a process. `claim` only takes it if nothing is tracked.
+    Track { pid: u32, child: Option<CommandChild>, adopted: bool, claim: bool, reply: oneshot::Sender<bool> },
+    RequestStop(oneshot::Sender<Option<Tracked>>),
+    Kill(oneshot::Sender<Result<(), CommandError>>),
+    /// The process exited or was stopped; answers whether a stop was asked for
+    Exited { pid: u32, reply: oneshot::Sender<bool> },
+    Wait { pid: u32, reply: oneshot::Sender<()> },
+}
+
+/// Handle to the supervisor task, managed as Tauri state.
+#[derive(Clone)]
+pub struct SidecarState {
+    tx: mpsc::UnboundedSender<Message>,
+}
+
+impl SidecarState {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tauri::async_runtime::spawn(run(rx));
+        Self { tx }
+    }
+
+    async fn ask<T>(&self, message: impl FnOnce(oneshot::Sender<T>) -> Message) -> Option<T> {
+        let (reply, answer) = oneshot::channel();
+        self.tx.send(message(reply)).ok()?;
+        answer.await.ok()
+    }
+
+    pub async fn tracked(&self) -> Option<Tracked> {
+        self.ask(Message::Get).await.flatten()
+    }
+
+    pub async fn pid(&self) -> Option<u32> {
+        self.tracked().await.map(|t| t.pid)
+    }
+
+    pub async fn is_running(&self) -> bool {
+        self.tracked().await.is_some()
+    }
+
+    /// Hand a freshly spawned child to the supervisor.
+    pub(crate) async fn track_child(&self, child: CommandChild) -> u32 {
+        let pid = child.pid();
+        self.ask(|reply| Message::Track { pid, child: Some(child), adopted: false, claim: false, reply }).await;
+        pid
+    }
+
+    /// Track a bridge the app didn't spawn, unless one is tracked already.
+    /// `adopted` tells a bridge started outside the app from one left
+    /// behind by a previous run of it.
+    pub(crate) async fn claim(&self, pid: u32, adopted: bool) -> bool {
+        self.ask(|reply| Message::Track { pid, child: None, adopted, claim: true, reply }).await.unwrap_or(false)
+    }
+
+    /// Mark the tracked process as being stopped.
+    pub(crate) async fn request_stop(&self) -> Option<Tracked> {
+        self.ask(Message::RequestStop).await.flatten()
+    }
+
+    /// Kill the owned child.
+    pub async fn kill(&self) -> Result<(), CommandError> {
+        self.ask(Message::Kill).await.unwrap_or(Err(CommandError::SidecarNotRunning))
+    }
+
+    /// Report that `pid` exited (or was stopped) and stop tracking it.
+    /// Returns whether the exit was asked for.
+    pub(crate) async fn exited(&self, pid: u32) -> bool {
+        self.ask(|reply| Message::Exited { pid, reply }).await.unwrap_or(false)
+    }
+
+    /// Wait until `pid` is no longer tracked, for at most `timeout`. Returns
+    /// whether it went away in time.
+    pub async fn wait(&self, pid: u32, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, self.ask(|reply| Message::Wait { pid, reply })).await.is_ok()
+    }
+
+    /// Stop the tracked child: ask it to exit, kill it if it doesn't within
+    /// `STOP_TIMEOUT`, and wait for the exit.
+    pub(crate) async fn stop_child(&self, pid: u32) -> Result<(), CommandError> {
+        crate::process::terminate(pid);
+        if self.wait(pid, STOP_TIMEOUT).await {
+            return Ok(());
+        }
+        log::warn!("The bridge (pid {}) did not exit within {}s; killing it", pid, STOP_TIMEOUT.as_secs());
+        self.kill().await?;
+        if !self.wait(pid, STOP_TIMEOUT).await {
+            return Err(CommandError::SidecarCommandFailed(format!("The bridge (pid {}) did not exit", pid)));
+        }
+        Ok(())
+    }
+}
+
+impl Default for SidecarState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn run(mut rx: mpsc::UnboundedReceiver<Message>) {
+    let mut current: Option<Process> = None;
+    let mut waiters: Vec<(u32, oneshot::Sender<()>)> = Vec::new();
+    while let Some(message) = rx.recv().await {
+        match message {
+            Message::Get(reply) => {
+                let _ = reply.send(current.as_ref().map(Process::tracked));
+            }
+            Message::Track { pid, child, adopted, claim, reply } => {
+                if claim && current.is_some() {
+                    let _ = reply.send(false);
+                    continue;
+                }
+                if let Some(previous) = current.as_ref().filter(|p| p.pid != pid) {
+                    log::warn!("Tracking bridge {} in place of {}", pid, previous.pid);
+                }
+                current = Some(Process { pid, child, adopted, stop_requested: false });
+                let _ = reply.send(true);
+            }
+            Message::RequestStop(reply) => {
+                let tracked = current.as_mut().map(|p| {
+                    p.stop_requested = true;
+                    p.tracked()
+                });
+                let _ = reply.send(tracked);
+            }
+            Message::Kill(reply) => {
+                let result = match current.as_mut().and_then(|p| p.child.take()) {
+                    Some(child) => child.kill().map_err(|e| CommandError::SidecarCommandFailed(e.to_string())),
+                    None => Err(CommandError::SidecarNotRunning),
+                };
+                let _ = reply.send(result);
+            }
+            Message::Exited { pid, reply } => {
+                let stop_requested = match current.take_if(|p| p.pid == pid) {
+                    Some(process) => process.stop_requested,
+                    None => false,
+                };
+                let _ = reply.send(stop_requested);
+                let (done, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut waiters).into_iter().partition(|(waiting_for, _)| *waiting_for == pid);
+                waiters = rest;
+                for (_, waiter) in done {
+                    let _ = waiter.send(());
+                }
+            }
+            Message::Wait { pid, reply } => {
+                if current.as_ref().is_some_and(|p| p.pid == pid) {
+                    waiters.push((pid, reply));
+                } else {
+                    let _ = reply.send(());
+                }
+            }
+        }
+        waiters.retain(|(_, waiter)| !waiter.is_closed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri::async_runtime::block_on;
+
+    #[test]
+    fn test_claim_only_when_free() {
+        let state = SidecarState::new();
+        assert_eq!(block_on(state.pid()), None);
+        assert!(block_on(state.claim(12345, true)));
+        assert!(!block_on(state.claim(999, false)));
+        assert_eq!(block_on(state.tracked()), Some(Tracked { pid: 12345, adopted: true, owned: false }));
+    }
+
+    #[test]
+    fn test_exit_reports_requested_stop() {
+        let state = SidecarState::new();
+        block_on(state.claim(1, false));
+        assert!(!block_on(state.exited(1)));
+        assert!(!block_on(state.is_running()));
+
+        block_on(state.claim(2, false));
+        assert_eq!(block_on(state.request_stop()).map(|t| t.pid), Some(2));
+        // An exit of another process leaves the tracked one alone
+        assert!(!block_on(state.exited(3)));
+        assert!(block_on(state.exited(2)));
+        assert_eq!(block_on(state.pid()), None);
+    }
+
+    #[test]
+    fn test_wait_returns_on_exit() {
+        let state = SidecarState::new();
+        // Nothing to wait for
+        assert!(block_on(state.wait(7, Duration::from_millis(50))));
+        block_on(state.claim(7, false));
+        assert!(!block_on(state.wait(7, Duration::from_millis(50))));
+        let waiting = state.clone();
+        let waiter = tauri::async_runtime::spawn(async move { waiting.wait(7, Duration::from_secs(5)).await });
+        std::thread::sleep(Duration::from_millis(50));
+        block_on(state.exited(7));
+        assert!(block_on(waiter).unwrap());
+    }
+
+    #[test]
+    fn test_kill_without_child() {
+        let state = SidecarState::new();
+        block_on(state.claim(5, true));
+        assert_eq!(block_on(state.kill()).unwrap_err().code(), "SIDECAR_NOT_RUNNING");
+    }
+}
@@ -0,0 +1,915 @@
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::cache::MetadataCache;
+use crate::conflicts::{conflicted_copy_name, Conflict, ConflictRegistry, ConflictSource, Resolution};
+use crate::dav::{join_path, normalize_path, DavClient, DavEntry};
+use crate::db;
+use crate::feature_flags::{FeatureFlag, FeatureFlagState};
+use crate::ignore::{IgnoreRules, IgnoreScope};
+use crate::name_mapping::{self, NameMapper};
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+use crate::special_files::{self, LocalKind, SkipReason, SkippedItem, SpecialFileSettings};
+use crate::transfer_concurrency::TransferConcurrencyState;
+
+// ============================================================================
+// Two-way sync
+// ============================================================================
+//
+// A sync pair keeps a local folder and a remote folder identical. Each run
+// compares both sides with the state recorded after the previous run (local
+// size and modification time, remote ETag or size and modification time):
+// a file changed on one side is copied to the other, a file deleted on one
+// side and unchanged on the other is deleted there too, and a file changed
+// on both sides is a conflict. On the first run, a file already on both
+// sides is only taken as in sync when both hold the same data, by SHA-256;
+// otherwise it is a conflict too. Conflicted files are left alone on both sides
+// and recorded in the conflict registry until the user resolves them. Empty
+// folders, and files matching the sync ignore rules, are not synced;
+// symlinks and special files are handled by `special_files`, and names the
+// local filesystem can't hold by `name_mapping`. Copies run once everything
+// else is done, side by side up to the `transfer_concurrency` limits.
+//
+// Pairs live in the `sync` config section; the per-pair state is kept in
+// the state database. Pairs are synced on the configured
+// interval and with `sync_now`. The engine only runs with the `syncEngine`
+// feature flag, and never while travel mode is on or the share is read-only.
+
+const CONFIG_KEY: &str = "sync";
+
+/// Delay before the first scheduled run so it doesn't compete with startup.
+const INITIAL_DELAY: Duration = Duration::from_secs(90);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct SyncConfig {
+    pairs: Vec<SyncPair>,
+    interval_minutes: u64,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self { pairs: Vec::new(), interval_minutes: 15 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPair {
+    pub id: String,
+    pub local_path: String,
+    pub remote_path: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LocalStamp {
+    pub size: u64,
+    /// Unix timestamp (seconds)
+    pub modified: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RemoteStamp {
+    size: Option<u64>,
+    modified: Option<String>,
+    etag: Option<String>,
+}
+
+impl RemoteStamp {
+    fn from_entry(entry: &DavEntry) -> Self {
+        Self { size: entry.size, modified: entry.modified.clone(), etag: entry.etag.clone() }
+    }
+
+    /// Same remote version, by ETag when both have one.
+    fn same(&self, other: &RemoteStamp) -> bool {
+        match (&self.etag, &other.etag) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.size == other.size && self.modified == other.modified,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct SyncRecord {
+    local: LocalStamp,
+    remote: RemoteStamp,
+}
+
+#[derive(Debug, Default)]
+struct PairState {
+    /// Both sides as of the last run, by path relative to the pair's folders
+    files: BTreeMap<String, SyncRecord>,
+    last_synced: Option<u64>,
+    last_error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPairStatus {
+    #[serde(flatten)]
+    pub pair: SyncPair,
+    /// Files in sync as of the last run
+    pub files: usize,
+    /// Unix timestamp (seconds) of the last successful run
+    pub last_synced: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncFileError {
+    /// Path relative to the pair's folders
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub pair_id: String,
+    pub uploaded: usize,
+    pub downloaded: usize,
+    pub deleted_local: usize,
+    pub deleted_remote: usize,
+    /// Paths that changed on both sides
+    pub conflicts: Vec<String>,
+    pub errors: Vec<SyncFileError>,
+    /// Local entries left alone under the symlink and special file policy
+    pub skipped: Vec<SkippedItem>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SyncFailed {
+    pair_id: String,
+    error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Keep,
+    Upload,
+    Download,
+    DeleteLocal,
+    DeleteRemote,
+    Conflict,
+    /// Both sides present with the same size on the first run; tracked
+    /// from now on if their content matches, a conflict otherwise
+    Record,
+    /// Gone on both sides; stop tracking
+    Forget,
+}
+
+/// Runs one sync at a time.
+#[derive(Default)]
+pub struct SyncState {
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl SyncState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// What to do with a file given both sides and the state of the last run.
+fn decide(local: Option<&LocalStamp>, remote: Option<&RemoteStamp>, base: Option<&SyncRecord>) -> Action {
+    let local_changed = |l: &LocalStamp| base.is_none_or(|b| b.local != *l);
+    let remote_changed = |r: &RemoteStamp| base.is_none_or(|b| !b.remote.same(r));
+    match (local, remote, base) {
+        (None, None, Some(_)) => Action::Forget,
+        (None, None, None) => Action::Keep,
+        (Some(_), None, None) => Action::Upload,
+        (Some(l), None, Some(_)) if local_changed(l) => Action::Upload,
+        (Some(_), None, Some(_)) => Action::DeleteLocal,
+        (None, Some(_), None) => Action::Download,
+        (None, Some(r), Some(_)) if remote_changed(r) => Action::Download,
+        (None, Some(_), Some(_)) => Action::DeleteRemote,
+        // First run over files present on both sides: same size is worth
+        // comparing the content
+        (Some(l), Some(r), None) if r.size == Some(l.size) => Action::Record,
+        (Some(_), Some(_), None) => Action::Conflict,
+        (Some(l), Some(r), Some(_)) => match (local_changed(l), remote_changed(r)) {
+            (false, false) => Action::Keep,
+            (true, false) => Action::Upload,
+            (false, true) => Action::Download,
+            (true, true) => Action::Conflict,
+        },
+    }
+}
+
+/// What a first-run `Record` becomes once both sides are hashed.
+fn compare_content(local_sha256: &str, remote_sha256: &str) -> Action {
+    if local_sha256.eq_ignore_ascii_case(remote_sha256) {
+        Action::Record
+    } else {
+        Action::Conflict
+    }
+}
+
+/// Local path of `rel`, a path in remote names, under `root`, or `None` if
+/// it would escape it.
+pub(crate) fn local_file(root: &Path, rel: &str, names: &NameMapper) -> Option<PathBuf> {
+    let local = names.local_rel(rel);
+    let relative = Path::new(&local);
+    relative.components().all(|c| matches!(c, Component::Normal(_))).then(|| root.join(relative))
+}
+
+fn remote_file(root: &str, rel: &str) -> String {
+    join_path(root, rel)
+}
+
+/// `path` relative to the remote folder `root`.
+fn remote_relative<'a>(root: &str, path: &'a str) -> &'a str {
+    let prefix = if root == "/" { 1 } else { root.len() + 1 };
+    path.get(prefix..).unwrap_or_default()
+}
+
+fn local_stamp(metadata: &std::fs::Metadata) -> LocalStamp {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    LocalStamp { size: metadata.len(), modified }
+}
+
+/// Files below the local folder `root`, by relative path in remote names
+/// (see `name_mapping`), and the entries left out under the symlink and
+/// special file policy or because their name cannot be mapped. Unfinished
+/// downloads (`.part`) and whatever `ignore` matches are left out silently.
+pub(crate) fn scan_local(
+    root: &Path,
+    ignore: &IgnoreRules,
+    special: &SpecialFileSettings,
+    names: &NameMapper,
+) -> Result<(BTreeMap<String, LocalStamp>, Vec<SkippedItem>), CommandError> {
+    let mut files = BTreeMap::new();
+    let mut skipped = Vec::new();
+    // Folders already descended into, so followed links cannot loop
+    let mut visited: HashSet<PathBuf> = root.canonicalize().into_iter().collect();
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir, rel)) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".part") {
+                continue;
+            }
+            let path = entry.path();
+            let Some(remote_name) = names.remote_name(&name) else {
+                let rel_path = if rel.is_empty() { name } else { format!("{}/{}", rel, name) };
+                skipped.push(SkippedItem { path: rel_path, reason: SkipReason::UnmappableName });
+                continue;
+            };
+            let rel_path = if rel.is_empty() { remote_name } else { format!("{}/{}", rel, remote_name) };
+            match special_files::classify(&path, special) {
+                Ok(LocalKind::Dir) if !ignore.is_ignored_dir(&rel_path) => {
+                    if visited.insert(path.canonicalize()?) {
+                        pending.push((path, rel_path));
+                    } else {
+                        skipped.push(SkippedItem { path: rel_path, reason: SkipReason::SymlinkLoop });
+                    }
+                }
+                Ok(LocalKind::File | LocalKind::Link) if !ignore.is_ignored(&rel_path) => {
+                    files.insert(rel_path, local_stamp(&special_files::metadata(&path, special)?));
+                }
+                Ok(_) => {}
+                Err(reason) => {
+                    if !ignore.is_ignored(&rel_path) {
+                        skipped.push(SkippedItem { path: rel_path, reason });
+                    }
+                }
+            }
+        }
+    }
+    Ok((files, skipped))
+}
+
+/// Files and folders below the remote folder `root`, by relative path,
+/// without what `ignore` matches. Listings always come from the bridge; they
+/// are recorded in the metadata cache for the prefetcher and search.
+async fn scan_remote(
+    client: &DavClient,
+    cache: &MetadataCache,
+    root: &str,
+    ignore: &IgnoreRules,
+) -> Result<(BTreeMap<String, DavEntry>, HashSet<String>), CommandError> {
+    let mut files = BTreeMap::new();
+    let mut dirs = HashSet::new();
+    let mut pending = vec![root.to_string()];
+    while let Some(dir) = pending.pop() {
+        let listing = client.propfind(&dir, 1).await?;
+        cache.remember_listing(&dir, &listing);
+        for entry in listing {
+            if entry.path == dir {
+                continue;
+            }
+            let rel = remote_relative(root, &entry.path).to_string();
+            let ignored = if entry.is_dir { ignore.is_ignored_dir(&rel) } else { ignore.is_ignored(&rel) };
+            if ignored {
+                continue;
+            }
+            if entry.is_dir {
+                pending.push(entry.path.clone());
+                dirs.insert(rel);
+            } else {
+                files.insert(rel, entry);
+            }
+        }
+    }
+    Ok((files, dirs))
+}
+
+/// Create the remote folders above `rel` that don't exist yet. A folder is
+/// only taken as existing once MKCOL succeeded (or found it there), so a
+/// failed one is tried again for the next file in it.
+async fn ensure_remote_dirs(client: &DavClient, root: &str, rel: &str, dirs: &mut HashSet<String>) -> Result<(), CommandError> {
+    let segments: Vec<&str> = rel.split('/').collect();
+    let mut prefix = String::new();
+    for segment in &segments[..segments.len() - 1] {
+        prefix = if prefix.is_empty() { segment.to_string() } else { format!("{}/{}", prefix, segment) };
+        if !dirs.contains(&prefix) {
+            client.mkcol(&remote_file(root, &prefix)).await?;
+            dirs.insert(prefix.clone());
+        }
+    }
+    Ok(())
+}
+
+struct PairRun<'a> {
+    app: &'a AppHandle,
+    pair_id: &'a str,
+    client: &'a DavClient,
+    local_root: &'a Path,
+    remote_root: &'a str,
+    /// Remote folders known to exist; held while creating them, so uploads
+    /// running side by side don't create the same folder twice
+    remote_dirs: tokio::sync::Mutex<HashSet<String>>,
+    special: SpecialFileSettings,
+    names: NameMapper,
+}
+
+impl PairRun<'_> {
+    async fn upload(&self, rel: &str, local: &Path) -> Result<SyncRecord, CommandError> {
+        ensure_remote_dirs(self.client, self.remote_root, rel, &mut *self.remote_dirs.lock().await).await?;
+        let remote = remote_file(self.remote_root, rel);
+        special_files::upload(self.app, self.client, local, &remote, true).await?;
+        let entry = self.client.stat(&remote).await?.ok_or_else(|| CommandError::RemotePathNotFound(remote.clone()))?;
+        let metadata = special_files::metadata(local, &self.special)?;
+        Ok(SyncRecord { local: local_stamp(&metadata), remote: RemoteStamp::from_entry(&entry) })
+    }
+
+    async fn download(&self, entry: &DavEntry, local: &Path) -> Result<SyncRecord, CommandError> {
+        if let Some(parent) = local.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let target = special_files::download_target(local, &self.special);
+        crate::remote::download_to_path(self.app, self.client, &entry.path, entry.size, &target).await?;
+        let metadata = special_files::metadata(local, &self.special)?;
+        Ok(SyncRecord { local: local_stamp(&metadata), remote: RemoteStamp::from_entry(entry) })
+    }
+
+    /// SHA-256 of the local file and of the remote one: the checksum the
+    /// bridge publishes, or else the hash of the file read from it.
+    async fn hashes(&self, rel: &str, entry: &DavEntry) -> Result<(String, String), CommandError> {
+        let local = local_file(self.local_root, rel, &self.names).ok_or_else(|| CommandError::InvalidRemotePath(format!("Unsafe path {}", rel)))?;
+        let local_sha256 = tauri::async_runtime::spawn_blocking(move || crate::photo_backup::hash_file(&local))
+            .await
+            .map_err(|e| CommandError::Unknown(e.to_string()))??;
+        if let Some(remote_sha256) = self.client.sha256(&entry.path).await? {
+            return Ok((local_sha256, remote_sha256));
+        }
+        let mut hasher = Sha256::new();
+        let mut body = self.client.get(&entry.path).await?.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            hasher.update(chunk.map_err(|e| CommandError::WebDavError(e.to_string()))?);
+        }
+        Ok((local_sha256, hex::encode(hasher.finalize())))
+    }
+
+    fn source(&self, rel: &str) -> ConflictSource {
+        ConflictSource::Sync { pair_id: self.pair_id.to_string(), path: rel.to_string() }
+    }
+
+    /// Keep the remote file under `rel` and the local one next to it as a
+    /// conflicted copy, on both sides.
+    async fn keep_both(&self, rel: &str, entry: &DavEntry, state: &mut PairState) -> Result<(), CommandError> {
+        let local = local_file(self.local_root, rel, &self.names).ok_or_else(|| CommandError::InvalidRemotePath(format!("Unsafe path {}", rel)))?;
+        let copy_rel = conflicted_copy_name(rel, crate::sidecar::unix_now());
+        let copy = local_file(self.local_root, &copy_rel, &self.names).ok_or_else(|| CommandError::InvalidRemotePath(format!("Unsafe path {}", copy_rel)))?;
+        tokio::fs::rename(&local, &copy).await?;
+        let record = self.download(entry, &local).await?;
+        state.files.insert(rel.to_string(), record);
+        let record = self.upload(&copy_rel, &copy).await?;
+        state.files.insert(copy_rel, record);
+        Ok(())
+    }
+
+    /// Upload or download `rel`, as `action` says.
+    async fn transfer(&self, action: Action, rel: &str, remote: Option<&DavEntry>) -> Result<SyncRecord, CommandError> {
+        let local = local_file(self.local_root, rel, &self.names).ok_or_else(|| CommandError::InvalidRemotePath(format!("Unsafe path {}", rel)))?;
+        if action == Action::Upload {
+            return self.upload(rel, &local).await;
+        }
+        let entry = remote.ok_or_else(|| CommandError::RemotePathNotFound(rel.to_string()))?;
+        self.download(entry, &local).await
+    }
+
+    /// Apply `action` to `rel`, updating `state` and `report`. Uploads and
+    /// downloads are left to `transfer`.
+    async fn apply(&self, action: Action, rel: &str, remote: Option<&DavEntry>, state: &mut PairState, report: &mut SyncReport) -> Result<(), CommandError> {
+        let local = local_file(self.local_root, rel, &self.names).ok_or_else(|| CommandError::InvalidRemotePath(format!("Unsafe path {}", rel)))?;
+        match action {
+            Action::Keep | Action::Upload | Action::Download => {}
+            Action::DeleteLocal => {
+                tokio::fs::remove_file(&local).await?;
+                state.files.remove(rel);
+                report.deleted_local += 1;
+            }
+            Action::DeleteRemote => {
+                let remote = remote_file(self.remote_root, rel);
+                self.client.delete(&remote).await?;
+                self.app.state::<MetadataCache>().invalidate(&remote);
+                state.files.remove(rel);
+                report.deleted_remote += 1;
+            }
+            Action::Conflict => {
+                let entry = remote.ok_or_else(|| CommandError::RemotePathNotFound(rel.to_string()))?;
+                let metadata = tokio::fs::metadata(&local).await?;
+                let stamp = local_stamp(&metadata);
+                let conflict = Conflict {
+                    id: 0,
+                    source: self.source(rel),
+                    local_path: local.display().to_string(),
+                    remote_path: entry.path.clone(),
+                    local_size: Some(stamp.size),
+                    local_modified: Some(stamp.modified),
+                    remote_size: entry.size,
+                    remote_modified: entry.modified.clone(),
+                    detected_at: 0,
+                };
+                self.app.state::<ConflictRegistry>().record(self.app, conflict);
+                report.conflicts.push(rel.to_string());
+            }
+            Action::Record => {
+                let entry = remote.ok_or_else(|| CommandError::RemotePathNotFound(rel.to_string()))?;
+                let metadata = tokio::fs::metadata(&local).await?;
+                state.files.insert(rel.to_string(), SyncRecord { local: local_stamp(&metadata), remote: RemoteStamp::from_entry(entry) });
+            }
+            Action::Forget => {
+                state.files.remove(rel);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_state(conn: &Connection, pair_id: &str) -> rusqlite::Result<PairState> {
+    let (last_synced, last_error) = conn
+        .query_row("SELECT last_synced, last_error FROM sync_pair_state WHERE pair_id = ?1", [pair_id], |row| {
+            Ok((row.get::<_, Option<i64>>(0)?, row.get(1)?))
+        })
+        .optional()?
+        .unwrap_or_default();
+    let mut stmt = conn.prepare(
+        "SELECT path, local_size, local_modified, remote_size, remote_modified, remote_etag FROM sync_records WHERE pair_id = ?1",
+    )?;
+    let files = stmt
+        .query_map([pair_id], |row| {
+            let record = SyncRecord {
+                local: LocalStamp { size: row.get::<_, i64>(1)? as u64, modified: row.get::<_, i64>(2)? as u64 },
+                remote: RemoteStamp { size: row.get::<_, Option<i64>>(3)?.map(|s| s as u64), modified: row.get(4)?, etag: row.get(5)? },
+            };
+            Ok((row.get(0)?, record))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(PairState { files, last_synced: last_synced.map(|t| t as u64), last_error })
+}
+
+fn write_state(tx: &Transaction, pair_id: &str, state: &PairState) -> rusqlite::Result<()> {
+    delete_state(tx, pair_id)?;
+    tx.execute(
+        "INSERT INTO sync_pair_state (pair_id, last_synced, last_error) VALUES (?1, ?2, ?3)",
+        rusqlite::params![pair_id, state.last_synced.map(|t| t as i64), state.last_error],
+    )?;
+    let mut stmt = tx.prepare(
+        "INSERT INTO sync_records (pair_id, path, local_size, local_modified, remote_size, remote_modified, remote_etag)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for (path, record) in &state.files {
+        stmt.execute(rusqlite::params![
+            pair_id,
+            path,
+            record.local.size as i64,
+            record.local.modified as i64,
+            record.remote.size.map(|s| s as i64),
+            record.remote.modified,
+            record.remote.etag,
+        ])?;
+    }
+    Ok(())
+}
+
+fn delete_state(tx: &Transaction, pair_id: &str) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM sync_pair_state WHERE pair_id = ?1", [pair_id])?;
+    tx.execute("DELETE FROM sync_records WHERE pair_id = ?1", [pair_id])?;
+    Ok(())
+}
+
+fn load_state(app: &AppHandle, pair_id: &str) -> Result<PairState, CommandError> {
+    db::with(app, |conn| read_state(conn, pair_id))
+}
+
+/// Replace the recorded state of `pair_id` in one transaction, so a crash
+/// never leaves half of a run recorded.
+fn save_state(app: &AppHandle, pair_id: &str, state: &PairState) -> Result<(), CommandError> {
+    db::transaction(app, |tx| write_state(tx, pair_id, state))
+}
+
+/// Fail unless the engine may run now.
+fn check_enabled(app: &AppHandle) -> Result<(), CommandError> {
+    if !app.state::<FeatureFlagState>().is_enabled(FeatureFlag::SyncEngine) {
+        return Err(CommandError::FeatureDisabled("syncEngine".into()));
+    }
+    app.state::<ReadOnlyState>().check_writable()
+}
+
+async fn sync_pair(app: &AppHandle, pair: &SyncPair, state: &mut PairState) -> Result<SyncReport, CommandError> {
+    let client = DavClient::for_app(app)?;
+    let local_root = PathBuf::from(&pair.local_path);
+    if !local_root.is_dir() {
+        return Err(CommandError::InvalidSyncPair(format!("{} is not a folder", local_root.display())));
+    }
+    let ignore = crate::ignore::rules(IgnoreScope::Sync);
+    // Files ignored since the last run are no longer tracked; nothing is
+    // deleted on either side for them
+    state.files.retain(|rel, _| !ignore.is_ignored(rel));
+    let special = special_files::settings();
+    let names = NameMapper::current();
+    let scan_root = local_root.clone();
+    let ((local, skipped), ignore) =
+        tauri::async_runtime::spawn_blocking(move || scan_local(&scan_root, &ignore, &special, &names).map(|scan| (scan, ignore)))
+            .await
+            .map_err(|e| CommandError::Unknown(e.to_string()))??;
+    let (remote, remote_dirs) = scan_remote(&client, &app.state::<MetadataCache>(), &pair.remote_path, &ignore).await?;
+
+    // A side that suddenly has no files at all is more likely an unmounted
+    // disk or a wrong folder than a deliberate wipe
+    if !state.files.is_empty() && (local.is_empty() != remote.is_empty()) {
+        let empty = if local.is_empty() { pair.local_path.as_str() } else { pair.remote_path.as_str() };
+        return Err(CommandError::InvalidSyncPair(format!("{} is empty; not deleting everything on the other side", empty)));
+    }
+
+    let run = PairRun {
+        app,
+        pair_id: &pair.id,
+        client: &client,
+        local_root: &local_root,
+        remote_root: &pair.remote_path,
+        remote_dirs: tokio::sync::Mutex::new(remote_dirs),
+        special,
+        names,
+    };
+    let conflicts = app.state::<ConflictRegistry>();
+    // A skipped entry is neither uploaded nor replaced by the remote file
+    let skipped_paths: HashSet<&str> = skipped.iter().map(|s| s.path.as_str()).collect();
+    let paths: BTreeSet<String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(state.files.keys())
+        .filter(|rel| !skipped_paths.contains(rel.as_str()))
+        .cloned()
+        .collect();
+    let mut report = SyncReport { pair_id: pair.id.clone(), skipped: skipped.clone(), ..Default::default() };
+    let mut transfers = Vec::new();
+    for rel in paths {
+        let remote_entry = remote.get(&rel);
+        let remote_stamp = remote_entry.map(RemoteStamp::from_entry);
+        let mut action = decide(local.get(&rel), remote_stamp.as_ref(), state.files.get(&rel));
+        if let (Action::Record, Some(entry)) = (action, remote_entry) {
+            match run.hashes(&rel, entry).await {
+                Ok((local_sha256, remote_sha256)) => action = compare_content(&local_sha256, &remote_sha256),
+                Err(e) => {
+                    log::warn!("Could not compare {} in pair {}: {}", rel, pair.id, e);
+                    report.errors.push(SyncFileError { path: rel, error: e.to_string() });
+                    continue;
+                }
+            }
+        }
+        if action != Action::Conflict {
+            conflicts.dismiss(app, &run.source(&rel));
+        }
+        if matches!(action, Action::Upload | Action::Download) {
+            transfers.push((action, rel));
+        } else if let Err(e) = run.apply(action, &rel, remote_entry, state, &mut report).await {
+            log::warn!("Sync of {} in pair {} failed: {}", rel, pair.id, e);
+            report.errors.push(SyncFileError { path: rel, error: e.to_string() });
+        }
+    }
+
+    // Transfers run side by side, as many in each direction as the transfer
+    // concurrency settings allow
+    let parallel = app.state::<TransferConcurrencyState>().parallel();
+    let (run, remote) = (&run, &remote);
+    let mut running = futures_util::stream::iter(transfers.into_iter().map(|(action, rel)| async move {
+        let result = run.transfer(action, &rel, remote.get(&rel)).await;
+        (action, rel, result)
+    }))
+    .buffer_unordered(parallel);
+    while let Some((action, rel, result)) = running.next().await {
+        match result {
+            Ok(record) => {
+                state.files.insert(rel, record);
+                match action {
+                    Action::Upload => report.uploaded += 1,
+                    _ => report.downloaded += 1,
+                }
+            }
+            Err(e) => {
+                log::warn!("Sync of {} in pair {} failed: {}", rel, pair.id, e);
+                report.errors.push(SyncFileError { path: rel, error: e.to_string() });
+            }
+        }
+    }
+    let source = format!("sync:{}", pair.id);
+    let renamed: Vec<_> = state
+        .files
+        .keys()
+        .filter_map(|rel| {
+            let local = names.local_rel(rel);
+            (local != *rel).then(|| {
+                name_mapping::renamed(&source, local_root.join(&local).display().to_string(), remote_file(&pair.remote_path, rel))
+            })
+        })
+        .collect();
+    name_mapping::replace(app, &source, &renamed);
+    Ok(report)
+}
+
+/// Sync `pair` and persist its state, reporting through `sync:*` events.
+async fn run_pair(app: &AppHandle, pair: &SyncPair) -> Result<SyncReport, CommandError> {
+    check_enabled(app)?;
+    let sync_state = app.state::<SyncState>();
+    let _guard = sync_state.lock.lock().await;
+
+    let _ = app.emit("sync:started", &pair.id);
+    let mut state = load_state(app, &pair.id)?;
+    let result = sync_pair(app, pair, &mut state).await;
+    match &result {
+        Ok(report) => {
+            state.last_synced = Some(crate::sidecar::unix_now());
+            state.last_error = None;
+            log::info!(
+                "Synced pair {}: {} up, {} down, {} conflicts, {} errors",
+                pair.id,
+                report.uploaded,
+                report.downloaded,
+                report.conflicts.len(),
+                report.errors.len()
+            );
+            let _ = app.emit("sync:completed", report.clone());
+        }
+        Err(e) => {
+            state.last_error = Some(e.to_string());
+            let _ = app.emit("sync:failed", SyncFailed { pair_id: pair.id.clone(), error: e.to_string() });
+        }
+    }
+    save_state(app, &pair.id, &state)?;
+    result
+}
+
+/// Carry out the user's resolution of a conflict on `rel` in pair `pair_id`
+/// and record the outcome as in sync.
+pub(crate) async fn resolve(app: &AppHandle, pair_id: &str, rel: &str, resolution: Resolution) -> Result<(), CommandError> {
+    check_enabled(app)?;
+    let sync_state = app.state::<SyncState>();
+    let _guard = sync_state.lock.lock().await;
+    let pair = find_pair(pair_id)?;
+    let client = DavClient::for_app(app)?;
+    let local_root = PathBuf::from(&pair.local_path);
+    let names = NameMapper::current();
+    let local = local_file(&local_root, rel, &names).ok_or_else(|| CommandError::InvalidRemotePath(format!("Unsafe path {}", rel)))?;
+    let remote = remote_file(&pair.remote_path, rel);
+    let entry = client.stat(&remote).await?.ok_or_else(|| CommandError::RemotePathNotFound(remote.clone()))?;
+
+    let run = PairRun {
+        app,
+        pair_id,
+        client: &client,
+        local_root: &local_root,
+        remote_root: &pair.remote_path,
+        remote_dirs: Default::default(),
+        special: special_files::settings(),
+        names,
+    };
+    let mut state = load_state(app, pair_id)?;
+    match resolution {
+        Resolution::KeepLocal => {
+            let record = run.upload(rel, &local).await?;
+            state.files.insert(rel.to_string(), record);
+        }
+        Resolution::KeepRemote => {
+            let record = run.download(&entry, &local).await?;
+            state.files.insert(rel.to_string(), record);
+        }
+        Resolution::KeepBoth => run.keep_both(rel, &entry, &mut state).await?,
+    }
+    save_state(app, pair_id, &state)
+}
+
+/// Periodic sync of all pairs; spawned once from `setup`.
+pub async fn run(app: AppHandle) {
+    tokio::time::sleep(INITIAL_DELAY).await;
+    loop {
+        let config: SyncConfig = read_config_section(CONFIG_KEY);
+        for pair in &config.pairs {
+            if app.state::<crate::power::PowerState>().is_suspended() || !app.state::<crate::network::NetworkState>().is_online() {
+                break;
+            }
+            match run_pair(&app, pair).await {
+                Ok(_) => {}
+                Err(CommandError::FeatureDisabled(_) | CommandError::TravelModeActive | CommandError::ReadOnlyShare | CommandError::ServerNotRunning) => break,
+                Err(e) => log::warn!("Scheduled sync of pair {} failed: {}", pair.id, e),
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(config.interval_minutes.max(1) * 60)).await;
+    }
+}
+
+fn find_pair(pair_id: &str) -> Result<SyncPair, CommandError> {
+    let config: SyncConfig = read_config_section(CONFIG_KEY);
+    config.pairs.into_iter().find(|p| p.id == pair_id).ok_or_else(|| CommandError::SyncPairNotFound(pair_id.to_string()))
+}
+
+/// Whether one of two paths contains the other.
+fn overlaps(a: &str, b: &str, separator: char) -> bool {
+    let within = |inner: &str, outer: &str| inner == outer || inner.starts_with(&format!("{}{}", outer.trim_end_matches(separator), separator));
+    within(a, b) || within(b, a)
+}
+
+#[tauri::command]
+pub async fn list_sync_pairs(app: AppHandle) -> Result<Vec<SyncPairStatus>, CommandError> {
+    let config: SyncConfig = read_config_section(CONFIG_KEY);
+    config
+        .pairs
+        .into_iter()
+        .map(|pair| {
+            let state = load_state(&app, &pair.id)?;
+            Ok(SyncPairStatus { files: state.files.len(), last_synced: state.last_synced, last_error: state.last_error, pair })
+        })
+        .collect()
+}
+
+/// Start keeping `local_path` and `remote_path` in sync. Both folders must
+/// exist and must not overlap another pair. The first sync runs right away
+/// in the background.
+#[tauri::command]
+pub async fn add_sync_pair(app: AppHandle, local_path: String, remote_path: String) -> Result<SyncPair, CommandError> {
+    if !app.state::<FeatureFlagState>().is_enabled(FeatureFlag::SyncEngine) {
+        return Err(CommandError::FeatureDisabled("syncEngine".into()));
+    }
+    let local = PathBuf::from(&local_path);
+    if !local.is_absolute() || !local.is_dir() {
+        return Err(CommandError::InvalidSyncPair(format!("{} is not a local folder", local_path)));
+    }
+    let local_path = local.display().to_string();
+    let remote_path = normalize_path(&remote_path);
+    match DavClient::for_app(&app)?.stat(&remote_path).await? {
+        Some(entry) if entry.is_dir => {}
+        Some(_) => return Err(CommandError::InvalidSyncPair(format!("{} is not a folder", remote_path))),
+        None => return Err(CommandError::RemotePathNotFound(remote_path)),
+    }
+
+    let mut config: SyncConfig = read_config_section(CONFIG_KEY);
+    if let Some(other) = config
+        .pairs
+        .iter()
+        .find(|p| overlaps(&p.local_path, &local_path, std::path::MAIN_SEPARATOR) || overlaps(&p.remote_path, &remote_path, '/'))
+    {
+        return Err(CommandError::InvalidSyncPair(format!("Overlaps the pair {} <-> {}", other.local_path, other.remote_path)));
+    }
+    let pair = SyncPair { id: format!("{:08x}", rand::random::<u32>()), local_path, remote_path };
+    config.pairs.push(pair.clone());
+    write_config_section(CONFIG_KEY, &config)?;
+    log::info!("Added sync pair {}: {} <-> {}", pair.id, pair.local_path, pair.remote_path);
+
+    let handle = app.clone();
+    let first = pair.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_pair(&handle, &first).await {
+            log::warn!("First sync of pair {} failed: {}", first.id, e);
+        }
+    });
+    Ok(pair)
+}
+
+/// Stop syncing a pair. Files on both sides are left as they are.
+#[tauri::command]
+pub async fn remove_sync_pair(app: AppHandle, state: State<'_, SyncState>, pair_id: String) -> Result<(), CommandError> {
+    let _guard = state.lock.lock().await;
+    let mut config: SyncConfig = read_config_section(CONFIG_KEY);
+    let before = config.pairs.len();
+    config.pairs.retain(|p| p.id != pair_id);
+    if config.pairs.len() == before {
+        return Err(CommandError::SyncPairNotFound(pair_id));
+    }
+    write_config_section(CONFIG_KEY, &config)?;
+    app.state::<ConflictRegistry>().dismiss_pair(&app, &pair_id);
+    db::transaction(&app, |tx| delete_state(tx, &pair_id))?;
+    log::info!("Removed sync pair {}", pair_id);
+    Ok(())
+}
+
+/// Sync a pair now, waiting for a sync already running to finish first.
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, pair_id: String) -> Result<SyncReport, CommandError> {
+    let pair = find_pair(&pair_id)?;
+    run_pair(&app, &pair).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(size: u64, modified: u64) -> LocalStamp {
+        LocalStamp { size, modified }
+    }
+
+    fn remote(size: u64, etag: &str) -> RemoteStamp {
+        RemoteStamp { size: Some(size), modified: None, etag: Some(etag.into()) }
+    }
+
+    #[test]
+    fn test_decide_without_history() {
+        assert_eq!(decide(Some(&local(1, 1)), None, None), Action::Upload);
+        assert_eq!(decide(None, Some(&remote(1, "a")), None), Action::Download);
+        assert_eq!(decide(Some(&local(1, 1)), Some(&remote(1, "a")), None), Action::Record);
+        assert_eq!(decide(Some(&local(1, 1)), Some(&remote(2, "a")), None), Action::Conflict);
+    }
+
+    #[test]
+    fn test_same_size_with_different_content_conflicts() {
+        let dir = std::env::temp_dir().join(format!("sync-content-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("local.txt"), b"hello").unwrap();
+        std::fs::write(dir.join("remote.txt"), b"world").unwrap();
+        let local_sha256 = crate::photo_backup::hash_file(&dir.join("local.txt")).unwrap();
+        let remote_sha256 = crate::photo_backup::hash_file(&dir.join("remote.txt")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(decide(Some(&local(5, 1)), Some(&remote(5, "a")), None), Action::Record);
+        assert_eq!(compare_content(&local_sha256, &remote_sha256), Action::Conflict);
+        assert_eq!(compare_content(&local_sha256, &local_sha256.to_uppercase()), Action::Record);
+    }
+
+    #[test]
+    fn test_decide_against_last_run() {
+        let base = SyncRecord { local: local(1, 1), remote: remote(1, "a") };
+        assert_eq!(decide(Some(&local(1, 1)), Some(&remote(1, "a")), Some(&base)), Action::Keep);
+        assert_eq!(decide(Some(&local(2, 5)), Some(&remote(1, "a")), Some(&base)), Action::Upload);
+        assert_eq!(decide(Some(&local(1, 1)), Some(&remote(3, "b")), Some(&base)), Action::Download);
+        assert_eq!(decide(Some(&local(2, 5)), Some(&remote(3, "b")), Some(&base)), Action::Conflict);
+        assert_eq!(decide(Some(&local(1, 1)), None, Some(&base)), Action::DeleteLocal);
+        assert_eq!(decide(Some(&local(2, 5)), None, Some(&base)), Action::Upload);
+        assert_eq!(decide(None, Some(&remote(1, "a")), Some(&base)), Action::DeleteRemote);
+        assert_eq!(decide(None, Some(&remote(3, "b")), Some(&base)), Action::Download);
+        assert_eq!(decide(None, None, Some(&base)), Action::Forget);
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let mut conn = db::open_in_memory();
+        let mut state = PairState { last_synced: Some(7), ..Default::default() };
+        state.files.insert("a/b.txt".into(), SyncRecord { local: local(1, 2), remote: remote(1, "e") });
+        let tx = conn.transaction().unwrap();
+        write_state(&tx, "p1", &state).unwrap();
+        write_state(&tx, "p1", &state).unwrap();
+        tx.commit().unwrap();
+        let loaded = read_state(&conn, "p1").unwrap();
+        assert_eq!(loaded.files, state.files);
+        assert_eq!(loaded.last_synced, Some(7));
+        assert!(read_state(&conn, "p2").unwrap().files.is_empty());
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(remote_relative("/", "/a/b.txt"), "a/b.txt");
+        assert_eq!(remote_relative("/Sync", "/Sync/a/b.txt"), "a/b.txt");
+        assert!(local_file(Path::new("/home/u/Sync"), "../x", &NameMapper::off()).is_none());
+        assert_eq!(local_file(Path::new("/home/u/Sync"), "a:b/c", &NameMapper::windows()), Some(PathBuf::from("/home/u/Sync/a%3Ab/c")));
+        assert!(overlaps("/Sync/Photos", "/Sync", '/'));
+        assert!(!overlaps("/Sync2", "/Sync", '/'));
+    }
+}
@@ -0,0 +1,266 @@
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// System requirements
+// ============================================================================
+//
+// Mounting relies on WebDAV support that ships separately from the app:
+// gvfs-backends or davfs2 on Linux, mount_webdav on macOS and the WebClient
+// service on Windows. On a fresh system the missing piece only shows up as a
+// cryptic mount error ("volume doesn't implement mount"), so this module
+// probes for each component and says how to install what's missing.
+
+/// Locations of the GVFS WebDAV daemon across distributions.
+const GVFSD_DAV_PATHS: &[&str] = &[
+    "/usr/libexec/gvfsd-dav",
+    "/usr/lib/gvfs/gvfsd-dav",
+    "/usr/libexec/gvfs/gvfsd-dav",
+    "/usr/lib64/gvfs/gvfsd-dav",
+];
+
+const DAVFS_PATHS: &[&str] = &["/sbin/mount.davfs", "/usr/sbin/mount.davfs"];
+
+const MOUNT_WEBDAV_PATH: &str = "/sbin/mount_webdav";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RequirementStatus {
+    Available,
+    /// Installed but not usable yet (e.g. a stopped service)
+    Inactive,
+    Missing,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Requirement {
+    pub id: String,
+    pub name: String,
+    pub status: RequirementStatus,
+    pub path: Option<String>,
+    /// What the component is needed for
+    pub purpose: String,
+    /// How to fix it; only set when the status isn't `available`
+    pub remediation: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemReport {
+    pub platform: String,
+    /// Whether at least one mount method is available
+    pub can_mount: bool,
+    pub requirements: Vec<Requirement>,
+}
+
+/// Linux package manager families, for install hints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Zypper,
+    Unknown,
+}
+
+impl PackageManager {
+    /// Pick the package manager from the contents of `/etc/os-release`.
+    fn from_os_release(os_release: &str) -> Self {
+        let ids: Vec<String> = os_release
+            .lines()
+            .filter_map(|l| l.strip_prefix("ID=").or_else(|| l.strip_prefix("ID_LIKE=")))
+            .flat_map(|v| v.trim_matches('"').split_whitespace().map(str::to_string).collect::<Vec<_>>())
+            .collect();
+        let has = |names: &[&str]| ids.iter().any(|id| names.contains(&id.as_str()));
+        if has(&["debian", "ubuntu"]) {
+            PackageManager::Apt
+        } else if has(&["fedora", "rhel", "centos"]) {
+            PackageManager::Dnf
+        } else if has(&["arch"]) {
+            PackageManager::Pacman
+        } else if has(&["suse", "opensuse"]) {
+            PackageManager::Zypper
+        } else {
+            PackageManager::Unknown
+        }
+    }
+
+    fn install_hint(self, packages: &str) -> String {
+        match self {
+            PackageManager::Apt => format!("Install it with: sudo apt install {}", packages),
+            PackageManager::Dnf => format!("Install it with: sudo dnf install {}", packages),
+            PackageManager::Pacman => format!("Install it with: sudo pacman -S {}", packages),
+            PackageManager::Zypper => format!("Install it with: sudo zypper install {}", packages),
+            PackageManager::Unknown => format!("Install the {} package(s) from your distribution", packages),
+        }
+    }
+}
+
+fn first_existing(paths: &[&str]) -> Option<PathBuf> {
+    paths.iter().map(PathBuf::from).find(|p| p.exists())
+}
+
+pub(crate) fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(binary)).find(|p| p.is_file())
+}
+
+fn requirement(id: &str, name: &str, purpose: &str, found: Option<PathBuf>, remediation: String) -> Requirement {
+    let status = if found.is_some() { RequirementStatus::Available } else { RequirementStatus::Missing };
+    Requirement {
+        id: id.to_string(),
+        name: name.to_string(),
+        status,
+        path: found.as_deref().map(|p| p.display().to_string()),
+        purpose: purpose.to_string(),
+        remediation: (status != RequirementStatus::Available).then_some(remediation),
+    }
+}
+
+fn linux_requirements() -> Vec<Requirement> {
+    let pm = PackageManager::from_os_release(&std::fs::read_to_string("/etc/os-release").unwrap_or_default());
+    let gvfs_package = match pm {
+        PackageManager::Apt => "gvfs-backends",
+        _ => "gvfs",
+    };
+    let gio_package = match pm {
+        PackageManager::Apt => "libglib2.0-bin",
+        _ => "glib2",
+    };
+    vec![
+        requirement(
+            "gvfsd-dav",
+            "GVFS WebDAV backend",
+            "Mounting the drive in the file manager",
+            first_existing(GVFSD_DAV_PATHS),
+            pm.install_hint(gvfs_package),
+        ),
+        requirement(
+            "gio",
+            "gio command-line tool",
+            "Mounting and unmounting from scripts and the terminal",
+            find_in_path("gio"),
+            pm.install_hint(gio_package),
+        ),
+        requirement(
+            "davfs2",
+            "davfs2",
+            "Mounting the drive as a regular filesystem without GVFS",
+            first_existing(DAVFS_PATHS),
+            pm.install_hint("davfs2"),
+        ),
+    ]
+}
+
+fn macos_requirements() -> Vec<Requirement> {
+    vec![requirement(
+        "mount_webdav",
+        "mount_webdav",
+        "Mounting the drive in Finder",
+        Some(PathBuf::from(MOUNT_WEBDAV_PATH)).filter(|p| p.exists()),
+        "mount_webdav ships with macOS; reinstall or update macOS to restore it".to_string(),
+    )]
+}
+
+/// Parse the `STATE` line of `sc query` output. `None` means the service
+/// isn't installed.
+fn parse_sc_state(output: &str) -> Option<RequirementStatus> {
+    if output.contains("1060") {
+        return None;
+    }
+    let state = output.lines().find(|l| l.trim_start().starts_with("STATE"))?;
+    Some(if state.contains("RUNNING") { RequirementStatus::Available } else { RequirementStatus::Inactive })
+}
+
+fn windows_requirements() -> Vec<Requirement> {
+    let output = std::process::Command::new("sc").args(["query", "WebClient"]).output();
+    let state = output.ok().and_then(|o| parse_sc_state(&String::from_utf8_lossy(&o.stdout)));
+    let (status, remediation) = match state {
+        Some(RequirementStatus::Available) => (RequirementStatus::Available, None),
+        Some(_) => (
+            RequirementStatus::Inactive,
+            Some("Start the WebClient service: sc config WebClient start= auto && net start WebClient (as administrator)".to_string()),
+        ),
+        None => (
+            RequirementStatus::Missing,
+            Some("Enable the \"WebDAV Redirector\" Windows feature, then start the WebClient service".to_string()),
+        ),
+    };
+    vec![Requirement {
+        id: "webclient".to_string(),
+        name: "WebClient service".to_string(),
+        status,
+        path: None,
+        purpose: "Mapping the drive in Explorer".to_string(),
+        remediation,
+    }]
+}
+
+/// Probe the current system.
+pub fn probe() -> SystemReport {
+    let requirements = if cfg!(target_os = "linux") {
+        linux_requirements()
+    } else if cfg!(target_os = "macos") {
+        macos_requirements()
+    } else if cfg!(target_os = "windows") {
+        windows_requirements()
+    } else {
+        Vec::new()
+    };
+    // Any of these can mount the share on its own; `gio` is only a helper.
+    // A stopped WebClient service is started on demand by Windows.
+    let can_mount = requirements
+        .iter()
+        .any(|r| r.status != RequirementStatus::Missing && r.id != "gio");
+    SystemReport { platform: std::env::consts::OS.to_string(), can_mount, requirements }
+}
+
+/// Remediation for the preferred mount method, when none is available.
+pub fn mount_remediation(report: &SystemReport) -> Option<String> {
+    if report.can_mount {
+        return None;
+    }
+    report.requirements.iter().filter(|r| r.id != "gio").find_map(|r| r.remediation.clone())
+}
+
+#[tauri::command]
+pub async fn check_system_requirements() -> Result<SystemReport, CommandError> {
+    tauri::async_runtime::spawn_blocking(probe)
+        .await
+        .map_err(|e| CommandError::Unknown(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_manager_from_os_release() {
+        assert_eq!(PackageManager::from_os_release("ID=ubuntu\nID_LIKE=debian\n"), PackageManager::Apt);
+        assert_eq!(PackageManager::from_os_release("ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n"), PackageManager::Dnf);
+        assert_eq!(PackageManager::from_os_release("ID=endeavouros\nID_LIKE=arch\n"), PackageManager::Pacman);
+        assert_eq!(PackageManager::from_os_release("ID=nixos\n"), PackageManager::Unknown);
+    }
+
+    #[test]
+    fn test_parse_sc_state() {
+        let running = "SERVICE_NAME: WebClient\n        TYPE               : 20  WIN32_SHARE_PROCESS\n        STATE              : 4  RUNNING\n";
+        let stopped = "SERVICE_NAME: WebClient\n        STATE              : 1  STOPPED\n";
+        let missing = "[SC] EnumQueryServicesStatus:OpenService FAILED 1060:\n";
+        assert_eq!(parse_sc_state(running), Some(RequirementStatus::Available));
+        assert_eq!(parse_sc_state(stopped), Some(RequirementStatus::Inactive));
+        assert_eq!(parse_sc_state(missing), None);
+    }
+
+    #[test]
+    fn test_requirement_sets_remediation_only_when_missing() {
+        let found = requirement("x", "X", "", Some(PathBuf::from("/bin/sh")), "install x".into());
+        let missing = requirement("x", "X", "", None, "install x".into());
+        assert_eq!(found.remediation, None);
+        assert_eq!(missing.remediation.as_deref(), Some("install x"));
+        assert_eq!(missing.status, RequirementStatus::Missing);
+    }
+}
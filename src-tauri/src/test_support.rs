@@ -0,0 +1,176 @@
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use tauri::async_runtime::block_on;
+
+use crate::sidecar::{CommandError, StatusResponse};
+use crate::sidecar_commands::CommandOutput;
+
+pub use crate::supervisor::SidecarState;
+
+// ============================================================================
+// Test support
+// ============================================================================
+//
+// Drives the `mock-bridge` binary (see `src/bin/mock_bridge.rs`) through the
+// same parsing and supervision the app applies to the real bridge, so the
+// integration tests in `tests/` can cover status, login and the bridge's
+// lifecycle without a Tauri window or Proton credentials. Setting
+// `PROTON_DRIVE_BRIDGE_BINARY` in a build with the `mock-sidecar` feature
+// also points the app itself at the mock (see `sandbox.rs`).
+
+/// Overrides the bridge executable in `mock-sidecar` builds.
+pub const BINARY_ENV: &str = "PROTON_DRIVE_BRIDGE_BINARY";
+
+/// How to run the mock bridge, and in which scenario.
+#[derive(Clone, Debug)]
+pub struct MockBridge {
+    program: PathBuf,
+    env: Vec<(String, String)>,
+}
+
+impl MockBridge {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self { program: program.into(), env: Vec::new() }
+    }
+
+    /// Set a `MOCK_BRIDGE_*` scenario variable.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(args).envs(self.env.iter().map(|(k, v)| (k, v)));
+        command
+    }
+
+    /// Run `auth login` and judge its outcome the way `login` does.
+    pub fn login(&self, email: &str) -> Result<(), CommandError> {
+        let output = self.command(&["auth", "login", "--username", email]).output().map_err(|e| CommandError::SidecarSpawnFailed(e.to_string()))?;
+        crate::sidecar::login_result(CommandOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    /// Start an `rpc` control process.
+    pub fn rpc(&self) -> std::io::Result<RpcSession> {
+        let mut child = self.command(&["rpc"]).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(RpcSession { child, stdin, stdout, next_id: 1 })
+    }
+
+    /// Start the server and hand it to `supervisor`, as `start_sidecar` does
+    /// with the real bridge. Its exit is reported to the supervisor.
+    pub fn start(&self, supervisor: &SidecarState, port: u16) -> std::io::Result<RunningBridge> {
+        let port = port.to_string();
+        let mut child = self
+            .command(&["start", "--no-auth", "--no-daemon", "--host", "127.0.0.1", "--port", &port])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let pid = child.id();
+        block_on(supervisor.claim(pid, false));
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (line_tx, lines) = mpsc::channel();
+        let (exit_tx, exit) = mpsc::channel();
+        let supervisor = supervisor.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let _ = line_tx.send(line);
+            }
+            let _ = child.wait();
+            let _ = exit_tx.send(block_on(supervisor.exited(pid)));
+        });
+        Ok(RunningBridge { pid, lines, exit })
+    }
+}
+
+/// A JSON-RPC session with the mock's `rpc` process.
+pub struct RpcSession {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl RpcSession {
+    /// Send a request and read lines until its response, skipping other
+    /// output as the app's control channel does.
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        writeln!(self.stdin, "{}", request).map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+                return Err(format!("The bridge exited while handling {}", method));
+            }
+            if let Some((response_id, reply)) = crate::sidecar_client::parse_response(&line) {
+                if response_id == id {
+                    return reply.map_err(|failure| failure.message);
+                }
+            }
+        }
+    }
+
+    /// The bridge's status, parsed like `get_status` parses it.
+    pub fn status(&mut self) -> Result<StatusResponse, CommandError> {
+        let payload = self.call("status", Value::Null).map_err(CommandError::SidecarCommandFailed)?;
+        Ok(crate::status_schema::parse(payload).0?)
+    }
+}
+
+impl Drop for RpcSession {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A mock server started with [`MockBridge::start`].
+pub struct RunningBridge {
+    pub pid: u32,
+    lines: Receiver<String>,
+    exit: Receiver<bool>,
+}
+
+impl RunningBridge {
+    /// The next line of output within `timeout`.
+    pub fn next_line(&self, timeout: Duration) -> Option<String> {
+        self.lines.recv_timeout(timeout).ok()
+    }
+
+    /// Wait for the process to exit. Returns whether the supervisor had
+    /// been asked to stop it, i.e. whether it was not a crash.
+    pub fn wait_exit(&self, timeout: Duration) -> Option<bool> {
+        self.exit.recv_timeout(timeout).ok()
+    }
+}
+
+/// Whether a line of bridge output makes the app refresh its status.
+pub fn affects_status(line: &str) -> bool {
+    crate::status::affects_status(line)
+}
+
+/// Stop the tracked bridge as `stop_sidecar` does: mark the stop as
+/// requested, signal the process and wait for it to exit.
+pub fn stop(supervisor: &SidecarState) -> Result<(), CommandError> {
+    let tracked = block_on(supervisor.request_stop()).ok_or(CommandError::SidecarNotRunning)?;
+    block_on(supervisor.stop_child(tracked.pid))
+}
+
+/// PID of the bridge `supervisor` tracks.
+pub fn tracked_pid(supervisor: &SidecarState) -> Option<u32> {
+    block_on(supervisor.pid())
+}
@@ -0,0 +1,474 @@
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Semaphore;
+
+use crate::dav::{normalize_path, DavClient, DavEntry};
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::CommandError;
+use crate::system_requirements::find_in_path;
+
+// ============================================================================
+// Thumbnails
+// ============================================================================
+//
+// `get_thumbnail` gives the in-app file browser a small preview of an image
+// or video on the drive. Thumbnails are rendered in one of three sizes and
+// cached under the app cache dir, keyed by path, size and the file's ETag,
+// so an edited file gets a new one. Rendering fetches as little as it can:
+// for JPEGs the first `HEAD_BYTES` are requested with a Range request, and
+// the small thumbnail most cameras embed in the EXIF data is used for the
+// smallest size. Videos are rendered from their first `VIDEO_HEAD_BYTES`,
+// which is enough for files whose index comes first. Anything else is
+// downloaded whole, up to `MAX_SOURCE_BYTES`, and handed to the first
+// thumbnailer found on `PATH` (`gdk-pixbuf-thumbnailer`, ImageMagick,
+// `ffmpegthumbnailer` or `ffmpeg`). The cache is trimmed to
+// `MAX_CACHE_BYTES`, least recently used first. Nothing is rendered in
+// travel mode, which also purges the cache.
+
+/// Edge lengths thumbnails are rendered at; requests are rounded up.
+const SIZES: &[u32] = &[128, 256, 512];
+
+/// Leading bytes of a JPEG searched for an EXIF thumbnail.
+const HEAD_BYTES: usize = 128 * 1024;
+
+/// Leading bytes of a video rendered from before downloading it whole.
+const VIDEO_HEAD_BYTES: usize = 16 * 1024 * 1024;
+
+const MAX_SOURCE_BYTES: u64 = 256 * 1024 * 1024;
+
+const MAX_CACHE_BYTES: u64 = 200 * 1024 * 1024;
+
+/// Directory under the app cache dir holding generated thumbnails.
+pub(crate) const CACHE_DIR: &str = "thumbnails";
+
+/// Thumbnails rendered at the same time.
+const MAX_CONCURRENT: usize = 2;
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp", "tif", "tiff", "heic", "heif", "avif"];
+
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "mkv", "webm", "avi", "wmv", "mpg", "mpeg", "3gp"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MediaKind {
+    Jpeg,
+    Image,
+    Video,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Thumbnail {
+    /// Cached file
+    pub path: String,
+    pub mime_type: String,
+    /// Edge length it was rendered for
+    pub size: u32,
+    /// Whether it was already cached
+    pub cached: bool,
+    /// The image, base64-encoded, when asked for inline
+    pub data: Option<String>,
+}
+
+pub struct ThumbnailState {
+    rendering: Semaphore,
+}
+
+impl ThumbnailState {
+    pub fn new() -> Self {
+        Self { rendering: Semaphore::new(MAX_CONCURRENT) }
+    }
+}
+
+/// The rendered size for a requested edge length.
+fn size_bucket(requested: u32) -> u32 {
+    SIZES.iter().copied().find(|s| *s >= requested).unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+/// What kind of media `entry` is, by content type, else by extension.
+fn media_kind(entry: &DavEntry) -> Option<MediaKind> {
+    let extension = entry.name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()).unwrap_or_default();
+    let content_type = entry.content_type.as_deref().unwrap_or("").to_ascii_lowercase();
+    if content_type == "image/jpeg" || extension == "jpg" || extension == "jpeg" {
+        Some(MediaKind::Jpeg)
+    } else if content_type.starts_with("image/") || IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        Some(MediaKind::Image)
+    } else if content_type.starts_with("video/") || VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+        Some(MediaKind::Video)
+    } else {
+        None
+    }
+}
+
+/// Cache file name stem of the thumbnail of `entry` at `size`.
+fn cache_key(entry: &DavEntry, size: u32) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(entry.path.as_bytes());
+    for part in [entry.etag.as_deref(), entry.modified.as_deref()] {
+        hasher.update([0]);
+        hasher.update(part.unwrap_or("").as_bytes());
+    }
+    hasher.update(entry.size.unwrap_or(0).to_le_bytes());
+    format!("{}-{}", hex::encode(hasher.finalize()), size)
+}
+
+/// The thumbnail TIFF/EXIF data points to in IFD1, if it is a JPEG.
+fn tiff_thumbnail(tiff: &[u8]) -> Option<&[u8]> {
+    let little_endian = match tiff.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| -> Option<u16> {
+        let b: [u8; 2] = tiff.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if little_endian { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) })
+    };
+    let u32_at = |offset: usize| -> Option<usize> {
+        let b: [u8; 4] = tiff.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) } as usize)
+    };
+    if u16_at(2)? != 42 {
+        return None;
+    }
+    let ifd0 = u32_at(4)?;
+    let ifd1 = u32_at(ifd0 + 2 + usize::from(u16_at(ifd0)?) * 12)?;
+    if ifd1 == 0 {
+        return None;
+    }
+    let (mut offset, mut length) = (None, None);
+    for i in 0..usize::from(u16_at(ifd1)?) {
+        let entry = ifd1 + 2 + i * 12;
+        match u16_at(entry)? {
+            0x0201 => offset = u32_at(entry + 8),
+            0x0202 => length = u32_at(entry + 8),
+            _ => {}
+        }
+    }
+    let (offset, length) = (offset?, length?);
+    let thumbnail = tiff.get(offset..offset.checked_add(length)?)?;
+    thumbnail.starts_with(&[0xFF, 0xD8]).then_some(thumbnail)
+}
+
+/// The thumbnail embedded in the EXIF data of the start of a JPEG.
+fn exif_thumbnail(jpeg: &[u8]) -> Option<&[u8]> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    while pos + 4 <= jpeg.len() {
+        if jpeg[pos] != 0xFF {
+            return None;
+        }
+        let marker = jpeg[pos + 1];
+        match marker {
+            // Fill byte
+            0xFF => pos += 1,
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => pos += 2,
+            // Image data starts; metadata comes before it
+            0xDA | 0xD9 => return None,
+            _ => {
+                let length = usize::from(u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]));
+                let data = jpeg.get(pos + 4..pos + 2 + length.max(2))?;
+                if marker == 0xE1 {
+                    if let Some(thumbnail) = data.strip_prefix(b"Exif\0\0").and_then(tiff_thumbnail) {
+                        return Some(thumbnail);
+                    }
+                }
+                pos += 2 + length;
+            }
+        }
+    }
+    None
+}
+
+/// Commands that render `source` into `target` at `size`, for the tools
+/// found on `PATH`, in order of preference.
+fn thumbnailer_commands(kind: MediaKind, source: &Path, target: &Path, size: u32) -> Vec<(&'static str, Vec<String>)> {
+    let source = source.display().to_string();
+    let target = target.display().to_string();
+    let edge = size.to_string();
+    let scale = format!("scale={0}:{0}:force_original_aspect_ratio=decrease", size);
+    let ffmpeg = |seek: &str| {
+        let mut args: Vec<String> = vec!["-v".into(), "error".into(), "-y".into()];
+        if !seek.is_empty() {
+            args.extend(["-ss".into(), seek.into()]);
+        }
+        args.extend(["-i".into(), source.clone(), "-frames:v".into(), "1".into(), "-vf".into(), scale.clone(), target.clone()]);
+        ("ffmpeg", args)
+    };
+    match kind {
+        MediaKind::Video => vec![
+            ("ffmpegthumbnailer", vec!["-i".into(), source.clone(), "-o".into(), target.clone(), "-s".into(), edge.clone()]),
+            ffmpeg("1"),
+            ffmpeg(""),
+        ],
+        MediaKind::Jpeg | MediaKind::Image => vec![
+            ("gdk-pixbuf-thumbnailer", vec!["-s".into(), edge.clone(), source.clone(), target.clone()]),
+            ("magick", vec![source.clone(), "-auto-orient".into(), "-thumbnail".into(), format!("{0}x{0}", size), target.clone()]),
+            ("convert", vec![format!("{}[0]", source), "-auto-orient".into(), "-thumbnail".into(), format!("{0}x{0}", size), target.clone()]),
+            ffmpeg(""),
+        ],
+    }
+}
+
+/// Render `source` with the first thumbnailer that works. Blocks.
+fn render(kind: MediaKind, source: &Path, target: &Path, size: u32) -> Result<&'static str, String> {
+    let mut tried = Vec::new();
+    for (program, args) in thumbnailer_commands(kind, source, target, size) {
+        let Some(executable) = find_in_path(program) else { continue };
+        let _ = std::fs::remove_file(target);
+        match std::process::Command::new(executable).args(&args).output() {
+            Ok(output) if output.status.success() && std::fs::metadata(target).is_ok_and(|m| m.len() > 0) => {
+                return Ok(program);
+            }
+            Ok(output) => tried.push(format!("{}: {}", program, String::from_utf8_lossy(&output.stderr).trim())),
+            Err(e) => tried.push(format!("{}: {}", program, e)),
+        }
+    }
+    if tried.is_empty() {
+        Err("no thumbnailer installed".into())
+    } else {
+        Err(tried.join("; "))
+    }
+}
+
+/// Download `path` into `file`, giving up beyond `MAX_SOURCE_BYTES`.
+async fn download(client: &DavClient, path: &str, file: &Path) -> Result<(), CommandError> {
+    use futures_util::StreamExt;
+    use tokio::io::AsyncWriteExt;
+
+    let mut body = client.get(path).await?.bytes_stream();
+    let mut out = tokio::fs::File::create(file).await?;
+    let mut written = 0u64;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| CommandError::WebDavError(e.to_string()))?;
+        written += chunk.len() as u64;
+        if written > MAX_SOURCE_BYTES {
+            return Err(CommandError::ThumbnailUnavailable(format!("{}: {}", path, too_large())));
+        }
+        out.write_all(&chunk).await?;
+    }
+    out.flush().await?;
+    Ok(())
+}
+
+fn too_large() -> String {
+    format!("larger than {} MiB", MAX_SOURCE_BYTES / (1024 * 1024))
+}
+
+/// Drop the least recently used thumbnails beyond `MAX_CACHE_BYTES`.
+fn trim_cache(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    let mut files: Vec<(PathBuf, u64, std::time::SystemTime)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            Some((e.path(), meta.len(), meta.modified().ok()?))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, len, _) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+fn cached_thumbnail(dir: &Path, key: &str) -> Option<PathBuf> {
+    ["jpg", "png"].iter().map(|ext| dir.join(format!("{}.{}", key, ext))).find(|p| p.is_file())
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => "image/png",
+        _ => "image/jpeg",
+    }
+}
+
+/// Render the thumbnail of `entry` into the cache dir `dir`.
+async fn generate(client: &DavClient, entry: &DavEntry, kind: MediaKind, dir: &Path, key: &str, size: u32) -> Result<PathBuf, CommandError> {
+    if kind == MediaKind::Jpeg && size == SIZES[0] {
+        if let Some(thumbnail) = exif_thumbnail(&client.get_head(&entry.path, HEAD_BYTES).await?) {
+            let target = dir.join(format!("{}.jpg", key));
+            tokio::fs::write(&target, thumbnail).await?;
+            return Ok(target);
+        }
+    }
+
+    let source = dir.join(format!("{}.source", key));
+    let target = dir.join(format!("{}.png", key));
+    // Leading bytes to render from, `None` for the whole file
+    let mut attempts = Vec::new();
+    if kind == MediaKind::Video && entry.size.is_none_or(|s| s > VIDEO_HEAD_BYTES as u64) {
+        attempts.push(Some(VIDEO_HEAD_BYTES));
+    }
+    if entry.size.is_none_or(|s| s <= MAX_SOURCE_BYTES) {
+        attempts.push(None);
+    }
+    let mut reason = too_large();
+    for head in attempts {
+        let fetched = match head {
+            Some(len) => match client.get_head(&entry.path, len).await {
+                Ok(bytes) => tokio::fs::write(&source, bytes).await.map_err(CommandError::from),
+                Err(e) => Err(e),
+            },
+            None => download(client, &entry.path, &source).await,
+        };
+        if let Err(e) = fetched {
+            let _ = tokio::fs::remove_file(&source).await;
+            return Err(e);
+        }
+        let (from, to) = (source.clone(), target.clone());
+        let rendered = tauri::async_runtime::spawn_blocking(move || render(kind, &from, &to, size))
+            .await
+            .map_err(|e| CommandError::Unknown(e.to_string()))?;
+        let _ = tokio::fs::remove_file(&source).await;
+        match rendered {
+            Ok(program) => {
+                log::debug!("Rendered the thumbnail of {} with {}", entry.path, program);
+                return Ok(target);
+            }
+            Err(e) => reason = e,
+        }
+    }
+    Err(CommandError::ThumbnailUnavailable(format!("{}: {}", entry.path, reason)))
+}
+
+/// A thumbnail of the image or video at `remote_path`, about `size` pixels
+/// on its longer edge (default 256), rendered and cached on first use. With
+/// `inline` the image is also returned base64-encoded.
+#[tauri::command]
+pub async fn get_thumbnail(
+    app: AppHandle,
+    state: State<'_, ThumbnailState>,
+    remote_path: String,
+    size: Option<u32>,
+    inline: Option<bool>,
+) -> Result<Thumbnail, CommandError> {
+    let remote_path = normalize_path(&remote_path);
+    if app.state::<ReadOnlyState>().status().travel_mode {
+        return Err(CommandError::ThumbnailUnavailable("not kept in travel mode".into()));
+    }
+    let size = size_bucket(size.unwrap_or(256));
+    let client = DavClient::for_app(&app)?;
+    let entry = client
+        .stat(&remote_path)
+        .await?
+        .ok_or_else(|| CommandError::RemotePathNotFound(remote_path.clone()))?;
+    let kind = media_kind(&entry)
+        .filter(|_| !entry.is_dir)
+        .ok_or_else(|| CommandError::ThumbnailUnavailable(format!("{}: not an image or video", remote_path)))?;
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| CommandError::IoError(e.to_string()))?
+        .join(CACHE_DIR);
+    tokio::fs::create_dir_all(&dir).await?;
+    let key = cache_key(&entry, size);
+
+    let (path, cached) = match cached_thumbnail(&dir, &key) {
+        Some(path) => (path, true),
+        None => {
+            let _permit = state.rendering.acquire().await.map_err(|e| CommandError::Unknown(e.to_string()))?;
+            // Another request may have rendered it meanwhile
+            match cached_thumbnail(&dir, &key) {
+                Some(path) => (path, true),
+                None => {
+                    let path = generate(&client, &entry, kind, &dir, &key, size).await?;
+                    trim_cache(&dir);
+                    (path, false)
+                }
+            }
+        }
+    };
+    if cached {
+        // Mark as recently used for `trim_cache`
+        if let Ok(file) = std::fs::File::options().append(true).open(&path) {
+            let _ = file.set_modified(std::time::SystemTime::now());
+        }
+    }
+    let data = match inline {
+        Some(true) => Some(base64::engine::general_purpose::STANDARD.encode(tokio::fs::read(&path).await?)),
+        _ => None,
+    };
+    Ok(Thumbnail { path: path.display().to_string(), mime_type: mime_type(&path).to_string(), size, cached, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A JPEG start whose EXIF data holds `thumbnail` in IFD1.
+    fn jpeg_with_exif_thumbnail(thumbnail: &[u8]) -> Vec<u8> {
+        let mut tiff = b"II".to_vec();
+        tiff.extend(42u16.to_le_bytes());
+        tiff.extend(8u32.to_le_bytes());
+        // IFD0: no entries, IFD1 at 14
+        tiff.extend(0u16.to_le_bytes());
+        tiff.extend(14u32.to_le_bytes());
+        // IFD1: offset and length of the thumbnail, no further IFD
+        let offset = 14 + 2 + 2 * 12 + 4;
+        tiff.extend(2u16.to_le_bytes());
+        for (tag, value) in [(0x0201u16, offset as u32), (0x0202, thumbnail.len() as u32)] {
+            tiff.extend(tag.to_le_bytes());
+            tiff.extend(4u16.to_le_bytes());
+            tiff.extend(1u32.to_le_bytes());
+            tiff.extend(value.to_le_bytes());
+        }
+        tiff.extend(0u32.to_le_bytes());
+        tiff.extend(thumbnail);
+
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend(tiff);
+        let mut jpeg = vec![0xFF, 0xD8];
+        // A JFIF segment first, as most files have
+        jpeg.extend([0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00]);
+        jpeg.extend([0xFF, 0xE1]);
+        jpeg.extend(((app1.len() + 2) as u16).to_be_bytes());
+        jpeg.extend(app1);
+        jpeg.extend([0xFF, 0xDA, 0x00, 0x02]);
+        jpeg
+    }
+
+    #[test]
+    fn test_exif_thumbnail_found_in_app1() {
+        let thumbnail = [0xFF, 0xD8, 0x01, 0x02, 0xFF, 0xD9];
+        let jpeg = jpeg_with_exif_thumbnail(&thumbnail);
+        assert_eq!(exif_thumbnail(&jpeg), Some(&thumbnail[..]));
+        // Cut short before the thumbnail ends
+        assert_eq!(exif_thumbnail(&jpeg[..jpeg.len() - 8]), None);
+        assert_eq!(exif_thumbnail(b"\x89PNG\r\n"), None);
+        assert_eq!(exif_thumbnail(&[0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02]), None);
+    }
+
+    #[test]
+    fn test_media_kind_and_size_bucket() {
+        let entry = |name: &str, content_type: Option<&str>| DavEntry {
+            path: format!("/{}", name),
+            name: name.into(),
+            is_dir: false,
+            size: Some(1),
+            modified: None,
+            etag: None,
+            content_type: content_type.map(str::to_string),
+        };
+        assert_eq!(media_kind(&entry("a.JPG", None)), Some(MediaKind::Jpeg));
+        assert_eq!(media_kind(&entry("scan", Some("image/png"))), Some(MediaKind::Image));
+        assert_eq!(media_kind(&entry("clip.mkv", None)), Some(MediaKind::Video));
+        assert_eq!(media_kind(&entry("notes.txt", Some("text/plain"))), None);
+        assert_eq!(size_bucket(64), 128);
+        assert_eq!(size_bucket(200), 256);
+        assert_eq!(size_bucket(4096), 512);
+        let mut edited = entry("a.jpg", None);
+        let key = cache_key(&edited, 128);
+        edited.etag = Some("\"2\"".into());
+        assert_ne!(cache_key(&edited, 128), key);
+    }
+}
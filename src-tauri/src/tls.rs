@@ -0,0 +1,425 @@
+use rustls::crypto::aws_lc_rs::{self, kx_group};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::CipherSuite;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio_rustls::TlsAcceptor;
+
+use crate::sidecar::{get_config_file_path, read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// TLS for the WebDAV gateway
+// ============================================================================
+//
+// When HTTPS is enabled the gateway terminates TLS itself with a self-signed
+// certificate kept next to `config.json`; the sidecar keeps serving plain
+// HTTP on its private loopback port. Settings changes and certificate
+// rotations are applied to new connections without restarting anything.
+
+const CONFIG_KEY: &str = "tls";
+
+const TLS_DIR: &str = "tls";
+const CERT_FILE: &str = "cert.pem";
+const KEY_FILE: &str = "key.pem";
+const CERT_INFO_FILE: &str = "cert.json";
+
+/// Validity of generated certificates.
+const CERT_VALIDITY_DAYS: i64 = 365;
+
+/// Certificates closer than this to expiry are replaced when TLS is loaded.
+const RENEW_BEFORE_DAYS: i64 = 30;
+
+const HSTS_HEADER_VALUE: &str = "max-age=31536000";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum TlsMinVersion {
+    #[default]
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CipherPolicy {
+    /// Every AEAD suite supported by rustls
+    #[default]
+    Modern,
+    /// Only 256-bit AES-GCM and ChaCha20-Poly1305
+    Strict,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TlsSettings {
+    pub enabled: bool,
+    pub min_version: TlsMinVersion,
+    pub cipher_policy: CipherPolicy,
+    /// Prefer the X25519MLKEM768 hybrid key exchange when the client offers it
+    pub post_quantum: bool,
+    /// Send `Strict-Transport-Security` on every response
+    pub hsts: bool,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_version: TlsMinVersion::default(),
+            cipher_policy: CipherPolicy::default(),
+            post_quantum: true,
+            hsts: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    /// SHA-256 of the DER certificate, as colon-separated uppercase hex
+    pub fingerprint_sha256: String,
+    /// Unix timestamps (seconds)
+    pub not_before: i64,
+    pub not_after: i64,
+    pub subject_alt_names: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsStatus {
+    pub settings: TlsSettings,
+    pub certificate: Option<CertificateInfo>,
+    /// PEM file users can import to trust the certificate on other machines
+    pub certificate_path: Option<String>,
+    /// Whether the running gateway is currently serving HTTPS
+    pub active: bool,
+}
+
+struct ActiveTls {
+    acceptor: TlsAcceptor,
+    hsts: bool,
+}
+
+#[derive(Default)]
+pub struct TlsState {
+    active: Mutex<Option<ActiveTls>>,
+}
+
+impl TlsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Acceptor for new gateway connections, or `None` when serving HTTP.
+    pub fn acceptor(&self) -> Option<TlsAcceptor> {
+        self.active.lock().unwrap().as_ref().map(|t| t.acceptor.clone())
+    }
+
+    pub fn hsts_header(&self) -> Option<&'static str> {
+        self.active.lock().unwrap().as_ref().filter(|t| t.hsts).map(|_| HSTS_HEADER_VALUE)
+    }
+
+    /// (Re)load the TLS configuration from the persisted settings. Called by
+    /// the gateway when it starts.
+    pub fn load(&self) -> Result<(), CommandError> {
+        let settings: TlsSettings = read_config_section(CONFIG_KEY);
+        let active = if settings.enabled {
+            ensure_certificate()?;
+            Some(ActiveTls { acceptor: build_acceptor(&settings)?, hsts: settings.hsts })
+        } else {
+            None
+        };
+        *self.active.lock().unwrap() = active;
+        Ok(())
+    }
+
+    pub fn unload(&self) {
+        *self.active.lock().unwrap() = None;
+    }
+
+    fn is_active(&self) -> bool {
+        self.active.lock().unwrap().is_some()
+    }
+
+    /// Apply changed settings or a new certificate if the gateway is serving
+    /// HTTPS right now. Existing connections keep their session.
+    fn reload_if_active(&self) -> Result<(), CommandError> {
+        if self.is_active() {
+            self.load()?;
+        }
+        Ok(())
+    }
+}
+
+fn tls_err(e: impl std::fmt::Display) -> CommandError {
+    CommandError::TlsError(e.to_string())
+}
+
+fn tls_dir() -> Result<PathBuf, CommandError> {
+    let config_file = get_config_file_path()?;
+    let dir = config_file.parent().map(|p| p.join(TLS_DIR)).ok_or_else(|| tls_err("Invalid config path"))?;
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Format a SHA-256 fingerprint the way browsers and `openssl x509` show it.
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der).iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+/// Names the certificate is issued for: loopback plus the configured bind
+/// host when the share is exposed on a specific address.
+fn subject_alt_names() -> Vec<String> {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    let webdav: serde_json::Value = read_config_section("webdav");
+    if let Some(host) = webdav.get("host").and_then(|h| h.as_str()) {
+        if !matches!(host, "" | "0.0.0.0" | "::") && !names.iter().any(|n| n == host) {
+            names.push(host.to_string());
+        }
+    }
+    names
+}
+
+fn write_private(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Generate a fresh self-signed certificate and key, replacing the old ones.
+fn generate_certificate() -> Result<CertificateInfo, CommandError> {
+    let names = subject_alt_names();
+    let mut params = rcgen::CertificateParams::new(names.clone()).map_err(tls_err)?;
+    params.distinguished_name.push(rcgen::DnType::CommonName, "Proton Drive WebDAV Bridge");
+    let now = time::OffsetDateTime::now_utc();
+    let (not_before, not_after) = (now - time::Duration::minutes(5), now + time::Duration::days(CERT_VALIDITY_DAYS));
+    params.not_before = not_before;
+    params.not_after = not_after;
+
+    let key = rcgen::KeyPair::generate().map_err(tls_err)?;
+    let cert = params.self_signed(&key).map_err(tls_err)?;
+
+    let info = CertificateInfo {
+        fingerprint_sha256: fingerprint(cert.der()),
+        not_before: not_before.unix_timestamp(),
+        not_after: not_after.unix_timestamp(),
+        subject_alt_names: names,
+    };
+
+    let dir = tls_dir()?;
+    write_private(&dir.join(KEY_FILE), key.serialize_pem().as_bytes())?;
+    std::fs::write(dir.join(CERT_FILE), cert.pem())?;
+    std::fs::write(dir.join(CERT_INFO_FILE), serde_json::to_vec_pretty(&info).map_err(tls_err)?)?;
+    log::info!("Generated TLS certificate {}", info.fingerprint_sha256);
+    Ok(info)
+}
+
+fn read_certificate_info() -> Option<CertificateInfo> {
+    let dir = tls_dir().ok()?;
+    if !dir.join(CERT_FILE).exists() || !dir.join(KEY_FILE).exists() {
+        return None;
+    }
+    let data = std::fs::read(dir.join(CERT_INFO_FILE)).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+fn needs_renewal(info: &CertificateInfo, now: i64) -> bool {
+    info.not_after - now < RENEW_BEFORE_DAYS * 24 * 60 * 60
+}
+
+/// Make sure a usable certificate exists, generating one if it is missing
+/// or about to expire.
+fn ensure_certificate() -> Result<CertificateInfo, CommandError> {
+    match read_certificate_info() {
+        Some(info) if !needs_renewal(&info, crate::sidecar::unix_now() as i64) => Ok(info),
+        _ => generate_certificate(),
+    }
+}
+
+/// Certificate details for the status view, when HTTPS is enabled.
+pub fn enabled_certificate() -> Option<CertificateInfo> {
+    let settings: TlsSettings = read_config_section(CONFIG_KEY);
+    if settings.enabled {
+        read_certificate_info()
+    } else {
+        None
+    }
+}
+
+fn crypto_provider(settings: &TlsSettings) -> CryptoProvider {
+    let mut provider = aws_lc_rs::default_provider();
+    if settings.cipher_policy == CipherPolicy::Strict {
+        provider.cipher_suites.retain(|s| {
+            matches!(
+                s.suite(),
+                CipherSuite::TLS13_AES_256_GCM_SHA384
+                    | CipherSuite::TLS13_CHACHA20_POLY1305_SHA256
+                    | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384
+                    | CipherSuite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256
+            )
+        });
+    }
+    provider.kx_groups = if settings.post_quantum {
+        vec![kx_group::X25519MLKEM768, kx_group::X25519, kx_group::SECP256R1, kx_group::SECP384R1]
+    } else {
+        vec![kx_group::X25519, kx_group::SECP256R1, kx_group::SECP384R1]
+    };
+    provider
+}
+
+fn build_acceptor(settings: &TlsSettings) -> Result<TlsAcceptor, CommandError> {
+    let dir = tls_dir()?;
+    let certs = CertificateDer::pem_file_iter(dir.join(CERT_FILE))
+        .map_err(tls_err)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(tls_err)?;
+    let key = PrivateKeyDer::from_pem_file(dir.join(KEY_FILE)).map_err(tls_err)?;
+
+    let versions: &[&'static rustls::SupportedProtocolVersion] = match settings.min_version {
+        TlsMinVersion::Tls12 => &[&rustls::version::TLS13, &rustls::version::TLS12],
+        TlsMinVersion::Tls13 => &[&rustls::version::TLS13],
+    };
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(crypto_provider(settings)))
+        .with_protocol_versions(versions)
+        .map_err(tls_err)?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(tls_err)?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn current_status(state: &TlsState) -> TlsStatus {
+    let certificate = read_certificate_info();
+    let certificate_path = certificate
+        .as_ref()
+        .and_then(|_| tls_dir().ok())
+        .map(|dir| dir.join(CERT_FILE).to_string_lossy().to_string());
+    TlsStatus {
+        settings: read_config_section(CONFIG_KEY),
+        certificate,
+        certificate_path,
+        active: state.is_active(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_tls_settings(state: State<'_, TlsState>) -> Result<TlsStatus, CommandError> {
+    Ok(current_status(&state))
+}
+
+/// Switch the gateway between HTTP and HTTPS. A certificate is generated on
+/// first use so its fingerprint can be shown before any client connects.
+/// Existing mounts keep using the old scheme until they are remounted.
+#[tauri::command]
+pub async fn enable_https(app: AppHandle, enabled: bool) -> Result<TlsStatus, CommandError> {
+    let current: TlsSettings = read_config_section(CONFIG_KEY);
+    if enabled {
+        ensure_certificate()?;
+    }
+    write_config_section(CONFIG_KEY, &TlsSettings { enabled, ..current })?;
+
+    let state = app.state::<TlsState>();
+    if crate::gateway::is_running(&app) {
+        state.load()?;
+    }
+
+    let status = current_status(&state);
+    let _ = app.emit("tls:changed", status.clone());
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn set_tls_settings(
+    app: AppHandle,
+    state: State<'_, TlsState>,
+    min_version: TlsMinVersion,
+    cipher_policy: CipherPolicy,
+    post_quantum: bool,
+    hsts: bool,
+) -> Result<TlsStatus, CommandError> {
+    let current: TlsSettings = read_config_section(CONFIG_KEY);
+    let settings = TlsSettings { min_version, cipher_policy, post_quantum, hsts, ..current };
+    write_config_section(CONFIG_KEY, &settings)?;
+    state.reload_if_active()?;
+
+    let status = current_status(&state);
+    let _ = app.emit("tls:changed", status.clone());
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn rotate_certificate(app: AppHandle) -> Result<CertificateInfo, CommandError> {
+    let info = generate_certificate()?;
+    let state = app.state::<TlsState>();
+    state.reload_if_active()?;
+    crate::status::invalidate(&app);
+    let _ = app.emit("tls:changed", current_status(&state));
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_format() {
+        let fp = fingerprint(b"certificate");
+        assert_eq!(fp.len(), 32 * 3 - 1);
+        assert!(fp.split(':').all(|b| b.len() == 2 && b.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_lowercase())));
+    }
+
+    #[test]
+    fn test_needs_renewal() {
+        let info = CertificateInfo {
+            fingerprint_sha256: String::new(),
+            not_before: 0,
+            not_after: 100 * 24 * 60 * 60,
+            subject_alt_names: vec![],
+        };
+        assert!(!needs_renewal(&info, 0));
+        assert!(needs_renewal(&info, 80 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn test_tls_settings_defaults_and_serialization() {
+        let settings: TlsSettings = serde_json::from_str(r#"{"minVersion":"1.3"}"#).unwrap();
+        assert_eq!(settings.min_version, TlsMinVersion::Tls13);
+        assert_eq!(settings.cipher_policy, CipherPolicy::Modern);
+        assert!(settings.post_quantum);
+        assert!(!settings.enabled);
+
+        let json = serde_json::to_value(TlsSettings::default()).unwrap();
+        assert_eq!(json["minVersion"], "1.2");
+        assert_eq!(json["cipherPolicy"], "modern");
+    }
+
+    #[test]
+    fn test_strict_policy_keeps_only_256_bit_suites() {
+        let settings = TlsSettings { cipher_policy: CipherPolicy::Strict, ..Default::default() };
+        let provider = crypto_provider(&settings);
+        assert!(!provider.cipher_suites.is_empty());
+        assert!(provider
+            .cipher_suites
+            .iter()
+            .all(|s| s.suite() != CipherSuite::TLS13_AES_128_GCM_SHA256));
+    }
+
+    #[test]
+    fn test_post_quantum_toggle_controls_hybrid_group() {
+        let pq = crypto_provider(&TlsSettings::default());
+        assert_eq!(pq.kx_groups[0].name(), rustls::NamedGroup::X25519MLKEM768);
+        let classic = crypto_provider(&TlsSettings { post_quantum: false, ..Default::default() });
+        assert!(classic.kx_groups.iter().all(|g| g.name() != rustls::NamedGroup::X25519MLKEM768));
+    }
+}
@@ -0,0 +1,173 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Trace IDs
+// ============================================================================
+//
+// Operations that reach the bridge run under a short random trace ID. The
+// ID goes to every sidecar invocation the operation makes (through
+// `PROTON_DRIVE_BRIDGE_TRACE_ID`, or the `traceId` member of an RPC request)
+// and the bridge tags its log lines with `[trace:<id>]`. Failed operations
+// are kept in memory with their ID, so `export_diagnostics` can put each
+// failure next to the bridge's log lines for it. A bridge run started by
+// the app is traced as a whole; its crash is recorded under that ID.
+
+/// Environment variable the bridge reads its trace ID from.
+pub const TRACE_ENV: &str = "PROTON_DRIVE_BRIDGE_TRACE_ID";
+
+/// Failed operations kept for diagnostics.
+const MAX_FAILURES: usize = 50;
+
+tokio::task_local! {
+    static CURRENT: String;
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedOperation {
+    pub trace_id: String,
+    pub operation: String,
+    /// Unix timestamp (seconds) of when the operation started
+    pub started_at: u64,
+    pub elapsed_ms: u64,
+    pub error: String,
+}
+
+#[derive(Default)]
+pub struct TraceState {
+    failures: Mutex<VecDeque<FailedOperation>>,
+}
+
+impl TraceState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_failure(&self, failure: FailedOperation) {
+        let mut failures = self.failures.lock().unwrap();
+        if failures.len() == MAX_FAILURES {
+            failures.pop_front();
+        }
+        failures.push_back(failure);
+    }
+
+    /// Recorded failures, oldest first.
+    pub fn failures(&self) -> Vec<FailedOperation> {
+        self.failures.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+pub fn new_id() -> String {
+    format!("{:08x}", rand::random::<u32>())
+}
+
+/// How log lines carry `id`.
+pub fn tag(id: &str) -> String {
+    format!("[trace:{}]", id)
+}
+
+/// Trace ID of the operation running on this task, if any.
+pub fn current() -> Option<String> {
+    CURRENT.try_with(|id| id.clone()).ok()
+}
+
+/// Run `operation` under a new trace ID and record it if it fails. Nested
+/// calls stay part of the outer operation.
+pub async fn traced<T, F>(app: &AppHandle, operation: &str, future: F) -> Result<T, CommandError>
+where
+    F: Future<Output = Result<T, CommandError>>,
+{
+    if current().is_some() {
+        return future.await;
+    }
+    let id = new_id();
+    let started_at = crate::sidecar::unix_now();
+    let started = Instant::now();
+    let result = CURRENT.scope(id.clone(), future).await;
+    if let Err(e) = &result {
+        log::warn!("{} {} failed: {}", tag(&id), operation, e);
+        app.state::<TraceState>().record_failure(FailedOperation {
+            trace_id: id,
+            operation: operation.to_string(),
+            started_at,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            error: e.to_string(),
+        });
+    }
+    result
+}
+
+/// Lines of `log` tagged with `id`.
+fn matching_lines<'a>(log: &'a str, id: &str) -> Vec<&'a str> {
+    let tag = tag(id);
+    log.lines().filter(|line| line.contains(&tag)).collect()
+}
+
+/// Each failure, newest first, followed by the bridge's log lines for it.
+pub(crate) fn report(failures: &[FailedOperation], bridge_log: &str) -> String {
+    let mut out = String::new();
+    for failure in failures.iter().rev() {
+        out.push_str(&format!(
+            "{} {} at {} failed after {} ms: {}\n",
+            tag(&failure.trace_id),
+            failure.operation,
+            failure.started_at,
+            failure.elapsed_ms,
+            failure.error
+        ));
+        let lines = matching_lines(bridge_log, &failure.trace_id);
+        if lines.is_empty() {
+            out.push_str("  (no bridge log lines)\n");
+        }
+        for line in lines {
+            out.push_str("  ");
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(id: &str, operation: &str) -> FailedOperation {
+        FailedOperation { trace_id: id.into(), operation: operation.into(), started_at: 1700000000, elapsed_ms: 12, error: "boom".into() }
+    }
+
+    #[test]
+    fn test_report_pairs_failures_with_bridge_lines() {
+        let log = "2024-01-01 10:00:00 [INFO] [trace:aaaa0001] Listing trash\n\
+                   2024-01-01 10:00:01 [INFO] unrelated\n\
+                   2024-01-01 10:00:02 [ERROR] [trace:aaaa0001] Session expired\n";
+        let report = report(&[failure("bbbb0002", "share create"), failure("aaaa0001", "trash list")], log);
+        let expected = "[trace:aaaa0001] trash list at 1700000000 failed after 12 ms: boom\n\
+                        \x20 2024-01-01 10:00:00 [INFO] [trace:aaaa0001] Listing trash\n\
+                        \x20 2024-01-01 10:00:02 [ERROR] [trace:aaaa0001] Session expired\n\
+                        \n\
+                        [trace:bbbb0002] share create at 1700000000 failed after 12 ms: boom\n\
+                        \x20 (no bridge log lines)\n\
+                        \n";
+        assert_eq!(report, expected);
+    }
+
+    #[test]
+    fn test_failures_are_capped() {
+        let state = TraceState::new();
+        for i in 0..MAX_FAILURES + 5 {
+            state.record_failure(failure(&format!("{:08x}", i), "op"));
+        }
+        let failures = state.failures();
+        assert_eq!(failures.len(), MAX_FAILURES);
+        assert_eq!(failures[0].trace_id, "00000005");
+    }
+}
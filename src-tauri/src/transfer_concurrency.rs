@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+use tokio::sync::Notify;
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+use crate::transfers::TransferDirection;
+
+// ============================================================================
+// Transfer concurrency
+// ============================================================================
+//
+// Uploads and downloads the app makes itself (the upload and download
+// commands, sync, the backups, the drop folder and the upload spool) take a
+// slot for their direction before they start, so no more than
+// `maxParallelUploads` and `maxParallelDownloads` run at once; the rest
+// wait their turn. Sync runs its transfers side by side up to those limits,
+// so on a fast connection raising them fills the link, and on a flaky one
+// setting them to 1 sends one file at a time. `chunkSizeMb` is the size of
+// the pieces local files are read and sent in. Transfers of WebDAV clients
+// through the gateway are not limited here.
+//
+// Settings live in the `transferConcurrency` config section. A change
+// applies to transfers that haven't started yet; lowering a limit lets
+// those in flight finish.
+
+const CONFIG_KEY: &str = "transferConcurrency";
+
+const MAX_PARALLEL: usize = 16;
+const MAX_CHUNK_SIZE_MB: usize = 64;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransferConcurrency {
+    pub max_parallel_uploads: usize,
+    pub max_parallel_downloads: usize,
+    pub chunk_size_mb: usize,
+}
+
+impl Default for TransferConcurrency {
+    fn default() -> Self {
+        Self { max_parallel_uploads: 3, max_parallel_downloads: 3, chunk_size_mb: 1 }
+    }
+}
+
+/// A resizable set of slots for one direction.
+#[derive(Default)]
+struct Slots {
+    limit: AtomicUsize,
+    active: Mutex<usize>,
+    /// Woken when a slot is freed or the limit changes
+    freed: Notify,
+}
+
+impl Slots {
+    async fn acquire(self: &Arc<Self>) -> TransferSlot {
+        loop {
+            let freed = self.freed.notified();
+            {
+                let mut active = self.active.lock().unwrap();
+                if *active < self.limit.load(Ordering::Relaxed) {
+                    *active += 1;
+                    return TransferSlot(self.clone());
+                }
+            }
+            freed.await;
+        }
+    }
+
+    fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::Relaxed);
+        self.freed.notify_waiters();
+    }
+}
+
+/// A running transfer's slot, given back when dropped.
+pub struct TransferSlot(Arc<Slots>);
+
+impl Drop for TransferSlot {
+    fn drop(&mut self) {
+        *self.0.active.lock().unwrap() -= 1;
+        self.0.freed.notify_waiters();
+    }
+}
+
+#[derive(Default)]
+pub struct TransferConcurrencyState {
+    uploads: Arc<Slots>,
+    downloads: Arc<Slots>,
+    /// In bytes
+    chunk_size: AtomicUsize,
+}
+
+impl TransferConcurrencyState {
+    /// Build the limits from the settings persisted in `config.json`.
+    pub fn from_config() -> Self {
+        let state = Self::default();
+        state.apply(&read_config_section(CONFIG_KEY));
+        state
+    }
+
+    /// Pick up the persisted limits after config.json was replaced.
+    pub fn reload_settings(&self) {
+        self.apply(&read_config_section(CONFIG_KEY));
+    }
+
+    fn apply(&self, settings: &TransferConcurrency) {
+        self.uploads.set_limit(settings.max_parallel_uploads.clamp(1, MAX_PARALLEL));
+        self.downloads.set_limit(settings.max_parallel_downloads.clamp(1, MAX_PARALLEL));
+        self.chunk_size.store(settings.chunk_size_mb.clamp(1, MAX_CHUNK_SIZE_MB) * 1024 * 1024, Ordering::Relaxed);
+    }
+
+    /// Wait for a free slot for a transfer in `direction`. Copies inside the
+    /// drive move no data through the app and take none.
+    pub async fn slot(&self, direction: TransferDirection) -> Option<TransferSlot> {
+        match direction {
+            TransferDirection::Upload => Some(self.uploads.acquire().await),
+            TransferDirection::Download => Some(self.downloads.acquire().await),
+            TransferDirection::Copy => None,
+        }
+    }
+
+    /// How many transfers may run at once in both directions together.
+    pub fn parallel(&self) -> usize {
+        self.uploads.limit.load(Ordering::Relaxed) + self.downloads.limit.load(Ordering::Relaxed)
+    }
+
+    /// Size of the chunks local files are read in, in bytes.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size.load(Ordering::Relaxed)
+    }
+}
+
+#[tauri::command]
+pub async fn get_transfer_concurrency() -> Result<TransferConcurrency, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+#[tauri::command]
+pub async fn set_transfer_concurrency(
+    state: State<'_, TransferConcurrencyState>,
+    max_parallel_uploads: usize,
+    max_parallel_downloads: usize,
+    chunk_size_mb: usize,
+) -> Result<TransferConcurrency, CommandError> {
+    if !(1..=MAX_PARALLEL).contains(&max_parallel_uploads) || !(1..=MAX_PARALLEL).contains(&max_parallel_downloads) {
+        return Err(CommandError::InvalidConfig(format!("Parallel transfers must be between 1 and {}", MAX_PARALLEL)));
+    }
+    if !(1..=MAX_CHUNK_SIZE_MB).contains(&chunk_size_mb) {
+        return Err(CommandError::InvalidConfig(format!("The chunk size must be between 1 and {} MB", MAX_CHUNK_SIZE_MB)));
+    }
+    let settings = TransferConcurrency { max_parallel_uploads, max_parallel_downloads, chunk_size_mb };
+    write_config_section(CONFIG_KEY, &settings)?;
+    state.apply(&settings);
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::FutureExt;
+    use tauri::async_runtime::block_on;
+
+    #[test]
+    fn test_slots_follow_the_limit() {
+        let state = TransferConcurrencyState::default();
+        state.apply(&TransferConcurrency { max_parallel_uploads: 1, ..Default::default() });
+        let first = block_on(state.slot(TransferDirection::Upload));
+        assert!(state.slot(TransferDirection::Upload).now_or_never().is_none());
+        // Downloads have slots of their own
+        assert!(state.slot(TransferDirection::Download).now_or_never().is_some());
+        drop(first);
+        let first = state.slot(TransferDirection::Upload).now_or_never().flatten();
+        assert!(first.is_some());
+
+        state.apply(&TransferConcurrency { max_parallel_uploads: 2, ..Default::default() });
+        assert!(state.slot(TransferDirection::Upload).now_or_never().is_some());
+        assert_eq!(state.parallel(), 5);
+    }
+
+    #[test]
+    fn test_settings_are_clamped() {
+        let state = TransferConcurrencyState::default();
+        state.apply(&TransferConcurrency { max_parallel_uploads: 0, max_parallel_downloads: 100, chunk_size_mb: 0 });
+        assert_eq!(state.parallel(), 1 + MAX_PARALLEL);
+        assert_eq!(state.chunk_size(), 1024 * 1024);
+        let settings: TransferConcurrency = serde_json::from_str(r#"{"maxParallelUploads":8}"#).unwrap();
+        assert_eq!(settings, TransferConcurrency { max_parallel_uploads: 8, ..Default::default() });
+    }
+}
@@ -0,0 +1,344 @@
+use bytes::Bytes;
+use futures_util::Stream;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Transfer tracking
+// ============================================================================
+//
+// Every upload or download that passes through the bridge registers itself
+// here and receives a `TransferTicket`. The ticket is updated as bytes flow,
+// emits throttled `transfer:progress` events, and reports the outcome with a
+// `transfer:finished` event when it is dropped.
+
+/// Minimum delay between two `transfer:progress` events for one transfer.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+    /// Remote-to-remote copy inside the drive
+    Copy,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferOutcome {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferInfo {
+    pub id: u64,
+    pub path: String,
+    pub direction: TransferDirection,
+    pub bytes_transferred: u64,
+    pub total_bytes: Option<u64>,
+    /// Average speed since the transfer started, in bytes per second
+    pub speed_bps: u64,
+    pub eta_seconds: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferFinishedEvent {
+    pub id: u64,
+    pub path: String,
+    pub direction: TransferDirection,
+    pub bytes_transferred: u64,
+    pub outcome: TransferOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct ActiveTransfer {
+    path: String,
+    direction: TransferDirection,
+    total_bytes: Option<u64>,
+    started: Instant,
+    bytes: Arc<AtomicU64>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ActiveTransfer {
+    fn info(&self, id: u64) -> TransferInfo {
+        let bytes = self.bytes.load(Ordering::Relaxed);
+        let speed = speed_bps(bytes, self.started.elapsed());
+        TransferInfo {
+            id,
+            path: self.path.clone(),
+            direction: self.direction,
+            bytes_transferred: bytes,
+            total_bytes: self.total_bytes,
+            speed_bps: speed,
+            eta_seconds: eta_seconds(bytes, self.total_bytes, speed),
+        }
+    }
+}
+
+/// Compute the average throughput in bytes per second.
+fn speed_bps(bytes: u64, elapsed: Duration) -> u64 {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return 0;
+    }
+    (bytes as f64 / secs) as u64
+}
+
+/// Estimate the remaining time, if the total size and a speed are known.
+fn eta_seconds(bytes: u64, total: Option<u64>, speed_bps: u64) -> Option<u64> {
+    let total = total?;
+    if speed_bps == 0 {
+        return None;
+    }
+    Some(total.saturating_sub(bytes).div_ceil(speed_bps))
+}
+
+#[derive(Default)]
+pub struct TransferState {
+    next_id: AtomicU64,
+    active: Arc<Mutex<HashMap<u64, ActiveTransfer>>>,
+}
+
+impl TransferState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new transfer and return the ticket used to report progress.
+    pub fn begin(
+        &self,
+        app: &AppHandle,
+        path: &str,
+        direction: TransferDirection,
+        total_bytes: Option<u64>,
+    ) -> TransferTicket {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let bytes = Arc::new(AtomicU64::new(0));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.active.lock().unwrap().insert(
+            id,
+            ActiveTransfer {
+                path: path.to_string(),
+                direction,
+                total_bytes,
+                started: Instant::now(),
+                bytes: bytes.clone(),
+                cancelled: cancelled.clone(),
+            },
+        );
+
+        TransferTicket {
+            id,
+            app: app.clone(),
+            active: self.active.clone(),
+            bytes,
+            cancelled,
+            last_emit: None,
+            outcome: None,
+        }
+    }
+
+    pub fn list(&self) -> Vec<TransferInfo> {
+        let active = self.active.lock().unwrap();
+        let mut list: Vec<TransferInfo> = active.iter().map(|(id, t)| t.info(*id)).collect();
+        list.sort_by_key(|t| t.id);
+        list
+    }
+
+    /// Flag a transfer as cancelled. The owner of the ticket notices the flag
+    /// on its next chunk and aborts the underlying request.
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.active.lock().unwrap().get(&id) {
+            Some(t) => {
+                t.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Handle held by whoever moves the bytes of a transfer. Dropping it removes
+/// the transfer from the registry; if no outcome was recorded it counts as
+/// failed (the stream was abandoned half-way).
+pub struct TransferTicket {
+    id: u64,
+    app: AppHandle,
+    active: Arc<Mutex<HashMap<u64, ActiveTransfer>>>,
+    bytes: Arc<AtomicU64>,
+    cancelled: Arc<AtomicBool>,
+    last_emit: Option<Instant>,
+    outcome: Option<(TransferOutcome, Option<String>)>,
+}
+
+impl TransferTicket {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn add_bytes(&mut self, n: u64) {
+        self.bytes.fetch_add(n, Ordering::Relaxed);
+        let due = self.last_emit.is_none_or(|t| t.elapsed() >= PROGRESS_EMIT_INTERVAL);
+        if due {
+            self.last_emit = Some(Instant::now());
+            let info = self.active.lock().unwrap().get(&self.id).map(|t| t.info(self.id));
+            if let Some(info) = info {
+                let _ = self.app.emit("transfer:progress", info);
+            }
+        }
+    }
+
+    /// Set the expected size once it is known (e.g. after walking a folder).
+    pub fn set_total_bytes(&self, total: u64) {
+        if let Some(t) = self.active.lock().unwrap().get_mut(&self.id) {
+            t.total_bytes = Some(total);
+        }
+    }
+
+    pub fn complete(&mut self) {
+        self.outcome.get_or_insert((TransferOutcome::Completed, None));
+    }
+
+    pub fn fail(&mut self, error: String) {
+        let outcome = if self.is_cancelled() { TransferOutcome::Cancelled } else { TransferOutcome::Failed };
+        self.outcome.get_or_insert((outcome, Some(error)));
+    }
+}
+
+impl Drop for TransferTicket {
+    fn drop(&mut self) {
+        let Some(entry) = self.active.lock().unwrap().remove(&self.id) else {
+            return;
+        };
+        let (outcome, error) = self.outcome.take().unwrap_or_else(|| {
+            if self.is_cancelled() {
+                (TransferOutcome::Cancelled, None)
+            } else {
+                (TransferOutcome::Failed, Some("Transfer interrupted".to_string()))
+            }
+        });
+        let event = TransferFinishedEvent {
+            id: self.id,
+            path: entry.path,
+            direction: entry.direction,
+            bytes_transferred: self.bytes.load(Ordering::Relaxed),
+            outcome,
+            error,
+        };
+        crate::notifications::transfer_finished(&self.app, &event);
+        crate::recent::transfer_finished(&self.app, &event);
+        let _ = self.app.emit("transfer:finished", event);
+    }
+}
+
+/// Byte stream wrapper that reports every chunk to a [`TransferTicket`] and
+/// aborts with an error once the transfer is cancelled.
+pub struct MeteredStream {
+    inner: Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send>>,
+    ticket: TransferTicket,
+}
+
+impl MeteredStream {
+    pub fn new<S>(inner: S, ticket: TransferTicket) -> Self
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        Self { inner: Box::pin(inner), ticket }
+    }
+}
+
+impl Stream for MeteredStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if this.ticket.is_cancelled() {
+            this.ticket.fail("Cancelled by user".to_string());
+            return Poll::Ready(Some(Err(std::io::Error::other("transfer cancelled"))));
+        }
+        match this.inner.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                this.ticket.add_bytes(chunk.len() as u64);
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                this.ticket.fail(e.to_string());
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => {
+                this.ticket.complete();
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_active_transfers(state: State<'_, TransferState>) -> Result<Vec<TransferInfo>, CommandError> {
+    Ok(state.list())
+}
+
+#[tauri::command]
+pub async fn cancel_transfer(state: State<'_, TransferState>, id: u64) -> Result<(), CommandError> {
+    if state.cancel(id) {
+        Ok(())
+    } else {
+        Err(CommandError::TransferNotFound(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed_bps() {
+        assert_eq!(speed_bps(1_000, Duration::from_secs(2)), 500);
+        assert_eq!(speed_bps(1_000, Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn test_eta_seconds() {
+        assert_eq!(eta_seconds(500, Some(1_000), 100), Some(5));
+        assert_eq!(eta_seconds(950, Some(1_000), 100), Some(1));
+        assert_eq!(eta_seconds(500, None, 100), None);
+        assert_eq!(eta_seconds(500, Some(1_000), 0), None);
+    }
+
+    #[test]
+    fn test_transfer_info_serializes_camel_case() {
+        let info = TransferInfo {
+            id: 1,
+            path: "/a.txt".to_string(),
+            direction: TransferDirection::Upload,
+            bytes_transferred: 10,
+            total_bytes: Some(20),
+            speed_bps: 5,
+            eta_seconds: Some(2),
+        };
+        let json = serde_json::to_value(info).unwrap();
+        assert_eq!(json["direction"], "upload");
+        assert_eq!(json["bytesTransferred"], 10);
+        assert_eq!(json["etaSeconds"], 2);
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::sidecar::{configured_listen_addr, read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Transport
+// ============================================================================
+//
+// By default the sidecar listens on a loopback TCP port picked when it
+// starts. It can listen on a Unix domain socket instead, which no other
+// program's port can collide with and which file permissions protect; on
+// Linux an abstract socket name (`@name`) leaves no file behind at all. GVFS
+// and most WebDAV clients only speak TCP, so in that mode the gateway is a
+// small local forwarder on a free loopback port (see `gateway::Upstream`).
+// The choice is stored in the `transport` config section and applies the
+// next time the bridge starts.
+
+const CONFIG_KEY: &str = "transport";
+
+/// Longest path a `sockaddr_un` holds, leaving room for the terminating NUL.
+const MAX_SOCKET_PATH: usize = 107;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Transport {
+    /// Loopback TCP, with the share on `port`
+    Tcp { port: u16 },
+    /// A socket file, or an abstract socket name starting with `@`
+    UnixSocket { path: String },
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct TransportConfig {
+    unix_socket: Option<String>,
+}
+
+/// The configured transport. TCP uses the share's configured port.
+pub fn configured() -> Transport {
+    let config: TransportConfig = read_config_section(CONFIG_KEY);
+    match config.unix_socket {
+        Some(path) if cfg!(unix) => Transport::UnixSocket { path },
+        _ => Transport::Tcp { port: configured_listen_addr().1 },
+    }
+}
+
+/// Address to connect to for a configured socket path; `@name` becomes an
+/// abstract socket name.
+pub fn socket_path(path: &str) -> PathBuf {
+    match path.strip_prefix('@') {
+        Some(name) => PathBuf::from(format!("\0{}", name)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The configured form of a socket address, as passed to the sidecar.
+pub fn display_socket_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    match path.strip_prefix('\0') {
+        Some(name) => format!("@{}", name),
+        None => path.into_owned(),
+    }
+}
+
+fn invalid(message: impl Into<String>) -> CommandError {
+    CommandError::InvalidTransport(message.into())
+}
+
+fn validate_socket_path(path: &str) -> Result<(), CommandError> {
+    if !cfg!(unix) {
+        return Err(invalid("Unix sockets are not supported on this platform"));
+    }
+    if path.len() > MAX_SOCKET_PATH {
+        return Err(invalid(format!("Socket path is longer than {} bytes", MAX_SOCKET_PATH)));
+    }
+    if let Some(name) = path.strip_prefix('@') {
+        if !cfg!(any(target_os = "linux", target_os = "android")) {
+            return Err(invalid("Abstract socket names are only available on Linux"));
+        }
+        if name.is_empty() {
+            return Err(invalid("Abstract socket name is empty"));
+        }
+        return Ok(());
+    }
+
+    let socket = Path::new(path);
+    if !socket.is_absolute() {
+        return Err(invalid("Socket path must be absolute"));
+    }
+    match socket.parent() {
+        Some(parent) if parent.is_dir() => {}
+        _ => return Err(invalid(format!("The folder for {} does not exist", path))),
+    }
+    #[cfg(unix)]
+    if let Ok(meta) = std::fs::symlink_metadata(socket) {
+        use std::os::unix::fs::FileTypeExt;
+        if !meta.file_type().is_socket() {
+            return Err(invalid(format!("{} exists and is not a socket", path)));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_transport() -> Result<Transport, CommandError> {
+    Ok(configured())
+}
+
+/// Choose how the bridge listens. A TCP port is saved as the share's port.
+/// Takes effect the next time the bridge starts; `set_network_port` moves a
+/// running share.
+#[tauri::command]
+pub async fn set_transport(transport: Transport) -> Result<Transport, CommandError> {
+    let config = match &transport {
+        Transport::Tcp { port } => {
+            crate::sidecar::validate_port(*port)?;
+            crate::sidecar::save_port(*port)?;
+            TransportConfig::default()
+        }
+        Transport::UnixSocket { path } => {
+            validate_socket_path(path)?;
+            TransportConfig { unix_socket: Some(path.clone()) }
+        }
+    };
+    write_config_section(CONFIG_KEY, &config)?;
+    log::info!("WebDAV transport set to {:?}", transport);
+    Ok(configured())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_serde() {
+        let tcp: Transport = serde_json::from_str(r#"{"kind": "tcp", "port": 8080}"#).unwrap();
+        assert_eq!(tcp, Transport::Tcp { port: 8080 });
+        let socket = serde_json::to_value(Transport::UnixSocket { path: "/run/bridge.sock".into() }).unwrap();
+        assert_eq!(socket, serde_json::json!({"kind": "unixSocket", "path": "/run/bridge.sock"}));
+    }
+
+    #[test]
+    fn test_socket_path_round_trip() {
+        assert_eq!(socket_path("/run/bridge.sock"), PathBuf::from("/run/bridge.sock"));
+        assert_eq!(socket_path("@bridge"), PathBuf::from("\0bridge"));
+        assert_eq!(display_socket_path(&socket_path("@bridge")), "@bridge");
+        assert_eq!(display_socket_path(&socket_path("/run/bridge.sock")), "/run/bridge.sock");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_socket_path() {
+        let dir = std::env::temp_dir();
+        assert!(validate_socket_path(&dir.join("bridge.sock").to_string_lossy()).is_ok());
+        assert!(validate_socket_path("relative.sock").is_err());
+        #[cfg(target_os = "linux")]
+        assert!(validate_socket_path("@bridge").is_ok());
+        assert!(validate_socket_path("/no/such/folder/bridge.sock").is_err());
+        assert!(validate_socket_path(&format!("/{}", "a".repeat(MAX_SOCKET_PATH))).is_err());
+
+        let file = dir.join(format!("transport-test-{}", std::process::id()));
+        std::fs::write(&file, b"").unwrap();
+        assert!(validate_socket_path(&file.to_string_lossy()).is_err());
+        std::fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{parse_json_output, sidecar_output, CommandError};
+
+// ============================================================================
+// Trash
+// ============================================================================
+//
+// Files deleted through the mount go to the Proton Drive trash rather than
+// being destroyed. WebDAV has no notion of a trash, so it is reached through
+// the sidecar's `trash` subcommands instead: `list --json`, `restore <uid>`
+// and `empty`. Restoring and emptying change the drive, so they are refused
+// in travel mode and on a read-only share, and they drop the metadata cache
+// so restored files show up in the mount right away.
+
+/// Listing a large trash means decrypting every node in it.
+const LIST_TIMEOUT: Duration = Duration::from_secs(120);
+const CHANGE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub uid: String,
+    pub name: String,
+    /// "file" or "folder"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub size: u64,
+    /// ISO 8601 time the item was trashed, if known
+    pub trashed_time: Option<String>,
+}
+
+/// Run a sidecar `trash` subcommand and return its stdout.
+async fn run_trash_command(app: &AppHandle, args: &[&str], limit: Duration) -> Result<String, CommandError> {
+    let what = format!("trash {}", args[0]);
+    crate::trace::traced(app, &what, async {
+        let command = crate::sandbox::sidecar_command(app)?.arg("trash").args(args);
+        sidecar_output(app, command, &what, limit).await
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_trash(app: AppHandle) -> Result<Vec<TrashItem>, CommandError> {
+    let stdout = run_trash_command(&app, &["list", "--json"], LIST_TIMEOUT).await?;
+    parse_json_output(&stdout, "trash listing")
+}
+
+/// Put a trashed item back in the folder it was deleted from.
+#[tauri::command]
+pub async fn restore_from_trash(app: AppHandle, item_id: String) -> Result<(), CommandError> {
+    app.state::<ReadOnlyState>().check_writable()?;
+    run_trash_command(&app, &["restore", &item_id], CHANGE_TIMEOUT).await?;
+    app.state::<crate::cache::MetadataCache>().clear();
+    log::info!("Restored {} from the trash", item_id);
+    Ok(())
+}
+
+/// Permanently delete everything in the trash.
+#[tauri::command]
+pub async fn empty_trash(app: AppHandle) -> Result<(), CommandError> {
+    app.state::<ReadOnlyState>().check_writable()?;
+    run_trash_command(&app, &["empty"], CHANGE_TIMEOUT).await?;
+    app.state::<crate::cache::MetadataCache>().clear();
+    log::info!("Emptied the trash");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trash_list_skips_leading_output() {
+        let stdout = "10:00:00 \u{1b}[32minfo\u{1b}[39m: Initialized Drive client for user\n[\n  {\"uid\": \"v~1\", \"name\": \"a.txt\", \"type\": \"file\", \"size\": 3, \"trashedTime\": \"2024-05-01T10:00:00.000Z\"},\n  {\"uid\": \"v~2\", \"name\": \"Docs\", \"type\": \"folder\", \"size\": 0, \"trashedTime\": null}\n]\n";
+        let items = parse_json_output::<Vec<TrashItem>>(stdout, "trash listing").unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].kind, "file");
+        assert_eq!(items[1].trashed_time, None);
+    }
+
+    #[test]
+    fn test_parse_trash_list_rejects_missing_json() {
+        assert!(parse_json_output::<Vec<TrashItem>>("Not logged in\n", "trash listing").is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Travel mode
+// ============================================================================
+//
+// Travel mode is a single switch for users who cross borders or lend out
+// their machine. While it is on, subsystems that write to the drive or keep
+// local history stay idle; enabling it also wipes existing browsing traces.
+// Turning it off requires the passphrase chosen when it was enabled, stored
+// as an Argon2id PHC string. After `MAX_FAILURES` wrong passphrases in a row,
+// attempts are refused for `LOCKOUT`, doubled for every further lockout up
+// to `MAX_LOCKOUT`. The count lives in the `travelMode` section, so a
+// restart doesn't reset it, and every attempt is counted as a failure before
+// the passphrase is checked, so parallel guesses can't get past the limit;
+// a right passphrase clears it. It can't be turned on while a bridge started
+// outside the app serves the drive, since that bridge would keep writing.
+
+const CONFIG_KEY: &str = "travelMode";
+
+/// Wrong passphrases in a row that lead to a lockout.
+const MAX_FAILURES: u32 = 5;
+
+/// Seconds of the first lockout.
+const LOCKOUT: u64 = 60;
+
+const MAX_LOCKOUT: u64 = 60 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct TravelModeConfig {
+    enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enabled_at: Option<u64>,
+    /// Argon2id PHC string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    passphrase_hash: Option<String>,
+    #[serde(default, flatten)]
+    attempts: Attempts,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TravelModeStatus {
+    pub enabled: bool,
+    /// Unix timestamp (seconds) of when travel mode was turned on
+    pub enabled_at: Option<u64>,
+}
+
+impl From<&TravelModeConfig> for TravelModeStatus {
+    fn from(cfg: &TravelModeConfig) -> Self {
+        Self { enabled: cfg.enabled, enabled_at: cfg.enabled_at }
+    }
+}
+
+fn hash_passphrase(passphrase: &str, params: argon2::Params) -> Result<String, CommandError> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|e| CommandError::Unknown(e.to_string()))?;
+    Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| CommandError::Unknown(e.to_string()))
+}
+
+/// Run `f` off the async runtime; Argon2 takes a noticeable moment.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T, CommandError> {
+    tauri::async_runtime::spawn_blocking(f).await.map_err(|e| CommandError::IoError(e.to_string()))
+}
+
+/// Check `passphrase` against the stored hash, with the parameters it was
+/// made with.
+fn verify_passphrase(cfg: &TravelModeConfig, passphrase: &str) -> bool {
+    let Some(hash) = cfg.passphrase_hash.as_deref().and_then(|h| PasswordHash::new(h).ok()) else {
+        return false;
+    };
+    Argon2::default().verify_password(passphrase.as_bytes(), &hash).is_ok()
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct Attempts {
+    /// Wrong passphrases since the last lockout or right one
+    #[serde(skip_serializing_if = "is_zero")]
+    failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locked_until: Option<u64>,
+    /// Lockouts so far, for the back-off
+    #[serde(skip_serializing_if = "is_zero")]
+    lockouts: u32,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl Attempts {
+    fn locked_until(&self, now: u64) -> Option<u64> {
+        self.locked_until.filter(|until| *until > now)
+    }
+
+    /// Count a wrong passphrase; returns the end of the lockout if it led to one.
+    fn fail(&mut self, now: u64) -> Option<u64> {
+        self.failures += 1;
+        if self.failures < MAX_FAILURES {
+            return None;
+        }
+        let until = now + LOCKOUT.saturating_mul(1 << self.lockouts.min(16)).min(MAX_LOCKOUT);
+        self.failures = 0;
+        self.lockouts += 1;
+        self.locked_until = Some(until);
+        Some(until)
+    }
+}
+
+#[derive(Default)]
+pub struct TravelModeState {
+    /// Held while the persisted attempt count is read and updated
+    attempts: Mutex<()>,
+}
+
+impl TravelModeState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Remove thumbnails and the recent files list.
+pub(crate) fn purge_local_traces(app: &AppHandle) {
+    crate::recent::clear(app);
+    if let Ok(cache_dir) = app.path().app_cache_dir() {
+        let thumbnails = cache_dir.join(crate::thumbnails::CACHE_DIR);
+        if thumbnails.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&thumbnails) {
+                log::warn!("Failed to purge thumbnails at {}: {}", thumbnails.display(), e);
+            }
+        }
+    }
+}
+
+/// Whether travel mode is on.
+pub(crate) fn is_enabled() -> bool {
+    read_config_section::<TravelModeConfig>(CONFIG_KEY).enabled
+}
+
+#[tauri::command]
+pub async fn get_travel_mode() -> Result<TravelModeStatus, CommandError> {
+    let cfg: TravelModeConfig = read_config_section(CONFIG_KEY);
+    Ok(TravelModeStatus::from(&cfg))
+}
+
+#[tauri::command]
+pub async fn enable_travel_mode(app: AppHandle, passphrase: String) -> Result<TravelModeStatus, CommandError> {
+    let current: TravelModeConfig = read_config_section(CONFIG_KEY);
+    if current.enabled {
+        return Err(CommandError::TravelModeActive);
+    }
+    if passphrase.trim().is_empty() {
+        return Err(CommandError::InvalidPassphrase("Passphrase must not be empty".into()));
+    }
+    if crate::process::serving_adopted(&app).await {
+        return Err(CommandError::AdoptionRefused("travel mode".into()));
+    }
+
+    let hash = blocking(move || hash_passphrase(&passphrase, argon2::Params::default())).await??;
+    let cfg = TravelModeConfig {
+        enabled: true,
+        enabled_at: Some(crate::sidecar::unix_now()),
+        passphrase_hash: Some(hash),
+        attempts: Attempts::default(),
+    };
+    write_config_section(CONFIG_KEY, &cfg)?;
+
+    purge_local_traces(&app);
+    app.state::<crate::read_only::ReadOnlyState>().set_travel(true);
+
+    let status = TravelModeStatus::from(&cfg);
+    let _ = app.emit("travel:changed", status.clone());
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn disable_travel_mode(
+    app: AppHandle,
+    state: State<'_, TravelModeState>,
+    passphrase: String,
+) -> Result<TravelModeStatus, CommandError> {
+    let now = crate::sidecar::unix_now();
+    // Count the attempt as a failure before checking it
+    let (current, lockout) = {
+        let _attempts = state.attempts.lock().unwrap();
+        let mut current: TravelModeConfig = read_config_section(CONFIG_KEY);
+        if !current.enabled {
+            return Ok(TravelModeStatus::from(&current));
+        }
+        if let Some(until) = current.attempts.locked_until(now) {
+            return Err(CommandError::TravelModeLocked(until - now));
+        }
+        let lockout = current.attempts.fail(now);
+        write_config_section(CONFIG_KEY, &current)?;
+        (current, lockout)
+    };
+
+    let checked = current.clone();
+    if !blocking(move || verify_passphrase(&checked, &passphrase)).await? {
+        if let Some(until) = lockout {
+            log::warn!("Travel mode locked until {} after repeated wrong passphrases", until);
+            return Err(CommandError::TravelModeLocked(until - now));
+        }
+        return Err(CommandError::InvalidPassphrase("Passphrase does not match".into()));
+    }
+
+    let cfg = TravelModeConfig::default();
+    {
+        let _attempts = state.attempts.lock().unwrap();
+        write_config_section(CONFIG_KEY, &cfg)?;
+    }
+    app.state::<crate::read_only::ReadOnlyState>().set_travel(false);
+
+    let status = TravelModeStatus::from(&cfg);
+    let _ = app.emit("travel:changed", status.clone());
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so the tests stay fast.
+    fn test_params() -> argon2::Params {
+        argon2::Params::new(1024, 1, 1, None).unwrap()
+    }
+
+    fn config_with(passphrase: &str) -> TravelModeConfig {
+        TravelModeConfig {
+            enabled: true,
+            enabled_at: Some(1),
+            passphrase_hash: Some(hash_passphrase(passphrase, test_params()).unwrap()),
+            attempts: Attempts::default(),
+        }
+    }
+
+    #[test]
+    fn test_hash_passphrase_is_salted_argon2id() {
+        let first = hash_passphrase("secret", test_params()).unwrap();
+        assert!(first.starts_with("$argon2id$"));
+        assert_ne!(first, hash_passphrase("secret", test_params()).unwrap());
+    }
+
+    #[test]
+    fn test_verify_passphrase() {
+        let cfg = config_with("correct horse");
+        assert!(verify_passphrase(&cfg, "correct horse"));
+        assert!(!verify_passphrase(&cfg, "battery staple"));
+    }
+
+    #[test]
+    fn test_verify_passphrase_without_stored_hash_fails() {
+        let cfg = TravelModeConfig { enabled: true, ..Default::default() };
+        assert!(!verify_passphrase(&cfg, ""));
+        let cfg = TravelModeConfig { passphrase_hash: Some("not a hash".into()), ..cfg };
+        assert!(!verify_passphrase(&cfg, ""));
+    }
+
+    #[test]
+    fn test_lockout_after_repeated_failures() {
+        let mut attempts = Attempts::default();
+        for i in 0..MAX_FAILURES as u64 - 1 {
+            assert_eq!(attempts.fail(100 + i), None);
+        }
+        assert_eq!(attempts.fail(110), Some(110 + LOCKOUT));
+        assert_eq!(attempts.locked_until(111), Some(110 + LOCKOUT));
+        assert_eq!(attempts.locked_until(110 + LOCKOUT), None);
+
+        // The next lockout lasts twice as long
+        let later = 110 + LOCKOUT;
+        for i in 0..MAX_FAILURES as u64 - 1 {
+            assert_eq!(attempts.fail(later + i), None);
+        }
+        assert_eq!(attempts.fail(later + 10), Some(later + 10 + 2 * LOCKOUT));
+    }
+
+    #[test]
+    fn test_lockout_is_capped() {
+        let mut attempts = Attempts { lockouts: 30, failures: MAX_FAILURES - 1, ..Default::default() };
+        assert_eq!(attempts.fail(0), Some(MAX_LOCKOUT));
+    }
+
+    #[test]
+    fn test_attempts_survive_a_restart() {
+        let mut cfg = config_with("x");
+        for i in 0..MAX_FAILURES as u64 {
+            cfg.attempts.fail(i);
+        }
+        let json = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(json["lockedUntil"], serde_json::json!(MAX_FAILURES as u64 - 1 + LOCKOUT));
+        let restored: TravelModeConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.attempts, cfg.attempts);
+        assert!(restored.attempts.locked_until(MAX_FAILURES as u64).is_some());
+    }
+
+    #[test]
+    fn test_travel_mode_config_uses_camel_case() {
+        let json = serde_json::to_value(config_with("x")).unwrap();
+        assert!(json.get("enabledAt").is_some());
+        assert!(json.get("passphraseHash").is_some());
+        assert!(json.get("failures").is_none());
+    }
+}
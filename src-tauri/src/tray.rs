@@ -0,0 +1,116 @@
+use tauri::menu::{IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::i18n::t;
+use crate::sidecar::SidecarState;
+
+// ============================================================================
+// Tray icon
+// ============================================================================
+//
+// The tray menu is a shortcut to the drive: it shows the main window, opens
+// the drive in the file manager and lists the most recently used files,
+// which open with their default application. The recent files come from
+// `recent`, which rebuilds the menu whenever they change. Menu ids of recent
+// files are their remote paths behind `RECENT_PREFIX`.
+
+const TRAY_ID: &str = "main";
+
+/// Recent files listed in the menu.
+const MENU_ENTRIES: usize = 10;
+
+const SHOW_WINDOW: &str = "show-window";
+const OPEN_DRIVE: &str = "open-drive";
+const RECENT_PREFIX: &str = "recent:";
+
+/// Menu label of a recent file: its name, then the folder it is in.
+fn recent_label(path: &str) -> String {
+    match path.rsplit_once('/') {
+        Some(("", name)) => name.to_string(),
+        Some((folder, name)) => format!("{} — {}", name, folder),
+        None => path.to_string(),
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let show = MenuItem::with_id(app, SHOW_WINDOW, t("tray.showWindow"), true, None::<&str>)?;
+    let open = MenuItem::with_id(app, OPEN_DRIVE, t("tray.openDrive"), true, None::<&str>)?;
+    let files = crate::recent::recent(app, MENU_ENTRIES);
+    let mut items = Vec::with_capacity(files.len().max(1));
+    for file in &files {
+        let id = format!("{}{}", RECENT_PREFIX, file.path);
+        items.push(MenuItem::with_id(app, id, recent_label(&file.path), true, None::<&str>)?);
+    }
+    if items.is_empty() {
+        items.push(MenuItem::with_id(app, "recent-none", t("tray.noRecentFiles"), false, None::<&str>)?);
+    }
+    let item_refs: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|i| i as &dyn IsMenuItem<Wry>).collect();
+    let recent = Submenu::with_items(app, t("tray.recentFiles"), true, &item_refs)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    Menu::with_items(app, &[&show, &open, &separator, &recent])
+}
+
+fn on_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        SHOW_WINDOW => crate::instance::focus_main_window(app),
+        OPEN_DRIVE => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = crate::sidecar::open_in_files(app.clone(), app.state::<SidecarState>(), None, None).await {
+                    log::warn!("Opening the drive from the tray failed: {}", e);
+                }
+            });
+        }
+        _ => {
+            let Some(path) = id.strip_prefix(RECENT_PREFIX).map(str::to_string) else {
+                return;
+            };
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = crate::recent::open(&app, app.state::<SidecarState>(), &path).await {
+                    log::warn!("Opening {} from the tray failed: {}", path, e);
+                }
+            });
+        }
+    }
+}
+
+/// Add the tray icon. Called once at startup; a desktop without a tray just
+/// goes without.
+pub fn install(app: &AppHandle) {
+    let built = build_menu(app).and_then(|menu| {
+        let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+            .tooltip("Proton Drive WebDAV Bridge")
+            .menu(&menu)
+            .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()));
+        if let Some(icon) = app.default_window_icon() {
+            builder = builder.icon(icon.clone());
+        }
+        builder.build(app)
+    });
+    if let Err(e) = built {
+        log::warn!("No tray icon: {}", e);
+    }
+}
+
+/// Rebuild the menu, e.g. after the recent files changed.
+pub(crate) fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(e) = build_menu(app).and_then(|menu| tray.set_menu(Some(menu))) {
+        log::warn!("Failed to update the tray menu: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_label_puts_name_first() {
+        assert_eq!(recent_label("/report.odt"), "report.odt");
+        assert_eq!(recent_label("/Docs/2024/report.odt"), "report.odt — /Docs/2024");
+    }
+}
@@ -0,0 +1,318 @@
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Bridge updates
+// ============================================================================
+//
+// The bridge can be updated without reinstalling the app. Builds that set
+// `PDWB_SIDECAR_UPDATE_URL` and `PDWB_SIDECAR_UPDATE_PUBLIC_KEY` check a
+// release feed listing the newest bridge per target triple, with the
+// binary's SHA-256 and an Ed25519 signature binding that digest to the
+// version and target triple (see `signed_message`), so a feed can't pass off
+// an old signed build as a newer one. An update is downloaded next to its
+// final place in the app data dir, verified, and renamed over the previous
+// download, so a half-written binary is never run; the version manifest is
+// replaced the same way afterwards.
+// `sandbox::sidecar_command` prefers the downloaded bridge while it is newer
+// than the one bundled with the app (which has the app's version), so
+// installing a newer app makes a stale download fall out of use. A running
+// bridge keeps its binary until it is restarted.
+
+const FEED_URL: Option<&str> = option_env!("PDWB_SIDECAR_UPDATE_URL");
+
+/// Hex-encoded Ed25519 public key updates must be signed with.
+const FEED_PUBLIC_KEY: Option<&str> = option_env!("PDWB_SIDECAR_UPDATE_PUBLIC_KEY");
+
+const FEED_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Downloads are slow; only the connection is bounded.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+const UPDATE_DIR: &str = "sidecar";
+const MANIFEST_FILE: &str = "installed.json";
+const BINARY_NAME: &str = "proton-drive-webdav-bridge";
+
+#[derive(Deserialize, Clone, Debug)]
+struct Feed {
+    version: String,
+    #[serde(default)]
+    notes: Option<String>,
+    /// Keyed by target triple, e.g. `x86_64-unknown-linux-gnu`
+    platforms: HashMap<String, Asset>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct Asset {
+    url: String,
+    /// Hex SHA-256 of the binary
+    sha256: String,
+    /// Base64 Ed25519 signature over `signed_message`
+    signature: String,
+}
+
+/// Written after a verified binary is in place.
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarUpdateInfo {
+    pub current_version: Option<String>,
+    pub latest_version: String,
+    pub available: bool,
+    pub notes: Option<String>,
+}
+
+#[derive(Default)]
+pub struct UpdaterState {
+    /// Update found by the last check
+    pending: Mutex<Option<(Version, Asset)>>,
+    /// Held while an update is downloaded and installed
+    applying: tokio::sync::Mutex<()>,
+}
+
+impl UpdaterState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn failed(message: impl Into<String>) -> CommandError {
+    CommandError::SidecarUpdateFailed(message.into())
+}
+
+fn public_key() -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(FEED_PUBLIC_KEY?).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+fn update_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    Ok(app.path().app_data_dir().map_err(|e| CommandError::IoError(e.to_string()))?.join(UPDATE_DIR))
+}
+
+fn binary_file_name() -> String {
+    if cfg!(windows) {
+        format!("{}.exe", BINARY_NAME)
+    } else {
+        BINARY_NAME.to_string()
+    }
+}
+
+/// What the feed's signature covers: the version, the target triple and the
+/// binary's hex SHA-256, one per line.
+fn signed_message(version: &Version, triple: &str, digest: &[u8]) -> Vec<u8> {
+    format!("{}\n{}\n{}", version, triple, hex::encode(digest)).into_bytes()
+}
+
+/// Check a downloaded binary's digest against the feed, and the signature
+/// against the digest, `version` and `triple`.
+fn verify_asset(digest: &[u8], asset: &Asset, version: &Version, triple: &str, key: &VerifyingKey) -> Result<(), CommandError> {
+    if !hex::encode(digest).eq_ignore_ascii_case(asset.sha256.trim()) {
+        return Err(failed("checksum mismatch"));
+    }
+    let signature = base64::engine::general_purpose::STANDARD
+        .decode(asset.signature.trim())
+        .map_err(|_| failed("signature is not base64"))?;
+    let signature = Signature::from_slice(&signature).map_err(|_| failed("malformed signature"))?;
+    key.verify_strict(&signed_message(version, triple, digest), &signature).map_err(|_| failed("signature mismatch"))
+}
+
+/// Version of the downloaded bridge in `dir`, if its binary is present.
+fn installed_version(dir: &Path) -> Option<Version> {
+    let manifest: Manifest = serde_json::from_str(&std::fs::read_to_string(dir.join(MANIFEST_FILE)).ok()?).ok()?;
+    dir.join(binary_file_name()).is_file().then_some(())?;
+    Version::parse(&manifest.version).ok()
+}
+
+/// The downloaded bridge to run instead of the bundled one, if it is newer.
+pub fn installed_binary(app: &AppHandle) -> Option<PathBuf> {
+    let dir = update_dir(app).ok()?;
+    let version = installed_version(&dir)?;
+    (version > app.package_info().version).then(|| dir.join(binary_file_name()))
+}
+
+async fn fetch_feed() -> Result<Feed, CommandError> {
+    let url = FEED_URL.ok_or_else(|| failed("updates are not configured for this build"))?;
+    let client = reqwest::Client::builder().timeout(FEED_TIMEOUT).build().map_err(|e| failed(e.to_string()))?;
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| failed(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| failed(format!("invalid release feed: {}", e)))
+}
+
+/// Download `asset` into `dir` and return the temporary file and its digest.
+async fn download(asset: &Asset, dir: &Path) -> Result<(PathBuf, Vec<u8>), CommandError> {
+    let client = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).build().map_err(|e| failed(e.to_string()))?;
+    let mut response = client
+        .get(&asset.url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| failed(e.to_string()))?;
+
+    std::fs::create_dir_all(dir)?;
+    let partial = dir.join(format!("{}.download", binary_file_name()));
+    let mut file = std::fs::File::create(&partial)?;
+    let mut hasher = Sha256::new();
+    let written = async {
+        while let Some(chunk) = response.chunk().await.map_err(|e| failed(e.to_string()))? {
+            hasher.update(&chunk);
+            file.write_all(&chunk)?;
+        }
+        file.sync_all()?;
+        Ok::<_, CommandError>(())
+    };
+    if let Err(e) = written.await {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    Ok((partial, hasher.finalize().to_vec()))
+}
+
+/// Make `partial` the bridge the app runs, recording `version`. The manifest
+/// is replaced atomically after the binary, so an interrupted install at
+/// worst leaves the new binary under the old version.
+fn install(partial: &Path, dir: &Path, version: &Version) -> Result<(), CommandError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(partial, std::fs::Permissions::from_mode(0o755))?;
+    }
+    std::fs::rename(partial, dir.join(binary_file_name()))?;
+    let manifest = serde_json::to_string(&Manifest { version: version.to_string() }).expect("manifest serializes");
+    let partial_manifest = dir.join(format!("{}.tmp", MANIFEST_FILE));
+    let mut file = std::fs::File::create(&partial_manifest)?;
+    file.write_all(manifest.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&partial_manifest, dir.join(MANIFEST_FILE))?;
+    Ok(())
+}
+
+/// Ask the release feed for a bridge newer than the one the app runs.
+#[tauri::command]
+pub async fn check_for_sidecar_update(app: AppHandle, state: State<'_, UpdaterState>) -> Result<SidecarUpdateInfo, CommandError> {
+    let feed = fetch_feed().await?;
+    let latest = Version::parse(&feed.version).map_err(|e| failed(format!("invalid version {}: {}", feed.version, e)))?;
+    let current = crate::versions::versions(&app).await.sidecar.and_then(|v| Version::parse(&v).ok());
+    let triple = tauri::utils::platform::target_triple().map_err(|e| failed(e.to_string()))?;
+    let asset = feed.platforms.get(&triple).cloned();
+    let available = asset.is_some() && current.as_ref().is_none_or(|current| latest > *current);
+
+    *state.pending.lock().unwrap() = asset.filter(|_| available).map(|asset| (latest.clone(), asset));
+    if available {
+        log::info!("Bridge update available: {}", latest);
+    }
+    Ok(SidecarUpdateInfo {
+        current_version: current.map(|v| v.to_string()),
+        latest_version: latest.to_string(),
+        available,
+        notes: feed.notes,
+    })
+}
+
+/// Download, verify and install the update found by the last check. Takes
+/// effect the next time the bridge starts; returns the installed version.
+#[tauri::command]
+pub async fn apply_sidecar_update(app: AppHandle, state: State<'_, UpdaterState>) -> Result<String, CommandError> {
+    let _applying = state.applying.try_lock().map_err(|_| failed("an update is already being installed"))?;
+    let (version, asset) = state
+        .pending
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| failed("no update available; check for updates first"))?;
+    let key = public_key().ok_or_else(|| failed("updates are not configured for this build"))?;
+    let triple = tauri::utils::platform::target_triple().map_err(|e| failed(e.to_string()))?;
+    let dir = update_dir(&app)?;
+
+    let (partial, digest) = download(&asset, &dir).await?;
+    if let Err(e) = verify_asset(&digest, &asset, &version, &triple, &key).and_then(|_| install(&partial, &dir, &version)) {
+        let _ = std::fs::remove_file(&partial);
+        log::warn!("Discarded bridge update {}: {}", version, e);
+        return Err(e);
+    }
+
+    *state.pending.lock().unwrap() = None;
+    app.state::<crate::versions::VersionState>().reset();
+    app.state::<crate::sidecar_client::SidecarClient>().reset();
+    log::info!("Installed bridge update {}", version);
+    let _ = app.emit("sidecar-update:installed", version.to_string());
+    Ok(version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const TRIPLE: &str = "x86_64-unknown-linux-gnu";
+
+    fn signed_asset(key: &SigningKey, binary: &[u8], version: &Version) -> Asset {
+        let digest = Sha256::digest(binary);
+        let signature = key.sign(&signed_message(version, TRIPLE, &digest));
+        Asset {
+            url: "https://example.com/bridge".to_string(),
+            sha256: hex::encode(digest),
+            signature: base64::engine::general_purpose::STANDARD.encode(signature.to_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_verify_asset() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let binary = b"#!/bin/sh\necho bridge\n";
+        let version = Version::parse("0.3.0").unwrap();
+        let asset = signed_asset(&key, binary, &version);
+        let digest = Sha256::digest(binary);
+        assert!(verify_asset(&digest, &asset, &version, TRIPLE, &key.verifying_key()).is_ok());
+
+        let tampered = Sha256::digest(b"#!/bin/sh\necho evil\n");
+        assert!(verify_asset(&tampered, &asset, &version, TRIPLE, &key.verifying_key()).is_err());
+
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        let forged = Asset { signature: signed_asset(&other, binary, &version).signature, ..asset.clone() };
+        assert!(verify_asset(&digest, &forged, &version, TRIPLE, &key.verifying_key()).is_err());
+
+        // A validly signed build offered as another version or platform
+        let relabelled = Version::parse("999.0.0").unwrap();
+        assert!(verify_asset(&digest, &asset, &relabelled, TRIPLE, &key.verifying_key()).is_err());
+        assert!(verify_asset(&digest, &asset, &version, "aarch64-apple-darwin", &key.verifying_key()).is_err());
+    }
+
+    #[test]
+    fn test_install_replaces_binary() {
+        let dir = std::env::temp_dir().join(format!("updater-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(installed_version(&dir), None);
+
+        for (content, version) in [(&b"v1"[..], "0.2.0"), (&b"v2"[..], "0.3.0")] {
+            let partial = dir.join("partial");
+            std::fs::write(&partial, content).unwrap();
+            install(&partial, &dir, &Version::parse(version).unwrap()).unwrap();
+            assert!(!partial.exists());
+            assert_eq!(std::fs::read(dir.join(binary_file_name())).unwrap(), content);
+            assert_eq!(installed_version(&dir), Some(Version::parse(version).unwrap()));
+            assert!(!dir.join(format!("{}.tmp", MANIFEST_FILE)).exists());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::cache::MetadataCache;
+use crate::dav::{join_path, normalize_path, parent_path, DavClient};
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+
+// ============================================================================
+// Drop uploads
+// ============================================================================
+//
+// Files and folders dropped on an app window are uploaded to the destination
+// from the `dropUpload` config section; folders are recreated recursively.
+// Drops are handled one batch at a time in drop order. Each file is a regular
+// upload in the transfer registry (`transfer:progress` etc.); a batch also
+// reports `transfer:queued` when it starts and `transfer:batch-finished`
+// with the per-file outcome. Existing files are skipped, overwritten or
+// uploaded under a new name as set by `conflictPolicy`.
+
+const CONFIG_KEY: &str = "dropUpload";
+
+/// Give up looking for a free "name (n)" after this many attempts.
+const MAX_RENAME_ATTEMPTS: u32 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    /// Upload as "name (1).ext", "name (2).ext", ...
+    #[default]
+    Rename,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DropUploadSettings {
+    /// Remote folder dropped items are uploaded into
+    pub destination: String,
+    pub conflict_policy: ConflictPolicy,
+}
+
+impl Default for DropUploadSettings {
+    fn default() -> Self {
+        Self { destination: "/".into(), conflict_policy: ConflictPolicy::default() }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedBatch {
+    pub batch_id: u64,
+    pub destination: String,
+    pub files: usize,
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedUpload {
+    /// Local path
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    pub batch_id: u64,
+    /// Remote paths written
+    pub uploaded: Vec<String>,
+    /// Local paths left out because the target existed
+    pub skipped: Vec<String>,
+    pub failed: Vec<FailedUpload>,
+    /// Set when the whole batch could not run
+    pub error: Option<String>,
+}
+
+/// Folders to create and files to upload for one drop, parents first.
+#[derive(Debug, Default, PartialEq)]
+struct UploadPlan {
+    dirs: Vec<String>,
+    /// Local file, remote path, size
+    files: Vec<(PathBuf, String, u64)>,
+}
+
+#[derive(Default)]
+pub struct UploadQueue {
+    /// Held by the batch being uploaded so drops run one after another
+    turn: tokio::sync::Mutex<()>,
+    next_batch: AtomicU64,
+}
+
+impl UploadQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Walk the dropped `paths`, mapping them under `destination`. Symlinks and
+/// unreadable entries are left out.
+fn plan(paths: &[PathBuf], destination: &str) -> UploadPlan {
+    let mut plan = UploadPlan::default();
+    let mut pending: Vec<(PathBuf, String)> = paths
+        .iter()
+        .rev()
+        .filter_map(|p| p.file_name().map(|n| (p.clone(), join_path(destination, &n.to_string_lossy()))))
+        .collect();
+    while let Some((local, remote)) = pending.pop() {
+        let Ok(metadata) = std::fs::symlink_metadata(&local) else {
+            continue;
+        };
+        if metadata.is_file() {
+            plan.files.push((local, remote, metadata.len()));
+        } else if metadata.is_dir() {
+            let mut children: Vec<PathBuf> = match std::fs::read_dir(&local) {
+                Ok(entries) => entries.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+                Err(e) => {
+                    log::warn!("Cannot read {}: {}", local.display(), e);
+                    continue;
+                }
+            };
+            children.sort();
+            for child in children.into_iter().rev() {
+                let name = child.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                pending.push((child, join_path(&remote, &name)));
+            }
+            plan.dirs.push(remote);
+        }
+    }
+    plan
+}
+
+/// "name (n).ext" for the n-th rename of `name`; dotfiles keep their name
+/// whole.
+fn renamed(name: &str, n: u32) -> String {
+    match name.rfind('.') {
+        Some(i) if i > 0 => format!("{} ({}){}", &name[..i], n, &name[i..]),
+        _ => format!("{} ({})", name, n),
+    }
+}
+
+/// Remote path to upload to under `policy`, or `None` to skip the file.
+async fn resolve_conflict(client: &DavClient, remote: &str, policy: ConflictPolicy) -> Result<Option<String>, CommandError> {
+    if client.stat(remote).await?.is_none() {
+        return Ok(Some(remote.to_string()));
+    }
+    match policy {
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::Overwrite => Ok(Some(remote.to_string())),
+        ConflictPolicy::Rename => {
+            let name = remote.rsplit('/').next().unwrap_or_default();
+            for n in 1..=MAX_RENAME_ATTEMPTS {
+                let candidate = join_path(parent_path(remote), &renamed(name, n));
+                if client.stat(&candidate).await?.is_none() {
+                    return Ok(Some(candidate));
+                }
+            }
+            Err(CommandError::WebDavError(format!("No free name for {}", remote)))
+        }
+    }
+}
+
+async fn upload_batch(app: &AppHandle, batch_id: u64, paths: Vec<PathBuf>, destination: &str, policy: ConflictPolicy, result: &mut BatchResult) -> Result<(), CommandError> {
+    app.state::<ReadOnlyState>().check_writable()?;
+    let client = DavClient::for_app(app)?;
+    match client.stat(destination).await? {
+        Some(entry) if entry.is_dir => {}
+        Some(_) => return Err(CommandError::InvalidRemotePath(format!("{} is a file", destination))),
+        None => return Err(CommandError::RemotePathNotFound(destination.to_string())),
+    }
+
+    let destination_owned = destination.to_string();
+    let plan = tauri::async_runtime::spawn_blocking(move || plan(&paths, &destination_owned))
+        .await
+        .map_err(|e| CommandError::Unknown(e.to_string()))?;
+    let _ = app.emit(
+        "transfer:queued",
+        QueuedBatch {
+            batch_id,
+            destination: destination.to_string(),
+            files: plan.files.len(),
+            total_bytes: plan.files.iter().map(|(_, _, size)| size).sum(),
+        },
+    );
+
+    for dir in &plan.dirs {
+        // An existing folder is merged into; mkcol treats it as success
+        client.mkcol(dir).await?;
+        app.state::<MetadataCache>().invalidate(dir);
+    }
+    for (local, remote, _) in plan.files {
+        let local_display = local.display().to_string();
+        let outcome = match resolve_conflict(&client, &remote, policy).await {
+            Ok(Some(target)) => crate::remote::upload_local_file(app, &client, &local, &target, policy == ConflictPolicy::Overwrite)
+                .await
+                .map(|r| Some(r.path)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(e),
+        };
+        match outcome {
+            Ok(Some(path)) => result.uploaded.push(path),
+            Ok(None) => result.skipped.push(local_display),
+            Err(e) => {
+                log::warn!("Upload of {} failed: {}", local_display, e);
+                result.failed.push(FailedUpload { path: local_display, error: e.to_string() });
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_batch(app: AppHandle, batch_id: u64, paths: Vec<PathBuf>, destination: Option<String>) {
+    let settings: DropUploadSettings = read_config_section(CONFIG_KEY);
+    let destination = normalize_path(destination.as_deref().unwrap_or(&settings.destination));
+    let queue = app.state::<UploadQueue>();
+    let _turn = queue.turn.lock().await;
+
+    log::info!("Uploading {} dropped item(s) to {}", paths.len(), destination);
+    let mut result = BatchResult { batch_id, ..Default::default() };
+    if let Err(e) = upload_batch(&app, batch_id, paths, &destination, settings.conflict_policy, &mut result).await {
+        log::warn!("Drop upload to {} failed: {}", destination, e);
+        result.error = Some(e.to_string());
+    }
+    let _ = app.emit("transfer:batch-finished", result);
+}
+
+/// Queue `paths` for upload to `destination`, or the configured one.
+/// Returns the batch id used in the `transfer:*` batch events.
+pub fn enqueue(app: &AppHandle, paths: Vec<PathBuf>, destination: Option<String>) -> u64 {
+    let batch_id = app.state::<UploadQueue>().next_batch.fetch_add(1, Ordering::Relaxed) + 1;
+    tauri::async_runtime::spawn(run_batch(app.clone(), batch_id, paths, destination));
+    batch_id
+}
+
+/// Queue local files or folders for upload the way a drop on the window
+/// does, e.g. from a file picker.
+#[tauri::command]
+pub async fn enqueue_upload(app: AppHandle, paths: Vec<String>, destination: Option<String>) -> Result<u64, CommandError> {
+    Ok(enqueue(&app, paths.into_iter().map(PathBuf::from).collect(), destination))
+}
+
+#[tauri::command]
+pub async fn get_drop_upload_settings() -> Result<DropUploadSettings, CommandError> {
+    Ok(read_config_section(CONFIG_KEY))
+}
+
+#[tauri::command]
+pub async fn set_drop_upload_settings(settings: DropUploadSettings) -> Result<DropUploadSettings, CommandError> {
+    let settings = DropUploadSettings { destination: normalize_path(&settings.destination), ..settings };
+    write_config_section(CONFIG_KEY, &settings)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renamed_keeps_extension() {
+        assert_eq!(renamed("report.pdf", 1), "report (1).pdf");
+        assert_eq!(renamed("archive.tar.gz", 2), "archive.tar (2).gz");
+        assert_eq!(renamed("notes", 3), "notes (3)");
+        assert_eq!(renamed(".bashrc", 1), ".bashrc (1)");
+    }
+
+    #[test]
+    fn test_plan_walks_folders_parents_first() {
+        let root = std::env::temp_dir().join(format!("drop-plan-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("album/sub")).unwrap();
+        std::fs::write(root.join("album/a.jpg"), b"abc").unwrap();
+        std::fs::write(root.join("album/sub/b.jpg"), b"de").unwrap();
+        std::fs::write(root.join("single.txt"), b"x").unwrap();
+
+        let plan = plan(&[root.join("album"), root.join("single.txt"), root.join("missing")], "/Uploads");
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(plan.dirs, vec!["/Uploads/album", "/Uploads/album/sub"]);
+        let files: Vec<(&str, u64)> = plan.files.iter().map(|(_, r, s)| (r.as_str(), *s)).collect();
+        assert_eq!(files, vec![("/Uploads/album/a.jpg", 3), ("/Uploads/album/sub/b.jpg", 2), ("/Uploads/single.txt", 1)]);
+    }
+}
@@ -0,0 +1,738 @@
+use bytes::Bytes;
+use futures_util::{Stream, TryStreamExt};
+use rusqlite::{Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Notify;
+
+use crate::bandwidth::{BandwidthState, ThrottledStream};
+use crate::dav::DavClient;
+use crate::db;
+use crate::integrity::{self, TransferDigest};
+use crate::read_only::ReadOnlyState;
+use crate::sidecar::{read_config_section, write_config_section, CommandError};
+use crate::transfer_concurrency::TransferConcurrencyState;
+use crate::transfers::{MeteredStream, TransferDirection, TransferState};
+
+// ============================================================================
+// Upload spooling
+// ============================================================================
+//
+// A PUT through GVFS blocks the file manager until the bridge has pushed the
+// file to Proton. With spooling on, the gateway instead writes large PUTs to
+// a staging directory, answers `201 Created` as soon as the file is on disk,
+// and a background uploader sends staged files to the bridge in the order
+// they arrived, retrying failures with backoff and pausing while travel mode
+// is on. Uploads are held while the share is read-only, since it may have
+// become so after the file was staged. The queue is kept in the state
+// database, so pending uploads survive a restart or a crash.
+// `upload:progress`, `upload:completed`, `upload:failed` and
+// `upload:blocked` report each upload, and `list_pending_uploads` shows what
+// is not yet safe on Proton Drive.
+//
+// Until its upload finishes, a staged file is what GETs of its path return,
+// and any other change to that path (or a folder above it) waits for the
+// upload so writes reach the drive in order. Listings don't show files that
+// are still staged.
+//
+// Each staged file belongs to the account profile that was active when it
+// was written. Only the active profile's files are uploaded or served, so
+// after a switch the other account's files wait until it is active again,
+// and signing out discards the ones of the account signing out.
+
+const CONFIG_KEY: &str = "uploadSpool";
+
+const SPOOL_DIR: &str = "upload-spool";
+
+const ID_COUNTER: &str = "pending_uploads";
+
+/// Failed attempts after which an upload waits for the user.
+const MAX_ATTEMPTS: u32 = 5;
+
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(10);
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(600);
+
+/// How often the uploader looks again while it cannot upload, e.g. because
+/// the bridge is stopped.
+const IDLE_POLL: Duration = Duration::from_secs(30);
+
+/// Minimum delay between two `upload:progress` events for one upload.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SpoolSettings {
+    pub enabled: bool,
+    /// Smaller PUTs go straight to the bridge
+    pub min_size_mb: u32,
+}
+
+impl Default for SpoolSettings {
+    fn default() -> Self {
+        Self { enabled: false, min_size_mb: 8 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UploadStatus {
+    Queued,
+    Uploading,
+    /// Waiting for `nextAttemptAt` after a failed attempt
+    Retrying,
+    /// Gave up after `MAX_ATTEMPTS`; retried or discarded by the user
+    Failed,
+    /// Held because the share became read-only after the file was staged;
+    /// queued again once writes are allowed
+    Blocked,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingUpload {
+    pub id: u64,
+    /// Remote path the file is uploaded to
+    pub path: String,
+    pub size: u64,
+    /// Unix seconds
+    pub queued_at: u64,
+    pub status: UploadStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// Unix seconds
+    pub next_attempt_at: Option<u64>,
+    /// Entry in the transfer registry while uploading
+    #[serde(skip_deserializing)]
+    pub transfer_id: Option<u64>,
+    /// Account profile the file was staged under; `None` for the default one
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+#[derive(Default)]
+struct Manifest {
+    next_id: u64,
+    uploads: Vec<PendingUpload>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadProgressEvent {
+    pub id: u64,
+    pub path: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadCompletedEvent {
+    pub id: u64,
+    pub path: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadBlockedEvent {
+    pub id: u64,
+    pub path: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadFailedEvent {
+    pub id: u64,
+    pub path: String,
+    pub error: String,
+    pub attempts: u32,
+    /// Whether the uploader tries again by itself
+    pub will_retry: bool,
+}
+
+#[derive(Default)]
+pub struct UploadSpool {
+    /// Loaded from the state database on first use
+    manifest: Mutex<Option<Manifest>>,
+    /// Signalled whenever an upload is added, finishes or fails
+    changed: Notify,
+    settings: Mutex<SpoolSettings>,
+    /// Account profile whose uploads are served and sent; `None` for the
+    /// default one
+    profile: Mutex<Option<String>>,
+}
+
+/// Whether `path` is `root` or inside it.
+fn is_under(path: &str, root: &str) -> bool {
+    let root = root.trim_end_matches('/');
+    path == root || path.starts_with(&format!("{}/", root))
+}
+
+/// Delay before the attempt after `attempts` failed ones.
+fn retry_delay(attempts: u32) -> Duration {
+    RETRY_BASE_DELAY.saturating_mul(1 << attempts.saturating_sub(1).min(16)).min(MAX_RETRY_DELAY)
+}
+
+fn spool_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    let dir = app.path().app_data_dir().map_err(|e| CommandError::IoError(e.to_string()))?.join(SPOOL_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn staged_file(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{}.data", id))
+}
+
+fn read_manifest(conn: &Connection) -> rusqlite::Result<Manifest> {
+    let mut stmt = conn.prepare("SELECT data FROM pending_uploads ORDER BY id")?;
+    let mut uploads: Vec<PendingUpload> = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|json| db::from_json(&json?))
+        .collect::<rusqlite::Result<_>>()?;
+    // An upload interrupted by a quit starts over
+    for upload in &mut uploads {
+        if upload.status == UploadStatus::Uploading {
+            upload.status = UploadStatus::Queued;
+        }
+    }
+    Ok(Manifest { next_id: db::counter(conn, ID_COUNTER)?, uploads })
+}
+
+fn write_manifest(tx: &Transaction, manifest: &Manifest) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM pending_uploads", [])?;
+    for upload in &manifest.uploads {
+        tx.execute("INSERT INTO pending_uploads (id, data) VALUES (?1, ?2)", rusqlite::params![upload.id as i64, db::to_json(upload)?])?;
+    }
+    db::set_counter(tx, ID_COUNTER, manifest.next_id)
+}
+
+/// The next upload of `profile` that may start at `now`: the oldest queued
+/// one, or a retry whose time has come.
+fn next_due<'a>(manifest: &'a Manifest, profile: Option<&str>, now: u64) -> Option<&'a PendingUpload> {
+    manifest.uploads.iter().filter(|u| u.profile.as_deref() == profile).find(|u| match u.status {
+        UploadStatus::Queued => true,
+        UploadStatus::Retrying => u.next_attempt_at.is_none_or(|at| at <= now),
+        _ => false,
+    })
+}
+
+/// Hold every upload waiting to go, returning those newly held.
+fn block_waiting(manifest: &mut Manifest) -> Vec<PendingUpload> {
+    let mut blocked = Vec::new();
+    for u in manifest.uploads.iter_mut().filter(|u| matches!(u.status, UploadStatus::Queued | UploadStatus::Retrying)) {
+        u.status = UploadStatus::Blocked;
+        u.next_attempt_at = None;
+        blocked.push(u.clone());
+    }
+    blocked
+}
+
+/// Remove the uploads staged under `profile`, returning them.
+fn take_profile(manifest: &mut Manifest, profile: Option<&str>) -> Vec<PendingUpload> {
+    let (taken, kept) = std::mem::take(&mut manifest.uploads).into_iter().partition(|u| u.profile.as_deref() == profile);
+    manifest.uploads = kept;
+    taken
+}
+
+/// Queue held uploads again; returns whether there were any.
+fn unblock(manifest: &mut Manifest) -> bool {
+    let mut any = false;
+    for u in manifest.uploads.iter_mut().filter(|u| u.status == UploadStatus::Blocked) {
+        u.status = UploadStatus::Queued;
+        any = true;
+    }
+    any
+}
+
+impl UploadSpool {
+    /// Build the spool with the settings and account in `config.json`.
+    pub fn from_config() -> Self {
+        let spool = Self::default();
+        spool.reload_settings();
+        spool
+    }
+
+    /// Pick up the `uploadSpool` section and the active account after
+    /// config.json was replaced or the account switched.
+    pub fn reload_settings(&self) {
+        *self.settings.lock().unwrap() = read_config_section(CONFIG_KEY);
+        *self.profile.lock().unwrap() = crate::accounts::active_profile();
+    }
+
+    fn profile(&self) -> Option<String> {
+        self.profile.lock().unwrap().clone()
+    }
+
+    /// Whether spooling is off and nothing is left to upload, so requests
+    /// need not look at the queue. False until the queue was first loaded.
+    fn idle(&self) -> bool {
+        let enabled = self.settings.lock().unwrap().enabled;
+        !enabled && self.manifest.lock().unwrap().as_ref().is_some_and(|m| m.uploads.is_empty())
+    }
+
+    /// Run `f` on the manifest, loading it first if needed, and save it
+    /// afterwards when `f` says it changed something.
+    fn update<T>(&self, app: &AppHandle, f: impl FnOnce(&mut Manifest) -> (T, bool)) -> Result<T, CommandError> {
+        let mut guard = self.manifest.lock().unwrap();
+        if guard.is_none() {
+            *guard = Some(db::with(app, |conn| read_manifest(conn))?);
+        }
+        let manifest = guard.as_mut().expect("manifest was just loaded");
+        let (value, changed) = f(manifest);
+        if changed {
+            db::transaction(app, |tx| write_manifest(tx, manifest))?;
+            self.changed.notify_waiters();
+        }
+        Ok(value)
+    }
+
+    /// Whether a PUT of `content_length` bytes is staged rather than
+    /// forwarded. PUTs without a length always go through.
+    pub fn accepts(&self, content_length: Option<u64>) -> bool {
+        let settings = *self.settings.lock().unwrap();
+        settings.enabled && content_length.is_some_and(|len| len >= u64::from(settings.min_size_mb) * 1024 * 1024)
+    }
+
+    /// Write `body` to the staging directory and queue it for upload to
+    /// `path`. A queued upload to the same path is replaced.
+    pub async fn stage<S>(&self, app: &AppHandle, path: &str, size: u64, body: S) -> Result<u64, CommandError>
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + Unpin,
+    {
+        let dir = spool_dir(app)?;
+        let id = self.update(app, |m| {
+            m.next_id += 1;
+            (m.next_id, true)
+        })?;
+        let partial = dir.join(format!("{}.part", id));
+        if let Err(e) = write_body(&partial, size, body).await {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e);
+        }
+        tokio::fs::rename(&partial, staged_file(&dir, id)).await?;
+
+        let upload = PendingUpload {
+            id,
+            path: path.to_string(),
+            size,
+            queued_at: crate::sidecar::unix_now(),
+            status: UploadStatus::Queued,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: None,
+            transfer_id: None,
+            profile: self.profile(),
+        };
+        let replaced = self.update(app, |m| {
+            let (replaced, kept) = std::mem::take(&mut m.uploads)
+                .into_iter()
+                .partition(|u: &PendingUpload| u.path == path && u.profile == upload.profile && u.status != UploadStatus::Uploading);
+            m.uploads = kept;
+            m.uploads.push(upload);
+            (replaced, true)
+        })?;
+        for old in replaced {
+            let _ = std::fs::remove_file(staged_file(&dir, old.id));
+        }
+        log::info!("Staged {} ({} bytes) for upload", path, size);
+        Ok(id)
+    }
+
+    /// The staged file holding the newest data for `path`, if its upload is
+    /// still pending for the active account.
+    pub fn staged(&self, app: &AppHandle, path: &str) -> Option<(PathBuf, u64)> {
+        if self.idle() {
+            return None;
+        }
+        let profile = self.profile();
+        let upload = self
+            .update(app, |m| (m.uploads.iter().rev().find(|u| u.path == path && u.profile == profile).cloned(), false))
+            .ok()??;
+        Some((staged_file(&spool_dir(app).ok()?, upload.id), upload.size))
+    }
+
+    /// Wait until no upload of the active account to `path` or below it is
+    /// pending, other than ones that failed for good.
+    pub async fn wait_for(&self, app: &AppHandle, path: &str) {
+        if self.idle() {
+            return;
+        }
+        let profile = self.profile();
+        loop {
+            let changed = self.changed.notified();
+            let pending = self
+                .update(app, |m| {
+                    let pending = m.uploads.iter().any(|u| u.profile == profile && u.status != UploadStatus::Failed && is_under(&u.path, path));
+                    (pending, false)
+                })
+                .unwrap_or(false);
+            if !pending {
+                return;
+            }
+            log::debug!("Waiting for the staged upload of {}", path);
+            changed.await;
+        }
+    }
+}
+
+async fn write_body<S>(dest: &Path, size: u64, mut body: S) -> Result<(), CommandError>
+where
+    S: Stream<Item = std::io::Result<Bytes>> + Send + Unpin,
+{
+    let mut file = tokio::fs::File::create(dest).await?;
+    let mut written = 0;
+    while let Some(chunk) = body.try_next().await? {
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+    }
+    if written != size {
+        return Err(CommandError::IoError(format!("Received {} of {} bytes", written, size)));
+    }
+    // The client is told the data is safe once this returns
+    file.sync_all().await?;
+    Ok(())
+}
+
+/// Upload one staged file. Progress goes to the transfer registry and to
+/// `upload:progress`.
+async fn upload(app: &AppHandle, client: &DavClient, dir: &Path, upload: &PendingUpload) -> Result<(), CommandError> {
+    let concurrency = app.state::<TransferConcurrencyState>();
+    let _slot = concurrency.slot(TransferDirection::Upload).await;
+    let file = tokio::fs::File::open(staged_file(dir, upload.id)).await?;
+    let ticket = app.state::<TransferState>().begin(app, &upload.path, TransferDirection::Upload, Some(upload.size));
+    let transfer_id = ticket.id();
+    app.state::<UploadSpool>().update(app, |m| {
+        if let Some(u) = m.uploads.iter_mut().find(|u| u.id == upload.id) {
+            u.transfer_id = Some(transfer_id);
+        }
+        ((), false)
+    })?;
+
+    let (id, path, total) = (upload.id, upload.path.clone(), upload.size);
+    let progress_app = app.clone();
+    let mut sent = 0;
+    let mut last_emit: Option<Instant> = None;
+    let digest = integrity::enabled().then(TransferDigest::default);
+    let hashed = digest.clone();
+    let stream = crate::remote::local_file_stream(file, concurrency.chunk_size()).inspect_ok(move |chunk| {
+        if let Some(digest) = &hashed {
+            digest.update(chunk);
+        }
+        sent += chunk.len() as u64;
+        if sent == total || last_emit.is_none_or(|t| t.elapsed() >= PROGRESS_EMIT_INTERVAL) {
+            last_emit = Some(Instant::now());
+            let event = UploadProgressEvent { id, path: path.clone(), bytes_sent: sent, total_bytes: total };
+            let _ = progress_app.emit("upload:progress", event);
+        }
+    });
+    let stream = ThrottledStream::new(stream, app.state::<BandwidthState>().upload.clone());
+    client.put(&upload.path, MeteredStream::new(stream, ticket), Some(upload.size)).await?;
+    if let Some(digest) = digest {
+        integrity::verify(app, client, integrity::Direction::Upload, &upload.path, None, upload.size, digest).await;
+    }
+    Ok(())
+}
+
+/// Record the outcome of an attempt at `id`.
+fn finish(app: &AppHandle, dir: &Path, id: u64, result: Result<(), CommandError>) -> Result<(), CommandError> {
+    let spool = app.state::<UploadSpool>();
+    let Some(entry) = spool.update(app, |m| (m.uploads.iter().find(|u| u.id == id).cloned(), false))? else {
+        // Discarded while it was uploading
+        let _ = std::fs::remove_file(staged_file(dir, id));
+        return Ok(());
+    };
+    match result {
+        Ok(()) => {
+            spool.update(app, |m| {
+                m.uploads.retain(|u| u.id != id);
+                ((), true)
+            })?;
+            let _ = std::fs::remove_file(staged_file(dir, id));
+            app.state::<crate::cache::MetadataCache>().invalidate(&entry.path);
+            app.state::<crate::readahead::ReadaheadState>().invalidate(&entry.path);
+            log::info!("Uploaded staged file {}", entry.path);
+            crate::recent::record(app, &entry.path, crate::recent::RecentUse::Modified);
+            let _ = app.emit("upload:completed", UploadCompletedEvent { id, path: entry.path });
+        }
+        // The share became read-only (or travel mode came on) between the
+        // check in `run` and the upload; hold it rather than count a failure
+        Err(e @ (CommandError::ReadOnlyShare | CommandError::TravelModeActive)) => {
+            let travel = matches!(e, CommandError::TravelModeActive);
+            spool.update(app, |m| {
+                if let Some(u) = m.uploads.iter_mut().find(|u| u.id == id) {
+                    u.transfer_id = None;
+                    u.status = if travel { UploadStatus::Queued } else { UploadStatus::Blocked };
+                }
+                ((), true)
+            })?;
+            if !travel {
+                log::info!("Holding staged file {}: the share is read-only", entry.path);
+                let _ = app.emit("upload:blocked", UploadBlockedEvent { id, path: entry.path });
+            }
+        }
+        Err(e) => {
+            // Not the upload's fault; try again once the bridge is back
+            let counts = !matches!(e, CommandError::ServerNotRunning);
+            let attempts = entry.attempts + u32::from(counts);
+            let will_retry = attempts < MAX_ATTEMPTS;
+            let delay = if counts { retry_delay(attempts) } else { IDLE_POLL };
+            spool.update(app, |m| {
+                if let Some(u) = m.uploads.iter_mut().find(|u| u.id == id) {
+                    u.attempts = attempts;
+                    u.last_error = Some(e.to_string());
+                    u.transfer_id = None;
+                    u.status = if will_retry { UploadStatus::Retrying } else { UploadStatus::Failed };
+                    u.next_attempt_at = will_retry.then(|| crate::sidecar::unix_now() + delay.as_secs());
+                }
+                ((), true)
+            })?;
+            log::warn!("Upload of staged file {} failed (attempt {}): {}", entry.path, attempts, e);
+            let event = UploadFailedEvent { id, path: entry.path, error: e.to_string(), attempts, will_retry };
+            let _ = app.emit("upload:failed", event);
+        }
+    }
+    Ok(())
+}
+
+/// Upload staged files one at a time; spawned once from `setup`.
+pub async fn run(app: AppHandle) {
+    let dir = match spool_dir(&app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!("Upload spool is unavailable: {}", e);
+            return;
+        }
+    };
+    let spool = app.state::<UploadSpool>();
+    loop {
+        let changed = spool.changed.notified();
+        // Checked before every upload: the PUT was accepted when staged, but
+        // the share may have become read-only since
+        let read_only = app.state::<ReadOnlyState>().status();
+        if read_only.effective && !read_only.travel_mode {
+            let held = spool
+                .update(&app, |m| {
+                    let held = block_waiting(m);
+                    let changed = !held.is_empty();
+                    (held, changed)
+                })
+                .unwrap_or_default();
+            for held in held {
+                log::info!("Holding staged file {}: the share is read-only", held.path);
+                let _ = app.emit("upload:blocked", UploadBlockedEvent { id: held.id, path: held.path });
+            }
+        } else if !read_only.effective {
+            let _ = spool.update(&app, |m| {
+                let any = unblock(m);
+                ((), any)
+            });
+        }
+        // Staged files stay put while travel mode is on
+        let can_upload = !app.state::<crate::power::PowerState>().is_suspended()
+            && !read_only.effective
+            && app.state::<crate::network::NetworkState>().is_online()
+            && crate::gateway::is_running(&app);
+        let due = if can_upload {
+            let profile = spool.profile();
+            spool
+                .update(&app, |m| {
+                    let due = next_due(m, profile.as_deref(), crate::sidecar::unix_now()).map(|u| u.id);
+                    let entry = due.and_then(|id| m.uploads.iter_mut().find(|u| u.id == id));
+                    match entry {
+                        Some(u) => {
+                            u.status = UploadStatus::Uploading;
+                            (Some(u.clone()), true)
+                        }
+                        None => (None, false),
+                    }
+                })
+                .unwrap_or(None)
+        } else {
+            None
+        };
+        let Some(entry) = due else {
+            let _ = tokio::time::timeout(IDLE_POLL, changed).await;
+            continue;
+        };
+
+        let result = match DavClient::for_app(&app) {
+            Ok(client) => upload(&app, &client, &dir, &entry).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = finish(&app, &dir, entry.id, result) {
+            log::warn!("Failed to record the upload of {}: {}", entry.path, e);
+        }
+    }
+}
+
+/// Discard the uploads staged under the active account, cancelling any that
+/// is uploading; part of signing out.
+pub(crate) fn purge_account(app: &AppHandle) -> Result<(), CommandError> {
+    let spool = app.state::<UploadSpool>();
+    let profile = spool.profile();
+    let removed = spool.update(app, |m| {
+        let removed = take_profile(m, profile.as_deref());
+        let changed = !removed.is_empty();
+        (removed, changed)
+    })?;
+    let dir = spool_dir(app)?;
+    for upload in &removed {
+        match upload.transfer_id {
+            Some(transfer_id) => {
+                app.state::<TransferState>().cancel(transfer_id);
+            }
+            None => {
+                let _ = std::fs::remove_file(staged_file(&dir, upload.id));
+            }
+        }
+    }
+    if !removed.is_empty() {
+        log::info!("Discarded {} staged upload(s) of the signed-out account", removed.len());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_pending_uploads(app: AppHandle, spool: State<'_, UploadSpool>) -> Result<Vec<PendingUpload>, CommandError> {
+    spool.update(&app, |m| (m.uploads.clone(), false))
+}
+
+/// Queue a failed upload again, with a fresh set of attempts.
+#[tauri::command]
+pub async fn retry_pending_upload(app: AppHandle, spool: State<'_, UploadSpool>, id: u64) -> Result<(), CommandError> {
+    spool.update(&app, |m| match m.uploads.iter_mut().find(|u| u.id == id && u.status != UploadStatus::Uploading) {
+        Some(u) => {
+            u.status = UploadStatus::Queued;
+            u.attempts = 0;
+            u.next_attempt_at = None;
+            (Ok(()), true)
+        }
+        None => (Err(CommandError::PendingUploadNotFound(id)), false),
+    })?
+}
+
+/// Drop a pending upload and its staged data, cancelling it if it is
+/// uploading. The data is not uploaded.
+#[tauri::command]
+pub async fn discard_pending_upload(app: AppHandle, spool: State<'_, UploadSpool>, id: u64) -> Result<(), CommandError> {
+    let removed = spool.update(&app, |m| match m.uploads.iter().position(|u| u.id == id) {
+        Some(i) => (Some(m.uploads.remove(i)), true),
+        None => (None, false),
+    })?;
+    let upload = removed.ok_or(CommandError::PendingUploadNotFound(id))?;
+    match upload.transfer_id {
+        // The uploader removes the file once the transfer stops
+        Some(transfer_id) => {
+            app.state::<TransferState>().cancel(transfer_id);
+        }
+        None => {
+            let _ = std::fs::remove_file(staged_file(&spool_dir(&app)?, id));
+        }
+    }
+    log::info!("Discarded the staged upload of {}", upload.path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_upload_spool_settings(spool: State<'_, UploadSpool>) -> Result<SpoolSettings, CommandError> {
+    Ok(*spool.settings.lock().unwrap())
+}
+
+#[tauri::command]
+pub async fn set_upload_spool_settings(spool: State<'_, UploadSpool>, settings: SpoolSettings) -> Result<SpoolSettings, CommandError> {
+    write_config_section(CONFIG_KEY, &settings)?;
+    *spool.settings.lock().unwrap() = settings;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(id: u64, status: UploadStatus, next_attempt_at: Option<u64>) -> PendingUpload {
+        PendingUpload {
+            id,
+            path: format!("/file{}", id),
+            size: 1,
+            queued_at: 0,
+            status,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at,
+            transfer_id: None,
+            profile: None,
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), Duration::from_secs(10));
+        assert_eq!(retry_delay(2), Duration::from_secs(20));
+        assert_eq!(retry_delay(4), Duration::from_secs(80));
+        assert_eq!(retry_delay(30), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_next_due_keeps_order_and_waits_for_retries() {
+        let manifest = Manifest {
+            next_id: 4,
+            uploads: vec![
+                pending(1, UploadStatus::Failed, None),
+                pending(2, UploadStatus::Retrying, Some(100)),
+                pending(3, UploadStatus::Queued, None),
+            ],
+        };
+        assert_eq!(next_due(&manifest, None, 50).map(|u| u.id), Some(3));
+        assert_eq!(next_due(&manifest, None, 100).map(|u| u.id), Some(2));
+    }
+
+    #[test]
+    fn test_uploads_stay_with_their_account() {
+        let mut manifest = Manifest {
+            next_id: 4,
+            uploads: vec![
+                PendingUpload { profile: Some("work-1a2b3c4d".into()), ..pending(1, UploadStatus::Queued, None) },
+                pending(2, UploadStatus::Queued, None),
+                PendingUpload { profile: Some("work-1a2b3c4d".into()), ..pending(3, UploadStatus::Uploading, None) },
+            ],
+        };
+        assert_eq!(next_due(&manifest, None, 0).map(|u| u.id), Some(2));
+        assert_eq!(next_due(&manifest, Some("work-1a2b3c4d"), 0).map(|u| u.id), Some(1));
+        assert_eq!(next_due(&manifest, Some("home-5e6f7a8b"), 0).map(|u| u.id), None);
+
+        let taken: Vec<u64> = take_profile(&mut manifest, Some("work-1a2b3c4d")).iter().map(|u| u.id).collect();
+        assert_eq!(taken, [1, 3]);
+        assert_eq!(manifest.uploads.iter().map(|u| u.id).collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn test_read_only_holds_waiting_uploads() {
+        let mut manifest = Manifest {
+            next_id: 5,
+            uploads: vec![
+                pending(1, UploadStatus::Failed, None),
+                pending(2, UploadStatus::Retrying, Some(100)),
+                pending(3, UploadStatus::Queued, None),
+                pending(4, UploadStatus::Uploading, None),
+            ],
+        };
+        let held: Vec<u64> = block_waiting(&mut manifest).iter().map(|u| u.id).collect();
+        assert_eq!(held, [2, 3]);
+        assert!(block_waiting(&mut manifest).is_empty());
+        assert_eq!(next_due(&manifest, None, 1_000).map(|u| u.id), None);
+        assert!(unblock(&mut manifest));
+        assert_eq!(next_due(&manifest, None, 0).map(|u| u.id), Some(2));
+    }
+
+    #[test]
+    fn test_is_under() {
+        assert!(is_under("/Videos/a.mkv", "/Videos"));
+        assert!(is_under("/Videos/a.mkv", "/Videos/a.mkv"));
+        assert!(is_under("/a", "/"));
+        assert!(!is_under("/Videos2/a.mkv", "/Videos"));
+    }
+}
@@ -0,0 +1,178 @@
+use semver::Version;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Sidecar versions
+// ============================================================================
+//
+// The bridge CLI the app runs is not always the one it was built with: in
+// Flatpak it can be the host's, and that one is updated on its own. Before
+// starting it, `start_sidecar` asks its version (`--version`, cached until
+// the sandbox settings change) and refuses a bridge older than
+// `MIN_SIDECAR_VERSION`, whose CLI lacks commands and flags the app relies
+// on. A bridge newer than the app by a breaking release, or one whose version
+// cannot be read, is only logged; `status:schema_mismatch` covers the details.
+
+/// Oldest bridge CLI the app can drive.
+pub const MIN_SIDECAR_VERSION: &str = "0.1.0";
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Compatibility {
+    Compatible,
+    /// Older than `MIN_SIDECAR_VERSION`; the bridge is not started
+    SidecarTooOld,
+    /// A breaking release newer than the app; started with a warning
+    SidecarNewer,
+    /// The version could not be read; started with a warning
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Versions {
+    pub app: String,
+    pub sidecar: Option<String>,
+    pub min_sidecar: String,
+    pub compatibility: Compatibility,
+}
+
+#[derive(Default)]
+pub struct VersionState {
+    sidecar: Mutex<Option<Version>>,
+}
+
+impl VersionState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the cached version, e.g. after switching to another binary.
+    pub fn reset(&self) {
+        *self.sidecar.lock().unwrap() = None;
+    }
+}
+
+/// The version in `--version` output, skipping any log lines before it.
+fn parse_version_output(stdout: &str) -> Option<Version> {
+    stdout.lines().rev().find_map(|line| Version::parse(line.trim().trim_start_matches('v')).ok())
+}
+
+/// Whether `sidecar` is a newer release series than `app` (the minor version
+/// before 1.0), which may have changed its CLI.
+fn is_breaking_newer(app: &Version, sidecar: &Version) -> bool {
+    if app.major == 0 {
+        sidecar.major > 0 || sidecar.minor > app.minor
+    } else {
+        sidecar.major > app.major
+    }
+}
+
+fn compatibility(app: &Version, sidecar: Option<&Version>) -> Compatibility {
+    let min = Version::parse(MIN_SIDECAR_VERSION).expect("valid minimum version");
+    match sidecar {
+        None => Compatibility::Unknown,
+        Some(v) if *v < min => Compatibility::SidecarTooOld,
+        Some(v) if is_breaking_newer(app, v) => Compatibility::SidecarNewer,
+        Some(_) => Compatibility::Compatible,
+    }
+}
+
+/// The bridge CLI's version, asked once and cached.
+async fn sidecar_version(app: &AppHandle) -> Option<Version> {
+    let state = app.state::<VersionState>();
+    if let Some(version) = state.sidecar.lock().unwrap().clone() {
+        return Some(version);
+    }
+    let command = match crate::sandbox::sidecar_command(app) {
+        Ok(command) => command.args(["--version"]),
+        Err(e) => {
+            log::warn!("Cannot ask the bridge for its version: {}", e);
+            return None;
+        }
+    };
+    let version = match crate::sidecar::sidecar_output(app, command, "version check", VERSION_TIMEOUT).await {
+        Ok(stdout) => parse_version_output(&stdout),
+        Err(e) => {
+            log::warn!("Cannot ask the bridge for its version: {}", e);
+            return None;
+        }
+    };
+    if let Some(version) = &version {
+        *state.sidecar.lock().unwrap() = Some(version.clone());
+    }
+    version
+}
+
+pub async fn versions(app: &AppHandle) -> Versions {
+    let app_version = app.package_info().version.clone();
+    let sidecar = sidecar_version(app).await;
+    Versions {
+        app: app_version.to_string(),
+        compatibility: compatibility(&app_version, sidecar.as_ref()),
+        sidecar: sidecar.map(|v| v.to_string()),
+        min_sidecar: MIN_SIDECAR_VERSION.to_string(),
+    }
+}
+
+/// Refuse to start a bridge the app cannot drive; warn about combinations
+/// that may misbehave.
+pub async fn check_sidecar(app: &AppHandle) -> Result<(), CommandError> {
+    let versions = versions(app).await;
+    let sidecar = versions.sidecar.as_deref().unwrap_or("unknown");
+    match versions.compatibility {
+        Compatibility::Compatible => Ok(()),
+        Compatibility::SidecarTooOld => Err(CommandError::IncompatibleSidecar(format!(
+            "the bridge is version {}, but this app needs {} or newer",
+            sidecar, versions.min_sidecar
+        ))),
+        Compatibility::SidecarNewer => {
+            log::warn!("The bridge ({}) is newer than this app ({}); some features may not work", sidecar, versions.app);
+            Ok(())
+        }
+        Compatibility::Unknown => {
+            log::warn!("Could not determine the bridge version; starting it anyway");
+            Ok(())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_versions(app: AppHandle) -> Result<Versions, CommandError> {
+    Ok(versions(&app).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(parse_version_output("0.1.0\n"), Some(v("0.1.0")));
+        assert_eq!(parse_version_output("info: Created default config\nv1.2.3\n"), Some(v("1.2.3")));
+        assert_eq!(parse_version_output("error: unknown option '--version'\n"), None);
+    }
+
+    #[test]
+    fn test_compatibility() {
+        let app = v("0.3.1");
+        assert_eq!(compatibility(&app, Some(&v("0.3.0"))), Compatibility::Compatible);
+        assert_eq!(compatibility(&app, Some(&v("0.2.9"))), Compatibility::Compatible);
+        assert_eq!(compatibility(&app, Some(&v("0.4.0"))), Compatibility::SidecarNewer);
+        assert_eq!(compatibility(&app, Some(&v("0.0.9"))), Compatibility::SidecarTooOld);
+        assert_eq!(compatibility(&app, None), Compatibility::Unknown);
+        assert_eq!(compatibility(&v("1.2.0"), Some(&v("1.9.0"))), Compatibility::Compatible);
+        assert_eq!(compatibility(&v("1.2.0"), Some(&v("2.0.0"))), Compatibility::SidecarNewer);
+    }
+}
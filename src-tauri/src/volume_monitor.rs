@@ -0,0 +1,104 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+// ============================================================================
+// Live mount state
+// ============================================================================
+//
+// `check_mount_status` only looks at the mounts when asked, so a drive
+// unmounted from the file manager stays "mounted" in the UI until the next
+// check. A dedicated thread runs a GLib main loop with the GIO volume
+// monitor and forwards its `mount-added`/`mount-removed` signals for the
+// bridge's locations as `mount:added`/`mount:removed` events.
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MountEvent {
+    /// GIO location of the mount root
+    pub uri: String,
+    pub name: String,
+    /// Local path of the mount (GVFS FUSE directory), if any
+    pub mount_point: Option<String>,
+}
+
+/// Whether `uri` is a WebDAV location served by the bridge on `port`.
+/// Mounts of sub-folders (extra mount definitions) count as well.
+fn is_bridge_uri(uri: &str, port: u16) -> bool {
+    let Some(rest) = uri.strip_prefix("dav://").or_else(|| uri.strip_prefix("davs://")) else {
+        return false;
+    };
+    let authority = rest.split('/').next().unwrap_or("");
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let Some((host, port_str)) = authority.rsplit_once(':') else {
+        return false;
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]") && port_str.parse() == Ok(port)
+}
+
+#[cfg(target_os = "linux")]
+fn bridge_port(app: &AppHandle) -> u16 {
+    crate::gateway::public_port(app).unwrap_or_else(|| crate::sidecar::configured_listen_addr().1)
+}
+
+#[cfg(target_os = "linux")]
+fn forward(app: &AppHandle, event: &str, mount: &gio::Mount) {
+    use gio::prelude::*;
+    use tauri::Emitter;
+
+    let root = mount.root();
+    let uri = root.uri().to_string();
+    if !is_bridge_uri(&uri, bridge_port(app)) {
+        return;
+    }
+    log::info!("{}: {}", event, uri);
+    let payload = MountEvent {
+        uri,
+        name: mount.name().to_string(),
+        mount_point: root.path().map(|p| p.display().to_string()),
+    };
+    let _ = app.emit(event, payload);
+}
+
+/// Start the monitor thread; called once from `setup`.
+#[cfg(target_os = "linux")]
+pub fn spawn(app: AppHandle) {
+    use gio::prelude::*;
+
+    let spawned = std::thread::Builder::new().name("gio-volume-monitor".into()).spawn(move || {
+        let context = glib::MainContext::new();
+        let result = context.with_thread_default(|| {
+            // The monitor delivers its signals on the thread-default context
+            // it was obtained on.
+            let monitor = gio::VolumeMonitor::get();
+            let added_app = app.clone();
+            monitor.connect_mount_added(move |_, mount| forward(&added_app, "mount:added", mount));
+            let removed_app = app.clone();
+            monitor.connect_mount_removed(move |_, mount| forward(&removed_app, "mount:removed", mount));
+            glib::MainLoop::new(Some(&context), false).run();
+        });
+        if let Err(e) = result {
+            log::error!("Volume monitor stopped: {}", e);
+        }
+    });
+    if let Err(e) = spawned {
+        log::error!("Failed to start the volume monitor: {}", e);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn(_app: AppHandle) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_bridge_uri() {
+        assert!(is_bridge_uri("dav://localhost:8080/", 8080));
+        assert!(is_bridge_uri("davs://127.0.0.1:8080/Documents/", 8080));
+        assert!(is_bridge_uri("dav://user@localhost:8080/", 8080));
+        assert!(!is_bridge_uri("dav://localhost:8081/", 8080));
+        assert!(!is_bridge_uri("dav://nas.local:8080/", 8080));
+        assert!(!is_bridge_uri("smb://localhost:8080/", 8080));
+    }
+}
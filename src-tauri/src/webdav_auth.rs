@@ -0,0 +1,284 @@
+use base64::Engine;
+use hyper::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::network_sharing::parse_basic_auth;
+use crate::sidecar::{read_config_json, set_webdav_key, update_config_json, CommandError};
+
+// ============================================================================
+// WebDAV authentication
+// ============================================================================
+//
+// The sidecar never serves the drive anonymously: on first run a random
+// username and password are generated for this install and kept in the OS
+// keyring, and every sidecar the app spawns is handed them through its
+// environment and enforces them. Only the app knows them, so other local
+// processes can no longer read the drive over localhost; the backend
+// presents them itself (the DAV client, and GIO mounts through the
+// `ask-password` signal). Users who want to connect other clients can set
+// their own username and password with `set_webdav_auth` (`webdav.requireAuth`
+// in the config); the gateway checks clients against those and swaps in the
+// install's credential when forwarding. LAN clients that logged in with the
+// network sharing credentials are let through the same way. Passwords are
+// stored as the unsalted SHA-256 hex digest the sidecar compares against.
+
+/// Environment variables the sidecar's `start` reads its credentials from.
+const USERNAME_ENV: &str = "PROTON_DRIVE_BRIDGE_WEBDAV_USERNAME";
+const PASSWORD_HASH_ENV: &str = "PROTON_DRIVE_BRIDGE_WEBDAV_PASSWORD_HASH";
+
+const KEYRING_SERVICE: &str = "proton-drive-webdav-bridge";
+const KEYRING_ENTRY: &str = "webdav-gateway";
+
+pub(crate) const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct WebdavAuthConfig {
+    #[serde(default)]
+    require_auth: bool,
+    username: Option<String>,
+    password_hash: Option<String>,
+}
+
+/// The username and password hash configured by the user, if clients may
+/// log in with them.
+fn configured() -> Option<(String, String)> {
+    let webdav = read_config_json().ok()?.get("webdav").cloned()?;
+    let config: WebdavAuthConfig = serde_json::from_value(webdav).ok()?;
+    match (config.require_auth, config.username, config.password_hash) {
+        (true, Some(username), Some(hash)) if !username.is_empty() && !hash.is_empty() => Some((username, hash)),
+        _ => None,
+    }
+}
+
+fn hash_password(password: &str) -> String {
+    hex::encode(Sha256::digest(password.as_bytes()))
+}
+
+/// Credential the app's own sidecar enforces.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallCredential {
+    pub username: String,
+    pub password: String,
+}
+
+impl InstallCredential {
+    fn generate() -> Self {
+        Self {
+            username: format!("bridge-{}", Alphanumeric.sample_string(&mut rand::thread_rng(), 8)),
+            password: Alphanumeric.sample_string(&mut rand::thread_rng(), 32),
+        }
+    }
+
+    /// `Authorization` header value for this credential.
+    pub fn header_value(&self) -> HeaderValue {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", self.username, self.password));
+        HeaderValue::from_str(&format!("Basic {}", token)).expect("base64 is a valid header value")
+    }
+
+    /// Environment for the sidecar process.
+    pub fn env(&self) -> [(&'static str, String); 2] {
+        [(USERNAME_ENV, self.username.clone()), (PASSWORD_HASH_ENV, hash_password(&self.password))]
+    }
+}
+
+/// Read the install's credential from the keyring, creating it on first run.
+/// Blocks on the keyring service.
+fn load_or_create() -> Result<InstallCredential, keyring::Error> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_ENTRY)?;
+    match entry.get_password() {
+        Ok(secret) => match serde_json::from_str(&secret) {
+            Ok(credential) => return Ok(credential),
+            Err(e) => log::warn!("Stored WebDAV credential is unreadable, replacing it: {}", e),
+        },
+        Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(e),
+    }
+    let credential = InstallCredential::generate();
+    entry.set_password(&serde_json::to_string(&credential).expect("credential serializes"))?;
+    log::info!("Generated WebDAV credentials for this install");
+    Ok(credential)
+}
+
+struct Run {
+    credential: InstallCredential,
+    /// User-configured username and password hash when the run started
+    configured: Option<(String, String)>,
+}
+
+#[derive(Default)]
+pub struct WebdavAuthState {
+    install: Mutex<Option<InstallCredential>>,
+    run: Mutex<Option<Run>>,
+}
+
+impl WebdavAuthState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the running sidecar's credential once it is gone.
+    pub fn end_run(&self) {
+        *self.run.lock().unwrap() = None;
+    }
+
+    /// Credential of the sidecar the app is running, if any.
+    pub fn run_credential(&self) -> Option<InstallCredential> {
+        self.run.lock().unwrap().as_ref().map(|r| r.credential.clone())
+    }
+
+    /// Whether the running sidecar also accepts user-configured credentials.
+    pub fn has_configured_credentials(&self) -> bool {
+        self.run.lock().unwrap().as_ref().is_some_and(|r| r.configured.is_some())
+    }
+
+    /// Check a client's credentials and replace them with the install's
+    /// before the request is forwarded. `lan_login_required` means the
+    /// network sharing login applies to the client, which already passed it.
+    /// Returns `false` if the client must authenticate first; when the
+    /// sidecar was not started by the app every request is passed through
+    /// unchanged.
+    pub fn authorize(&self, headers: &mut HeaderMap, lan_login_required: bool) -> bool {
+        let run = self.run.lock().unwrap();
+        let Some(run) = run.as_ref() else { return true };
+        let expected = run.credential.header_value();
+        if headers.get(AUTHORIZATION) == Some(&expected) {
+            return true;
+        }
+        let ok = lan_login_required
+            || match (&run.configured, parse_basic_auth(headers)) {
+                (Some((username, hash)), Some((user, pass))) => user == *username && hash_password(&pass) == *hash,
+                _ => false,
+            };
+        if ok {
+            headers.insert(AUTHORIZATION, expected);
+        }
+        ok
+    }
+}
+
+/// Set up authentication for a sidecar about to be spawned and return the
+/// credential to hand it. If the keyring cannot be used, a credential for
+/// this run only is generated so the drive is still never served anonymously.
+pub async fn begin_run(app: &AppHandle) -> InstallCredential {
+    let state = app.state::<WebdavAuthState>();
+    let cached = state.install.lock().unwrap().clone();
+    let credential = match cached {
+        Some(credential) => credential,
+        None => match tauri::async_runtime::spawn_blocking(load_or_create).await {
+            Ok(Ok(credential)) => {
+                *state.install.lock().unwrap() = Some(credential.clone());
+                credential
+            }
+            Ok(Err(e)) => {
+                log::warn!("Keyring unavailable, using temporary WebDAV credentials: {}", e);
+                InstallCredential::generate()
+            }
+            Err(e) => {
+                log::warn!("Keyring lookup failed, using temporary WebDAV credentials: {}", e);
+                InstallCredential::generate()
+            }
+        },
+    };
+    *state.run.lock().unwrap() = Some(Run { credential: credential.clone(), configured: configured() });
+    credential
+}
+
+/// Username and password the running bridge accepts from local WebDAV
+/// clients, for connecting clients other than the app's own mount.
+#[tauri::command]
+pub async fn get_webdav_credentials(state: State<'_, WebdavAuthState>) -> Result<Option<InstallCredential>, CommandError> {
+    Ok(state.run_credential())
+}
+
+/// Store a username and password WebDAV clients can log in with and turn
+/// `requireAuth` on. Takes effect the next time the bridge starts.
+#[tauri::command]
+pub async fn set_webdav_auth(username: String, password: String) -> Result<(), CommandError> {
+    let username = username.trim().to_string();
+    if username.is_empty() || username.contains(':') {
+        return Err(CommandError::InvalidWebdavCredentials("Username must be non-empty and must not contain ':'".into()));
+    }
+    if password.len() < MIN_PASSWORD_LEN {
+        return Err(CommandError::InvalidWebdavCredentials(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        )));
+    }
+
+    update_config_json(|v| {
+        set_webdav_key(v, "username", serde_json::json!(username));
+        set_webdav_key(v, "passwordHash", serde_json::json!(hash_password(&password)));
+        set_webdav_key(v, "requireAuth", serde_json::json!(true));
+        Ok(())
+    })?;
+    log::info!("WebDAV credentials updated for {}", username);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn basic(user: &str, pass: &str) -> HeaderMap {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, pass));
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Basic {}", token)).unwrap());
+        headers
+    }
+
+    fn state_with_run(configured: Option<(&str, &str)>) -> (WebdavAuthState, InstallCredential) {
+        let credential = InstallCredential::generate();
+        let state = WebdavAuthState::new();
+        *state.run.lock().unwrap() = Some(Run {
+            credential: credential.clone(),
+            configured: configured.map(|(user, pass)| (user.to_string(), hash_password(pass))),
+        });
+        (state, credential)
+    }
+
+    #[test]
+    fn test_hash_matches_sidecar_format() {
+        assert_eq!(hash_password("password"), "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8");
+    }
+
+    #[test]
+    fn test_authorize_swaps_in_run_credential() {
+        let (state, credential) = state_with_run(Some(("alice", "secret123")));
+
+        let mut headers = basic("alice", "secret123");
+        assert!(state.authorize(&mut headers, false));
+        assert_eq!(headers.get(AUTHORIZATION), Some(&credential.header_value()));
+
+        assert!(!state.authorize(&mut basic("alice", "wrong"), false));
+        assert!(!state.authorize(&mut HeaderMap::new(), false));
+
+        let mut headers = basic(&credential.username, &credential.password);
+        assert!(state.authorize(&mut headers, false));
+
+        let mut headers = HeaderMap::new();
+        assert!(state.authorize(&mut headers, true));
+        assert_eq!(headers.get(AUTHORIZATION), Some(&credential.header_value()));
+    }
+
+    #[test]
+    fn test_authorize_requires_install_credential_by_default() {
+        let (state, credential) = state_with_run(None);
+        assert!(!state.authorize(&mut HeaderMap::new(), false));
+        assert!(!state.authorize(&mut basic("alice", "secret123"), false));
+        assert!(state.authorize(&mut basic(&credential.username, &credential.password), false));
+    }
+
+    #[test]
+    fn test_authorize_passes_through_without_run() {
+        let state = WebdavAuthState::new();
+        let mut headers = basic("alice", "wrong");
+        assert!(state.authorize(&mut headers, false));
+        assert_eq!(headers, basic("alice", "wrong"));
+    }
+}
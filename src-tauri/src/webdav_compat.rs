@@ -0,0 +1,347 @@
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::dav::{DavClient, DavEntry};
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// WebDAV client compatibility
+// ============================================================================
+//
+// `probe_webdav_compat` sends the running bridge the requests common WebDAV
+// clients depend on and reports which of them it handles, so the mount page
+// can tell users which client to pick. Each feature is probed on its own:
+// the DAV classes from OPTIONS, PROPFIND at depth 1 and infinity, LOCK with
+// a refresh and UNLOCK, `If` header evaluation, and ranged GET. The probes
+// only read, apart from a short lock on one file that is released at once.
+// Depth infinity is tried on a folder below the root so the probe does not
+// crawl the whole drive. A feature that could not be probed, e.g. because
+// the drive has no files, is reported as untested and does not count
+// against any client.
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const LOCK_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:lockinfo xmlns:D="DAV:">
+  <D:lockscope><D:exclusive/></D:lockscope>
+  <D:locktype><D:write/></D:locktype>
+  <D:owner>Proton Drive WebDAV Bridge compatibility probe</D:owner>
+</D:lockinfo>"#;
+
+/// A lock token no server has issued, to see whether `If` is evaluated.
+const UNKNOWN_LOCK_TOKEN: &str = "<opaquelocktoken:00000000-0000-0000-0000-000000000000>";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Feature {
+    /// OPTIONS advertises DAV class 1
+    Options,
+    PropfindDepth1,
+    PropfindDepthInfinity,
+    /// LOCK, refresh and UNLOCK
+    Locking,
+    /// Requests with an unknown lock token in `If` are refused
+    IfHeader,
+    /// GET honours `Range` with 206 Partial Content
+    RangedGet,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FeatureStatus {
+    Supported,
+    Unsupported,
+    Untested,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureResult {
+    pub feature: Feature,
+    pub status: FeatureStatus,
+    /// What the server answered, or why the feature was not probed
+    pub detail: String,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Client {
+    Gvfs,
+    Davfs2,
+    /// The WebClient service behind "Map network drive"
+    Windows,
+    Cyberduck,
+}
+
+impl Client {
+    const ALL: [Client; 4] = [Client::Gvfs, Client::Davfs2, Client::Windows, Client::Cyberduck];
+
+    /// Features the client cannot work without, and features it uses when
+    /// available.
+    fn needs(self) -> (&'static [Feature], &'static [Feature]) {
+        match self {
+            // gvfsd-dav checks OPTIONS before mounting and reads files in ranges
+            Client::Gvfs => (&[Feature::Options, Feature::PropfindDepth1], &[Feature::RangedGet]),
+            // davfs2 locks files it opens for writing unless `use_locks` is off
+            Client::Davfs2 => (&[Feature::Options, Feature::PropfindDepth1], &[Feature::Locking, Feature::IfHeader]),
+            // The Windows mini-redirector mounts class 1 servers read-only
+            Client::Windows => (&[Feature::Options, Feature::PropfindDepth1, Feature::Locking], &[Feature::IfHeader, Feature::RangedGet]),
+            // Cyberduck resumes transfers with ranges and lists recursively when allowed
+            Client::Cyberduck => (&[Feature::PropfindDepth1], &[Feature::RangedGet, Feature::PropfindDepthInfinity]),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Verdict {
+    /// Everything the client uses works
+    Compatible,
+    /// The client works without some optional features
+    Limited,
+    /// A feature the client needs is missing
+    Incompatible,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientResult {
+    pub client: Client,
+    pub verdict: Verdict,
+    /// Unsupported features the client needs or uses
+    pub missing: Vec<Feature>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatReport {
+    pub features: Vec<FeatureResult>,
+    pub clients: Vec<ClientResult>,
+}
+
+impl CompatReport {
+    fn new(features: Vec<FeatureResult>) -> Self {
+        let clients = Client::ALL.iter().map(|&client| assess(client, &features)).collect();
+        Self { features, clients }
+    }
+}
+
+fn assess(client: Client, features: &[FeatureResult]) -> ClientResult {
+    let unsupported = |feature: &Feature| features.iter().any(|r| r.feature == *feature && r.status == FeatureStatus::Unsupported);
+    let (required, optional) = client.needs();
+    let missing_required: Vec<Feature> = required.iter().copied().filter(unsupported).collect();
+    let missing_optional: Vec<Feature> = optional.iter().copied().filter(unsupported).collect();
+    let verdict = if !missing_required.is_empty() {
+        Verdict::Incompatible
+    } else if !missing_optional.is_empty() {
+        Verdict::Limited
+    } else {
+        Verdict::Compatible
+    };
+    ClientResult { client, verdict, missing: missing_required.into_iter().chain(missing_optional).collect() }
+}
+
+/// Compliance classes from a `DAV` header, e.g. `1, 2, access-control`.
+fn parse_dav_classes(header: &str) -> Vec<String> {
+    header.split(',').map(str::trim).filter(|c| !c.is_empty()).map(str::to_string).collect()
+}
+
+/// The coded URL from a `Lock-Token` header, angle brackets included.
+fn parse_lock_token(header: &str) -> Option<&str> {
+    let token = header.trim();
+    (token.starts_with('<') && token.ends_with('>') && token.len() > 2).then_some(token)
+}
+
+fn untested(feature: Feature, detail: impl Into<String>) -> FeatureResult {
+    FeatureResult { feature, status: FeatureStatus::Untested, detail: detail.into() }
+}
+
+/// Run `probe` with a timeout. A request that fails or times out leaves the
+/// feature untested: it says nothing about what the server supports.
+async fn run_probe<F>(feature: Feature, probe: F) -> FeatureResult
+where
+    F: std::future::Future<Output = Result<(bool, String), reqwest::Error>>,
+{
+    let (status, detail) = match tokio::time::timeout(PROBE_TIMEOUT, probe).await {
+        Ok(Ok((true, detail))) => (FeatureStatus::Supported, detail),
+        Ok(Ok((false, detail))) => (FeatureStatus::Unsupported, detail),
+        Ok(Err(e)) => (FeatureStatus::Untested, format!("Request failed: {}", e)),
+        Err(_) => (FeatureStatus::Untested, format!("No answer within {}s", PROBE_TIMEOUT.as_secs())),
+    };
+    FeatureResult { feature, status, detail }
+}
+
+async fn probe_options(client: &DavClient) -> Result<(bool, String), reqwest::Error> {
+    let resp = client.request("OPTIONS", "/").send().await?;
+    let header = |name: &str| resp.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+    let classes = parse_dav_classes(&header("DAV"));
+    if classes.is_empty() {
+        return Ok((false, format!("OPTIONS answered {} without a DAV header", resp.status())));
+    }
+    let allow = header("Allow");
+    let detail = if allow.is_empty() {
+        format!("DAV classes {}", classes.join(", "))
+    } else {
+        format!("DAV classes {}; allows {}", classes.join(", "), allow)
+    };
+    Ok((classes.iter().any(|c| c == "1"), detail))
+}
+
+async fn probe_propfind(client: &DavClient, path: &str, depth: &str) -> Result<(bool, String), reqwest::Error> {
+    let resp = client
+        .request("PROPFIND", path)
+        .header("Depth", depth)
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .body(crate::dav::PROPFIND_BODY)
+        .send()
+        .await?;
+    if resp.status() != StatusCode::MULTI_STATUS {
+        return Ok((false, format!("Depth {} on {} answered {}", depth, path, resp.status())));
+    }
+    let entries = crate::dav::parse_multistatus(&resp.text().await?).map(|e| e.len()).unwrap_or(0);
+    Ok((true, format!("Depth {} on {} listed {} item(s)", depth, path, entries)))
+}
+
+async fn probe_locking(client: &DavClient, path: &str) -> Result<(bool, String), reqwest::Error> {
+    let resp = client
+        .request("LOCK", path)
+        .header("Depth", "0")
+        .header("Timeout", "Second-30")
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .body(LOCK_BODY)
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Ok((false, format!("LOCK on {} answered {}", path, resp.status())));
+    }
+    let header = resp.headers().get("Lock-Token").and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let Some(token) = parse_lock_token(header) else {
+        return Ok((false, format!("LOCK on {} returned no lock token", path)));
+    };
+    let token = token.to_string();
+
+    // Refreshing is how clients keep a lock for longer than its timeout
+    let refresh = client.request("LOCK", path).header("If", format!("({})", token)).header("Timeout", "Second-30").send().await?.status();
+    let unlock = client.request("UNLOCK", path).header("Lock-Token", &token).send().await?.status();
+    if !unlock.is_success() {
+        log::warn!("Compatibility probe could not release its lock on {}: {}", path, unlock);
+        return Ok((false, format!("UNLOCK on {} answered {}", path, unlock)));
+    }
+    if !refresh.is_success() {
+        return Ok((false, format!("Refreshing the lock on {} answered {}", path, refresh)));
+    }
+    Ok((true, format!("Locked, refreshed and unlocked {}", path)))
+}
+
+async fn probe_if_header(client: &DavClient) -> Result<(bool, String), reqwest::Error> {
+    let status = client
+        .request("PROPFIND", "/")
+        .header("Depth", "0")
+        .header("If", format!("({})", UNKNOWN_LOCK_TOKEN))
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .body(crate::dav::PROPFIND_BODY)
+        .send()
+        .await?
+        .status();
+    Ok(match status {
+        StatusCode::PRECONDITION_FAILED => (true, "A request with an unknown lock token was refused".to_string()),
+        s => (false, format!("A request with an unknown lock token answered {}", s)),
+    })
+}
+
+async fn probe_ranged_get(client: &DavClient, file: &DavEntry) -> Result<(bool, String), reqwest::Error> {
+    let resp = client.request("GET", &file.path).header("Range", "bytes=0-0").send().await?;
+    Ok(match resp.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            let range = resp.headers().get("Content-Range").and_then(|v| v.to_str().ok()).unwrap_or("no Content-Range").to_string();
+            (true, format!("Ranged GET on {} answered 206 ({})", file.path, range))
+        }
+        s => (false, format!("Ranged GET on {} answered {}", file.path, s)),
+    })
+}
+
+#[tauri::command]
+pub async fn probe_webdav_compat(app: AppHandle) -> Result<CompatReport, CommandError> {
+    let client = DavClient::for_app(&app)?;
+    let mut features = Vec::new();
+    features.push(run_probe(Feature::Options, probe_options(&client)).await);
+    features.push(run_probe(Feature::PropfindDepth1, probe_propfind(&client, "/", "1")).await);
+
+    // The root listing supplies the file and folder the other probes use
+    let entries = client.propfind("/", 1).await.unwrap_or_default();
+    let file = entries.iter().skip(1).find(|e| !e.is_dir);
+    let folder = entries.iter().skip(1).find(|e| e.is_dir).map_or("/", |e| e.path.as_str());
+
+    features.push(run_probe(Feature::PropfindDepthInfinity, probe_propfind(&client, folder, "infinity")).await);
+    features.push(match file {
+        Some(file) => run_probe(Feature::Locking, probe_locking(&client, &file.path)).await,
+        None => untested(Feature::Locking, "No file in the drive root to lock"),
+    });
+    features.push(run_probe(Feature::IfHeader, probe_if_header(&client)).await);
+    features.push(match file {
+        Some(file) => run_probe(Feature::RangedGet, probe_ranged_get(&client, file)).await,
+        None => untested(Feature::RangedGet, "No file in the drive root to read"),
+    });
+
+    let report = CompatReport::new(features);
+    for result in &report.clients {
+        log::info!("WebDAV compatibility with {:?}: {:?}", result.client, result.verdict);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(feature: Feature, status: FeatureStatus) -> FeatureResult {
+        FeatureResult { feature, status, detail: String::new() }
+    }
+
+    #[test]
+    fn test_parse_dav_classes() {
+        assert_eq!(parse_dav_classes("1, 2,access-control"), vec!["1", "2", "access-control"]);
+        assert!(parse_dav_classes(" ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_lock_token() {
+        assert_eq!(parse_lock_token(" <urn:uuid:abc> "), Some("<urn:uuid:abc>"));
+        assert_eq!(parse_lock_token("urn:uuid:abc"), None);
+        assert_eq!(parse_lock_token("<>"), None);
+    }
+
+    #[test]
+    fn test_verdicts() {
+        let features = vec![
+            result(Feature::Options, FeatureStatus::Supported),
+            result(Feature::PropfindDepth1, FeatureStatus::Supported),
+            result(Feature::PropfindDepthInfinity, FeatureStatus::Unsupported),
+            result(Feature::Locking, FeatureStatus::Unsupported),
+            result(Feature::IfHeader, FeatureStatus::Supported),
+            result(Feature::RangedGet, FeatureStatus::Supported),
+        ];
+        let report = CompatReport::new(features);
+        let verdict = |client| report.clients.iter().find(|r| r.client == client).unwrap();
+        assert_eq!(verdict(Client::Gvfs).verdict, Verdict::Compatible);
+        assert_eq!(verdict(Client::Davfs2).verdict, Verdict::Limited);
+        assert_eq!(verdict(Client::Davfs2).missing, vec![Feature::Locking]);
+        assert_eq!(verdict(Client::Windows).verdict, Verdict::Incompatible);
+        assert_eq!(verdict(Client::Cyberduck).missing, vec![Feature::PropfindDepthInfinity]);
+    }
+
+    #[test]
+    fn test_untested_features_do_not_count_against_clients() {
+        let report = CompatReport::new(vec![
+            result(Feature::Options, FeatureStatus::Supported),
+            result(Feature::PropfindDepth1, FeatureStatus::Supported),
+            result(Feature::Locking, FeatureStatus::Untested),
+        ]);
+        assert!(report.clients.iter().all(|r| r.verdict == Verdict::Compatible));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["clients"][2]["client"], "windows");
+        assert_eq!(json["features"][2]["status"], "untested");
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+
+use crate::sidecar::CommandError;
+
+// ============================================================================
+// Auxiliary windows
+// ============================================================================
+//
+// Tools that benefit from their own space (logs, transfers, file browser)
+// open in separate windows. Each window loads the same frontend with a
+// `?window=<label>` query parameter telling it which view to render, and
+// subscribes to the events it needs on its own. Windows are singletons per
+// kind: opening one that already exists just focuses it.
+//
+// The main window is declared in `tauri.conf.json` with `create: false` and
+// built on first use, so a `--headless` run never starts a WebView.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowKind {
+    LogViewer,
+    TransferManager,
+    FileBrowser,
+}
+
+impl WindowKind {
+    /// Window label; must match the labels granted in `capabilities/`.
+    fn label(self) -> &'static str {
+        match self {
+            WindowKind::LogViewer => "log-viewer",
+            WindowKind::TransferManager => "transfer-manager",
+            WindowKind::FileBrowser => "file-browser",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            WindowKind::LogViewer => "Logs",
+            WindowKind::TransferManager => "Transfers",
+            WindowKind::FileBrowser => "Files",
+        }
+    }
+
+    fn inner_size(self) -> (f64, f64) {
+        match self {
+            WindowKind::LogViewer => (800.0, 500.0),
+            WindowKind::TransferManager => (600.0, 450.0),
+            WindowKind::FileBrowser => (900.0, 650.0),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowLifecycleEvent {
+    pub kind: WindowKind,
+    pub label: String,
+}
+
+fn window_url(kind: WindowKind) -> WebviewUrl {
+    WebviewUrl::App(format!("index.html?window={}", kind.label()).into())
+}
+
+/// The main window, built from its `tauri.conf.json` entry if it doesn't
+/// exist yet.
+pub fn main_window(app: &AppHandle) -> Result<WebviewWindow, CommandError> {
+    if let Some(window) = app.get_webview_window("main") {
+        return Ok(window);
+    }
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == "main")
+        .cloned()
+        .ok_or_else(|| CommandError::Unknown("No main window in the app config".into()))?;
+    WebviewWindowBuilder::from_config(app, &config)
+        .and_then(|builder| builder.build())
+        .map_err(|e| CommandError::Unknown(e.to_string()))
+}
+
+/// Open (or focus) the auxiliary window of the given kind and return its label.
+#[tauri::command]
+pub async fn open_window(app: AppHandle, kind: WindowKind) -> Result<String, CommandError> {
+    let label = kind.label();
+    if let Some(existing) = app.get_webview_window(label) {
+        let _ = existing.unminimize();
+        let _ = existing.show();
+        let _ = existing.set_focus();
+        return Ok(label.to_string());
+    }
+
+    let (width, height) = kind.inner_size();
+    let window = WebviewWindowBuilder::new(&app, label, window_url(kind))
+        .title(format!("{} — Proton Drive WebDAV Bridge", kind.title()))
+        .inner_size(width, height)
+        .min_inner_size(400.0, 300.0)
+        .decorations(false)
+        .build()
+        .map_err(|e| CommandError::Unknown(e.to_string()))?;
+
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            let _ = handle.emit("window:closed", WindowLifecycleEvent { kind, label: label.to_string() });
+        }
+    });
+
+    let _ = app.emit("window:opened", WindowLifecycleEvent { kind, label: label.to_string() });
+    Ok(label.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_kind_deserializes_camel_case() {
+        let kind: WindowKind = serde_json::from_str("\"transferManager\"").unwrap();
+        assert_eq!(kind, WindowKind::TransferManager);
+    }
+
+    #[test]
+    fn test_window_url_carries_label() {
+        match window_url(WindowKind::LogViewer) {
+            WebviewUrl::App(path) => assert_eq!(path.to_str(), Some("index.html?window=log-viewer")),
+            _ => panic!("expected an app URL"),
+        }
+    }
+}
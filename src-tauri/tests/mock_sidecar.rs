@@ -0,0 +1,82 @@
+//! The app against `mock-bridge`, a stand-in for the bridge CLI; run with
+//! `cargo test --features mock-sidecar`.
+#![cfg(feature = "mock-sidecar")]
+
+use app::test_support::{self, MockBridge, SidecarState};
+use serde_json::Value;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn bridge() -> MockBridge {
+    MockBridge::new(env!("CARGO_BIN_EXE_mock-bridge"))
+}
+
+#[test]
+fn test_status_over_rpc() {
+    let mut rpc = bridge().env("MOCK_BRIDGE_SERVER_PID", "4242").rpc().unwrap();
+    let status = rpc.status().unwrap();
+    assert!(status.server.running);
+    assert_eq!(status.server.pid, Some(4242));
+    assert!(status.auth.logged_in);
+    assert_eq!(status.auth.username.as_deref(), Some("user@example.com"));
+    assert_eq!(status.config.webdav.port, 8080);
+
+    // The same process keeps answering
+    assert!(rpc.status().unwrap().server.running);
+
+    let stopped = bridge().env("MOCK_BRIDGE_LOGGED_OUT", "1").rpc().unwrap().status().unwrap();
+    assert!(!stopped.server.running);
+    assert!(!stopped.auth.logged_in);
+}
+
+#[test]
+fn test_rpc_errors() {
+    let mut rpc = bridge().rpc().unwrap();
+    assert_eq!(rpc.call("auth.logout", Value::Null).unwrap(), serde_json::json!({"loggedOut": true}));
+    assert_eq!(rpc.call("drive.format", Value::Null).unwrap_err(), "Method not found: drive.format");
+}
+
+#[test]
+fn test_login() {
+    bridge().login("user@example.com").unwrap();
+    let error = bridge().env("MOCK_BRIDGE_LOGIN_FAIL", "1").login("user@example.com").unwrap_err();
+    assert_eq!(error.code(), "AUTH_FAILED");
+    assert!(error.to_string().contains("Incorrect login credentials"));
+}
+
+#[test]
+fn test_start_and_stop() {
+    let supervisor = SidecarState::new();
+    let running = bridge().start(&supervisor, 18080).unwrap();
+    assert_eq!(test_support::tracked_pid(&supervisor), Some(running.pid));
+    assert_eq!(running.next_line(TIMEOUT).as_deref(), Some("Starting WebDAV server on 127.0.0.1:18080"));
+    assert!(test_support::affects_status(&running.next_line(TIMEOUT).unwrap()));
+
+    test_support::stop(&supervisor).unwrap();
+    assert_eq!(running.wait_exit(TIMEOUT), Some(true));
+    assert_eq!(test_support::tracked_pid(&supervisor), None);
+    assert_eq!(test_support::stop(&supervisor).unwrap_err().code(), "SIDECAR_NOT_RUNNING");
+}
+
+#[test]
+fn test_crash_is_not_a_requested_stop() {
+    let supervisor = SidecarState::new();
+    let running = bridge().env("MOCK_BRIDGE_CRASH_AFTER_MS", "100").start(&supervisor, 18081).unwrap();
+    assert_eq!(running.wait_exit(TIMEOUT), Some(false));
+    assert_eq!(test_support::tracked_pid(&supervisor), None);
+}
+
+#[test]
+fn test_restart_tracks_the_new_process() {
+    let supervisor = SidecarState::new();
+    let first = bridge().start(&supervisor, 18082).unwrap();
+    test_support::stop(&supervisor).unwrap();
+    assert_eq!(first.wait_exit(TIMEOUT), Some(true));
+
+    let second = bridge().start(&supervisor, 18082).unwrap();
+    assert_ne!(second.pid, first.pid);
+    assert_eq!(test_support::tracked_pid(&supervisor), Some(second.pid));
+    test_support::stop(&supervisor).unwrap();
+    assert_eq!(second.wait_exit(TIMEOUT), Some(true));
+}
@@ -70,34 +70,8 @@ export function registerConfigCommand(program: Command): void {
     .argument('<value>', 'Value to set')
     .action((key, value) => {
       try {
-        const config = loadConfig();
-        const parts = key.split('.');
-
-        // Parse value
-        let parsedValue: unknown = value;
-        if (value === 'true') parsedValue = true;
-        else if (value === 'false') parsedValue = false;
-        else if (/^\d+$/.test(value)) parsedValue = parseInt(value, 10);
-
-        // Set nested value
-        if (parts.length === 1) {
-          (config as unknown as Record<string, unknown>)[parts[0]] = parsedValue;
-        } else if (parts.length === 2) {
-          const section = (config as unknown as Record<string, Record<string, unknown>>)[parts[0]];
-          if (section && typeof section === 'object') {
-            section[parts[1]] = parsedValue;
-          } else {
-            console.error(`Invalid config section: ${parts[0]}`);
-            process.exit(1);
-          }
-        } else {
-          console.error('Only one level of nesting is supported (e.g., webdav.port)');
-          process.exit(1);
-        }
-
-        updateConfig(config);
+        setConfigValue(key, value);
         console.log(`✓ Set ${key} = ${value}`);
-        logger.info(`Config updated: ${key} = ${value}`);
       } catch (error) {
         const message = error instanceof Error ? error.message : String(error);
         console.error(`✗ Failed to set config: ${message}`);
@@ -265,4 +239,37 @@ export function registerConfigCommand(program: Command): void {
     });
 }
 
+/**
+ * Set a config value by dotted key (e.g. webdav.port), parsing booleans and
+ * integers from their string form
+ */
+export function setConfigValue(key: string, value: string): void {
+  const config = loadConfig();
+  const parts = key.split('.');
+
+  // Parse value
+  let parsedValue: unknown = value;
+  if (value === 'true') parsedValue = true;
+  else if (value === 'false') parsedValue = false;
+  else if (/^\d+$/.test(value)) parsedValue = parseInt(value, 10);
+
+  // Set nested value
+  if (parts.length === 1) {
+    (config as unknown as Record<string, unknown>)[parts[0]] = parsedValue;
+  } else if (parts.length === 2) {
+    const section = (config as unknown as Record<string, Record<string, unknown>>)[parts[0]];
+    if (section && typeof section === 'object') {
+      section[parts[1]] = parsedValue;
+    } else {
+      throw new Error(`Invalid config section: ${parts[0]}`);
+    }
+  } else {
+    throw new Error('Only one level of nesting is supported (e.g., webdav.port)');
+  }
+
+  updateConfig(config);
+  logger.info(`Config updated: ${key} = ${value}`);
+}
+
+
 export default registerConfigCommand;
@@ -9,3 +9,6 @@ export { registerStartCommand } from './start.js';
 export { registerStopCommand } from './stop.js';
 export { registerStatusCommand } from './status.js';
 export { registerConfigCommand } from './config.js';
+export { registerTrashCommand } from './trash.js';
+export { registerShareCommand } from './share.js';
+export { registerRpcCommand } from './rpc.js';
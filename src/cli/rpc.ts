@@ -0,0 +1,174 @@
+/**
+ * Proton Drive WebDAV Bridge - RPC CLI Command
+ *
+ * Serves JSON-RPC 2.0 over stdin/stdout, one message per line, so the desktop
+ * app can query and control the bridge through a long-lived process instead
+ * of running a CLI command and parsing its output for every operation.
+ */
+
+import { Command } from 'commander';
+import { createInterface } from 'readline';
+import { writeFileSync } from 'fs';
+import { clearProcessTraceId, disableConsoleLogging, logger, withTraceId } from '../logger.js';
+import { loadConfig, updateConfig } from '../config.js';
+import { deleteStoredCredentials, hasStoredCredentials } from '../keychain.js';
+import { getCachePurgeFilePath } from '../paths.js';
+import { toAppError } from '../utils/error.js';
+import { collectStatus } from './status.js';
+import { setConfigValue } from './config.js';
+
+// ============================================================================
+// Protocol
+// ============================================================================
+
+/** JSON-RPC 2.0 error codes */
+const PARSE_ERROR = -32700;
+const INVALID_REQUEST = -32600;
+const METHOD_NOT_FOUND = -32601;
+const INVALID_PARAMS = -32602;
+/** Application errors; `data.code` carries the bridge's error code */
+const SERVER_ERROR = -32000;
+
+type RequestId = number | string | null;
+
+class RpcError extends Error {
+  constructor(
+    public readonly code: number,
+    message: string,
+    public readonly data?: unknown
+  ) {
+    super(message);
+  }
+}
+
+type Params = Record<string, unknown>;
+
+function stringParam(params: Params, name: string): string {
+  const value = params[name];
+  if (typeof value !== 'string') {
+    throw new RpcError(INVALID_PARAMS, `Missing string parameter: ${name}`);
+  }
+  return value;
+}
+
+// ============================================================================
+// Methods
+// ============================================================================
+
+const methods: Record<string, (params: Params) => Promise<unknown>> = {
+  status: () => collectStatus(),
+
+  'auth.status': async () => (await collectStatus()).auth,
+
+  'auth.logout': async () => {
+    const loggedIn = await hasStoredCredentials();
+    if (loggedIn) {
+      await deleteStoredCredentials();
+      updateConfig({ username: undefined });
+      logger.info('User logged out');
+    }
+    return { loggedOut: loggedIn };
+  },
+
+  'config.get': async () => {
+    const config = loadConfig();
+    return {
+      ...config,
+      webdav: {
+        ...config.webdav,
+        passwordHash: config.webdav.passwordHash ? '****' : undefined,
+      },
+    };
+  },
+
+  'config.set': async (params) => {
+    setConfigValue(stringParam(params, 'key'), stringParam(params, 'value'));
+    return null;
+  },
+
+  // The server runs in another process; it watches this file for changes
+  'cache.purge': async () => {
+    writeFileSync(getCachePurgeFilePath(), String(Date.now()));
+    logger.info('Requested cache purge');
+    return null;
+  },
+};
+
+async function handle(line: string): Promise<object | null> {
+  let id: RequestId = null;
+  // `traceId` is an extension: the app's ID for the operation, for the logs
+  let traceId: string | undefined;
+  try {
+    let request: unknown;
+    try {
+      request = JSON.parse(line);
+    } catch {
+      throw new RpcError(PARSE_ERROR, 'Parse error');
+    }
+    if (typeof request !== 'object' || request === null || Array.isArray(request)) {
+      throw new RpcError(INVALID_REQUEST, 'Invalid request');
+    }
+    const { jsonrpc, method, params } = request as Record<string, unknown>;
+    const rawId = (request as Record<string, unknown>).id;
+    const rawTraceId = (request as Record<string, unknown>).traceId;
+    if (typeof rawTraceId === 'string') traceId = rawTraceId;
+    if (typeof rawId === 'number' || typeof rawId === 'string') id = rawId;
+    if (jsonrpc !== '2.0' || typeof method !== 'string') {
+      throw new RpcError(INVALID_REQUEST, 'Invalid request');
+    }
+    const handler = Object.hasOwn(methods, method) ? methods[method] : undefined;
+    if (!handler) {
+      throw new RpcError(METHOD_NOT_FOUND, `Method not found: ${method}`);
+    }
+    // The app writes config.json while this process runs; never act on a stale copy
+    loadConfig();
+    const result = await withTraceId(traceId, () => handler((params ?? {}) as Params));
+    // Notifications (no id) get no response
+    return rawId === undefined ? null : { jsonrpc: '2.0', id, result };
+  } catch (error) {
+    if (error instanceof RpcError) {
+      return { jsonrpc: '2.0', id, error: { code: error.code, message: error.message } };
+    }
+    const appError = toAppError(error);
+    withTraceId(traceId, () => logger.error(`RPC request failed: [${appError.code}] ${appError.message}`));
+    return {
+      jsonrpc: '2.0',
+      id,
+      error: {
+        code: SERVER_ERROR,
+        message: appError.getPublicMessage(),
+        data: { code: appError.code },
+      },
+    };
+  }
+}
+
+// ============================================================================
+// Command Registration
+// ============================================================================
+
+export function registerRpcCommand(program: Command): void {
+  program
+    .command('rpc')
+    .description('Serve JSON-RPC requests on stdin/stdout (used by the desktop app)')
+    .action(async () => {
+      // stdout carries responses only
+      disableConsoleLogging();
+      // Requests carry their own trace IDs
+      clearProcessTraceId();
+
+      const input = createInterface({ input: process.stdin, terminal: false });
+      for await (const line of input) {
+        if (!line.trim()) continue;
+        const response = await handle(line);
+        if (response) {
+          process.stdout.write(`${JSON.stringify(response)}\n`);
+        }
+      }
+
+      // stdin closes when the app goes away
+      process.exit(0);
+    });
+}
+
+export default registerRpcCommand;
@@ -0,0 +1,82 @@
+/**
+ * Proton Drive WebDAV Bridge - Share CLI Command
+ *
+ * Creates and revokes public share links.
+ */
+
+import { Command } from 'commander';
+import { driveClient } from '../drive.js';
+import { logger } from '../logger.js';
+import { NotFoundError, InvalidRequestError } from '../errors/index.js';
+import { toAppError } from '../utils/error.js';
+
+/**
+ * Environment variable holding the link password, so it stays out of the
+ * process list.
+ */
+export const SHARE_PASSWORD_ENV = 'SHARE_LINK_PASSWORD';
+
+export function registerShareCommand(program: Command): void {
+  const shareCmd = program.command('share').description('Manage public share links');
+
+  // Create subcommand
+  shareCmd
+    .command('create')
+    .description(`Create a public link to a file or folder (password from $${SHARE_PASSWORD_ENV})`)
+    .argument('<path>', 'Path of the file or folder in Proton Drive')
+    .option('-e, --expires <timestamp>', 'Expiry as a Unix timestamp in seconds')
+    .option('-j, --json', 'Output as JSON')
+    .action(async (path: string, options) => {
+      try {
+        let expiration: Date | undefined;
+        if (options.expires !== undefined) {
+          const seconds = Number(options.expires);
+          if (!Number.isInteger(seconds) || seconds * 1000 <= Date.now()) {
+            throw new InvalidRequestError(`Invalid expiry: ${options.expires}`);
+          }
+          expiration = new Date(seconds * 1000);
+        }
+
+        await driveClient.initialize();
+        const node = await driveClient.resolvePath(path);
+        if (!node) {
+          throw new NotFoundError(path);
+        }
+        const link = await driveClient.createShareLink(node.uid, {
+          expiration,
+          password: process.env[SHARE_PASSWORD_ENV] || undefined,
+        });
+
+        if (options.json) {
+          console.log(JSON.stringify(link, null, 2));
+        } else {
+          console.log(`✓ ${link.url}`);
+        }
+        logger.info(`Created share link for ${path}`);
+      } catch (error) {
+        const appError = toAppError(error);
+        console.error(`✗ Sharing failed: ${appError.getPublicMessage()}`);
+        logger.error(`Sharing failed: [${appError.code}] ${appError.message}`);
+        process.exit(1);
+      }
+    });
+
+  // Revoke subcommand
+  shareCmd
+    .command('revoke')
+    .description('Remove the public link of a file or folder')
+    .argument('<id>', 'Link ID, as printed by `share create --json`')
+    .action(async (id: string) => {
+      try {
+        await driveClient.initialize();
+        await driveClient.revokeShareLink(id);
+        console.log('✓ Share link revoked.');
+        logger.info(`Revoked share link ${id}`);
+      } catch (error) {
+        const appError = toAppError(error);
+        console.error(`✗ Revoking failed: ${appError.getPublicMessage()}`);
+        logger.error(`Revoking failed: [${appError.code}] ${appError.message}`);
+        process.exit(1);
+      }
+    });
+}
@@ -21,6 +21,7 @@ export function registerStartCommand(program: Command): void {
     .description('Start the WebDAV server')
     .option('-p, --port <port>', 'Port to listen on', (val) => parseInt(val, 10))
     .option('-H, --host <host>', 'Host to bind to')
+    .option('--socket <path>', 'Listen on a Unix socket instead of a TCP port (@name: abstract)')
     .option('--no-auth', 'Disable authentication (not recommended)')
     .option('-d, --daemon', 'Run as background daemon')
     .option('--no-daemon', 'Run in foreground')
@@ -67,7 +68,17 @@ export function registerStartCommand(program: Command): void {
         const serverOptions: Record<string, unknown> = {};
         if (options.port) serverOptions.port = options.port;
         if (options.host) serverOptions.host = options.host;
+        if (options.socket) serverOptions.socketPath = options.socket;
         if (options.auth === false) serverOptions.requireAuth = false;
+        // Credentials handed over by the desktop app are always enforced,
+        // even with --no-auth
+        const envUsername = process.env.PROTON_DRIVE_BRIDGE_WEBDAV_USERNAME;
+        const envPasswordHash = process.env.PROTON_DRIVE_BRIDGE_WEBDAV_PASSWORD_HASH;
+        if (envUsername && envPasswordHash) {
+          serverOptions.requireAuth = true;
+          serverOptions.username = envUsername;
+          serverOptions.passwordHash = envPasswordHash;
+        }
 
         const server = new WebDAVServer(serverOptions);
 
@@ -113,6 +124,7 @@ async function spawnDaemon(options: Record<string, unknown>): Promise<void> {
 
   if (options.port) args.push('--port', String(options.port));
   if (options.host) args.push('--host', String(options.host));
+  if (options.socket) args.push('--socket', String(options.socket));
   if (options.auth === false) args.push('--no-auth');
 
   // Get the path to this script
@@ -12,6 +12,12 @@ import { logger } from '../logger.js';
 import { readPidFile, isProcessRunning } from './daemon-utils.js';
 import { existsSync } from 'fs';
 
+/**
+ * Version of the status JSON; bump when fields are renamed or removed so
+ * the desktop app can tell it is talking to a newer or older bridge
+ */
+export const STATUS_SCHEMA_VERSION = 2;
+
 export function registerStatusCommand(program: Command): void {
   program
     .command('status')
@@ -19,61 +25,7 @@ export function registerStatusCommand(program: Command): void {
     .option('-j, --json', 'Output status as JSON')
     .action(async (options) => {
       try {
-        const config = getConfig();
-        const status = {
-          server: {
-            running: false,
-            pid: null as number | null,
-            url: null as string | null,
-          },
-          auth: {
-            loggedIn: false,
-            username: null as string | null,
-          },
-          config: {
-            webdav: {
-              host: config.webdav.host,
-              port: config.webdav.port,
-              https: config.webdav.https,
-              requireAuth: config.webdav.requireAuth,
-            },
-            remotePath: config.remotePath,
-          },
-          logFile: getLogFilePath(),
-        };
-
-        // Check server status
-        const pid = readPidFile();
-        if (pid && isProcessRunning(pid)) {
-          status.server.running = true;
-          status.server.pid = pid;
-
-          const config = status.config;
-          const protocol = config.webdav.https ? 'https' : 'http';
-          status.server.url = `${protocol}://${config.webdav.host}:${config.webdav.port}`;
-        }
-
-        // Check auth status
-        // Check if credentials exist (keyring has tokens)
-        // Username is stored in config.json (non-sensitive metadata)
-        const credsFileExists = existsSync(getCredentialsFilePath());
-        try {
-          const creds = await getStoredCredentials();
-          if (creds) {
-            status.auth.loggedIn = true;
-            // Username is stored in config as non-sensitive metadata
-            status.auth.username = config.username || creds.username || null;
-          }
-        } catch (error) {
-          // Fallback: if keyring fails but file exists, check config for username
-          if (credsFileExists && config.username) {
-            status.auth.loggedIn = true;
-            status.auth.username = config.username;
-          } else {
-            const message = error instanceof Error ? error.message : String(error);
-            logger.warn(`Failed to retrieve stored credentials: ${message}`);
-          }
-        }
+        const status = await collectStatus();
 
         // Output
         if (options.json) {
@@ -123,4 +75,68 @@ export function registerStatusCommand(program: Command): void {
     });
 }
 
+/**
+ * Collect server, authentication and configuration status
+ */
+export async function collectStatus() {
+  const config = getConfig();
+  const status = {
+    schemaVersion: STATUS_SCHEMA_VERSION,
+    server: {
+      running: false,
+      pid: null as number | null,
+      url: null as string | null,
+    },
+    auth: {
+      loggedIn: false,
+      username: null as string | null,
+    },
+    config: {
+      webdav: {
+        host: config.webdav.host,
+        port: config.webdav.port,
+        https: config.webdav.https,
+        requireAuth: config.webdav.requireAuth,
+      },
+      remotePath: config.remotePath,
+    },
+    logFile: getLogFilePath(),
+  };
+
+  // Check server status
+  const pid = readPidFile();
+  if (pid && isProcessRunning(pid)) {
+    status.server.running = true;
+    status.server.pid = pid;
+
+    const config = status.config;
+    const protocol = config.webdav.https ? 'https' : 'http';
+    status.server.url = `${protocol}://${config.webdav.host}:${config.webdav.port}`;
+  }
+
+  // Check auth status
+  // Check if credentials exist (keyring has tokens)
+  // Username is stored in config.json (non-sensitive metadata)
+  const credsFileExists = existsSync(getCredentialsFilePath());
+  try {
+    const creds = await getStoredCredentials();
+    if (creds) {
+      status.auth.loggedIn = true;
+      // Username is stored in config as non-sensitive metadata
+      status.auth.username = config.username || creds.username || null;
+    }
+  } catch (error) {
+    // Fallback: if keyring fails but file exists, check config for username
+    if (credsFileExists && config.username) {
+      status.auth.loggedIn = true;
+      status.auth.username = config.username;
+    } else {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn(`Failed to retrieve stored credentials: ${message}`);
+    }
+  }
+
+  return status;
+}
+
 export default registerStatusCommand;
@@ -0,0 +1,78 @@
+/**
+ * Proton Drive WebDAV Bridge - Trash CLI Command
+ *
+ * Lists, restores and empties the Proton Drive trash.
+ */
+
+import { Command } from 'commander';
+import { driveClient } from '../drive.js';
+import { logger } from '../logger.js';
+import { toAppError } from '../utils/error.js';
+
+export function registerTrashCommand(program: Command): void {
+  const trashCmd = program.command('trash').description('Manage the Proton Drive trash');
+
+  // List subcommand
+  trashCmd
+    .command('list')
+    .description('List trashed files and folders')
+    .option('-j, --json', 'Output as JSON')
+    .action(async (options) => {
+      try {
+        await driveClient.initialize();
+        const items = await driveClient.listTrash();
+
+        if (options.json) {
+          console.log(JSON.stringify(items, null, 2));
+        } else if (items.length === 0) {
+          console.log('The trash is empty.');
+        } else {
+          for (const item of items) {
+            const kind = item.type === 'folder' ? 'folder' : `${item.size} bytes`;
+            console.log(`${item.uid}  ${item.name} (${kind})`);
+          }
+        }
+      } catch (error) {
+        const appError = toAppError(error);
+        console.error(`✗ Failed to list trash: ${appError.getPublicMessage()}`);
+        logger.error(`Failed to list trash: [${appError.code}] ${appError.message}`);
+        process.exit(1);
+      }
+    });
+
+  // Restore subcommand
+  trashCmd
+    .command('restore')
+    .description('Restore trashed items to their original folders')
+    .argument('<uids...>', 'UIDs of the items to restore')
+    .action(async (uids: string[]) => {
+      try {
+        await driveClient.initialize();
+        await driveClient.restoreNodes(uids);
+        console.log(`✓ Restored ${uids.length} item(s).`);
+      } catch (error) {
+        const appError = toAppError(error);
+        console.error(`✗ Restore failed: ${appError.getPublicMessage()}`);
+        logger.error(`Restore failed: [${appError.code}] ${appError.message}`);
+        process.exit(1);
+      }
+    });
+
+  // Empty subcommand
+  trashCmd
+    .command('empty')
+    .description('Permanently delete everything in the trash')
+    .action(async () => {
+      try {
+        await driveClient.initialize();
+        await driveClient.emptyTrash();
+        console.log('✓ Trash emptied.');
+        logger.info('Trash emptied');
+      } catch (error) {
+        const appError = toAppError(error);
+        console.error(`✗ Emptying trash failed: ${appError.getPublicMessage()}`);
+        logger.error(`Emptying trash failed: [${appError.code}] ${appError.message}`);
+        process.exit(1);
+      }
+    });
+}
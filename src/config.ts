@@ -5,7 +5,7 @@
  * mount paths, and security options.
  */
 
-import { readFileSync, writeFileSync, existsSync, watchFile, unwatchFile, mkdirSync } from 'fs';
+import { readFileSync, writeFileSync, renameSync, existsSync, watchFile, unwatchFile, mkdirSync } from 'fs';
 import { dirname } from 'path';
 import { getConfigFilePath } from './paths.js';
 import { logger } from './logger.js';
@@ -43,6 +43,8 @@ export interface CacheConfig {
   ttlSeconds: number;
   /** Maximum cache size in MB */
   maxSizeMB: number;
+  /** Scheduled purge interval set by the desktop app (e.g. "daily", "every 6h") */
+  purgeSchedule?: string;
 }
 
 export interface Config {
@@ -58,6 +60,8 @@ export interface Config {
   autoStart: boolean;
   /** Logged-in Proton account username/email (non-sensitive metadata) */
   username?: string;
+  /** Layout version, migrated by the desktop app */
+  configVersion?: number;
 }
 
 // ============================================================================
@@ -138,7 +142,10 @@ export function saveConfig(config: Config): void {
     // between getConfigDir() and the computed config path.
     const dir = dirname(configPath);
     mkdirSync(dir, { recursive: true });
-    writeFileSync(configPath, JSON.stringify(config, null, 2), { mode: 0o600 });
+    // Replace the file in one step so the desktop app never reads half of it
+    const tempPath = `${configPath}.tmp`;
+    writeFileSync(tempPath, JSON.stringify(config, null, 2), { mode: 0o600 });
+    renameSync(tempPath, configPath);
     currentConfig = config;
     logger.debug(`Saved config to ${configPath}`);
   } catch (error) {
@@ -121,6 +121,43 @@ export interface DeleteResult {
   error?: unknown;
 }
 
+export interface ShareLink {
+  /** UID of the shared node; a node has at most one public link */
+  id: string;
+  url: string;
+  expirationTime: Date | null;
+  hasPassword: boolean;
+}
+
+export interface ShareLinkOptions {
+  expiration?: Date;
+  password?: string;
+}
+
+interface ShareNodeSettings {
+  publicLink: {
+    role: 'viewer';
+    customPassword?: string;
+    expiration?: Date;
+  };
+}
+
+interface ShareResult {
+  publicLink?: {
+    uid: string;
+    url: string;
+    expirationTime?: Date;
+  };
+}
+
+export interface TrashedNode {
+  uid: string;
+  name: string;
+  type: 'file' | 'folder';
+  size: number;
+  trashedTime: Date | null;
+}
+
 export interface UploadMetadata {
   mediaType: string;
   expectedSize: number;
@@ -213,6 +250,11 @@ export interface ProtonDriveClient {
   ): Promise<FileRevisionUploader>;
   trashNodes(nodeUids: string[]): AsyncIterable<DeleteResult>;
   deleteNodes(nodeUids: string[]): AsyncIterable<DeleteResult>;
+  iterateTrashedNodes(signal?: AbortSignal): AsyncIterable<MaybeNode>;
+  restoreNodes(nodeUids: string[]): AsyncIterable<DeleteResult>;
+  emptyTrash(): Promise<void>;
+  shareNode(nodeUid: string, settings: ShareNodeSettings): Promise<ShareResult>;
+  unshareNode(nodeUid: string, settings: { publicLink: 'remove' }): Promise<unknown>;
   renameNode(nodeUid: string, newName: string): Promise<NodeResult>;
   moveNodes(
     nodeUids: string[],
@@ -1703,6 +1745,89 @@ export class DriveClientManager {
       }
     }
   }
+
+  // ==========================================================================
+  // Sharing
+  // ==========================================================================
+
+  /**
+   * Create (or update) the public link of a node
+   */
+  async createShareLink(nodeUid: string, options: ShareLinkOptions = {}): Promise<ShareLink> {
+    const client = this.getClient();
+    const result = await client.shareNode(nodeUid, {
+      publicLink: {
+        role: 'viewer',
+        customPassword: options.password,
+        expiration: options.expiration,
+      },
+    });
+    if (!result.publicLink) {
+      throw new ApiError('Failed to share: no public link returned', 502);
+    }
+    return {
+      id: nodeUid,
+      url: result.publicLink.url,
+      expirationTime: result.publicLink.expirationTime ?? null,
+      hasPassword: !!options.password,
+    };
+  }
+
+  /**
+   * Remove the public link of a node
+   */
+  async revokeShareLink(nodeUid: string): Promise<void> {
+    await this.getClient().unshareNode(nodeUid, { publicLink: 'remove' });
+  }
+
+  // ==========================================================================
+  // Trash
+  // ==========================================================================
+
+  /**
+   * List the nodes in the trash
+   */
+  async listTrash(): Promise<TrashedNode[]> {
+    const client = this.getClient();
+    const nodes: TrashedNode[] = [];
+    for await (const maybeNode of client.iterateTrashedNodes()) {
+      const { node } = getNodeEntity(maybeNode);
+      if (typeof node.uid !== 'string') continue;
+      const isFolder = node.type === 'folder';
+      nodes.push({
+        uid: node.uid,
+        name: normalizeNodeName(node.name as unknown),
+        type: isFolder ? 'folder' : 'file',
+        size: isFolder ? 0 : (extractClaimedSize(node.activeRevision as unknown) ?? 0),
+        trashedTime: node.trashTime ?? null,
+      });
+    }
+    return nodes;
+  }
+
+  /**
+   * Restore trashed nodes to their original folders
+   */
+  async restoreNodes(nodeUids: string[]): Promise<void> {
+    const client = this.getClient();
+    for await (const result of client.restoreNodes(nodeUids)) {
+      if (!result.ok) {
+        throw new ApiError(
+          `Failed to restore: ${String(result.error)}`,
+          502,
+          undefined,
+          result.error
+        );
+      }
+    }
+  }
+
+  /**
+   * Permanently delete everything in the trash
+   */
+  async emptyTrash(): Promise<void> {
+    await this.getClient().emptyTrash();
+  }
 }
 
 // Singleton instance
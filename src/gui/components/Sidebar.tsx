@@ -1,7 +1,6 @@
 import { useState, useEffect } from 'react';
 import * as Mie from '@mielo-ui/mielo-react';
 import type { UnlistenFn } from '@tauri-apps/api/event';
-import { enable as autostartEnable, isEnabled as autostartIsEnabled, disable as autostartDisable } from '@tauri-apps/plugin-autostart';
 import { useTauri } from '../tauri/TauriProvider';
 
 interface SidebarProps {
@@ -68,13 +67,9 @@ export function Sidebar({ onViewChange: _onViewChange, onAccountSelect, opened =
     }
 
     // Autostart probe
-    autostartIsEnabled()
-      .then((enabled) => setAutostartEnabled(enabled))
-      .catch(() => {
-        invokeFn('get_autostart')
-          .then((enabled: any) => setAutostartEnabled(!!enabled))
-          .catch(() => setAutostartEnabled(false));
-      });
+    invokeFn('get_autostart')
+      .then((enabled: any) => setAutostartEnabled(!!enabled))
+      .catch(() => setAutostartEnabled(false));
 
     return () => {
       if (unlisten) unlisten();
@@ -84,15 +79,9 @@ export function Sidebar({ onViewChange: _onViewChange, onAccountSelect, opened =
 
   const handleAutostartToggle = async (checked: boolean) => {
     try {
-      if (checked) {
-        await autostartEnable();
-      } else {
-        await autostartDisable();
-      }
-      
-      // Persist preference
-      await invokeFn('set_autostart', { enabled: checked });
-      setAutostartEnabled(checked);
+      // The backend creates or removes the login entry
+      const applied = await invokeFn('set_autostart', { enabled: checked });
+      setAutostartEnabled(!!applied);
     } catch (err) {
       console.error('Autostart toggle failed:', err);
       setAutostartEnabled(!checked); // Revert on error
@@ -1,14 +1,9 @@
 import { useState, useEffect, useCallback } from 'react';
-import {
-  enable as autostartEnable,
-  isEnabled as autostartIsEnabled,
-  disable as autostartDisable,
-} from '@tauri-apps/plugin-autostart';
 import { useTauri } from '../tauri/TauriProvider.js';
 
 /**
  * Hook for managing system autostart
- * Handles both plugin API and backend persistence
+ * The backend creates or removes the login entry and reports its real state
  */
 export function useAutostart() {
   const { invoke } = useTauri();
@@ -22,17 +17,6 @@ export function useAutostart() {
       try {
         setIsLoading(true);
 
-        // Try native plugin first
-        try {
-          const enabled = await autostartIsEnabled();
-          setIsEnabled(enabled);
-          setError(null);
-          return;
-        } catch (pluginErr) {
-          console.debug('Autostart plugin unavailable, using backend persistence:', pluginErr);
-        }
-
-        // Fallback: backend persistence
         try {
           const persisted = await invoke<boolean>('get_autostart');
           setIsEnabled(!!persisted);
@@ -52,7 +36,7 @@ export function useAutostart() {
   }, [invoke]);
 
   /**
-   * Toggle autostart with system API and backend fallback
+   * Toggle autostart; the backend returns the state it ended up in
    */
   const setAutostart = useCallback(
     async (enabled: boolean) => {
@@ -61,29 +45,17 @@ export function useAutostart() {
       try {
         setIsLoading(true);
 
-        // Apply system autostart
-        try {
-          if (enabled) {
-            await autostartEnable();
-          } else {
-            await autostartDisable();
-          }
-        } catch (pluginErr) {
-          console.debug('Autostart plugin unavailable, using backend only:', pluginErr);
-        }
-
-        // Persist to backend
+        let applied: boolean;
         try {
-          await invoke('set_autostart', { enabled });
+          applied = await invoke<boolean>('set_autostart', { enabled });
         } catch (err) {
-          console.error('Failed to persist autostart state:', err);
-          // Revert UI if persistence fails
+          console.error('Failed to update autostart:', err);
+          // Revert UI if the login entry could not be changed
           setIsEnabled(prevState);
           throw err;
         }
 
-        // Success
-        setIsEnabled(enabled);
+        setIsEnabled(applied);
         setError(null);
       } catch (err) {
         const error = err instanceof Error ? err : new Error(String(err));
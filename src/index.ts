@@ -13,6 +13,9 @@ import { registerStartCommand } from './cli/start.js';
 import { registerStopCommand } from './cli/stop.js';
 import { registerStatusCommand } from './cli/status.js';
 import { registerConfigCommand } from './cli/config.js';
+import { registerTrashCommand } from './cli/trash.js';
+import { registerShareCommand } from './cli/share.js';
+import { registerRpcCommand } from './cli/rpc.js';
 import { loadConfig } from './config.js';
 import { setDebugMode } from './logger.js';
 
@@ -39,6 +42,9 @@ export function buildProgram() {
   registerStopCommand(program);
   registerStatusCommand(program);
   registerConfigCommand(program);
+  registerTrashCommand(program);
+  registerShareCommand(program);
+  registerRpcCommand(program);
 
   return program;
 }
@@ -18,14 +18,13 @@ import { Entry } from '@napi-rs/keyring';
 import { readFileSync, writeFileSync, unlinkSync, existsSync } from 'fs';
 import { randomBytes, createCipheriv, createDecipheriv, pbkdf2Sync } from 'crypto';
 import { logger } from './logger.js';
-import { getCredentialsFilePath } from './paths.js';
+import { getAccountProfile, getCredentialsFilePath } from './paths.js';
 
 // ============================================================================
 // Constants
 // ============================================================================
 
 const SERVICE_NAME = 'proton-drive-webdav-bridge';
-const ACCOUNT_NAME = 'proton-drive-webdav-bridge:credentials';
 const DEFAULT_KEYRING_PASSWORD = 'proton-drive-webdav-bridge-default';
 
 // Encryption constants
@@ -35,6 +34,16 @@ const KEY_LENGTH = 32; // AES-256
 const PBKDF2_ITERATIONS = 100000;
 const AUTH_TAG_LENGTH = 16;
 
+/**
+ * Keyring account holding the credentials of the active account profile.
+ * The default profile keeps the original entry name.
+ */
+function keyringAccountName(): string {
+  const profile = getAccountProfile();
+  const base = 'proton-drive-webdav-bridge:credentials';
+  return profile ? `${base}:${profile}` : base;
+}
+
 // ============================================================================
 // Types
 // ============================================================================
@@ -203,12 +212,13 @@ function deleteCredentialsFile(): void {
  * Store credentials using native keyring
  */
 async function storeCredentialsToKeyring(credentials: StoredCredentials): Promise<void> {
-  const entry = new Entry(SERVICE_NAME, ACCOUNT_NAME);
+  const account = keyringAccountName();
+  const entry = new Entry(SERVICE_NAME, account);
   const jsonData = JSON.stringify(credentials);
   try {
     entry.setPassword(jsonData);
     logger.info(
-      `Stored credentials to native keyring (service: ${SERVICE_NAME}, account: ${ACCOUNT_NAME})`
+      `Stored credentials to native keyring (service: ${SERVICE_NAME}, account: ${account})`
     );
   } catch (error) {
     logger.error(`Failed to store credentials to keyring: ${error}`);
@@ -221,7 +231,7 @@ async function storeCredentialsToKeyring(credentials: StoredCredentials): Promis
  */
 function getCredentialsFromKeyring(): StoredCredentials | null {
   try {
-    const entry = new Entry(SERVICE_NAME, ACCOUNT_NAME);
+    const entry = new Entry(SERVICE_NAME, keyringAccountName());
     const password = entry.getPassword();
     if (!password) {
       logger.debug('No credentials found in keyring');
@@ -244,7 +254,7 @@ function getCredentialsFromKeyring(): StoredCredentials | null {
  */
 function deleteCredentialsFromKeyring(): void {
   try {
-    const entry = new Entry(SERVICE_NAME, ACCOUNT_NAME);
+    const entry = new Entry(SERVICE_NAME, keyringAccountName());
     entry.deletePassword();
     logger.debug('Deleted credentials from native keyring');
   } catch (error) {
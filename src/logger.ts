@@ -4,6 +4,7 @@
  * Winston-based logging with console and optional rotating file transports.
  */
 
+import { AsyncLocalStorage } from 'async_hooks';
 import winston from 'winston';
 import DailyRotateFile from 'winston-daily-rotate-file';
 import { join } from 'path';
@@ -24,6 +25,44 @@ try {
   console.error(`Failed to create log directory: ${error}`);
 }
 
+// ============================================================================
+// Trace IDs
+// ============================================================================
+
+/** Set by the desktop app so a command's log lines can be matched to its own */
+const TRACE_ENV = 'PROTON_DRIVE_BRIDGE_TRACE_ID';
+
+const traceContext = new AsyncLocalStorage<string>();
+
+/**
+ * Trace ID of the current operation: the one `withTraceId` set, else the
+ * one this process was started with
+ */
+export function currentTraceId(): string | undefined {
+  return traceContext.getStore() ?? (process.env[TRACE_ENV] || undefined);
+}
+
+/**
+ * Run `fn` with log lines tagged with `traceId`
+ */
+export function withTraceId<T>(traceId: string | undefined, fn: () => T): T {
+  return traceId ? traceContext.run(traceId, fn) : fn();
+}
+
+/**
+ * Stop tagging lines with the trace ID from the environment, for processes
+ * serving several operations
+ */
+export function clearProcessTraceId(): void {
+  delete process.env[TRACE_ENV];
+}
+
+const traceTag = winston.format((info) => {
+  const traceId = currentTraceId();
+  if (traceId) info.traceId = traceId;
+  return info;
+});
+
 // ============================================================================
 // Log Format
 // ============================================================================
@@ -31,11 +70,12 @@ try {
 const logFormat = winston.format.combine(
   winston.format.timestamp({ format: 'YYYY-MM-DD HH:mm:ss' }),
   winston.format.errors({ stack: true }),
-  winston.format.printf(({ level, message, timestamp, stack }) => {
+  winston.format.printf(({ level, message, timestamp, stack, traceId }) => {
+    const trace = traceId ? ` [trace:${traceId}]` : '';
     if (stack) {
-      return `${timestamp} [${level.toUpperCase()}] ${message}\n${stack}`;
+      return `${timestamp} [${level.toUpperCase()}]${trace} ${message}\n${stack}`;
     }
-    return `${timestamp} [${level.toUpperCase()}] ${message}`;
+    return `${timestamp} [${level.toUpperCase()}]${trace} ${message}`;
   })
 );
 
@@ -89,6 +129,7 @@ if (!isTestEnv) {
 
 export const logger = winston.createLogger({
   level: 'debug',
+  format: traceTag(),
   transports,
 });
 
@@ -107,6 +148,13 @@ export function setDebugMode(enabled: boolean): void {
   logger.info(`Debug mode ${enabled ? 'enabled' : 'disabled'}`);
 }
 
+/**
+ * Stop logging to the console, for commands whose stdout is a protocol
+ */
+export function disableConsoleLogging(): void {
+  consoleTransport.silent = true;
+}
+
 /**
  * Check if debug mode is enabled
  */
@@ -53,14 +53,27 @@ export function getConfigDir(): string {
   return ensureDir(paths.config);
 }
 
+/**
+ * Get the account profile selected by the desktop app through the
+ * PROTON_DRIVE_BRIDGE_ACCOUNT environment variable. Unset for the default
+ * (first) account, whose data stays at the top level of the data directory.
+ */
+export function getAccountProfile(): string | undefined {
+  const profile = process.env.PROTON_DRIVE_BRIDGE_ACCOUNT?.trim();
+  return profile || undefined;
+}
+
 /**
  * Get the data directory path (for databases, cache, etc.)
  * - Linux: ~/.local/share/proton-drive-webdav-bridge
  * - macOS: ~/Library/Application Support/proton-drive-webdav-bridge
  * - Windows: %LOCALAPPDATA%/proton-drive-webdav-bridge
+ *
+ * Other account profiles get their own `accounts/<profile>` subdirectory.
  */
 export function getDataDir(): string {
-  return ensureDir(paths.data);
+  const profile = getAccountProfile();
+  return ensureDir(profile && paths.data ? join(paths.data, 'accounts', profile) : paths.data);
 }
 
 /**
@@ -110,6 +123,13 @@ export function getPidFilePath(): string {
   return join(getRuntimeDir(), 'bridge.pid');
 }
 
+/**
+ * Get the path to the file whose changes tell a running server to drop its caches
+ */
+export function getCachePurgeFilePath(): string {
+  return join(getRuntimeDir(), 'cache-purge');
+}
+
 /**
  * Get the path to the main log file
  */
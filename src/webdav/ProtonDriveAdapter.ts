@@ -237,6 +237,15 @@ export default class ProtonDriveAdapter implements AdapterInterface {
     return fetchPromise;
   }
 
+  /**
+   * Drop every cached folder listing and resolved path
+   */
+  clearCaches(): void {
+    this.folderCache.clear();
+    this.pathCache.clear();
+    logger.info('Cleared folder and path caches');
+  }
+
   /**
    * Invalidate cache for a specific folder and all paths containing it
    */
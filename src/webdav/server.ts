@@ -8,11 +8,12 @@
 import express from 'express';
 import { createServer as createHttpServer, type Server as HttpServer } from 'http';
 import { createServer as createHttpsServer, type Server as HttpsServer } from 'https';
-import { readFileSync } from 'fs';
+import { existsSync, lstatSync, readFileSync, unlinkSync, unwatchFile, watchFile } from 'fs';
 import { createHash } from 'crypto';
 import nepheleServer, { ResourceNotFoundError } from 'nephele';
 import { logger } from '../logger.js';
 import { getConfig } from '../config.js';
+import { getCachePurgeFilePath } from '../paths.js';
 import { driveClient } from '../drive.js';
 import ProtonDriveAdapter from './ProtonDriveAdapter.js';
 import ProtonDriveAuthenticator from './ProtonDriveAuthenticator.js';
@@ -31,6 +32,8 @@ interface WebDAVServerOptions {
   https?: boolean;
   certPath?: string;
   keyPath?: string;
+  /** Unix socket to listen on instead of host/port; `@name` is an abstract socket */
+  socketPath?: string;
 }
 
 // ============================================================================
@@ -86,6 +89,7 @@ export function getWebDAVServer(): WebDAVServer | null {
 export class WebDAVServer {
   private app: express.Application;
   private httpServer: HttpServer | HttpsServer | null = null;
+  private adapter: ProtonDriveAdapter;
   private options: Required<WebDAVServerOptions>;
 
   constructor(options: WebDAVServerOptions = {}) {
@@ -100,6 +104,7 @@ export class WebDAVServer {
       https: options.https ?? config.webdav.https,
       certPath: options.certPath ?? config.webdav.certPath ?? '',
       keyPath: options.keyPath ?? config.webdav.keyPath ?? '',
+      socketPath: options.socketPath ?? '',
     };
 
     this.app = express();
@@ -345,6 +350,7 @@ export class WebDAVServer {
     const sharedAdapter = new ProtonDriveAdapter({
       cacheTTL: cacheCfg.enabled ? cacheCfg.ttlSeconds * 1000 : 0,
     });
+    this.adapter = sharedAdapter;
     logger.debug(
       `Shared adapter created with cache enabled=${cacheCfg.enabled} ttl=${cacheCfg.ttlSeconds}s`
     );
@@ -412,17 +418,32 @@ export class WebDAVServer {
     }
 
     // Start listening
+    const socketPath = this.options.socketPath;
+    if (socketPath) {
+      removeStaleSocket(socketPath);
+    }
     await new Promise<void>((resolve) => {
-      if (this.httpServer) {
-        this.httpServer.listen(this.options.port, this.options.host, () => {
-          logger.info(`WebDAV server started on ${this.getUrl()}`);
-          resolve();
-        });
+      if (!this.httpServer) return;
+      const onListening = () => {
+        logger.info(`WebDAV server started on ${socketPath || this.getUrl()}`);
+        resolve();
+      };
+      if (socketPath) {
+        const address = socketPath.startsWith('@') ? `\0${socketPath.slice(1)}` : socketPath;
+        this.httpServer.listen(address, onListening);
+      } else {
+        this.httpServer.listen(this.options.port, this.options.host, onListening);
       }
     });
+
+    // `cache.purge` requests from other processes (see cli/rpc.ts) touch this file
+    watchFile(getCachePurgeFilePath(), { interval: 1000, persistent: false }, (curr, prev) => {
+      if (curr.mtimeMs !== prev.mtimeMs) this.adapter.clearCaches();
+    });
   }
 
   async stop(): Promise<void> {
+    unwatchFile(getCachePurgeFilePath());
     if (this.httpServer) {
       await new Promise<void>((resolve) => {
         if (this.httpServer) {
@@ -447,8 +468,21 @@ export class WebDAVServer {
 
   getUrl(): string {
     const protocol = this.options.https ? 'https' : 'http';
+    // Requests over a socket carry whatever authority the client chose
+    if (this.options.socketPath) return `${protocol}://localhost`;
     return `${protocol}://${this.options.host}:${this.options.port}`;
   }
 }
 
+/**
+ * Remove a socket file left behind by a server that did not shut down cleanly
+ */
+function removeStaleSocket(socketPath: string): void {
+  if (socketPath.startsWith('@') || !existsSync(socketPath)) return;
+  if (!lstatSync(socketPath).isSocket()) {
+    throw new Error(`${socketPath} exists and is not a socket`);
+  }
+  unlinkSync(socketPath);
+}
+
 export default WebDAVServer;
@@ -0,0 +1,79 @@
+/**
+ * Shared helpers for CLI command tests
+ *
+ * Builds a commander program with the commands under test, captures what the
+ * commands print, and wires a DriveClientManager to a mocked Drive client.
+ */
+
+import { Command } from 'commander';
+import { DriveClientManager } from '../../src/drive.js';
+import type { ProtonDriveClient } from '../../src/drive.js';
+
+// ============================================================================
+// Program
+// ============================================================================
+
+/**
+ * Create a program with the given commands registered
+ *
+ * Commander errors throw instead of exiting the test runner.
+ *
+ * @example
+ * await createProgram(registerTrashCommand).parseAsync(['trash', 'list'], { from: 'user' });
+ */
+export function createProgram(...registers: Array<(program: Command) => void>): Command {
+  const program = new Command();
+  program.exitOverride();
+  for (const register of registers) {
+    register(program);
+  }
+  return program;
+}
+
+// ============================================================================
+// Output
+// ============================================================================
+
+/**
+ * Capture console output and turn `process.exit` into a thrown `exit:<code>`
+ *
+ * Call `restore` in a `finally` block once the command has run.
+ */
+export function captureOutput() {
+  const originalLog = console.log;
+  const originalError = console.error;
+  const originalExit = process.exit;
+  const logs: string[] = [];
+  const errors: string[] = [];
+
+  console.log = ((message?: unknown, ...args: unknown[]) => {
+    logs.push([message, ...args].map((val) => String(val)).join(' '));
+  }) as typeof console.log;
+  console.error = ((message?: unknown, ...args: unknown[]) => {
+    errors.push([message, ...args].map((val) => String(val)).join(' '));
+  }) as typeof console.error;
+  process.exit = ((code?: number) => {
+    throw new Error(`exit:${code ?? 0}`);
+  }) as never;
+
+  return {
+    logs,
+    errors,
+    restore: () => {
+      console.log = originalLog;
+      console.error = originalError;
+      process.exit = originalExit;
+    },
+  };
+}
+
+// ============================================================================
+// Drive Client
+// ============================================================================
+
+/** A DriveClientManager backed by the given mocked Drive client methods */
+export function withClient(client: Record<string, unknown>): DriveClientManager {
+  const manager = new DriveClientManager();
+  (manager as unknown as Record<string, unknown>).client = client as unknown as ProtonDriveClient;
+  return manager;
+}
@@ -8,7 +8,7 @@ import { afterEach, beforeEach, describe, expect, mock, test } from 'bun:test';
 import { mkdtempSync, rmSync } from 'fs';
 import { tmpdir } from 'os';
 import { join } from 'path';
-import { logger, setDebugMode } from '../src/logger.js';
+import { currentTraceId, logger, setDebugMode, withTraceId } from '../src/logger.js';
 
 const DEFAULT_PATHS_BASE = join(tmpdir(), 'pdb-logger-default');
 let pathsBase = DEFAULT_PATHS_BASE;
@@ -41,6 +41,21 @@ describe('Logger - Instance Methods', () => {
   });
 });
 
+describe('Logger - Trace IDs', () => {
+  test('should scope the trace ID to the callback', async () => {
+    const inner = await withTraceId('a1b2c3', async () => {
+      await Promise.resolve();
+      return currentTraceId();
+    });
+    expect(inner).toBe('a1b2c3');
+    expect(currentTraceId()).toBe(process.env.PROTON_DRIVE_BRIDGE_TRACE_ID || undefined);
+  });
+
+  test('should log inside a trace without throwing', () => {
+    expect(() => withTraceId('a1b2c3', () => logger.info('Traced message'))).not.toThrow();
+  });
+});
+
 describe('Logger - Logging Operations', () => {
   test('should log info messages without throwing', () => {
     expect(() => logger.info('Test info message')).not.toThrow();
@@ -227,3 +227,31 @@ describe('Paths - Platform Compliance', () => {
     }
   });
 });
+
+describe('Paths - Account Profiles', () => {
+  beforeEach(() => {
+    tempBase = mkdtempSync(join(tmpdir(), 'pdb-paths-'));
+  });
+
+  afterEach(() => {
+    delete process.env.PROTON_DRIVE_BRIDGE_ACCOUNT;
+    rmSync(tempBase, { recursive: true, force: true });
+  });
+
+  test('default profile keeps the top-level data directory', async () => {
+    const { getAccountProfile, getDataDir } = await loadPaths();
+    expect(getAccountProfile()).toBeUndefined();
+    expect(getDataDir()).toBe(join(tempBase, 'data', 'proton-drive-webdav-bridge'));
+  });
+
+  test('other profiles get their own data directory', async () => {
+    process.env.PROTON_DRIVE_BRIDGE_ACCOUNT = 'work-example-com';
+    const { getAccountProfile, getDataDir } = await loadPaths();
+    expect(getAccountProfile()).toBe('work-example-com');
+    const dataDir = getDataDir();
+    expect(dataDir).toBe(
+      join(tempBase, 'data', 'proton-drive-webdav-bridge', 'accounts', 'work-example-com')
+    );
+    expect(existsSync(dataDir)).toBe(true);
+  });
+});
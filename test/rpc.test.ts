@@ -0,0 +1,254 @@
+/**
+ * Unit Tests - RPC Command
+ *
+ * Drives the `rpc` command with requests on a fake stdin and checks the
+ * JSON-RPC responses it writes to stdout: one message per line, error
+ * responses, and what happens when the app or the keychain goes away.
+ */
+
+import { afterEach, describe, expect, mock, spyOn, test } from 'bun:test';
+import { Readable } from 'stream';
+import {
+  createMockState,
+  createKeychainMocks,
+  createConfigMocks,
+  resetTestHelpers,
+} from './helpers/mocks.js';
+import { captureOutput, createProgram } from './helpers/cli.js';
+
+const mockState = createMockState();
+const keychainMocks = createKeychainMocks(mockState);
+const configMocks = createConfigMocks(mockState);
+
+mock.module('../src/keychain.js', () => keychainMocks);
+mock.module('../src/config.js', () => configMocks);
+
+// Use real modules
+import { registerRpcCommand } from '../src/cli/rpc.js';
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+type Response = {
+  jsonrpc: string;
+  id: number | string | null;
+  result?: unknown;
+  error?: { code: number; message: string; data?: { code: string } };
+};
+
+/**
+ * Serve the given stdin chunks until stdin closes, returning the responses
+ * and how the command exited
+ */
+const serve = async (chunks: string[]) => {
+  const writes: string[] = [];
+  const stdin = Object.getOwnPropertyDescriptor(process, 'stdin')!;
+  Object.defineProperty(process, 'stdin', { value: Readable.from(chunks), configurable: true });
+  const write = spyOn(process.stdout, 'write').mockImplementation(((chunk: unknown) => {
+    writes.push(String(chunk));
+    return true;
+  }) as typeof process.stdout.write);
+
+  const output = captureOutput();
+  let exit = '';
+  try {
+    await createProgram(registerRpcCommand).parseAsync(['rpc'], { from: 'user' });
+  } catch (error) {
+    exit = String(error);
+  } finally {
+    output.restore();
+    write.mockRestore();
+    Object.defineProperty(process, 'stdin', stdin);
+  }
+
+  const responses = writes
+    .join('')
+    .split('\n')
+    .filter(Boolean)
+    .map((line) => JSON.parse(line) as Response);
+  return { responses, writes, exit };
+};
+
+const request = (id: number | string | undefined, method: string, params?: unknown) =>
+  `${JSON.stringify({ jsonrpc: '2.0', id, method, params })}\n`;
+
+afterEach(() => {
+  resetTestHelpers(mockState);
+  keychainMocks.deleteStoredCredentials.mockClear();
+});
+
+// ============================================================================
+// Framing
+// ============================================================================
+
+describe('RPC - Framing', () => {
+  test('answers each request on its own line, in order', async () => {
+    const { responses, writes } = await serve([
+      request(1, 'auth.logout') + request('two', 'config.get'),
+    ]);
+
+    expect(writes.every((chunk) => chunk.endsWith('\n'))).toBe(true);
+    expect(responses.map((response) => response.id)).toEqual([1, 'two']);
+    expect(responses[0]).toEqual({ jsonrpc: '2.0', id: 1, result: { loggedOut: false } });
+  });
+
+  test('reassembles a request split across chunks', async () => {
+    const line = request(7, 'auth.logout');
+
+    const { responses } = await serve([line.slice(0, 10), line.slice(10, 25), line.slice(25)]);
+
+    expect(responses).toEqual([{ jsonrpc: '2.0', id: 7, result: { loggedOut: false } }]);
+  });
+
+  test('skips blank lines', async () => {
+    const { responses } = await serve(['\n', '   \n', request(1, 'auth.logout'), '\n']);
+
+    expect(responses.map((response) => response.id)).toEqual([1]);
+  });
+
+  test('does not answer notifications', async () => {
+    mockState.credentials = { username: 'testuser' };
+
+    const { responses } = await serve([
+      request(undefined, 'auth.logout'),
+      request(2, 'config.get'),
+    ]);
+
+    expect(keychainMocks.deleteStoredCredentials).toHaveBeenCalled();
+    expect(responses.map((response) => response.id)).toEqual([2]);
+  });
+
+  test('masks the WebDAV password hash in config.get', async () => {
+    mockState.config.webdav.passwordHash = 'secret-hash';
+
+    const { responses } = await serve([request(1, 'config.get')]);
+
+    const result = responses[0].result as { webdav: { passwordHash?: string; port: number } };
+    expect(result.webdav.passwordHash).toBe('****');
+    expect(result.webdav.port).toBe(8080);
+  });
+
+  test('reloads the config before every request', async () => {
+    configMocks.loadConfig.mockClear();
+
+    await serve([request(1, 'auth.logout') + request(2, 'auth.logout')]);
+
+    expect(configMocks.loadConfig).toHaveBeenCalledTimes(2);
+  });
+
+  test('logout keeps settings the app wrote since the process started', async () => {
+    mockState.credentials = { username: 'testuser' };
+    const written = { ...mockState.config, webdav: { ...mockState.config.webdav, port: 9191 } };
+    configMocks.loadConfig.mockImplementationOnce(() => {
+      mockState.config = written;
+      return written;
+    });
+
+    await serve([request(1, 'auth.logout')]);
+
+    expect(mockState.config.webdav.port).toBe(9191);
+    expect(mockState.config.username).toBeUndefined();
+  });
+
+  test('config.set updates the config', async () => {
+    const { responses } = await serve([
+      request(1, 'config.set', { key: 'webdav.port', value: '9090' }),
+    ]);
+
+    expect(responses[0]).toEqual({ jsonrpc: '2.0', id: 1, result: null });
+    expect(mockState.config.webdav.port).toBe(9090);
+  });
+});
+
+// ============================================================================
+// Error Responses
+// ============================================================================
+
+describe('RPC - Error Responses', () => {
+  test('malformed JSON is a parse error with a null id', async () => {
+    const { responses } = await serve(['{"jsonrpc": "2.0", "id": 1,\n']);
+
+    expect(responses).toEqual([
+      { jsonrpc: '2.0', id: null, error: { code: -32700, message: 'Parse error' } },
+    ]);
+  });
+
+  test('a request that is not an object is invalid', async () => {
+    const { responses } = await serve(['[1, 2]\n', '"status"\n']);
+
+    expect(responses.map((response) => response.error?.code)).toEqual([-32600, -32600]);
+  });
+
+  test('an invalid request keeps its id', async () => {
+    const line = JSON.stringify({ jsonrpc: '1.0', id: 3, method: 'status' });
+
+    const { responses } = await serve([`${line}\n`]);
+
+    expect(responses).toEqual([
+      { jsonrpc: '2.0', id: 3, error: { code: -32600, message: 'Invalid request' } },
+    ]);
+  });
+
+  test('an unknown method is not found', async () => {
+    const { responses } = await serve([request(1, 'toString'), request(2, 'drive.format')]);
+
+    expect(responses.map((response) => response.error)).toEqual([
+      { code: -32601, message: 'Method not found: toString' },
+      { code: -32601, message: 'Method not found: drive.format' },
+    ]);
+  });
+
+  test('a missing parameter is invalid params', async () => {
+    const { responses } = await serve([request(1, 'config.set', { key: 'webdav.port' })]);
+
+    expect(responses[0].error).toEqual({
+      code: -32602,
+      message: 'Missing string parameter: value',
+    });
+  });
+
+  test('a failing method is a server error that hides the details', async () => {
+    const { responses } = await serve([
+      request(1, 'config.set', { key: 'webdav.port.number', value: '1' }),
+    ]);
+
+    expect(responses[0].error).toEqual({
+      code: -32000,
+      message: 'Internal Server Error',
+      data: { code: 'UNKNOWN_ERROR' },
+    });
+  });
+});
+
+// ============================================================================
+// Connection Failure
+// ============================================================================
+
+describe('RPC - Connection Failure', () => {
+  test('exits cleanly when the app closes stdin', async () => {
+    const { responses, exit } = await serve([]);
+
+    expect(responses).toEqual([]);
+    expect(exit).toContain('exit:0');
+  });
+
+  test('answers a last request without a newline before exiting', async () => {
+    const { responses, exit } = await serve([request(1, 'auth.logout').trimEnd()]);
+
+    expect(responses.map((response) => response.id)).toEqual([1]);
+    expect(exit).toContain('exit:0');
+  });
+
+  test('keeps serving after the keychain fails', async () => {
+    keychainMocks.hasStoredCredentials.mockImplementationOnce(() =>
+      Promise.reject(new Error('Keychain unavailable'))
+    );
+
+    const { responses } = await serve([request(1, 'auth.logout'), request(2, 'auth.logout')]);
+
+    expect(responses[0].error?.code).toBe(-32000);
+    expect(responses[0].error?.data).toEqual({ code: 'UNKNOWN_ERROR' });
+    expect(responses[1]).toEqual({ jsonrpc: '2.0', id: 2, result: { loggedOut: false } });
+  });
+});
@@ -0,0 +1,200 @@
+/**
+ * Unit Tests - Share Links
+ *
+ * Tests DriveClientManager.createShareLink/revokeShareLink against a mocked
+ * Drive client, and the `share` CLI command wiring.
+ */
+
+import { afterEach, beforeEach, describe, expect, mock, spyOn, test } from 'bun:test';
+import { DriveClientManager, driveClient } from '../src/drive.js';
+import { registerShareCommand, SHARE_PASSWORD_ENV } from '../src/cli/share.js';
+import { captureOutput, createProgram, withClient } from './helpers/cli.js';
+
+// ============================================================================
+// DriveClientManager
+// ============================================================================
+
+describe('DriveClientManager - Share Links', () => {
+  test('createShareLink creates a viewer link with the given options', async () => {
+    const expiration = new Date(Date.now() + 3600_000);
+    const shareNode = mock(async () => ({
+      publicLink: { uid: 'link-1', url: 'https://drive.proton.me/urls/ABC#key', expirationTime: expiration },
+    }));
+    const manager = withClient({ shareNode });
+
+    const link = await manager.createShareLink('volume~node', { expiration, password: 'secret' });
+
+    expect(shareNode).toHaveBeenCalledWith('volume~node', {
+      publicLink: { role: 'viewer', customPassword: 'secret', expiration },
+    });
+    expect(link).toEqual({
+      id: 'volume~node',
+      url: 'https://drive.proton.me/urls/ABC#key',
+      expirationTime: expiration,
+      hasPassword: true,
+    });
+  });
+
+  test('createShareLink without options has no expiry or password', async () => {
+    const manager = withClient({
+      shareNode: async () => ({ publicLink: { uid: 'link-1', url: 'https://drive.proton.me/urls/ABC' } }),
+    });
+
+    const link = await manager.createShareLink('volume~node');
+
+    expect(link.expirationTime).toBeNull();
+    expect(link.hasPassword).toBe(false);
+  });
+
+  test('createShareLink fails when no link is returned', async () => {
+    const manager = withClient({ shareNode: async () => ({}) });
+
+    await expect(manager.createShareLink('volume~node')).rejects.toThrow('no public link returned');
+  });
+
+  test('revokeShareLink removes the public link', async () => {
+    const unshareNode = mock(async () => ({}));
+    const manager = withClient({ unshareNode });
+
+    await manager.revokeShareLink('volume~node');
+
+    expect(unshareNode).toHaveBeenCalledWith('volume~node', { publicLink: 'remove' });
+  });
+
+  test('share links need an initialized client', async () => {
+    const manager = new DriveClientManager();
+
+    await expect(manager.createShareLink('volume~node')).rejects.toThrow('Client not initialized');
+    await expect(manager.revokeShareLink('volume~node')).rejects.toThrow('Client not initialized');
+  });
+});
+
+// ============================================================================
+// CLI
+// ============================================================================
+
+describe('CLI - Share Command', () => {
+  beforeEach(() => {
+    spyOn(driveClient, 'initialize').mockResolvedValue(undefined);
+  });
+
+  afterEach(() => {
+    mock.restore();
+    delete process.env[SHARE_PASSWORD_ENV];
+  });
+
+  test('share create resolves the path and prints the link', async () => {
+    process.env[SHARE_PASSWORD_ENV] = 'secret';
+    const resolvePath = spyOn(driveClient, 'resolvePath').mockResolvedValue({
+      uid: 'volume~node',
+      type: 'file',
+    });
+    const createShareLink = spyOn(driveClient, 'createShareLink').mockResolvedValue({
+      id: 'volume~node',
+      url: 'https://drive.proton.me/urls/ABC#key',
+      expirationTime: null,
+      hasPassword: true,
+    });
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerShareCommand).parseAsync(
+        ['share', 'create', '/Docs/report.pdf'],
+        { from: 'user' }
+      );
+    } finally {
+      output.restore();
+    }
+
+    expect(resolvePath).toHaveBeenCalledWith('/Docs/report.pdf');
+    expect(createShareLink).toHaveBeenCalledWith('volume~node', {
+      expiration: undefined,
+      password: 'secret',
+    });
+    expect(output.logs.join('\n')).toContain('https://drive.proton.me/urls/ABC#key');
+  });
+
+  test('share create --json prints the link as JSON', async () => {
+    const expires = Math.floor(Date.now() / 1000) + 3600;
+    spyOn(driveClient, 'resolvePath').mockResolvedValue({ uid: 'volume~node', type: 'folder' });
+    const createShareLink = spyOn(driveClient, 'createShareLink').mockResolvedValue({
+      id: 'volume~node',
+      url: 'https://drive.proton.me/urls/ABC',
+      expirationTime: new Date(expires * 1000),
+      hasPassword: false,
+    });
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerShareCommand).parseAsync(
+        ['share', 'create', '/Docs', '--expires', String(expires), '--json'],
+        { from: 'user' }
+      );
+    } finally {
+      output.restore();
+    }
+
+    expect(createShareLink).toHaveBeenCalledWith('volume~node', {
+      expiration: new Date(expires * 1000),
+      password: undefined,
+    });
+    const parsed = JSON.parse(output.logs.join('\n')) as { id: string; hasPassword: boolean };
+    expect(parsed.id).toBe('volume~node');
+    expect(parsed.hasPassword).toBe(false);
+  });
+
+  test('share create fails when the node is not found', async () => {
+    spyOn(driveClient, 'resolvePath').mockResolvedValue(null);
+    const createShareLink = spyOn(driveClient, 'createShareLink');
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerShareCommand).parseAsync(['share', 'create', '/Missing'], {
+        from: 'user',
+      });
+      expect(true).toBe(false);
+    } catch (error) {
+      expect(String(error)).toContain('exit:1');
+    } finally {
+      output.restore();
+    }
+
+    expect(createShareLink).not.toHaveBeenCalled();
+    expect(output.errors.join('\n')).toContain('/Missing not found.');
+  });
+
+  test('share create rejects an expiry in the past', async () => {
+    const resolvePath = spyOn(driveClient, 'resolvePath');
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerShareCommand).parseAsync(
+        ['share', 'create', '/Docs', '--expires', '1'],
+        { from: 'user' }
+      );
+      expect(true).toBe(false);
+    } catch (error) {
+      expect(String(error)).toContain('exit:1');
+    } finally {
+      output.restore();
+    }
+
+    expect(resolvePath).not.toHaveBeenCalled();
+  });
+
+  test('share revoke removes the link', async () => {
+    const revokeShareLink = spyOn(driveClient, 'revokeShareLink').mockResolvedValue(undefined);
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerShareCommand).parseAsync(['share', 'revoke', 'volume~node'], {
+        from: 'user',
+      });
+    } finally {
+      output.restore();
+    }
+
+    expect(revokeShareLink).toHaveBeenCalledWith('volume~node');
+    expect(output.logs.join('\n')).toContain('Share link revoked');
+  });
+});
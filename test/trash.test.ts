@@ -0,0 +1,239 @@
+/**
+ * Unit Tests - Trash
+ *
+ * Tests DriveClientManager.listTrash/restoreNodes/emptyTrash against a
+ * mocked Drive client, and the `trash` CLI command wiring.
+ */
+
+import { afterEach, beforeEach, describe, expect, mock, spyOn, test } from 'bun:test';
+import { DriveClientManager, driveClient } from '../src/drive.js';
+import { registerTrashCommand } from '../src/cli/trash.js';
+import { captureOutput, createProgram, withClient } from './helpers/cli.js';
+
+// ============================================================================
+// Fixtures
+// ============================================================================
+
+const trashedTime = new Date('2026-01-02T03:04:05Z');
+
+const trashedNodes = [
+  {
+    ok: true,
+    value: {
+      uid: 'volume~file',
+      name: 'report.pdf',
+      type: 'file',
+      trashTime: trashedTime,
+      activeRevision: { claimedSize: 2048, storageSize: 4096 },
+    },
+  },
+  {
+    ok: true,
+    value: { uid: 'volume~folder', name: 'Old', type: 'folder', trashTime: trashedTime },
+  },
+  {
+    // Degraded node whose name could not be decrypted
+    ok: false,
+    error: {
+      uid: 'volume~degraded',
+      name: { ok: false, error: { message: 'decryption failed' } },
+      type: 'file',
+      activeRevision: { ok: true, value: { claimedSize: 7 } },
+    },
+  },
+  // Node without a uid is skipped
+  { ok: true, value: { name: 'ghost', type: 'file' } },
+];
+
+// ============================================================================
+// DriveClientManager
+// ============================================================================
+
+describe('DriveClientManager - Trash', () => {
+  test('listTrash returns trashed files and folders', async () => {
+    const manager = withClient({
+      iterateTrashedNodes: async function* () {
+        yield* trashedNodes;
+      },
+    });
+
+    const items = await manager.listTrash();
+
+    expect(items).toEqual([
+      { uid: 'volume~file', name: 'report.pdf', type: 'file', size: 2048, trashedTime },
+      { uid: 'volume~folder', name: 'Old', type: 'folder', size: 0, trashedTime },
+      { uid: 'volume~degraded', name: 'Undecryptable', type: 'file', size: 7, trashedTime: null },
+    ]);
+  });
+
+  test('listTrash of an empty trash is empty', async () => {
+    const manager = withClient({ iterateTrashedNodes: async function* () {} });
+
+    expect(await manager.listTrash()).toEqual([]);
+  });
+
+  test('restoreNodes restores every node', async () => {
+    const restored: string[] = [];
+    const manager = withClient({
+      restoreNodes: async function* (nodeUids: string[]) {
+        for (const uid of nodeUids) {
+          restored.push(uid);
+          yield { ok: true, value: { uid } };
+        }
+      },
+    });
+
+    await manager.restoreNodes(['volume~a', 'volume~b']);
+
+    expect(restored).toEqual(['volume~a', 'volume~b']);
+  });
+
+  test('restoreNodes fails when some nodes are not restored', async () => {
+    const manager = withClient({
+      restoreNodes: async function* () {
+        yield { ok: true, value: { uid: 'volume~a' } };
+        yield { ok: false, error: 'Parent folder was deleted' };
+        yield { ok: true, value: { uid: 'volume~c' } };
+      },
+    });
+
+    await expect(manager.restoreNodes(['volume~a', 'volume~b', 'volume~c'])).rejects.toThrow(
+      'Failed to restore: Parent folder was deleted'
+    );
+  });
+
+  test('emptyTrash empties the trash', async () => {
+    const emptyTrash = mock(async () => {});
+    const manager = withClient({ emptyTrash });
+
+    await manager.emptyTrash();
+
+    expect(emptyTrash).toHaveBeenCalledTimes(1);
+  });
+
+  test('trash needs an initialized client', async () => {
+    const manager = new DriveClientManager();
+
+    await expect(manager.listTrash()).rejects.toThrow('Client not initialized');
+    await expect(manager.restoreNodes(['volume~a'])).rejects.toThrow('Client not initialized');
+    await expect(manager.emptyTrash()).rejects.toThrow('Client not initialized');
+  });
+});
+
+// ============================================================================
+// CLI
+// ============================================================================
+
+describe('CLI - Trash Command', () => {
+  beforeEach(() => {
+    spyOn(driveClient, 'initialize').mockResolvedValue(undefined);
+  });
+
+  afterEach(() => {
+    mock.restore();
+  });
+
+  test('trash list prints the trashed items', async () => {
+    spyOn(driveClient, 'listTrash').mockResolvedValue([
+      { uid: 'volume~file', name: 'report.pdf', type: 'file', size: 2048, trashedTime },
+      { uid: 'volume~folder', name: 'Old', type: 'folder', size: 0, trashedTime },
+    ]);
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerTrashCommand).parseAsync(['trash', 'list'], { from: 'user' });
+    } finally {
+      output.restore();
+    }
+
+    expect(output.logs).toEqual(['volume~file  report.pdf (2048 bytes)', 'volume~folder  Old (folder)']);
+  });
+
+  test('trash list --json prints the items as JSON', async () => {
+    spyOn(driveClient, 'listTrash').mockResolvedValue([
+      { uid: 'volume~file', name: 'report.pdf', type: 'file', size: 2048, trashedTime: null },
+    ]);
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerTrashCommand).parseAsync(['trash', 'list', '--json'], {
+        from: 'user',
+      });
+    } finally {
+      output.restore();
+    }
+
+    const parsed = JSON.parse(output.logs.join('\n')) as Array<{ uid: string }>;
+    expect(parsed.map((item) => item.uid)).toEqual(['volume~file']);
+  });
+
+  test('trash list reports an empty trash', async () => {
+    spyOn(driveClient, 'listTrash').mockResolvedValue([]);
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerTrashCommand).parseAsync(['trash', 'list'], { from: 'user' });
+    } finally {
+      output.restore();
+    }
+
+    expect(output.logs.join('\n')).toContain('The trash is empty.');
+  });
+
+  test('trash restore restores the given items', async () => {
+    const restoreNodes = spyOn(driveClient, 'restoreNodes').mockResolvedValue(undefined);
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerTrashCommand).parseAsync(
+        ['trash', 'restore', 'volume~a', 'volume~b'],
+        { from: 'user' }
+      );
+    } finally {
+      output.restore();
+    }
+
+    expect(restoreNodes).toHaveBeenCalledWith(['volume~a', 'volume~b']);
+    expect(output.logs.join('\n')).toContain('Restored 2 item(s).');
+  });
+
+  test('trash restore exits with an error when some items fail', async () => {
+    const manager = withClient({
+      restoreNodes: async function* () {
+        yield { ok: true, value: { uid: 'volume~a' } };
+        yield { ok: false, error: 'Parent folder was deleted' };
+      },
+    });
+    spyOn(driveClient, 'restoreNodes').mockImplementation((uids) => manager.restoreNodes(uids));
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerTrashCommand).parseAsync(
+        ['trash', 'restore', 'volume~a', 'volume~b'],
+        { from: 'user' }
+      );
+      expect(true).toBe(false);
+    } catch (error) {
+      expect(String(error)).toContain('exit:1');
+    } finally {
+      output.restore();
+    }
+
+    expect(output.logs.join('\n')).not.toContain('Restored');
+    expect(output.errors.join('\n')).toContain('Restore failed');
+  });
+
+  test('trash empty empties the trash', async () => {
+    const emptyTrash = spyOn(driveClient, 'emptyTrash').mockResolvedValue(undefined);
+
+    const output = captureOutput();
+    try {
+      await createProgram(registerTrashCommand).parseAsync(['trash', 'empty'], { from: 'user' });
+    } finally {
+      output.restore();
+    }
+
+    expect(emptyTrash).toHaveBeenCalledTimes(1);
+    expect(output.logs.join('\n')).toContain('Trash emptied.');
+  });
+});